@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use shared::GameFolderPaths;
+
+use crate::camera::CameraController;
+use crate::constants::CINEMATICS_PATH;
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::ui::hud::toasts::ToastEvent;
+use crate::KeyMap;
+
+/// Seconds a path takes to play when no duration was given
+const DEFAULT_PATH_DURATION: f32 = 10.0;
+
+/// Arguments of the `/camera` chat command, which is handled by the client instead of being sent to the server
+#[derive(Event, Debug)]
+pub struct CameraCommandEvent(pub String);
+
+/// A view the camera passes through, the keyframes of a path are evenly spaced in time
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CameraKeyframe {
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    /// Seconds from the first keyframe to the last
+    pub duration: f32,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            duration: DEFAULT_PATH_DURATION,
+        }
+    }
+}
+
+impl CameraPath {
+    /// View at `progress`, from 0 at the first keyframe to 1 at the last, on a Catmull-Rom spline
+    /// going through every keyframe
+    pub fn sample(&self, progress: f32) -> Option<CameraKeyframe> {
+        let last = self.keyframes.len().checked_sub(1)?;
+        if last == 0 {
+            return self.keyframes.first().copied();
+        }
+
+        let position = progress.clamp(0.0, 1.0) * last as f32;
+        let segment = (position.floor() as usize).min(last - 1);
+        let t = position - segment as f32;
+        // The ends are repeated, so that the path starts and stops on them
+        let point = |i: isize| self.keyframes[i.clamp(0, last as isize) as usize];
+        let points = [-1, 0, 1, 2].map(|offset| point(segment as isize + offset));
+
+        // Opposite quaternions are the same rotation, they are flipped to turn the short way
+        let mut rotations = points.map(|point| Vec4::from(point.rotation));
+        for i in 1..rotations.len() {
+            if rotations[i].dot(rotations[i - 1]) < 0.0 {
+                rotations[i] = -rotations[i];
+            }
+        }
+
+        Some(CameraKeyframe {
+            translation: catmull_rom(points.map(|point| point.translation), t),
+            rotation: Quat::from_vec4(catmull_rom(rotations, t)).normalize(),
+        })
+    }
+}
+
+/// Point `t` of the way between the two middle points of a uniform Catmull-Rom spline
+fn catmull_rom<T>(p: [T; 4], t: f32) -> T
+where
+    T: Copy
+        + std::ops::Add<Output = T>
+        + std::ops::Sub<Output = T>
+        + std::ops::Mul<f32, Output = T>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p[1] * 2.0
+        + (p[2] - p[0]) * t
+        + (p[0] * 2.0 - p[1] * 5.0 + p[2] * 4.0 - p[3]) * t2
+        + (p[1] * 3.0 - p[0] - p[2] * 3.0 + p[3]) * t3)
+        * 0.5
+}
+
+/// Camera path being edited with `/camera`, and its playback
+#[derive(Resource, Default)]
+pub struct Cinematic {
+    pub path: CameraPath,
+    playback: Option<Playback>,
+}
+
+struct Playback {
+    elapsed: f32,
+    /// Interface roots hidden for the playback, with the visibility they get back, set on its first frame
+    hidden_ui: Option<Vec<(Entity, Visibility)>>,
+}
+
+impl Cinematic {
+    /// Drops the playback without giving the interface back, when it is despawned anyway
+    pub fn abort(&mut self) {
+        self.playback = None;
+    }
+}
+
+/// Path names are file names, kept to letters, digits, `-` and `_`
+fn valid_path_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// `/camera add|remove|clear|duration <seconds>|play|stop|save <name>|load <name>`
+fn camera_command(
+    args: &str,
+    cinematic: &mut Cinematic,
+    camera: &Transform,
+    paths: &GameFolderPaths,
+) -> Result<String, String> {
+    const USAGE: &str =
+        "Usage: /camera add|remove|clear|duration <seconds>|play|stop|save <name>|load <name>";
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let path = &mut cinematic.path;
+
+    match args.as_slice() {
+        ["add"] => {
+            path.keyframes.push(CameraKeyframe {
+                translation: camera.translation,
+                rotation: camera.rotation,
+            });
+            Ok(format!("Keyframe {} added", path.keyframes.len()))
+        }
+        ["remove"] => match path.keyframes.pop() {
+            Some(_) => Ok(format!("Keyframe {} removed", path.keyframes.len() + 1)),
+            None => Err("The camera path has no keyframes".into()),
+        },
+        ["clear"] => {
+            path.keyframes.clear();
+            Ok("Camera path cleared".into())
+        }
+        ["duration", seconds] => match seconds.parse::<f32>() {
+            Ok(seconds) if seconds > 0.0 => {
+                path.duration = seconds;
+                Ok(format!("The camera path lasts {seconds} seconds"))
+            }
+            _ => Err("The duration must be a positive number of seconds".into()),
+        },
+        ["play"] => {
+            if path.keyframes.len() < 2 {
+                return Err("The camera path needs at least 2 keyframes".into());
+            }
+            cinematic.playback = Some(Playback {
+                elapsed: 0.0,
+                hidden_ui: None,
+            });
+            Ok("Playing the camera path".into())
+        }
+        ["stop"] => match cinematic.playback {
+            Some(ref mut playback) => {
+                playback.elapsed = f32::INFINITY;
+                Ok("Camera path stopped".into())
+            }
+            None => Err("No camera path is playing".into()),
+        },
+        ["save", name] if valid_path_name(name) => {
+            let folder = paths.game_folder_path.join(CINEMATICS_PATH);
+            let file = folder.join(name).with_extension("ron");
+            let serialized = ron::ser::to_string_pretty(&*path, PrettyConfig::new())
+                .map_err(|e| e.to_string())?;
+            std::fs::create_dir_all(&folder)
+                .and_then(|_| std::fs::write(&file, serialized))
+                .map_err(|e| format!("Could not save the camera path: {e}"))?;
+            Ok(format!("Camera path saved to {}", file.display()))
+        }
+        ["load", name] if valid_path_name(name) => {
+            let file = paths
+                .game_folder_path
+                .join(CINEMATICS_PATH)
+                .join(name)
+                .with_extension("ron");
+            let content = std::fs::read_to_string(&file)
+                .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+            *path = ron::from_str(&content)
+                .map_err(|e| format!("Invalid camera path {}: {}", file.display(), e))?;
+            Ok(format!(
+                "Camera path {name} loaded, {} keyframes",
+                path.keyframes.len()
+            ))
+        }
+        ["save" | "load", _] => Err("Path names may only hold letters, digits, - and _".into()),
+        _ => Err(USAGE.into()),
+    }
+}
+
+pub fn camera_command_system(
+    mut events: EventReader<CameraCommandEvent>,
+    mut cinematic: ResMut<Cinematic>,
+    camera: Query<&Transform, With<CameraController>>,
+    paths: Res<GameFolderPaths>,
+    mut ev_toast: EventWriter<ToastEvent>,
+) {
+    let Ok(camera) = camera.single() else {
+        return;
+    };
+    for CameraCommandEvent(args) in events.read() {
+        let text = camera_command(args, &mut cinematic, camera, &paths).unwrap_or_else(|e| e);
+        ev_toast.write(ToastEvent(text));
+    }
+}
+
+/// Moves the camera along the path while it plays, with the interface hidden. Escape stops it early
+pub fn cinematic_playback_system(
+    mut cinematic: ResMut<Cinematic>,
+    mut camera: Query<&mut Transform, With<CameraController>>,
+    mut ui_roots: Query<(Entity, &mut Visibility), (With<Node>, Without<ChildOf>)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    time: Res<Time>,
+) {
+    let Cinematic { path, playback } = &mut *cinematic;
+    let Some(playback) = playback else {
+        return;
+    };
+
+    let hidden_ui = playback.hidden_ui.get_or_insert_with(|| {
+        ui_roots
+            .iter_mut()
+            .map(|(entity, mut visibility)| {
+                let shown = *visibility;
+                *visibility = Visibility::Hidden;
+                (entity, shown)
+            })
+            .collect()
+    });
+    if is_action_just_pressed(GameAction::Escape, &keyboard_input, &key_map) {
+        playback.elapsed = f32::INFINITY;
+    }
+    playback.elapsed += time.delta_secs();
+
+    let progress = playback.elapsed / path.duration;
+    if let (Some(view), Ok(mut transform)) = (path.sample(progress), camera.single_mut()) {
+        transform.translation = view.translation;
+        transform.rotation = view.rotation;
+    }
+
+    if progress >= 1.0 {
+        for (entity, visibility) in hidden_ui.drain(..) {
+            if let Ok((_, mut current)) = ui_roots.get_mut(entity) {
+                *current = visibility;
+            }
+        }
+        cinematic.playback = None;
+    }
+}
@@ -1,25 +1,69 @@
-use crate::camera::CameraController;
+use crate::camera::{
+    CameraController, CameraSettings, DetachedView, Spectate, EYES_HEIGHT,
+    GAMEPAD_RADIANS_PER_SECOND, MOUSE_RADIANS_PER_PIXEL,
+};
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_pressed;
+use crate::input::MouseCapture;
 use crate::player::*;
+use crate::ui::hud::debug::DebugOptions;
 use crate::ui::hud::UIMode;
-use bevy::{input::mouse::MouseMotion, prelude::*, window::PrimaryWindow};
-use shared::players::ViewMode;
+use crate::world::VideoSettings;
+use crate::KeyMap;
+use bevy::{input::mouse::MouseMotion, prelude::*};
+use shared::players::{Player, ViewMode};
+
+/// Speed of the detached camera of the freecam, in blocks per second
+const FREECAM_SPEED: f32 = 20.0;
+/// Field of view is divided by this while zooming
+const ZOOM_FACTOR: f32 = 4.0;
+/// How fast the field of view follows the zoom key, higher is snappier
+const ZOOM_SPEED: f32 = 12.0;
+
+/// Direction the freecam flies towards, from the movement keys
+fn freecam_direction(
+    camera_transform: &Transform,
+    keyboard_input: &ButtonInput<KeyCode>,
+    key_map: &KeyMap,
+) -> Vec3 {
+    let mut direction = Vec3::ZERO;
+    for (action, axis) in [
+        (GameAction::MoveForward, *camera_transform.forward()),
+        (GameAction::MoveBackward, *camera_transform.back()),
+        (GameAction::MoveLeft, *camera_transform.left()),
+        (GameAction::MoveRight, *camera_transform.right()),
+        (GameAction::FlyUp, Vec3::Y),
+        (GameAction::FlyDown, Vec3::NEG_Y),
+    ] {
+        if is_action_pressed(action, keyboard_input, key_map) {
+            direction += axis;
+        }
+    }
+    direction.normalize_or_zero()
+}
 
 // System to control the camera based on mouse movement
 pub fn camera_control_system(
-    windows: Query<&Window, With<PrimaryWindow>>,
     mut mouse_motion_events: EventReader<MouseMotion>,
     mut camera_query: Query<
         (&mut Transform, &mut CameraController),
         (With<Camera>, Without<CurrentPlayerMarker>),
     >,
     player_query: Query<&Transform, With<CurrentPlayerMarker>>,
+    others: Query<(&Player, &Transform), (Without<CurrentPlayerMarker>, Without<Camera>)>,
+    spectate: Res<Spectate>,
     view_mode: Res<ViewMode>,
+    mouse_capture: Res<MouseCapture>,
     ui_mode: Res<UIMode>,
+    settings: Res<CameraSettings>,
+    gamepads: Query<&Gamepad>,
+    time: Res<Time>,
+    debug_options: Res<DebugOptions>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
 ) {
-    let window = windows.single().unwrap();
-
-    // if the window is not focused, ignore camera movement
-    if !window.focused || *ui_mode != UIMode::Closed {
+    // motion only turns the camera while the cursor is captured, and not right after capturing it
+    if !mouse_capture.accepts_motion() {
         mouse_motion_events.clear();
     }
 
@@ -29,8 +73,69 @@ pub fn camera_control_system(
     for event in mouse_motion_events.read() {
         delta += event.delta;
     }
+    delta *= MOUSE_RADIANS_PER_PIXEL * settings.mouse_sensitivity;
+
+    // the right stick turns the camera too, its y axis points up where the mouse one points down
+    if *ui_mode == UIMode::Closed {
+        for gamepad in gamepads.iter() {
+            let stick = gamepad.right_stick() * Vec2::new(1.0, -1.0);
+            delta += stick
+                * GAMEPAD_RADIANS_PER_SECOND
+                * settings.gamepad_sensitivity
+                * time.delta_secs();
+        }
+    }
+
+    if settings.invert_y {
+        delta.y = -delta.y;
+    }
+    let smoothing = settings.smoothing_factor(time.delta_secs());
 
     for (mut camera_transform, mut controller) in camera_query.iter_mut() {
+        let freecam = debug_options.is_freecam_enabled();
+        if freecam && controller.detached_view.is_none() {
+            controller.detached_view = Some(DetachedView {
+                transform: *camera_transform,
+                angle_x: controller.angle_x,
+                angle_y: controller.angle_y,
+            });
+        } else if !freecam {
+            if let Some(view) = controller.detached_view.take() {
+                controller.angle_x = view.angle_x;
+                controller.angle_y = view.angle_y;
+                controller.target_angle_x = view.angle_x;
+                controller.target_angle_y = view.angle_y;
+            }
+        }
+
+        controller.target_angle_x -= delta.x;
+        // limit vertical angle to prevent flipping
+        controller.target_angle_y = (controller.target_angle_y + delta.y)
+            .clamp(-89.0f32.to_radians(), 89.0f32.to_radians());
+
+        controller.angle_x += (controller.target_angle_x - controller.angle_x) * smoothing;
+        controller.angle_y += (controller.target_angle_y - controller.angle_y) * smoothing;
+
+        if freecam {
+            if *ui_mode == UIMode::Closed {
+                let direction = freecam_direction(&camera_transform, &keyboard_input, &key_map);
+                camera_transform.translation += direction * FREECAM_SPEED * time.delta_secs();
+            }
+            camera_transform.rotation = Quat::from_rotation_y(controller.angle_x)
+                * Quat::from_rotation_x(-controller.angle_y);
+            continue;
+        }
+
+        // Spectators see through the eyes of the player they follow
+        let followed = spectate
+            .target
+            .and_then(|id| others.iter().find(|(player, _)| player.id == id));
+        if let Some((followed, transform)) = followed {
+            camera_transform.translation = transform.translation + Vec3::Y * EYES_HEIGHT;
+            camera_transform.rotation = followed.camera_transform.rotation;
+            continue;
+        }
+
         // first-person view
         if *view_mode == ViewMode::FirstPerson {
             // distance is set to 0 for first-person view
@@ -40,15 +145,6 @@ pub fn camera_control_system(
             let player_transform = player_query.single().unwrap();
             let player_position = player_transform.translation;
 
-            // apply mouse sensitivity and adjust camera angle
-            controller.angle_x -= delta.x * controller.mouse_sensitivity;
-            controller.angle_y += delta.y * controller.mouse_sensitivity;
-
-            // limit vertical angle to prevent flipping
-            controller.angle_y = controller
-                .angle_y
-                .clamp(-89.0f32.to_radians(), 89.0f32.to_radians());
-
             // adjust the camera's position to be at the player's eye level
             camera_transform.translation = Vec3::new(
                 player_position.x,
@@ -66,15 +162,6 @@ pub fn camera_control_system(
             // in third-person view, place the camera behind the player
             controller.distance = 10.0;
 
-            // apply mouse sensitivity and adjust camera angle
-            controller.angle_x -= delta.x * controller.mouse_sensitivity;
-            controller.angle_y += delta.y * controller.mouse_sensitivity;
-
-            // limit vertical angle to prevent flipping
-            controller.angle_y = controller
-                .angle_y
-                .clamp(-89.0f32.to_radians(), 89.0f32.to_radians());
-
             let player_transform = player_query.single().unwrap();
             let player_position = player_transform.translation;
 
@@ -93,3 +180,38 @@ pub fn camera_control_system(
         }
     }
 }
+
+/// Narrows the field of view while the zoom key is held, easing in and out of it
+pub fn camera_zoom_system(
+    mut projections: Query<&mut Projection, With<CameraController>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+    video: Res<VideoSettings>,
+    time: Res<Time>,
+) {
+    let zooming = *ui_mode == UIMode::Closed
+        && is_action_pressed(GameAction::Zoom, &keyboard_input, &key_map);
+    let target_fov = if zooming {
+        video.fov.to_radians() / ZOOM_FACTOR
+    } else {
+        video.fov.to_radians()
+    };
+    let factor = 1.0 - (-ZOOM_SPEED * time.delta_secs()).exp();
+
+    for mut projection in projections.iter_mut() {
+        // Only touch the projection while it moves, so that it is not flagged as changed every frame
+        let Projection::Perspective(perspective) = &*projection else {
+            continue;
+        };
+        if perspective.fov == target_fov {
+            continue;
+        }
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov += (target_fov - perspective.fov) * factor;
+            if (perspective.fov - target_fov).abs() < 1e-4 {
+                perspective.fov = target_fov;
+            }
+        }
+    }
+}
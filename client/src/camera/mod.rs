@@ -1,5 +1,11 @@
+mod cinematic;
 mod controller;
+mod settings;
 mod spawn;
+mod spectate;
 
+pub use cinematic::*;
 pub use controller::*;
+pub use settings::*;
 pub use spawn::*;
+pub use spectate::*;
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use ron::{from_str, ser::PrettyConfig};
+use serde::{Deserialize, Serialize};
+use shared::GameFolderPaths;
+use std::{fs, path::PathBuf};
+
+use crate::constants::CAMERA_SETTINGS_PATH;
+
+/// Camera rotation for one pixel of mouse motion, at a sensitivity of 1
+pub const MOUSE_RADIANS_PER_PIXEL: f32 = 0.003;
+/// Camera rotation per second with a gamepad stick fully tilted, at a sensitivity of 1
+pub const GAMEPAD_RADIANS_PER_SECOND: f32 = 3.0;
+
+pub const MIN_SENSITIVITY: f32 = 0.1;
+pub const MAX_SENSITIVITY: f32 = 5.0;
+pub const MAX_SMOOTHING: f32 = 0.2;
+
+/// How the camera reacts to the mouse and gamepad sticks, saved in the game folder
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CameraSettings {
+    pub mouse_sensitivity: f32,
+    pub gamepad_sensitivity: f32,
+    pub invert_y: bool,
+    /// Seconds for the camera to catch up with most of a rotation, 0 turns smoothing off
+    pub smoothing: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: 1.0,
+            gamepad_sensitivity: 1.0,
+            invert_y: false,
+            smoothing: 0.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Fraction of the remaining rotation the camera does during a frame lasting `delta` seconds
+    pub fn smoothing_factor(&self, delta: f32) -> f32 {
+        if self.smoothing <= 0.0 {
+            1.0
+        } else {
+            1.0 - (-delta / self.smoothing).exp()
+        }
+    }
+}
+
+fn camera_settings_path(paths: &GameFolderPaths) -> PathBuf {
+    paths.game_folder_path.join(CAMERA_SETTINGS_PATH)
+}
+
+pub fn load_camera_settings(paths: &GameFolderPaths) -> CameraSettings {
+    let Ok(content) = fs::read_to_string(camera_settings_path(paths)) else {
+        return CameraSettings::default();
+    };
+
+    match from_str::<CameraSettings>(&content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Invalid camera settings, using the defaults: {}", e);
+            CameraSettings::default()
+        }
+    }
+}
+
+pub fn save_camera_settings(settings: &CameraSettings, paths: &GameFolderPaths) {
+    let path = camera_settings_path(paths);
+
+    match ron::ser::to_string_pretty(settings, PrettyConfig::new()) {
+        Ok(serialized) => {
+            if let Err(e) = fs::write(&path, serialized) {
+                error!("Error while saving camera settings to {:?}: {}", path, e);
+            }
+        }
+        Err(e) => error!("Failed to serialize camera settings: {}", e),
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
-use bevy_atmosphere::prelude::AtmosphereCamera;
 
+use crate::world::VideoSettings;
 use crate::GameState;
 
 #[derive(Component)]
@@ -8,46 +8,64 @@ pub struct CameraController {
     pub distance: f32,
     pub angle_x: f32,
     pub angle_y: f32,
-    pub mouse_sensitivity: f32,
+    /// Angles the camera is turning towards, it only lags behind them when smoothing is on
+    pub target_angle_x: f32,
+    pub target_angle_y: f32,
+    /// Set while the freecam is on, to give the player its view back when it goes off
+    pub detached_view: Option<DetachedView>,
+}
+
+/// View of the player when the camera got detached from it
+#[derive(Clone, Copy, Debug)]
+pub struct DetachedView {
+    pub transform: Transform,
+    pub angle_x: f32,
+    pub angle_y: f32,
 }
 
 const DEFAULT_DISTANCE: f32 = 10.0;
-const DEFAULT_MOUSE_SENSITIVITY: f32 = 0.003;
+/// Field of view of the camera when not zooming, in degrees, until the video settings change it
+pub const DEFAULT_FOV: f32 = 60.0;
 
-impl Default for CameraController {
-    fn default() -> Self {
+impl CameraController {
+    fn new(angle_x: f32, angle_y: f32) -> Self {
         Self {
             distance: DEFAULT_DISTANCE,
-            angle_x: 0.0,
-            angle_y: 20.0f32.to_radians(),
-            mouse_sensitivity: DEFAULT_MOUSE_SENSITIVITY,
+            angle_x,
+            angle_y,
+            target_angle_x: angle_x,
+            target_angle_y: angle_y,
+            detached_view: None,
         }
     }
 }
 
+impl Default for CameraController {
+    fn default() -> Self {
+        Self::new(0.0, 20.0f32.to_radians())
+    }
+}
+
 impl From<Quat> for CameraController {
     fn from(quat: Quat) -> Self {
-        Self {
-            distance: DEFAULT_DISTANCE,
-            angle_x: quat.to_euler(EulerRot::XYZ).0,
-            angle_y: quat.to_euler(EulerRot::XYZ).1,
-            mouse_sensitivity: DEFAULT_MOUSE_SENSITIVITY,
-        }
+        let (angle_x, angle_y, _) = quat.to_euler(EulerRot::XYZ);
+        Self::new(angle_x, angle_y)
     }
 }
 
 #[allow(deprecated)]
-pub fn spawn_camera(mut commands: Commands) {
+pub fn spawn_camera(mut commands: Commands, video: Res<VideoSettings>) {
     commands.spawn((
         Camera3d::default(),
         Projection::Perspective(PerspectiveProjection {
-            fov: f32::to_radians(60.0),
+            fov: video.fov.to_radians(),
             ..Default::default()
         }),
         Transform::from_translation(Vec3::new(0.0, 5.0, 10.0))
             .looking_at(Vec3::new(0.0, 0.5, 0.0), Vec3::Y),
         CameraController::default(),
-        AtmosphereCamera::default(),
+        // Set every frame by the fog system
+        DistanceFog::default(),
         StateScoped(GameState::Game),
     ));
 }
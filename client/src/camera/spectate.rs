@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::{ClientToServerMessage, PlayerId};
+use shared::players::{GameMode, Player};
+
+use crate::camera::CameraController;
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::input::MouseCapture;
+use crate::network::SendGameMessageExtension;
+use crate::player::CurrentPlayerMarker;
+use crate::ui::hud::UIMode;
+use crate::KeyMap;
+
+/// Players further away than this cannot be clicked on to spectate them
+const SPECTATE_CLICK_DISTANCE: f32 = 64.0;
+/// Distance from the center of a player within which clicks land on them
+const SPECTATE_CLICK_RADIUS: f32 = 1.0;
+/// Height of the eyes of a player above their center
+pub const EYES_HEIGHT: f32 = 0.8;
+
+#[derive(Resource, Default, Debug)]
+pub struct Spectate {
+    /// Game mode of the current player, told by the server
+    pub game_mode: GameMode,
+    /// Player whose view the camera is attached to
+    pub target: Option<PlayerId>,
+}
+
+impl Spectate {
+    pub fn is_spectator(&self) -> bool {
+        self.game_mode == GameMode::Spectator
+    }
+
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
+        self.game_mode = game_mode;
+        if !self.is_spectator() {
+            self.target = None;
+        }
+    }
+}
+
+/// Player after `current` in the order of their ids, going back to the first one after the last
+fn next_player(mut ids: Vec<PlayerId>, current: Option<PlayerId>) -> Option<PlayerId> {
+    ids.sort_unstable();
+    current
+        .and_then(|current| ids.iter().find(|id| **id > current))
+        .or(ids.first())
+        .copied()
+}
+
+/// Player under the crosshair, the closest one if several are
+fn clicked_player<'a>(
+    camera: &GlobalTransform,
+    players: impl Iterator<Item = (&'a Player, &'a Transform)>,
+) -> Option<PlayerId> {
+    let origin = camera.translation();
+    let direction = camera.forward();
+    players
+        .filter_map(|(player, transform)| {
+            let along = (transform.translation - origin).dot(*direction);
+            let closest = origin + direction * along;
+            (along > 0.0
+                && along <= SPECTATE_CLICK_DISTANCE
+                && closest.distance(transform.translation) <= SPECTATE_CLICK_RADIUS)
+                .then_some((player.id, along))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id)
+}
+
+/// Attaches the camera of a spectator to the player they click on or cycle to, and detaches it with its own key
+pub fn spectate_input_system(
+    mut spectate: ResMut<Spectate>,
+    players: Query<(&Player, &Transform), Without<CurrentPlayerMarker>>,
+    camera: Query<&GlobalTransform, With<CameraController>>,
+    (keyboard_input, mouse_input, key_map, mouse_capture, ui_mode): (
+        Res<ButtonInput<KeyCode>>,
+        Res<ButtonInput<MouseButton>>,
+        Res<KeyMap>,
+        Res<MouseCapture>,
+        Res<UIMode>,
+    ),
+    mut client: ResMut<RenetClient>,
+) {
+    // The player followed may have left
+    if let Some(target) = spectate.target {
+        if !players.iter().any(|(player, _)| player.id == target) {
+            spectate.target = None;
+        }
+    }
+
+    if !spectate.is_spectator() || *ui_mode != UIMode::Closed {
+        return;
+    }
+
+    let target =
+        if is_action_just_pressed(GameAction::SpectateNextPlayer, &keyboard_input, &key_map) {
+            let ids = players.iter().map(|(player, _)| player.id).collect();
+            next_player(ids, spectate.target)
+        } else if is_action_just_pressed(GameAction::StopSpectating, &keyboard_input, &key_map) {
+            None
+        } else if spectate.target.is_none()
+            && mouse_capture.accepts_clicks()
+            && mouse_input.just_pressed(MouseButton::Left)
+        {
+            let Ok(camera) = camera.single() else {
+                return;
+            };
+            let Some(target) = clicked_player(camera, players.iter()) else {
+                return;
+            };
+            Some(target)
+        } else {
+            return;
+        };
+
+    if target != spectate.target {
+        spectate.target = target;
+        client.send_game_message(ClientToServerMessage::Spectate(target));
+    }
+}
+
+/// Hides the player being spectated, the camera is inside their head
+pub fn spectate_visibility_system(
+    spectate: Res<Spectate>,
+    mut players: Query<(&Player, &mut Visibility), Without<CurrentPlayerMarker>>,
+) {
+    for (player, mut visibility) in players.iter_mut() {
+        visibility.set_if_neq(if spectate.target == Some(player.id) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        });
+    }
+}
@@ -2,9 +2,9 @@ pub const INTERACTION_DISTANCE: f32 = 7.;
 pub const BASE_ROUGHNESS: f32 = 0.6;
 pub const BASE_SPECULAR_HIGHLIGHT: f32 = 0.;
 
-pub const CELESTIAL_SIZE: f32 = 10.;
-pub const CELESTIAL_DISTANCE: f32 = 50.; // Low value for testing ; will be increased later
-pub const DAY_DURATION_IN_TICKS: u64 = 20 * 60; // 20 ticks per second * 60 seconds = 1 minute
+pub const CELESTIAL_SIZE: f32 = 140.;
+/// Just in front of the sky dome
+pub const CELESTIAL_DISTANCE: f32 = 700.;
 
 pub const MAX_HOTBAR_SLOTS: u32 = 9;
 
@@ -13,10 +13,23 @@ pub const HOTBAR_PADDING: f32 = 5.;
 pub const HOTBAR_BORDER: f32 = 5.;
 
 pub const SAVE_PATH: &str = "saves/";
+/// World archive shipped in the assets folder, installed in the saves the first time the demo is played
+pub const DEMO_WORLD_ARCHIVE: &str = "demo_world.tar.gz";
+/// Name of the world held by the demo archive
+pub const DEMO_WORLD_NAME: &str = "demo_world";
 pub const SERVER_LIST_SAVE_NAME: &str = "servers.ron";
 pub const BINDS_PATH: &str = "keybindings.ron";
+pub const CAMERA_SETTINGS_PATH: &str = "camera.ron";
+pub const ACCESSIBILITY_SETTINGS_PATH: &str = "accessibility.ron";
+pub const VIDEO_SETTINGS_PATH: &str = "video.ron";
+pub const LOG_SETTINGS_PATH: &str = "logging.ron";
+/// Regions exported with `/export`, in the game folder
+pub const EXPORTS_PATH: &str = "exports/";
+/// Camera paths saved with `/camera save`, in the game folder
+pub const CINEMATICS_PATH: &str = "cinematics/";
 
 pub const GRASS_COLOR: [f32; 4] = [0.1, 1.0, 0.3, 1.0];
+pub const FIRE_COLOR: [f32; 4] = [1.0, 0.5, 0.1, 1.0];
 
 pub const TEXTURE_PATH_BASE: &str = "graphics/base_textures/";
 pub const TEXTURE_PATH_CUSTOM: &str = "graphics/custom_textures/";
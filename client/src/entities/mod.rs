@@ -1 +1,2 @@
+pub mod projectile;
 pub mod stack;
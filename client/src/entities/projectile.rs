@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use shared::{
+    messages::projectile::{ProjectileDespawnEvent, ProjectileSpawnEvent},
+    world::{Projectile, ProjectileKind},
+};
+
+use crate::world::time::ServerTickRate;
+use crate::GameState;
+
+#[derive(Debug, Component)]
+pub struct ProjectileMarker {
+    pub id: u128,
+    pub projectile: Projectile,
+}
+
+#[derive(Resource)]
+pub struct ProjectileAssets {
+    arrow_mesh: Handle<Mesh>,
+    arrow_material: Handle<StandardMaterial>,
+    snowball_mesh: Handle<Mesh>,
+    snowball_material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for ProjectileAssets {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            arrow_mesh: world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Cuboid::new(0.05, 0.05, 0.5)),
+            arrow_material: world.resource_mut::<Assets<StandardMaterial>>().add(
+                StandardMaterial {
+                    base_color: Color::srgb(0.45, 0.3, 0.15),
+                    ..Default::default()
+                },
+            ),
+            snowball_mesh: world.resource_mut::<Assets<Mesh>>().add(Sphere::new(0.12)),
+            snowball_material: world.resource_mut::<Assets<StandardMaterial>>().add(
+                StandardMaterial {
+                    base_color: Color::srgb(0.95, 0.95, 1.0),
+                    ..Default::default()
+                },
+            ),
+        }
+    }
+}
+
+fn projectile_rotation(projectile: &Projectile) -> Quat {
+    Transform::default()
+        .looking_to(projectile.velocity.normalize_or(Vec3::Z), Vec3::Y)
+        .rotation
+}
+
+pub fn spawn_projectiles_system(
+    mut commands: Commands,
+    mut ev_spawn: EventReader<ProjectileSpawnEvent>,
+    mut ev_despawn: EventReader<ProjectileDespawnEvent>,
+    projectiles: Query<(Entity, &ProjectileMarker)>,
+    assets: Res<ProjectileAssets>,
+) {
+    for event in ev_spawn.read() {
+        let (mesh, material) = match event.projectile.kind {
+            ProjectileKind::Arrow => (assets.arrow_mesh.clone(), assets.arrow_material.clone()),
+            ProjectileKind::Snowball => (
+                assets.snowball_mesh.clone(),
+                assets.snowball_material.clone(),
+            ),
+        };
+
+        commands.spawn((
+            StateScoped(GameState::Game),
+            ProjectileMarker {
+                id: event.id,
+                projectile: event.projectile.clone(),
+            },
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            Transform::from_translation(event.projectile.position)
+                .with_rotation(projectile_rotation(&event.projectile)),
+        ));
+    }
+
+    for event in ev_despawn.read() {
+        for (entity, marker) in projectiles.iter() {
+            if marker.id == event.id {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+/// Moves projectiles along the same trajectory as on the server, which tells when they hit something
+pub fn simulate_projectiles_system(
+    mut commands: Commands,
+    mut projectiles: Query<(Entity, &mut ProjectileMarker, &mut Transform)>,
+    time: Res<Time>,
+    tick_rate: Res<ServerTickRate>,
+) {
+    let delta = time.delta_secs() * tick_rate.0.time_scale();
+    if delta == 0. {
+        return;
+    }
+    for (entity, mut marker, mut transform) in projectiles.iter_mut() {
+        marker.projectile.step(delta);
+
+        // The despawn message may have been lost, or the projectile left the area known by the server
+        if marker.projectile.age > marker.projectile.kind.lifetime_secs() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation = marker.projectile.position;
+        transform.rotation = projectile_rotation(&marker.projectile);
+    }
+}
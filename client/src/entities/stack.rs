@@ -1,7 +1,9 @@
-use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use std::collections::HashMap;
+
+use bevy::{pbr::NotShadowCaster, prelude::*, render::mesh::VertexAttributeValues};
 use shared::{
     messages::ItemStackUpdateEvent,
-    world::{ItemStack, ItemType},
+    world::{ItemId, ItemStack, ItemType},
 };
 
 use crate::{
@@ -15,11 +17,78 @@ pub struct StackMarker {
     pub stack: ItemStack,
 }
 
+/// One mesh per item, shared by all the stacks of that item along with the items material,
+/// so that they are all drawn as a single instanced batch instead of one draw call each
+#[derive(Resource, Default)]
+pub struct ItemStackMeshes(HashMap<ItemId, Handle<Mesh>>);
+
+impl ItemStackMeshes {
+    /// Mesh of the item, built the first time it is needed
+    pub fn get_or_build(
+        &mut self,
+        stack: &ItemStack,
+        meshes: &mut Assets<Mesh>,
+        material_resource: &MaterialResource,
+    ) -> Handle<Mesh> {
+        self.0
+            .entry(stack.item_id)
+            .or_insert_with(|| meshes.add(build_item_stack_mesh(stack, material_resource)))
+            .clone()
+    }
+
+    /// Builds the meshes again with the UVs of the current items atlas, in place so that
+    /// the dropped and held items already shown pick them up too
+    pub fn rebuild_all(&self, meshes: &mut Assets<Mesh>, material_resource: &MaterialResource) {
+        for (item_id, handle) in self.0.iter() {
+            let stack = ItemStack {
+                item_id: *item_id,
+                item_type: item_id.get_default_type(),
+                nb: 1,
+            };
+            if let Some(mesh) = meshes.get_mut(handle) {
+                *mesh = build_item_stack_mesh(&stack, material_resource);
+            }
+        }
+    }
+}
+
+fn build_item_stack_mesh(stack: &ItemStack, material_resource: &MaterialResource) -> Mesh {
+    let mut mesh = Cuboid::from_size(if let ItemType::Block(_) = stack.item_type {
+        Vec3::new(0.2, 0.2, 0.2)
+    } else {
+        Vec3::new(0.2, 0.2, 0.05)
+    })
+    .mesh()
+    .build();
+
+    let uv_attribute = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0).unwrap();
+
+    let VertexAttributeValues::Float32x2(uv_attribute) = uv_attribute else {
+        panic!("Unexpected vertex format, expected Float32x2.");
+    };
+
+    if let Some(uv_coords) = material_resource
+        .items
+        .as_ref()
+        .unwrap()
+        .uvs
+        .get(&format!("{:?}", stack.item_id))
+    {
+        for uv in uv_attribute.iter_mut() {
+            uv[0] = uv[0].clamp(uv_coords.u0, uv_coords.u1);
+            uv[1] = uv[1].clamp(uv_coords.v0, uv_coords.v1);
+        }
+    }
+
+    mesh
+}
+
 pub fn stack_update_system(
     mut events: EventReader<ItemStackUpdateEvent>,
     mut commands: Commands,
     mut stacks: Query<(Entity, &mut StackMarker, &mut Transform), Without<CurrentPlayerMarker>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut stack_meshes: ResMut<ItemStackMeshes>,
     time: Res<Time>,
     material_resource: Res<MaterialResource>,
     distance: Res<RenderDistance>,
@@ -35,37 +104,13 @@ pub fn stack_update_system(
                 }
             }
 
-            let mut mesh = Cuboid::from_size(if let ItemType::Block(_) = stack.item_type {
-                Vec3::new(0.2, 0.2, 0.2)
-            } else {
-                Vec3::new(0.2, 0.2, 0.05)
-            })
-            .mesh()
-            .build();
-
-            let uv_attribute = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0).unwrap();
-
-            let VertexAttributeValues::Float32x2(uv_attribute) = uv_attribute else {
-                panic!("Unexpected vertex format, expected Float32x2.");
-            };
-
-            if let Some(uv_coords) = material_resource
-                .items
-                .as_ref()
-                .unwrap()
-                .uvs
-                .get(&format!("{:?}", stack.item_id))
-            {
-                for uv in uv_attribute.iter_mut() {
-                    uv[0] = uv[0].clamp(uv_coords.u0, uv_coords.u1);
-                    uv[1] = uv[1].clamp(uv_coords.v0, uv_coords.v1);
-                }
-            }
+            let mesh = stack_meshes.get_or_build(&stack, &mut meshes, &material_resource);
 
             // If no stack exists with this id, we have to create one
             commands.spawn((
                 StackMarker { id: ev.id, stack },
-                Mesh3d(meshes.add(mesh)),
+                Mesh3d(mesh),
+                NotShadowCaster,
                 MeshMaterial3d(
                     material_resource
                         .global_materials
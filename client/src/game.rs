@@ -1,24 +1,55 @@
-use std::collections::HashMap;
-
-use crate::entities::stack::stack_update_system;
+use crate::entities::projectile::{
+    simulate_projectiles_system, spawn_projectiles_system, ProjectileAssets,
+};
+use crate::entities::stack::{stack_update_system, ItemStackMeshes};
+use crate::input::profiles::{enter_world_key_profile, leave_world_key_profile};
 use crate::mob::*;
-use crate::network::buffered_client::{CurrentFrameInputs, PlayerTickInputsBuffer, SyncTime};
+use crate::network::buffered_client::{
+    send_time_sync_requests_system, CurrentFrameInputs, PlayerTickInputsBuffer, SyncTime,
+};
+use crate::network::save::UnsavedProgress;
 use crate::ui::hud::chat::{render_chat, setup_chat};
-use crate::ui::menus::{setup_server_connect_loading_screen, update_server_connect_loading_screen};
+use crate::ui::hud::combat::{
+    combat_overlays_system, hit_feedback_system, mob_health_bars_system, setup_hit_marker,
+};
+use crate::ui::menus::{
+    pack_prompt_system, setup_server_connect_loading_screen, setup_world_loading_screen,
+    update_server_connect_loading_screen, update_world_loading_screen,
+};
 use bevy::prelude::*;
-use bevy_atmosphere::prelude::*;
-use shared::messages::mob::MobUpdateEvent;
-use shared::messages::{ItemStackUpdateEvent, PlayerSpawnEvent, PlayerUpdateEvent};
-use shared::players::{Inventory, ViewMode};
+use shared::messages::mob::{MobDespawnEvent, MobHitEvent, MobUpdateEvent, TradeOffersEvent};
+use shared::messages::projectile::{ProjectileDespawnEvent, ProjectileSpawnEvent};
+use shared::messages::{
+    BlockBreakingProgress, EmoteEvent, ItemStackUpdateEvent, ItemUseEvent, PlayerAfkEvent,
+    PlayerDeathEvent, PlayerLeftEvent, PlayerSpawnEvent, PlayerUpdateEvent, RecipeUnlockEvent,
+};
+use shared::players::{Inventory, Teams, ViewMode};
 use shared::TICKS_PER_SECOND;
 use time::time_update_system;
 
-use crate::world::time::ClientTime;
+use crate::world::export::{export_region_system, ExportCommandEvent};
+use crate::world::time::{ClientTime, ServerTickRate};
 use crate::world::ClientWorldMap;
 
 use crate::ui::hud::debug::BlockDebugWireframeSettings;
-use crate::ui::hud::reticle::spawn_reticle;
+use crate::ui::hud::effects::{effects_display_system, setup_effects_display};
+use crate::ui::hud::emote_menu::{emote_menu_system, setup_emote_menu};
+use crate::ui::hud::health::{health_bar_system, setup_health_bar};
+use crate::ui::hud::held_item::{held_item_widget_system, setup_held_item_widget};
+use crate::ui::hud::kill_feed::{kill_feed_system, setup_kill_feed};
+use crate::ui::hud::player_list::{player_list_system, setup_player_list};
+use crate::ui::hud::reticle::{spawn_reticle, update_reticle_system};
+use crate::ui::hud::toasts::{
+    crafting_hint_system, recipe_toasts_system, setup_toasts, toast_system, ToastEvent,
+};
+use crate::ui::hud::trading::{
+    open_trading_dialog_system, setup_trading_dialog, trading_dialog_system,
+};
 use crate::ui::menus::pause::{render_pause_menu, setup_pause_menu};
+use crate::ui::narration::{
+    chat_narration_system, low_health_narration_system, narration_system,
+    notification_narration_system, NarrationEvent, Narrator,
+};
 use bevy::color::palettes::basic::WHITE;
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::pbr::wireframe::{WireframeConfig, WireframePlugin};
@@ -29,20 +60,27 @@ use crate::world::celestial::setup_main_lighting;
 use crate::ui::hud::debug::*;
 use crate::ui::hud::hotbar::*;
 use crate::ui::hud::set_ui_mode;
+use crate::world::blob_shadows::*;
+use crate::world::block_lights::*;
 use crate::world::celestial::*;
+use crate::world::clouds::*;
+use crate::world::fog::*;
+use crate::world::sky::*;
 use crate::world::*;
 
 use crate::camera::*;
 use crate::input::*;
 use crate::player::*;
 use crate::ui::hud::inventory::*;
-use shared::world::{BlockId, ItemId, WorldSeed};
+use shared::world::{BlockId, GameRules, ItemId, Weather, WorldSeed};
 
 use crate::network::{
+    apply_resource_packs_system, connect_grant_system, connection_fallback_system,
     establish_authenticated_connection_to_server, init_server_connection,
-    launch_local_server_system, network_failure_handler, poll_network_messages,
-    terminate_server_connection, upload_player_inputs_system, CurrentPlayerProfile, TargetServer,
-    TargetServerState, UnacknowledgedInputs,
+    launch_local_server_system, load_cached_chunks_system, network_failure_handler,
+    poll_network_messages, receive_pack_chunks_system, reset_server_packs,
+    terminate_server_connection, upload_player_inputs_system, CurrentPlayerProfile,
+    PendingConnectGrant, ServerPacks, TargetServer, TargetServerState, UnacknowledgedInputs,
 };
 
 use crate::GameState;
@@ -50,15 +88,20 @@ use crate::GameState;
 #[derive(Resource)]
 pub struct PreLoadingCompletion {
     pub textures_loaded: bool,
+    /// Chunks of the spawn area the server announced, the world is loaded once they are received.
+    /// The farther ones keep streaming in while playing
+    pub spawn_chunks: u32,
 }
 
 pub fn game_plugin(app: &mut App) {
     app.add_plugins(FrameTimeDiagnosticsPlugin::default())
         .add_plugins(WireframePlugin::default())
+        .add_plugins(MaterialPlugin::<BlockOutlineMaterial>::default())
         .add_plugins(bevy_simple_text_input::TextInputPlugin)
-        .add_plugins(AtmospherePlugin)
         .insert_resource(WorldSeed(0))
         .insert_resource(ClientTime(0))
+        .init_resource::<ServerTickRate>()
+        .init_resource::<Narrator>()
         .insert_resource(FirstChunkReceived(false))
         .insert_resource(AmbientLight {
             color: Color::WHITE,
@@ -67,6 +110,7 @@ pub fn game_plugin(app: &mut App) {
         })
         .insert_resource(PreLoadingCompletion {
             textures_loaded: false,
+            spawn_chunks: 0,
         })
         .insert_resource(BlockDebugWireframeSettings { is_enabled: false })
         .insert_resource(WireframeConfig {
@@ -87,25 +131,58 @@ pub fn game_plugin(app: &mut App) {
         .insert_resource(DebugOptions::default())
         .insert_resource(Inventory::new())
         .init_resource::<CurrentPlayerProfile>()
+        .init_resource::<ServerPacks>()
         .init_resource::<ParticleAssets>()
+        .init_resource::<FireParticleAssets>()
+        .init_resource::<EffectParticleAssets>()
+        .init_resource::<BlockDamageMeshes>()
+        .init_resource::<ProjectileAssets>()
+        .init_resource::<ItemStackMeshes>()
+        .init_resource::<TargetedBlockOutline>()
         .init_resource::<FoxFeetTargets>()
         .init_resource::<Animations>()
         .init_resource::<TargetedMob>()
+        .init_resource::<Spectate>()
         .init_resource::<PlayerTickInputsBuffer>()
         .init_resource::<CurrentFrameInputs>()
         .init_resource::<SyncTime>()
         .init_resource::<UnacknowledgedInputs>()
+        .init_resource::<UnsavedProgress>()
+        .init_resource::<MouseCapture>()
+        .init_resource::<PendingMeshes>()
+        .init_resource::<Teams>()
+        .init_resource::<GameRules>()
+        .init_resource::<Weather>()
+        .init_resource::<PendingBlockEdits>()
         .insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND as f64))
         .add_event::<WorldRenderRequestUpdateEvent>()
         .add_event::<PlayerSpawnEvent>()
+        .add_event::<PlayerLeftEvent>()
+        .add_event::<PlayerAfkEvent>()
         .add_event::<PlayerUpdateEvent>()
         .add_event::<MobUpdateEvent>()
+        .add_event::<MobDespawnEvent>()
+        .add_event::<MobHitEvent>()
+        .add_event::<TradeOffersEvent>()
+        .add_event::<ProjectileSpawnEvent>()
+        .add_event::<ProjectileDespawnEvent>()
         .add_event::<ItemStackUpdateEvent>()
+        .add_event::<EmoteEvent>()
+        .add_event::<ItemUseEvent>()
+        .add_event::<BlockBreakingProgress>()
+        .add_event::<PlayerDeathEvent>()
+        .add_event::<RecipeUnlockEvent>()
+        .add_event::<ToastEvent>()
+        .add_event::<NarrationEvent>()
+        .add_event::<ExportCommandEvent>()
+        .add_event::<CameraCommandEvent>()
+        .init_resource::<Cinematic>()
         .add_systems(
-            OnEnter(GameState::PreGameLoading),
+            OnEnter(GameState::Connecting),
             (
                 launch_local_server_system,
                 init_server_connection,
+                reset_server_packs,
                 setup_materials,
                 setup_server_connect_loading_screen,
                 spawn_camera,
@@ -116,35 +193,82 @@ pub fn game_plugin(app: &mut App) {
             Update,
             (
                 establish_authenticated_connection_to_server,
+                connection_fallback_system,
+                connect_grant_system.run_if(resource_exists::<PendingConnectGrant>),
+                receive_pack_chunks_system,
+                apply_resource_packs_system,
                 create_all_atlases,
                 check_pre_loading_complete,
                 spawn_players_system,
                 update_server_connect_loading_screen,
+                pack_prompt_system,
             )
-                .run_if(in_state(GameState::PreGameLoading)),
+                .run_if(in_state(GameState::Connecting)),
+        )
+        .add_systems(
+            OnEnter(GameState::LoadingWorld),
+            (setup_world_loading_screen, load_cached_chunks_system),
+        )
+        .add_systems(
+            Update,
+            update_world_loading_screen.run_if(in_state(GameState::LoadingWorld)),
         )
         .add_systems(
             OnEnter(GameState::Game),
             (
                 setup_main_lighting,
+                setup_sky,
+                setup_clouds,
+                setup_blob_shadows,
                 spawn_reticle,
+                setup_hit_marker,
                 setup_hud,
+                setup_health_bar,
+                setup_effects_display,
+                setup_held_item_widget,
                 setup_chat,
+                setup_player_list,
+                setup_emote_menu,
+                setup_kill_feed,
+                setup_toasts,
+                setup_trading_dialog,
                 setup_pause_menu,
             )
                 .chain(),
         )
         .add_systems(
             OnEnter(GameState::Game),
-            (setup_hotbar, setup_inventory).chain(),
+            (setup_hotbar, setup_inventory, setup_item_tooltip).chain(),
         )
         .add_systems(OnEnter(GameState::Game), setup_chunk_ghost)
+        .add_systems(OnEnter(GameState::Game), capture_mouse_on_enter)
+        .add_systems(OnEnter(GameState::Game), enter_world_key_profile)
+        .add_systems(OnEnter(GameState::Game), setup_block_stats_panel)
+        .add_systems(OnEnter(GameState::Game), setup_block_outline)
         .add_systems(
             Update,
             (
                 render_pause_menu,
                 render_chat,
-                render_inventory_hotbar,
+                player_list_system,
+                update_reticle_system,
+                health_bar_system,
+                effects_display_system,
+                held_item_widget_system,
+                emote_menu_system,
+                kill_feed_system,
+                (recipe_toasts_system, toast_system).chain(),
+                crafting_hint_system,
+                (
+                    chat_narration_system,
+                    notification_narration_system.after(recipe_toasts_system),
+                    low_health_narration_system,
+                    narration_system,
+                )
+                    .chain(),
+                (open_trading_dialog_system, trading_dialog_system).chain(),
+                (render_inventory_hotbar, item_tooltip_system).chain(),
+                hotbar_swap_animation_system,
                 set_ui_mode,
             )
                 .run_if(in_state(GameState::Game)),
@@ -156,12 +280,19 @@ pub fn game_plugin(app: &mut App) {
                 first_and_third_person_view_system,
                 toggle_chunk_debug_mode_system,
                 toggle_raycast_debug_mode_system,
+                toggle_freecam_system,
+                camera_zoom_system,
                 chunk_force_reload_system,
                 (
+                    handle_mouse_system,
+                    hotbar_selection_system,
                     update_frame_inputs_system,
+                    spectate_input_system,
                     handle_block_interactions,
+                    block_outline_system,
                     player_movement_system,
                     camera_control_system,
+                    spectate_visibility_system,
                 )
                     .chain(),
                 fps_text_update_system,
@@ -170,11 +301,19 @@ pub fn game_plugin(app: &mut App) {
                 block_text_update_system,
                 time_text_update_system,
                 toggle_hud_system,
+                block_stats_system,
                 chunk_ghost_update_system,
                 raycast_debug_update_system,
                 toggle_wireframe_system,
-                handle_mouse_system,
-                update_celestial_bodies,
+                (
+                    update_celestial_bodies,
+                    celestial_shadows_system,
+                    update_sky_system,
+                    clouds_system,
+                    fog_system,
+                    (attach_blob_shadows_system, blob_shadows_system).chain(),
+                    block_lights_system,
+                ),
             )
                 .run_if(in_state(GameState::Game)),
         )
@@ -183,38 +322,72 @@ pub fn game_plugin(app: &mut App) {
             (
                 setup_fox_once_loaded,
                 simulate_particles,
+                burning_mobs_particles_system,
+                effect_particles_system,
                 update_targetted_mob_color,
                 stack_update_system,
+                expire_block_edits_system,
+                (spawn_projectiles_system, simulate_projectiles_system).chain(),
+                (
+                    hit_feedback_system,
+                    mob_health_bars_system,
+                    combat_overlays_system,
+                )
+                    .chain(),
             )
                 .run_if(in_state(GameState::Game)),
         )
         .add_observer(observe_on_step)
+        // Chunks, players and mobs are received and shown while the world loads, behind the loading screen
+        .add_systems(PostUpdate, (world_render_system).run_if(in_world))
+        .add_systems(Update, chunk_rise_system.run_if(in_world))
         .add_systems(
-            PostUpdate,
-            (world_render_system).run_if(in_state(GameState::Game)),
+            Update,
+            (
+                export_region_system,
+                camera_command_system,
+                cinematic_playback_system.after(camera_control_system),
+            )
+                .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             Update,
             (
                 network_failure_handler,
                 spawn_players_system,
+                despawn_players_system,
+                apply_player_afk_system,
                 update_players_system,
                 spawn_mobs_system,
                 player_labels_system,
+                player_label_colors_system,
+                block_damage_overlay_system,
+            )
+                .run_if(in_world),
+        )
+        .add_systems(
+            Update,
+            (
+                apply_emote_events_system,
+                animate_emotes_system,
+                apply_item_use_events_system,
+                animate_item_use_system,
+                held_items_system,
             )
+                .chain()
+                .after(update_players_system)
+                .after(player_movement_system)
                 .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             PreUpdate,
             pre_input_update_system.run_if(in_state(GameState::Game)),
         )
-        .add_systems(
-            FixedPreUpdate,
-            poll_network_messages.run_if(in_state(GameState::Game)),
-        )
+        .add_systems(FixedPreUpdate, poll_network_messages.run_if(in_world))
         .add_systems(
             FixedUpdate,
-            (upload_player_inputs_system).run_if(in_state(GameState::Game)),
+            (upload_player_inputs_system, send_time_sync_requests_system)
+                .run_if(in_state(GameState::Game)),
         )
         .add_systems(
             FixedPostUpdate,
@@ -222,23 +395,52 @@ pub fn game_plugin(app: &mut App) {
         )
         .add_systems(
             OnExit(GameState::Game),
-            (clear_resources, terminate_server_connection).chain(),
+            (
+                clear_resources,
+                terminate_server_connection,
+                release_mouse_on_exit,
+                leave_world_key_profile,
+            )
+                .chain(),
         );
 }
 
-fn clear_resources(mut world_map: ResMut<ClientWorldMap>) {
-    world_map.map = HashMap::new();
-    world_map.total_blocks_count = 0;
-    world_map.total_chunks_count = 0;
+/// The world is being loaded or played in
+fn in_world(state: Res<State<GameState>>) -> bool {
+    matches!(state.get(), GameState::LoadingWorld | GameState::Game)
+}
+
+fn clear_resources(
+    mut world_map: ResMut<ClientWorldMap>,
+    mut teams: ResMut<Teams>,
+    mut game_rules: ResMut<GameRules>,
+    mut weather: ResMut<Weather>,
+    mut pending_edits: ResMut<PendingBlockEdits>,
+    mut spectate: ResMut<Spectate>,
+    mut cinematic: ResMut<Cinematic>,
+    mut tick_rate: ResMut<ServerTickRate>,
+) {
+    world_map.clear();
     world_map.name = "".into();
+    *teams = Teams::default();
+    *game_rules = GameRules::default();
+    *weather = Weather::default();
+    pending_edits.clear();
+    *spectate = Spectate::default();
+    cinematic.abort();
+    *tick_rate = ServerTickRate::default();
 }
 
 fn check_pre_loading_complete(
     loading: Res<PreLoadingCompletion>,
     mut game_state: ResMut<NextState<GameState>>,
     target_server: Res<TargetServer>,
+    packs: Res<ServerPacks>,
 ) {
-    if loading.textures_loaded && target_server.state == TargetServerState::FullyReady {
-        game_state.set(GameState::Game);
+    if loading.textures_loaded
+        && packs.is_ready()
+        && target_server.state == TargetServerState::FullyReady
+    {
+        game_state.set(GameState::LoadingWorld);
     }
 }
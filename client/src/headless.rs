@@ -0,0 +1,275 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use bevy::prelude::*;
+use bevy_renet::netcode::NetcodeClientTransport;
+use bevy_renet::renet::RenetClient;
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use shared::messages::{
+    AuthRegisterRequest, ChatMessageRequest, ClientToServerMessage, NetworkAction,
+    PlayerFrameInput, ServerToClientMessage,
+};
+use shared::network::{bind_client_socket, request_connect_grant, TlsIdentity};
+use shared::players::ViewMode;
+use shared::utils::unix_time_ms;
+use shared::{get_shared_renet_config, STC_AUTH_CHANNEL, TICKS_PER_SECOND};
+
+use crate::network::SendGameMessageExtension;
+
+/// Time between two frames of a bot, inputs are sent once per server tick
+const FRAME_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND);
+/// Bots are started a bit apart so that the server does not get all the connections at once
+const CONNECT_INTERVAL: Duration = Duration::from_millis(200);
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
+/// Height of the eyes above the player position, the server raycasts block edits from there
+const EYE_HEIGHT: f32 = 0.8;
+
+/// Chance per frame to break the block looked at, to place one, and to say something in the chat
+const BREAK_CHANCE: f64 = 0.01;
+const PLACE_CHANCE: f64 = 0.01;
+const CHAT_CHANCE: f64 = 0.002;
+
+const CHAT_LINES: [&str; 4] = [
+    "Hello!",
+    "Anyone around?",
+    "Nice place",
+    "Load testing, please ignore",
+];
+
+/// Shared by all the bots, printed regularly
+#[derive(Default)]
+struct BotStats {
+    connected: AtomicUsize,
+    messages_received: AtomicUsize,
+    bytes_received: AtomicUsize,
+    disconnects: AtomicUsize,
+}
+
+/// Scripted player: walks in a random direction for a while, then picks another one
+struct Bot {
+    id: u64,
+    name: String,
+    position: Vec3,
+    yaw: f32,
+    walk_until: Instant,
+    jumping: bool,
+    /// Difference between the server clock and ours, inputs are timestamped on the server clock
+    clock_offset_ms: i64,
+    authenticated: bool,
+    last_frame_ms: u64,
+}
+
+impl Bot {
+    fn new(index: usize) -> Self {
+        Self {
+            id: 0,
+            name: format!("Bot-{index}"),
+            position: Vec3::ZERO,
+            yaw: 0.,
+            walk_until: Instant::now(),
+            jumping: false,
+            clock_offset_ms: 0,
+            authenticated: false,
+            last_frame_ms: 0,
+        }
+    }
+
+    fn server_time_ms(&self) -> u64 {
+        unix_time_ms().saturating_add_signed(self.clock_offset_ms)
+    }
+
+    fn receive_messages(&mut self, client: &mut RenetClient, stats: &BotStats) {
+        while let Some(Ok(message)) = client.receive_game_message_by_channel(STC_AUTH_CHANNEL) {
+            if let ServerToClientMessage::AuthRegisterResponse(response) = message {
+                self.clock_offset_ms = response.timestamp_ms as i64 - unix_time_ms() as i64;
+                self.authenticated = true;
+                stats.connected.fetch_add(1, Ordering::Relaxed);
+                println!("{} joined as {}", self.name, response.username);
+            }
+        }
+
+        while let Some(message) = client.receive_game_message_except_channel(STC_AUTH_CHANNEL) {
+            let Ok(message) = message else {
+                continue;
+            };
+            stats.messages_received.fetch_add(1, Ordering::Relaxed);
+            stats.bytes_received.fetch_add(
+                bincode::serialized_size(&message).unwrap_or(0) as usize,
+                Ordering::Relaxed,
+            );
+            if let ServerToClientMessage::PlayerUpdate(update) = message {
+                if update.id == self.id {
+                    self.position = update.position;
+                }
+            }
+        }
+    }
+
+    fn next_input(&mut self, rng: &mut ThreadRng) -> PlayerFrameInput {
+        let now = Instant::now();
+        if now >= self.walk_until {
+            self.yaw = rng.gen_range(0.0..std::f32::consts::TAU);
+            self.jumping = rng.gen_bool(0.2);
+            self.walk_until = now + Duration::from_millis(rng.gen_range(2000..6000));
+        }
+
+        let mut inputs = vec![NetworkAction::MoveForward, NetworkAction::AutoJump];
+        if self.jumping {
+            inputs.push(NetworkAction::JumpOrFlyUp);
+        }
+        if rng.gen_bool(BREAK_CHANCE) {
+            inputs.push(NetworkAction::LeftClick);
+        } else if rng.gen_bool(PLACE_CHANCE) {
+            inputs.push(NetworkAction::RightClick);
+        }
+
+        // Looking down in front of the feet, so that clicks hit the ground
+        let camera = Transform::from_translation(self.position + Vec3::Y * EYE_HEIGHT)
+            .with_rotation(Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(-0.8));
+
+        let time_ms = self.server_time_ms().max(self.last_frame_ms + 1);
+        let delta_ms = if self.last_frame_ms == 0 {
+            FRAME_DURATION.as_millis() as u64
+        } else {
+            time_ms - self.last_frame_ms
+        };
+        self.last_frame_ms = time_ms;
+
+        PlayerFrameInput {
+            time_ms,
+            delta_ms,
+            inputs: inputs.into_iter().collect(),
+            camera,
+            hotbar_slot: 0,
+            view_mode: ViewMode::FirstPerson,
+            position: self.position,
+            predicted_edit: None,
+        }
+    }
+}
+
+/// Connects as `name` with a certificate of its own, returns the player id the server bound to it
+fn connect(server_addr: SocketAddr, name: &str) -> Result<(NetcodeClientTransport, u64), String> {
+    // Load testing does not need to check who the server is
+    let identity = TlsIdentity::generate()?;
+    let (grant, _) = request_connect_grant(server_addr, name, &identity, None)?;
+    let client_id = grant.client_id();
+    let socket = bind_client_socket(server_addr).map_err(|e| e.to_string())?;
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let transport =
+        NetcodeClientTransport::new(current_time, grant.into_authentication(server_addr), socket)
+            .map_err(|e| format!("{e:?}"))?;
+    Ok((transport, client_id))
+}
+
+fn run_bot(index: usize, server_addr: SocketAddr, stats: Arc<BotStats>) {
+    let mut rng = rand::thread_rng();
+    let mut bot = Bot::new(index);
+    let mut client = RenetClient::new(get_shared_renet_config());
+    let mut transport = match connect(server_addr, &bot.name) {
+        Ok((transport, id)) => {
+            bot.id = id;
+            transport
+        }
+        Err(e) => {
+            eprintln!("{} could not connect: {}", bot.name, e);
+            return;
+        }
+    };
+
+    let mut auth_sent = false;
+    let mut last_update = Instant::now();
+    loop {
+        let now = Instant::now();
+        let delta = now - last_update;
+        last_update = now;
+
+        client.update(delta);
+        if let Err(e) = transport.update(delta, &mut client) {
+            eprintln!("{} network error: {}", bot.name, e);
+        }
+
+        if client.is_disconnected() {
+            if bot.authenticated {
+                stats.connected.fetch_sub(1, Ordering::Relaxed);
+            }
+            stats.disconnects.fetch_add(1, Ordering::Relaxed);
+            eprintln!("{} was disconnected", bot.name);
+            return;
+        }
+
+        if client.is_connected() {
+            if !auth_sent {
+                client.send_game_message(
+                    AuthRegisterRequest {
+                        username: bot.name.clone(),
+                    }
+                    .into(),
+                );
+                auth_sent = true;
+            }
+
+            bot.receive_messages(&mut client, &stats);
+
+            if bot.authenticated {
+                let input = bot.next_input(&mut rng);
+                client.send_game_message(ClientToServerMessage::PlayerInputs(vec![input]));
+                if rng.gen_bool(CHAT_CHANCE) {
+                    let line = CHAT_LINES[rng.gen_range(0..CHAT_LINES.len())];
+                    client.send_game_message(ClientToServerMessage::ChatMessage(
+                        ChatMessageRequest {
+                            content: line.to_string(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        if let Err(e) = transport.send_packets(&mut client) {
+            eprintln!("{} network error: {}", bot.name, e);
+        }
+        thread::sleep(FRAME_DURATION.saturating_sub(now.elapsed()));
+    }
+}
+
+/// Connects `count` scripted players to a server without opening a window, to load test it.
+/// Runs until all the bots are disconnected
+pub fn run_bots(server_addr: SocketAddr, count: usize) {
+    println!("Connecting {count} bots to {server_addr}");
+    let stats = Arc::new(BotStats::default());
+
+    let mut handles = Vec::with_capacity(count);
+    for index in 0..count {
+        let stats = stats.clone();
+        handles.push(thread::spawn(move || run_bot(index, server_addr, stats)));
+        thread::sleep(CONNECT_INTERVAL);
+    }
+
+    let mut last_report = Instant::now();
+    let mut last_bytes = 0;
+    while handles.iter().any(|handle| !handle.is_finished()) {
+        thread::sleep(Duration::from_millis(500));
+        if last_report.elapsed() < STATS_INTERVAL {
+            continue;
+        }
+
+        let bytes = stats.bytes_received.load(Ordering::Relaxed);
+        println!(
+            "{}/{} bots connected, {} disconnected, {} messages received, {} KiB/s",
+            stats.connected.load(Ordering::Relaxed),
+            count,
+            stats.disconnects.load(Ordering::Relaxed),
+            stats.messages_received.load(Ordering::Relaxed),
+            (bytes - last_bytes) as f32 / 1024. / last_report.elapsed().as_secs_f32()
+        );
+        last_bytes = bytes;
+        last_report = Instant::now();
+    }
+    println!("All bots are disconnected");
+}
@@ -24,4 +24,39 @@ pub enum GameAction {
     RenderDistanceMinus,
     RenderDistancePlus,
     ReloadChunks,
+    ShowPlayerList,
+    OpenEmoteMenu,
+    Zoom,
+    ToggleFreecam,
+    ToggleBlockStats,
+    /// Attaches the camera of a spectator to the next player
+    SpectateNextPlayer,
+    /// Gives a spectator their own camera back
+    StopSpectating,
+    HotbarSlot1,
+    HotbarSlot2,
+    HotbarSlot3,
+    HotbarSlot4,
+    HotbarSlot5,
+    HotbarSlot6,
+    HotbarSlot7,
+    HotbarSlot8,
+    HotbarSlot9,
+    /// Selects the targeted block in the hotbar, or takes it in creative. The middle click always does it
+    PickBlock,
+}
+
+impl GameAction {
+    /// Actions selecting the hotbar slots, in slot order
+    pub const HOTBAR_SLOTS: [GameAction; 9] = [
+        GameAction::HotbarSlot1,
+        GameAction::HotbarSlot2,
+        GameAction::HotbarSlot3,
+        GameAction::HotbarSlot4,
+        GameAction::HotbarSlot5,
+        GameAction::HotbarSlot6,
+        GameAction::HotbarSlot7,
+        GameAction::HotbarSlot8,
+        GameAction::HotbarSlot9,
+    ];
 }
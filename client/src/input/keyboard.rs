@@ -1,3 +1,4 @@
+use crate::input::profiles::{Bindings, KeyProfiles, DEFAULT_PROFILE};
 use crate::{constants::BINDS_PATH, input::data::GameAction, KeyMap};
 use bevy::prelude::*;
 use bevy::{
@@ -6,12 +7,10 @@ use bevy::{
 };
 use ron::{from_str, ser::PrettyConfig};
 use shared::GameFolderPaths;
-use std::path::Path;
 use std::{
     collections::BTreeMap,
     fs::{self, File},
     io::Write,
-    path::PathBuf,
 };
 
 pub fn is_action_pressed(
@@ -63,70 +62,115 @@ pub fn get_action_keys(action: GameAction, key_map: &KeyMap) -> Vec<KeyCode> {
     key_map.map.get(&action).unwrap().to_vec()
 }
 
-pub fn get_bindings(game_folder_paths: &GameFolderPaths) -> KeyMap {
-    let binds_path: PathBuf = Path::new(&game_folder_paths.assets_folder_path).join(BINDS_PATH);
+pub fn default_bindings() -> Bindings {
+    let mut map = BTreeMap::new();
+    map.insert(
+        GameAction::MoveForward,
+        vec![KeyCode::KeyW, KeyCode::ArrowUp],
+    );
+    map.insert(
+        GameAction::MoveBackward,
+        vec![KeyCode::KeyS, KeyCode::ArrowDown],
+    );
+    map.insert(
+        GameAction::MoveLeft,
+        vec![KeyCode::KeyA, KeyCode::ArrowLeft],
+    );
+    map.insert(
+        GameAction::MoveRight,
+        vec![KeyCode::KeyD, KeyCode::ArrowRight],
+    );
+    map.insert(GameAction::Jump, vec![KeyCode::Space]);
+    map.insert(GameAction::Escape, vec![KeyCode::Escape]);
+    map.insert(GameAction::ToggleFps, vec![KeyCode::F3]);
+    map.insert(GameAction::ToggleChunkDebugMode, vec![KeyCode::F4]);
+    map.insert(GameAction::ToggleViewMode, vec![KeyCode::F5]);
+    map.insert(GameAction::ToggleBlockWireframeDebugMode, vec![KeyCode::F6]);
+    map.insert(GameAction::ToggleRaycastDebugMode, vec![KeyCode::F7]);
+    map.insert(GameAction::ToggleFlyMode, vec![KeyCode::KeyF]);
+    map.insert(GameAction::FlyUp, vec![KeyCode::Space]);
+    map.insert(GameAction::FlyDown, vec![KeyCode::ShiftLeft]);
+    map.insert(GameAction::ToggleInventory, vec![KeyCode::KeyE]);
+    map.insert(GameAction::OpenChat, vec![KeyCode::KeyT]);
+    map.insert(GameAction::RenderDistanceMinus, vec![KeyCode::KeyO]);
+    map.insert(GameAction::RenderDistancePlus, vec![KeyCode::KeyP]);
+    map.insert(GameAction::ReloadChunks, vec![KeyCode::KeyR]);
+    map.insert(GameAction::ShowPlayerList, vec![KeyCode::Tab]);
+    map.insert(GameAction::OpenEmoteMenu, vec![KeyCode::KeyG]);
+    map.insert(GameAction::Zoom, vec![KeyCode::KeyC]);
+    map.insert(GameAction::ToggleFreecam, vec![KeyCode::F8]);
+    map.insert(GameAction::ToggleBlockStats, vec![KeyCode::F9]);
+    map.insert(GameAction::SpectateNextPlayer, vec![KeyCode::KeyN]);
+    map.insert(GameAction::StopSpectating, vec![KeyCode::KeyX]);
+    let digits = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    for (action, key) in GameAction::HOTBAR_SLOTS.into_iter().zip(digits) {
+        map.insert(action, vec![key]);
+    }
+    map.insert(GameAction::PickBlock, vec![]);
+    map
+}
 
-    let mut binds = KeyMap {
-        map: {
-            let mut map = BTreeMap::new();
-            map.insert(
-                GameAction::MoveForward,
-                vec![KeyCode::KeyW, KeyCode::ArrowUp],
-            );
-            map.insert(
-                GameAction::MoveBackward,
-                vec![KeyCode::KeyS, KeyCode::ArrowDown],
-            );
-            map.insert(
-                GameAction::MoveLeft,
-                vec![KeyCode::KeyA, KeyCode::ArrowLeft],
-            );
-            map.insert(
-                GameAction::MoveRight,
-                vec![KeyCode::KeyD, KeyCode::ArrowRight],
-            );
-            map.insert(GameAction::Jump, vec![KeyCode::Space]);
-            map.insert(GameAction::Escape, vec![KeyCode::Escape]);
-            map.insert(GameAction::ToggleFps, vec![KeyCode::F3]);
-            map.insert(GameAction::ToggleChunkDebugMode, vec![KeyCode::F4]);
-            map.insert(GameAction::ToggleViewMode, vec![KeyCode::F5]);
-            map.insert(GameAction::ToggleBlockWireframeDebugMode, vec![KeyCode::F6]);
-            map.insert(GameAction::ToggleRaycastDebugMode, vec![KeyCode::F7]);
-            map.insert(GameAction::ToggleFlyMode, vec![KeyCode::KeyF]);
-            map.insert(GameAction::FlyUp, vec![KeyCode::Space]);
-            map.insert(GameAction::FlyDown, vec![KeyCode::ShiftLeft]);
-            map.insert(GameAction::ToggleInventory, vec![KeyCode::KeyE]);
-            map.insert(GameAction::OpenChat, vec![KeyCode::KeyT]);
-            map.insert(GameAction::RenderDistanceMinus, vec![KeyCode::KeyO]);
-            map.insert(GameAction::RenderDistancePlus, vec![KeyCode::KeyP]);
-            map.insert(GameAction::ReloadChunks, vec![KeyCode::KeyR]);
-            map
-        },
+pub fn load_key_profiles(game_folder_paths: &GameFolderPaths) -> KeyProfiles {
+    let binds_path = game_folder_paths.game_folder_path.join(BINDS_PATH);
+    let Ok(content) = fs::read_to_string(&binds_path) else {
+        return KeyProfiles::default();
     };
 
-    // Try to get & serialize existing binds
-    if let Ok(content) = fs::read_to_string(binds_path.as_path()) {
-        if let Ok(key_map) = from_str::<KeyMap>(&content) {
-            for (k, v) in key_map.map.iter() {
-                binds.map.insert(*k, v.clone());
-            }
-        }
-    }
+    let mut profiles = if let Ok(profiles) = from_str::<KeyProfiles>(&content) {
+        profiles
+    } else if let Ok(key_map) = from_str::<KeyMap>(&content) {
+        // Files written before the profiles existed hold a single set of bindings
+        info!("Moving the keybindings to the {} profile", DEFAULT_PROFILE);
+        KeyProfiles::from_single(key_map.map)
+    } else {
+        warn!(
+            "Invalid keybindings in {:?}, using the defaults",
+            binds_path
+        );
+        KeyProfiles::default()
+    };
 
-    binds
+    if !profiles.profiles.contains_key(&profiles.active) {
+        profiles.active = DEFAULT_PROFILE.into();
+        profiles
+            .profiles
+            .entry(profiles.active.clone())
+            .or_insert_with(default_bindings);
+    }
+    profiles.in_use = profiles.active.clone();
+    profiles
+}
 
-    // Get default binds
+/// Writes the edits made to the bindings in use to their profile, then saves all the profiles
+pub fn save_keybindings(
+    key_map: Res<KeyMap>,
+    mut profiles: ResMut<KeyProfiles>,
+    game_folder_path: Res<GameFolderPaths>,
+) {
+    let in_use = profiles.in_use.clone();
+    profiles.profiles.insert(in_use, key_map.map.clone());
+    save_key_profiles(&profiles, &game_folder_path);
 }
 
-pub fn save_keybindings(key_map: Res<KeyMap>, game_folder_path: Res<GameFolderPaths>) {
+pub fn save_key_profiles(profiles: &KeyProfiles, game_folder_path: &GameFolderPaths) {
     let binds_path = game_folder_path.game_folder_path.join(BINDS_PATH);
 
     let pretty_config = PrettyConfig::new()
-        .with_depth_limit(3)
+        .with_depth_limit(4)
         .with_separate_tuple_members(true)
         .with_enumerate_arrays(true);
 
-    if let Ok(serialized) = ron::ser::to_string_pretty(key_map.into_inner(), pretty_config) {
+    if let Ok(serialized) = ron::ser::to_string_pretty(profiles, pretty_config) {
         match File::create(&binds_path) {
             Ok(mut file) => {
                 if let Err(e) = file.write_all(serialized.as_bytes()) {
@@ -1,5 +1,6 @@
 pub mod data;
 pub mod keyboard;
 pub mod mouse;
+pub mod profiles;
 
 pub use mouse::*;
@@ -2,19 +2,102 @@ use crate::ui::hud::UIMode;
 use bevy::prelude::*;
 use bevy::window::{CursorGrabMode, PrimaryWindow};
 
+/// Frames of mouse motion dropped after the cursor gets captured, they hold the jump of the cursor into the window
+const IGNORED_MOTION_FRAMES_AFTER_CAPTURE: u8 = 2;
+
+/// Owns the cursor grab: it is captured while playing and released whenever a UI opens or the window loses focus
+#[derive(Resource, Default, Debug)]
+pub struct MouseCapture {
+    captured: bool,
+    ui_was_open: bool,
+    ignored_motion_frames: u8,
+    /// The click which captured the cursor, kept from the game until it is released
+    capture_click_held: bool,
+}
+
+impl MouseCapture {
+    /// Whether mouse motion should move the camera this frame
+    pub fn accepts_motion(&self) -> bool {
+        self.captured && self.ignored_motion_frames == 0
+    }
+
+    /// Whether mouse buttons should act on the world this frame
+    pub fn accepts_clicks(&self) -> bool {
+        self.captured && !self.capture_click_held
+    }
+
+    fn capture(&mut self) {
+        self.captured = true;
+        self.ignored_motion_frames = IGNORED_MOTION_FRAMES_AFTER_CAPTURE;
+    }
+
+    fn release(&mut self) {
+        self.captured = false;
+    }
+}
+
+fn apply_cursor(window: &mut Window, captured: bool) {
+    window.cursor_options.grab_mode = if captured {
+        CursorGrabMode::Locked
+    } else {
+        CursorGrabMode::None
+    };
+    window.cursor_options.visible = !captured;
+}
+
+pub fn capture_mouse_on_enter(
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut capture: ResMut<MouseCapture>,
+) {
+    let mut window = windows.single_mut().unwrap();
+    if window.focused {
+        capture.capture();
+    }
+    apply_cursor(&mut window, capture.captured);
+}
+
+pub fn release_mouse_on_exit(
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut capture: ResMut<MouseCapture>,
+) {
+    capture.release();
+    if let Ok(mut window) = windows.single_mut() {
+        apply_cursor(&mut window, false);
+    }
+}
+
 pub fn handle_mouse_system(
     mut windows: Query<&mut Window, With<PrimaryWindow>>,
     ui_mode: Res<UIMode>,
+    mut mouse_buttons: ResMut<ButtonInput<MouseButton>>,
+    mut capture: ResMut<MouseCapture>,
 ) {
     let mut window = windows.single_mut().unwrap();
+    let ui_open = *ui_mode != UIMode::Closed;
+    let was_captured = capture.captured;
 
-    let is_playing = *ui_mode == UIMode::Closed;
+    capture.ignored_motion_frames = capture.ignored_motion_frames.saturating_sub(1);
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        capture.capture_click_held = false;
+    }
 
-    window.cursor_options.grab_mode = if is_playing {
-        CursorGrabMode::Locked
-    } else {
-        CursorGrabMode::None
-    };
+    if ui_open || !window.focused {
+        capture.release();
+    } else if !capture.captured {
+        if capture.ui_was_open {
+            // Closing a UI goes straight back to playing
+            capture.capture();
+        } else if mouse_buttons.just_pressed(MouseButton::Left) {
+            // The click only brings the cursor back, it must not break a block too
+            mouse_buttons.clear_just_pressed(MouseButton::Left);
+            capture.capture_click_held = true;
+            capture.capture();
+        }
+    }
+    capture.ui_was_open = ui_open;
 
-    window.cursor_options.visible = !is_playing;
+    if capture.captured != was_captured {
+        debug!("Mouse captured: {}", capture.captured);
+        apply_cursor(&mut window, capture.captured);
+    }
 }
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::input::{data::GameAction, keyboard::default_bindings};
+use crate::menus::solo::SelectedWorld;
+use crate::network::TargetServer;
+use crate::KeyMap;
+
+pub type Bindings = BTreeMap<GameAction, Vec<KeyCode>>;
+
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// Named sets of bindings, saved in the keybindings file.
+/// The `KeyMap` read by the inputs holds a copy of the profile in use.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+pub struct KeyProfiles {
+    /// Profile picked in the controls menu
+    pub active: String,
+    pub profiles: BTreeMap<String, Bindings>,
+    /// Profile used instead of the active one while playing a world, by world name or server address
+    #[serde(default)]
+    pub world_overrides: BTreeMap<String, String>,
+    /// Profile copied in the `KeyMap`, which the edits made to it are written back to
+    #[serde(skip)]
+    pub in_use: String,
+    /// World being played, `None` in the menus
+    #[serde(skip)]
+    pub world: Option<String>,
+}
+
+impl Default for KeyProfiles {
+    fn default() -> Self {
+        Self::from_single(default_bindings())
+    }
+}
+
+impl KeyProfiles {
+    /// Profiles of a keybindings file written before there could be several of them
+    pub fn from_single(bindings: Bindings) -> Self {
+        Self {
+            active: DEFAULT_PROFILE.into(),
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.into(), bindings)]),
+            world_overrides: BTreeMap::new(),
+            in_use: DEFAULT_PROFILE.into(),
+            world: None,
+        }
+    }
+
+    /// Bindings of a profile, the actions it does not mention keep their default keys
+    pub fn key_map(&self, name: &str) -> KeyMap {
+        let mut map = default_bindings();
+        if let Some(bindings) = self.profiles.get(name) {
+            for (action, keys) in bindings {
+                map.insert(*action, keys.clone());
+            }
+        }
+        KeyMap { map }
+    }
+
+    pub fn world_override(&self) -> Option<&String> {
+        self.world_overrides.get(self.world.as_ref()?)
+    }
+
+    /// Profile which should be in use: the one of the current world, or else the active one
+    pub fn wanted(&self) -> &str {
+        self.world_override()
+            .filter(|name| self.profiles.contains_key(*name))
+            .unwrap_or(&self.active)
+    }
+
+    /// Keeps the edits made to the bindings in use, then copies the ones of `name` in the key map
+    pub fn switch_to(&mut self, name: &str, key_map: &mut KeyMap) {
+        self.profiles
+            .insert(self.in_use.clone(), key_map.map.clone());
+        *key_map = self.key_map(name);
+        self.in_use = name.to_string();
+    }
+
+    /// Profile after `name` in alphabetical order, going back to the first one after the last
+    pub fn next_profile(&self, name: &str) -> String {
+        self.profiles
+            .keys()
+            .find(|other| other.as_str() > name)
+            .or_else(|| self.profiles.keys().next())
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_PROFILE.into())
+    }
+
+    /// Creates a profile starting with the bindings in use, returns its name
+    pub fn add_profile(&mut self, key_map: &KeyMap) -> String {
+        let name = (2..)
+            .map(|i| format!("Profile {i}"))
+            .find(|name| !self.profiles.contains_key(name))
+            .unwrap();
+        self.profiles.insert(name.clone(), key_map.map.clone());
+        name
+    }
+
+    /// Goes through the profiles for the current world, then back to using the active one
+    pub fn cycle_world_override(&mut self) {
+        let Some(world) = self.world.clone() else {
+            return;
+        };
+        let next = match self.world_overrides.get(&world) {
+            None => self.profiles.keys().next().cloned(),
+            Some(current) => self.profiles.keys().find(|other| *other > current).cloned(),
+        };
+        match next {
+            Some(name) => self.world_overrides.insert(world, name),
+            None => self.world_overrides.remove(&world),
+        };
+    }
+}
+
+/// Remembers which world is played, singleplayer worlds by name and servers by address
+pub fn enter_world_key_profile(
+    mut profiles: ResMut<KeyProfiles>,
+    target: Res<TargetServer>,
+    selected_world: Res<SelectedWorld>,
+) {
+    profiles.world = if target.is_solo {
+        selected_world.name.clone()
+    } else {
+        target.address.map(|address| address.to_string())
+    };
+}
+
+pub fn leave_world_key_profile(mut profiles: ResMut<KeyProfiles>) {
+    profiles.world = None;
+}
+
+/// Puts the bindings of the wanted profile in the key map whenever it changes
+pub fn apply_key_profile_system(mut profiles: ResMut<KeyProfiles>, mut key_map: ResMut<KeyMap>) {
+    if !profiles.is_changed() || profiles.wanted() == profiles.in_use {
+        return;
+    }
+    let wanted = profiles.wanted().to_string();
+    info!("Using the {} keybindings", wanted);
+    profiles.switch_to(&wanted, &mut key_map);
+}
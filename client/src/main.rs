@@ -2,6 +2,7 @@ mod camera;
 mod constants;
 mod entities;
 mod game;
+mod headless;
 mod input;
 mod mob;
 mod network;
@@ -9,7 +10,14 @@ mod player;
 mod ui;
 mod world;
 
+use crate::camera::load_camera_settings;
+use crate::network::{ChunkCache, CHUNK_CACHE_PATH};
+use crate::ui::accessibility::load_accessibility_settings;
+use crate::ui::hud::theme::{update_hud_theme_system, HudTheme};
+use crate::ui::lang::{update_localization_system, Localization};
+use crate::world::mesh_cache::{MeshCache, MESH_CACHE_PATH};
 use crate::world::ClientWorldMap;
+use crate::world::{apply_video_settings_system, load_video_settings};
 use bevy::{
     prelude::*,
     render::{
@@ -19,12 +27,13 @@ use bevy::{
     window::PresentMode,
 };
 use bevy_inspector_egui::{bevy_egui::EguiPlugin, DefaultInspectorConfigPlugin};
-use clap::Parser;
-use constants::{TEXTURE_PATH_BASE, TEXTURE_PATH_CUSTOM};
-use input::{data::GameAction, keyboard::get_bindings};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use constants::{LOG_SETTINGS_PATH, TEXTURE_PATH_BASE, TEXTURE_PATH_CUSTOM};
+use input::{data::GameAction, keyboard::load_key_profiles, profiles::apply_key_profile_system};
 use menus::solo::SelectedWorld;
 use serde::{Deserialize, Serialize};
-use shared::{get_game_folder_paths, SpecialFlag};
+use shared::network::resolve_server_address;
+use shared::{get_game_folder_paths, logging::LogSettings, GameFolderPaths, SpecialFlag};
 use std::collections::BTreeMap;
 use ui::{
     hud::debug::inspector::inspector_ui,
@@ -53,6 +62,32 @@ struct Args {
 
     #[arg(short, long, help = "Player name to use for the game")]
     player_name: Option<String>,
+
+    #[arg(long, help = "Do not cache chunk meshes on disk")]
+    no_mesh_cache: bool,
+
+    #[arg(long, help = "Do not cache the chunks of the servers joined on disk")]
+    no_chunk_cache: bool,
+
+    #[arg(
+        long,
+        requires = "server",
+        help = "Connect scripted bots to a server without opening a window, for load testing"
+    )]
+    headless: bool,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Number of bots connected in headless mode"
+    )]
+    bots: usize,
+
+    #[arg(
+        long,
+        help = "Address or host name of the server the headless bots connect to"
+    )]
+    server: Option<String>,
 }
 
 #[derive(Component)]
@@ -66,7 +101,10 @@ pub enum GameState {
     Splash,
     #[default]
     Menu,
-    PreGameLoading,
+    /// Connecting and authenticating to the server, while textures load
+    Connecting,
+    /// Receiving and meshing the chunks around the player before letting them play
+    LoadingWorld,
     Game,
 }
 
@@ -90,10 +128,47 @@ pub struct PlayerNameSupplied {
     pub name: String,
 }
 
+/// Reads the log levels from the game folder, the logger is not set up yet so errors go to stderr
+fn load_log_settings(paths: &GameFolderPaths) -> LogSettings {
+    let path = paths.game_folder_path.join(LOG_SETTINGS_PATH);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return LogSettings::default();
+    };
+
+    ron::from_str(&content).unwrap_or_else(|e| {
+        eprintln!(
+            "Invalid log settings {}, using the defaults: {}",
+            path.display(),
+            e
+        );
+        LogSettings::default()
+    })
+}
+
 fn main() {
     // Parse command-line arguments
     let args = Args::parse();
 
+    if args.headless {
+        // `requires` already makes clap refuse `--headless` alone, this keeps the error if the attribute goes away
+        let Some(server) = args.server else {
+            Args::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "--headless needs the address of a server, given with --server <SERVER>",
+                )
+                .exit();
+        };
+        match resolve_server_address(&server) {
+            Ok(addresses) => headless::run_bots(addresses[0], args.bots),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     // Determine which texture path to use
     let texture_path = if args.use_custom_textures {
         TEXTURE_PATH_CUSTOM
@@ -120,12 +195,14 @@ fn main() {
     );
 
     let special_flag = SpecialFlag { special_flag };
+    let log_settings = load_log_settings(&game_folder_paths);
 
     let mut app = App::new();
     app.add_plugins(
         DefaultPlugins
             // Ensures that pixel-art textures will remain pixelated, and not become a blurry mess
             .set(ImagePlugin::default_nearest())
+            .set(log_settings.log_plugin(|_| None))
             .set(RenderPlugin {
                 render_creation: RenderCreation::Automatic(WgpuSettings {
                     // WARNING: This is a native-only feature. It will not work with WebGL or WebGPU
@@ -154,11 +231,41 @@ fn main() {
         enable_multipass_for_primary_context: false,
     })
     .add_plugins(DefaultInspectorConfigPlugin)
-    .add_systems(Update, inspector_ui);
+    .add_systems(Update, inspector_ui.run_if(in_state(GameState::Game)));
 
     app.add_event::<LoadWorldEvent>();
     network::add_base_netcode(&mut app);
-    app.insert_resource(get_bindings(&game_folder_paths))
+    let mesh_cache = if args.no_mesh_cache {
+        MeshCache::disabled()
+    } else {
+        MeshCache::open(game_folder_paths.game_folder_path.join(MESH_CACHE_PATH))
+    };
+    let chunk_cache = if args.no_chunk_cache {
+        ChunkCache::disabled()
+    } else {
+        ChunkCache::open(game_folder_paths.game_folder_path.join(CHUNK_CACHE_PATH))
+    };
+
+    let key_profiles = load_key_profiles(&game_folder_paths);
+    let accessibility = load_accessibility_settings(&game_folder_paths);
+    app.insert_resource(key_profiles.key_map(&key_profiles.in_use))
+        .insert_resource(key_profiles)
+        .insert_resource(load_camera_settings(&game_folder_paths))
+        .insert_resource(Localization::new(&accessibility.language))
+        .insert_resource(accessibility)
+        .insert_resource(load_video_settings(&game_folder_paths))
+        .init_resource::<HudTheme>()
+        .add_systems(
+            Update,
+            (
+                update_hud_theme_system,
+                update_localization_system,
+                apply_video_settings_system,
+                apply_key_profile_system,
+            ),
+        )
+        .insert_resource(mesh_cache)
+        .insert_resource(chunk_cache)
         .insert_resource(SelectedWorld::default())
         // Declare the game state, whose starting value is determined by the `Default` trait
         .insert_resource(ClientWorldMap { ..default() })
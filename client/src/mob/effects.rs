@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use shared::players::{Player, StatusEffectKind, StatusEffects};
+
+use crate::{player::CurrentPlayerMarker, ui::hud::effects::effect_color};
+
+use super::{spawn_particle, MobRoot};
+
+/// Effects the server reports on a mob
+#[derive(Component, Default)]
+pub struct MobEffects(pub StatusEffects);
+
+/// Particles per second around an entity, for each of its effects
+const EFFECT_PARTICLES_RATE: f32 = 4.0;
+
+#[derive(Resource)]
+pub struct EffectParticleAssets {
+    mesh: Handle<Mesh>,
+    materials: Vec<(StatusEffectKind, Handle<StandardMaterial>)>,
+}
+
+impl FromWorld for EffectParticleAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::from_length(0.1));
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let materials = StatusEffectKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let material = materials.add(StandardMaterial {
+                    base_color: effect_color(kind),
+                    unlit: true,
+                    ..Default::default()
+                });
+                (kind, material)
+            })
+            .collect();
+        Self { mesh, materials }
+    }
+}
+
+impl EffectParticleAssets {
+    fn material(&self, kind: StatusEffectKind) -> Option<Handle<StandardMaterial>> {
+        self.materials
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, material)| material.clone())
+    }
+}
+
+/// Swirls colored particles around the players and the mobs under effects.
+/// The current player is left out, the particles would get in the way of the camera.
+pub fn effect_particles_system(
+    mut commands: Commands,
+    particle: Res<EffectParticleAssets>,
+    mobs: Query<(&Transform, &MobEffects), With<MobRoot>>,
+    players: Query<(&Transform, &Player), Without<CurrentPlayerMarker>>,
+    time: Res<Time>,
+) {
+    let mut rng = thread_rng();
+    let chance = (EFFECT_PARTICLES_RATE * time.delta_secs()).min(1.0);
+
+    let sources = mobs
+        .iter()
+        .map(|(transform, effects)| (transform, &effects.0))
+        .chain(
+            players
+                .iter()
+                .map(|(transform, player)| (transform, &player.effects)),
+        );
+    for (transform, effects) in sources {
+        for (kind, _) in effects.iter() {
+            if rng.r#gen::<f32>() > chance {
+                continue;
+            }
+            let Some(material) = particle.material(kind) else {
+                continue;
+            };
+
+            let offset = Vec3::new(
+                rng.gen_range(-0.4..0.4),
+                rng.gen_range(-0.6..0.9),
+                rng.gen_range(-0.4..0.4),
+            );
+            commands.queue(spawn_particle(
+                particle.mesh.clone(),
+                material,
+                transform.translation + offset,
+                rng.gen_range(0.6..1.0),
+                rng.gen_range(0.6..1.0),
+                Vec3::new(0.0, rng.gen_range(0.3..0.8), 0.0),
+            ));
+        }
+    }
+}
@@ -2,10 +2,14 @@
 
 use std::time::Duration;
 
-use bevy::{animation::AnimationTargetId, color::palettes::css::WHITE, prelude::*};
+use bevy::{
+    animation::AnimationTargetId, color::palettes::css::WHITE, pbr::NotShadowCaster, prelude::*,
+};
 use rand::{thread_rng, Rng};
+use shared::world::MobKind;
 
 use super::{MobMarker, MobRoot, TargetedMob};
+use crate::world::VideoSettings;
 
 const FOX_PATH: &str = "models/animated/Fox.glb";
 
@@ -73,6 +77,7 @@ pub fn setup_fox(
             MobRoot {
                 name: name.clone(),
                 id,
+                kind: MobKind::Fox,
             },
             MobMarker {
                 name: name.clone(),
@@ -167,7 +172,7 @@ pub fn simulate_particles(
     }
 }
 
-fn spawn_particle<M: Material>(
+pub(crate) fn spawn_particle<M: Material>(
     mesh: Handle<Mesh>,
     material: Handle<M>,
     translation: Vec3,
@@ -176,14 +181,24 @@ fn spawn_particle<M: Material>(
     velocity: Vec3,
 ) -> impl Command {
     move |world: &mut World| {
+        // Lower video settings only spawn some of the particles
+        let density = world
+            .get_resource::<VideoSettings>()
+            .map_or(1.0, |video| video.particles);
+        if thread_rng().gen::<f32>() >= density {
+            return;
+        }
         world.spawn((
             Particle {
                 lifeteime_timer: Timer::from_seconds(lifetime, TimerMode::Once),
                 size,
                 velocity,
             },
+            // Particles share their mesh and material so that they are drawn as one instanced batch,
+            // and are too small and short-lived to be worth a shadow
             Mesh3d(mesh),
             MeshMaterial3d(material),
+            NotShadowCaster,
             Transform {
                 translation,
                 scale: Vec3::splat(size),
@@ -290,15 +305,14 @@ pub fn update_targetted_mob_color(
         None => 0u128,
     };
 
+    // Glows instead of tinting the base color, which mobs built from plain colored meshes rely on
     for (material, mob) in &mut query.iter_mut() {
-        if mob.id == target_id {
-            let handle = material.0.clone();
-            let material = materials.get_mut(&handle).unwrap();
-            material.base_color = Color::srgb(1.0, 0.0, 0.0);
+        let handle = material.0.clone();
+        let material = materials.get_mut(&handle).unwrap();
+        material.emissive = if mob.id == target_id {
+            LinearRgba::rgb(0.6, 0.0, 0.0)
         } else {
-            let handle = material.0.clone();
-            let material = materials.get_mut(&handle).unwrap();
-            material.base_color = Color::srgb(1.0, 1.0, 1.0);
-        }
+            LinearRgba::BLACK
+        };
     }
 }
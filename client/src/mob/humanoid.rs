@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use shared::world::MobKind;
+
+use super::{spawn_particle, MobMarker, MobRoot};
+
+/// Added to the mobs the server reports as burning
+#[derive(Component)]
+pub struct MobOnFire;
+
+const ZOMBIE_SKIN_COLOR: Color = Color::srgb(0.33, 0.55, 0.3);
+const ZOMBIE_SHIRT_COLOR: Color = Color::srgb(0.2, 0.55, 0.6);
+const ZOMBIE_PANTS_COLOR: Color = Color::srgb(0.25, 0.2, 0.5);
+const SKELETON_BONE_COLOR: Color = Color::srgb(0.85, 0.85, 0.8);
+const WOLF_FUR_COLOR: Color = Color::srgb(0.7, 0.7, 0.68);
+const WOLF_SNOUT_COLOR: Color = Color::srgb(0.35, 0.3, 0.28);
+const VILLAGER_SKIN_COLOR: Color = Color::srgb(0.75, 0.55, 0.42);
+const VILLAGER_ROBE_COLOR: Color = Color::srgb(0.45, 0.3, 0.2);
+
+/// Body part as `(size, offset from the mob center, color)`, mobs face +Z
+type BodyPart = (Vec3, Vec3, Color);
+
+const ZOMBIE_PARTS: [BodyPart; 6] = [
+    (
+        Vec3::new(0.5, 0.5, 0.5),
+        Vec3::new(0.0, 0.65, 0.0),
+        ZOMBIE_SKIN_COLOR,
+    ),
+    (
+        Vec3::new(0.5, 0.7, 0.3),
+        Vec3::new(0.0, 0.05, 0.0),
+        ZOMBIE_SHIRT_COLOR,
+    ),
+    (
+        Vec3::new(0.2, 0.2, 0.7),
+        Vec3::new(-0.35, 0.3, 0.25),
+        ZOMBIE_SKIN_COLOR,
+    ),
+    (
+        Vec3::new(0.2, 0.2, 0.7),
+        Vec3::new(0.35, 0.3, 0.25),
+        ZOMBIE_SKIN_COLOR,
+    ),
+    (
+        Vec3::new(0.24, 0.6, 0.24),
+        Vec3::new(-0.13, -0.6, 0.0),
+        ZOMBIE_PANTS_COLOR,
+    ),
+    (
+        Vec3::new(0.24, 0.6, 0.24),
+        Vec3::new(0.13, -0.6, 0.0),
+        ZOMBIE_PANTS_COLOR,
+    ),
+];
+
+/// Thinner than a zombie, holding its bow arm forward
+const SKELETON_PARTS: [BodyPart; 6] = [
+    (
+        Vec3::new(0.45, 0.45, 0.45),
+        Vec3::new(0.0, 0.65, 0.0),
+        SKELETON_BONE_COLOR,
+    ),
+    (
+        Vec3::new(0.4, 0.7, 0.15),
+        Vec3::new(0.0, 0.05, 0.0),
+        SKELETON_BONE_COLOR,
+    ),
+    (
+        Vec3::new(0.1, 0.1, 0.65),
+        Vec3::new(-0.27, 0.3, 0.25),
+        SKELETON_BONE_COLOR,
+    ),
+    (
+        Vec3::new(0.1, 0.65, 0.1),
+        Vec3::new(0.27, 0.05, 0.0),
+        SKELETON_BONE_COLOR,
+    ),
+    (
+        Vec3::new(0.1, 0.6, 0.1),
+        Vec3::new(-0.1, -0.6, 0.0),
+        SKELETON_BONE_COLOR,
+    ),
+    (
+        Vec3::new(0.1, 0.6, 0.1),
+        Vec3::new(0.1, -0.6, 0.0),
+        SKELETON_BONE_COLOR,
+    ),
+];
+
+const WOLF_PARTS: [BodyPart; 9] = [
+    (
+        Vec3::new(0.4, 0.4, 0.8),
+        Vec3::new(0.0, 0.05, 0.0),
+        WOLF_FUR_COLOR,
+    ),
+    (
+        Vec3::new(0.35, 0.35, 0.35),
+        Vec3::new(0.0, 0.2, 0.55),
+        WOLF_FUR_COLOR,
+    ),
+    (
+        Vec3::new(0.15, 0.12, 0.15),
+        Vec3::new(0.0, 0.12, 0.78),
+        WOLF_SNOUT_COLOR,
+    ),
+    (
+        Vec3::new(0.1, 0.1, 0.35),
+        Vec3::new(0.0, 0.15, -0.55),
+        WOLF_FUR_COLOR,
+    ),
+    (
+        Vec3::new(0.12, 0.3, 0.12),
+        Vec3::new(-0.13, -0.28, 0.28),
+        WOLF_FUR_COLOR,
+    ),
+    (
+        Vec3::new(0.12, 0.3, 0.12),
+        Vec3::new(0.13, -0.28, 0.28),
+        WOLF_FUR_COLOR,
+    ),
+    (
+        Vec3::new(0.12, 0.3, 0.12),
+        Vec3::new(-0.13, -0.28, -0.28),
+        WOLF_FUR_COLOR,
+    ),
+    (
+        Vec3::new(0.12, 0.3, 0.12),
+        Vec3::new(0.13, -0.28, -0.28),
+        WOLF_FUR_COLOR,
+    ),
+    (
+        Vec3::new(0.08, 0.1, 0.08),
+        Vec3::new(0.0, 0.42, 0.5),
+        WOLF_FUR_COLOR,
+    ),
+];
+
+/// Long robe down to the feet, arms crossed in front of it
+const VILLAGER_PARTS: [BodyPart; 4] = [
+    (
+        Vec3::new(0.5, 0.55, 0.5),
+        Vec3::new(0.0, 0.62, 0.0),
+        VILLAGER_SKIN_COLOR,
+    ),
+    (
+        Vec3::new(0.12, 0.25, 0.12),
+        Vec3::new(0.0, 0.5, 0.3),
+        VILLAGER_SKIN_COLOR,
+    ),
+    (
+        Vec3::new(0.5, 1.25, 0.35),
+        Vec3::new(0.0, -0.27, 0.0),
+        VILLAGER_ROBE_COLOR,
+    ),
+    (
+        Vec3::new(0.6, 0.2, 0.25),
+        Vec3::new(0.0, 0.05, 0.28),
+        VILLAGER_ROBE_COLOR,
+    ),
+];
+
+/// Tilt of the models of sitting mobs, raising their head
+const SITTING_TILT: f32 = 0.4;
+const SITTING_DROP: f32 = 0.15;
+
+/// Fire particles spawned per second on a burning mob
+const FIRE_PARTICLES_RATE: f32 = 20.0;
+
+/// Builds a mob model out of plain colored boxes
+fn setup_cuboid_mob(
+    id: u128,
+    kind: MobKind,
+    parts: &[BodyPart],
+    spawn_pos: Vec3,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) -> Entity {
+    let name = format!("{kind:?}");
+
+    commands
+        .spawn((
+            Name::new(name.clone()),
+            Transform::from_translation(spawn_pos),
+            Visibility::default(),
+            MobRoot {
+                name: name.clone(),
+                id,
+                kind,
+            },
+        ))
+        .with_children(|root| {
+            for (size, offset, color) in parts {
+                // Each mob owns its materials, so that highlighting one does not highlight them all
+                root.spawn((
+                    Mesh3d(meshes.add(Cuboid::from_size(*size))),
+                    MeshMaterial3d(materials.add(StandardMaterial {
+                        base_color: *color,
+                        perceptual_roughness: 1.0,
+                        ..default()
+                    })),
+                    Transform::from_translation(*offset),
+                    MobMarker {
+                        name: name.clone(),
+                        id,
+                    },
+                ));
+            }
+        })
+        .id()
+}
+
+pub fn setup_zombie(
+    id: u128,
+    spawn_pos: Vec3,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let zombie = setup_cuboid_mob(
+        id,
+        MobKind::Zombie,
+        &ZOMBIE_PARTS,
+        spawn_pos,
+        commands,
+        meshes,
+        materials,
+    );
+    info!("Spawned zombie: {:?}", zombie);
+}
+
+pub fn setup_skeleton(
+    id: u128,
+    spawn_pos: Vec3,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let skeleton = setup_cuboid_mob(
+        id,
+        MobKind::Skeleton,
+        &SKELETON_PARTS,
+        spawn_pos,
+        commands,
+        meshes,
+        materials,
+    );
+    info!("Spawned skeleton: {:?}", skeleton);
+}
+
+pub fn setup_wolf(
+    id: u128,
+    spawn_pos: Vec3,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let wolf = setup_cuboid_mob(
+        id,
+        MobKind::Wolf,
+        &WOLF_PARTS,
+        spawn_pos,
+        commands,
+        meshes,
+        materials,
+    );
+    info!("Spawned wolf: {:?}", wolf);
+}
+
+pub fn setup_villager(
+    id: u128,
+    spawn_pos: Vec3,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let villager = setup_cuboid_mob(
+        id,
+        MobKind::Villager,
+        &VILLAGER_PARTS,
+        spawn_pos,
+        commands,
+        meshes,
+        materials,
+    );
+    info!("Spawned villager: {:?}", villager);
+}
+
+/// Poses the model of a sitting mob, from the transform matching its server position
+pub fn apply_sitting_pose(transform: &mut Transform) {
+    transform.rotation *= Quat::from_rotation_x(-SITTING_TILT);
+    transform.translation.y -= SITTING_DROP;
+}
+
+#[derive(Resource)]
+pub struct FireParticleAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for FireParticleAssets {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh: world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Cuboid::from_length(0.15)),
+            material: world
+                .resource_mut::<Assets<StandardMaterial>>()
+                .add(StandardMaterial {
+                    base_color: Color::srgb(1.0, 0.5, 0.0),
+                    emissive: LinearRgba::rgb(4.0, 1.5, 0.0),
+                    unlit: true,
+                    ..Default::default()
+                }),
+        }
+    }
+}
+
+pub fn burning_mobs_particles_system(
+    mut commands: Commands,
+    particle: Res<FireParticleAssets>,
+    mobs: Query<&Transform, (With<MobRoot>, With<MobOnFire>)>,
+    time: Res<Time>,
+) {
+    let mut rng = thread_rng();
+    let chance = (FIRE_PARTICLES_RATE * time.delta_secs()).min(1.0);
+
+    for transform in mobs.iter() {
+        if rng.r#gen::<f32>() > chance {
+            continue;
+        }
+
+        let offset = Vec3::new(
+            rng.gen_range(-0.3..0.3),
+            rng.gen_range(-0.9..0.9),
+            rng.gen_range(-0.3..0.3),
+        );
+        commands.queue(spawn_particle(
+            particle.mesh.clone(),
+            particle.material.clone(),
+            transform.translation + offset,
+            rng.gen_range(0.3..0.7),
+            rng.gen_range(0.6..1.2),
+            Vec3::new(0.0, rng.gen_range(1.0..2.5), 0.0),
+        ));
+    }
+}
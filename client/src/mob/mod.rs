@@ -1,9 +1,14 @@
 use bevy::prelude::*;
+use shared::world::MobKind;
 
+mod effects;
 mod fox;
+mod humanoid;
 mod spawn;
 
+pub use effects::*;
 pub use fox::*;
+pub use humanoid::*;
 pub use spawn::*;
 
 #[derive(Debug, Component, Clone)]
@@ -12,6 +17,7 @@ pub struct MobRoot {
     pub name: String,
     #[allow(dead_code)]
     pub id: u128,
+    pub kind: MobKind,
 }
 
 #[derive(Debug, Component, Clone)]
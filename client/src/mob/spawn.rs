@@ -1,15 +1,24 @@
 use bevy::prelude::*;
-use shared::messages::mob::MobUpdateEvent;
+use shared::messages::mob::{MobDespawnEvent, MobUpdateEvent};
+use shared::world::MobKind;
 
-use crate::{mob::setup_fox, player::CurrentPlayerMarker, world::RenderDistance};
+use crate::{
+    mob::{
+        apply_sitting_pose, setup_fox, setup_skeleton, setup_villager, setup_wolf, setup_zombie,
+    },
+    player::CurrentPlayerMarker,
+    world::RenderDistance,
+};
 
-use super::MobRoot;
+use super::{MobEffects, MobOnFire, MobRoot};
 
 pub fn spawn_mobs_system(
     mut ev_update: EventReader<MobUpdateEvent>,
+    mut ev_despawn: EventReader<MobDespawnEvent>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut graphs: ResMut<Assets<AnimationGraph>>,
+    (mut meshes, mut materials): (ResMut<Assets<Mesh>>, ResMut<Assets<StandardMaterial>>),
     mut mobs: Query<(Entity, &MobRoot, &mut Transform), Without<CurrentPlayerMarker>>,
     player_pos: Query<&Transform, With<CurrentPlayerMarker>>,
     render_distance: Res<RenderDistance>,
@@ -21,19 +30,58 @@ pub fn spawn_mobs_system(
 
         let position = event.mob.position;
 
-        for (_, mob, mut transform) in mobs.iter_mut() {
+        for (entity, mob, mut transform) in mobs.iter_mut() {
             if mob.id == id {
                 transform.translation = position;
                 transform.rotation = event.mob.rotation;
+                if event.mob.sitting {
+                    apply_sitting_pose(&mut transform);
+                }
+                if event.mob.on_fire {
+                    commands.entity(entity).insert(MobOnFire);
+                } else {
+                    commands.entity(entity).remove::<MobOnFire>();
+                }
+                commands
+                    .entity(entity)
+                    .insert(MobEffects(event.mob.effects.clone()));
                 continue 'event_loop;
             }
         }
 
-        if event.mob.kind == shared::world::MobKind::Fox
-            && render_distance.close_enough(&event.mob.position, &player_pos)
-        {
-            info!("Spawning fox at {:?}", position);
-            setup_fox(id, position, &mut commands, &asset_server, &mut graphs);
+        if !render_distance.close_enough(&event.mob.position, &player_pos) {
+            continue;
+        }
+
+        match event.mob.kind {
+            MobKind::Fox => {
+                info!("Spawning fox at {:?}", position);
+                setup_fox(id, position, &mut commands, &asset_server, &mut graphs);
+            }
+            MobKind::Zombie => {
+                info!("Spawning zombie at {:?}", position);
+                setup_zombie(id, position, &mut commands, &mut meshes, &mut materials);
+            }
+            MobKind::Skeleton => {
+                info!("Spawning skeleton at {:?}", position);
+                setup_skeleton(id, position, &mut commands, &mut meshes, &mut materials);
+            }
+            MobKind::Wolf => {
+                info!("Spawning wolf at {:?}", position);
+                setup_wolf(id, position, &mut commands, &mut meshes, &mut materials);
+            }
+            MobKind::Villager => {
+                info!("Spawning villager at {:?}", position);
+                setup_villager(id, position, &mut commands, &mut meshes, &mut materials);
+            }
+        }
+    }
+
+    for event in ev_despawn.read() {
+        for (entity, mob, _) in mobs.iter() {
+            if mob.id == event.id {
+                commands.entity(entity).despawn();
+            }
         }
     }
 
@@ -1,5 +1,14 @@
+use std::collections::VecDeque;
+
 use bevy::{platform::collections::HashSet, prelude::*};
-use shared::{messages::PlayerFrameInput, players::ViewMode};
+use bevy_renet::renet::RenetClient;
+use shared::{
+    messages::{ClientToServerMessage, PlayerFrameInput, TimeSyncRequest, TimeSyncResponse},
+    players::ViewMode,
+    utils::unix_time_ms,
+};
+
+use super::SendGameMessageExtension;
 
 #[derive(Debug, Default, Resource)]
 pub struct PlayerTickInputsBuffer {
@@ -23,29 +32,46 @@ impl CurrentFrameInputsExt for CurrentFrameInputs {
             position: Vec3::default(),
             hotbar_slot: 0,
             view_mode: ViewMode::default(),
+            predicted_edit: None,
         };
     }
 }
 
-// Represents the synchronized time of the client
-// Currently, this is just the UNIX timestamp in milliseconds, assumed to be the same on the server and client
-// A NTP-like system could be implemented in the future
+/// Round trips slower than this give poor offset estimates and are ignored
+const MAX_SAMPLE_RTT_MS: u64 = 1000;
+const SAMPLES_KEPT: usize = 8;
+/// Offset errors larger than this are a resync: ahead of the estimate the clock jumps, behind it slows down a lot
+const RESYNC_THRESHOLD_MS: i64 = 250;
+/// How much faster or slower than real time the clock may run while it catches up with the server
+const SLEW_RATE: f64 = 0.1;
+const RESYNC_SLEW_RATE: f64 = 0.5;
+
+/// Clock of the client, following the one of the server.
+/// It never goes backwards since inputs are acknowledged by their timestamp, so it is slowed down instead.
 #[derive(Resource)]
 pub struct SyncTime {
     pub last_time_ms: u64,
     pub curr_time_ms: u64,
+    /// Added to the local clock to get the server one
+    pub offset_ms: i64,
+    /// Offset estimated from the last sync responses, `offset_ms` moves towards it
+    pub target_offset_ms: i64,
+    /// Round trip and offset of the last sync responses
+    samples: VecDeque<(u64, i64)>,
+    last_local_ms: u64,
 }
 
 impl Default for SyncTime {
     fn default() -> Self {
-        let current_time_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        let current_time_ms = unix_time_ms();
 
         Self {
             last_time_ms: current_time_ms,
             curr_time_ms: current_time_ms,
+            offset_ms: 0,
+            target_offset_ms: 0,
+            samples: VecDeque::new(),
+            last_local_ms: current_time_ms,
         }
     }
 }
@@ -53,18 +79,89 @@ impl Default for SyncTime {
 pub trait SyncTimeExt {
     fn delta(&self) -> u64;
     fn advance(&mut self);
+    /// Starts over from a server timestamp, before any input was sent
+    fn reset_to_server_time(&mut self, server_time_ms: u64);
+    fn add_sync_sample(&mut self, response: &TimeSyncResponse);
 }
 
 impl SyncTimeExt for SyncTime {
     fn delta(&self) -> u64 {
-        self.curr_time_ms - self.last_time_ms
+        self.curr_time_ms.saturating_sub(self.last_time_ms)
     }
 
     fn advance(&mut self) {
+        let local_ms = unix_time_ms();
+        let real_delta = local_ms.saturating_sub(self.last_local_ms);
+        self.last_local_ms = local_ms;
+
+        let error = self.target_offset_ms - self.offset_ms;
+        if error > RESYNC_THRESHOLD_MS {
+            self.offset_ms = self.target_offset_ms;
+        } else {
+            let rate = if error.abs() > RESYNC_THRESHOLD_MS {
+                RESYNC_SLEW_RATE
+            } else {
+                SLEW_RATE
+            };
+            let max_step = (real_delta as f64 * rate).ceil() as i64;
+            self.offset_ms += error.clamp(-max_step, max_step);
+        }
+
         self.last_time_ms = self.curr_time_ms;
-        self.curr_time_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        self.curr_time_ms = (local_ms as i64 + self.offset_ms).max(self.last_time_ms as i64) as u64;
     }
+
+    fn reset_to_server_time(&mut self, server_time_ms: u64) {
+        let local_ms = unix_time_ms();
+        self.offset_ms = server_time_ms as i64 - local_ms as i64;
+        self.target_offset_ms = self.offset_ms;
+        self.samples.clear();
+        self.last_local_ms = local_ms;
+        self.last_time_ms = server_time_ms;
+        self.curr_time_ms = server_time_ms;
+    }
+
+    fn add_sync_sample(&mut self, response: &TimeSyncResponse) {
+        let local_ms = unix_time_ms();
+        let rtt = local_ms.saturating_sub(response.client_send_ms);
+        if rtt > MAX_SAMPLE_RTT_MS {
+            return;
+        }
+
+        let offset = response.server_time_ms as i64 + (rtt / 2) as i64 - local_ms as i64;
+        self.samples.push_back((rtt, offset));
+        if self.samples.len() > SAMPLES_KEPT {
+            self.samples.pop_front();
+        }
+
+        // The fastest round trip waited the least in queues, so its offset is the most accurate
+        let Some(&(_, best_offset)) = self.samples.iter().min_by_key(|(rtt, _)| *rtt) else {
+            return;
+        };
+        let drift = best_offset - self.offset_ms;
+        if drift.abs() > RESYNC_THRESHOLD_MS {
+            warn!("Clock is {} ms away from the server, resyncing", drift);
+        }
+        self.target_offset_ms = best_offset;
+    }
+}
+
+/// Asks the server for its time every that many seconds
+const TIME_SYNC_INTERVAL_SECS: f32 = 5.0;
+
+pub fn send_time_sync_requests_system(
+    mut client: ResMut<RenetClient>,
+    time: Res<Time>,
+    mut since_last: Local<Option<f32>>,
+) {
+    let elapsed = since_last.get_or_insert(TIME_SYNC_INTERVAL_SECS);
+    *elapsed += time.delta_secs();
+    if *elapsed < TIME_SYNC_INTERVAL_SECS {
+        return;
+    }
+    *elapsed = 0.0;
+
+    client.send_game_message(ClientToServerMessage::TimeSync(TimeSyncRequest {
+        client_send_ms: unix_time_ms(),
+    }));
 }
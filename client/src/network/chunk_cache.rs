@@ -0,0 +1,341 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::{Instant, UNIX_EPOCH},
+};
+
+use bevy::{prelude::*, tasks::IoTaskPool};
+use bevy_renet::renet::RenetClient;
+use shared::messages::{CachedChunk, CachedChunksBatch, ClientToServerMessage};
+use shared::world::ServerChunk;
+
+use crate::world::{ClientChunk, ClientWorldMap, WorldRenderRequestUpdateEvent};
+
+use super::SendGameMessageExtension;
+
+/// Folder of the chunks cached from servers, relative to the game folder. Each world of each server has its own folder
+pub const CHUNK_CACHE_PATH: &str = "cache/worlds";
+/// The list of cached chunks is split so that large bases do not end up in a single huge message
+const CACHED_CHUNKS_PER_MESSAGE: usize = 1024;
+/// Once the cached chunks of all the worlds get bigger than this on disk, the least recently used ones are evicted
+const CHUNK_CACHE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+fn chunk_file_name(pos: IVec3, ts: u64) -> String {
+    format!("{}_{}_{}_{}.bin", pos.x, pos.y, pos.z, ts)
+}
+
+fn parse_chunk_file_name(name: &str) -> Option<(IVec3, u64)> {
+    let mut parts = name.strip_suffix(".bin")?.split('_');
+    let x = parts.next()?.parse().ok()?;
+    let y = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    let ts = parts.next()?.parse().ok()?;
+    Some((IVec3::new(x, y, z), ts))
+}
+
+/// A chunk of the world being played which is on disk
+struct CacheEntry {
+    /// Timestamp of the last update of the chunk this copy holds
+    ts: u64,
+    size: u64,
+    /// Value of `ChunkCache::clock` when the chunk was last loaded or stored
+    last_used: u64,
+}
+
+/// Chunks received from servers, kept on disk so that rejoining a world shows them right away
+/// and the server only sends again the ones updated since.
+/// Singleplayer worlds are not cached, their server reads the chunks from the same disk
+#[derive(Resource, Default)]
+pub struct ChunkCache {
+    /// `None` when chunks are not cached at all
+    root: Option<PathBuf>,
+    /// Folder of the world being played, `None` while it is not cached
+    world: Option<PathBuf>,
+    index: HashMap<IVec3, CacheEntry>,
+    /// Chunks read when the world was opened, until the world starts loading
+    loaded: Vec<(IVec3, ServerChunk)>,
+    world_size: u64,
+    /// Size of the chunks cached for the other worlds, they are only evicted when a world is opened
+    other_worlds_size: u64,
+    clock: u64,
+}
+
+impl ChunkCache {
+    /// A cache which never stores anything
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Caches the chunks of each world in a folder of `root`
+    pub fn open(root: PathBuf) -> Self {
+        Self {
+            root: Some(root),
+            ..default()
+        }
+    }
+
+    /// Stops caching chunks until the next world is opened
+    pub fn close_world(&mut self) {
+        self.world = None;
+        self.index.clear();
+        self.loaded.clear();
+        self.world_size = 0;
+        self.other_worlds_size = 0;
+    }
+
+    /// Switches to the folder of a world of a server and reads the chunks already cached for it.
+    /// `world_id` changes along with anything the terrain is generated from, so that a world
+    /// regenerated with another preset or other packs does not show stale chunks
+    pub fn open_world(&mut self, address: SocketAddr, world_id: &str) {
+        self.close_world();
+        let Some(root) = &self.root else {
+            return;
+        };
+
+        let mut hasher = DefaultHasher::new();
+        address.hash(&mut hasher);
+        world_id.hash(&mut hasher);
+        let folder = root.join(format!("{:016x}", hasher.finish()));
+
+        if let Err(e) = fs::create_dir_all(&folder) {
+            warn!(
+                "Could not create chunk cache folder {}, chunks will not be cached: {}",
+                folder.display(),
+                e
+            );
+            return;
+        }
+
+        let total_size = evict_least_recently_written(root);
+        let mut folder_size = 0;
+        if let Ok(files) = fs::read_dir(&folder) {
+            for file in files.flatten() {
+                let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                folder_size += size;
+                let Some((pos, ts)) = file.file_name().to_str().and_then(parse_chunk_file_name)
+                else {
+                    continue;
+                };
+                let entry = CacheEntry {
+                    ts,
+                    size,
+                    last_used: 0,
+                };
+                // Older copies are left behind when the game stops while writing a newer one
+                match self.index.get(&pos) {
+                    Some(kept) if kept.ts >= ts => {
+                        let _ = fs::remove_file(file.path());
+                    }
+                    Some(kept) => {
+                        let _ = fs::remove_file(folder.join(chunk_file_name(pos, kept.ts)));
+                        self.index.insert(pos, entry);
+                    }
+                    None => {
+                        self.index.insert(pos, entry);
+                    }
+                }
+            }
+        }
+        self.other_worlds_size = total_size.saturating_sub(folder_size);
+        self.world = Some(folder);
+        self.loaded = self.load();
+
+        info!(
+            "Chunk cache of {} ({}) holds {} chunks",
+            address,
+            world_id,
+            self.index.len()
+        );
+    }
+
+    /// Reads the cached chunks of the world, the unreadable ones are dropped
+    fn load(&mut self) -> Vec<(IVec3, ServerChunk)> {
+        let Some(folder) = &self.world else {
+            return vec![];
+        };
+
+        self.clock += 1;
+        let mut chunks = Vec::with_capacity(self.index.len());
+        let mut unreadable = vec![];
+        for (&pos, entry) in self.index.iter_mut() {
+            let path = folder.join(chunk_file_name(pos, entry.ts));
+            match fs::read(&path)
+                .ok()
+                .and_then(|bytes| bincode::deserialize::<ServerChunk>(&bytes).ok())
+            {
+                Some(chunk) => {
+                    entry.last_used = self.clock;
+                    chunks.push((pos, chunk));
+                }
+                None => {
+                    warn!("Dropping unreadable cached chunk {}", path.display());
+                    let _ = fs::remove_file(&path);
+                    unreadable.push(pos);
+                }
+            }
+        }
+        for pos in unreadable {
+            self.index.remove(&pos);
+        }
+        self.world_size = self.index.values().map(|entry| entry.size).sum();
+        chunks
+    }
+
+    /// Tells the server which chunks the client has, it waits for the last batch before streaming any.
+    /// Sent even when nothing is cached
+    pub fn send_cached_chunks(&self, client: &mut RenetClient) {
+        let cached: Vec<CachedChunk> = self
+            .loaded
+            .iter()
+            .map(|(pos, chunk)| CachedChunk {
+                pos: *pos,
+                ts: chunk.ts,
+            })
+            .collect();
+        let batches = cached.len().div_ceil(CACHED_CHUNKS_PER_MESSAGE).max(1);
+        let mut chunks = cached.chunks(CACHED_CHUNKS_PER_MESSAGE);
+        for i in 0..batches {
+            client.send_game_message(ClientToServerMessage::CachedChunks(CachedChunksBatch {
+                chunks: chunks.next().unwrap_or_default().to_vec(),
+                last: i + 1 == batches,
+            }));
+        }
+    }
+
+    /// Keeps a chunk received from the server, written in the background unless this copy is already cached
+    pub fn store(&mut self, pos: IVec3, chunk: &ServerChunk) {
+        let Some(folder) = &self.world else {
+            return;
+        };
+        self.clock += 1;
+        if let Some(entry) = self.index.get_mut(&pos) {
+            if entry.ts == chunk.ts {
+                entry.last_used = self.clock;
+                return;
+            }
+        }
+        let Ok(bytes) = bincode::serialize(chunk) else {
+            return;
+        };
+
+        let path = folder.join(chunk_file_name(pos, chunk.ts));
+        let previous = self.index.insert(
+            pos,
+            CacheEntry {
+                ts: chunk.ts,
+                size: bytes.len() as u64,
+                last_used: self.clock,
+            },
+        );
+        self.world_size += bytes.len() as u64;
+        let stale = previous.map(|entry| {
+            self.world_size -= entry.size;
+            folder.join(chunk_file_name(pos, entry.ts))
+        });
+        IoTaskPool::get()
+            .spawn(async move {
+                if let Err(e) = fs::write(&path, bytes) {
+                    warn!("Could not write cached chunk {}: {}", path.display(), e);
+                    return;
+                }
+                if let Some(stale) = stale {
+                    let _ = fs::remove_file(stale);
+                }
+            })
+            .detach();
+
+        self.evict();
+    }
+
+    /// Removes the least recently used chunks of the world until the cache fits in its size cap
+    fn evict(&mut self) {
+        if self.world_size + self.other_worlds_size <= CHUNK_CACHE_MAX_BYTES {
+            return;
+        }
+        let Some(folder) = &self.world else {
+            return;
+        };
+
+        let mut by_use: Vec<(IVec3, u64)> = self
+            .index
+            .iter()
+            .map(|(pos, entry)| (*pos, entry.last_used))
+            .collect();
+        by_use.sort_by_key(|(_, last_used)| *last_used);
+
+        for (pos, _) in by_use {
+            if self.world_size + self.other_worlds_size <= CHUNK_CACHE_MAX_BYTES {
+                break;
+            }
+            if let Some(entry) = self.index.remove(&pos) {
+                self.world_size -= entry.size;
+                let _ = fs::remove_file(folder.join(chunk_file_name(pos, entry.ts)));
+            }
+        }
+    }
+}
+
+/// Removes the least recently written chunks of all the worlds until the cache fits in its size cap,
+/// returns the size of what is left
+fn evict_least_recently_written(root: &Path) -> u64 {
+    let mut files = vec![];
+    for world in fs::read_dir(root).into_iter().flatten().flatten() {
+        for file in fs::read_dir(world.path()).into_iter().flatten().flatten() {
+            if let Ok(metadata) = file.metadata() {
+                let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+                files.push((file.path(), metadata.len(), modified));
+            }
+        }
+    }
+
+    let mut total_size: u64 = files.iter().map(|(_, size, _)| size).sum();
+    if total_size <= CHUNK_CACHE_MAX_BYTES {
+        return total_size;
+    }
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in files {
+        if total_size <= CHUNK_CACHE_MAX_BYTES {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total_size -= size;
+        }
+    }
+    total_size
+}
+
+/// Shows the cached chunks of the world as soon as it starts loading
+pub fn load_cached_chunks_system(
+    mut cache: ResMut<ChunkCache>,
+    mut world: ResMut<ClientWorldMap>,
+    mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
+) {
+    let chunks = std::mem::take(&mut cache.loaded);
+    if chunks.is_empty() {
+        return;
+    }
+
+    let mut shown = 0;
+    for (pos, chunk) in chunks {
+        // Chunks already received from the server are newer
+        if world.map.contains_key(&pos) {
+            continue;
+        }
+        world.insert_chunk(
+            pos,
+            ClientChunk {
+                map: chunk.map,
+                entity: None,
+                mesh: None,
+                last_mesh_ts: Instant::now(),
+                biomes: chunk.biomes,
+            },
+        );
+        ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(pos));
+        shown += 1;
+    }
+    info!("Loaded {} chunks from the cache", shown);
+}
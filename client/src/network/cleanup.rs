@@ -1,24 +1,35 @@
-use crate::network::{SendGameMessageExtension, TargetServer, TargetServerState};
+use crate::network::{
+    PendingConnectGrant, SendGameMessageExtension, TargetServer, TargetServerState,
+};
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 use shared::messages::ClientToServerMessage;
 
-use super::{buffered_client::PlayerTickInputsBuffer, UnacknowledgedInputs};
+use super::{buffered_client::PlayerTickInputsBuffer, save::UnsavedProgress, UnacknowledgedInputs};
 
 pub fn terminate_server_connection(
+    mut commands: Commands,
     mut client: ResMut<RenetClient>,
     mut target: ResMut<TargetServer>,
     mut unacknowledged_inputs: ResMut<UnacknowledgedInputs>,
     mut current_frame: ResMut<PlayerTickInputsBuffer>,
+    mut unsaved: ResMut<UnsavedProgress>,
 ) {
     info!("Terminating server connection");
     client.send_game_message(ClientToServerMessage::Exit);
+    // A grant still on its way would connect again
+    commands.remove_resource::<PendingConnectGrant>();
 
     target.address = None;
     target.username = None;
     target.session_token = None;
     target.state = TargetServerState::Initial;
+    target.is_solo = false;
+    target.local_server = None;
+    target.fallback_addresses.clear();
+    target.connection_error = None;
 
     unacknowledged_inputs.0.clear();
     current_frame.buffer.clear();
+    unsaved.0 = false;
 }
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
-use bincode::ErrorKind;
 use shared::{
+    codec::CodecError,
     game_message_to_payload, get_customized_server_to_client_channels,
     messages::{ClientToServerMessage, ServerToClientMessage},
     ChannelResolvableExt,
@@ -12,12 +12,12 @@ pub trait SendGameMessageExtension {
     fn receive_game_message_by_channel(
         &mut self,
         channel: u8,
-    ) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>>;
+    ) -> Option<Result<ServerToClientMessage, CodecError>>;
     fn receive_game_message_except_channel(
         &mut self,
         channel: u8,
-    ) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>>;
-    // fn receive_game_message(&mut self) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>>;
+    ) -> Option<Result<ServerToClientMessage, CodecError>>;
+    // fn receive_game_message(&mut self) -> Option<Result<ServerToClientMessage, CodecError>>;
 }
 
 impl SendGameMessageExtension for RenetClient {
@@ -30,7 +30,7 @@ impl SendGameMessageExtension for RenetClient {
     fn receive_game_message_by_channel(
         &mut self,
         channel: u8,
-    ) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>> {
+    ) -> Option<Result<ServerToClientMessage, CodecError>> {
         let payload = self.receive_message(channel);
         if let Some(payload) = payload {
             // debug!("Received payload: {:?}", payload);
@@ -52,7 +52,7 @@ impl SendGameMessageExtension for RenetClient {
     fn receive_game_message_except_channel(
         &mut self,
         excluded_channel_id: u8,
-    ) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>> {
+    ) -> Option<Result<ServerToClientMessage, CodecError>> {
         let channels = get_customized_server_to_client_channels();
         for channel in channels {
             if channel.channel_id == excluded_channel_id {
@@ -66,7 +66,7 @@ impl SendGameMessageExtension for RenetClient {
         None
     }
 
-    // fn receive_game_message(&mut self) -> Option<Result<ServerToClientMessage, Box<ErrorKind>>> {
+    // fn receive_game_message(&mut self) -> Option<Result<ServerToClientMessage, CodecError>> {
     //     let channels = get_customized_server_to_client_channels();
     //     for channel in channels {
     //         let res = self.receive_game_message_by_channel(channel.channel_id);
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, IoTaskPool, Task};
+use bevy_renet::netcode::NetcodeClientTransport;
+use shared::network::{
+    bind_client_socket, request_connect_grant, ActiveClientTransport, ConnectGrant, TlsIdentity,
+    TransportError,
+};
+use shared::GameFolderPaths;
+
+use super::CurrentPlayerProfile;
+
+/// Certificate the servers recognize the client by, along with its key
+const CLIENT_IDENTITY_FILE: &str = "client_identity.ron";
+/// Fingerprints of the certificates of the servers joined before, by address
+const KNOWN_SERVERS_FILE: &str = "known_servers.ron";
+
+/// Reads the certificate of the client, creating it the first time
+pub fn load_client_identity(paths: &GameFolderPaths) -> Result<TlsIdentity, String> {
+    let path = paths.game_folder_path.join(CLIENT_IDENTITY_FILE);
+    if let Ok(contents) = fs::read_to_string(&path) {
+        return ron::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e));
+    }
+
+    let identity = TlsIdentity::generate()?;
+    let contents = ron::to_string(&identity).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    Ok(identity)
+}
+
+fn load_known_servers(paths: &GameFolderPaths) -> HashMap<String, String> {
+    fs::read_to_string(paths.game_folder_path.join(KNOWN_SERVERS_FILE))
+        .ok()
+        .and_then(|contents| ron::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Remembers the certificate a server presented the first time, later connections fail if it changes
+fn pin_server(paths: &GameFolderPaths, address: SocketAddr, fingerprint: &str) {
+    let mut known = load_known_servers(paths);
+    known.insert(address.to_string(), fingerprint.to_string());
+    let written = ron::to_string(&known)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| {
+            fs::write(paths.game_folder_path.join(KNOWN_SERVERS_FILE), contents)
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = written {
+        warn!("Could not remember the certificate of {}: {}", address, e);
+    }
+}
+
+/// Connect grant being asked to a server, the transport is only created once it arrives
+#[derive(Resource)]
+pub struct PendingConnectGrant {
+    address: SocketAddr,
+    /// Whether the server had no pinned certificate yet
+    first_contact: bool,
+    task: Task<Result<(ConnectGrant, String), String>>,
+}
+
+impl PendingConnectGrant {
+    /// Asks `address` for a grant to play as `name`, in the background as it takes up to a few seconds
+    pub fn request(address: SocketAddr, name: String, paths: &GameFolderPaths) -> Self {
+        let pinned = load_known_servers(paths).remove(&address.to_string());
+        let first_contact = pinned.is_none();
+        let paths = paths.clone();
+        let task = IoTaskPool::get().spawn(async move {
+            let identity = load_client_identity(&paths)?;
+            request_connect_grant(address, &name, &identity, pinned.as_deref())
+        });
+        Self {
+            address,
+            first_contact,
+            task,
+        }
+    }
+}
+
+/// Connects to the server once it answered with a grant. A server which cannot be asked for one
+/// is not connected to, as an attacker could otherwise make the client connect unencrypted
+pub fn connect_grant_system(
+    mut commands: Commands,
+    mut pending: ResMut<PendingConnectGrant>,
+    mut profile: ResMut<CurrentPlayerProfile>,
+    paths: Res<GameFolderPaths>,
+    mut transport_errors: EventWriter<TransportError>,
+) {
+    let Some(result) = block_on(future::poll_once(&mut pending.task)) else {
+        return;
+    };
+    commands.remove_resource::<PendingConnectGrant>();
+    let address = pending.address;
+
+    let (grant, fingerprint) = match result {
+        Ok(answer) => answer,
+        Err(e) => {
+            transport_errors.write(TransportError(format!("no connect grant: {e}")));
+            return;
+        }
+    };
+    if pending.first_contact {
+        info!(
+            "First connection to {}, its certificate is {}",
+            address, fingerprint
+        );
+        pin_server(paths.as_ref(), address, &fingerprint);
+    }
+    if let ConnectGrant::Unsecure { .. } = grant {
+        warn!(
+            "{} does not encrypt connections, it has no private key",
+            address
+        );
+    }
+
+    // The server decides the id, bound to the certificate of the client
+    profile.id = grant.client_id();
+    let transport = bind_client_socket(address)
+        .map_err(|e| e.to_string())
+        .and_then(|socket| {
+            let current_time = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            NetcodeClientTransport::new(current_time, grant.into_authentication(address), socket)
+                .map_err(|e| e.to_string())
+        });
+    match transport {
+        Ok(transport) => {
+            info!("Attempting to connect to: {}", address);
+            commands.insert_resource(ActiveClientTransport(Box::new(transport)));
+        }
+        Err(e) => {
+            transport_errors.write(TransportError(e));
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
-use shared::messages::{ClientToServerMessage, PlayerFrameInput};
+use shared::messages::{ClientToServerMessage, NetworkAction, PlayerFrameInput};
 
 use super::buffered_client::PlayerTickInputsBuffer;
+use super::save::UnsavedProgress;
 use super::SendGameMessageExtension;
 
 // vector of time_ms values for inputs that have not been acknowledged by the server
@@ -13,6 +14,7 @@ pub fn upload_player_inputs_system(
     mut client: ResMut<RenetClient>,
     mut inputs: ResMut<PlayerTickInputsBuffer>,
     mut unacknowledged_inputs: ResMut<UnacknowledgedInputs>,
+    mut unsaved: ResMut<UnsavedProgress>,
 ) {
     if client.is_disconnected() {
         inputs.buffer.clear();
@@ -22,6 +24,11 @@ pub fn upload_player_inputs_system(
 
     let mut frames = vec![];
     for input in inputs.buffer.iter() {
+        if input.inputs.contains(&NetworkAction::LeftClick)
+            || input.inputs.contains(&NetworkAction::RightClick)
+        {
+            unsaved.0 = true;
+        }
         frames.push(input.clone());
         unacknowledged_inputs.0.push(input.clone());
     }
@@ -0,0 +1,86 @@
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use shared::network::{LanBeacon, LAN_DISCOVERY_PORT};
+
+/// A game stops being listed when its server has not announced itself for that long
+const LAN_GAME_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct LanGame {
+    pub address: SocketAddr,
+    pub beacon: LanBeacon,
+    last_seen: Instant,
+}
+
+/// Servers announcing themselves on the local network, listened to while the multiplayer menu is open
+#[derive(Resource, Default)]
+pub struct LanGames {
+    socket: Option<UdpSocket>,
+    pub games: Vec<LanGame>,
+}
+
+pub fn start_lan_discovery(mut commands: Commands) {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, LAN_DISCOVERY_PORT))
+        .and_then(|socket| socket.set_nonblocking(true).map(|_| socket));
+    let socket = match socket {
+        Ok(socket) => Some(socket),
+        Err(e) => {
+            // Likely another client running on this machine
+            warn!("Could not listen for LAN games: {}", e);
+            None
+        }
+    };
+    commands.insert_resource(LanGames {
+        socket,
+        games: Vec::new(),
+    });
+}
+
+pub fn stop_lan_discovery(mut commands: Commands) {
+    commands.remove_resource::<LanGames>();
+}
+
+/// Reads the beacons which arrived, and forgets the servers which went quiet.
+/// The resource is only marked changed when the list shown to the player changes
+pub fn lan_discovery_system(mut lan: ResMut<LanGames>) {
+    let lan_games = lan.bypass_change_detection();
+    let now = Instant::now();
+    let mut changed = false;
+
+    let mut buffer = [0u8; 1024];
+    let socket = lan_games.socket.as_ref();
+    while let Some(Ok((len, from))) = socket.map(|socket| socket.recv_from(&mut buffer)) {
+        let Some(beacon) = LanBeacon::from_bytes(&buffer[..len]) else {
+            continue;
+        };
+        let address = SocketAddr::new(from.ip(), beacon.port);
+        let games = &mut lan_games.games;
+        match games.iter_mut().find(|game| game.address == address) {
+            Some(game) => {
+                changed |= game.beacon != beacon;
+                game.beacon = beacon;
+                game.last_seen = now;
+            }
+            None => {
+                games.push(LanGame {
+                    address,
+                    beacon,
+                    last_seen: now,
+                });
+                changed = true;
+            }
+        }
+    }
+
+    let count = lan_games.games.len();
+    lan_games
+        .games
+        .retain(|game| now.duration_since(game.last_seen) < LAN_GAME_TIMEOUT);
+    changed |= lan_games.games.len() != count;
+
+    if changed {
+        lan.set_changed();
+    }
+}
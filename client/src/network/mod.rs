@@ -1,14 +1,21 @@
 pub mod buffered_client;
 mod chat;
+mod chunk_cache;
 mod cleanup;
 pub mod extensions;
+mod grants;
 mod inputs;
+pub mod lan;
+mod packs;
 pub mod save;
 mod setup;
 mod world;
 
 pub use chat::*;
+pub use chunk_cache::*;
 pub use cleanup::*;
 pub use extensions::SendGameMessageExtension;
+pub use grants::*;
 pub use inputs::*;
+pub use packs::*;
 pub use setup::*;
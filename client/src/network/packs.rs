@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::packs::{
+    is_valid_pack_hash, pack_hash, PackChunk, PackDownloadRequest, PackInfo, PackKind,
+    MAX_PACK_SIZE,
+};
+use shared::messages::ServerToClientMessage;
+use shared::world::{BlockId, ItemId};
+use shared::{GameFolderPaths, STC_PACK_CHANNEL};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::game::PreLoadingCompletion;
+use crate::world::{AtlasHandles, MaterialResource};
+
+use super::{SendGameMessageExtension, TargetServer};
+
+/// Folder of the game caching the packs downloaded from servers, extracted in a folder named after their hash.
+/// Downloads stopped midway are kept as `<hash>.part` and resumed on the next connection
+pub const SERVER_PACKS_DIR: &str = "server_packs/";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PackConsent {
+    #[default]
+    Pending,
+    Accepted,
+    Declined,
+}
+
+/// A pack being received from the server
+#[derive(Debug, Clone)]
+pub struct PackDownload {
+    pub hash: String,
+    pub size: u64,
+    pub received: u64,
+}
+
+/// Packs offered by the server being joined
+#[derive(Resource, Default)]
+pub struct ServerPacks {
+    pub offered: Vec<PackInfo>,
+    pub consent: PackConsent,
+    /// Packs asked for and not fully received yet
+    pub downloads: Vec<PackDownload>,
+    /// Resource packs whose textures are in the atlases, kept across connections like the atlases
+    applied: Vec<String>,
+}
+
+fn server_packs_dir(paths: &GameFolderPaths) -> PathBuf {
+    paths.game_folder_path.join(SERVER_PACKS_DIR)
+}
+
+fn partial_download_path(paths: &GameFolderPaths, hash: &str) -> PathBuf {
+    server_packs_dir(paths).join(format!("{hash}.part"))
+}
+
+impl ServerPacks {
+    /// Forgets the packs of the previous server, its textures stay until the next ones are applied
+    pub fn reset(&mut self) {
+        self.offered.clear();
+        self.consent = PackConsent::Pending;
+        self.downloads.clear();
+    }
+
+    /// Packs of the auth response, the player has nothing to accept when there are none.
+    /// Packs with a malformed hash are left out, as their files would be named after it
+    pub fn offer(&mut self, packs: Vec<PackInfo>) {
+        let packs: Vec<PackInfo> = packs
+            .into_iter()
+            .filter(|pack| {
+                let valid = is_valid_pack_hash(&pack.hash);
+                if !valid {
+                    error!(
+                        "The server offered pack {} with an invalid hash {:?}, it is ignored",
+                        pack.name, pack.hash
+                    );
+                }
+                valid
+            })
+            .collect();
+        self.consent = if packs.is_empty() {
+            PackConsent::Accepted
+        } else {
+            PackConsent::Pending
+        };
+        self.offered = packs;
+        self.downloads.clear();
+    }
+
+    pub fn any_required(&self) -> bool {
+        self.offered.iter().any(|pack| pack.required)
+    }
+
+    /// Only the resource packs are downloaded, the data packs are read by the server
+    fn resource_packs(&self) -> Vec<String> {
+        self.offered
+            .iter()
+            .filter(|pack| pack.kind == PackKind::Resource)
+            .map(|pack| pack.hash.clone())
+            .collect()
+    }
+
+    /// Starts downloading the resource packs missing from the cache,
+    /// returns the requests to send to the server
+    pub fn accept(&mut self, paths: &GameFolderPaths) -> Vec<PackDownloadRequest> {
+        self.consent = PackConsent::Accepted;
+        self.downloads = self
+            .offered
+            .iter()
+            .filter(|pack| pack.kind == PackKind::Resource && pack.size <= MAX_PACK_SIZE)
+            .filter(|pack| !server_packs_dir(paths).join(&pack.hash).is_dir())
+            .map(|pack| {
+                let partial = partial_download_path(paths, &pack.hash);
+                let received = fs::metadata(&partial)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+                // Longer than the pack, the partial file cannot be resumed
+                let received = if received > pack.size {
+                    let _ = fs::remove_file(&partial);
+                    0
+                } else {
+                    received
+                };
+                PackDownload {
+                    hash: pack.hash.clone(),
+                    size: pack.size,
+                    received,
+                }
+            })
+            .collect();
+        self.downloads
+            .iter()
+            .map(|download| PackDownloadRequest {
+                hash: download.hash.clone(),
+                offset: download.received,
+            })
+            .collect()
+    }
+
+    /// Bytes received and to receive, for the loading screen
+    pub fn download_progress(&self) -> (u64, u64) {
+        self.downloads
+            .iter()
+            .fold((0, 0), |(received, size), download| {
+                (received + download.received, size + download.size)
+            })
+    }
+
+    fn wanted_resource_packs(&self) -> Vec<String> {
+        if self.consent == PackConsent::Accepted {
+            self.resource_packs()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// The packs are accepted or declined, downloaded and their textures applied
+    pub fn is_ready(&self) -> bool {
+        self.consent != PackConsent::Pending
+            && self.downloads.is_empty()
+            && self.applied == self.wanted_resource_packs()
+    }
+}
+
+/// Checks a fully received pack against its hash and extracts it to the cache
+fn verify_and_extract(download: &PackDownload, paths: &GameFolderPaths) -> Result<(), String> {
+    let partial = partial_download_path(paths, &download.hash);
+    let data = fs::read(&partial).map_err(|e| e.to_string())?;
+    // Either way, a new download starts from scratch
+    let _ = fs::remove_file(&partial);
+
+    if data.len() as u64 != download.size {
+        return Err(format!(
+            "expected {} bytes, received {}",
+            download.size,
+            data.len()
+        ));
+    }
+    let hash = pack_hash(&data);
+    if hash != download.hash {
+        return Err(format!("content does not match its hash, got {hash}"));
+    }
+
+    // Extracted aside first, so that a folder named after a hash is always complete
+    let target_dir = server_packs_dir(paths).join(&download.hash);
+    let extracting_dir = server_packs_dir(paths).join(format!("{}.extracting", download.hash));
+    let _ = fs::remove_dir_all(&extracting_dir);
+    server::extract_pack_archive(&data, &extracting_dir)?;
+    fs::rename(&extracting_dir, &target_dir).map_err(|e| e.to_string())
+}
+
+/// Appends a chunk to its partial download, the pack is verified once complete
+fn receive_chunk(
+    packs: &mut ServerPacks,
+    chunk: PackChunk,
+    paths: &GameFolderPaths,
+) -> Result<(), String> {
+    let Some(index) = packs
+        .downloads
+        .iter()
+        .position(|download| download.hash == chunk.hash)
+    else {
+        warn!(
+            "Received a chunk of pack {} that was not asked for",
+            chunk.hash
+        );
+        return Ok(());
+    };
+    let download = &mut packs.downloads[index];
+    if chunk.offset != download.received
+        || download.received + chunk.data.len() as u64 > download.size
+    {
+        return Err(format!(
+            "unexpected chunk at {} of {} bytes",
+            chunk.offset,
+            chunk.data.len()
+        ));
+    }
+
+    fs::create_dir_all(server_packs_dir(paths)).map_err(|e| e.to_string())?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(partial_download_path(paths, &chunk.hash))
+        .and_then(|mut file| file.write_all(&chunk.data))
+        .map_err(|e| e.to_string())?;
+    download.received += chunk.data.len() as u64;
+
+    if download.received == download.size {
+        verify_and_extract(download, paths)?;
+        info!("Pack {} downloaded and verified", chunk.hash);
+        packs.downloads.remove(index);
+    }
+    Ok(())
+}
+
+/// Writes the chunks of packs sent by the server to the cache.
+/// A pack failing to download keeps the player from joining, it is downloaded again next time
+pub fn receive_pack_chunks_system(
+    mut client: ResMut<RenetClient>,
+    mut packs: ResMut<ServerPacks>,
+    mut target: ResMut<TargetServer>,
+    paths: Res<GameFolderPaths>,
+) {
+    while let Some(Ok(message)) = client.receive_game_message_by_channel(STC_PACK_CHANNEL) {
+        let ServerToClientMessage::PackChunk(chunk) = message else {
+            warn!("Unexpected message on the pack channel: {:?}", message);
+            continue;
+        };
+        let hash = chunk.hash.clone();
+        if let Err(err) = receive_chunk(&mut packs, chunk, &paths) {
+            error!("Could not download pack {}: {}", hash, err);
+            if is_valid_pack_hash(&hash) {
+                let _ = fs::remove_file(partial_download_path(&paths, &hash));
+            }
+            if target.connection_error.is_none() {
+                target.connection_error = Some(format!("Could not download a pack: {err}"));
+            }
+        }
+    }
+}
+
+/// Replaces the textures of the atlas by the ones of the same name in `dir`
+fn override_textures<T>(atlas: &mut AtlasHandles<T>, dir: &Path, asset_server: &AssetServer) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().is_none_or(|ext| ext != "png") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let handle = asset_server.load(path.to_string_lossy().into_owned());
+        match atlas
+            .handles
+            .iter_mut()
+            .find(|(_, texture)| *texture == name)
+        {
+            Some(entry) => entry.0 = handle,
+            None => atlas.handles.push((handle, name.to_owned())),
+        }
+    }
+}
+
+/// Rebuilds the atlases with the textures of the accepted resource packs,
+/// or with the ones of the game when the previous server had some
+pub fn apply_resource_packs_system(
+    mut packs: ResMut<ServerPacks>,
+    mut atlases: (ResMut<AtlasHandles<BlockId>>, ResMut<AtlasHandles<ItemId>>),
+    mut material_resource: ResMut<MaterialResource>,
+    mut loading: ResMut<PreLoadingCompletion>,
+    asset_server: Res<AssetServer>,
+    paths: Res<GameFolderPaths>,
+) {
+    if packs.consent == PackConsent::Pending || !packs.downloads.is_empty() {
+        return;
+    }
+    let wanted = packs.wanted_resource_packs();
+    if packs.applied == wanted {
+        return;
+    }
+
+    // The handles were set back to the textures of the game when connecting
+    for hash in wanted.iter() {
+        let pack_dir = server_packs_dir(&paths).join(hash);
+        override_textures(&mut atlases.0, &pack_dir.join("blocks/"), &asset_server);
+        override_textures(&mut atlases.1, &pack_dir.join("items/"), &asset_server);
+    }
+    material_resource.blocks = None;
+    material_resource.items = None;
+    loading.textures_loaded = false;
+
+    info!("Applied {} resource pack(s)", wanted.len());
+    packs.applied = wanted;
+}
+
+pub fn reset_server_packs(mut packs: ResMut<ServerPacks>) {
+    packs.reset();
+}
@@ -2,8 +2,16 @@ use crate::network::SendGameMessageExtension;
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 
+/// Whether the world may have changed since it was last saved, clicks breaking or placing blocks
+#[derive(Resource, Default, Debug)]
+pub struct UnsavedProgress(pub bool);
+
 // Send save request to server
-pub fn send_save_request_to_server(client: &mut ResMut<RenetClient>) {
+pub fn send_save_request_to_server(
+    client: &mut ResMut<RenetClient>,
+    unsaved: &mut ResMut<UnsavedProgress>,
+) {
+    unsaved.0 = false;
     client.send_game_message(shared::messages::ClientToServerMessage::SaveWorldRequest);
     debug!("Save request sent to server.");
 }
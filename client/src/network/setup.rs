@@ -1,31 +1,42 @@
 use bevy::prelude::*;
-use bevy_renet::netcode::{
-    ClientAuthentication, NetcodeClientPlugin, NetcodeClientTransport, NetcodeTransportError,
-};
 use bevy_renet::{renet::RenetClient, RenetClientPlugin};
 use rand::Rng;
-use shared::messages::mob::MobUpdateEvent;
+use shared::messages::mob::{MobDespawnEvent, MobHitEvent, MobUpdateEvent, TradeOffersEvent};
+use shared::messages::projectile::{ProjectileDespawnEvent, ProjectileSpawnEvent};
+use shared::network::{
+    ActiveClientTransport, ClientTransportPlugin, MemoryConnector, MemoryServerTransport,
+    TransportError,
+};
+use shared::players::Teams;
+use shared::world::{GameRules, Weather};
 use shared::{get_shared_renet_config, GameServerConfig, STC_AUTH_CHANNEL};
 
+use crate::camera::Spectate;
+use crate::game::PreLoadingCompletion;
 use crate::menus::solo::SelectedWorld;
+use crate::network::buffered_client::{SyncTime, SyncTimeExt};
+use crate::network::grants::PendingConnectGrant;
 use crate::network::world::update_world_from_network;
 use crate::network::CachedChatConversation;
-use crate::world::time::ClientTime;
+use crate::player::PendingBlockEdits;
+use crate::ui::hud::toasts::ToastEvent;
+use crate::world::time::{ClientTime, ServerTickRate};
 use crate::world::WorldRenderRequestUpdateEvent;
 use crate::PlayerNameSupplied;
 use shared::messages::{
-    AuthRegisterRequest, ItemStackUpdateEvent, PlayerId, PlayerSpawnEvent, PlayerUpdateEvent,
-    ServerToClientMessage,
+    AuthRegisterRequest, BlockBreakingProgress, EmoteEvent, ItemStackUpdateEvent, ItemUseEvent,
+    PlayerAfkEvent, PlayerDeathEvent, PlayerId, PlayerLeftEvent, PlayerSpawnEvent,
+    PlayerUpdateEvent, RecipeUnlockEvent, ServerToClientMessage,
 };
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::{net::UdpSocket, thread, time::SystemTime};
+use std::net::SocketAddr;
+use std::thread;
 
 use crate::world::ClientWorldMap;
 use shared::GameFolderPaths;
 
-use super::SendGameMessageExtension;
+use super::{ChunkCache, SendGameMessageExtension, ServerPacks};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TargetServerState {
@@ -77,6 +88,14 @@ pub struct TargetServer {
     pub username: Option<String>,
     pub session_token: Option<u64>,
     pub state: TargetServerState,
+    /// Set when connected to the server launched for a singleplayer world
+    pub is_solo: bool,
+    /// Reaches the singleplayer server, which runs in this process, without going through sockets
+    pub local_server: Option<MemoryConnector>,
+    /// Other addresses the host name of the server resolved to, tried in turn when `address` cannot be reached
+    pub fallback_addresses: Vec<SocketAddr>,
+    /// Why the server could not be reached, shown on the connecting screen
+    pub connection_error: Option<String>,
 }
 
 pub fn add_base_netcode(app: &mut App) {
@@ -86,7 +105,7 @@ pub fn add_base_netcode(app: &mut App) {
     app.insert_resource(client);
 
     // Setup the transport layer
-    app.add_plugins(NetcodeClientPlugin);
+    app.add_plugins(ClientTransportPlugin);
 
     // TODO: change username
     app.insert_resource(TargetServer {
@@ -94,6 +113,10 @@ pub fn add_base_netcode(app: &mut App) {
         username: None,
         session_token: None,
         state: TargetServerState::Initial,
+        is_solo: false,
+        local_server: None,
+        fallback_addresses: Vec::new(),
+        connection_error: None,
     });
 }
 
@@ -102,7 +125,7 @@ pub fn launch_local_server_system(
     selected_world: Res<SelectedWorld>,
     paths: Res<GameFolderPaths>,
 ) {
-    if target.address.is_some() {
+    if target.address.is_some() || target.local_server.is_some() {
         debug!("Skipping launch local server");
         return;
     }
@@ -110,26 +133,33 @@ pub fn launch_local_server_system(
     if let Some(world_name) = &selected_world.name {
         info!("Launching local server with world: {}", world_name);
 
-        let socket =
-            server::acquire_local_ephemeral_udp_socket(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
-        let addr = socket.local_addr().unwrap();
-        debug!("Obtained UDP socket: {}", addr);
+        let (transport, connector) = MemoryServerTransport::new();
 
         let world_name_clone = world_name.clone();
+        let world_seed = selected_world.seed;
+        let spawn_platform = selected_world.spawn_platform;
         let cloned_paths = paths.clone();
 
         thread::spawn(move || {
-            server::init(
-                socket,
+            let started = server::init(
+                server::ServerListener::Memory(transport),
                 GameServerConfig {
                     world_name: world_name_clone,
                     is_solo: true,
+                    world_seed,
+                    world_gen_settings: None,
+                    spawn_platform,
+                    pregen_radius: None,
                 },
                 cloned_paths,
             );
+            if let Err(e) = started {
+                error!("Could not start the local server: {}", e);
+            }
         });
 
-        target.address = Some(addr);
+        target.local_server = Some(connector);
+        target.is_solo = true;
     } else {
         error!("Error: No world selected. Unable to launch the server.");
     }
@@ -137,61 +167,117 @@ pub fn launch_local_server_system(
 
 pub fn poll_network_messages(
     mut client: ResMut<RenetClient>,
-    // mut chat_state: ResMut<CachedChatConversation>,
-    // client_time: ResMut<ClientTime>,
+    mut chat_state: ResMut<CachedChatConversation>,
     mut world: ResMut<ClientWorldMap>,
     mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
-    mut ev_player_spawn: EventWriter<PlayerSpawnEvent>,
+    mut ev_player_list: (
+        EventWriter<PlayerSpawnEvent>,
+        EventWriter<PlayerLeftEvent>,
+        EventWriter<PlayerAfkEvent>,
+    ),
     mut ev_mob_update: EventWriter<MobUpdateEvent>,
     mut ev_item_stacks_update: EventWriter<ItemStackUpdateEvent>,
     mut ev_player_update: EventWriter<PlayerUpdateEvent>,
+    mut world_state: (
+        ResMut<Teams>,
+        ResMut<GameRules>,
+        ResMut<ClientTime>,
+        ResMut<Weather>,
+        ResMut<Spectate>,
+        ResMut<ServerTickRate>,
+    ),
+    mut ev_animations: (EventWriter<EmoteEvent>, EventWriter<ItemUseEvent>),
+    mut ev_player_progress: (
+        EventWriter<PlayerDeathEvent>,
+        EventWriter<RecipeUnlockEvent>,
+    ),
+    mut ev_mob_events: (
+        EventWriter<MobDespawnEvent>,
+        EventWriter<TradeOffersEvent>,
+        EventWriter<MobHitEvent>,
+    ),
+    mut sync: (
+        ResMut<SyncTime>,
+        ResMut<PendingBlockEdits>,
+        ResMut<ChunkCache>,
+    ),
+    mut ev_projectiles: (
+        EventWriter<ProjectileSpawnEvent>,
+        EventWriter<ProjectileDespawnEvent>,
+    ),
+    mut ev_block_damage: EventWriter<BlockBreakingProgress>,
+    mut ev_toast: EventWriter<ToastEvent>,
 ) {
     // poll_reliable_ordered_messages(&mut client, &mut chat_state);
     update_world_from_network(
         &mut client,
+        &mut chat_state,
         &mut world,
         &mut ev_render,
-        &mut ev_player_spawn,
+        (
+            &mut ev_player_list.0,
+            &mut ev_player_list.1,
+            &mut ev_player_list.2,
+        ),
         &mut ev_mob_update,
         &mut ev_item_stacks_update,
         &mut ev_player_update,
+        (
+            &mut world_state.0,
+            &mut world_state.1,
+            &mut world_state.2,
+            &mut world_state.3,
+            &mut world_state.4,
+            &mut world_state.5,
+        ),
+        (&mut ev_animations.0, &mut ev_animations.1),
+        (&mut ev_player_progress.0, &mut ev_player_progress.1),
+        (
+            &mut ev_mob_events.0,
+            &mut ev_mob_events.1,
+            &mut ev_mob_events.2,
+        ),
+        (&mut sync.0, &mut sync.1, &mut sync.2),
+        (&mut ev_projectiles.0, &mut ev_projectiles.1),
+        &mut ev_block_damage,
+        &mut ev_toast,
     );
 }
 
 pub fn init_server_connection(
     mut commands: Commands,
     target: Res<TargetServer>,
-    current_player_id: Res<CurrentPlayerProfile>,
+    current_player: Res<CurrentPlayerProfile>,
+    paths: Res<GameFolderPaths>,
 ) {
-    let addr = target.address.unwrap();
-    let id = current_player_id.into_inner().id;
+    let target = target.clone();
+    let id = current_player.id;
+    let pending_grant = match target.local_server {
+        Some(_) => None,
+        None => Some(PendingConnectGrant::request(
+            target.address.unwrap(),
+            current_player.name.clone(),
+            &paths,
+        )),
+    };
     commands.queue(move |world: &mut World| {
         world.remove_resource::<RenetClient>();
-        world.remove_resource::<NetcodeClientTransport>();
+        world.remove_resource::<ActiveClientTransport>();
+        world.remove_resource::<PendingConnectGrant>();
         world.remove_resource::<CachedChatConversation>();
 
         let client = RenetClient::new(get_shared_renet_config());
         world.insert_resource(client);
 
-        let authentication = ClientAuthentication::Unsecure {
-            server_addr: addr,
-            client_id: id,
-            user_data: None,
-            protocol_id: shared::PROTOCOL_ID,
-        };
-
-        info!(
-            "Attempting to connect to: {} with data {:?}",
-            addr, authentication
-        );
-
-        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
-        let current_time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap();
-        let transport = NetcodeClientTransport::new(current_time, authentication, socket).unwrap();
-
-        world.insert_resource(transport);
+        match (target.local_server, pending_grant) {
+            (Some(local_server), _) => {
+                info!("Connecting to the local server");
+                world.insert_resource(ActiveClientTransport(Box::new(local_server.connect(id))));
+            }
+            // The transport is created by `connect_grant_system` once the server answered
+            (None, Some(pending_grant)) => world.insert_resource(pending_grant),
+            (None, None) => {}
+        }
 
         world.insert_resource(CachedChatConversation { ..default() });
 
@@ -199,9 +285,52 @@ pub fn init_server_connection(
     })
 }
 
-pub fn network_failure_handler(mut renet_error: EventReader<NetcodeTransportError>) {
-    for e in renet_error.read() {
-        error!("network error: {}", e);
+/// While connecting, moves on to the next address of the server when the current one cannot be reached.
+/// When none is left, the reason is shown on the connecting screen
+pub fn connection_fallback_system(
+    mut commands: Commands,
+    mut transport_errors: EventReader<TransportError>,
+    mut target: ResMut<TargetServer>,
+    current_player: Res<CurrentPlayerProfile>,
+    paths: Res<GameFolderPaths>,
+) {
+    let Some(error) = transport_errors.read().last() else {
+        return;
+    };
+    if target.local_server.is_some() || target.session_token.is_some() {
+        return;
+    }
+    let Some(failed) = target.address else {
+        return;
+    };
+
+    if target.fallback_addresses.is_empty() {
+        if target.connection_error.is_none() {
+            error!("Could not connect to {}: {}", failed, error.0);
+            target.connection_error = Some(format!("Could not connect to {}: {}", failed, error.0));
+        }
+        return;
+    }
+
+    let next = target.fallback_addresses.remove(0);
+    warn!(
+        "Could not connect to {}: {}, trying {}",
+        failed, error.0, next
+    );
+    target.address = Some(next);
+    target.state = TargetServerState::Initial;
+
+    let pending_grant = PendingConnectGrant::request(next, current_player.name.clone(), &paths);
+    commands.queue(move |world: &mut World| {
+        world.insert_resource(RenetClient::new(get_shared_renet_config()));
+        world.remove_resource::<ActiveClientTransport>();
+        world.insert_resource(pending_grant);
+    });
+}
+
+pub fn network_failure_handler(mut transport_errors: EventReader<TransportError>) {
+    for e in transport_errors.read() {
+        error!("network error: {}", e.0);
     }
 }
 
@@ -211,6 +340,11 @@ pub fn establish_authenticated_connection_to_server(
     current_profile: Res<CurrentPlayerProfile>,
     mut ev_spawn: EventWriter<PlayerSpawnEvent>,
     mut client_time: ResMut<ClientTime>,
+    mut loading: ResMut<PreLoadingCompletion>,
+    mut sync_time: ResMut<SyncTime>,
+    mut world_map: ResMut<ClientWorldMap>,
+    mut packs: ResMut<ServerPacks>,
+    mut chunk_cache: ResMut<ChunkCache>,
 ) {
     if target.session_token.is_some() {
         info!(
@@ -242,11 +376,21 @@ pub fn establish_authenticated_connection_to_server(
                 target.session_token = Some(message.session_token);
                 target.state = TargetServerState::ConnectionEstablished;
                 client_time.0 = message.tick;
-                // TODO: handle clock sync using the timestamp_ms field
-                // it will become very important if the lantency is high
+                loading.spawn_chunks = message.spawn_chunks;
+                world_map.height_limits = message.height_limits;
+                // Coarse until the first sync responses account for the latency
+                sync_time.reset_to_server_time(message.timestamp_ms);
                 for player in message.players {
                     ev_spawn.write(player);
                 }
+                packs.offer(message.packs);
+                match target.address {
+                    Some(address) if !target.is_solo => {
+                        chunk_cache.open_world(address, &message.world_id)
+                    }
+                    _ => chunk_cache.close_world(),
+                }
+                chunk_cache.send_cached_chunks(&mut client);
                 info!("Connected! {:?}", target);
             }
             _ => {
@@ -4,11 +4,23 @@ use crate::world::ClientChunk;
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 use shared::messages::{
-    mob::MobUpdateEvent, ItemStackUpdateEvent, PlayerSpawnEvent, PlayerUpdateEvent,
+    mob::{MobDespawnEvent, MobHitEvent, MobUpdateEvent, TradeOffersEvent},
+    projectile::{ProjectileDespawnEvent, ProjectileSpawnEvent},
+    BlockBreakingProgress, EmoteEvent, ItemStackUpdateEvent, ItemUseEvent, PlayerAfkEvent,
+    PlayerDeathEvent, PlayerLeftEvent, PlayerSpawnEvent, PlayerUpdateEvent, RecipeUnlockEvent,
     ServerToClientMessage,
 };
+use shared::players::Teams;
+use shared::world::{chunk_offset_to_global_pos, GameRules, Weather, SIX_OFFSETS};
 use shared::STC_AUTH_CHANNEL;
 
+use crate::camera::Spectate;
+use crate::network::buffered_client::{SyncTime, SyncTimeExt};
+use crate::network::{update_cached_chat_state, CachedChatConversation, ChunkCache};
+use crate::player::PendingBlockEdits;
+use crate::ui::hud::toasts::ToastEvent;
+use crate::world::meshing::border_changed;
+use crate::world::time::{ClientTime, ServerTickRate};
 use crate::world::ClientWorldMap;
 
 use crate::world::WorldRenderRequestUpdateEvent;
@@ -17,12 +29,46 @@ use super::SendGameMessageExtension;
 
 pub fn update_world_from_network(
     client: &mut ResMut<RenetClient>,
+    chat_state: &mut ResMut<CachedChatConversation>,
     world: &mut ResMut<ClientWorldMap>,
     ev_render: &mut EventWriter<WorldRenderRequestUpdateEvent>,
-    ev_player_spawn: &mut EventWriter<PlayerSpawnEvent>,
+    (ev_player_spawn, ev_player_left, ev_player_afk): (
+        &mut EventWriter<PlayerSpawnEvent>,
+        &mut EventWriter<PlayerLeftEvent>,
+        &mut EventWriter<PlayerAfkEvent>,
+    ),
     ev_mob_update: &mut EventWriter<MobUpdateEvent>,
     ev_item_stacks_update: &mut EventWriter<ItemStackUpdateEvent>,
     ev_player_update: &mut EventWriter<PlayerUpdateEvent>,
+    (teams, game_rules, client_time, weather, spectate, tick_rate): (
+        &mut ResMut<Teams>,
+        &mut ResMut<GameRules>,
+        &mut ResMut<ClientTime>,
+        &mut ResMut<Weather>,
+        &mut ResMut<Spectate>,
+        &mut ResMut<ServerTickRate>,
+    ),
+    (ev_emote, ev_item_use): (&mut EventWriter<EmoteEvent>, &mut EventWriter<ItemUseEvent>),
+    (ev_player_death, ev_recipe_unlock): (
+        &mut EventWriter<PlayerDeathEvent>,
+        &mut EventWriter<RecipeUnlockEvent>,
+    ),
+    (ev_mob_despawn, ev_trade_offers, ev_mob_hit): (
+        &mut EventWriter<MobDespawnEvent>,
+        &mut EventWriter<TradeOffersEvent>,
+        &mut EventWriter<MobHitEvent>,
+    ),
+    (sync_time, pending_edits, chunk_cache): (
+        &mut ResMut<SyncTime>,
+        &mut ResMut<PendingBlockEdits>,
+        &mut ResMut<ChunkCache>,
+    ),
+    ev_projectiles: (
+        &mut EventWriter<ProjectileSpawnEvent>,
+        &mut EventWriter<ProjectileDespawnEvent>,
+    ),
+    ev_block_damage: &mut EventWriter<BlockBreakingProgress>,
+    ev_toast: &mut EventWriter<ToastEvent>,
 ) {
     while let Some(Ok(msg)) = client.receive_game_message_except_channel(STC_AUTH_CHANNEL) {
         // truncate the message to 1000 characters
@@ -36,19 +82,39 @@ pub fn update_world_from_network(
                 );
 
                 for (pos, chunk) in world_update.new_map {
+                    chunk_cache.store(pos, &chunk);
+
+                    // Blocks damaged before the chunk arrived get their cracks too
+                    ev_block_damage.write_batch(
+                        chunk
+                            .map
+                            .iter()
+                            .filter(|(_, block)| block.breaking_progress > 0)
+                            .map(|(local, block)| BlockBreakingProgress {
+                                position: chunk_offset_to_global_pos(&pos, local),
+                                progress: block.breaking_progress,
+                            }),
+                    );
+
+                    let previous = world.map.get(&pos);
                     let chunk = ClientChunk {
                         map: chunk.map,
-                        entity: {
-                            if let Some(c) = world.map.get(&pos) {
-                                c.entity
-                            } else {
-                                None
-                            }
-                        },
+                        entity: previous.and_then(|c| c.entity),
+                        mesh: previous.and_then(|c| c.mesh.clone()),
                         last_mesh_ts: Instant::now(),
+                        biomes: chunk.biomes,
                     };
 
-                    world.map.insert(pos, chunk.clone());
+                    // Neighbours only need to be meshed again when the blocks they touch changed
+                    for offset in &SIX_OFFSETS {
+                        if border_changed(previous, &chunk, *offset) {
+                            ev_render
+                                .write(WorldRenderRequestUpdateEvent::ChunkToReload(pos + *offset));
+                        }
+                    }
+
+                    world.insert_chunk(pos, chunk);
+                    pending_edits.reapply(world, pos);
                     ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(pos));
                 }
 
@@ -62,10 +128,17 @@ pub fn update_world_from_network(
                 // get current time
                 // client_time.0 = world_update.time;
             }
-            ServerToClientMessage::PlayerSpawn(spawn_event) => {
-                info!("Received SINGLE spawn event {:?}", spawn_event);
+            ServerToClientMessage::PlayerJoined(spawn_event) => {
+                info!("Player {} joined", spawn_event.name);
                 ev_player_spawn.write(spawn_event);
             }
+            ServerToClientMessage::PlayerLeft(left_event) => {
+                info!("Player {} left", left_event.name);
+                ev_player_left.write(left_event);
+            }
+            ServerToClientMessage::PlayerAfk(afk_event) => {
+                ev_player_afk.write(afk_event);
+            }
             ServerToClientMessage::MobUpdate(update_event) => {
                 // info!("Received mob update event {:?}", update_event);
                 ev_mob_update.write(update_event);
@@ -73,8 +146,68 @@ pub fn update_world_from_network(
             ServerToClientMessage::PlayerUpdate(update) => {
                 ev_player_update.write(update);
             }
-            ServerToClientMessage::AuthRegisterResponse(_) => {}
-            ServerToClientMessage::ChatConversation(_) => {}
+            ServerToClientMessage::AuthRegisterResponse(_)
+            | ServerToClientMessage::PackChunk(_) => {}
+            ServerToClientMessage::ChatConversation(conversation) => {
+                update_cached_chat_state(chat_state, conversation);
+            }
+            ServerToClientMessage::TeamsUpdate(new_teams) => {
+                **teams = new_teams;
+            }
+            ServerToClientMessage::BlockEditResult(result) => {
+                pending_edits.resolve(&result, world, ev_render);
+            }
+            ServerToClientMessage::BlockBreakingProgress(damage) => {
+                ev_block_damage.write(damage);
+            }
+            ServerToClientMessage::GameRules(update) => {
+                **game_rules = update.rules;
+                client_time.0 = update.day_time;
+            }
+            ServerToClientMessage::Weather(new_weather) => {
+                **weather = new_weather;
+            }
+            ServerToClientMessage::TickRate(update) => {
+                info!("Server simulation speed: {:?}", update);
+                tick_rate.0 = update;
+            }
+            ServerToClientMessage::Emote(emote_event) => {
+                ev_emote.write(emote_event);
+            }
+            ServerToClientMessage::ItemUse(item_use_event) => {
+                ev_item_use.write(item_use_event);
+            }
+            ServerToClientMessage::PlayerDeath(death_event) => {
+                ev_player_death.write(death_event);
+            }
+            ServerToClientMessage::RecipeUnlock(unlock_event) => {
+                ev_recipe_unlock.write(unlock_event);
+            }
+            ServerToClientMessage::MobDespawn(despawn_event) => {
+                ev_mob_despawn.write(despawn_event);
+            }
+            ServerToClientMessage::MobHit(hit_event) => {
+                ev_mob_hit.write(hit_event);
+            }
+            ServerToClientMessage::TradeOffers(offers_event) => {
+                ev_trade_offers.write(offers_event);
+            }
+            ServerToClientMessage::GameMode(game_mode) => {
+                info!("Game mode set to {:?}", game_mode);
+                spectate.set_game_mode(game_mode);
+            }
+            ServerToClientMessage::Hint(hint) => {
+                ev_toast.write(ToastEvent(hint.text().to_string()));
+            }
+            ServerToClientMessage::TimeSync(response) => {
+                sync_time.add_sync_sample(&response);
+            }
+            ServerToClientMessage::ProjectileSpawn(spawn_event) => {
+                ev_projectiles.0.write(spawn_event);
+            }
+            ServerToClientMessage::ProjectileDespawn(despawn_event) => {
+                ev_projectiles.1.write(despawn_event);
+            }
         }
     }
 }
@@ -1,8 +1,11 @@
+use crate::camera::CameraController;
 use crate::input::data::GameAction;
 use crate::input::keyboard::*;
+use crate::mob::MobRoot;
 use crate::network::buffered_client::{
     CurrentFrameInputs, CurrentFrameInputsExt, PlayerTickInputsBuffer, SyncTime, SyncTimeExt,
 };
+use crate::ui::accessibility::AccessibilitySettings;
 use crate::ui::hud::debug::DebugOptions;
 use crate::ui::hud::hotbar::Hotbar;
 use crate::ui::hud::UIMode;
@@ -10,13 +13,14 @@ use crate::world::{ClientWorldMap, WorldRenderRequestUpdateEvent};
 use crate::KeyMap;
 use bevy::prelude::*;
 use shared::messages::NetworkAction;
+use shared::players::collision::{apply_player_pushback, entity_pushback, player_half_size};
 use shared::players::movement::simulate_player_movement;
 use shared::players::{Player, ViewMode};
 
 use super::CurrentPlayerMarker;
 
 pub fn update_frame_inputs_system(
-    camera: Query<&Transform, With<Camera>>,
+    camera: Query<(&Transform, &CameraController), With<Camera>>,
     hotbar: Query<&Hotbar>,
     mut frame_inputs: ResMut<CurrentFrameInputs>,
     view_mode: Res<ViewMode>,
@@ -25,8 +29,11 @@ pub fn update_frame_inputs_system(
         return;
     }
 
-    let camera = camera.single().unwrap();
-    frame_inputs.0.camera = *camera;
+    let (camera, controller) = camera.single().unwrap();
+    // In freecam the player keeps looking where it was when the camera got detached
+    frame_inputs.0.camera = controller
+        .detached_view
+        .map_or(*camera, |view| view.transform);
     frame_inputs.0.hotbar_slot = hotbar.single().unwrap().selected;
     frame_inputs.0.view_mode = *view_mode;
 }
@@ -55,11 +62,18 @@ pub fn player_movement_system(
         Res<UIMode>,
         Res<KeyMap>,
         ResMut<CurrentFrameInputs>,
+        Res<DebugOptions>,
+        Res<AccessibilitySettings>,
     ),
     world_map: Res<ClientWorldMap>,
+    others: (
+        Query<&Player, Without<CurrentPlayerMarker>>,
+        Query<(&MobRoot, &Transform), Without<CurrentPlayerMarker>>,
+    ),
 ) {
     let mut player_query = queries;
-    let (keyboard_input, ui_mode, key_map, mut frame_inputs) = resources;
+    let (keyboard_input, ui_mode, key_map, mut frame_inputs, debug_options, accessibility) =
+        resources;
 
     if frame_inputs.0.delta_ms == 0 {
         return;
@@ -80,27 +94,59 @@ pub fn player_movement_system(
         frame_inputs.0.inputs.insert(NetworkAction::ToggleFlyMode);
     }
 
-    if is_action_pressed(GameAction::MoveBackward, &keyboard_input, &key_map) {
-        frame_inputs.0.inputs.insert(NetworkAction::MoveBackward);
-    }
-    if is_action_pressed(GameAction::MoveForward, &keyboard_input, &key_map) {
-        frame_inputs.0.inputs.insert(NetworkAction::MoveForward);
-    }
-    if is_action_pressed(GameAction::MoveLeft, &keyboard_input, &key_map) {
-        frame_inputs.0.inputs.insert(NetworkAction::MoveLeft);
-    }
-    if is_action_pressed(GameAction::MoveRight, &keyboard_input, &key_map) {
-        frame_inputs.0.inputs.insert(NetworkAction::MoveRight);
-    }
-    if is_action_pressed(GameAction::Jump, &keyboard_input, &key_map) {
-        frame_inputs.0.inputs.insert(NetworkAction::JumpOrFlyUp);
-    }
-    if is_action_pressed(GameAction::FlyDown, &keyboard_input, &key_map) {
-        frame_inputs.0.inputs.insert(NetworkAction::SneakOrFlyDown);
+    // The movement keys fly the camera in freecam, the player stays in place
+    if !debug_options.is_freecam_enabled() {
+        if is_action_pressed(GameAction::MoveBackward, &keyboard_input, &key_map) {
+            frame_inputs.0.inputs.insert(NetworkAction::MoveBackward);
+        }
+        if is_action_pressed(GameAction::MoveForward, &keyboard_input, &key_map) {
+            frame_inputs.0.inputs.insert(NetworkAction::MoveForward);
+        }
+        if is_action_pressed(GameAction::MoveLeft, &keyboard_input, &key_map) {
+            frame_inputs.0.inputs.insert(NetworkAction::MoveLeft);
+        }
+        if is_action_pressed(GameAction::MoveRight, &keyboard_input, &key_map) {
+            frame_inputs.0.inputs.insert(NetworkAction::MoveRight);
+        }
+        if is_action_pressed(GameAction::Jump, &keyboard_input, &key_map) {
+            frame_inputs.0.inputs.insert(NetworkAction::JumpOrFlyUp);
+        }
+        if is_action_pressed(GameAction::FlyDown, &keyboard_input, &key_map) {
+            frame_inputs.0.inputs.insert(NetworkAction::SneakOrFlyDown);
+        }
+        if accessibility.auto_jump {
+            frame_inputs.0.inputs.insert(NetworkAction::AutoJump);
+        }
     }
 
     simulate_player_movement(&mut player, world_map.as_ref(), &frame_inputs.0);
 
+    // Predicts the pushback the server applies when overlapping other entities
+    let (other_players, mobs) = others;
+    let delta_t = frame_inputs.0.delta_ms as f32 / 1000.0;
+    let half_size = player_half_size(&player);
+    let others = other_players
+        .iter()
+        .map(|other| (other.position, player_half_size(other)))
+        .chain(mobs.iter().map(|(mob, transform)| {
+            let (width, height, depth) = mob.kind.get_size();
+            (transform.translation, Vec3::new(width, height, depth) / 2.0)
+        }));
+    let push: Vec3 = others
+        .map(|(position, other_half_size)| {
+            entity_pushback(
+                player.position,
+                half_size,
+                position,
+                other_half_size,
+                delta_t,
+            ) / 2.0
+        })
+        .sum();
+    if push != Vec3::ZERO {
+        apply_player_pushback(&mut player, push, world_map.as_ref());
+    }
+
     frame_inputs.0.position = player.position;
 
     player_transform.translation = player.position;
@@ -173,6 +219,19 @@ pub fn toggle_raycast_debug_mode_system(
     }
 }
 
+pub fn toggle_freecam_system(
+    mut debug_options: ResMut<DebugOptions>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+) {
+    if *ui_mode == UIMode::Closed
+        && is_action_just_pressed(GameAction::ToggleFreecam, &keyboard_input, &key_map)
+    {
+        debug_options.toggle_freecam();
+    }
+}
+
 pub fn chunk_force_reload_system(
     mut world_map: ResMut<ClientWorldMap>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
@@ -186,6 +245,7 @@ pub fn chunk_force_reload_system(
             if let Some(e) = chunk.entity {
                 commands.entity(e).despawn();
                 chunk.entity = None;
+                chunk.mesh = None;
             }
             // Request a render for this chunk
             ev_writer.write(WorldRenderRequestUpdateEvent::ChunkToReload(*pos));
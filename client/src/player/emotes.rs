@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use shared::{
+    messages::{Emote, EmoteEvent},
+    players::Player,
+};
+
+/// Emote currently played by a player model
+#[derive(Component, Debug)]
+pub struct EmoteAnimation {
+    pub emote: Emote,
+    pub elapsed: f32,
+}
+
+const SIT_SCALE: f32 = 0.6;
+const WAVE_SPEED: f32 = 8.0;
+const WAVE_AMPLITUDE: f32 = 0.3;
+const POINT_LEAN: f32 = 0.35;
+
+pub fn apply_emote_events_system(
+    mut commands: Commands,
+    mut events: EventReader<EmoteEvent>,
+    mut players: Query<(Entity, &Player, &mut Transform)>,
+) {
+    for event in events.read() {
+        let Some((entity, player, mut transform)) = players
+            .iter_mut()
+            .find(|(_, player, _)| player.id == event.player_id)
+        else {
+            continue;
+        };
+
+        match event.emote {
+            Some(emote) => {
+                commands.entity(entity).insert(EmoteAnimation {
+                    emote,
+                    elapsed: 0.0,
+                });
+            }
+            None => {
+                reset_player_model(&mut transform, player);
+                commands.entity(entity).remove::<EmoteAnimation>();
+            }
+        }
+    }
+}
+
+/// Poses the player models on top of the position set by the movement systems
+pub fn animate_emotes_system(
+    mut commands: Commands,
+    mut players: Query<(Entity, &Player, &mut Transform, &mut EmoteAnimation)>,
+    time: Res<Time>,
+) {
+    for (entity, player, mut transform, mut animation) in players.iter_mut() {
+        animation.elapsed += time.delta_secs();
+
+        if animation
+            .emote
+            .duration_secs()
+            .is_some_and(|duration| animation.elapsed >= duration)
+        {
+            reset_player_model(&mut transform, player);
+            commands.entity(entity).remove::<EmoteAnimation>();
+            continue;
+        }
+
+        transform.translation = player.position;
+        match animation.emote {
+            Emote::Wave => {
+                let angle = (animation.elapsed * WAVE_SPEED).sin() * WAVE_AMPLITUDE;
+                transform.rotation = Quat::from_rotation_z(angle);
+            }
+            Emote::Point => {
+                let facing = player.camera_transform.rotation.to_euler(EulerRot::YXZ).0;
+                let lean = POINT_LEAN * (animation.elapsed * 4.0).min(1.0);
+                transform.rotation = Quat::from_rotation_y(facing) * Quat::from_rotation_x(-lean);
+            }
+            Emote::Sit => {
+                transform.scale = Vec3::new(1.0, SIT_SCALE, 1.0);
+                transform.translation.y -= player.height * (1.0 - SIT_SCALE) / 2.0;
+            }
+        }
+    }
+}
+
+fn reset_player_model(transform: &mut Transform, player: &Player) {
+    transform.translation = player.position;
+    transform.rotation = Quat::IDENTITY;
+    transform.scale = Vec3::ONE;
+}
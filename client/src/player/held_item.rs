@@ -0,0 +1,90 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+use shared::{players::Player, world::ItemId};
+
+use crate::{
+    entities::stack::ItemStackMeshes,
+    world::{GlobalMaterial, MaterialResource},
+    GameState,
+};
+
+use super::CurrentPlayerMarker;
+
+/// Item in the hand of another player, following them at their side
+#[derive(Component, Debug)]
+pub struct HeldItemModel {
+    pub player: Entity,
+    pub item: ItemId,
+    /// Seconds since the player swapped to this item
+    pub elapsed: f32,
+}
+
+/// Position of the hand from the center of a player facing -Z
+const HAND_OFFSET: Vec3 = Vec3::new(0.5, 0.1, -0.3);
+/// Time taken by a new item to rise into the hand
+const SWAP_ANIMATION_SECS: f32 = 0.2;
+/// How far below the hand a new item starts rising from
+const SWAP_ANIMATION_DROP: f32 = 0.4;
+
+/// Shows the item the other players hold, swapping it with a short animation when they change it
+pub fn held_items_system(
+    mut commands: Commands,
+    players: Query<(Entity, &Player, &Transform), Without<CurrentPlayerMarker>>,
+    mut models: Query<(Entity, &mut HeldItemModel, &mut Transform), Without<Player>>,
+    (mut meshes, mut stack_meshes, material_resource): (
+        ResMut<Assets<Mesh>>,
+        ResMut<ItemStackMeshes>,
+        Res<MaterialResource>,
+    ),
+    time: Res<Time>,
+) {
+    for (entity, mut model, mut transform) in models.iter_mut() {
+        let Ok((_, player, player_transform)) = players.get(model.player) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let held = player.inventory.get(player.hotbar_slot);
+        if held.is_none_or(|stack| stack.item_id != model.item) {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        model.elapsed += time.delta_secs();
+        let rise = (model.elapsed / SWAP_ANIMATION_SECS).min(1.0);
+        let facing =
+            Quat::from_rotation_y(player.camera_transform.rotation.to_euler(EulerRot::YXZ).0);
+        transform.translation = player_transform.translation
+            + facing * (HAND_OFFSET - Vec3::Y * SWAP_ANIMATION_DROP * (1.0 - rise));
+        transform.rotation = facing;
+    }
+
+    let Some(material) = material_resource
+        .global_materials
+        .get(&GlobalMaterial::Items)
+        .cloned()
+    else {
+        return;
+    };
+    for (entity, player, player_transform) in players.iter() {
+        let Some(stack) = player.inventory.get(player.hotbar_slot) else {
+            continue;
+        };
+        let shown = models
+            .iter()
+            .any(|(_, model, _)| model.player == entity && model.item == stack.item_id);
+        if shown {
+            continue;
+        }
+        commands.spawn((
+            HeldItemModel {
+                player: entity,
+                item: stack.item_id,
+                elapsed: 0.0,
+            },
+            Mesh3d(stack_meshes.get_or_build(stack, &mut meshes, &material_resource)),
+            MeshMaterial3d(material.clone()),
+            NotShadowCaster,
+            Transform::from_translation(player_transform.translation),
+            StateScoped(GameState::Game),
+        ));
+    }
+}
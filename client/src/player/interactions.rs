@@ -1,15 +1,27 @@
+use crate::camera::Spectate;
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::input::MouseCapture;
 use crate::mob::{MobMarker, TargetedMob, TargetedMobData};
 use crate::network::buffered_client::CurrentFrameInputs;
-use crate::ui::hud::UIMode;
-use crate::world::ClientWorldMap;
-use bevy::color::palettes::css::{GREEN, WHITE};
+use crate::network::SendGameMessageExtension;
+use crate::ui::hud::debug::DebugOptions;
+use crate::ui::hud::hotbar::Hotbar;
+use crate::world::{ClientWorldMap, TargetedBlockOutline, WorldRenderRequestUpdateEvent};
+use crate::KeyMap;
+use bevy::color::palettes::css::GREEN;
 use bevy::prelude::*;
-use shared::messages::NetworkAction;
-use shared::players::blocks::{simulate_player_block_interactions, CallerType};
-use shared::players::{Player, ViewMode};
-use shared::world::raycast;
-
-use super::CurrentPlayerMarker;
+use bevy_renet::renet::RenetClient;
+use shared::messages::{
+    mob::MobInteractRequest, BlockBreakingProgress, ClientToServerMessage, NetworkAction,
+    PlayerFrameInput,
+};
+use shared::players::blocks::{simulate_player_block_interactions, BlockChange, CallerType};
+use shared::players::item_use::simulate_item_use;
+use shared::players::{GameMode, Player, ViewMode, HOTBAR_SLOTS};
+use shared::world::{raycast, BlockData, BlockDirection, BlockId, ItemId, WorldMap};
+
+use super::{request_block_remesh, CurrentPlayerMarker, PendingBlockEdits};
 
 // Function to handle block placement and breaking
 pub fn handle_block_interactions(
@@ -18,25 +30,45 @@ pub fn handle_block_interactions(
         Query<&mut Transform, With<CurrentPlayerMarker>>,
         Query<&Transform, (With<Camera>, Without<CurrentPlayerMarker>)>,
         Query<&MobMarker>,
+        Query<&mut Hotbar>,
     ),
     resources: (
         ResMut<ClientWorldMap>,
         Res<ButtonInput<MouseButton>>,
-        Res<UIMode>,
+        Res<MouseCapture>,
         Res<ViewMode>,
         ResMut<TargetedMob>,
         ResMut<CurrentFrameInputs>,
+        Res<DebugOptions>,
     ),
     mut ray_cast: MeshRayCast,
-    mut gizmos: Gizmos,
+    mut outline: ResMut<TargetedBlockOutline>,
+    mut client: ResMut<RenetClient>,
+    mut pending_edits: ResMut<PendingBlockEdits>,
+    mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
+    mut ev_block_damage: EventWriter<BlockBreakingProgress>,
+    spectate: Res<Spectate>,
+    (keyboard_input, key_map): (Res<ButtonInput<KeyCode>>, Res<KeyMap>),
 ) {
-    let (mut player_query, p_transform, camera_query, mob_query) = queries;
-    let (world_map, mouse_input, ui_mode, view_mode, mut targeted_mob, mut frame_inputs) =
-        resources;
+    let (mut player_query, p_transform, camera_query, mob_query, mut hotbar) = queries;
+    let (
+        world_map,
+        mouse_input,
+        mouse_capture,
+        view_mode,
+        mut targeted_mob,
+        mut frame_inputs,
+        debug_options,
+    ) = resources;
 
     let mut player = player_query.single_mut().unwrap();
+    outline.0 = None;
 
-    if *ui_mode == UIMode::Opened {
+    // The detached camera of the freecam does not aim for the player, and spectators only watch
+    if !mouse_capture.accepts_clicks()
+        || debug_options.is_freecam_enabled()
+        || spectate.is_spectator()
+    {
         return;
     }
 
@@ -49,6 +81,7 @@ pub fn handle_block_interactions(
     let world_map = world_map.into_inner();
 
     let maybe_block = raycast::raycast(world_map, camera_transform, player_translation, *view_mode);
+    outline.0 = maybe_block.as_ref().map(|res| res.bbox);
 
     bounce_ray(ray, &mut ray_cast);
 
@@ -71,39 +104,114 @@ pub fn handle_block_interactions(
     }
 
     if mouse_input.just_pressed(MouseButton::Left) && targeted_mob.target.is_some() {
-        // TODO: Attack the targeted
-
-        targeted_mob.target = None;
+        if let Some(target) = targeted_mob.target.take() {
+            client.send_game_message(ClientToServerMessage::AttackMob(target.id));
+        }
 
         return;
     }
 
-    if let Some(res) = maybe_block {
-        // Draw gizmos for the bounding box
-        let center = (res.bbox.max + res.bbox.min) / 2.0;
-        let hsize = res.bbox.max - res.bbox.min;
-        gizmos.cuboid(
-            Transform::from_translation(center.into()).with_scale(hsize.into()),
-            WHITE,
+    if mouse_input.just_pressed(MouseButton::Right) {
+        if let Some(target) = &targeted_mob.target {
+            client.send_game_message(ClientToServerMessage::InteractMob(MobInteractRequest {
+                mob_id: target.id,
+                hotbar_slot: hotbar.single().map_or(0, |hotbar| hotbar.selected),
+            }));
+
+            return;
+        }
+    }
+
+    let picking = mouse_input.just_pressed(MouseButton::Middle)
+        || is_action_just_pressed(GameAction::PickBlock, &keyboard_input, &key_map);
+    if let (true, Some(res), Ok(mut hotbar)) = (picking, &maybe_block, hotbar.single_mut()) {
+        let creative = spectate.game_mode == GameMode::Creative;
+        pick_block(
+            &player,
+            &mut hotbar,
+            &mut frame_inputs.0,
+            res.block.id,
+            creative,
         );
+    }
+
+    // Right-click places blocks, and uses the held item when it is not one
+    if mouse_input.pressed(MouseButton::Right) {
+        frame_inputs.0.inputs.insert(NetworkAction::RightClick);
+    }
+    let now_ms = frame_inputs.0.time_ms;
+    simulate_item_use(&mut player, &frame_inputs.0, now_ms);
 
+    if let Some(res) = maybe_block {
         // Handle left-click for breaking blocks
         if mouse_input.pressed(MouseButton::Left) {
             frame_inputs.0.inputs.insert(NetworkAction::LeftClick);
         }
 
-        // Handle right-click for placing blocks
-        if mouse_input.pressed(MouseButton::Right) {
-            frame_inputs.0.inputs.insert(NetworkAction::RightClick);
-        }
-
-        simulate_player_block_interactions(
+        let targeted = world_map.get_block_by_coordinates(&res.position).copied();
+        let change = simulate_player_block_interactions(
             &mut player,
             world_map,
             &frame_inputs.0,
             CallerType::Client,
         );
+
+        // Shown right away, the server tells later whether it agrees
+        let (position, previous, predicted) = match change {
+            None => return,
+            // Cracks are not edits, the server sends their progress to every player around
+            Some(BlockChange::Damaged { position, progress }) => {
+                ev_block_damage.write(BlockBreakingProgress { position, progress });
+                return;
+            }
+            Some(BlockChange::Broken(broken)) => {
+                let previous = targeted
+                    .filter(|_| res.position == broken.position)
+                    .unwrap_or_else(|| BlockData::new(broken.id, BlockDirection::Front));
+                (broken.position, Some(previous), None)
+            }
+            Some(BlockChange::Placed { position, .. }) => (
+                position,
+                None,
+                world_map.get_block_by_coordinates(&position).copied(),
+            ),
+        };
+        pending_edits.record(frame_inputs.0.time_ms, position, previous, predicted);
+        frame_inputs.0.predicted_edit = Some(position);
+        request_block_remesh(&mut ev_render, position);
+    }
+}
+
+/// Selects the slot of the hotbar holding the block. In creative, a block missing from the hotbar
+/// is asked for in the first empty slot, or in the selected one when they are all taken
+fn pick_block(
+    player: &Player,
+    hotbar: &mut Hotbar,
+    inputs: &mut PlayerFrameInput,
+    block: BlockId,
+    creative: bool,
+) {
+    let Some(item) = ItemId::from_block(block) else {
+        return;
+    };
+    let held_in = (0..HOTBAR_SLOTS).find(|slot| {
+        player
+            .inventory
+            .get(*slot)
+            .is_some_and(|stack| stack.item_id == item)
+    });
+    match held_in {
+        Some(slot) => hotbar.select(slot as i32),
+        None if creative => {
+            let slot = (0..HOTBAR_SLOTS)
+                .find(|slot| player.inventory.get(*slot).is_none())
+                .unwrap_or(hotbar.selected);
+            hotbar.select(slot as i32);
+            inputs.inputs.insert(NetworkAction::PickBlock);
+        }
+        None => return,
     }
+    inputs.hotbar_slot = hotbar.selected;
 }
 
 const MAX_BOUNCES: usize = 1;
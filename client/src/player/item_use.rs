@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use shared::{
+    messages::ItemUseEvent,
+    players::Player,
+    world::{ItemId, ItemUseKind},
+};
+
+/// Item another player is charging or consuming
+#[derive(Component, Debug)]
+pub struct ItemUseAnimation {
+    pub item: ItemId,
+    pub elapsed: f32,
+}
+
+const EAT_SPEED: f32 = 14.0;
+const EAT_AMPLITUDE: f32 = 0.12;
+const DRAW_LEAN: f32 = 0.25;
+
+pub fn apply_item_use_events_system(
+    mut commands: Commands,
+    mut events: EventReader<ItemUseEvent>,
+    mut players: Query<(Entity, &Player, &mut Transform)>,
+) {
+    for event in events.read() {
+        let Some((entity, player, mut transform)) = players
+            .iter_mut()
+            .find(|(_, player, _)| player.id == event.player_id)
+        else {
+            continue;
+        };
+
+        match event.item {
+            Some(item) => {
+                commands
+                    .entity(entity)
+                    .insert(ItemUseAnimation { item, elapsed: 0.0 });
+            }
+            None => {
+                transform.translation = player.position;
+                transform.rotation = Quat::IDENTITY;
+                commands.entity(entity).remove::<ItemUseAnimation>();
+            }
+        }
+    }
+}
+
+/// Poses the player models on top of the position set by the movement systems
+pub fn animate_item_use_system(
+    mut players: Query<(&Player, &mut Transform, &mut ItemUseAnimation)>,
+    time: Res<Time>,
+) {
+    for (player, mut transform, mut animation) in players.iter_mut() {
+        animation.elapsed += time.delta_secs();
+
+        let facing = player.camera_transform.rotation.to_euler(EulerRot::YXZ).0;
+        transform.translation = player.position;
+        match animation.item.use_kind() {
+            Some(ItemUseKind::Consume { .. }) => {
+                let nod = (animation.elapsed * EAT_SPEED).sin().abs() * EAT_AMPLITUDE;
+                transform.rotation = Quat::from_rotation_y(facing) * Quat::from_rotation_x(-nod);
+            }
+            Some(ItemUseKind::Charge { full_charge_ms, .. }) => {
+                let charge = (animation.elapsed * 1000.0 / full_charge_ms as f32).min(1.0);
+                transform.rotation =
+                    Quat::from_rotation_y(facing) * Quat::from_rotation_x(DRAW_LEAN * charge);
+            }
+            Some(ItemUseKind::Instant) | None => {}
+        }
+    }
+}
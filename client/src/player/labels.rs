@@ -1,5 +1,8 @@
+use bevy::color::palettes::css::ORANGE;
 use bevy::prelude::*;
-use shared::players::ViewMode;
+use shared::players::{Player, Teams, ViewMode};
+
+use crate::ui::hud::theme::HudTheme;
 
 use super::PLAYER_LABEL_FONT_SIZE;
 
@@ -43,10 +46,40 @@ pub fn player_labels_system(
                     node.top = Val::Px(viewport_position.y);
                     node.left = Val::Px(viewport_position.x - (name_px_size / 4.0));
                 } else {
-                    warn!("Viewport position not found: {:?}", viewport_position);
+                    shared::warn_throttled!("Viewport position not found: {:?}", viewport_position);
                 }
             } else {
-                warn!("Entity not found: {:?}", label);
+                shared::warn_throttled!("Entity not found: {:?}", label);
+            }
+        }
+    }
+}
+
+/// Colors name tags with the team of their player, players without a team keep the default color
+pub fn player_label_colors_system(
+    labels: Query<(&PlayerLabel, &Children)>,
+    new_labels: Query<(), Added<PlayerLabel>>,
+    players: Query<&Player>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+    teams: Res<Teams>,
+    theme: Res<HudTheme>,
+) {
+    if !teams.is_changed() && !theme.is_changed() && new_labels.is_empty() {
+        return;
+    }
+
+    for (label, children) in &labels {
+        let Ok(player) = players.get(label.entity) else {
+            continue;
+        };
+        let color = theme
+            .player_team_color(&teams, player.id)
+            .unwrap_or(ORANGE.into());
+        let pattern = theme.player_team_pattern(&teams, player.id);
+        for child in children.iter() {
+            if let Ok((mut text, mut text_color)) = texts.get_mut(child) {
+                text.0 = format!("{}{}", pattern, label.name);
+                text_color.0 = color;
             }
         }
     }
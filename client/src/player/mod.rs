@@ -1,9 +1,17 @@
 mod controller;
+mod emotes;
+mod held_item;
 mod interactions;
+mod item_use;
 mod labels;
+mod prediction;
 mod update;
 
 pub use controller::*;
+pub use emotes::*;
+pub use held_item::*;
 pub use interactions::*;
+pub use item_use::*;
 pub use labels::*;
+pub use prediction::*;
 pub use update::*;
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use shared::messages::BlockEditResult;
+use shared::world::{global_block_to_chunk_pos, BlockData, WorldMap, SIX_OFFSETS};
+
+use crate::world::{ClientWorldMap, WorldRenderRequestUpdateEvent};
+
+/// Predicted edits the server has not answered after this long are rolled back
+const EDIT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A block edit shown before the server confirmed it
+#[derive(Debug)]
+struct PendingEdit {
+    position: IVec3,
+    previous: Option<BlockData>,
+    predicted: Option<BlockData>,
+    predicted_at: Instant,
+}
+
+/// Journal of the block edits predicted by the client, keyed by the time of the input that made them.
+/// Inputs are acknowledged by their timestamp, so it is also the sequence number of the edit.
+#[derive(Resource, Default, Debug)]
+pub struct PendingBlockEdits(BTreeMap<u64, PendingEdit>);
+
+fn set_block(world_map: &mut ClientWorldMap, position: &IVec3, block: Option<BlockData>) {
+    match block {
+        Some(block) => world_map.set_block(position, block),
+        None => {
+            world_map.remove_block_by_coordinates(position);
+        }
+    }
+}
+
+/// Meshes the chunk of the block again, along with the neighbouring chunks it touches
+pub fn request_block_remesh(
+    ev_render: &mut EventWriter<WorldRenderRequestUpdateEvent>,
+    position: IVec3,
+) {
+    let chunk_pos = global_block_to_chunk_pos(&position);
+    ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(chunk_pos));
+    for offset in &SIX_OFFSETS {
+        let neighbour = global_block_to_chunk_pos(&(position + *offset));
+        if neighbour != chunk_pos {
+            ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(neighbour));
+        }
+    }
+}
+
+impl PendingBlockEdits {
+    pub fn record(
+        &mut self,
+        input_time_ms: u64,
+        position: IVec3,
+        previous: Option<BlockData>,
+        predicted: Option<BlockData>,
+    ) {
+        self.0.insert(
+            input_time_ms,
+            PendingEdit {
+                position,
+                previous,
+                predicted,
+                predicted_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn is_pending_at(&self, position: IVec3) -> bool {
+        self.0.values().any(|edit| edit.position == position)
+    }
+
+    /// Applies the answer of the server, the block is set to its version unless the player edited it again since
+    pub fn resolve(
+        &mut self,
+        result: &BlockEditResult,
+        world_map: &mut ClientWorldMap,
+        ev_render: &mut EventWriter<WorldRenderRequestUpdateEvent>,
+    ) {
+        if let Some(edit) = self.0.remove(&result.input_time_ms) {
+            if edit.predicted.map(|b| b.id) != result.block.map(|b| b.id) {
+                debug!(
+                    "Server refused the edit at {:?}, rolling back to {:?}",
+                    result.position, result.block
+                );
+            }
+        }
+        if self.is_pending_at(result.position) {
+            return;
+        }
+
+        let current = world_map
+            .get_block_by_coordinates(&result.position)
+            .copied();
+        if current.map(|b| b.id) != result.block.map(|b| b.id) {
+            set_block(world_map, &result.position, result.block);
+            request_block_remesh(ev_render, result.position);
+        }
+    }
+
+    /// Puts the predictions back on a chunk just received from the server, it may predate them
+    pub fn reapply(&self, world_map: &mut ClientWorldMap, chunk_pos: IVec3) {
+        for edit in self.0.values() {
+            if global_block_to_chunk_pos(&edit.position) == chunk_pos {
+                set_block(world_map, &edit.position, edit.predicted);
+            }
+        }
+    }
+}
+
+/// Rolls back the predicted edits the server never answered, their input may have been dropped
+pub fn expire_block_edits_system(
+    mut pending: ResMut<PendingBlockEdits>,
+    mut world_map: ResMut<ClientWorldMap>,
+    mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
+) {
+    let expired: Vec<u64> = pending
+        .0
+        .iter()
+        .filter(|(_, edit)| edit.predicted_at.elapsed() > EDIT_CONFIRMATION_TIMEOUT)
+        .map(|(time_ms, _)| *time_ms)
+        .collect();
+
+    // Newest first, so that a block edited several times ends up as it was before the oldest edit
+    for time_ms in expired.into_iter().rev() {
+        let Some(edit) = pending.0.remove(&time_ms) else {
+            continue;
+        };
+        if pending.is_pending_at(edit.position) {
+            continue;
+        }
+        warn!(
+            "No answer from the server for the edit at {:?}, rolling it back",
+            edit.position
+        );
+        set_block(&mut world_map, &edit.position, edit.previous);
+        request_block_remesh(&mut ev_render, edit.position);
+    }
+}
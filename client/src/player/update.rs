@@ -1,5 +1,5 @@
 use crate::{
-    camera::CameraController,
+    camera::{CameraController, Spectate},
     network::{CurrentPlayerProfile, TargetServer, TargetServerState, UnacknowledgedInputs},
     player::{PlayerLabel, PlayerMaterialHandle},
     world::ClientWorldMap,
@@ -8,13 +8,17 @@ use crate::{
 use bevy::color::palettes::css::ORANGE;
 use bevy::prelude::*;
 use shared::{
-    messages::{PlayerSpawnEvent, PlayerUpdateEvent},
-    players::{blocks::CallerType, simulation::simulate_player_actions, Inventory, Player},
+    messages::{EmoteEvent, PlayerAfkEvent, PlayerLeftEvent, PlayerSpawnEvent, PlayerUpdateEvent},
+    players::{movement::simulate_player_movement, Inventory, Player},
 };
 
 #[derive(Component)]
 pub struct CurrentPlayerMarker {}
 
+/// Set on the players the server reported as away from their keyboard
+#[derive(Component)]
+pub struct AfkMarker;
+
 pub const PLAYER_LABEL_FONT_SIZE: f32 = 24.0;
 
 pub fn spawn_players_system(
@@ -85,22 +89,24 @@ pub fn spawn_players_system(
 
         let label_text_style = (text_style.clone(), TextColor(ORANGE.into()));
 
+        if event.afk {
+            entity.insert(AfkMarker);
+        }
+
         if is_current_player {
             target_server.state = TargetServerState::FullyReady;
             entity.insert(CurrentPlayerMarker {});
-            info!("Inserted current player marker");
+            debug!("Inserted current player marker");
 
-            info!("aaa ---");
             for (transform, controller) in camera_query.iter_mut() {
                 *transform.into_inner() = event.data.camera_transform;
                 *controller.into_inner() = event.data.camera_transform.rotation.into();
 
-                info!(
+                debug!(
                     "Setting camera transform: {:?}",
                     event.data.camera_transform
                 );
             }
-            info!("bbb ---");
         }
 
         let entity_id = entity.id();
@@ -128,13 +134,58 @@ pub fn spawn_players_system(
     }
 }
 
+/// Removes the players that left, along with their name tag
+pub fn despawn_players_system(
+    mut commands: Commands,
+    mut ev_left: EventReader<PlayerLeftEvent>,
+    players: Query<(Entity, &Player)>,
+    labels: Query<(Entity, &PlayerLabel)>,
+) {
+    for event in ev_left.read() {
+        for (entity, player) in players.iter() {
+            if player.id != event.id {
+                continue;
+            }
+            info!("Despawning player object: {}", player.id);
+            commands.entity(entity).despawn();
+            for (label_entity, label) in labels.iter() {
+                if label.entity == entity {
+                    commands.entity(label_entity).despawn();
+                }
+            }
+        }
+    }
+}
+
+pub fn apply_player_afk_system(
+    mut commands: Commands,
+    mut ev_afk: EventReader<PlayerAfkEvent>,
+    players: Query<(Entity, &Player)>,
+) {
+    for event in ev_afk.read() {
+        let Some((entity, _)) = players
+            .iter()
+            .find(|(_, player)| player.id == event.player_id)
+        else {
+            continue;
+        };
+        if event.afk {
+            commands.entity(entity).insert(AfkMarker);
+        } else {
+            commands.entity(entity).remove::<AfkMarker>();
+        }
+    }
+}
+
 pub fn update_players_system(
     mut players: Query<(&mut Player, &mut Transform)>,
     mut ev_player_update: EventReader<PlayerUpdateEvent>,
+    mut ev_emote: EventWriter<EmoteEvent>,
     mut unacknowledged_inputs: ResMut<UnacknowledgedInputs>,
     client: Res<TargetServer>,
     world_map: ResMut<ClientWorldMap>,
     mut inventory: ResMut<Inventory>,
+    spectate: Res<Spectate>,
 ) {
     let my_id = client.session_token.unwrap();
 
@@ -146,6 +197,8 @@ pub fn update_players_system(
         for (mut player, mut transform) in players.iter_mut() {
             if player.id == event.id && event.id == my_id {
                 player.inventory = event.inventory.clone();
+                player.health = event.health;
+                player.effects = event.effects.clone();
                 inventory.inner = event.inventory.inner.clone();
 
                 // Get the local input matching this update event
@@ -158,10 +211,13 @@ pub fn update_players_system(
                     let does_position_match = event.position == matching_input.position;
 
                     if !does_position_match {
-                        warn!(
-                            "Player position mismatch: Client({:?}) != Server({:?}) at t={} (id={})",
-                            event.position, matching_input.position, matching_input.time_ms, player.id
-                        );
+                        // The server moves spectators along with the player they follow
+                        if spectate.target.is_none() {
+                            warn!(
+                                "Player position mismatch: Client({:?}) != Server({:?}) at t={} (id={})",
+                                event.position, matching_input.position, matching_input.time_ms, player.id
+                            );
+                        }
 
                         // Reconcile the player position
                         player.position = event.position;
@@ -173,14 +229,11 @@ pub fn update_players_system(
                             .cloned()
                             .collect::<Vec<_>>();
 
+                        // Only the movement is replayed, the block edits of these inputs are
+                        // already in the world and wait for the answer of the server
                         for input in remaining_inputs.iter() {
                             // debug!("Reapplying input: {:?}", input);
-                            simulate_player_actions(
-                                &mut player,
-                                world_map,
-                                input,
-                                CallerType::Client,
-                            );
+                            simulate_player_movement(&mut player, world_map, input);
                         }
 
                         debug!(
@@ -222,7 +275,20 @@ pub fn update_players_system(
                     player.id, event.position
                 );
                 player.position = event.position;
+                player.camera_transform.rotation = event.orientation;
+                player.effects = event.effects.clone();
+                player.inventory = event.inventory.clone();
+                player.hotbar_slot = event.hotbar_slot;
                 *transform = Transform::from_translation(event.position);
+
+                // Catches up with the looping emotes started before the player came into view
+                if player.emote != event.emote {
+                    player.emote = event.emote;
+                    ev_emote.write(EmoteEvent {
+                        player_id: player.id,
+                        emote: event.emote,
+                    });
+                }
             }
         }
     }
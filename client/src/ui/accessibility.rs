@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use ron::{from_str, ser::PrettyConfig};
+use serde::{Deserialize, Serialize};
+use shared::GameFolderPaths;
+use std::{fs, path::PathBuf};
+
+use crate::constants::ACCESSIBILITY_SETTINGS_PATH;
+use crate::ui::lang::DEFAULT_LANGUAGE;
+
+pub const MIN_HUD_SCALE: f32 = 0.5;
+pub const MAX_HUD_SCALE: f32 = 2.0;
+
+/// Color sets of the HUD, all but the default one stay readable with the common kinds of color blindness
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UiPalette {
+    #[default]
+    Default,
+    /// Red-green color blindness, also fine for protanopia
+    Deuteranopia,
+    /// Blue-yellow color blindness
+    Tritanopia,
+    HighContrast,
+}
+
+impl UiPalette {
+    pub const ALL: [UiPalette; 4] = [
+        UiPalette::Default,
+        UiPalette::Deuteranopia,
+        UiPalette::Tritanopia,
+        UiPalette::HighContrast,
+    ];
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|p| p == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrosshairSize {
+    #[default]
+    Small,
+    Medium,
+    Large,
+}
+
+impl CrosshairSize {
+    pub fn next(&self) -> Self {
+        match self {
+            CrosshairSize::Small => CrosshairSize::Medium,
+            CrosshairSize::Medium => CrosshairSize::Large,
+            CrosshairSize::Large => CrosshairSize::Small,
+        }
+    }
+
+    /// Length and thickness of the crosshair lines, in pixels
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            CrosshairSize::Small => (20.0, 2.0),
+            CrosshairSize::Medium => (32.0, 3.0),
+            CrosshairSize::Large => (48.0, 5.0),
+        }
+    }
+}
+
+/// Options making the game easier to play, saved in the game folder
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    /// Jumps automatically when walking into a block one step high
+    pub auto_jump: bool,
+    pub palette: UiPalette,
+    /// Scale of the whole interface, 1 being the default size
+    pub hud_scale: f32,
+    pub crosshair: CrosshairSize,
+    /// Reads incoming chat messages aloud
+    pub narrate_chat: bool,
+    /// Reads notifications aloud, such as unlocked recipes
+    pub narrate_notifications: bool,
+    /// Warns aloud when health gets low
+    pub narrate_low_health: bool,
+    /// Code of the language of the names and messages, one of `LANGUAGES`
+    pub language: String,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            auto_jump: false,
+            palette: UiPalette::Default,
+            hud_scale: 1.0,
+            crosshair: CrosshairSize::Small,
+            narrate_chat: false,
+            narrate_notifications: false,
+            narrate_low_health: false,
+            language: DEFAULT_LANGUAGE.to_string(),
+        }
+    }
+}
+
+fn accessibility_settings_path(paths: &GameFolderPaths) -> PathBuf {
+    paths.game_folder_path.join(ACCESSIBILITY_SETTINGS_PATH)
+}
+
+pub fn load_accessibility_settings(paths: &GameFolderPaths) -> AccessibilitySettings {
+    let Ok(content) = fs::read_to_string(accessibility_settings_path(paths)) else {
+        return AccessibilitySettings::default();
+    };
+
+    match from_str::<AccessibilitySettings>(&content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Invalid accessibility settings, using the defaults: {}", e);
+            AccessibilitySettings::default()
+        }
+    }
+}
+
+pub fn save_accessibility_settings(settings: &AccessibilitySettings, paths: &GameFolderPaths) {
+    let path = accessibility_settings_path(paths);
+
+    match ron::ser::to_string_pretty(settings, PrettyConfig::new()) {
+        Ok(serialized) => {
+            if let Err(e) = fs::write(&path, serialized) {
+                error!(
+                    "Error while saving accessibility settings to {:?}: {}",
+                    path, e
+                );
+            }
+        }
+        Err(e) => error!("Failed to serialize accessibility settings: {}", e),
+    }
+}
@@ -22,6 +22,8 @@ pub struct SelectedOption;
 #[derive(Component)]
 pub enum MenuButtonAction {
     Solo,
+    /// Plays the world shipped with the game
+    Demo,
     Multi,
     Settings,
     SettingsControls,
@@ -1,14 +1,18 @@
+use crate::camera::CameraCommandEvent;
 use crate::input::keyboard::is_action_just_pressed;
 use crate::input::keyboard::is_action_just_released;
 use crate::network::CachedChatConversation;
 use crate::network::SendGameMessageExtension;
 use crate::ui::hud::UiDialog;
+use crate::ui::lang::Localization;
+use crate::world::export::ExportCommandEvent;
 use crate::KeyMap;
 use bevy::prelude::*;
 use bevy_renet::renet::RenetClient;
 use bevy_simple_text_input::*;
 use shared::GameFolderPaths;
 
+use super::theme::HudTheme;
 use super::UIMode;
 
 #[derive(Component)]
@@ -25,7 +29,6 @@ pub struct MessageAnimator {
     created_ts: u64,
 }
 
-const CHAT_COLOR: Color = Color::srgba(0., 0., 0., 0.6);
 const CHAT_SIZE: f32 = 17.;
 const CHAT_MAX_MESSAGES: usize = 2;
 
@@ -36,6 +39,7 @@ const ANIMATION_HIDE: u64 = 2_000;
 pub fn setup_chat(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<HudTheme>,
     _paths: Res<GameFolderPaths>,
 ) {
     commands
@@ -60,7 +64,7 @@ pub fn setup_chat(
                     flex_direction: FlexDirection::Column,
                     ..default()
                 },
-                BackgroundColor(CHAT_COLOR),
+                BackgroundColor(theme.chat_background),
                 Visibility::Hidden,
             ),
         ))
@@ -104,7 +108,7 @@ pub fn setup_chat(
                         font_size: 17.,
                         ..default()
                     }),
-                    TextInputTextColor(TextColor(Color::WHITE)),
+                    TextInputTextColor(TextColor(theme.chat_text)),
                     TextInputInactive(true),
                 ),
             ));
@@ -119,10 +123,11 @@ pub fn render_chat(
         Res<ButtonInput<KeyCode>>,
         Res<KeyMap>,
         Res<UIMode>,
+        Res<HudTheme>,
     ),
     queries: (
         Query<(Entity, &mut TextInputInactive, &mut TextInputValue), With<ChatInput>>,
-        Query<&mut Visibility, With<ChatRoot>>,
+        Query<(&mut Visibility, &mut BackgroundColor), With<ChatRoot>>,
         Query<(Entity, &Children), With<ChatDisplay>>,
         Query<
             (
@@ -136,14 +141,21 @@ pub fn render_chat(
     ),
     mut last_render_ts: Local<u64>,
     mut event: EventReader<TextInputSubmitEvent>,
+    mut ev_export: EventWriter<ExportCommandEvent>,
+    mut ev_camera: EventWriter<CameraCommandEvent>,
     mut commands: Commands,
     _paths: Res<GameFolderPaths>,
+    localization: Res<Localization>,
 ) {
-    let (cached_conv, asset_server, mut client, keyboard_input, key_map, ui_mode) = resources;
+    let (cached_conv, asset_server, mut client, keyboard_input, key_map, ui_mode, theme) =
+        resources;
     let (mut text_query, mut visibility_query, parent_query, mut animation_query) = queries;
     let (entity_check, mut inactive, mut value) = text_query.single_mut().unwrap();
 
-    let mut visibility = visibility_query.single_mut().unwrap();
+    let (mut visibility, mut root_background) = visibility_query.single_mut().unwrap();
+    if theme.is_changed() {
+        *root_background = BackgroundColor(theme.chat_background);
+    }
     let (parent, children) = parent_query.single().unwrap();
 
     if is_action_just_released(
@@ -185,7 +197,8 @@ pub fn render_chat(
         } else if diff > ANIMATION_BEGIN_FADE {
             // Animate linear fade
             let alpha = 1. - ((diff - ANIMATION_BEGIN_FADE) as f32 / ANIMATION_HIDE as f32);
-            *bg = BackgroundColor(CHAT_COLOR.with_alpha(0.6 * alpha));
+            let base_alpha = theme.chat_background.alpha();
+            *bg = BackgroundColor(theme.chat_background.with_alpha(base_alpha * alpha));
             // text.sections[0].style.color = Color::WHITE.with_alpha(alpha);
         }
     }
@@ -206,15 +219,19 @@ pub fn render_chat(
                             .as_millis() as u64,
                     },
                     (
-                        Text::new(format!("<{}> : {}", message.author, message.content)),
+                        Text::new(format!(
+                            "<{}> : {}",
+                            message.author,
+                            localization.localize(&message.content)
+                        )),
                         TextFont {
                             font: asset_server.load("./fonts/RustCraftRegular-Bmg3.otf"),
                             font_size: 17.,
                             ..default()
                         },
-                        TextColor(Color::WHITE),
+                        TextColor(theme.chat_text),
                         Visibility::Visible,
-                        BackgroundColor(CHAT_COLOR),
+                        BackgroundColor(theme.chat_background),
                     ),
                 ))
                 .id();
@@ -238,6 +255,22 @@ pub fn render_chat(
 
     for message in event.read() {
         if entity_check == message.entity {
+            // Exports and camera paths only involve the client, they are not sent to the server
+            let (command, args) = message
+                .value
+                .split_once(' ')
+                .unwrap_or((&message.value, ""));
+            match command {
+                "/export" => {
+                    ev_export.write(ExportCommandEvent(args.to_string()));
+                    continue;
+                }
+                "/camera" => {
+                    ev_camera.write(CameraCommandEvent(args.to_string()));
+                    continue;
+                }
+                _ => {}
+            }
             client.send_game_message(shared::messages::ClientToServerMessage::ChatMessage(
                 shared::messages::ChatMessageRequest {
                     content: message.value.clone(),
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::prelude::*;
+use shared::messages::mob::{MobDespawnEvent, MobHitEvent, MobUpdateEvent};
+use shared::world::MobId;
+
+use crate::mob::MobRoot;
+use crate::ui::hud::theme::HudTheme;
+use crate::GameState;
+
+/// Seconds the hit marker stays on the crosshair
+const HIT_MARKER_DURATION: f32 = 0.25;
+/// Pixels from the center of the screen to each tick of the hit marker
+const HIT_MARKER_SPREAD: f32 = 9.;
+const HIT_MARKER_TICK_LENGTH: f32 = 7.;
+const HIT_MARKER_TICK_THICKNESS: f32 = 2.;
+
+/// Seconds a damage number floats before disappearing
+const DAMAGE_NUMBER_DURATION: f32 = 0.8;
+/// Blocks a damage number rises over its lifetime
+const DAMAGE_NUMBER_RISE: f32 = 0.8;
+const DAMAGE_NUMBER_FONT_SIZE: f32 = 18.;
+const DAMAGE_NUMBER_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+/// Seconds a mob health bar stays after the last time the mob got hurt, the last of them fading it out
+const MOB_HEALTH_BAR_DURATION: f32 = 4.;
+const MOB_HEALTH_BAR_FADE: f32 = 1.;
+const MOB_HEALTH_BAR_WIDTH: f32 = 40.;
+const MOB_HEALTH_BAR_HEIGHT: f32 = 5.;
+/// Blocks between the top of a mob and its health bar
+const MOB_HEALTH_BAR_OFFSET: f32 = 0.4;
+
+/// Cross around the crosshair showing that an attack landed
+#[derive(Component)]
+pub struct HitMarker(Timer);
+
+/// Damage dealt to a mob, floating up from where it was hit
+#[derive(Component)]
+pub struct DamageNumber {
+    position: Vec3,
+    timer: Timer,
+}
+
+/// Health of a mob which got hurt recently, shown above it
+#[derive(Component)]
+pub struct MobHealthBar {
+    mob: MobId,
+    height: f32,
+    timer: Timer,
+}
+
+#[derive(Component)]
+pub struct MobHealthBarFill;
+
+pub fn setup_hit_marker(mut commands: Commands) {
+    let mut timer = Timer::from_seconds(HIT_MARKER_DURATION, TimerMode::Once);
+    // Hidden until the first hit
+    timer.tick(timer.duration());
+
+    commands
+        .spawn((
+            Name::new("HitMarker"),
+            StateScoped(GameState::Game),
+            HitMarker(timer),
+            Node {
+                position_type: PositionType::Absolute,
+                margin: UiRect::all(Val::Auto),
+                ..default()
+            },
+            Visibility::Hidden,
+        ))
+        .with_children(|marker| {
+            // One tick along each diagonal, leaving the crosshair itself visible
+            for (x, y) in [(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)] {
+                marker.spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(HIT_MARKER_TICK_LENGTH),
+                        height: Val::Px(HIT_MARKER_TICK_THICKNESS),
+                        left: Val::Px(x * HIT_MARKER_SPREAD - HIT_MARKER_TICK_LENGTH / 2.),
+                        top: Val::Px(y * HIT_MARKER_SPREAD - HIT_MARKER_TICK_THICKNESS / 2.),
+                        ..default()
+                    },
+                    Transform::from_rotation(Quat::from_rotation_z(x * y * FRAC_PI_4)),
+                    BackgroundColor(Color::WHITE),
+                ));
+            }
+        });
+}
+
+/// Shows the hit marker and a damage number when the server confirms an attack landed
+pub fn hit_feedback_system(
+    mut commands: Commands,
+    mut events: EventReader<MobHitEvent>,
+    marker: Single<(&mut HitMarker, &mut Visibility)>,
+    mobs: Query<(&MobRoot, &GlobalTransform)>,
+    assets: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    let (mut marker, mut visibility) = marker.into_inner();
+
+    for hit in events.read() {
+        marker.0.reset();
+
+        let Some((_, transform)) = mobs.iter().find(|(mob, _)| mob.id == hit.id) else {
+            continue;
+        };
+        commands.spawn((
+            Name::new("DamageNumber"),
+            StateScoped(GameState::Game),
+            DamageNumber {
+                position: transform.translation() + Vec3::Y,
+                timer: Timer::from_seconds(DAMAGE_NUMBER_DURATION, TimerMode::Once),
+            },
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Text::new(format!("{:.0}", hit.damage.ceil())),
+            TextFont {
+                font: assets.load("fonts/FiraMono-Medium.ttf"),
+                font_size: DAMAGE_NUMBER_FONT_SIZE,
+                ..default()
+            },
+            TextColor(DAMAGE_NUMBER_COLOR),
+            Visibility::Hidden,
+        ));
+    }
+
+    marker.0.tick(time.delta());
+    visibility.set_if_neq(if marker.0.finished() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    });
+}
+
+/// Spawns a health bar above each mob whose health went down, or keeps its bar up for longer
+pub fn mob_health_bars_system(
+    mut commands: Commands,
+    mut ev_update: EventReader<MobUpdateEvent>,
+    mut ev_despawn: EventReader<MobDespawnEvent>,
+    mut bars: Query<(Entity, &mut MobHealthBar, &Children)>,
+    mut fills: Query<(&mut Node, &mut BackgroundColor), With<MobHealthBarFill>>,
+    mut last_health: Local<HashMap<MobId, f32>>,
+    theme: Res<HudTheme>,
+) {
+    // A mob can be updated several times in a frame, it only gets one bar
+    let mut hurt = HashMap::new();
+    for event in ev_update.read() {
+        let previous = last_health.insert(event.id, event.mob.health);
+        if previous.is_some_and(|previous| event.mob.health < previous) {
+            hurt.insert(event.id, event);
+        }
+    }
+
+    for event in hurt.into_values() {
+        let fraction = (event.mob.health / event.mob.kind.max_health()).clamp(0., 1.);
+        let existing = bars.iter_mut().find(|(_, bar, _)| bar.mob == event.id);
+        if let Some((_, mut bar, children)) = existing {
+            bar.timer.reset();
+            for child in children.iter() {
+                if let Ok((mut node, mut color)) = fills.get_mut(child) {
+                    node.width = Val::Percent(fraction * 100.);
+                    color.0 = theme.health_color(fraction);
+                }
+            }
+            continue;
+        }
+
+        commands
+            .spawn((
+                Name::new("MobHealthBar"),
+                StateScoped(GameState::Game),
+                MobHealthBar {
+                    mob: event.id,
+                    height: event.mob.height,
+                    timer: Timer::from_seconds(MOB_HEALTH_BAR_DURATION, TimerMode::Once),
+                },
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(MOB_HEALTH_BAR_WIDTH),
+                    height: Val::Px(MOB_HEALTH_BAR_HEIGHT),
+                    ..default()
+                },
+                BackgroundColor(theme.bar_background),
+                Visibility::Hidden,
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    MobHealthBarFill,
+                    Node {
+                        width: Val::Percent(fraction * 100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    BackgroundColor(theme.health_color(fraction)),
+                ));
+            });
+    }
+
+    for event in ev_despawn.read() {
+        last_health.remove(&event.id);
+        for (entity, bar, _) in bars.iter() {
+            if bar.mob == event.id {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+/// Places the damage numbers and health bars over their mobs on the screen, fading them out as they expire
+pub fn combat_overlays_system(
+    mut commands: Commands,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mut numbers: Query<
+        (
+            Entity,
+            &mut DamageNumber,
+            &mut Node,
+            &mut Visibility,
+            &mut TextColor,
+        ),
+        Without<MobHealthBar>,
+    >,
+    mut bars: Query<
+        (
+            Entity,
+            &mut MobHealthBar,
+            &mut Node,
+            &mut Visibility,
+            &mut BackgroundColor,
+            &Children,
+        ),
+        Without<DamageNumber>,
+    >,
+    mut fills: Query<
+        &mut BackgroundColor,
+        (
+            With<MobHealthBarFill>,
+            Without<MobHealthBar>,
+            Without<DamageNumber>,
+        ),
+    >,
+    mobs: Query<(&MobRoot, &GlobalTransform)>,
+    theme: Res<HudTheme>,
+    time: Res<Time>,
+) {
+    let (camera, camera_transform) = camera.into_inner();
+    let on_screen = |position: Vec3| camera.world_to_viewport(camera_transform, position).ok();
+
+    for (entity, mut number, mut node, mut visibility, mut color) in numbers.iter_mut() {
+        number.timer.tick(time.delta());
+        if number.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let progress = number.timer.fraction();
+        let Some(screen) = on_screen(number.position + Vec3::Y * DAMAGE_NUMBER_RISE * progress)
+        else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        node.left = Val::Px(screen.x);
+        node.top = Val::Px(screen.y);
+        color.0 = DAMAGE_NUMBER_COLOR.with_alpha(1. - progress);
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+
+    for (entity, mut bar, mut node, mut visibility, mut background, children) in bars.iter_mut() {
+        bar.timer.tick(time.delta());
+        let mob = mobs.iter().find(|(mob, _)| mob.id == bar.mob);
+        let (Some((_, transform)), false) = (mob, bar.timer.finished()) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let above = transform.translation() + Vec3::Y * (bar.height + MOB_HEALTH_BAR_OFFSET);
+        let Some(screen) = on_screen(above) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        node.left = Val::Px(screen.x - MOB_HEALTH_BAR_WIDTH / 2.);
+        node.top = Val::Px(screen.y);
+        visibility.set_if_neq(Visibility::Inherited);
+
+        let alpha = (bar.timer.remaining_secs() / MOB_HEALTH_BAR_FADE).min(1.);
+        background.0 = theme
+            .bar_background
+            .with_alpha(theme.bar_background.alpha() * alpha);
+        for child in children.iter() {
+            if let Ok(mut fill) = fills.get_mut(child) {
+                fill.0.set_alpha(alpha);
+            }
+        }
+    }
+}
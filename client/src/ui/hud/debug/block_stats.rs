@@ -0,0 +1,109 @@
+use crate::input::data::GameAction;
+use crate::input::keyboard::is_action_just_pressed;
+use crate::world::{ClientWorldMap, PendingMeshes};
+use crate::{GameState, KeyMap};
+use bevy::prelude::*;
+
+/// Only the most common kinds of blocks are listed
+const MAX_LISTED_BLOCKS: usize = 12;
+
+#[derive(Component)]
+pub struct BlockStatsPanel;
+
+#[derive(Component)]
+pub struct BlockStatsText;
+
+pub fn setup_block_stats_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            BlockStatsPanel,
+            StateScoped(GameState::Game),
+            BackgroundColor(Color::BLACK.with_alpha(0.5)),
+            GlobalZIndex(i32::MAX),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Percent(1.),
+                top: Val::Percent(1.),
+                padding: UiRect::all(Val::Px(4.0)),
+                ..Default::default()
+            },
+            Visibility::Hidden,
+        ))
+        .with_child((
+            BlockStatsText,
+            Text::default(),
+            TextFont {
+                font_size: 16.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f32 / (1024. * 1024.))
+    } else {
+        format!("{:.1} KiB", bytes as f32 / 1024.)
+    }
+}
+
+/// Shows how many blocks of each kind are loaded, and how much memory the chunks take
+pub fn block_stats_system(
+    mut panel: Query<&mut Visibility, With<BlockStatsPanel>>,
+    mut text: Query<&mut Text, With<BlockStatsText>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    world_map: Res<ClientWorldMap>,
+    pending_meshes: Res<PendingMeshes>,
+) {
+    let Ok(mut visibility) = panel.single_mut() else {
+        return;
+    };
+
+    if is_action_just_pressed(GameAction::ToggleBlockStats, &keyboard_input, &key_map) {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+
+    let chunks = world_map.map.len();
+    let memory: usize = world_map
+        .map
+        .values()
+        .map(|chunk| chunk.estimated_memory())
+        .sum();
+    let per_chunk = memory.checked_div(chunks).unwrap_or_default();
+
+    let mut lines = vec![
+        format!("Chunks: {}", chunks),
+        format!(
+            "Chunk memory: {} ({} per chunk)",
+            format_bytes(memory),
+            format_bytes(per_chunk)
+        ),
+        format!("Meshes pending: {}", pending_meshes.0),
+        format!("Blocks: {}", world_map.total_blocks_count),
+    ];
+
+    let mut counts: Vec<_> = world_map.block_counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1));
+    for (id, count) in counts.iter().take(MAX_LISTED_BLOCKS) {
+        lines.push(format!("  {:?}: {}", id, count));
+    }
+    if counts.len() > MAX_LISTED_BLOCKS {
+        lines.push(format!(
+            "  ... {} more kinds",
+            counts.len() - MAX_LISTED_BLOCKS
+        ));
+    }
+
+    for mut text in text.iter_mut() {
+        text.0 = lines.join("\n");
+    }
+}
@@ -1,6 +1,6 @@
 use crate::player::CurrentPlayerMarker;
 use bevy::prelude::*;
-use shared::world::block_to_chunk_coord;
+use shared::world::world_position_to_chunk_position;
 
 #[derive(Component)]
 pub struct CoordsText;
@@ -11,11 +11,7 @@ pub fn coords_text_update_system(
     mut writer: TextUiWriter,
 ) {
     let coords = player.single().unwrap();
-    let player_chunk = IVec3::new(
-        block_to_chunk_coord(coords.translation.x as i32),
-        block_to_chunk_coord(coords.translation.y as i32),
-        block_to_chunk_coord(coords.translation.z as i32),
-    );
+    let player_chunk = world_position_to_chunk_position(coords.translation);
 
     for entity in query.iter() {
         *writer.text(entity, 0) = format!(
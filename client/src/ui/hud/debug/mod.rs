@@ -1,3 +1,4 @@
+pub mod block_stats;
 pub mod blocks;
 pub mod chunks;
 pub mod coords;
@@ -8,7 +9,8 @@ pub mod raycast;
 pub mod setup;
 pub mod targeted_block;
 
-use bevy::prelude::Resource;
+use bevy::prelude::{info, Resource};
+pub use block_stats::*;
 pub use blocks::*;
 pub use chunks::*;
 pub use coords::*;
@@ -21,6 +23,8 @@ pub use setup::*;
 pub struct DebugOptions {
     is_chunk_debug_mode_enabled: bool,
     is_raycast_debug_mode_enabled: bool,
+    /// The camera is detached from the player and flies on its own
+    is_freecam_enabled: bool,
 }
 
 impl DebugOptions {
@@ -35,4 +39,13 @@ impl DebugOptions {
             self.is_raycast_debug_mode_enabled
         );
     }
+
+    pub fn toggle_freecam(&mut self) {
+        self.is_freecam_enabled = !self.is_freecam_enabled;
+        info!("Freecam is now {}", self.is_freecam_enabled);
+    }
+
+    pub fn is_freecam_enabled(&self) -> bool {
+        self.is_freecam_enabled
+    }
 }
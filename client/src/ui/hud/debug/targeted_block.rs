@@ -1,5 +1,6 @@
 use crate::constants::INTERACTION_DISTANCE;
 use crate::player::CurrentPlayerMarker;
+use crate::ui::lang::Localization;
 use crate::world::ClientWorldMap;
 use bevy::prelude::*;
 use shared::players::ViewMode;
@@ -15,6 +16,7 @@ pub fn block_text_update_system(
     mut query: Query<(&mut Text, &mut TextColor), With<BlockText>>,
     camera_query: Query<&Transform, With<Camera>>,
     view_mode: Res<ViewMode>,
+    localization: Res<Localization>,
 ) {
     let mut col = Color::srgb(1.0, 1.0, 1.0);
     let mut txt = "<none>".to_string();
@@ -33,7 +35,13 @@ pub fn block_text_update_system(
         let block_pos = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32);
         if (block_pos - player.single().unwrap().translation).length() < INTERACTION_DISTANCE {
             col = Color::WHITE;
-            txt = format!("{:?} pos = ({}, {}, {})", res.block, pos.x, pos.y, pos.z);
+            txt = format!(
+                "{} pos = ({}, {}, {})",
+                localization.block_name(res.block.id),
+                pos.x,
+                pos.y,
+                pos.z
+            );
         }
     }
 
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use shared::{
+    players::{Player, StatusEffectKind},
+    TICKS_PER_SECOND,
+};
+
+use crate::player::CurrentPlayerMarker;
+use crate::GameState;
+
+#[derive(Component)]
+pub struct EffectsRoot;
+
+const EFFECT_ICON_SIZE: f32 = 14.;
+const EFFECT_FONT_SIZE: f32 = 14.;
+
+/// Color of the icon and of the particles of an effect
+pub fn effect_color(kind: StatusEffectKind) -> Color {
+    match kind {
+        StatusEffectKind::Poison => Color::srgb(0.3, 0.6, 0.1),
+        StatusEffectKind::Regeneration => Color::srgb(0.9, 0.3, 0.6),
+        StatusEffectKind::Speed => Color::srgb(0.5, 0.8, 1.0),
+        StatusEffectKind::Slowness => Color::srgb(0.35, 0.4, 0.55),
+        StatusEffectKind::Fire => Color::srgb(1.0, 0.5, 0.0),
+    }
+}
+
+/// Column in the top right corner listing the effects of the player
+pub fn setup_effects_display(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Effects"),
+        StateScoped(GameState::Game),
+        EffectsRoot,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            right: Val::Px(10.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            row_gap: Val::Px(4.),
+            ..default()
+        },
+    ));
+}
+
+/// Shows an icon per active effect with its level and the seconds left, rebuilt when the effects change
+pub fn effects_display_system(
+    mut commands: Commands,
+    player: Query<&Player, (With<CurrentPlayerMarker>, Changed<Player>)>,
+    root: Single<Entity, With<EffectsRoot>>,
+    mut shown: Local<Vec<(StatusEffectKind, u8, u32)>>,
+) {
+    let Ok(player) = player.single() else {
+        return;
+    };
+    let effects: Vec<(StatusEffectKind, u8, u32)> = player
+        .effects
+        .iter()
+        .map(|(kind, effect)| {
+            let seconds = effect.remaining_ticks.div_ceil(TICKS_PER_SECOND as u32);
+            (kind, effect.amplifier, seconds)
+        })
+        .collect();
+    if *shown == effects {
+        return;
+    }
+
+    let root = root.into_inner();
+    commands.entity(root).despawn_related::<Children>();
+    for (kind, amplifier, seconds) in effects.iter() {
+        let entry = commands
+            .spawn((
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(6.),
+                    padding: UiRect::axes(Val::Px(6.), Val::Px(2.)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.6)),
+                children![
+                    (
+                        Text::new(format!(
+                            "{} {} {}:{:02}",
+                            kind.name(),
+                            amplifier + 1,
+                            seconds / 60,
+                            seconds % 60
+                        )),
+                        TextFont {
+                            font_size: EFFECT_FONT_SIZE,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ),
+                    (
+                        Node {
+                            width: Val::Px(EFFECT_ICON_SIZE),
+                            height: Val::Px(EFFECT_ICON_SIZE),
+                            ..default()
+                        },
+                        BackgroundColor(effect_color(*kind)),
+                    )
+                ],
+            ))
+            .id();
+        commands.entity(root).add_child(entry);
+    }
+    *shown = effects;
+}
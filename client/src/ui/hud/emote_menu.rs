@@ -0,0 +1,106 @@
+use crate::input::{data::GameAction, keyboard::is_action_just_pressed};
+use crate::network::SendGameMessageExtension;
+use crate::ui::hud::{UIMode, UiDialog};
+use crate::GameState;
+use crate::KeyMap;
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::{ClientToServerMessage, Emote};
+
+#[derive(Component)]
+pub struct EmoteMenuRoot;
+
+#[derive(Component)]
+pub struct EmoteButton(Emote);
+
+const EMOTE_MENU_RADIUS: f32 = 90.;
+const EMOTE_BUTTON_SIZE: f32 = 80.;
+const EMOTE_BUTTON_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.8);
+const EMOTE_BUTTON_HOVER_COLOR: Color = Color::srgba(0.4, 0.4, 0.4, 0.9);
+
+/// Spawns the emote buttons evenly spread on a circle around the screen center
+pub fn setup_emote_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("EmoteMenu"),
+            StateScoped(GameState::Game),
+            EmoteMenuRoot,
+            UiDialog,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(50.),
+                top: Val::Percent(50.),
+                ..default()
+            },
+            GlobalZIndex(2),
+            Visibility::Hidden,
+        ))
+        .with_children(|root| {
+            let count = Emote::ALL.len() as f32;
+            for (i, emote) in Emote::ALL.into_iter().enumerate() {
+                // Starts at the top of the circle, going clockwise
+                let angle = std::f32::consts::TAU * i as f32 / count;
+                let x = angle.sin() * EMOTE_MENU_RADIUS;
+                let y = -angle.cos() * EMOTE_MENU_RADIUS;
+
+                root.spawn((
+                    Button,
+                    EmoteButton(emote),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(EMOTE_BUTTON_SIZE),
+                        height: Val::Px(EMOTE_BUTTON_SIZE),
+                        left: Val::Px(x - EMOTE_BUTTON_SIZE / 2.),
+                        top: Val::Px(y - EMOTE_BUTTON_SIZE / 2.),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(EMOTE_BUTTON_COLOR),
+                ))
+                .with_children(|button| {
+                    button.spawn((
+                        Text::new(emote.name()),
+                        TextFont {
+                            font_size: 18.,
+                            ..default()
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+pub fn emote_menu_system(
+    mut menu: Single<&mut Visibility, With<EmoteMenuRoot>>,
+    mut buttons: Query<(&Interaction, &EmoteButton, &mut BackgroundColor), Changed<Interaction>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+    mut client: ResMut<RenetClient>,
+) {
+    let is_open = **menu == Visibility::Visible;
+
+    if is_action_just_pressed(GameAction::OpenEmoteMenu, &keyboard_input, &key_map)
+        && (is_open || *ui_mode == UIMode::Closed)
+    {
+        **menu = if is_open {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+        return;
+    }
+
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match interaction {
+            Interaction::Pressed => {
+                client.send_game_message(ClientToServerMessage::Emote(Some(button.0)));
+                **menu = Visibility::Hidden;
+            }
+            Interaction::Hovered => *color = BackgroundColor(EMOTE_BUTTON_HOVER_COLOR),
+            Interaction::None => *color = BackgroundColor(EMOTE_BUTTON_COLOR),
+        }
+    }
+}
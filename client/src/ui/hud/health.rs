@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use shared::players::{Player, MAX_PLAYER_HEALTH};
+
+use crate::player::CurrentPlayerMarker;
+use crate::ui::hud::theme::HudTheme;
+use crate::GameState;
+
+#[derive(Component)]
+pub struct HealthBar;
+
+#[derive(Component)]
+pub struct HealthBarFill;
+
+const HEALTH_BAR_WIDTH: f32 = 200.;
+const HEALTH_BAR_HEIGHT: f32 = 10.;
+
+/// Bar above the hotbar showing the health of the player
+pub fn setup_health_bar(mut commands: Commands, theme: Res<HudTheme>) {
+    commands
+        .spawn((
+            Name::new("HealthBar"),
+            StateScoped(GameState::Game),
+            HealthBar,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(135.),
+                width: Val::Px(HEALTH_BAR_WIDTH),
+                height: Val::Px(HEALTH_BAR_HEIGHT),
+                margin: UiRect::horizontal(Val::Auto),
+                left: Val::Px(0.),
+                right: Val::Px(0.),
+                ..default()
+            },
+            BackgroundColor(theme.bar_background),
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                HealthBarFill,
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                BackgroundColor(theme.health_full),
+            ));
+        });
+}
+
+pub fn health_bar_system(
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    mut bar: Single<&mut BackgroundColor, (With<HealthBar>, Without<HealthBarFill>)>,
+    fill: Single<(&mut Node, &mut BackgroundColor), With<HealthBarFill>>,
+    theme: Res<HudTheme>,
+) {
+    let Ok(player) = player.single() else {
+        return;
+    };
+    let fraction = (player.health / MAX_PLAYER_HEALTH).clamp(0.0, 1.0);
+    let (mut node, mut color) = fill.into_inner();
+
+    node.width = Val::Percent(fraction * 100.);
+    color.0 = theme.health_color(fraction);
+    if theme.is_changed() {
+        bar.0 = theme.bar_background;
+    }
+}
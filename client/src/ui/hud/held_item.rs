@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use shared::{players::Player, world::ItemId, DAY_DURATION_IN_TICKS, WORLD_SPAWN_POSITION};
+
+use crate::camera::CameraController;
+use crate::player::CurrentPlayerMarker;
+use crate::ui::hud::hotbar::Hotbar;
+use crate::world::time::ClientTime;
+use crate::GameState;
+
+/// Panel above the hotbar showing what the utility item in hand tells
+#[derive(Component)]
+pub struct HeldItemWidget;
+
+/// Turned so that its needle points to the world spawn
+#[derive(Component)]
+pub struct CompassDial;
+
+#[derive(Component)]
+pub struct HeldItemText;
+
+const COMPASS_SIZE: f32 = 48.;
+const HELD_ITEM_FONT_SIZE: f32 = 16.;
+/// Hour of the day when the time is 0, the sun rises at the start of the cycle
+const FIRST_HOUR: u64 = 6;
+
+pub fn setup_held_item_widget(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("HeldItem"),
+            StateScoped(GameState::Game),
+            HeldItemWidget,
+            Node {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(140.),
+                width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.),
+                ..default()
+            },
+        ))
+        .with_children(|widget| {
+            widget
+                .spawn((
+                    CompassDial,
+                    Node {
+                        width: Val::Px(COMPASS_SIZE),
+                        height: Val::Px(COMPASS_SIZE),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BorderRadius::MAX,
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.6)),
+                ))
+                .with_children(|dial| {
+                    // Half a needle, from the center to the top of the dial
+                    dial.spawn((
+                        Node {
+                            width: Val::Px(4.),
+                            height: Val::Px(COMPASS_SIZE / 2.),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.85, 0.15, 0.1)),
+                    ));
+                });
+            widget.spawn((
+                HeldItemText,
+                Text::new(""),
+                TextFont {
+                    font_size: HELD_ITEM_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    padding: UiRect::axes(Val::Px(6.), Val::Px(2.)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.6)),
+            ));
+        });
+}
+
+/// Hour and minute of the day at `time`
+fn time_of_day(time: u64) -> (u64, u64) {
+    let minutes = (time % DAY_DURATION_IN_TICKS) * 24 * 60 / DAY_DURATION_IN_TICKS;
+    ((FIRST_HOUR + minutes / 60) % 24, minutes % 60)
+}
+
+/// Clockwise angle from where the camera looks to the world spawn, seen from above
+fn angle_to_spawn(camera: &GlobalTransform, position: Vec3) -> f32 {
+    let to_spawn = WORLD_SPAWN_POSITION - position;
+    let forward = camera.forward();
+    let right = camera.right();
+    to_spawn
+        .dot(*right)
+        .atan2(to_spawn.with_y(0.).dot(forward.with_y(0.)))
+}
+
+/// A compass points to the spawn, a clock reads the time and a map the coordinates of the player
+pub fn held_item_widget_system(
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    hotbar: Query<&Hotbar>,
+    camera: Query<&GlobalTransform, With<CameraController>>,
+    time: Res<ClientTime>,
+    mut widget: Query<
+        &mut Node,
+        (
+            With<HeldItemWidget>,
+            Without<CompassDial>,
+            Without<HeldItemText>,
+        ),
+    >,
+    mut dial: Query<(&mut Node, &mut Transform), (With<CompassDial>, Without<HeldItemText>)>,
+    mut text: Query<(&mut Node, &mut Text), With<HeldItemText>>,
+) {
+    let (Ok(player), Ok(hotbar), Ok(mut widget)) =
+        (player.single(), hotbar.single(), widget.single_mut())
+    else {
+        return;
+    };
+    let held = player
+        .inventory
+        .get(hotbar.selected)
+        .map(|stack| stack.item_id);
+
+    let shown = matches!(held, Some(ItemId::Compass | ItemId::Clock | ItemId::Map));
+    widget.display = if shown { Display::Flex } else { Display::None };
+    if !shown {
+        return;
+    }
+
+    if let Ok((mut node, mut transform)) = dial.single_mut() {
+        node.display = Display::None;
+        if let (Some(ItemId::Compass), Ok(camera)) = (held, camera.single()) {
+            transform.rotation = Quat::from_rotation_z(angle_to_spawn(camera, player.position));
+            node.display = Display::Flex;
+        }
+    }
+
+    if let Ok((mut node, mut text)) = text.single_mut() {
+        let line = match held {
+            Some(ItemId::Clock) => {
+                let (hour, minute) = time_of_day(time.0);
+                Some(format!("{hour:02}:{minute:02}"))
+            }
+            Some(ItemId::Map) => {
+                let position = player.position.floor();
+                Some(format!(
+                    "X: {} Y: {} Z: {}",
+                    position.x, position.y, position.z
+                ))
+            }
+            _ => None,
+        };
+        node.display = Display::None;
+        if let Some(line) = line {
+            text.0 = line;
+            node.display = Display::Flex;
+        }
+    }
+}
@@ -1,15 +1,42 @@
-use bevy::{prelude::*, ui::FocusPolicy};
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    ui::FocusPolicy,
+};
 
 use crate::{
     constants::{HOTBAR_BORDER, HOTBAR_CELL_SIZE, HOTBAR_PADDING, MAX_HOTBAR_SLOTS},
-    ui::hud::InventoryCell,
+    input::{data::GameAction, keyboard::is_action_just_pressed},
+    ui::hud::{InventoryCell, UIMode},
     world::MaterialResource,
-    GameState,
+    GameState, KeyMap,
 };
 
+/// Pixels of a touchpad scroll moving the selection by one slot
+const SCROLL_PIXELS_PER_SLOT: f32 = 20.;
+/// Length of the pop of the newly selected cell
+const SWAP_ANIMATION_SECS: f32 = 0.15;
+/// Growth of the newly selected cell at the start of its pop
+const SWAP_ANIMATION_SCALE: f32 = 0.2;
+
 #[derive(Component)]
 pub struct Hotbar {
     pub selected: u32,
+    /// Scrolling not turned into slots yet, so that slow touchpad scrolls still move the selection
+    scroll: f32,
+    /// Time left to the pop of the selected cell
+    swap_animation: f32,
+}
+
+impl Hotbar {
+    /// Selects the slot, wrapping around the ends of the hotbar
+    pub fn select(&mut self, slot: i32) {
+        let slot = slot.rem_euclid(MAX_HOTBAR_SLOTS as i32) as u32;
+        if slot != self.selected {
+            self.selected = slot;
+            self.swap_animation = SWAP_ANIMATION_SECS;
+        }
+    }
 }
 
 pub fn setup_hotbar(mut commands: Commands, materials_resource: Res<MaterialResource>) {
@@ -17,7 +44,11 @@ pub fn setup_hotbar(mut commands: Commands, materials_resource: Res<MaterialReso
 
     commands
         .spawn((
-            Hotbar { selected: 0 },
+            Hotbar {
+                selected: 0,
+                scroll: 0.,
+                swap_animation: 0.,
+            },
             StateScoped(GameState::Game),
             (
                 Node {
@@ -92,3 +123,60 @@ pub fn setup_hotbar(mut commands: Commands, materials_resource: Res<MaterialReso
             }
         });
 }
+
+/// Cycles the selection with the scroll wheel and selects slots with their keys, while no UI is open
+pub fn hotbar_selection_system(
+    mut hotbar: Single<&mut Hotbar>,
+    mut scroll: EventReader<MouseWheel>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    ui_mode: Res<UIMode>,
+) {
+    if *ui_mode != UIMode::Closed {
+        scroll.clear();
+        hotbar.scroll = 0.;
+        return;
+    }
+
+    for event in scroll.read() {
+        hotbar.scroll -= match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / SCROLL_PIXELS_PER_SLOT,
+        };
+    }
+    let slots = hotbar.scroll.trunc();
+    if slots != 0. {
+        hotbar.scroll -= slots;
+        let selected = hotbar.selected as i32 + slots as i32;
+        hotbar.select(selected);
+    }
+
+    if let Some(slot) = GameAction::HOTBAR_SLOTS
+        .iter()
+        .position(|action| is_action_just_pressed(*action, &keyboard_input, &key_map))
+    {
+        hotbar.select(slot as i32);
+    }
+}
+
+/// Pops the newly selected cell of the hotbar
+pub fn hotbar_swap_animation_system(
+    hotbar: Single<(&mut Hotbar, &Children)>,
+    mut cells: Query<(&InventoryCell, &mut Transform)>,
+    time: Res<Time>,
+) {
+    let (mut hotbar, children) = hotbar.into_inner();
+    hotbar.swap_animation = (hotbar.swap_animation - time.delta_secs()).max(0.);
+    let pop = 1. + SWAP_ANIMATION_SCALE * hotbar.swap_animation / SWAP_ANIMATION_SECS;
+
+    for child in children.iter() {
+        let Ok((cell, mut transform)) = cells.get_mut(child) else {
+            continue;
+        };
+        transform.scale = if cell.id == hotbar.selected {
+            Vec3::splat(pop)
+        } else {
+            Vec3::ONE
+        };
+    }
+}
@@ -9,11 +9,10 @@ use crate::KeyMap;
 use bevy::color::Color;
 use bevy::ecs::hierarchy::Children;
 use bevy::image::TextureAtlas;
-use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 use bevy::input::ButtonInput;
 use bevy::prelude::{
-    EventReader, ImageNode, KeyCode, MouseButton, Node, Query, Res, ResMut, Text, Val, Visibility,
-    Window, With, Without,
+    ImageNode, KeyCode, MouseButton, Node, Query, Res, ResMut, Text, Val, Visibility, Window, With,
+    Without,
 };
 use bevy::ui::{BorderColor, Interaction};
 use bevy::window::PrimaryWindow;
@@ -27,7 +26,7 @@ pub fn render_inventory_hotbar(
         mut cursor_query,
         mut visibility_query,
         window_query,
-        mut hotbar_query,
+        hotbar_query,
     ): (
         Query<&mut Text>,
         Query<(&mut ImageNode, &mut Visibility), Without<InventoryRoot>>,
@@ -35,7 +34,7 @@ pub fn render_inventory_hotbar(
         Query<(&Interaction, &mut BorderColor, &InventoryCell, &Children), With<InventoryCell>>,
         Query<&mut Visibility, With<InventoryRoot>>,
         Query<&Window, With<PrimaryWindow>>,
-        Query<&mut Hotbar>,
+        Query<&Hotbar>,
     ),
     (keyboard_input, mouse_input, key_map, mut inventory, materials, ui_mode): (
         Res<ButtonInput<KeyCode>>,
@@ -45,7 +44,6 @@ pub fn render_inventory_hotbar(
         Res<MaterialResource>,
         Res<UIMode>,
     ),
-    mut scroll: EventReader<MouseWheel>,
 ) {
     let mut vis = visibility_query.single_mut().unwrap();
 
@@ -62,23 +60,6 @@ pub fn render_inventory_hotbar(
     let mut txt = text_query.get_mut(children[0]).unwrap();
     let (mut stack_img, mut stack_vis) = atlas_query.get_mut(children[1]).unwrap();
 
-    // Change selected stack via scrolling
-    let mut stack_scrolling = hotbar_query.single().unwrap().selected as i32;
-
-    for sc in scroll.read() {
-        match sc.unit {
-            MouseScrollUnit::Line => {
-                stack_scrolling -= sc.y as i32;
-            }
-            MouseScrollUnit::Pixel => {
-                stack_scrolling -= sc.y as i32 / 20;
-            }
-        }
-    }
-
-    // Add scrolling
-    hotbar_query.single_mut().unwrap().selected =
-        stack_scrolling.rem_euclid(MAX_HOTBAR_SLOTS as i32) as u32;
     if let Some(atlas) = &mut stack_img.texture_atlas {
         update_inventory_cell(
             &floating_stack.items,
@@ -19,7 +19,7 @@ pub fn remove_item_floating_stack(floating_stack: &mut FloatingStack, nb: u32) -
 }
 
 /// Adds `nb` items to the floating stack\
-/// Cannot go higher than `MAX_ITEM_STACK` items\
+/// Cannot go higher than the max stack size of the item, nor mix different items\
 /// Parameter `item_type` will **ONLY BE USED** if no items are present in the floating stack\
 /// Returns number of items _actually_ added
 pub fn add_item_floating_stack(
@@ -31,10 +31,12 @@ pub fn add_item_floating_stack(
     if nb == 0 {
         0
     } else if let Some(mut item) = floating_stack.items {
-        if nb + item.nb > item.item_id.get_max_stack() {
-            nb = item.item_id.get_max_stack() - item.nb;
+        if item.item_id != item_id {
+            return 0;
         }
+        nb = nb.min(item.item_id.get_max_stack().saturating_sub(item.nb));
         item.nb += nb;
+        floating_stack.items = Some(item);
         nb
     } else {
         if nb > item_id.get_max_stack() {
@@ -48,3 +50,61 @@ pub fn add_item_floating_stack(
         nb
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn added_items_are_kept_in_the_floating_stack() {
+        let mut floating_stack = FloatingStack {
+            items: Some(ItemStack::new(ItemId::Dirt, 10)),
+        };
+
+        // The items used to be added to a copy of the stack, and lost
+        assert_eq!(
+            add_item_floating_stack(
+                &mut floating_stack,
+                5,
+                ItemId::Dirt,
+                ItemId::Dirt.get_default_type()
+            ),
+            5
+        );
+        assert_eq!(floating_stack.items, Some(ItemStack::new(ItemId::Dirt, 15)));
+    }
+
+    #[test]
+    fn floating_stack_stops_at_the_max_stack_size() {
+        let mut floating_stack = FloatingStack { items: None };
+        let item_type = ItemId::Dirt.get_default_type();
+
+        assert_eq!(
+            add_item_floating_stack(&mut floating_stack, 100, ItemId::Dirt, item_type),
+            64
+        );
+        assert_eq!(
+            add_item_floating_stack(&mut floating_stack, 1, ItemId::Dirt, item_type),
+            0
+        );
+        assert_eq!(floating_stack.items, Some(ItemStack::new(ItemId::Dirt, 64)));
+    }
+
+    #[test]
+    fn floating_stack_keeps_other_items() {
+        let mut floating_stack = FloatingStack {
+            items: Some(ItemStack::new(ItemId::Dirt, 10)),
+        };
+
+        assert_eq!(
+            add_item_floating_stack(
+                &mut floating_stack,
+                5,
+                ItemId::Sand,
+                ItemId::Sand.get_default_type()
+            ),
+            0
+        );
+        assert_eq!(floating_stack.items, Some(ItemStack::new(ItemId::Dirt, 10)));
+    }
+}
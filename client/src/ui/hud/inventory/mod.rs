@@ -62,9 +62,11 @@ pub fn set_ui_mode(
 mod display;
 pub mod items;
 mod setup;
+mod tooltip;
 
 use bevy_simple_text_input::TextInputInactive;
 pub use display::*;
 use items::*;
 pub use setup::*;
 use shared::world::ItemStack;
+pub use tooltip::*;
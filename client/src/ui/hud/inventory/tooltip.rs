@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use shared::players::Inventory;
+
+use crate::ui::hud::{FloatingStack, InventoryCell, InventoryRoot};
+use crate::ui::lang::Localization;
+use crate::GameState;
+
+/// Name of the item under the cursor in the open inventory
+#[derive(Component)]
+pub struct ItemTooltip;
+
+/// Gap between the cursor and the tooltip, in pixels
+const TOOLTIP_OFFSET: f32 = 14.;
+
+pub fn setup_item_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Name::new("ItemTooltip"),
+        StateScoped(GameState::Game),
+        ItemTooltip,
+        Text::new(""),
+        TextFont::from_font_size(15.),
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            padding: UiRect::axes(Val::Px(6.), Val::Px(3.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.1, 0.05, 0.15, 0.9)),
+        GlobalZIndex(3),
+        Visibility::Hidden,
+    ));
+}
+
+/// Shows the localized name of the hovered stack, unless a stack is being moved
+pub fn item_tooltip_system(
+    tooltip: Single<(&mut Text, &mut Node, &mut Visibility), With<ItemTooltip>>,
+    inventory_root: Query<&Visibility, (With<InventoryRoot>, Without<ItemTooltip>)>,
+    cells: Query<(&Interaction, &InventoryCell)>,
+    floating_stack: Query<&FloatingStack>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    inventory: Res<Inventory>,
+    localization: Res<Localization>,
+) {
+    let (mut text, mut node, mut visibility) = tooltip.into_inner();
+
+    let inventory_open = inventory_root
+        .single()
+        .is_ok_and(|vis| *vis == Visibility::Visible);
+    let moving_stack = floating_stack.iter().any(|stack| stack.items.is_some());
+    let hovered = cells
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Hovered)
+        .and_then(|(_, cell)| inventory.inner.get(&cell.id));
+    let cursor = window.single().ok().and_then(Window::cursor_position);
+
+    let (Some(stack), Some(cursor), true, false) = (hovered, cursor, inventory_open, moving_stack)
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let name = localization.item_name(stack.item_id);
+    if text.0 != name {
+        text.0 = name;
+    }
+    node.left = Val::Px(cursor.x + TOOLTIP_OFFSET);
+    node.top = Val::Px(cursor.y + TOOLTIP_OFFSET);
+    *visibility = Visibility::Visible;
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use shared::messages::PlayerDeathEvent;
+
+use crate::ui::lang::Localization;
+use crate::GameState;
+
+#[derive(Component)]
+pub struct KillFeedRoot;
+
+#[derive(Component)]
+pub struct KillFeedEntry {
+    pub shown_at: f32,
+}
+
+const KILL_FEED_DURATION: f32 = 5.0;
+const KILL_FEED_MAX_ENTRIES: usize = 5;
+const KILL_FEED_FONT_SIZE: f32 = 16.;
+
+pub fn setup_kill_feed(mut commands: Commands) {
+    commands.spawn((
+        Name::new("KillFeed"),
+        StateScoped(GameState::Game),
+        KillFeedRoot,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            right: Val::Px(10.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::End,
+            row_gap: Val::Px(4.),
+            ..default()
+        },
+    ));
+}
+
+/// Shows a short line for each death in the top right corner, oldest entries fade away
+pub fn kill_feed_system(
+    mut commands: Commands,
+    mut events: EventReader<PlayerDeathEvent>,
+    root: Single<(Entity, Option<&Children>), With<KillFeedRoot>>,
+    entries: Query<&KillFeedEntry>,
+    time: Res<Time>,
+    localization: Res<Localization>,
+) {
+    let (root, children) = root.into_inner();
+    let now = time.elapsed_secs();
+
+    let mut alive: Vec<Entity> = Vec::new();
+    for child in children.into_iter().flatten() {
+        match entries.get(*child) {
+            Ok(entry) if now - entry.shown_at < KILL_FEED_DURATION => alive.push(*child),
+            _ => commands.entity(*child).despawn(),
+        }
+    }
+
+    for event in events.read() {
+        let text = match event.source.attacker_name() {
+            Some(attacker) => format!("{} > {}", localization.get(&attacker), event.victim_name),
+            None => format!(
+                "{} ({})",
+                event.victim_name,
+                localization.get(&event.source.cause_key())
+            ),
+        };
+
+        let entry = commands
+            .spawn((
+                KillFeedEntry { shown_at: now },
+                Node {
+                    padding: UiRect::axes(Val::Px(6.), Val::Px(2.)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0., 0., 0., 0.5)),
+                children![(
+                    Text::new(text),
+                    TextFont {
+                        font_size: KILL_FEED_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )],
+            ))
+            .id();
+        commands.entity(root).add_child(entry);
+        alive.push(entry);
+    }
+
+    // Drops the oldest entries when too many players die at once
+    let overflow = alive.len().saturating_sub(KILL_FEED_MAX_ENTRIES);
+    for entity in alive.into_iter().take(overflow) {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,7 +1,17 @@
 pub mod chat;
+pub mod combat;
 pub mod debug;
+pub mod effects;
+pub mod emote_menu;
+pub mod health;
+pub mod held_item;
 pub mod hotbar;
 pub mod inventory;
+pub mod kill_feed;
+pub mod player_list;
 pub mod reticle;
+pub mod theme;
+pub mod toasts;
+pub mod trading;
 
 pub use inventory::*;
@@ -0,0 +1,115 @@
+use crate::input::{data::GameAction, keyboard::is_action_pressed};
+use crate::player::AfkMarker;
+use crate::ui::hud::theme::HudTheme;
+use crate::KeyMap;
+use bevy::prelude::*;
+use shared::players::{Player, Teams};
+
+use crate::GameState;
+
+#[derive(Component)]
+pub struct PlayerListRoot;
+
+const PLAYER_LIST_FONT_SIZE: f32 = 18.;
+
+pub fn setup_player_list(mut commands: Commands) {
+    commands.spawn((
+        Name::new("PlayerList"),
+        StateScoped(GameState::Game),
+        PlayerListRoot,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.),
+            left: Val::Percent(40.),
+            width: Val::Percent(20.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+        Visibility::Hidden,
+    ));
+}
+
+/// Shows the connected players, sorted by team, while the player list key is held
+pub fn player_list_system(
+    mut commands: Commands,
+    root: Single<(Entity, &mut Visibility), With<PlayerListRoot>>,
+    players: Query<(&Player, Has<AfkMarker>)>,
+    new_players: Query<(), Added<Player>>,
+    mut removed_players: RemovedComponents<Player>,
+    new_afk: Query<(), Added<AfkMarker>>,
+    mut back_from_afk: RemovedComponents<AfkMarker>,
+    teams: Res<Teams>,
+    theme: Res<HudTheme>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    assets: Res<AssetServer>,
+    mut was_shown: Local<bool>,
+) {
+    let (root, mut visibility) = root.into_inner();
+    let shown = is_action_pressed(GameAction::ShowPlayerList, &keyboard_input, &key_map);
+
+    if !shown {
+        *visibility = Visibility::Hidden;
+        *was_shown = false;
+        return;
+    }
+
+    // Only rebuilt when opened or when the teams or players change
+    let players_left = removed_players.read().count() > 0;
+    let afk_changed = !new_afk.is_empty() || back_from_afk.read().count() > 0;
+    if *was_shown
+        && !teams.is_changed()
+        && !theme.is_changed()
+        && new_players.is_empty()
+        && !players_left
+        && !afk_changed
+    {
+        return;
+    }
+    *was_shown = true;
+    *visibility = Visibility::Visible;
+
+    let mut entries: Vec<(String, String, Color)> = players
+        .iter()
+        .map(|(player, afk)| {
+            let team = teams.get_player_team(player.id);
+            let team_name = team.map(|t| t.name.clone()).unwrap_or_default();
+            let mut label = match team {
+                Some(team) => format!(
+                    "{}[{}] {}",
+                    theme.team_pattern(team.color),
+                    team.name,
+                    player.name
+                ),
+                None => player.name.clone(),
+            };
+            if afk {
+                label.push_str(" (AFK)");
+            }
+            let color = theme
+                .player_team_color(&teams, player.id)
+                .unwrap_or(theme.no_team);
+            (team_name, label, color)
+        })
+        .collect();
+    entries.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+    let font = assets.load("fonts/FiraMono-Medium.ttf");
+    commands.entity(root).despawn_related::<Children>();
+    commands.entity(root).with_children(|list| {
+        for (_, label, color) in entries {
+            list.spawn((
+                Text::new(label),
+                TextFont {
+                    font: font.clone(),
+                    font_size: PLAYER_LIST_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(color),
+            ));
+        }
+    });
+}
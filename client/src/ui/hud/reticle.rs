@@ -1,48 +1,69 @@
 use bevy::prelude::*;
 
+use crate::ui::hud::theme::HudTheme;
 use crate::GameState;
 
+#[derive(Component)]
+pub struct Reticle;
+
 pub fn spawn_reticle(mut commands: Commands) {
-    // Main container for the reticle
-    commands
-        .spawn((
-            StateScoped(GameState::Game), // Link the reticle to the Game state
-            Node {
-                position_type: PositionType::Absolute,
-                margin: UiRect {
-                    left: Val::Auto,
-                    right: Val::Auto,
-                    top: Val::Auto,
-                    bottom: Val::Auto,
-                },
-                ..default()
+    // Main container for the reticle, its lines are drawn by `update_reticle_system`
+    commands.spawn((
+        Reticle,
+        StateScoped(GameState::Game), // Link the reticle to the Game state
+        Node {
+            position_type: PositionType::Absolute,
+            margin: UiRect {
+                left: Val::Auto,
+                right: Val::Auto,
+                top: Val::Auto,
+                bottom: Val::Auto,
             },
-        ))
+            ..default()
+        },
+    ));
+}
+
+/// Redraws the reticle with the size and color of the theme
+pub fn update_reticle_system(
+    mut commands: Commands,
+    reticle: Single<Entity, With<Reticle>>,
+    new_reticle: Query<(), Added<Reticle>>,
+    theme: Res<HudTheme>,
+) {
+    if !theme.is_changed() && new_reticle.is_empty() {
+        return;
+    }
+
+    let (length, thickness) = theme.crosshair_size.dimensions();
+    commands
+        .entity(*reticle)
+        .despawn_related::<Children>()
         .with_children(|parent| {
             // Horizontal line (horizontal bar of the cross)
             parent.spawn((
                 Node {
                     position_type: PositionType::Absolute,
-                    width: Val::Px(20.0),
-                    height: Val::Px(2.0),
-                    left: Val::Px(-10.0),
-                    top: Val::Px(-1.0),
+                    width: Val::Px(length),
+                    height: Val::Px(thickness),
+                    left: Val::Px(-length / 2.0),
+                    top: Val::Px(-thickness / 2.0),
                     ..Default::default()
                 },
-                BackgroundColor(Color::WHITE),
+                BackgroundColor(theme.crosshair),
             ));
 
             // Vertical line (vertical bar of the cross)
             parent.spawn((
                 Node {
                     position_type: PositionType::Absolute,
-                    width: Val::Px(2.0),
-                    height: Val::Px(20.0),
-                    left: Val::Px(-1.0),
-                    top: Val::Px(-10.0),
+                    width: Val::Px(thickness),
+                    height: Val::Px(length),
+                    left: Val::Px(-thickness / 2.0),
+                    top: Val::Px(-length / 2.0),
                     ..Default::default()
                 },
-                BackgroundColor(Color::WHITE),
+                BackgroundColor(theme.crosshair),
             ));
         });
 }
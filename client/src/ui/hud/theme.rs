@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use shared::players::{TeamColor, Teams};
+
+use crate::ui::accessibility::{AccessibilitySettings, CrosshairSize, UiPalette};
+
+/// Colors and sizes used by the HUD, derived from the accessibility settings
+/// HUD code reads them from here instead of hard-coding its own
+#[derive(Resource, Debug, Clone)]
+pub struct HudTheme {
+    pub palette: UiPalette,
+    pub health_full: Color,
+    pub health_low: Color,
+    pub bar_background: Color,
+    pub chat_background: Color,
+    pub chat_text: Color,
+    pub crosshair: Color,
+    pub crosshair_size: CrosshairSize,
+    /// Color of players without a team in the player list
+    pub no_team: Color,
+}
+
+impl Default for HudTheme {
+    fn default() -> Self {
+        Self::from_settings(&AccessibilitySettings::default())
+    }
+}
+
+impl HudTheme {
+    pub fn from_settings(settings: &AccessibilitySettings) -> Self {
+        let palette = settings.palette;
+        let (health_full, health_low) = match palette {
+            UiPalette::Default => (Color::srgb(0.2, 0.8, 0.2), Color::srgb(0.9, 0.15, 0.15)),
+            UiPalette::Deuteranopia => (Color::srgb_u8(0, 114, 178), Color::srgb_u8(230, 159, 0)),
+            UiPalette::Tritanopia => (Color::srgb_u8(0, 158, 115), Color::srgb_u8(213, 94, 0)),
+            UiPalette::HighContrast => (Color::WHITE, Color::srgb(1.0, 1.0, 0.0)),
+        };
+        let high_contrast = palette == UiPalette::HighContrast;
+
+        Self {
+            palette,
+            health_full,
+            health_low,
+            bar_background: if high_contrast {
+                Color::BLACK
+            } else {
+                Color::srgba(0., 0., 0., 0.5)
+            },
+            chat_background: if high_contrast {
+                Color::BLACK
+            } else {
+                Color::srgba(0., 0., 0., 0.6)
+            },
+            chat_text: if high_contrast {
+                Color::srgb(1.0, 1.0, 0.0)
+            } else {
+                Color::WHITE
+            },
+            crosshair: if high_contrast {
+                Color::srgb(1.0, 1.0, 0.0)
+            } else {
+                Color::WHITE
+            },
+            crosshair_size: settings.crosshair,
+            no_team: Color::WHITE,
+        }
+    }
+
+    /// Health bar color, going from the full to the low color as health drops
+    pub fn health_color(&self, fraction: f32) -> Color {
+        self.health_low
+            .mix(&self.health_full, fraction.clamp(0.0, 1.0))
+    }
+
+    pub fn team_color(&self, color: TeamColor) -> Color {
+        let [r, g, b] = match self.palette {
+            UiPalette::Default | UiPalette::HighContrast => color.rgb(),
+            // Okabe-Ito colors, which stay apart for all common kinds of color blindness
+            UiPalette::Deuteranopia | UiPalette::Tritanopia => match color {
+                TeamColor::White => [255, 255, 255],
+                TeamColor::Red => [213, 94, 0],
+                TeamColor::Orange => [230, 159, 0],
+                TeamColor::Yellow => [240, 228, 66],
+                TeamColor::Green => [0, 158, 115],
+                TeamColor::Aqua => [86, 180, 233],
+                TeamColor::Blue => [0, 114, 178],
+                TeamColor::Purple => [204, 121, 167],
+                TeamColor::Pink => [245, 190, 215],
+                TeamColor::Gray => [150, 150, 150],
+            },
+        };
+        Color::srgb_u8(r, g, b)
+    }
+
+    /// Symbol shown next to the names of a team, so that teams do not only differ by color
+    /// Empty with the default palette
+    pub fn team_pattern(&self, color: TeamColor) -> &'static str {
+        if self.palette == UiPalette::Default {
+            return "";
+        }
+        match color {
+            TeamColor::White => "o ",
+            TeamColor::Red => "x ",
+            TeamColor::Orange => "+ ",
+            TeamColor::Yellow => "* ",
+            TeamColor::Green => "# ",
+            TeamColor::Aqua => "~ ",
+            TeamColor::Blue => "= ",
+            TeamColor::Purple => "% ",
+            TeamColor::Pink => "& ",
+            TeamColor::Gray => "- ",
+        }
+    }
+
+    /// Color of a player's name tag and player list entry, given by their team
+    pub fn player_team_color(&self, teams: &Teams, player_id: u64) -> Option<Color> {
+        teams
+            .get_player_team(player_id)
+            .map(|team| self.team_color(team.color))
+    }
+
+    /// Team symbol of a player, empty if they have no team
+    pub fn player_team_pattern(&self, teams: &Teams, player_id: u64) -> &'static str {
+        teams
+            .get_player_team(player_id)
+            .map_or("", |team| self.team_pattern(team.color))
+    }
+}
+
+/// Rebuilds the theme and rescales the interface when the accessibility settings change
+pub fn update_hud_theme_system(
+    settings: Res<AccessibilitySettings>,
+    mut theme: ResMut<HudTheme>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    *theme = HudTheme::from_settings(&settings);
+    ui_scale.0 = settings.hud_scale;
+}
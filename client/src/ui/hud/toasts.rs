@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::messages::{ClientToServerMessage, Hint, RecipeUnlockEvent};
+use shared::world::GameRules;
+
+use crate::network::SendGameMessageExtension;
+use crate::ui::hud::InventoryRoot;
+use crate::GameState;
+
+/// A short notification shown in the top left corner
+#[derive(Event, Debug)]
+pub struct ToastEvent(pub String);
+
+#[derive(Component)]
+pub struct ToastRoot;
+
+#[derive(Component)]
+pub struct Toast {
+    pub shown_at: f32,
+}
+
+const TOAST_DURATION: f32 = 4.0;
+const TOAST_MAX_ENTRIES: usize = 3;
+const TOAST_FONT_SIZE: f32 = 16.;
+
+pub fn setup_toasts(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Toasts"),
+        StateScoped(GameState::Game),
+        ToastRoot,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(10.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.),
+            ..default()
+        },
+    ));
+}
+
+pub fn recipe_toasts_system(
+    mut events: EventReader<RecipeUnlockEvent>,
+    mut ev_toast: EventWriter<ToastEvent>,
+) {
+    for event in events.read() {
+        let names: Vec<String> = event
+            .recipes
+            .iter()
+            .map(|recipe| format!("{recipe:?}"))
+            .collect();
+        let text = if event.unlocked {
+            format!("Recipe unlocked: {}", names.join(", "))
+        } else {
+            format!("Recipe removed: {}", names.join(", "))
+        };
+        ev_toast.write(ToastEvent(text));
+    }
+}
+
+/// Asks the server for the crafting hint when the inventory is opened, it is only shown the first time
+pub fn crafting_hint_system(
+    inventory: Query<&Visibility, (With<InventoryRoot>, Changed<Visibility>)>,
+    game_rules: Res<GameRules>,
+    mut client: ResMut<RenetClient>,
+) {
+    if !game_rules.show_hints {
+        return;
+    }
+    if inventory
+        .iter()
+        .any(|visibility| *visibility == Visibility::Visible)
+    {
+        client.send_game_message(ClientToServerMessage::Hint(Hint::Crafting));
+    }
+}
+
+/// Stacks the toasts under each other, they disappear after a few seconds
+pub fn toast_system(
+    mut commands: Commands,
+    mut events: EventReader<ToastEvent>,
+    root: Single<(Entity, Option<&Children>), With<ToastRoot>>,
+    toasts: Query<&Toast>,
+    time: Res<Time>,
+) {
+    let (root, children) = root.into_inner();
+    let now = time.elapsed_secs();
+
+    let mut alive: Vec<Entity> = Vec::new();
+    for child in children.into_iter().flatten() {
+        match toasts.get(*child) {
+            Ok(toast) if now - toast.shown_at < TOAST_DURATION => alive.push(*child),
+            _ => commands.entity(*child).despawn(),
+        }
+    }
+
+    for event in events.read() {
+        let toast = commands
+            .spawn((
+                Toast { shown_at: now },
+                Node {
+                    padding: UiRect::axes(Val::Px(8.), Val::Px(4.)),
+                    border: UiRect::all(Val::Px(1.)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.8)),
+                BorderColor(Color::srgb(0.9, 0.8, 0.3)),
+                children![(
+                    Text::new(event.0.clone()),
+                    TextFont {
+                        font_size: TOAST_FONT_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )],
+            ))
+            .id();
+        commands.entity(root).add_child(toast);
+        alive.push(toast);
+    }
+
+    let overflow = alive.len().saturating_sub(TOAST_MAX_ENTRIES);
+    for entity in alive.into_iter().take(overflow) {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use shared::{
+    messages::{
+        mob::{TradeOffersEvent, TradeRequest},
+        ClientToServerMessage,
+    },
+    world::{MobId, TradeOffer},
+};
+
+use crate::input::{data::GameAction, keyboard::is_action_just_pressed};
+use crate::network::SendGameMessageExtension;
+use crate::ui::hud::UiDialog;
+use crate::ui::lang::Localization;
+use crate::GameState;
+use crate::KeyMap;
+
+/// Window listing the trades of a trader, opened by the server when the player interacts with one
+#[derive(Component, Default)]
+pub struct TradingDialog {
+    pub mob_id: Option<MobId>,
+}
+
+#[derive(Component)]
+pub struct TradeButton {
+    pub mob_id: MobId,
+    pub offer: usize,
+}
+
+const TRADE_BUTTON_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.9);
+const TRADE_BUTTON_HOVER_COLOR: Color = Color::srgba(0.4, 0.4, 0.4, 0.9);
+const TRADE_SOLD_OUT_COLOR: Color = Color::srgba(0.35, 0.1, 0.1, 0.9);
+const TRADE_FONT_SIZE: f32 = 18.;
+
+pub fn setup_trading_dialog(mut commands: Commands) {
+    commands.spawn((
+        Name::new("TradingDialog"),
+        StateScoped(GameState::Game),
+        TradingDialog::default(),
+        UiDialog,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(30.),
+            right: Val::Percent(30.),
+            top: Val::Percent(20.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0., 0., 0., 0.7)),
+        GlobalZIndex(2),
+        Visibility::Hidden,
+    ));
+}
+
+fn offer_label(offer: &TradeOffer, localization: &Localization) -> String {
+    let (cost_item, cost_nb) = offer.trade.cost;
+    let (result_item, result_nb) = offer.trade.result;
+    let availability = if offer.is_sold_out() {
+        localization.get("trade.sold_out").to_string()
+    } else {
+        let left = (offer.trade.max_uses - offer.uses).to_string();
+        localization.format("trade.uses_left", &[&left])
+    };
+    format!(
+        "{cost_nb} {} -> {result_nb} {} ({availability})",
+        localization.item_name(cost_item),
+        localization.item_name(result_item)
+    )
+}
+
+/// Fills the window with the offers sent by the server and shows it
+pub fn open_trading_dialog_system(
+    mut commands: Commands,
+    mut events: EventReader<TradeOffersEvent>,
+    dialog: Single<(Entity, &mut Visibility, &mut TradingDialog)>,
+    localization: Res<Localization>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+    let (entity, mut visibility, mut dialog) = dialog.into_inner();
+
+    dialog.mob_id = Some(event.mob_id);
+    *visibility = Visibility::Visible;
+
+    commands
+        .entity(entity)
+        .despawn_related::<Children>()
+        .with_children(|root| {
+            root.spawn((
+                Text::new("Trades"),
+                TextFont {
+                    font_size: TRADE_FONT_SIZE + 4.,
+                    ..default()
+                },
+            ));
+            for (index, offer) in event.offers.iter().enumerate() {
+                root.spawn((
+                    Button,
+                    TradeButton {
+                        mob_id: event.mob_id,
+                        offer: index,
+                    },
+                    Node {
+                        padding: UiRect::axes(Val::Px(8.), Val::Px(4.)),
+                        ..default()
+                    },
+                    BackgroundColor(if offer.is_sold_out() {
+                        TRADE_SOLD_OUT_COLOR
+                    } else {
+                        TRADE_BUTTON_COLOR
+                    }),
+                    children![(
+                        Text::new(offer_label(offer, &localization)),
+                        TextFont {
+                            font_size: TRADE_FONT_SIZE,
+                            ..default()
+                        },
+                    )],
+                ));
+            }
+        });
+}
+
+/// Asks the server for the trades the player clicks, it answers with the updated offers
+pub fn trading_dialog_system(
+    dialog: Single<(&mut Visibility, &mut TradingDialog)>,
+    mut buttons: Query<(&Interaction, &TradeButton, &mut BackgroundColor), Changed<Interaction>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_map: Res<KeyMap>,
+    mut client: ResMut<RenetClient>,
+) {
+    let (mut visibility, mut dialog) = dialog.into_inner();
+    if *visibility != Visibility::Visible {
+        return;
+    }
+
+    if is_action_just_pressed(GameAction::Escape, &keyboard_input, &key_map)
+        || is_action_just_pressed(GameAction::ToggleInventory, &keyboard_input, &key_map)
+    {
+        *visibility = Visibility::Hidden;
+        dialog.mob_id = None;
+        return;
+    }
+
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        if *color == BackgroundColor(TRADE_SOLD_OUT_COLOR) {
+            continue;
+        }
+        match interaction {
+            Interaction::Pressed => {
+                client.send_game_message(ClientToServerMessage::Trade(TradeRequest {
+                    mob_id: button.mob_id,
+                    offer: button.offer,
+                }));
+            }
+            Interaction::Hovered => *color = BackgroundColor(TRADE_BUTTON_HOVER_COLOR),
+            Interaction::None => *color = BackgroundColor(TRADE_BUTTON_COLOR),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use shared::lang::{TRANSLATABLE_END, TRANSLATABLE_SEPARATOR, TRANSLATABLE_START};
+use shared::world::{BlockId, ItemId};
+
+use crate::ui::accessibility::AccessibilitySettings;
+
+/// Used for the keys missing from the picked language
+pub const DEFAULT_LANGUAGE: &str = "en_us";
+
+/// Localization tables shipped with the game, by language code
+pub const LANGUAGES: [(&str, &str); 2] = [
+    ("en_us", include_str!("../../../data/lang/en_us.ron")),
+    ("fr_fr", include_str!("../../../data/lang/fr_fr.ron")),
+];
+
+/// Next language of `LANGUAGES`, to cycle through them in the options
+pub fn next_language(language: &str) -> String {
+    let index = LANGUAGES
+        .iter()
+        .position(|(code, _)| *code == language)
+        .map_or(0, |index| index + 1);
+    LANGUAGES[index % LANGUAGES.len()].0.to_string()
+}
+
+fn load_table(language: &str) -> HashMap<String, String> {
+    let Some((_, content)) = LANGUAGES.iter().find(|(code, _)| *code == language) else {
+        warn!("Unknown language {}, using {}", language, DEFAULT_LANGUAGE);
+        return HashMap::new();
+    };
+    ron::from_str(content).unwrap_or_else(|e| {
+        error!("Invalid localization table {}: {}", language, e);
+        HashMap::new()
+    })
+}
+
+/// Names and messages of the game in the language picked in the options
+#[derive(Resource)]
+pub struct Localization {
+    pub language: String,
+    entries: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Localization {
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            entries: load_table(language),
+            fallback: load_table(DEFAULT_LANGUAGE),
+        }
+    }
+
+    /// Translation of `key`, the key itself when no table has it
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.entries
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// Translation of `key` with `{0}`, `{1}`... replaced by the arguments,
+    /// which are translated too when they are keys
+    pub fn format(&self, key: &str, args: &[&str]) -> String {
+        let mut text = self.get(key).to_string();
+        for (i, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{i}}}"), self.get(arg));
+        }
+        text
+    }
+
+    pub fn block_name(&self, block: BlockId) -> String {
+        self.get(&block.translation_key()).to_string()
+    }
+
+    pub fn item_name(&self, item: ItemId) -> String {
+        self.get(&item.translation_key()).to_string()
+    }
+
+    /// Translates the parts of a server message made with `shared::lang::translatable`
+    pub fn localize(&self, message: &str) -> String {
+        let mut text = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(start) = rest.find(TRANSLATABLE_START) {
+            let Some(len) = rest[start..].find(TRANSLATABLE_END) else {
+                break;
+            };
+            text.push_str(&rest[..start]);
+            let mut parts = rest[start + 1..start + len].split(TRANSLATABLE_SEPARATOR);
+            let key = parts.next().unwrap_or_default();
+            let args: Vec<&str> = parts.collect();
+            text.push_str(&self.format(key, &args));
+            rest = &rest[start + len + 1..];
+        }
+        text.push_str(rest);
+        text
+    }
+}
+
+/// Loads the tables of the language picked in the options when it changes
+pub fn update_localization_system(
+    accessibility: Res<AccessibilitySettings>,
+    mut localization: ResMut<Localization>,
+) {
+    if accessibility.is_changed() && localization.language != accessibility.language {
+        info!("Switching language to {}", accessibility.language);
+        *localization = Localization::new(&accessibility.language);
+    }
+}
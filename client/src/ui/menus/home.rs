@@ -45,6 +45,7 @@ pub fn home_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             // Add buttons for each action available in the menu
             for (action, label) in [
                 (MenuButtonAction::Solo, "Singleplayer"),
+                (MenuButtonAction::Demo, "Play demo"),
                 (MenuButtonAction::Multi, "Multiplayer"),
                 (MenuButtonAction::Settings, "Settings"),
                 (MenuButtonAction::Quit, "Quit"),
@@ -5,19 +5,24 @@ pub mod server_connect_loading;
 pub mod settings;
 pub mod solo;
 pub mod splash;
+pub mod world_loading;
 
 use bevy::prelude::*;
 pub use home::*;
 pub use server_connect_loading::*;
+pub use world_loading::*;
 
 use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
 
 use bevy::app::AppExit;
 use multi::multiplayer_action;
-use settings::controls::{controls_menu_setup, controls_update_system};
+use settings::controls::{controls_menu_setup, controls_profile_system, controls_update_system};
 
 use crate::input::keyboard::save_keybindings;
-use crate::{GameState, MenuCamera};
+use crate::network::lan::{lan_discovery_system, start_lan_discovery, stop_lan_discovery};
+use crate::{GameState, LoadWorldEvent, MenuCamera};
+use shared::GameFolderPaths;
+use solo::SelectedWorld;
 
 use super::button::*;
 
@@ -32,7 +37,16 @@ pub fn menu_plugin(app: &mut App) {
             OnEnter(MenuState::Solo),
             (solo::solo_menu_setup, solo::list_worlds).chain(),
         )
-        .add_systems(Update, solo::solo_action.run_if(in_state(MenuState::Solo)))
+        .add_systems(
+            Update,
+            (
+                solo::solo_action,
+                solo::seed_preview_action,
+                solo::showcase_seed_action,
+                solo::spawn_platform_toggle_action,
+            )
+                .run_if(in_state(MenuState::Solo)),
+        )
         // Systems to handle the settings menu screen
         .add_systems(OnEnter(MenuState::Settings), settings::settings_menu_setup)
         // Systems to handle the display settings screen
@@ -42,16 +56,29 @@ pub fn menu_plugin(app: &mut App) {
         .add_systems(OnExit(MenuState::SettingsControls), save_keybindings)
         .add_systems(
             OnEnter(MenuState::Multi),
-            (multi::multiplayer_menu_setup, multi::load_server_list).chain(),
+            (
+                multi::multiplayer_menu_setup,
+                multi::load_server_list,
+                start_lan_discovery,
+            )
+                .chain(),
         )
         .add_systems(
             Update,
-            (multiplayer_action).run_if(in_state(MenuState::Multi)),
+            (
+                multiplayer_action,
+                (lan_discovery_system, multi::lan_game_list_system).chain(),
+            )
+                .run_if(in_state(MenuState::Multi)),
+        )
+        .add_systems(
+            OnExit(MenuState::Multi),
+            (multi::save_server_list, stop_lan_discovery),
         )
-        .add_systems(OnExit(MenuState::Multi), multi::save_server_list)
         .add_systems(
             Update,
-            controls_update_system.run_if(in_state(MenuState::SettingsControls)),
+            (controls_update_system, controls_profile_system)
+                .run_if(in_state(MenuState::SettingsControls)),
         )
         // Common systems to all screens that handles buttons behavior
         .add_systems(
@@ -75,6 +102,12 @@ fn menu_action(
     >,
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
+    (mut game_state, mut selected_world, mut load_event, paths): (
+        ResMut<NextState<GameState>>,
+        ResMut<SelectedWorld>,
+        EventWriter<LoadWorldEvent>,
+        Res<GameFolderPaths>,
+    ),
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -83,6 +116,18 @@ fn menu_action(
                     app_exit_events.write(AppExit::Success);
                 }
                 MenuButtonAction::Solo => menu_state.set(MenuState::Solo),
+                MenuButtonAction::Demo => match solo::install_demo_world(&paths) {
+                    Ok(world_name) => {
+                        *selected_world = SelectedWorld {
+                            name: Some(world_name.clone()),
+                            ..default()
+                        };
+                        load_event.write(LoadWorldEvent { world_name });
+                        game_state.set(GameState::Connecting);
+                        menu_state.set(MenuState::Disabled);
+                    }
+                    Err(e) => error!("Could not install the demo world: {}", e),
+                },
                 MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
                 MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
                 MenuButtonAction::BackToSettings => {
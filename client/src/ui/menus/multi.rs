@@ -1,11 +1,13 @@
 use super::{MenuButtonAction, MenuState, ScrollingList};
 use crate::constants::SERVER_LIST_SAVE_NAME;
+use crate::network::lan::LanGames;
 use crate::network::{TargetServer, TargetServerState};
 use crate::ui::assets::*;
 use crate::ui::style::*;
 use crate::GameState;
 use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
 use bevy::{
     asset::AssetServer,
     color::Color,
@@ -21,10 +23,12 @@ use bevy_simple_text_input::{
     TextInput, TextInputInactive, TextInputPlaceholder, TextInputSettings, TextInputValue,
 };
 use ron::{from_str, ser::PrettyConfig};
+use shared::network::resolve_server_address;
 use shared::GameFolderPaths;
 use std::{
     fs,
     io::Write,
+    net::SocketAddr,
     path::{Path, PathBuf},
 };
 
@@ -44,14 +48,30 @@ pub enum MultiplayerButtonAction {
     Add,
     Connect(Entity),
     Delete(Entity),
+    JoinLan(SocketAddr),
 }
 
+/// Games found on the local network, filled from `LanGames`
+#[derive(Component)]
+pub struct LanGameList;
+
 #[derive(Component)]
 pub struct ServerIpInput;
 
+/// Tells why the address of the server picked could not be used
+#[derive(Component)]
+pub struct ServerAddressError;
+
 #[derive(Component)]
 pub struct ServerNameInput;
 
+/// Address of the server picked being resolved on another thread, looking up a host name can take a while
+pub struct ResolvingServer {
+    name: String,
+    ip: String,
+    task: Task<Result<Vec<SocketAddr>, String>>,
+}
+
 pub fn multiplayer_menu_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -122,7 +142,7 @@ pub fn multiplayer_menu_setup(
                 BorderColor(BACKGROUND_COLOR),
                 Node {
                     width: Val::Percent(100.0),
-                    height: Val::Percent(50.0),
+                    height: Val::Percent(35.0),
                     flex_direction: FlexDirection::Column,
                     overflow: Overflow::clip_y(),
                     border: UiRect::all(Val::Px(2.0)),
@@ -145,6 +165,23 @@ pub fn multiplayer_menu_setup(
                 ));
             });
 
+            root.spawn((Text::new("LAN Games"), txt_font.clone(), txt_color));
+
+            root.spawn((
+                BorderColor(BACKGROUND_COLOR),
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(15.0),
+                    flex_direction: FlexDirection::Column,
+                    overflow: Overflow::clip_y(),
+                    border: UiRect::all(Val::Px(2.0)),
+                    padding: UiRect::all(Val::Px(5.0)),
+                    row_gap: Val::Px(5.0),
+                    ..default()
+                },
+                LanGameList,
+            ));
+
             root.spawn((Node {
                 width: Val::Percent(100.0),
                 display: Display::Grid,
@@ -230,6 +267,13 @@ pub fn multiplayer_menu_setup(
                             btn.spawn((Text::new("Back to menu"), txt_font.clone(), txt_color));
                         });
                 });
+
+            root.spawn((
+                Text::new(""),
+                txt_font.clone(),
+                TextColor(Color::srgb(0.9, 0.3, 0.3)),
+                ServerAddressError,
+            ));
         });
 }
 
@@ -471,6 +515,7 @@ pub fn multiplayer_action(
         Query<&TextInputValue, (With<ServerNameInput>, Without<ServerIpInput>)>,
         Query<&TextInputValue, (With<ServerIpInput>, Without<ServerNameInput>)>,
         Query<(Entity, &mut ServerList), With<ServerList>>,
+        Query<&mut Text, With<ServerAddressError>>,
     ),
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -478,12 +523,39 @@ pub fn multiplayer_action(
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
     paths: Res<GameFolderPaths>,
+    mut resolving: Local<Option<ResolvingServer>>,
 ) {
-    let (interaction_query, name_query, ip_query, mut list_query) = queries;
+    let (interaction_query, name_query, ip_query, mut list_query, mut error_text) = queries;
     if list_query.is_empty() {
         return;
     }
 
+    if let Some(server) = resolving.as_mut() {
+        if let Some(result) = block_on(future::poll_once(&mut server.task)) {
+            match result {
+                Ok(mut addresses) => {
+                    debug!("{} resolved to {:?}", server.ip, addresses);
+                    for mut text in error_text.iter_mut() {
+                        text.0.clear();
+                    }
+                    target_server.address = Some(addresses.remove(0));
+                    target_server.fallback_addresses = addresses;
+                    target_server.connection_error = None;
+                    target_server.state = TargetServerState::Initial;
+                    game_state.set(GameState::Connecting);
+                    menu_state.set(MenuState::Disabled);
+                }
+                Err(e) => {
+                    error!("Cannot connect to {}: {}", server.name, e);
+                    for mut text in error_text.iter_mut() {
+                        text.0 = e.clone();
+                    }
+                }
+            }
+            *resolving = None;
+        }
+    }
+
     let (entity, mut list) = list_query.single_mut().unwrap();
 
     for (interaction, menu_button_action) in &interaction_query {
@@ -506,15 +578,34 @@ pub fn multiplayer_action(
                     }
                 }
                 MultiplayerButtonAction::Connect(serv_entity) => {
+                    if resolving.is_some() {
+                        continue;
+                    }
                     if let Some(srv) = list.servers.get(&serv_entity) {
                         info!("Server : name={}, ip={}", srv.name, srv.ip);
 
-                        target_server.address = Some(srv.ip.parse().unwrap());
-                        target_server.state = TargetServerState::Initial;
-                        game_state.set(GameState::PreGameLoading);
-                        menu_state.set(MenuState::Disabled);
+                        for mut text in error_text.iter_mut() {
+                            text.0 = format!("Looking up {}...", srv.ip);
+                        }
+                        let ip = srv.ip.clone();
+                        *resolving = Some(ResolvingServer {
+                            name: srv.name.clone(),
+                            ip: srv.ip.clone(),
+                            task: AsyncComputeTaskPool::get()
+                                .spawn(async move { resolve_server_address(&ip) }),
+                        });
                     }
                 }
+                MultiplayerButtonAction::JoinLan(address) => {
+                    info!("LAN game : {}", address);
+
+                    target_server.address = Some(address);
+                    target_server.fallback_addresses.clear();
+                    target_server.connection_error = None;
+                    target_server.state = TargetServerState::Initial;
+                    game_state.set(GameState::Connecting);
+                    menu_state.set(MenuState::Disabled);
+                }
                 MultiplayerButtonAction::Delete(serv_entity) => {
                     debug!("Old list : {:?}", list.servers);
                     commands.entity(entity).remove_children(&[serv_entity]);
@@ -526,3 +617,64 @@ pub fn multiplayer_action(
         }
     }
 }
+
+/// Lists the games announced on the local network, whenever one appears, changes or goes away
+pub fn lan_game_list_system(
+    mut commands: Commands,
+    lan: Res<LanGames>,
+    list: Query<Entity, With<LanGameList>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(list) = list.single() else {
+        return;
+    };
+    if !lan.is_changed() {
+        return;
+    }
+
+    let font = load_font(&asset_server);
+    commands.entity(list).despawn_related::<Children>();
+    commands.entity(list).with_children(|list| {
+        if lan.games.is_empty() {
+            list.spawn((
+                Text::new("Looking for games on the local network..."),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 15.,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.4, 0.4, 0.4)),
+            ));
+        }
+
+        for game in lan.games.iter() {
+            list.spawn((
+                Button,
+                MultiplayerButtonAction::JoinLan(game.address),
+                Node {
+                    width: Val::Percent(100.),
+                    padding: UiRect::horizontal(Val::Px(5.)),
+                    ..default()
+                },
+                BackgroundColor(BACKGROUND_COLOR),
+            ))
+            .with_children(|row| {
+                row.spawn((
+                    Text::new(format!(
+                        "{} - {}/{} players - {}",
+                        game.beacon.name,
+                        game.beacon.players,
+                        game.beacon.max_players,
+                        game.address
+                    )),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 18.,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+            });
+        }
+    });
+}
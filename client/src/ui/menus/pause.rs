@@ -1,4 +1,18 @@
-use crate::network::save::send_save_request_to_server;
+use crate::camera::{
+    save_camera_settings, CameraSettings, MAX_SENSITIVITY, MAX_SMOOTHING, MIN_SENSITIVITY,
+};
+use crate::network::save::{send_save_request_to_server, UnsavedProgress};
+use crate::network::TargetServer;
+use crate::player::CurrentPlayerMarker;
+use crate::ui::accessibility::{
+    save_accessibility_settings, AccessibilitySettings, MAX_HUD_SCALE, MIN_HUD_SCALE,
+};
+use crate::ui::lang::next_language;
+use crate::world::{
+    change_render_distance, save_video_settings, GraphicsPreset, RenderDistance, VideoSettings,
+    WorldRenderRequestUpdateEvent, MAX_BRIGHTNESS, MAX_FOV, MAX_GAMMA, MAX_OUTLINE_THICKNESS,
+    MIN_BRIGHTNESS, MIN_FOV, MIN_GAMMA, MIN_OUTLINE_THICKNESS,
+};
 use bevy::{
     asset::AssetServer,
     color::{Alpha, Color},
@@ -12,18 +26,300 @@ use bevy::{
 use bevy_renet::renet::RenetClient;
 use shared::GameFolderPaths;
 
-use crate::{input::keyboard::is_action_just_pressed, GameState, KeyMap};
+use crate::input::keyboard::{is_action_just_pressed, save_key_profiles};
+use crate::input::profiles::KeyProfiles;
+use crate::{GameState, KeyMap};
 
 use crate::ui::hud::UiDialog;
 
 #[derive(Component)]
 pub struct PauseMenu;
 
+/// Pages of the pause menu, only one of them is displayed at a time
+#[derive(Component, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum PausePage {
+    #[default]
+    Main,
+    Options,
+    Video,
+    /// Asked before leaving a singleplayer world which was not saved
+    ConfirmDisconnect,
+}
+
+/// Settings which can be changed from the options and video pages
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PauseOption {
+    RenderDistance,
+    MouseSensitivity,
+    GamepadSensitivity,
+    InvertY,
+    CameraSmoothing,
+    AutoJump,
+    Palette,
+    HudScale,
+    Crosshair,
+    NarrateChat,
+    NarrateNotifications,
+    NarrateLowHealth,
+    Language,
+    /// Keybinding profile used in this world instead of the one picked in the menu
+    Controls,
+    WindowMode,
+    Vsync,
+    Fov,
+    Brightness,
+    Gamma,
+    Preset,
+    AmbientOcclusion,
+    FancyLeaves,
+    Particles,
+    Shadows,
+    ChunkAnimation,
+    OutlineColor,
+    OutlineThickness,
+}
+
+impl PauseOption {
+    pub const ALL: [PauseOption; 14] = [
+        PauseOption::RenderDistance,
+        PauseOption::MouseSensitivity,
+        PauseOption::GamepadSensitivity,
+        PauseOption::InvertY,
+        PauseOption::CameraSmoothing,
+        PauseOption::AutoJump,
+        PauseOption::Palette,
+        PauseOption::HudScale,
+        PauseOption::Crosshair,
+        PauseOption::NarrateChat,
+        PauseOption::NarrateNotifications,
+        PauseOption::NarrateLowHealth,
+        PauseOption::Language,
+        PauseOption::Controls,
+    ];
+
+    pub const VIDEO: [PauseOption; 13] = [
+        PauseOption::WindowMode,
+        PauseOption::Vsync,
+        PauseOption::Fov,
+        PauseOption::Brightness,
+        PauseOption::Gamma,
+        PauseOption::Preset,
+        PauseOption::AmbientOcclusion,
+        PauseOption::FancyLeaves,
+        PauseOption::Particles,
+        PauseOption::Shadows,
+        PauseOption::ChunkAnimation,
+        PauseOption::OutlineColor,
+        PauseOption::OutlineThickness,
+    ];
+
+    fn is_toggle(&self) -> bool {
+        matches!(
+            self,
+            PauseOption::InvertY
+                | PauseOption::AutoJump
+                | PauseOption::Palette
+                | PauseOption::Crosshair
+                | PauseOption::NarrateChat
+                | PauseOption::NarrateNotifications
+                | PauseOption::NarrateLowHealth
+                | PauseOption::Language
+                | PauseOption::Controls
+                | PauseOption::WindowMode
+                | PauseOption::Vsync
+                | PauseOption::Preset
+                | PauseOption::AmbientOcclusion
+                | PauseOption::FancyLeaves
+                | PauseOption::Shadows
+                | PauseOption::ChunkAnimation
+                | PauseOption::OutlineColor
+        )
+    }
+
+    fn label(
+        &self,
+        render_distance: &RenderDistance,
+        camera: &CameraSettings,
+        accessibility: &AccessibilitySettings,
+        video: &VideoSettings,
+        key_profiles: &KeyProfiles,
+    ) -> String {
+        match self {
+            PauseOption::RenderDistance => format!("Render distance: {}", render_distance.chunks),
+            PauseOption::MouseSensitivity => {
+                format!("Mouse sensitivity: {:.1}x", camera.mouse_sensitivity)
+            }
+            PauseOption::GamepadSensitivity => {
+                format!("Gamepad sensitivity: {:.1}x", camera.gamepad_sensitivity)
+            }
+            PauseOption::InvertY => {
+                format!("Invert Y: {}", on_off(camera.invert_y))
+            }
+            PauseOption::CameraSmoothing if camera.smoothing <= 0.0 => {
+                "Camera smoothing: Off".to_string()
+            }
+            PauseOption::CameraSmoothing => {
+                format!("Camera smoothing: {:.2}s", camera.smoothing)
+            }
+            PauseOption::AutoJump => format!("Auto-jump: {}", on_off(accessibility.auto_jump)),
+            PauseOption::Palette => format!("Colors: {:?}", accessibility.palette),
+            PauseOption::HudScale => format!("HUD scale: {:.1}x", accessibility.hud_scale),
+            PauseOption::Crosshair => format!("Crosshair: {:?}", accessibility.crosshair),
+            PauseOption::NarrateChat => {
+                format!("Read chat aloud: {}", on_off(accessibility.narrate_chat))
+            }
+            PauseOption::NarrateNotifications => format!(
+                "Read notifications aloud: {}",
+                on_off(accessibility.narrate_notifications)
+            ),
+            PauseOption::NarrateLowHealth => format!(
+                "Low health warning: {}",
+                on_off(accessibility.narrate_low_health)
+            ),
+            PauseOption::Language => format!("Language: {}", accessibility.language),
+            PauseOption::Controls => match key_profiles.world_override() {
+                Some(profile) => format!("Controls: {profile} (this world)"),
+                None => format!("Controls: {}", key_profiles.active),
+            },
+            PauseOption::WindowMode => format!("Window: {:?}", video.window_mode),
+            PauseOption::Vsync => format!("VSync: {}", on_off(video.vsync)),
+            PauseOption::Fov => format!("Field of view: {:.0}", video.fov),
+            PauseOption::Brightness => format!("Brightness: {:+.2}", video.brightness),
+            PauseOption::Gamma => format!("Gamma: {:.1}", video.gamma),
+            PauseOption::Preset => format!("Graphics: {:?}", video.preset),
+            PauseOption::AmbientOcclusion => {
+                format!("Ambient occlusion: {}", on_off(video.ambient_occlusion))
+            }
+            PauseOption::FancyLeaves => format!("Fancy leaves: {}", on_off(video.fancy_leaves)),
+            PauseOption::Particles => format!("Particles: {:.0}%", video.particles * 100.),
+            PauseOption::Shadows => format!("Shadows: {:?}", video.shadows),
+            PauseOption::ChunkAnimation => {
+                format!("Chunk animation: {}", on_off(video.chunk_animation))
+            }
+            PauseOption::OutlineColor => format!("Block outline: {:?}", video.outline_color),
+            PauseOption::OutlineThickness => {
+                format!("Outline thickness: {:.3}", video.outline_thickness)
+            }
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+/// Text showing the current value of an option
+#[derive(Component)]
+pub struct OptionText(PauseOption);
+
 #[derive(Component)]
 pub enum PauseButtonAction {
     Resume,
     Save,
-    Menu,
+    Options,
+    Video,
+    Disconnect,
+    Decrease(PauseOption),
+    Increase(PauseOption),
+    Toggle(PauseOption),
+    /// Goes back to the main page
+    Back,
+    SaveAndDisconnect,
+    DisconnectWithoutSaving,
+}
+
+fn spawn_pause_button(
+    parent: &mut ChildSpawnerCommands,
+    font: &TextFont,
+    msg: &str,
+    action: PauseButtonAction,
+) {
+    parent
+        .spawn((
+            action,
+            (
+                Button,
+                Node {
+                    width: Val::Percent(100.),
+                    border: UiRect::all(Val::Px(3.)),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    padding: UiRect::all(Val::Px(7.)),
+                    ..Default::default()
+                },
+                BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
+                BorderColor(Color::BLACK),
+            ),
+        ))
+        .with_children(|btn| {
+            btn.spawn((Text::new(msg), font.clone(), TextColor(Color::WHITE)));
+        });
+}
+
+fn spawn_option_row(parent: &mut ChildSpawnerCommands, font: &TextFont, option: PauseOption) {
+    parent
+        .spawn(Node {
+            width: Val::Percent(100.),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.),
+            ..Default::default()
+        })
+        .with_children(|row| {
+            let text = (
+                OptionText(option),
+                Text::default(),
+                font.clone(),
+                TextColor(Color::WHITE),
+                Node {
+                    flex_shrink: 0.,
+                    ..Default::default()
+                },
+            );
+
+            if option.is_toggle() {
+                row.spawn(text);
+                spawn_pause_button(row, font, "Toggle", PauseButtonAction::Toggle(option));
+            } else {
+                spawn_pause_button(row, font, "-", PauseButtonAction::Decrease(option));
+                row.spawn(text);
+                spawn_pause_button(row, font, "+", PauseButtonAction::Increase(option));
+            }
+        });
+}
+
+/// Moves a setting by one step, rounded so that steps do not accumulate float errors
+fn step_setting(value: f32, step: f32, min: f32, max: f32) -> f32 {
+    ((value + step) * 100.)
+        .round()
+        .clamp(min * 100., max * 100.)
+        / 100.
+}
+
+fn page_node(page: PausePage) -> (PausePage, Node) {
+    (
+        page,
+        Node {
+            display: if page == PausePage::Main {
+                Display::Flex
+            } else {
+                Display::None
+            },
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::SpaceAround,
+            height: Val::Vh(40.),
+            min_width: Val::Vw(40.),
+            ..Default::default()
+        },
+    )
 }
 
 pub fn setup_pause_menu(
@@ -31,6 +327,13 @@ pub fn setup_pause_menu(
     assets: Res<AssetServer>,
     _paths: Res<GameFolderPaths>,
 ) {
+    let font = TextFont {
+        font: assets.load("./fonts/RustCraftRegular-Bmg3.otf"),
+        font_size: 20.,
+        font_smoothing: default(),
+        ..default()
+    };
+
     commands
         .spawn((
             PauseMenu,
@@ -53,100 +356,343 @@ pub fn setup_pause_menu(
             GlobalZIndex(5),
         ))
         .with_children(|root| {
-            root.spawn(Node {
-                display: Display::Flex,
-                flex_direction: FlexDirection::Column,
-                align_items: AlignItems::Center,
-                justify_content: JustifyContent::SpaceAround,
-                height: Val::Vh(40.),
-                min_width: Val::Vw(40.),
-                ..Default::default()
-            })
-            .with_children(|wrapper| {
-                for (msg, action) in [
-                    ("Resume", PauseButtonAction::Resume),
-                    ("Save", PauseButtonAction::Save),
-                    ("Back to menu", PauseButtonAction::Menu),
-                ] {
-                    wrapper
-                        .spawn((
-                            action,
-                            (
-                                Button,
-                                Node {
-                                    width: Val::Percent(100.),
-                                    border: UiRect::all(Val::Px(3.)),
-                                    display: Display::Flex,
-                                    flex_direction: FlexDirection::Row,
-                                    align_items: AlignItems::Center,
-                                    justify_content: JustifyContent::Center,
-                                    padding: UiRect::all(Val::Px(7.)),
-                                    ..Default::default()
-                                },
-                                BackgroundColor(Color::srgb(0.3, 0.3, 0.3)),
-                                BorderColor(Color::BLACK),
-                            ),
-                        ))
-                        .with_children(|btn| {
-                            btn.spawn((
-                                Text::new(msg),
-                                TextFont {
-                                    font: assets.load("./fonts/RustCraftRegular-Bmg3.otf"),
-                                    font_size: 20.,
-                                    font_smoothing: default(),
-                                    ..default()
-                                },
-                                TextColor(Color::WHITE),
-                            ));
-                        });
-                }
-            });
+            root.spawn(page_node(PausePage::Main))
+                .with_children(|wrapper| {
+                    for (msg, action) in [
+                        ("Resume", PauseButtonAction::Resume),
+                        ("Save", PauseButtonAction::Save),
+                        ("Options", PauseButtonAction::Options),
+                        ("Video", PauseButtonAction::Video),
+                        ("Disconnect", PauseButtonAction::Disconnect),
+                    ] {
+                        spawn_pause_button(wrapper, &font, msg, action);
+                    }
+                });
+
+            root.spawn(page_node(PausePage::Options))
+                .with_children(|wrapper| {
+                    for option in PauseOption::ALL {
+                        spawn_option_row(wrapper, &font, option);
+                    }
+                    spawn_pause_button(wrapper, &font, "Back", PauseButtonAction::Back);
+                });
+
+            root.spawn(page_node(PausePage::Video))
+                .with_children(|wrapper| {
+                    for option in PauseOption::VIDEO {
+                        spawn_option_row(wrapper, &font, option);
+                    }
+                    spawn_pause_button(wrapper, &font, "Back", PauseButtonAction::Back);
+                });
+
+            root.spawn(page_node(PausePage::ConfirmDisconnect))
+                .with_children(|wrapper| {
+                    wrapper.spawn((
+                        Text::new("This world has unsaved changes"),
+                        font.clone(),
+                        TextColor(Color::WHITE),
+                    ));
+                    for (msg, action) in [
+                        ("Save and disconnect", PauseButtonAction::SaveAndDisconnect),
+                        (
+                            "Disconnect without saving",
+                            PauseButtonAction::DisconnectWithoutSaving,
+                        ),
+                        ("Cancel", PauseButtonAction::Back),
+                    ] {
+                        spawn_pause_button(wrapper, &font, msg, action);
+                    }
+                });
         });
 }
 
 pub fn render_pause_menu(
     queries: (
-        Query<(&PauseButtonAction, &mut BorderColor, &Interaction)>,
+        Query<(&PauseButtonAction, &mut BorderColor, Ref<Interaction>)>,
         Query<&mut Visibility, With<PauseMenu>>,
+        Query<(&PausePage, &mut Node)>,
+        Query<(&mut Text, &OptionText)>,
+        Query<&Transform, With<CurrentPlayerMarker>>,
     ),
     input: Res<ButtonInput<KeyCode>>,
     mut game_state: ResMut<NextState<GameState>>,
     key_map: Res<KeyMap>,
     mut client: ResMut<RenetClient>,
+    target: Res<TargetServer>,
+    mut unsaved: ResMut<UnsavedProgress>,
+    mut render_distance: ResMut<RenderDistance>,
+    mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut accessibility: ResMut<AccessibilitySettings>,
+    mut video: ResMut<VideoSettings>,
+    mut key_profiles: ResMut<KeyProfiles>,
+    paths: Res<GameFolderPaths>,
+    mut current_page: Local<PausePage>,
 ) {
-    let (mut button, mut visibility) = queries;
+    let (mut button, mut visibility, mut pages, mut option_texts, player) = queries;
     let mut vis = visibility.single_mut().unwrap();
+    let mut page = *current_page;
 
     if is_action_just_pressed(crate::input::data::GameAction::Escape, &input, &key_map) {
-        *vis = match *vis {
-            Visibility::Visible | Visibility::Inherited => Visibility::Hidden,
-            Visibility::Hidden => Visibility::Visible,
+        // Escape leaves the options, video and confirmation pages before closing the menu
+        if *vis == Visibility::Visible && page != PausePage::Main {
+            page = PausePage::Main;
+        } else {
+            *vis = match *vis {
+                Visibility::Visible | Visibility::Inherited => Visibility::Hidden,
+                Visibility::Hidden => Visibility::Visible,
+            };
+            page = PausePage::Main;
         }
     }
 
-    if *vis != Visibility::Visible {
-        return;
-    }
-
-    for (action, mut bcolor, interaction) in button.iter_mut() {
-        match *interaction {
-            Interaction::Pressed => match *action {
-                PauseButtonAction::Menu => {
-                    game_state.set(GameState::Menu);
-                }
-                PauseButtonAction::Resume => {
-                    *vis = Visibility::Hidden;
+    if *vis == Visibility::Visible {
+        for (action, mut bcolor, interaction) in button.iter_mut() {
+            match *interaction {
+                // Buttons only act on the frame they get pressed, not while they are held
+                Interaction::Pressed if interaction.is_changed() => match *action {
+                    PauseButtonAction::Resume => {
+                        *vis = Visibility::Hidden;
+                        page = PausePage::Main;
+                    }
+                    PauseButtonAction::Save => {
+                        send_save_request_to_server(&mut client, &mut unsaved);
+                    }
+                    PauseButtonAction::Options => {
+                        page = PausePage::Options;
+                    }
+                    PauseButtonAction::Video => {
+                        page = PausePage::Video;
+                    }
+                    PauseButtonAction::Disconnect => {
+                        if target.is_solo && unsaved.0 {
+                            page = PausePage::ConfirmDisconnect;
+                        } else {
+                            game_state.set(GameState::Menu);
+                        }
+                    }
+                    PauseButtonAction::Decrease(option) | PauseButtonAction::Increase(option) => {
+                        let direction = match *action {
+                            PauseButtonAction::Increase(_) => 1.,
+                            _ => -1.,
+                        };
+                        match option {
+                            PauseOption::RenderDistance => {
+                                let player_position = player.single().ok().map(|t| t.translation);
+                                change_render_distance(
+                                    &mut render_distance,
+                                    direction as i32,
+                                    player_position,
+                                    &mut ev_render,
+                                );
+                            }
+                            PauseOption::MouseSensitivity => {
+                                camera_settings.mouse_sensitivity = step_setting(
+                                    camera_settings.mouse_sensitivity,
+                                    direction * 0.1,
+                                    MIN_SENSITIVITY,
+                                    MAX_SENSITIVITY,
+                                );
+                            }
+                            PauseOption::GamepadSensitivity => {
+                                camera_settings.gamepad_sensitivity = step_setting(
+                                    camera_settings.gamepad_sensitivity,
+                                    direction * 0.1,
+                                    MIN_SENSITIVITY,
+                                    MAX_SENSITIVITY,
+                                );
+                            }
+                            PauseOption::CameraSmoothing => {
+                                camera_settings.smoothing = step_setting(
+                                    camera_settings.smoothing,
+                                    direction * 0.05,
+                                    0.,
+                                    MAX_SMOOTHING,
+                                );
+                            }
+                            PauseOption::HudScale => {
+                                accessibility.hud_scale = step_setting(
+                                    accessibility.hud_scale,
+                                    direction * 0.1,
+                                    MIN_HUD_SCALE,
+                                    MAX_HUD_SCALE,
+                                );
+                            }
+                            PauseOption::Fov => {
+                                video.fov =
+                                    step_setting(video.fov, direction * 5., MIN_FOV, MAX_FOV);
+                            }
+                            PauseOption::Brightness => {
+                                video.brightness = step_setting(
+                                    video.brightness,
+                                    direction * 0.25,
+                                    MIN_BRIGHTNESS,
+                                    MAX_BRIGHTNESS,
+                                );
+                            }
+                            PauseOption::Gamma => {
+                                video.gamma = step_setting(
+                                    video.gamma,
+                                    direction * 0.1,
+                                    MIN_GAMMA,
+                                    MAX_GAMMA,
+                                );
+                            }
+                            PauseOption::Particles => {
+                                video.particles =
+                                    step_setting(video.particles, direction * 0.25, 0., 1.);
+                                video.preset = GraphicsPreset::Custom;
+                            }
+                            PauseOption::OutlineThickness => {
+                                video.outline_thickness = step_setting(
+                                    video.outline_thickness,
+                                    direction * 0.005,
+                                    MIN_OUTLINE_THICKNESS,
+                                    MAX_OUTLINE_THICKNESS,
+                                );
+                            }
+                            PauseOption::InvertY
+                            | PauseOption::AutoJump
+                            | PauseOption::Palette
+                            | PauseOption::Crosshair
+                            | PauseOption::NarrateChat
+                            | PauseOption::NarrateNotifications
+                            | PauseOption::NarrateLowHealth
+                            | PauseOption::Language
+                            | PauseOption::Controls
+                            | PauseOption::WindowMode
+                            | PauseOption::Vsync
+                            | PauseOption::Preset
+                            | PauseOption::AmbientOcclusion
+                            | PauseOption::FancyLeaves
+                            | PauseOption::Shadows
+                            | PauseOption::ChunkAnimation
+                            | PauseOption::OutlineColor => {}
+                        }
+                    }
+                    PauseButtonAction::Toggle(option) => match option {
+                        PauseOption::InvertY => {
+                            camera_settings.invert_y = !camera_settings.invert_y;
+                        }
+                        PauseOption::AutoJump => {
+                            accessibility.auto_jump = !accessibility.auto_jump;
+                        }
+                        PauseOption::Palette => {
+                            accessibility.palette = accessibility.palette.next();
+                        }
+                        PauseOption::Crosshair => {
+                            accessibility.crosshair = accessibility.crosshair.next();
+                        }
+                        PauseOption::NarrateChat => {
+                            accessibility.narrate_chat = !accessibility.narrate_chat;
+                        }
+                        PauseOption::NarrateNotifications => {
+                            accessibility.narrate_notifications =
+                                !accessibility.narrate_notifications;
+                        }
+                        PauseOption::NarrateLowHealth => {
+                            accessibility.narrate_low_health = !accessibility.narrate_low_health;
+                        }
+                        PauseOption::Language => {
+                            accessibility.language = next_language(&accessibility.language);
+                        }
+                        PauseOption::Controls => {
+                            key_profiles.cycle_world_override();
+                        }
+                        PauseOption::WindowMode => {
+                            video.window_mode = video.window_mode.next();
+                        }
+                        PauseOption::Vsync => {
+                            video.vsync = !video.vsync;
+                        }
+                        PauseOption::Preset => {
+                            let preset = video.preset.next();
+                            video.apply_preset(preset);
+                        }
+                        // Changing one of the features of the presets on its own leaves them
+                        PauseOption::AmbientOcclusion => {
+                            video.ambient_occlusion = !video.ambient_occlusion;
+                            video.preset = GraphicsPreset::Custom;
+                        }
+                        PauseOption::FancyLeaves => {
+                            video.fancy_leaves = !video.fancy_leaves;
+                            video.preset = GraphicsPreset::Custom;
+                        }
+                        PauseOption::Shadows => {
+                            video.shadows = video.shadows.next();
+                            video.preset = GraphicsPreset::Custom;
+                        }
+                        PauseOption::ChunkAnimation => {
+                            video.chunk_animation = !video.chunk_animation;
+                        }
+                        PauseOption::OutlineColor => {
+                            video.outline_color = video.outline_color.next();
+                        }
+                        _ => {}
+                    },
+                    PauseButtonAction::Back => {
+                        page = PausePage::Main;
+                    }
+                    PauseButtonAction::SaveAndDisconnect => {
+                        send_save_request_to_server(&mut client, &mut unsaved);
+                        game_state.set(GameState::Menu);
+                    }
+                    PauseButtonAction::DisconnectWithoutSaving => {
+                        game_state.set(GameState::Menu);
+                    }
+                },
+                Interaction::Pressed => {}
+                Interaction::Hovered => {
+                    bcolor.0 = Color::WHITE;
                 }
-                PauseButtonAction::Save => {
-                    send_save_request_to_server(&mut client);
+                Interaction::None => {
+                    bcolor.0 = Color::BLACK;
                 }
-            },
-            Interaction::Hovered => {
-                bcolor.0 = Color::WHITE;
-            }
-            Interaction::None => {
-                bcolor.0 = Color::BLACK;
             }
         }
     }
+
+    if page != *current_page {
+        *current_page = page;
+        for (node_page, mut node) in pages.iter_mut() {
+            node.display = if *node_page == page {
+                Display::Flex
+            } else {
+                Display::None
+            };
+        }
+    }
+
+    if camera_settings.is_changed() && !camera_settings.is_added() {
+        save_camera_settings(&camera_settings, &paths);
+    }
+
+    if accessibility.is_changed() && !accessibility.is_added() {
+        save_accessibility_settings(&accessibility, &paths);
+    }
+
+    if video.is_changed() && !video.is_added() {
+        save_video_settings(&video, &paths);
+    }
+
+    if key_profiles.is_changed() && !key_profiles.is_added() {
+        save_key_profiles(&key_profiles, &paths);
+    }
+
+    if render_distance.is_changed()
+        || camera_settings.is_changed()
+        || accessibility.is_changed()
+        || video.is_changed()
+        || key_profiles.is_changed()
+    {
+        for (mut text, option) in option_texts.iter_mut() {
+            text.0 = option.0.label(
+                &render_distance,
+                &camera_settings,
+                &accessibility,
+                &video,
+                &key_profiles,
+            );
+        }
+    }
 }
@@ -1,8 +1,16 @@
 use crate::{
-    network::{TargetServer, TargetServerState},
+    network::{
+        PackConsent, SendGameMessageExtension, ServerPacks, TargetServer, TargetServerState,
+    },
+    world::AtlasHandles,
     GameState,
 };
 use bevy::{color::palettes::tailwind::YELLOW_500, prelude::*};
+use bevy_renet::renet::RenetClient;
+use shared::messages::ClientToServerMessage;
+use shared::utils::format_bytes;
+use shared::world::{BlockId, ItemId};
+use shared::GameFolderPaths;
 
 #[derive(Component)]
 pub struct CancelButtonMarker;
@@ -10,8 +18,24 @@ pub struct CancelButtonMarker;
 #[derive(Component)]
 pub struct LoadingTextMarker;
 
+#[derive(Component)]
+pub struct TexturesProgressTextMarker;
+
+/// Asks the player whether to use the packs of the server, hidden when it has none
+#[derive(Component)]
+pub struct PackPromptMarker;
+
+#[derive(Component)]
+pub struct PackPromptTextMarker;
+
+#[derive(Component)]
+pub struct AcceptPacksButtonMarker;
+
+#[derive(Component)]
+pub struct DeclinePacksButtonMarker;
+
 pub fn setup_server_connect_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn((Camera2d, StateScoped(GameState::PreGameLoading)));
+    commands.spawn((Camera2d, StateScoped(GameState::Connecting)));
 
     let root_bundle = (
         Node {
@@ -24,7 +48,7 @@ pub fn setup_server_connect_loading_screen(mut commands: Commands, asset_server:
             column_gap: Val::Px(60.),
             ..default()
         },
-        StateScoped(GameState::PreGameLoading),
+        StateScoped(GameState::Connecting),
     );
 
     let loading_text_bundle = (
@@ -37,6 +61,16 @@ pub fn setup_server_connect_loading_screen(mut commands: Commands, asset_server:
         LoadingTextMarker,
     );
 
+    let textures_progress_bundle = (
+        Text::new(""),
+        TextFont {
+            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+            font_size: 24.0,
+            ..default()
+        },
+        TexturesProgressTextMarker,
+    );
+
     let cancel_button_bundle = (
         Text::new("[Cancel]"),
         TextFont {
@@ -49,23 +83,152 @@ pub fn setup_server_connect_loading_screen(mut commands: Commands, asset_server:
         Button,
     );
 
+    let button_font = TextFont {
+        font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+        font_size: 32.0,
+        ..default()
+    };
+
     commands.spawn(root_bundle).with_children(|p| {
         p.spawn(loading_text_bundle);
+        p.spawn(textures_progress_bundle);
+        p.spawn((
+            Node {
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.),
+                margin: UiRect::vertical(Val::Px(20.)),
+                ..default()
+            },
+            PackPromptMarker,
+        ))
+        .with_children(|prompt| {
+            prompt.spawn((
+                Text::new(""),
+                TextFont {
+                    font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                    font_size: 22.0,
+                    ..default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+                PackPromptTextMarker,
+            ));
+            prompt
+                .spawn(Node {
+                    column_gap: Val::Px(40.),
+                    ..default()
+                })
+                .with_children(|buttons| {
+                    buttons.spawn((
+                        Text::new("[Accept]"),
+                        button_font.clone(),
+                        TextColor(Color::from(YELLOW_500)),
+                        AcceptPacksButtonMarker,
+                        Button,
+                    ));
+                    buttons.spawn((
+                        Text::new("[Decline]"),
+                        button_font.clone(),
+                        TextColor(Color::from(YELLOW_500)),
+                        DeclinePacksButtonMarker,
+                        Button,
+                    ));
+                });
+        });
         p.spawn(cancel_button_bundle);
     });
 }
 
+fn describe_packs(packs: &ServerPacks) -> String {
+    let mut text = String::from("This server uses packs:");
+    for pack in packs.offered.iter() {
+        text.push_str(&format!(
+            "\n{} ({:?}, {} KiB{})",
+            pack.name,
+            pack.kind,
+            pack.size.div_ceil(1024),
+            if pack.required { ", required" } else { "" }
+        ));
+    }
+    text
+}
+
+/// Shows the packs offered by the server until the player accepts or declines them.
+/// Declining required packs leaves the player unable to join
+pub fn pack_prompt_system(
+    mut prompt_query: Query<&mut Node, With<PackPromptMarker>>,
+    mut prompt_text_query: Query<&mut Text, With<PackPromptTextMarker>>,
+    accept_query: Query<&Interaction, (Changed<Interaction>, With<AcceptPacksButtonMarker>)>,
+    decline_query: Query<&Interaction, (Changed<Interaction>, With<DeclinePacksButtonMarker>)>,
+    mut packs: ResMut<ServerPacks>,
+    mut target: ResMut<TargetServer>,
+    mut client: ResMut<RenetClient>,
+    paths: Res<GameFolderPaths>,
+) {
+    let asking = packs.consent == PackConsent::Pending
+        && !packs.offered.is_empty()
+        && target.connection_error.is_none();
+    let display = if asking { Display::Flex } else { Display::None };
+    for mut node in prompt_query.iter_mut() {
+        if node.display != display {
+            node.display = display;
+        }
+    }
+    if !asking {
+        return;
+    }
+
+    let description = describe_packs(&packs);
+    for mut text in prompt_text_query.iter_mut() {
+        if text.0 != description {
+            text.0 = description.clone();
+        }
+    }
+
+    if accept_query.iter().any(|i| *i == Interaction::Pressed) {
+        let requests = packs.accept(&paths);
+        info!(
+            "Packs accepted, downloading {} of them missing from the cache",
+            requests.len()
+        );
+        if !requests.is_empty() {
+            client.send_game_message(ClientToServerMessage::PackRequest(requests));
+        }
+    } else if decline_query.iter().any(|i| *i == Interaction::Pressed) {
+        if packs.any_required() {
+            target.connection_error = Some("This server requires its packs to play".into());
+        } else {
+            info!("Packs declined");
+            packs.consent = PackConsent::Declined;
+        }
+    }
+}
+
 pub fn update_server_connect_loading_screen(
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<CancelButtonMarker>)>,
     mut game_state: ResMut<NextState<GameState>>,
     mut target: ResMut<TargetServer>,
     mut loading_text_query: Query<&mut Text, With<LoadingTextMarker>>,
+    mut textures_text_query: Query<
+        &mut Text,
+        (With<TexturesProgressTextMarker>, Without<LoadingTextMarker>),
+    >,
+    atlases: (Res<AtlasHandles<BlockId>>, Res<AtlasHandles<ItemId>>),
+    packs: Res<ServerPacks>,
+    asset_server: Res<AssetServer>,
     mut main_counter: Local<u64>,
     mut dot_counter: Local<u64>,
 ) {
     *main_counter += 1;
 
-    if (*main_counter).is_multiple_of(20) {
+    if let Some(error) = &target.connection_error {
+        for mut text in loading_text_query.iter_mut() {
+            if text.0 != *error {
+                text.0 = error.clone();
+            }
+        }
+    } else if (*main_counter).is_multiple_of(20) {
         for mut text in loading_text_query.iter_mut() {
             text.0 = format!(
                 "Connecting to server{}",
@@ -75,11 +238,33 @@ pub fn update_server_connect_loading_screen(
         *dot_counter += 1;
     }
 
+    let (blocks_loaded, blocks_total) = atlases.0.loading_progress(&asset_server);
+    let (items_loaded, items_total) = atlases.1.loading_progress(&asset_server);
+    let (pack_received, pack_total) = packs.download_progress();
+    for mut text in textures_text_query.iter_mut() {
+        text.0 = if pack_total > 0 {
+            format!(
+                "Downloading packs {}/{}",
+                format_bytes(pack_received),
+                format_bytes(pack_total)
+            )
+        } else {
+            format!(
+                "Textures {}/{}",
+                blocks_loaded + items_loaded,
+                blocks_total + items_total
+            )
+        };
+    }
+
     for interaction in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
             info!("Cancel button clicked");
             game_state.set(GameState::Menu);
             target.address = None;
+            target.local_server = None;
+            target.fallback_addresses.clear();
+            target.connection_error = None;
             target.username = None;
             target.session_token = None;
             target.state = TargetServerState::Initial;
@@ -13,6 +13,7 @@ use bevy::{
 use shared::GameFolderPaths;
 
 use crate::input::data::GameAction;
+use crate::input::profiles::KeyProfiles;
 use crate::menus::{MenuButtonAction, MenuState, ScrollingList};
 use crate::KeyMap;
 
@@ -25,6 +26,13 @@ pub struct ClearButton(GameAction, Entity);
 #[derive(Component, Debug, PartialEq, Eq)]
 pub struct EditControlButton(GameAction);
 
+/// Buttons above the bindings which switch between the profiles
+#[derive(Component, Debug, PartialEq, Eq)]
+pub enum ProfileButton {
+    Next,
+    New,
+}
+
 #[derive(Component)]
 pub struct ActionRecorder {
     pub action: GameAction,
@@ -35,6 +43,7 @@ pub fn controls_menu_setup(
     mut commands: Commands,
     assets: Res<AssetServer>,
     key_map: Res<KeyMap>,
+    profiles: Res<KeyProfiles>,
     paths: Res<GameFolderPaths>,
 ) {
     let background_image = load_background_image(&assets);
@@ -121,6 +130,39 @@ pub fn controls_menu_setup(
                                     ..default()
                                 },
                             ));
+                            list.spawn(Node {
+                                column_gap: Val::Px(15.),
+                                margin: UiRect::bottom(Val::Px(20.)),
+                                ..default()
+                            })
+                            .with_children(|row| {
+                                for (button, text) in [
+                                    (ProfileButton::Next, format!("Profile: {}", profiles.active)),
+                                    (ProfileButton::New, "New profile".to_string()),
+                                ] {
+                                    row.spawn((
+                                        Button,
+                                        BackgroundColor(NORMAL_BUTTON),
+                                        BorderRadius::all(Val::Px(10.)),
+                                        Node {
+                                            padding: UiRect::axes(Val::Px(15.), Val::Px(5.)),
+                                            ..default()
+                                        },
+                                        button,
+                                    ))
+                                    .with_children(|btn| {
+                                        btn.spawn((
+                                            Text::new(text),
+                                            TextFont {
+                                                font: font.clone(),
+                                                font_size: 24.,
+                                                ..default()
+                                            },
+                                            TextColor(Color::WHITE),
+                                        ));
+                                    });
+                                }
+                            });
                             for (action, keys) in &key_map.map {
                                 list.spawn((
                                     (
@@ -399,3 +441,24 @@ pub fn controls_update_system(
         }
     }
 }
+
+/// Switches to the next profile or to a new one, then builds the menu again with its bindings
+pub fn controls_profile_system(
+    buttons: Query<(&Interaction, &ProfileButton), Changed<Interaction>>,
+    mut profiles: ResMut<KeyProfiles>,
+    mut key_map: ResMut<KeyMap>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let name = match button {
+            ProfileButton::Next => profiles.next_profile(&profiles.active),
+            ProfileButton::New => profiles.add_profile(&key_map),
+        };
+        profiles.active = name.clone();
+        profiles.switch_to(&name, &mut key_map);
+        menu_state.set(MenuState::SettingsControls);
+    }
+}
@@ -1,11 +1,15 @@
 use super::{MenuButtonAction, MenuState, ScrollingList};
+use crate::constants::{DEMO_WORLD_ARCHIVE, DEMO_WORLD_NAME, SAVE_PATH};
 use crate::ui::assets::*;
 use crate::ui::style::*;
 use crate::world::ClientWorldMap;
-use crate::{constants::SAVE_PATH, GameState, LoadWorldEvent};
+use crate::{GameState, LoadWorldEvent};
+use bevy::image::ImageSampler;
 use bevy::platform::collections::HashMap;
 use bevy::prelude::Resource;
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use bevy::{
     asset::AssetServer,
     color::Color,
@@ -24,6 +28,8 @@ use bevy_simple_text_input::TextInputTextFont;
 use bevy_simple_text_input::{
     TextInputInactive, TextInputPlaceholder, TextInputSettings, TextInputValue,
 };
+use server::ColumnSample;
+use shared::world::{BiomeType, WorldSeed};
 use shared::GameFolderPaths;
 use std::io;
 use std::{
@@ -33,6 +39,10 @@ use std::{
 
 pub struct WorldItem {
     pub name: String,
+    /// Only set for worlds created in this menu, existing worlds already have their seed saved
+    pub seed: Option<u32>,
+    /// Only set for worlds created in this menu, the save remembers whether the platform was built
+    pub spawn_platform: bool,
 }
 
 #[derive(Component, Default)]
@@ -50,15 +60,46 @@ pub enum MultiplayerButtonAction {
 #[derive(Component)]
 pub struct WorldNameInput;
 
+#[derive(Component)]
+pub struct WorldSeedInput;
+
+#[derive(Component)]
+pub struct SeedPreviewButton;
+
+#[derive(Component)]
+pub struct SeedPreviewImage;
+
+/// Whether the next created world gets a spawn platform and a starter chest
+#[derive(Component, Default)]
+pub struct SpawnPlatformToggle(pub bool);
+
+/// Picks a seed listed by the world creation fields, along with its preview
+#[derive(Component)]
+pub struct ShowcaseSeedButton {
+    seed: u32,
+    preview: Handle<Image>,
+}
+
+// Side of the previewed area, in blocks (one pixel per block)
+const SEED_PREVIEW_SIZE: u32 = 128;
+
+/// Seeds suggested when creating a world, the first one is the seed of the demo world
+const SHOWCASE_SEEDS: [u32; 4] = [20240601, 42, 1337, 987654321];
+// Side of the preview shown in a showcase seed button, in pixels
+const SHOWCASE_THUMBNAIL_SIZE: f32 = 64.;
+
 #[derive(Resource, Default, Debug, Clone)]
 pub struct SelectedWorld {
     pub name: Option<String>,
+    pub seed: Option<u32>,
+    pub spawn_platform: bool,
 }
 
 pub fn solo_menu_setup(
     mut commands: Commands,
     assets_server: Res<AssetServer>,
     _paths: Res<GameFolderPaths>,
+    mut images: ResMut<Assets<Image>>,
 ) {
     let background_image = load_background_image(&assets_server);
     let font = load_font(&assets_server);
@@ -185,6 +226,68 @@ pub fn solo_menu_setup(
                         ),
                     ));
 
+                    wrapper.spawn((
+                        (
+                            BorderColor(BACKGROUND_COLOR),
+                            BackgroundColor(Color::BLACK),
+                            btn_style.clone(),
+                        ),
+                        WorldSeedInput,
+                        (
+                            TextInput,
+                            TextInputSettings {
+                                retain_on_submit: true,
+                                mask_character: None,
+                            },
+                            TextInputPlaceholder {
+                                value: "Seed (random if empty)".into(),
+                                ..default()
+                            },
+                            TextInputInactive(true),
+                            TextInputTextFont(txt_font.clone()),
+                            TextInputTextColor(txt_color),
+                            TextInputValue("".to_string()),
+                        ),
+                    ));
+
+                    wrapper
+                        .spawn((
+                            (
+                                Button,
+                                BorderColor(Color::BLACK),
+                                BackgroundColor(BACKGROUND_COLOR),
+                                btn_style.clone(),
+                                ImageNode::new(button_background_image.clone()),
+                            ),
+                            SeedPreviewButton,
+                        ))
+                        .with_children(|btn| {
+                            btn.spawn((Text::new("Preview seed"), txt_font.clone(), txt_color));
+                        });
+
+                    wrapper
+                        .spawn((
+                            (
+                                Button,
+                                BorderColor(Color::BLACK),
+                                BackgroundColor(BACKGROUND_COLOR),
+                                {
+                                    let mut style = btn_style.clone();
+                                    style.grid_column = GridPlacement::span(2);
+                                    style
+                                },
+                                ImageNode::new(button_background_image.clone()),
+                            ),
+                            SpawnPlatformToggle::default(),
+                        ))
+                        .with_children(|btn| {
+                            btn.spawn((
+                                Text::new(spawn_platform_label(false)),
+                                txt_font.clone(),
+                                txt_color,
+                            ));
+                        });
+
                     wrapper
                         .spawn((
                             (
@@ -223,6 +326,69 @@ pub fn solo_menu_setup(
                             btn.spawn((Text::new("Back to menu"), txt_font.clone(), txt_color));
                         });
                 });
+
+            // Seeds to pick from, in the free space left of the world list
+            root.spawn(Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(3.),
+                bottom: Val::Percent(5.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(5.),
+                ..default()
+            })
+            .with_children(|showcase| {
+                showcase.spawn((Text::new("Showcase seeds"), txt_font.clone(), txt_color));
+                for seed in SHOWCASE_SEEDS {
+                    let preview = images.add(build_seed_preview(seed));
+                    showcase
+                        .spawn((
+                            Button,
+                            Node {
+                                flex_direction: FlexDirection::Row,
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(8.),
+                                padding: UiRect::all(Val::Px(4.)),
+                                border: UiRect::all(Val::Px(2.)),
+                                ..default()
+                            },
+                            BorderColor(BACKGROUND_COLOR),
+                            BackgroundColor(Color::BLACK),
+                            ShowcaseSeedButton {
+                                seed,
+                                preview: preview.clone(),
+                            },
+                        ))
+                        .with_children(|btn| {
+                            btn.spawn((
+                                ImageNode::new(preview),
+                                Node {
+                                    width: Val::Px(SHOWCASE_THUMBNAIL_SIZE),
+                                    height: Val::Px(SHOWCASE_THUMBNAIL_SIZE),
+                                    ..default()
+                                },
+                            ));
+                            btn.spawn((Text::new(seed.to_string()), txt_font.clone(), txt_color));
+                        });
+                }
+            });
+
+            // Top-down heightmap of the spawn area, filled by `seed_preview_action`
+            root.spawn((
+                SeedPreviewImage,
+                Node {
+                    position_type: PositionType::Absolute,
+                    right: Val::Percent(3.),
+                    bottom: Val::Percent(5.),
+                    width: Val::Px(2. * SEED_PREVIEW_SIZE as f32),
+                    height: Val::Px(2. * SEED_PREVIEW_SIZE as f32),
+                    border: UiRect::all(Val::Px(2.)),
+                    ..default()
+                },
+                BorderColor(BACKGROUND_COLOR),
+                ImageNode::default(),
+                Visibility::Hidden,
+            ));
         });
 }
 
@@ -256,7 +422,11 @@ pub fn list_worlds(
             let world_ron_path = full_path.join("world.ron");
             if world_ron_path.exists() {
                 add_world_item(
-                    path_str,
+                    WorldItem {
+                        name: path_str,
+                        seed: None,
+                        spawn_platform: false,
+                    },
                     &mut commands,
                     &assets,
                     &mut list,
@@ -272,7 +442,7 @@ pub fn list_worlds(
 }
 
 fn add_world_item(
-    name: String,
+    item: WorldItem,
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     list: &mut WorldList,
@@ -280,6 +450,7 @@ fn add_world_item(
     world_map: &mut ClientWorldMap,
     paths: &Res<GameFolderPaths>,
 ) {
+    let name = &item.name;
     info!(
         "Adding world to list : name = {:?}, entity={:?}",
         name, list_entity
@@ -346,7 +517,10 @@ fn add_world_item(
     let txt = commands
         .spawn((
             (
-                Text::new(format!("{name}\n")),
+                Text::new(match item.seed {
+                    Some(seed) => format!("{name}\nSeed: {seed}"),
+                    None => format!("{name}\n"),
+                }),
                 TextFont {
                     font: asset_server.load("./fonts/RustCraftRegular-Bmg3.otf"),
                     font_size: 20.,
@@ -368,7 +542,7 @@ fn add_world_item(
 
     commands.entity(list_entity).add_children(&[world]);
 
-    list.worlds.insert(world, WorldItem { name: name.clone() });
+    list.worlds.insert(world, item);
 }
 
 fn generate_new_world_name(world_list: &WorldList) -> String {
@@ -388,10 +562,12 @@ fn generate_new_world_name(world_list: &WorldList) -> String {
 }
 
 pub fn solo_action(
-    (interaction_query, mut name_query, mut list_query): (
+    (interaction_query, mut name_query, mut seed_query, mut list_query, platform_query): (
         Query<(&Interaction, &MultiplayerButtonAction), (Changed<Interaction>, With<Button>)>,
-        Query<&mut TextInputValue, With<WorldNameInput>>,
+        Query<&mut TextInputValue, (With<WorldNameInput>, Without<WorldSeedInput>)>,
+        Query<&mut TextInputValue, With<WorldSeedInput>>,
         Query<(Entity, &mut WorldList), With<WorldList>>,
+        Query<&SpawnPlatformToggle>,
     ),
     (asset_server, mut menu_state, mut game_state, mut world_map, mut selected_world): (
         Res<AssetServer>,
@@ -425,8 +601,24 @@ pub fn solo_action(
                             name.0.clone()
                         };
 
+                        // if no seed, the server will pick a random one
+                        let seed = match seed_query.single_mut() {
+                            Ok(mut seed) if !seed.0.trim().is_empty() => {
+                                let value = WorldSeed::from_text(&seed.0).0;
+                                seed.0 = "".into();
+                                Some(value)
+                            }
+                            _ => None,
+                        };
+
                         add_world_item(
-                            new_name,
+                            WorldItem {
+                                name: new_name,
+                                seed,
+                                spawn_platform: platform_query
+                                    .single()
+                                    .is_ok_and(|toggle| toggle.0),
+                            },
                             &mut commands,
                             &asset_server,
                             &mut list,
@@ -443,11 +635,13 @@ pub fn solo_action(
                     if let Some(world) = list.worlds.get(&world_entity) {
                         // update ressource name
                         selected_world.name = Some(world.name.clone());
+                        selected_world.seed = world.seed;
+                        selected_world.spawn_platform = world.spawn_platform;
 
                         load_event.write(LoadWorldEvent {
                             world_name: world.name.clone(),
                         });
-                        game_state.set(GameState::PreGameLoading);
+                        game_state.set(GameState::Connecting);
                         menu_state.set(MenuState::Disabled);
                     }
                 }
@@ -466,6 +660,148 @@ pub fn solo_action(
     }
 }
 
+fn spawn_platform_label(enabled: bool) -> String {
+    format!("Spawn platform: {}", if enabled { "On" } else { "Off" })
+}
+
+pub fn spawn_platform_toggle_action(
+    mut toggle_query: Query<
+        (&Interaction, &mut SpawnPlatformToggle, &Children),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, mut toggle, children) in toggle_query.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        toggle.0 = !toggle.0;
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.0 = spawn_platform_label(toggle.0);
+            }
+        }
+    }
+}
+
+pub fn seed_preview_action(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<SeedPreviewButton>)>,
+    mut seed_query: Query<&mut TextInputValue, With<WorldSeedInput>>,
+    mut preview_query: Query<(&mut ImageNode, &mut Visibility), With<SeedPreviewImage>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Ok(mut seed_text) = seed_query.single_mut() else {
+            return;
+        };
+
+        // Pick a random seed if none was typed, so that the previewed world is the one that gets created
+        if seed_text.0.trim().is_empty() {
+            seed_text.0 = rand::random::<u32>().to_string();
+        }
+        let seed = WorldSeed::from_text(&seed_text.0);
+        debug!("Previewing seed {}", seed.0);
+
+        let handle = images.add(build_seed_preview(seed.0));
+        for (mut image_node, mut visibility) in preview_query.iter_mut() {
+            image_node.image = handle.clone();
+            *visibility = Visibility::Visible;
+        }
+    }
+}
+
+/// Fills the seed field with the picked showcase seed and shows its preview
+pub fn showcase_seed_action(
+    interaction_query: Query<(&Interaction, &ShowcaseSeedButton), Changed<Interaction>>,
+    mut seed_query: Query<&mut TextInputValue, With<WorldSeedInput>>,
+    mut preview_query: Query<(&mut ImageNode, &mut Visibility), With<SeedPreviewImage>>,
+) {
+    for (interaction, showcase) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if let Ok(mut seed_text) = seed_query.single_mut() {
+            seed_text.0 = showcase.seed.to_string();
+        }
+        for (mut image_node, mut visibility) in preview_query.iter_mut() {
+            image_node.image = showcase.preview.clone();
+            *visibility = Visibility::Visible;
+        }
+    }
+}
+
+// Players spawn around the origin, so the preview is centered on it
+fn build_seed_preview(seed: u32) -> Image {
+    let samples = server::sample_surface(seed, IVec2::ZERO, SEED_PREVIEW_SIZE);
+
+    let mut data = Vec::with_capacity(samples.len() * 4);
+    for sample in samples.iter() {
+        let [r, g, b] = seed_preview_color(sample);
+        data.extend_from_slice(&[r, g, b, 255]);
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: SEED_PREVIEW_SIZE,
+            height: SEED_PREVIEW_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+fn seed_preview_color(sample: &ColumnSample) -> [u8; 3] {
+    if sample.is_underwater {
+        // the deeper, the darker
+        let depth = (62 - sample.height).clamp(0, 16) as f32 / 16.;
+        let shade = 1. - 0.6 * depth;
+        return [
+            (40. * shade) as u8,
+            (90. * shade) as u8,
+            (200. * shade) as u8,
+        ];
+    }
+
+    let base: [f32; 3] = match sample.biome {
+        BiomeType::Desert | BiomeType::ShallowOcean | BiomeType::Ocean | BiomeType::DeepOcean => {
+            [219., 207., 142.]
+        }
+        BiomeType::IcePlain => [235., 240., 245.],
+        BiomeType::Forest => [60., 130., 50.],
+        BiomeType::FlowerPlains => [120., 180., 80.],
+        BiomeType::HighMountainGrass => [110., 140., 100.],
+        BiomeType::Plains | BiomeType::MediumMountain => [95., 160., 70.],
+    };
+
+    // higher terrain is drawn brighter
+    let shade = 0.7 + (sample.height - 62).clamp(0, 20) as f32 / 20. * 0.5;
+    base.map(|c| (c * shade).min(255.) as u8)
+}
+
+/// Extracts the demo world shipped with the game into the saves, unless it was already played.
+/// Returns the name of its save
+pub fn install_demo_world(paths: &GameFolderPaths) -> Result<String, Box<dyn std::error::Error>> {
+    let saves_dir = paths.game_folder_path.join(SAVE_PATH);
+    if saves_dir.join(DEMO_WORLD_NAME).join("world.ron").exists() {
+        return Ok(DEMO_WORLD_NAME.to_string());
+    }
+
+    let archive = paths.assets_folder_path.join(DEMO_WORLD_ARCHIVE);
+    info!("Installing the demo world from {}", archive.display());
+    // A leftover folder without world.ron is an interrupted install, it is replaced
+    server::extract_world_archive(&archive, &saves_dir, true)
+}
+
 pub fn delete_save_files(
     world_name: &str,
     game_folder_path: &Res<GameFolderPaths>,
@@ -0,0 +1,116 @@
+use crate::{game::PreLoadingCompletion, world::ClientWorldMap, GameState};
+use bevy::{color::palettes::tailwind::YELLOW_500, prelude::*};
+
+/// Players get in the game after that long even if chunks are missing, the server may still be generating them
+const WORLD_LOADING_TIMEOUT_SECS: f32 = 10.0;
+const PROGRESS_BAR_WIDTH: f32 = 400.0;
+
+#[derive(Component)]
+pub struct WorldLoadingTextMarker;
+
+#[derive(Component)]
+pub struct WorldLoadingBarMarker;
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct WorldLoadingTimer(Timer);
+
+pub fn setup_world_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((Camera2d, StateScoped(GameState::LoadingWorld)));
+
+    let root_bundle = (
+        Node {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(30.),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        StateScoped(GameState::LoadingWorld),
+    );
+
+    let loading_text_bundle = (
+        Text::new("Loading world"),
+        TextFont {
+            font: asset_server.load("fonts/FiraSans-SemiBold.ttf"),
+            font_size: 67.0,
+            ..default()
+        },
+        WorldLoadingTextMarker,
+    );
+
+    let bar_background_bundle = (
+        Node {
+            width: Val::Px(PROGRESS_BAR_WIDTH),
+            height: Val::Px(24.),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+    );
+
+    let bar_bundle = (
+        Node {
+            width: Val::Percent(0.),
+            height: Val::Percent(100.),
+            ..default()
+        },
+        BackgroundColor(Color::from(YELLOW_500)),
+        WorldLoadingBarMarker,
+    );
+
+    commands.spawn(root_bundle).with_children(|p| {
+        p.spawn(loading_text_bundle);
+        p.spawn(bar_background_bundle).with_children(|bar| {
+            bar.spawn(bar_bundle);
+        });
+    });
+
+    commands.insert_resource(WorldLoadingTimer(Timer::from_seconds(
+        WORLD_LOADING_TIMEOUT_SECS,
+        TimerMode::Once,
+    )));
+}
+
+/// Shows how many of the chunks around the player were received, and starts the game once they all are
+pub fn update_world_loading_screen(
+    world_map: Res<ClientWorldMap>,
+    loading: Res<PreLoadingCompletion>,
+    mut timer: ResMut<WorldLoadingTimer>,
+    time: Res<Time>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut text_query: Query<&mut Text, With<WorldLoadingTextMarker>>,
+    mut bar_query: Query<&mut Node, With<WorldLoadingBarMarker>>,
+) {
+    let received = world_map.map.len() as u32;
+    let expected = loading.spawn_chunks;
+
+    let progress = if expected == 0 {
+        1.0
+    } else {
+        (received as f32 / expected as f32).min(1.0)
+    };
+
+    for mut text in text_query.iter_mut() {
+        text.0 = format!(
+            "Loading spawn area ({}/{} chunks)",
+            received.min(expected),
+            expected
+        );
+    }
+    for mut node in bar_query.iter_mut() {
+        node.width = Val::Percent(progress * 100.);
+    }
+
+    if progress >= 1.0 || timer.tick(time.delta()).finished() {
+        if progress < 1.0 {
+            warn!(
+                "Only {}/{} chunks received, starting the game anyway",
+                received, expected
+            );
+        }
+        game_state.set(GameState::Game);
+    }
+}
@@ -1,5 +1,8 @@
+pub mod accessibility;
 pub mod assets;
 pub mod button;
 pub mod hud;
+pub mod lang;
 pub mod menus;
+pub mod narration;
 pub mod style;
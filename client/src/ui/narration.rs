@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+
+use bevy::prelude::*;
+use shared::players::{Player, MAX_PLAYER_HEALTH};
+
+use crate::network::CachedChatConversation;
+use crate::player::CurrentPlayerMarker;
+use crate::ui::accessibility::AccessibilitySettings;
+use crate::ui::hud::toasts::ToastEvent;
+use crate::ui::lang::Localization;
+
+/// Health under which the low health warning is read, as a fraction of the maximum
+const LOW_HEALTH_FRACTION: f32 = 0.3;
+
+/// Kinds of narrated text, each of them can be turned on separately in the options
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NarrationCategory {
+    Chat,
+    Notifications,
+    LowHealth,
+}
+
+impl NarrationCategory {
+    fn is_enabled(&self, settings: &AccessibilitySettings) -> bool {
+        match self {
+            NarrationCategory::Chat => settings.narrate_chat,
+            NarrationCategory::Notifications => settings.narrate_notifications,
+            NarrationCategory::LowHealth => settings.narrate_low_health,
+        }
+    }
+}
+
+/// Text to read aloud, dropped if its category is turned off
+#[derive(Event, Debug)]
+pub struct NarrationEvent {
+    pub category: NarrationCategory,
+    pub text: String,
+}
+
+/// Something able to read text aloud
+pub trait NarrationBackend: Send + Sync {
+    fn speak(&mut self, text: &str);
+    /// Called every frame, for backends speaking one sentence after the other
+    fn update(&mut self) {}
+}
+
+/// Sentences waiting for the one being spoken, the oldest are dropped past this
+const MAX_QUEUED_SENTENCES: usize = 8;
+
+/// Uses the speech command of the platform: `say` on macOS, `spd-say` on Linux and
+/// the speech synthesizer of PowerShell on Windows
+#[derive(Default)]
+pub struct SystemSpeech {
+    /// Set once the command failed to start, to stop trying
+    unavailable: bool,
+    /// Command speaking the current sentence, only one runs at a time so that voices do not overlap
+    speaking: Option<Child>,
+    queue: VecDeque<String>,
+}
+
+impl SystemSpeech {
+    fn command(text: &str) -> Command {
+        if cfg!(target_os = "macos") {
+            // `say` reads the text on its input, so that a text starting with `-` is not taken for an option
+            Command::new("say")
+        } else if cfg!(target_os = "windows") {
+            // The text goes through the environment so that it is never parsed as a script
+            let mut command = Command::new("powershell");
+            command
+                .args([
+                    "-NoProfile",
+                    "-Command",
+                    "Add-Type -AssemblyName System.Speech; \
+                     (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak($env:RUSTCRAFT_NARRATION)",
+                ])
+                .env("RUSTCRAFT_NARRATION", text);
+            command
+        } else {
+            let mut command = Command::new("spd-say");
+            command.arg("--").arg(text);
+            command
+        }
+    }
+
+    fn start(&mut self, text: &str) {
+        let stdin = if cfg!(target_os = "macos") {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        let spawned = Self::command(text)
+            .stdin(stdin)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                // Dropping the input closes it, which tells `say` the text is complete
+                if let Some(mut input) = child.stdin.take() {
+                    let _ = input.write_all(text.as_bytes());
+                }
+                self.speaking = Some(child);
+            }
+            Err(e) => {
+                warn!(
+                    "Text-to-speech is not available, narration is disabled: {}",
+                    e
+                );
+                self.unavailable = true;
+                self.queue.clear();
+            }
+        }
+    }
+}
+
+impl NarrationBackend for SystemSpeech {
+    fn speak(&mut self, text: &str) {
+        if self.unavailable {
+            return;
+        }
+        self.queue.push_back(text.to_string());
+        if self.queue.len() > MAX_QUEUED_SENTENCES {
+            self.queue.pop_front();
+        }
+        self.update();
+    }
+
+    fn update(&mut self) {
+        if let Some(child) = &mut self.speaking {
+            // Waiting on the finished command reaps it, it would otherwise linger as a zombie
+            if let Ok(None) = child.try_wait() {
+                return;
+            }
+            self.speaking = None;
+        }
+        if let Some(text) = self.queue.pop_front() {
+            self.start(&text);
+        }
+    }
+}
+
+impl Drop for SystemSpeech {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.speaking.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Reads the narrated text, the backend can be replaced to use another speech engine
+#[derive(Resource)]
+pub struct Narrator {
+    pub backend: Box<dyn NarrationBackend>,
+}
+
+impl Default for Narrator {
+    fn default() -> Self {
+        Self {
+            backend: Box::new(SystemSpeech::default()),
+        }
+    }
+}
+
+pub fn narration_system(
+    mut events: EventReader<NarrationEvent>,
+    mut narrator: ResMut<Narrator>,
+    settings: Res<AccessibilitySettings>,
+) {
+    narrator.backend.update();
+    for event in events.read() {
+        if event.category.is_enabled(&settings) {
+            debug!("Narrating {:?}: {}", event.category, event.text);
+            narrator.backend.speak(&event.text);
+        }
+    }
+}
+
+/// Narrates the chat messages received since the game started, not the older ones
+pub fn chat_narration_system(
+    chat: Res<CachedChatConversation>,
+    mut ev_narration: EventWriter<NarrationEvent>,
+    mut last_narrated_ts: Local<Option<u64>>,
+    localization: Res<Localization>,
+) {
+    let Some(conversation) = &chat.data else {
+        return;
+    };
+    if !chat.is_changed() {
+        return;
+    }
+
+    let latest = conversation.messages.iter().map(|m| m.timestamp).max();
+    let Some(last_narrated) = *last_narrated_ts else {
+        *last_narrated_ts = Some(latest.unwrap_or(0));
+        return;
+    };
+
+    for message in &conversation.messages {
+        if message.timestamp > last_narrated {
+            ev_narration.write(NarrationEvent {
+                category: NarrationCategory::Chat,
+                text: format!(
+                    "{} says {}",
+                    message.author,
+                    localization.localize(&message.content)
+                ),
+            });
+        }
+    }
+    *last_narrated_ts = Some(latest.unwrap_or(last_narrated).max(last_narrated));
+}
+
+pub fn notification_narration_system(
+    mut toasts: EventReader<ToastEvent>,
+    mut ev_narration: EventWriter<NarrationEvent>,
+) {
+    for toast in toasts.read() {
+        ev_narration.write(NarrationEvent {
+            category: NarrationCategory::Notifications,
+            text: toast.0.clone(),
+        });
+    }
+}
+
+/// Warns once each time health drops under the low health threshold
+pub fn low_health_narration_system(
+    player: Query<&Player, With<CurrentPlayerMarker>>,
+    mut ev_narration: EventWriter<NarrationEvent>,
+    mut was_low: Local<bool>,
+) {
+    let Ok(player) = player.single() else {
+        return;
+    };
+    let is_low = player.health > 0.0 && player.health < MAX_PLAYER_HEALTH * LOW_HEALTH_FRACTION;
+    if is_low && !*was_low {
+        ev_narration.write(NarrationEvent {
+            category: NarrationCategory::LowHealth,
+            text: "Low health".to_string(),
+        });
+    }
+    *was_low = is_low;
+}
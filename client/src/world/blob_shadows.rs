@@ -0,0 +1,167 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use shared::players::Player;
+use shared::world::{BlockTransparency, WorldMap};
+
+use crate::entities::stack::StackMarker;
+use crate::mob::MobRoot;
+use crate::world::{ClientWorldMap, ShadowQuality, VideoSettings};
+use crate::GameState;
+
+/// Blocks below an entity which are searched for ground to put its shadow on
+const BLOB_SHADOW_REACH: i32 = 4;
+/// Height of the shadow above the ground, so that it does not flicker through the block
+const BLOB_SHADOW_OFFSET: f32 = 0.02;
+/// Pixels along each side of the shadow texture
+const BLOB_SHADOW_TEXTURE_SIZE: u32 = 32;
+/// Opacity of the center of the shadow, 0 to 255
+const BLOB_SHADOW_ALPHA: f32 = 150.;
+
+const MOB_SHADOW_RADIUS: f32 = 0.5;
+const STACK_SHADOW_RADIUS: f32 = 0.2;
+
+/// Dark disc under an entity, standing in for its shadow while shadow maps are off
+#[derive(Component)]
+pub struct BlobShadow {
+    owner: Entity,
+    radius: f32,
+}
+
+/// Mesh and material shared by all the blob shadows, so that they are drawn as one batch
+#[derive(Resource)]
+pub struct BlobShadowAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+/// Disc fading out towards its edge
+fn blob_shadow_image() -> Image {
+    let half = BLOB_SHADOW_TEXTURE_SIZE as f32 / 2.;
+    let mut data =
+        Vec::with_capacity((BLOB_SHADOW_TEXTURE_SIZE * BLOB_SHADOW_TEXTURE_SIZE * 4) as usize);
+    for y in 0..BLOB_SHADOW_TEXTURE_SIZE {
+        for x in 0..BLOB_SHADOW_TEXTURE_SIZE {
+            let distance = Vec2::new(x as f32 + 0.5 - half, y as f32 + 0.5 - half).length() / half;
+            let alpha = (1. - distance).clamp(0., 1.).sqrt() * BLOB_SHADOW_ALPHA;
+            data.extend_from_slice(&[0, 0, 0, alpha as u8]);
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: BLOB_SHADOW_TEXTURE_SIZE,
+            height: BLOB_SHADOW_TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+pub fn setup_blob_shadows(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.insert_resource(BlobShadowAssets {
+        mesh: meshes.add(Plane3d::default().mesh().size(2., 2.)),
+        material: materials.add(StandardMaterial {
+            base_color_texture: Some(images.add(blob_shadow_image())),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+/// Gives a blob shadow to each player, mob and dropped item that appears
+pub fn attach_blob_shadows_system(
+    mut commands: Commands,
+    owners: Query<
+        (Entity, Option<&Player>, Has<StackMarker>),
+        Or<(Added<Player>, Added<MobRoot>, Added<StackMarker>)>,
+    >,
+    assets: Option<Res<BlobShadowAssets>>,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+
+    for (owner, player, is_stack) in owners.iter() {
+        let radius = match player {
+            Some(player) => player.width * 0.75,
+            None if is_stack => STACK_SHADOW_RADIUS,
+            None => MOB_SHADOW_RADIUS,
+        };
+        commands.spawn((
+            Name::new("BlobShadow"),
+            StateScoped(GameState::Game),
+            BlobShadow { owner, radius },
+            Mesh3d(assets.mesh.clone()),
+            MeshMaterial3d(assets.material.clone()),
+            Transform::from_scale(Vec3::ZERO),
+            Visibility::Hidden,
+            NotShadowCaster,
+            NotShadowReceiver,
+        ));
+    }
+}
+
+/// Height of the top of the first block under `position`, if there is one close enough
+fn ground_below(world_map: &ClientWorldMap, position: Vec3) -> Option<f32> {
+    let start = position.floor().as_ivec3();
+    (0..=BLOB_SHADOW_REACH)
+        .map(|depth| start - IVec3::Y * depth)
+        .find(|block_pos| {
+            world_map
+                .get_block_by_coordinates(block_pos)
+                .is_some_and(|block| {
+                    !matches!(
+                        block.id.get_visibility(),
+                        BlockTransparency::Decoration | BlockTransparency::Invisible
+                    )
+                })
+        })
+        .map(|block_pos| block_pos.y as f32 + 1.)
+}
+
+/// Puts each blob shadow on the ground under its entity, shrinking as the entity gets higher,
+/// and hides them all while the sun and the moon cast real shadows
+pub fn blob_shadows_system(
+    mut commands: Commands,
+    mut shadows: Query<(Entity, &BlobShadow, &mut Transform, &mut Visibility)>,
+    owners: Query<&GlobalTransform>,
+    world_map: Res<ClientWorldMap>,
+    video: Res<VideoSettings>,
+) {
+    for (entity, shadow, mut transform, mut visibility) in shadows.iter_mut() {
+        let Ok(owner) = owners.get(shadow.owner) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let position = owner.translation();
+        let ground = (video.shadows == ShadowQuality::Off)
+            .then(|| ground_below(&world_map, position))
+            .flatten();
+        let Some(ground) = ground else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+
+        let height = (position.y - ground).max(0.);
+        let scale = shadow.radius * (1. - height / (BLOB_SHADOW_REACH as f32 + 1.)).max(0.);
+        transform.set_if_neq(
+            Transform::from_xyz(position.x, ground + BLOB_SHADOW_OFFSET, position.z)
+                .with_scale(Vec3::new(scale, 1., scale)),
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use shared::world::{chunk_offset_to_global_pos, global_block_to_chunk_pos, MAX_LIGHT_LEVEL};
+
+use crate::player::CurrentPlayerMarker;
+use crate::world::ClientWorldMap;
+use crate::GameState;
+
+/// Chunks around the player searched for blocks giving off light
+const BLOCK_LIGHT_CHUNK_RADIUS: i32 = 2;
+/// Point lights are costly, only the closest blocks get one
+const MAX_BLOCK_LIGHTS: usize = 16;
+/// Seconds between two searches for blocks giving off light
+const BLOCK_LIGHT_REFRESH: f32 = 0.5;
+/// Reach in blocks of a light at the brightest level
+const BLOCK_LIGHT_MAX_RANGE: f32 = 15.;
+/// Intensity in lumens of a light at the brightest level
+const BLOCK_LIGHT_MAX_INTENSITY: f32 = 200_000.;
+
+/// Point light standing in for the light of the block at this position
+#[derive(Component)]
+pub struct BlockLight {
+    position: IVec3,
+    level: u8,
+}
+
+/// Gives a point light to the blocks giving off light closest to the player, light blocks have no geometry of their own
+pub fn block_lights_system(
+    mut commands: Commands,
+    mut lights: Query<(Entity, &mut BlockLight, &mut PointLight)>,
+    player: Query<&Transform, With<CurrentPlayerMarker>>,
+    world_map: Res<ClientWorldMap>,
+    mut refresh: Local<Option<Timer>>,
+    time: Res<Time>,
+) {
+    let refresh = refresh
+        .get_or_insert_with(|| Timer::from_seconds(BLOCK_LIGHT_REFRESH, TimerMode::Repeating));
+    if !refresh.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Ok(player) = player.single() else {
+        return;
+    };
+
+    let player_pos = player.translation;
+    let center = global_block_to_chunk_pos(&player_pos.floor().as_ivec3());
+    let mut sources = Vec::new();
+    for x in -BLOCK_LIGHT_CHUNK_RADIUS..=BLOCK_LIGHT_CHUNK_RADIUS {
+        for y in -BLOCK_LIGHT_CHUNK_RADIUS..=BLOCK_LIGHT_CHUNK_RADIUS {
+            for z in -BLOCK_LIGHT_CHUNK_RADIUS..=BLOCK_LIGHT_CHUNK_RADIUS {
+                let chunk_pos = center + IVec3::new(x, y, z);
+                let Some(chunk) = world_map.map.get(&chunk_pos) else {
+                    continue;
+                };
+                sources.extend(chunk.map.iter().filter_map(|(local, block)| {
+                    let level = block.light_level();
+                    (level > 0).then(|| (chunk_offset_to_global_pos(&chunk_pos, local), level))
+                }));
+            }
+        }
+    }
+    sources.sort_by(|(a, _), (b, _)| {
+        let distance = |pos: &IVec3| (pos.as_vec3() + 0.5).distance_squared(player_pos);
+        distance(a).total_cmp(&distance(b))
+    });
+    sources.truncate(MAX_BLOCK_LIGHTS);
+
+    for (entity, mut light, mut point_light) in lights.iter_mut() {
+        match sources.iter().position(|(pos, _)| *pos == light.position) {
+            Some(index) => {
+                let (_, level) = sources.swap_remove(index);
+                if light.level != level {
+                    light.level = level;
+                    *point_light = block_point_light(level);
+                }
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (position, level) in sources {
+        commands.spawn((
+            Name::new("BlockLight"),
+            StateScoped(GameState::Game),
+            BlockLight { position, level },
+            block_point_light(level),
+            Transform::from_translation(position.as_vec3() + 0.5),
+        ));
+    }
+}
+
+fn block_point_light(level: u8) -> PointLight {
+    let strength = level as f32 / MAX_LIGHT_LEVEL as f32;
+    PointLight {
+        color: Color::srgb(1., 0.9, 0.75),
+        intensity: BLOCK_LIGHT_MAX_INTENSITY * strength,
+        range: BLOCK_LIGHT_MAX_RANGE * strength,
+        shadows_enabled: false,
+        ..default()
+    }
+}
@@ -1,17 +1,27 @@
 use crate::player::CurrentPlayerMarker;
 use crate::world::materials::MaterialResource;
 use crate::world::time::ClientTime;
+use crate::world::{ShadowQuality, VideoSettings};
 use crate::GameState;
 use crate::{
-    constants::{CELESTIAL_DISTANCE, CELESTIAL_SIZE, DAY_DURATION_IN_TICKS},
+    constants::{CELESTIAL_DISTANCE, CELESTIAL_SIZE},
     world::GlobalMaterial,
 };
 use bevy::{
-    pbr::{NotShadowCaster, NotShadowReceiver},
+    pbr::{
+        CascadeShadowConfig, CascadeShadowConfigBuilder, DirectionalLightShadowMap,
+        NotShadowCaster, NotShadowReceiver,
+    },
     prelude::*,
 };
+use shared::DAY_DURATION_IN_TICKS;
 use std::f32::consts::PI;
 
+/// Height of the sun, relative to its highest, over which sunrise and sunset fade the sky
+const TWILIGHT_HEIGHT: f32 = 0.2;
+/// Blocks from the camera to the end of the first shadow cascade, which gets the sharpest shadows
+const FIRST_CASCADE_DISTANCE: f32 = 12.;
+
 //
 #[derive(Component)]
 pub struct CelestialRoot;
@@ -128,8 +138,78 @@ pub fn update_celestial_bodies(
     let normalized_time = (time.0 % DAY_DURATION_IN_TICKS) as f32 / DAY_DURATION_IN_TICKS as f32;
     let angle = normalized_time * 2.0 * PI;
 
-    // Apply the rotation to celestial bodies
+    // Apply the rotation to celestial bodies, only when the time moved, since the shadows are drawn again
+    // each time the lights turn
+    let rotation = Quat::from_rotation_x(angle);
     for mut tr in query.iter_mut() {
-        tr.rotation = Quat::from_rotation_x(angle);
+        if tr.rotation != rotation {
+            tr.rotation = rotation;
+        }
     }
 }
+
+/// Cascades of the shadow maps for `quality`: more of them reach further, with sharper shadows up close
+fn cascade_shadow_config(quality: ShadowQuality) -> CascadeShadowConfig {
+    let (num_cascades, maximum_distance) = match quality {
+        ShadowQuality::Off | ShadowQuality::Low => (2, 48.),
+        ShadowQuality::High => (4, 128.),
+    };
+    CascadeShadowConfigBuilder {
+        num_cascades,
+        first_cascade_far_bound: FIRST_CASCADE_DISTANCE,
+        maximum_distance,
+        ..default()
+    }
+    .build()
+}
+
+/// Only the sun or the moon, whichever is above the horizon, casts shadows, with the cascades of the video settings
+pub fn celestial_shadows_system(
+    mut commands: Commands,
+    mut lights: Query<
+        (Entity, &mut DirectionalLight, Has<SunLight>),
+        Or<(With<SunLight>, With<MoonLight>)>,
+    >,
+    new_lights: Query<
+        (),
+        (
+            Added<DirectionalLight>,
+            Or<(With<SunLight>, With<MoonLight>)>,
+        ),
+    >,
+    mut shadow_map: ResMut<DirectionalLightShadowMap>,
+    video: Res<VideoSettings>,
+    time: Res<ClientTime>,
+) {
+    let sun_up = sun_direction(time.0).y > 0.0;
+    let reconfigure = video.is_changed() || !new_lights.is_empty();
+
+    for (entity, mut light, is_sun) in lights.iter_mut() {
+        // Changing the light every frame would mark it changed, which is only worth it when it gets turned on or off
+        let enabled = video.shadows != ShadowQuality::Off && is_sun == sun_up;
+        if light.shadows_enabled != enabled {
+            light.shadows_enabled = enabled;
+        }
+        if reconfigure {
+            commands
+                .entity(entity)
+                .insert(cascade_shadow_config(video.shadows));
+        }
+    }
+
+    if shadow_map.size != video.shadows.map_size() {
+        shadow_map.size = video.shadows.map_size();
+    }
+}
+
+/// Direction of the sun from the player at `time`, following the rotation of the celestial root
+pub fn sun_direction(time: u64) -> Vec3 {
+    let normalized_time = (time % DAY_DURATION_IN_TICKS) as f32 / DAY_DURATION_IN_TICKS as f32;
+    Quat::from_rotation_x(normalized_time * 2.0 * PI) * Vec3::Z
+}
+
+/// How much the sun lights the sky at `time`: 0 at night, 1 once the sun is up, fading around sunrise and sunset
+pub fn daylight(time: u64) -> f32 {
+    let sun_height = sun_direction(time).y;
+    ((sun_height + TWILIGHT_HEIGHT) / (2.0 * TWILIGHT_HEIGHT)).clamp(0.0, 1.0)
+}
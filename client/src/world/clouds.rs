@@ -0,0 +1,167 @@
+use std::f64::consts::TAU;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor},
+    math::Affine2,
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        view::NoFrustumCulling,
+    },
+};
+use noise::{NoiseFn, Perlin};
+use shared::world::Weather;
+
+use crate::camera::CameraController;
+use crate::world::celestial::daylight;
+use crate::world::time::ClientTime;
+use crate::GameState;
+
+const CLOUD_ALTITUDE: f32 = 160.;
+/// Pixels along each side of the cloud texture, which repeats itself
+const CLOUD_TEXTURE_SIZE: u32 = 64;
+/// Blocks covered by each pixel of the cloud texture
+const CLOUD_CELL_SIZE: f32 = 12.;
+/// Blocks after which the clouds repeat themselves
+const CLOUD_PERIOD: f32 = CLOUD_TEXTURE_SIZE as f32 * CLOUD_CELL_SIZE;
+/// Periods along each side of the layer, so that it always reaches past the render distance
+const CLOUD_TILES: f32 = 3.;
+/// Blocks per second the clouds drift along the x axis
+const CLOUD_SPEED: f32 = 1.5;
+/// Noise value above which there is a cloud, lower in the sky of a storm
+const CLOUD_COVER: f64 = 0.15;
+const PRECIPITATION_CLOUD_COVER: f64 = -0.2;
+/// Opacity of the clouds, 0 to 255
+const CLOUD_ALPHA: u8 = 210;
+const CLOUD_SEED: u32 = 0x5EED;
+
+const DAY_CLOUD_COLOR: Srgba = Srgba::WHITE;
+const NIGHT_CLOUD_COLOR: Srgba = Srgba::rgb(0.1, 0.1, 0.15);
+const PRECIPITATION_CLOUD_COLOR: Srgba = Srgba::rgb(0.55, 0.55, 0.6);
+
+#[derive(Component)]
+pub struct CloudLayer {
+    material: Handle<StandardMaterial>,
+    /// Images of the clear and of the stormy sky
+    clear: Handle<Image>,
+    overcast: Handle<Image>,
+}
+
+/// Repeating texture of the clouds: white where the noise is above `cover`, transparent elsewhere
+fn cloud_image(cover: f64) -> Image {
+    let perlin = Perlin::new(CLOUD_SEED);
+    let mut data = Vec::with_capacity((CLOUD_TEXTURE_SIZE * CLOUD_TEXTURE_SIZE * 4) as usize);
+    for y in 0..CLOUD_TEXTURE_SIZE {
+        for x in 0..CLOUD_TEXTURE_SIZE {
+            // Walking around two circles makes the noise wrap around the edges of the texture
+            let u = x as f64 / CLOUD_TEXTURE_SIZE as f64 * TAU;
+            let v = y as f64 / CLOUD_TEXTURE_SIZE as f64 * TAU;
+            let value = perlin.get([u.cos() * 2., u.sin() * 2., v.cos() * 2., v.sin() * 2.]);
+            let alpha = if value > cover { CLOUD_ALPHA } else { 0 };
+            data.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: CLOUD_TEXTURE_SIZE,
+            height: CLOUD_TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        mag_filter: ImageFilterMode::Nearest,
+        min_filter: ImageFilterMode::Nearest,
+        ..default()
+    });
+    image
+}
+
+pub fn setup_clouds(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let clear = images.add(cloud_image(CLOUD_COVER));
+    let overcast = images.add(cloud_image(PRECIPITATION_CLOUD_COVER));
+    let material = materials.add(StandardMaterial {
+        base_color_texture: Some(clear.clone()),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        // Seen from below as well as from above
+        cull_mode: None,
+        uv_transform: Affine2::from_scale(Vec2::splat(CLOUD_TILES)),
+        ..default()
+    });
+
+    commands.spawn((
+        Name::new("Clouds"),
+        StateScoped(GameState::Game),
+        CloudLayer {
+            material: material.clone(),
+            clear,
+            overcast,
+        },
+        Mesh3d(
+            meshes.add(
+                Plane3d::default()
+                    .mesh()
+                    .size(CLOUD_PERIOD * CLOUD_TILES, CLOUD_PERIOD * CLOUD_TILES),
+            ),
+        ),
+        MeshMaterial3d(material),
+        Transform::from_xyz(0., CLOUD_ALTITUDE, 0.),
+        NotShadowCaster,
+        NotShadowReceiver,
+        NoFrustumCulling,
+    ));
+}
+
+/// Keeps the clouds above the camera while they drift, and shades them with the time of day and the weather
+pub fn clouds_system(
+    mut clouds: Query<(&CloudLayer, &mut Transform)>,
+    camera: Query<&GlobalTransform, With<CameraController>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    client_time: Res<ClientTime>,
+    weather: Res<Weather>,
+    time: Res<Time>,
+) {
+    let (Ok((layer, mut transform)), Ok(camera)) = (clouds.single_mut(), camera.single()) else {
+        return;
+    };
+
+    // The layer moves by whole periods, which do not change what it looks like, to stay centered on the camera
+    let drift = (time.elapsed_secs() * CLOUD_SPEED).rem_euclid(CLOUD_PERIOD);
+    let camera = camera.translation();
+    transform.translation = Vec3::new(
+        ((camera.x - drift) / CLOUD_PERIOD).round() * CLOUD_PERIOD + drift,
+        CLOUD_ALTITUDE,
+        (camera.z / CLOUD_PERIOD).round() * CLOUD_PERIOD,
+    );
+
+    let (color, image) = match *weather {
+        Weather::Clear => (DAY_CLOUD_COLOR, &layer.clear),
+        Weather::Precipitation => (PRECIPITATION_CLOUD_COLOR, &layer.overcast),
+    };
+    let base_color = Color::Srgba(NIGHT_CLOUD_COLOR.mix(&color, daylight(client_time.0)));
+    // Changing the material uploads it again, which is only worth it when it looks different
+    let unchanged = materials.get(&layer.material).is_some_and(|material| {
+        material.base_color == base_color && material.base_color_texture.as_ref() == Some(image)
+    });
+    if unchanged {
+        return;
+    }
+    if let Some(material) = materials.get_mut(&layer.material) {
+        material.base_color = base_color;
+        material.base_color_texture = Some(image.clone());
+    }
+}
@@ -1,19 +1,21 @@
 use bevy::prelude::*;
-use shared::world::BlockData;
 use shared::world::WorldMap;
+use shared::world::{BiomeType, BlockData, BlockId, WorldGenSettings};
 use std::collections::HashSet;
 use std::hash::Hash;
 use std::time::Instant;
 
 use bevy::math::IVec3;
 use bevy::prelude::Resource;
-use shared::world::{global_block_to_chunk_pos, global_block_to_local_offset};
+use shared::world::{column_biome, global_block_to_chunk_pos, global_block_to_local_offset};
 use std::collections::HashMap;
 
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
 pub enum GlobalMaterial {
     Sun,
     Moon,
+    Sky,
+    Stars,
     Blocks,
     Items,
 }
@@ -22,7 +24,22 @@ pub enum GlobalMaterial {
 pub struct ClientChunk {
     pub map: HashMap<IVec3, BlockData>, // Maps block positions within a chunk to block IDs
     pub entity: Option<Entity>,
+    /// Solid mesh shown by `entity`, updated in place when the chunk is meshed again
+    pub mesh: Option<Handle<Mesh>>,
     pub last_mesh_ts: Instant, // When was the last time a mesh was created for this chunk ?
+    /// Biome of each block column, as sent by the server
+    pub biomes: Vec<BiomeType>,
+}
+
+impl ClientChunk {
+    /// Rough size of the chunk in memory, its block map included
+    pub fn estimated_memory(&self) -> usize {
+        // Hash maps store one control byte next to each slot
+        let slot_size = std::mem::size_of::<IVec3>() + std::mem::size_of::<BlockData>() + 1;
+        std::mem::size_of::<Self>()
+            + self.map.capacity() * slot_size
+            + self.biomes.capacity() * std::mem::size_of::<BiomeType>()
+    }
 }
 
 impl Default for ClientChunk {
@@ -30,7 +47,9 @@ impl Default for ClientChunk {
         Self {
             map: HashMap::new(),
             entity: None,
+            mesh: None,
             last_mesh_ts: Instant::now(),
+            biomes: Vec::new(),
         }
     }
 }
@@ -41,9 +60,55 @@ pub struct ClientWorldMap {
     pub map: HashMap<IVec3, crate::world::ClientChunk>, // Maps global chunk positions to chunks
     pub total_blocks_count: u64,
     pub total_chunks_count: u64,
+    /// Number of loaded blocks of each kind
+    pub block_counts: HashMap<BlockId, u64>,
+    /// Sent by the server once connected
+    pub height_limits: WorldGenSettings,
+}
+
+impl ClientWorldMap {
+    fn count_block(&mut self, id: BlockId) {
+        *self.block_counts.entry(id).or_default() += 1;
+        self.total_blocks_count += 1;
+    }
+
+    fn uncount_block(&mut self, id: BlockId) {
+        if let Some(count) = self.block_counts.get_mut(&id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.block_counts.remove(&id);
+            }
+        }
+        self.total_blocks_count = self.total_blocks_count.saturating_sub(1);
+    }
+
+    /// Replaces a whole chunk, keeping the block counters up to date
+    pub fn insert_chunk(&mut self, pos: IVec3, chunk: ClientChunk) {
+        if let Some(previous) = self.map.remove(&pos) {
+            for block in previous.map.values() {
+                self.uncount_block(block.id);
+            }
+        }
+        for block in chunk.map.values() {
+            self.count_block(block.id);
+        }
+        self.map.insert(pos, chunk);
+        self.total_chunks_count = self.map.len() as u64;
+    }
+
+    pub fn clear(&mut self) {
+        self.map = HashMap::new();
+        self.block_counts.clear();
+        self.total_blocks_count = 0;
+        self.total_chunks_count = 0;
+    }
 }
 
 impl WorldMap for ClientWorldMap {
+    fn height_limits(&self) -> WorldGenSettings {
+        self.height_limits
+    }
+
     fn get_block_by_coordinates(&self, position: &IVec3) -> Option<&BlockData> {
         let chunk: Option<&ClientChunk> = self.map.get(&global_block_to_chunk_pos(position));
         match chunk {
@@ -71,21 +136,66 @@ impl WorldMap for ClientWorldMap {
         chunk_map
             .map
             .remove(&global_block_to_local_offset(global_block_pos));
+        self.uncount_block(kind.id);
 
         Some(kind)
     }
 
     fn set_block(&mut self, position: &IVec3, block: BlockData) {
-        let chunk_pos = global_block_to_chunk_pos(&position);
+        let chunk_pos = global_block_to_chunk_pos(position);
         let chunk: &mut ClientChunk = self.map.entry(chunk_pos).or_default();
-        chunk
+        let previous = chunk
             .map
             .insert(global_block_to_local_offset(position), block);
+
+        if let Some(previous) = previous {
+            self.uncount_block(previous.id);
+        }
+        self.count_block(block.id);
+        self.total_chunks_count = self.map.len() as u64;
+    }
+
+    fn set_chunk_blocks(
+        &mut self,
+        chunk_pos: IVec3,
+        blocks: &[(IVec3, Option<BlockData>)],
+    ) -> usize {
+        if blocks.iter().all(|(_, block)| block.is_none()) && !self.map.contains_key(&chunk_pos) {
+            return 0;
+        }
+        let chunk: &mut ClientChunk = self.map.entry(chunk_pos).or_default();
+        let previous: Vec<Option<BlockData>> = blocks
+            .iter()
+            .map(|(local_pos, block)| match block {
+                Some(block) => chunk.map.insert(*local_pos, *block),
+                None => chunk.map.remove(local_pos),
+            })
+            .collect();
+
+        let mut changed = 0;
+        for ((_, block), previous) in blocks.iter().zip(previous) {
+            if let Some(previous) = previous {
+                self.uncount_block(previous.id);
+            }
+            if let Some(block) = block {
+                self.count_block(block.id);
+            }
+            if previous != *block {
+                changed += 1;
+            }
+        }
+        self.total_chunks_count = self.map.len() as u64;
+        changed
     }
 
     fn mark_block_for_update(&mut self, _block_pos: &IVec3) {
         // Useless in client
     }
+
+    fn get_biome(&self, position: &IVec3) -> Option<BiomeType> {
+        let chunk = self.map.get(&global_block_to_chunk_pos(position))?;
+        column_biome(&chunk.biomes, position)
+    }
 }
 
 #[derive(Default, Debug)]
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use shared::world::{BlockId, Weather, WorldMap};
+
+use crate::camera::CameraController;
+use crate::world::celestial::daylight;
+use crate::world::sky::horizon_color;
+use crate::world::time::ClientTime;
+use crate::world::{ClientWorldMap, RenderDistance};
+
+/// Fraction of the render distance where the fog starts, it is opaque at the render distance
+const FOG_START: f32 = 0.6;
+/// Fraction of the render distance the fog reaches to while it rains or snows
+const PRECIPITATION_FOG_END: f32 = 0.5;
+/// Blocks seen through water
+const UNDERWATER_FOG_END: f32 = 12.;
+/// Seconds for the fog to get most of the way to a new color or distance
+const FOG_TRANSITION: f32 = 0.5;
+
+const PRECIPITATION_FOG_COLOR: Srgba = Srgba::rgb(0.5, 0.52, 0.56);
+const UNDERWATER_FOG_COLOR: Srgba = Srgba::rgb(0.1, 0.25, 0.6);
+
+/// Color and distances the fog should have for the camera at `position`
+fn target_fog(
+    world_map: &ClientWorldMap,
+    position: Vec3,
+    time: u64,
+    weather: Weather,
+    render_distance: f32,
+) -> (Srgba, f32, f32) {
+    let block = position.floor().as_ivec3();
+    if world_map
+        .get_block_by_coordinates(&block)
+        .is_some_and(|block| block.id == BlockId::Water)
+    {
+        return (UNDERWATER_FOG_COLOR, 0., UNDERWATER_FOG_END);
+    }
+
+    // Chunks still loading have no biome, the weather does not show there
+    if weather == Weather::Precipitation && world_map.get_biome(&block).is_some() {
+        let color = horizon_color(time).mix(&PRECIPITATION_FOG_COLOR, daylight(time));
+        return (color, 0., render_distance * PRECIPITATION_FOG_END);
+    }
+    (
+        horizon_color(time),
+        render_distance * FOG_START,
+        render_distance,
+    )
+}
+
+/// Fades the far chunks into the sky, hiding them popping in at the edge of the render distance
+pub fn fog_system(
+    mut camera: Query<(&GlobalTransform, &mut DistanceFog), With<CameraController>>,
+    world_map: Res<ClientWorldMap>,
+    render_distance: Res<RenderDistance>,
+    client_time: Res<ClientTime>,
+    weather: Res<Weather>,
+    time: Res<Time>,
+) {
+    let Ok((transform, mut fog)) = camera.single_mut() else {
+        return;
+    };
+    let (color, start, end) = target_fog(
+        &world_map,
+        transform.translation(),
+        client_time.0,
+        *weather,
+        render_distance.distance(),
+    );
+
+    let factor = 1. - (-time.delta_secs() / FOG_TRANSITION).exp();
+    fog.color = Color::Srgba(fog.color.to_srgba().mix(&color, factor));
+    let (current_start, current_end) = match fog.falloff {
+        FogFalloff::Linear { start, end } => (start, end),
+        _ => (start, end),
+    };
+    fog.falloff = FogFalloff::Linear {
+        start: current_start.lerp(start, factor),
+        end: current_end.lerp(end, factor),
+    };
+}
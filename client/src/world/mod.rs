@@ -1,6 +1,11 @@
+pub mod blob_shadows;
+pub mod block_lights;
 pub mod celestial;
+pub mod clouds;
 pub mod data;
+pub mod fog;
 pub mod rendering;
+pub mod sky;
 pub mod time;
 
 pub use data::*;
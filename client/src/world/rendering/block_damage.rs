@@ -0,0 +1,113 @@
+use bevy::{pbr::NotShadowCaster, prelude::*, render::mesh::VertexAttributeValues};
+use shared::messages::BlockBreakingProgress;
+use shared::world::WorldMap;
+use std::collections::HashMap;
+
+use crate::world::{ClientWorldMap, GlobalMaterial, MaterialResource};
+use crate::GameState;
+
+/// Blocks the overlay sticks out of each face of the damaged block, so that it is drawn over it
+const OVERLAY_OFFSET: f32 = 0.005;
+
+/// Cracks drawn over a block while it is being broken, by any player
+#[derive(Component)]
+pub struct BlockDamageOverlay {
+    position: IVec3,
+    stage: u8,
+}
+
+/// One cube per stage of the cracks, shared by all the overlays at that stage
+#[derive(Resource, Default)]
+pub struct BlockDamageMeshes(HashMap<u8, Handle<Mesh>>);
+
+fn build_stage_mesh(stage: u8, material_resource: &MaterialResource) -> Option<Mesh> {
+    let uv_coords = material_resource
+        .blocks
+        .as_ref()?
+        .uvs
+        .get(&format!("DestroyStage{stage}"))?;
+
+    let mut mesh = Cuboid::from_size(Vec3::splat(1. + 2. * OVERLAY_OFFSET))
+        .mesh()
+        .build();
+    if let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        for uv in uvs.iter_mut() {
+            uv[0] = uv_coords.u0 + uv[0] * (uv_coords.u1 - uv_coords.u0);
+            uv[1] = uv_coords.v0 + uv[1] * (uv_coords.v1 - uv_coords.v0);
+        }
+    }
+    Some(mesh)
+}
+
+/// Records the breaking progress received from the server or predicted locally, then shows it over each block,
+/// removing the cracks of the blocks which were broken or replaced
+pub fn block_damage_overlay_system(
+    mut commands: Commands,
+    mut events: EventReader<BlockBreakingProgress>,
+    mut world_map: ResMut<ClientWorldMap>,
+    mut overlays: Query<(Entity, &mut BlockDamageOverlay, &mut Mesh3d)>,
+    mut stage_meshes: ResMut<BlockDamageMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material_resource: Res<MaterialResource>,
+) {
+    let mut damaged = HashMap::new();
+    for damage in events.read() {
+        if let Some(block) = world_map.get_block_mut_by_coordinates(&damage.position) {
+            block.breaking_progress = damage.progress;
+            damaged.insert(damage.position, *block);
+        }
+    }
+
+    let mut stage_mesh = |stage: u8| -> Option<Handle<Mesh>> {
+        if let Some(mesh) = stage_meshes.0.get(&stage) {
+            return Some(mesh.clone());
+        }
+        let mesh = meshes.add(build_stage_mesh(stage, &material_resource)?);
+        stage_meshes.0.insert(stage, mesh.clone());
+        Some(mesh)
+    };
+
+    for (entity, mut overlay, mut mesh) in overlays.iter_mut() {
+        damaged.remove(&overlay.position);
+        let stage = world_map
+            .get_block_by_coordinates(&overlay.position)
+            .filter(|block| block.breaking_progress > 0)
+            .map(|block| block.get_breaking_level());
+        let Some(stage) = stage else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if stage != overlay.stage {
+            let Some(handle) = stage_mesh(stage) else {
+                continue;
+            };
+            overlay.stage = stage;
+            mesh.0 = handle;
+        }
+    }
+
+    let Some(material) = material_resource
+        .global_materials
+        .get(&GlobalMaterial::Blocks)
+    else {
+        return;
+    };
+    for (position, block) in damaged {
+        if block.breaking_progress == 0 {
+            continue;
+        }
+        let stage = block.get_breaking_level();
+        let Some(mesh) = stage_mesh(stage) else {
+            continue;
+        };
+        commands.spawn((
+            Name::new("BlockDamageOverlay"),
+            StateScoped(GameState::Game),
+            BlockDamageOverlay { position, stage },
+            Mesh3d(mesh),
+            MeshMaterial3d(material.clone()),
+            Transform::from_translation(position.as_vec3() + Vec3::splat(0.5)),
+            NotShadowCaster,
+        ));
+    }
+}
@@ -0,0 +1,421 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{
+    prelude::*,
+    render::mesh::VertexAttributeValues,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+use shared::{world::global_block_to_chunk_pos, GameFolderPaths, CHUNK_SIZE};
+
+use crate::constants::EXPORTS_PATH;
+use crate::ui::hud::toasts::ToastEvent;
+use crate::world::ClientWorldMap;
+
+use super::meshing::{generate_chunk_mesh, ChunkMeshingInput};
+use super::{MaterialResource, VideoSettings};
+
+/// Chunks an export may span, the whole region is meshed into memory before being written
+const MAX_EXPORT_CHUNKS: usize = 4096;
+/// Seconds between two progress notifications of a running export
+const PROGRESS_INTERVAL: f32 = 3.0;
+
+/// Arguments of the `/export` chat command, which is handled by the client instead of being sent to the server
+#[derive(Event, Debug)]
+pub struct ExportCommandEvent(pub String);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Obj,
+    Gltf,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Obj => "obj",
+            ExportFormat::Gltf => "gltf",
+        }
+    }
+}
+
+/// `/export <x1> <y1> <z1> <x2> <y2> <z2> [gltf|obj]`, the corners are included
+fn parse_export_command(args: &str) -> Result<(IVec3, IVec3, ExportFormat), String> {
+    const USAGE: &str = "Usage: /export <x1> <y1> <z1> <x2> <y2> <z2> [gltf|obj]";
+    let args: Vec<&str> = args.split_whitespace().collect();
+    if args.len() != 6 && args.len() != 7 {
+        return Err(USAGE.to_string());
+    }
+
+    let coords: Vec<i32> = args[..6]
+        .iter()
+        .map(|arg| arg.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| USAGE.to_string())?;
+    let format = match args.get(6).copied() {
+        None | Some("gltf") => ExportFormat::Gltf,
+        Some("obj") => ExportFormat::Obj,
+        Some(other) => return Err(format!("Unknown export format {other}, use gltf or obj")),
+    };
+
+    let a = IVec3::new(coords[0], coords[1], coords[2]);
+    let b = IVec3::new(coords[3], coords[4], coords[5]);
+    Ok((a.min(b), a.max(b), format))
+}
+
+/// An export meshing and writing its files on another thread
+pub struct RunningExport {
+    task: Task<Result<PathBuf, String>>,
+    meshed: Arc<AtomicUsize>,
+    total: usize,
+    progress_timer: Timer,
+}
+
+/// Meshes a region of the loaded world with the same code as the chunks shown in game, and writes it
+/// with the block atlas to the exports folder, to be rendered in other tools such as Blender
+pub fn export_region_system(
+    mut events: EventReader<ExportCommandEvent>,
+    mut running: Local<Option<RunningExport>>,
+    mut ev_toast: EventWriter<ToastEvent>,
+    world_map: Res<ClientWorldMap>,
+    material_resource: Res<MaterialResource>,
+    images: Res<Assets<Image>>,
+    video: Res<VideoSettings>,
+    paths: Res<GameFolderPaths>,
+    time: Res<Time>,
+) {
+    if let Some(export) = running.as_mut() {
+        if let Some(result) = block_on(future::poll_once(&mut export.task)) {
+            ev_toast.write(ToastEvent(match result {
+                Ok(path) => format!("Region exported to {}", path.display()),
+                Err(e) => format!("Could not export the region: {e}"),
+            }));
+            *running = None;
+        } else if export.progress_timer.tick(time.delta()).just_finished() {
+            ev_toast.write(ToastEvent(format!(
+                "Exporting region: {}/{} chunks",
+                export.meshed.load(Ordering::Relaxed),
+                export.total
+            )));
+        }
+    }
+
+    for ExportCommandEvent(args) in events.read() {
+        if running.is_some() {
+            ev_toast.write(ToastEvent("An export is already running".into()));
+            continue;
+        }
+        match start_export(
+            args,
+            &world_map,
+            &material_resource,
+            &images,
+            video.fancy_leaves,
+            &paths,
+        ) {
+            Ok(export) => {
+                ev_toast.write(ToastEvent(format!(
+                    "Exporting region: 0/{} chunks",
+                    export.total
+                )));
+                *running = Some(export);
+            }
+            Err(e) => {
+                ev_toast.write(ToastEvent(e));
+            }
+        }
+    }
+}
+
+fn start_export(
+    args: &str,
+    world_map: &ClientWorldMap,
+    material_resource: &MaterialResource,
+    images: &Assets<Image>,
+    fancy_leaves: bool,
+    paths: &GameFolderPaths,
+) -> Result<RunningExport, String> {
+    let (min, max, format) = parse_export_command(args)?;
+
+    let min_chunk = global_block_to_chunk_pos(&min);
+    let max_chunk = global_block_to_chunk_pos(&max);
+    let chunk_count = (max_chunk - min_chunk + IVec3::ONE)
+        .as_uvec3()
+        .element_product() as usize;
+    if chunk_count > MAX_EXPORT_CHUNKS {
+        return Err(format!(
+            "The region spans {chunk_count} chunks, at most {MAX_EXPORT_CHUNKS} can be exported"
+        ));
+    }
+
+    let atlas = material_resource
+        .blocks
+        .as_ref()
+        .ok_or("The block textures are not loaded")?;
+    let atlas_image = images
+        .get(&atlas.texture)
+        .cloned()
+        .ok_or("The block textures are not loaded")?;
+
+    // Only the blocks are copied here, they are meshed on the export thread
+    let mut inputs = Vec::new();
+    for x in min_chunk.x..=max_chunk.x {
+        for y in min_chunk.y..=max_chunk.y {
+            for z in min_chunk.z..=max_chunk.z {
+                let chunk_pos = IVec3::new(x, y, z);
+                let Some(mut input) = ChunkMeshingInput::new(world_map, chunk_pos, fancy_leaves)
+                else {
+                    continue;
+                };
+                input.crop(chunk_pos, min, max);
+                if !input.is_empty() {
+                    inputs.push((chunk_pos, input));
+                }
+            }
+        }
+    }
+    if inputs.is_empty() {
+        return Err("Nothing to export, the region is empty or not loaded".into());
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let output = paths
+        .game_folder_path
+        .join(EXPORTS_PATH)
+        .join(format!("region_{stamp}"))
+        .with_extension(format.extension());
+
+    let uvs = atlas.uvs.clone();
+    let meshed = Arc::new(AtomicUsize::new(0));
+    let total = inputs.len();
+    let task_meshed = Arc::clone(&meshed);
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let mut mesh = ExportMesh::default();
+        for (chunk_pos, input) in inputs {
+            let offset = (chunk_pos * CHUNK_SIZE - min).as_vec3();
+            mesh.append(generate_chunk_mesh(&input, &uvs).solid_mesh, offset);
+            task_meshed.fetch_add(1, Ordering::Relaxed);
+        }
+        write_export(&mesh, atlas_image, &output, format)?;
+        Ok(output)
+    });
+
+    info!(
+        "Exporting the region from {} to {} ({} chunks)",
+        min, max, total
+    );
+    Ok(RunningExport {
+        task,
+        meshed,
+        total,
+        progress_timer: Timer::from_seconds(PROGRESS_INTERVAL, TimerMode::Repeating),
+    })
+}
+
+/// Vertices of the whole region, relative to its lowest corner
+#[derive(Default)]
+struct ExportMesh {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl ExportMesh {
+    fn append(&mut self, mesh: Option<Mesh>, offset: Vec3) {
+        let Some(mesh) = mesh else {
+            return;
+        };
+        let (
+            Some(VertexAttributeValues::Float32x3(positions)),
+            Some(VertexAttributeValues::Float32x3(normals)),
+            Some(VertexAttributeValues::Float32x2(uvs)),
+            Some(VertexAttributeValues::Float32x4(colors)),
+            Some(indices),
+        ) = (
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION),
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL),
+            mesh.attribute(Mesh::ATTRIBUTE_UV_0),
+            mesh.attribute(Mesh::ATTRIBUTE_COLOR),
+            mesh.indices(),
+        )
+        else {
+            return;
+        };
+
+        let first = self.positions.len() as u32;
+        self.positions.extend(
+            positions
+                .iter()
+                .map(|p| (Vec3::from_array(*p) + offset).to_array()),
+        );
+        self.normals.extend_from_slice(normals);
+        self.uvs.extend_from_slice(uvs);
+        self.colors.extend_from_slice(colors);
+        self.indices
+            .extend(indices.iter().map(|index| first + index as u32));
+    }
+}
+
+/// Writes the mesh and the block atlas next to it, as `<name>.png`
+fn write_export(
+    mesh: &ExportMesh,
+    atlas: Image,
+    output: &Path,
+    format: ExportFormat,
+) -> Result<(), String> {
+    if let Some(folder) = output.parent() {
+        std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+    }
+
+    let texture_path = output.with_extension("png");
+    atlas
+        .try_into_dynamic()
+        .map_err(|e| e.to_string())?
+        .save(&texture_path)
+        .map_err(|e| e.to_string())?;
+    let texture_name = file_name(&texture_path);
+
+    match format {
+        ExportFormat::Obj => {
+            let material_path = output.with_extension("mtl");
+            std::fs::write(&material_path, obj_material(&texture_name))
+                .map_err(|e| e.to_string())?;
+            std::fs::write(output, obj_mesh(mesh, &file_name(&material_path)))
+                .map_err(|e| e.to_string())?;
+        }
+        ExportFormat::Gltf => {
+            let buffer_path = output.with_extension("bin");
+            let buffer = gltf_buffer(mesh);
+            std::fs::write(&buffer_path, &buffer).map_err(|e| e.to_string())?;
+            std::fs::write(
+                output,
+                gltf_document(mesh, buffer.len(), &file_name(&buffer_path), &texture_name),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn obj_material(texture_name: &str) -> String {
+    format!("newmtl blocks\nKd 1 1 1\nmap_Kd {texture_name}\nmap_d {texture_name}\n")
+}
+
+/// The vertex colors, which hold the grass and leaves tints, are written after the positions as Blender reads them
+fn obj_mesh(mesh: &ExportMesh, material_name: &str) -> String {
+    let mut obj = format!("mtllib {material_name}\no region\n");
+    for (p, c) in mesh.positions.iter().zip(&mesh.colors) {
+        let _ = writeln!(
+            obj,
+            "v {} {} {} {} {} {}",
+            p[0], p[1], p[2], c[0], c[1], c[2]
+        );
+    }
+    // The atlas is read from the top in game, OBJ textures from the bottom
+    for uv in &mesh.uvs {
+        let _ = writeln!(obj, "vt {} {}", uv[0], 1. - uv[1]);
+    }
+    for n in &mesh.normals {
+        let _ = writeln!(obj, "vn {} {} {}", n[0], n[1], n[2]);
+    }
+    obj.push_str("usemtl blocks\n");
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+        let _ = writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}");
+    }
+    obj
+}
+
+/// Positions, normals, uvs, colors then indices, each one a buffer view of the document
+fn gltf_buffer(mesh: &ExportMesh) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let floats = mesh
+        .positions
+        .iter()
+        .flatten()
+        .chain(mesh.normals.iter().flatten())
+        .chain(mesh.uvs.iter().flatten())
+        .chain(mesh.colors.iter().flatten());
+    for value in floats {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+    for index in &mesh.indices {
+        buffer.extend_from_slice(&index.to_le_bytes());
+    }
+    buffer
+}
+
+fn gltf_document(
+    mesh: &ExportMesh,
+    buffer_len: usize,
+    buffer_name: &str,
+    texture_name: &str,
+) -> String {
+    const FLOAT: u32 = 5126;
+    const UNSIGNED_INT: u32 = 5125;
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+    const NEAREST: u32 = 9728;
+
+    let vertices = mesh.positions.len();
+    let (min, max) = mesh.positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), p| (min.min(Vec3::from_array(*p)), max.max(Vec3::from_array(*p))),
+    );
+
+    // (accessor type, components per element, element count, component type, buffer view target)
+    let views = [
+        ("VEC3", 3, vertices, FLOAT, ARRAY_BUFFER),
+        ("VEC3", 3, vertices, FLOAT, ARRAY_BUFFER),
+        ("VEC2", 2, vertices, FLOAT, ARRAY_BUFFER),
+        ("VEC4", 4, vertices, FLOAT, ARRAY_BUFFER),
+        (
+            "SCALAR",
+            1,
+            mesh.indices.len(),
+            UNSIGNED_INT,
+            ELEMENT_ARRAY_BUFFER,
+        ),
+    ];
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut offset = 0;
+    for (i, (kind, components, count, component_type, target)) in views.into_iter().enumerate() {
+        let length = components * count * 4;
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{offset},"byteLength":{length},"target":{target}}}"#
+        ));
+        let bounds = if i == 0 {
+            format!(
+                r#","min":[{},{},{}],"max":[{},{},{}]"#,
+                min.x, min.y, min.z, max.x, max.y, max.z
+            )
+        } else {
+            String::new()
+        };
+        accessors.push(format!(
+            r#"{{"bufferView":{i},"componentType":{component_type},"count":{count},"type":"{kind}"{bounds}}}"#
+        ));
+        offset += length;
+    }
+
+    format!(
+        r#"{{"asset":{{"version":"2.0","generator":"rustcraft"}},"scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0,"name":"region"}}],"meshes":[{{"primitives":[{{"attributes":{{"POSITION":0,"NORMAL":1,"TEXCOORD_0":2,"COLOR_0":3}},"indices":4,"material":0}}]}}],"materials":[{{"name":"blocks","pbrMetallicRoughness":{{"baseColorTexture":{{"index":0}},"metallicFactor":0}},"alphaMode":"MASK"}}],"textures":[{{"sampler":0,"source":0}}],"samplers":[{{"magFilter":{NEAREST},"minFilter":{NEAREST}}}],"images":[{{"uri":"{texture_name}"}}],"buffers":[{{"uri":"{buffer_name}","byteLength":{buffer_len}}}],"bufferViews":[{}],"accessors":[{}]}}"#,
+        buffer_views.join(","),
+        accessors.join(",")
+    )
+}
@@ -1,5 +1,7 @@
 use crate::constants::{BASE_ROUGHNESS, BASE_SPECULAR_HIGHLIGHT};
+use crate::entities::stack::ItemStackMeshes;
 use crate::game::PreLoadingCompletion;
+use crate::world::sky::{sky_texture, starfield_image};
 use crate::world::GlobalMaterial;
 use crate::TexturePath;
 use bevy::image::ImageSampler;
@@ -37,6 +39,18 @@ pub struct AtlasHandles<T> {
     _d: PhantomData<T>,
 }
 
+impl<T> AtlasHandles<T> {
+    /// Number of textures of the atlas done loading, out of all of its textures
+    pub fn loading_progress(&self, asset_server: &AssetServer) -> (usize, usize) {
+        let loaded = self
+            .handles
+            .iter()
+            .filter(|(handle, _)| asset_server.is_loaded_with_dependencies(handle))
+            .count();
+        (loaded, self.handles.len())
+    }
+}
+
 impl<T> Default for AtlasHandles<T> {
     fn default() -> Self {
         Self {
@@ -50,25 +64,65 @@ impl<T> Default for AtlasHandles<T> {
 pub fn setup_materials(
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
     mut material_resource: ResMut<MaterialResource>,
     mut block_atlas_handles: ResMut<AtlasHandles<BlockId>>,
     mut item_atlas_handles: ResMut<AtlasHandles<ItemId>>,
     texture_path: Res<TexturePath>,
     paths: Res<GameFolderPaths>,
 ) {
+    // Textures may come with their own sky, the sun and the moon are plain squares otherwise
+    let sky_path = paths
+        .assets_folder_path
+        .join(&texture_path.path)
+        .join("sky/");
+
+    // The sky is far behind the fog, which would hide it
+    let sun_texture = sky_texture(&asset_server, &sky_path, "sun");
     let sun_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(1., 0.95, 0.1),
+        base_color: if sun_texture.is_some() {
+            Color::WHITE
+        } else {
+            Color::srgb(1., 0.95, 0.1)
+        },
+        base_color_texture: sun_texture,
         emissive: LinearRgba::new(1., 0.95, 0.1, 0.5),
         emissive_exposure_weight: 0.5,
         cull_mode: Some(Face::Front),
+        alpha_mode: AlphaMode::Mask(0.5),
+        fog_enabled: false,
         ..Default::default()
     });
 
     let moon_material = materials.add(StandardMaterial {
         base_color: Color::WHITE,
+        base_color_texture: sky_texture(&asset_server, &sky_path, "moon"),
         emissive: LinearRgba::WHITE,
         emissive_exposure_weight: 0.5,
         cull_mode: Some(Face::Front),
+        alpha_mode: AlphaMode::Mask(0.5),
+        fog_enabled: false,
+        ..Default::default()
+    });
+
+    // Colored through the vertices of the dome, seen from the inside
+    let sky_material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        unlit: true,
+        cull_mode: Some(Face::Front),
+        fog_enabled: false,
+        ..Default::default()
+    });
+
+    let stars_texture = sky_texture(&asset_server, &sky_path, "stars")
+        .unwrap_or_else(|| images.add(starfield_image()));
+    let stars_material = materials.add(StandardMaterial {
+        base_color: Color::WHITE.with_alpha(0.),
+        base_color_texture: Some(stars_texture),
+        unlit: true,
+        cull_mode: Some(Face::Front),
+        alpha_mode: AlphaMode::Blend,
+        fog_enabled: false,
         ..Default::default()
     });
 
@@ -78,6 +132,12 @@ pub fn setup_materials(
     material_resource
         .global_materials
         .insert(GlobalMaterial::Moon, moon_material);
+    material_resource
+        .global_materials
+        .insert(GlobalMaterial::Sky, sky_material);
+    material_resource
+        .global_materials
+        .insert(GlobalMaterial::Stars, stars_material);
 
     let blocks_path = paths
         .assets_folder_path
@@ -152,6 +212,7 @@ pub fn create_all_atlases(
     mut loading: ResMut<PreLoadingCompletion>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    (stack_meshes, mut meshes): (Res<ItemStackMeshes>, ResMut<Assets<Mesh>>),
 ) {
     loading.textures_loaded = true;
 
@@ -200,6 +261,8 @@ pub fn create_all_atlases(
                 }),
             );
             material_resource.items = Some(items);
+            // Resource packs change the atlas after the item meshes were built with its old UVs
+            stack_meshes.rebuild_all(&mut meshes, &material_resource);
         } else {
             loading.textures_loaded = false;
         }
@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+};
+use serde::{Deserialize, Serialize};
+
+use super::meshing::{ChunkMeshResponse, UvCoords};
+
+/// Folder of the mesh cache, relative to the game folder
+pub const MESH_CACHE_PATH: &str = "cache/meshes";
+/// Once the cache gets bigger than this on disk, the least recently used meshes are evicted
+const MESH_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Identifies the mesh of a chunk with a given content, neighbour borders and textures included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshCacheKey {
+    pub chunk_pos: IVec3,
+    pub content_hash: u64,
+}
+
+impl MeshCacheKey {
+    fn file_name(&self) -> String {
+        format!(
+            "{}_{}_{}_{:016x}.bin",
+            self.chunk_pos.x, self.chunk_pos.y, self.chunk_pos.z, self.content_hash
+        )
+    }
+
+    fn from_file_name(name: &str) -> Option<Self> {
+        let mut parts = name.strip_suffix(".bin")?.split('_');
+        let x = parts.next()?.parse().ok()?;
+        let y = parts.next()?.parse().ok()?;
+        let z = parts.next()?.parse().ok()?;
+        let content_hash = u64::from_str_radix(parts.next()?, 16).ok()?;
+        Some(Self {
+            chunk_pos: IVec3::new(x, y, z),
+            content_hash,
+        })
+    }
+}
+
+/// Stands for the texture atlas layout in cache keys, as meshes embed texture coordinates
+pub fn hash_uv_map(uvs: &HashMap<String, UvCoords>) -> u64 {
+    let mut sorted: Vec<_> = uvs.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut hasher = DefaultHasher::new();
+    for (name, uv) in sorted {
+        name.hash(&mut hasher);
+        for coord in [uv.u0, uv.u1, uv.v0, uv.v1] {
+            coord.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Vertex data of a chunk mesh as stored on disk, tangents included so they are not generated again
+#[derive(Serialize, Deserialize, Default)]
+struct CachedMesh {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    tangents: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl CachedMesh {
+    fn from_response(response: &ChunkMeshResponse) -> Option<Self> {
+        let Some(mesh) = &response.solid_mesh else {
+            // Chunks without anything to render are cached too, as empty meshes
+            return Some(Self::default());
+        };
+
+        macro_rules! attribute {
+            ($attribute:expr, $format:ident) => {
+                match mesh.attribute($attribute)? {
+                    VertexAttributeValues::$format(values) => values.clone(),
+                    _ => return None,
+                }
+            };
+        }
+
+        Some(Self {
+            positions: attribute!(Mesh::ATTRIBUTE_POSITION, Float32x3),
+            normals: attribute!(Mesh::ATTRIBUTE_NORMAL, Float32x3),
+            uvs: attribute!(Mesh::ATTRIBUTE_UV_0, Float32x2),
+            colors: attribute!(Mesh::ATTRIBUTE_COLOR, Float32x4),
+            tangents: attribute!(Mesh::ATTRIBUTE_TANGENT, Float32x4),
+            indices: match mesh.indices()? {
+                Indices::U32(indices) => indices.clone(),
+                Indices::U16(_) => return None,
+            },
+        })
+    }
+
+    fn into_response(self) -> ChunkMeshResponse {
+        if self.positions.is_empty() {
+            return ChunkMeshResponse { solid_mesh: None };
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, Default::default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, self.tangents);
+        mesh.insert_indices(Indices::U32(self.indices));
+        ChunkMeshResponse {
+            solid_mesh: Some(mesh),
+        }
+    }
+}
+
+struct CacheEntry {
+    size: u64,
+    /// Seconds since the epoch
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheIndex {
+    entries: HashMap<MeshCacheKey, CacheEntry>,
+    total_size: u64,
+}
+
+struct MeshCacheStorage {
+    folder: PathBuf,
+    index: Mutex<CacheIndex>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Disk cache of chunk meshes, shared with the meshing tasks, so that chunks whose content did not change
+/// (rejoining a world, reloading chunks...) get their mesh back without meshing them again
+#[derive(Resource, Clone, Default)]
+pub struct MeshCache(Option<Arc<MeshCacheStorage>>);
+
+impl MeshCache {
+    /// A cache which never stores anything
+    pub fn disabled() -> Self {
+        Self(None)
+    }
+
+    /// Opens the cache stored in `folder`, indexing the meshes already there
+    pub fn open(folder: PathBuf) -> Self {
+        if let Err(e) = fs::create_dir_all(&folder) {
+            warn!(
+                "Could not create mesh cache folder {}, meshes will not be cached: {}",
+                folder.display(),
+                e
+            );
+            return Self::disabled();
+        }
+
+        let mut index = CacheIndex::default();
+        if let Ok(files) = fs::read_dir(&folder) {
+            for file in files.flatten() {
+                let Some(key) = file
+                    .file_name()
+                    .to_str()
+                    .and_then(MeshCacheKey::from_file_name)
+                else {
+                    continue;
+                };
+                let Ok(metadata) = file.metadata() else {
+                    continue;
+                };
+                let last_used = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+
+                index.total_size += metadata.len();
+                index.entries.insert(
+                    key,
+                    CacheEntry {
+                        size: metadata.len(),
+                        last_used,
+                    },
+                );
+            }
+        }
+
+        info!(
+            "Mesh cache at {} holds {} meshes ({} bytes)",
+            folder.display(),
+            index.entries.len(),
+            index.total_size
+        );
+
+        Self(Some(Arc::new(MeshCacheStorage {
+            folder,
+            index: Mutex::new(index),
+        })))
+    }
+
+    pub fn get(&self, key: &MeshCacheKey) -> Option<ChunkMeshResponse> {
+        let storage = self.0.as_ref()?;
+
+        {
+            let mut index = storage.index.lock().unwrap();
+            index.entries.get_mut(key)?.last_used = now_secs();
+        }
+
+        let path = storage.folder.join(key.file_name());
+        match fs::read(&path)
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<CachedMesh>(&bytes).ok())
+        {
+            Some(cached) => Some(cached.into_response()),
+            None => {
+                // The file is gone or corrupted, forget about it
+                warn!("Dropping unreadable cached mesh {}", path.display());
+                self.remove(storage, key);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, key: MeshCacheKey, response: &ChunkMeshResponse) {
+        let Some(storage) = self.0.as_ref() else {
+            return;
+        };
+        let Some(cached) = CachedMesh::from_response(response) else {
+            return;
+        };
+        let Ok(bytes) = bincode::serialize(&cached) else {
+            return;
+        };
+
+        if let Err(e) = fs::write(storage.folder.join(key.file_name()), &bytes) {
+            warn!("Could not write cached mesh: {}", e);
+            return;
+        }
+
+        let mut index = storage.index.lock().unwrap();
+        if let Some(previous) = index.entries.insert(
+            key,
+            CacheEntry {
+                size: bytes.len() as u64,
+                last_used: now_secs(),
+            },
+        ) {
+            index.total_size -= previous.size;
+        }
+        index.total_size += bytes.len() as u64;
+
+        Self::evict(storage, &mut index);
+    }
+
+    fn remove(&self, storage: &MeshCacheStorage, key: &MeshCacheKey) {
+        let mut index = storage.index.lock().unwrap();
+        if let Some(entry) = index.entries.remove(key) {
+            index.total_size -= entry.size;
+        }
+        let _ = fs::remove_file(storage.folder.join(key.file_name()));
+    }
+
+    /// Removes the least recently used meshes until the cache fits in its size cap
+    fn evict(storage: &MeshCacheStorage, index: &mut CacheIndex) {
+        if index.total_size <= MESH_CACHE_MAX_BYTES {
+            return;
+        }
+
+        let mut by_age: Vec<(MeshCacheKey, u64)> = index
+            .entries
+            .iter()
+            .map(|(key, entry)| (*key, entry.last_used))
+            .collect();
+        by_age.sort_by_key(|(_, last_used)| *last_used);
+
+        for (key, _) in by_age {
+            if index.total_size <= MESH_CACHE_MAX_BYTES {
+                break;
+            }
+            if let Some(entry) = index.entries.remove(&key) {
+                index.total_size -= entry.size;
+                let _ = fs::remove_file(storage.folder.join(key.file_name()));
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use std::cell::RefCell;
 use std::f32::consts::PI;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::{collections::HashMap, time::Instant};
 
 use crate::world::{ClientChunk, ClientWorldMap};
@@ -7,10 +9,12 @@ use bevy::{
     prelude::*,
     render::mesh::{Indices, PrimitiveTopology},
 };
-use shared::world::{
-    chunk_offset_to_global_pos, BlockDirection, BlockId, BlockTransparency, WorldMap,
+use shared::{
+    world::{biome_index, BlockData, BlockDirection, BlockId, BlockTransparency, SIX_OFFSETS},
+    CHUNK_SIZE,
 };
 
+use super::tint::column_tints;
 use super::voxel::{Face, FaceDirection, VoxelShape};
 
 #[derive(Copy, Clone, Debug)]
@@ -37,14 +41,144 @@ pub struct MeshCreator {
     pub indices_offset: u32,
 }
 
-fn build_mesh(creator: &MeshCreator) -> Mesh {
-    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, Default::default());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, creator.vertices.clone());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, creator.normals.clone());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, creator.uvs.clone());
-    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, creator.colors.clone());
-    mesh.insert_indices(Indices::U32(creator.indices.clone()));
-    mesh
+impl MeshCreator {
+    /// Empties the buffers while keeping their memory, so that the next chunk can be meshed without allocating
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.normals.clear();
+        self.uvs.clear();
+        self.colors.clear();
+        self.indices_offset = 0;
+    }
+
+    fn build_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, Default::default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.vertices.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors.clone());
+        mesh.insert_indices(Indices::U32(self.indices.clone()));
+        mesh
+    }
+}
+
+thread_local! {
+    /// Scratch buffers of the meshing task running on this thread, they grow to fit the biggest chunk and are then reused
+    static MESH_CREATOR: RefCell<MeshCreator> = RefCell::new(MeshCreator::default());
+}
+
+/// Blocks needed to mesh one chunk: its own, plus the layer of each of its six neighbours touching it,
+/// so that faces against solid neighbours are culled without handing the whole world map to meshing tasks
+#[derive(Debug, Default, Clone)]
+pub struct ChunkMeshingInput {
+    /// Blocks of the chunk, indexed by their offset in the chunk
+    blocks: HashMap<IVec3, BlockData>,
+    /// Border layers of the neighbours, indexed by their offset from the chunk origin, so just outside of it
+    borders: HashMap<IVec3, BlockData>,
+    /// Grass and leaves color of each block column, ordered as `biome_index`
+    tints: Vec<[f32; 4]>,
+    /// Keeps the faces between two leaves, seen through the gaps of their texture
+    fancy_leaves: bool,
+}
+
+impl ChunkMeshingInput {
+    pub fn new(world_map: &ClientWorldMap, chunk_pos: IVec3, fancy_leaves: bool) -> Option<Self> {
+        let chunk = world_map.map.get(&chunk_pos)?;
+
+        let mut borders = HashMap::new();
+        for offset in &SIX_OFFSETS {
+            let Some(neighbour) = world_map.map.get(&(chunk_pos + *offset)) else {
+                continue;
+            };
+            // The face of the neighbour touching this chunk is the one pointing back at it
+            borders.extend(
+                border_layer(neighbour, -*offset)
+                    .map(|(local, block)| (local + *offset * CHUNK_SIZE, *block)),
+            );
+        }
+
+        Some(Self {
+            blocks: chunk.map.clone(),
+            borders,
+            tints: column_tints(world_map, chunk_pos),
+            fancy_leaves,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Keeps the blocks between `min` and `max`, global positions, as if the rest of the world was empty
+    pub fn crop(&mut self, chunk_pos: IVec3, min: IVec3, max: IVec3) {
+        let origin = chunk_pos * CHUNK_SIZE;
+        let inside = |local: &IVec3| {
+            let pos = origin + *local;
+            pos.cmpge(min).all() && pos.cmple(max).all()
+        };
+        self.blocks.retain(|local, _| inside(local));
+        self.borders.retain(|local, _| inside(local));
+    }
+
+    /// Hash of everything the mesh depends on, `uvs_hash` standing for the textures
+    pub fn content_hash(&self, uvs_hash: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        uvs_hash.hash(&mut hasher);
+        for blocks in [&self.blocks, &self.borders] {
+            let mut sorted: Vec<_> = blocks.iter().collect();
+            sorted.sort_by_key(|(pos, _)| pos.to_array());
+            sorted.hash(&mut hasher);
+        }
+        for tint in &self.tints {
+            tint.map(f32::to_bits).hash(&mut hasher);
+        }
+        self.fancy_leaves.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn get_block(&self, local_pos: &IVec3) -> Option<&BlockData> {
+        self.blocks
+            .get(local_pos)
+            .or_else(|| self.borders.get(local_pos))
+    }
+
+    /// Whether a face against `neighbour` has to be drawn even though both are transparent
+    fn shows_through(&self, neighbour: &BlockId) -> bool {
+        self.fancy_leaves && matches!(neighbour, BlockId::OakLeaves | BlockId::SpruceLeaves)
+    }
+}
+
+/// Blocks of the chunk on its face pointing towards `side`, one of the six offsets
+pub(crate) fn border_layer(
+    chunk: &ClientChunk,
+    side: IVec3,
+) -> impl Iterator<Item = (IVec3, &BlockData)> {
+    chunk
+        .map
+        .iter()
+        .map(|(local, block)| (*local, block))
+        .filter(move |(local, _)| {
+            (0..3).all(|axis| match side[axis] {
+                1 => local[axis] == CHUNK_SIZE - 1,
+                -1 => local[axis] == 0,
+                _ => true,
+            })
+        })
+}
+
+/// Whether the blocks on the face of the chunk pointing towards `side` differ enough to change how the neighbour there is meshed
+pub(crate) fn border_changed(old: Option<&ClientChunk>, new: &ClientChunk, side: IVec3) -> bool {
+    let Some(old) = old else {
+        return true;
+    };
+    // Culling only depends on block ids, not on their direction or breaking progress
+    let layer = |chunk| -> HashMap<IVec3, BlockId> {
+        border_layer(chunk, side)
+            .map(|(local, block)| (local, block.id))
+            .collect()
+    };
+    layer(old) != layer(new)
 }
 
 #[derive(Debug, Default, Clone)]
@@ -53,34 +187,40 @@ pub struct ChunkMeshResponse {
 }
 
 pub(crate) fn generate_chunk_mesh(
-    world_map: &ClientWorldMap,
-    chunk: &ClientChunk,
-    chunk_pos: &IVec3,
+    input: &ChunkMeshingInput,
     uv_map: &HashMap<String, UvCoords>,
 ) -> ChunkMeshResponse {
-    let start = Instant::now();
+    MESH_CREATOR.with_borrow_mut(|creator| {
+        creator.clear();
+        generate_chunk_mesh_with(creator, input, uv_map)
+    })
+}
 
-    let mut solid_mesh_creator = MeshCreator::default();
+fn generate_chunk_mesh_with(
+    solid_mesh_creator: &mut MeshCreator,
+    input: &ChunkMeshingInput,
+    uv_map: &HashMap<String, UvCoords>,
+) -> ChunkMeshResponse {
+    let start = Instant::now();
 
-    for (local_block_offset, block) in chunk.map.iter() {
+    for (local_block_offset, block) in input.blocks.iter() {
         let x = local_block_offset.x as f32;
         let y = local_block_offset.y as f32;
         let z = local_block_offset.z as f32;
 
-        let global_block_pos = &chunk_offset_to_global_pos(chunk_pos, local_block_offset);
         let visibility = block.id.get_visibility();
 
-        if is_block_surrounded(world_map, global_block_pos, &visibility, &block.id) {
+        if visibility == BlockTransparency::Invisible
+            || is_block_surrounded(input, local_block_offset, &visibility, &block.id)
+        {
             continue;
         }
 
-        let mut local_vertices: Vec<[f32; 3]> = vec![];
-        let mut local_indices: Vec<u32> = vec![];
-        let mut local_normals: Vec<[f32; 3]> = vec![];
-        let mut local_uvs: Vec<[f32; 2]> = vec![];
-        let mut local_colors: Vec<[f32; 4]> = vec![];
+        // Faces are written straight into the chunk buffers, then the vertices of this block are moved in place
+        let first_vertex = solid_mesh_creator.vertices.len();
 
-        let voxel: VoxelShape = VoxelShape::create_from_block(block);
+        let tint = input.tints[biome_index(local_block_offset.x, local_block_offset.z)];
+        let voxel: VoxelShape = VoxelShape::create_from_block(block, tint);
 
         for face in voxel.faces.iter() {
             let uv_coords: &UvCoords;
@@ -96,102 +236,58 @@ pub(crate) fn generate_chunk_mesh(
                 _ => 1.0,
             };
 
-            if should_render_face(world_map, global_block_pos, &face.direction, &visibility) {
-                render_face(
-                    &mut local_vertices,
-                    &mut local_indices,
-                    &mut local_normals,
-                    &mut local_uvs,
-                    &mut local_colors,
-                    &mut solid_mesh_creator.indices_offset,
-                    face,
-                    uv_coords,
-                    1.0,
-                    alpha,
-                );
-
-                if block.breaking_progress > 0 {
-                    // Overlay the current breaking progress based on the state of the current block (10 different states)
-                    let breaking_progress = block.get_breaking_level();
-
-                    render_face(
-                        &mut local_vertices,
-                        &mut local_indices,
-                        &mut local_normals,
-                        &mut local_uvs,
-                        &mut local_colors,
-                        &mut solid_mesh_creator.indices_offset,
-                        face,
-                        uv_map
-                            .get(&format!("DestroyStage{breaking_progress}"))
-                            .unwrap(),
-                        1.0,
-                        alpha,
-                    );
-                }
+            if should_render_face(input, local_block_offset, &face.direction, &visibility) {
+                render_face(solid_mesh_creator, face, uv_coords, 1.0, alpha);
             }
         }
 
-        let local_vertices: Vec<[f32; 3]> = local_vertices
-            .iter()
-            .map(|v| {
-                let v = rotate_vertices(v, &block.direction);
-                [v[0] + x, v[1] + y, v[2] + z]
-            })
-            .collect();
-
-        solid_mesh_creator.vertices.extend(local_vertices);
-        solid_mesh_creator.indices.extend(local_indices);
-        solid_mesh_creator.normals.extend(local_normals);
-        solid_mesh_creator.uvs.extend(local_uvs);
-        solid_mesh_creator.colors.extend(local_colors);
+        for v in solid_mesh_creator.vertices[first_vertex..].iter_mut() {
+            let rotated = rotate_vertices(v, &block.direction);
+            *v = [rotated[0] + x, rotated[1] + y, rotated[2] + z];
+        }
     }
 
-    let mut solid_mesh = build_mesh(&solid_mesh_creator);
-
     trace!("Render time : {:?}", Instant::now() - start);
 
-    let should_return_solid = !solid_mesh_creator.vertices.is_empty();
-    if should_return_solid {
-        if let Err(e) = solid_mesh.generate_tangents() {
-            warn!(
-                "Error while generating tangents for the mesh SOLID : {:?} | {:?}",
-                e, solid_mesh
-            );
-        }
-    };
+    if solid_mesh_creator.vertices.is_empty() {
+        return ChunkMeshResponse { solid_mesh: None };
+    }
+
+    let mut solid_mesh = solid_mesh_creator.build_mesh();
+    if let Err(e) = solid_mesh.generate_tangents() {
+        warn!(
+            "Error while generating tangents for the mesh SOLID : {:?} | {:?}",
+            e, solid_mesh
+        );
+    }
 
     ChunkMeshResponse {
-        solid_mesh: if should_return_solid {
-            Some(solid_mesh)
-        } else {
-            None
-        },
+        solid_mesh: Some(solid_mesh),
     }
 }
 
-pub(crate) fn is_block_surrounded(
-    world_map: &ClientWorldMap,
-    global_block_pos: &IVec3,
+fn is_block_surrounded(
+    input: &ChunkMeshingInput,
+    local_block_pos: &IVec3,
     block_visibility: &BlockTransparency,
     block_id: &BlockId,
 ) -> bool {
-    for offset in &shared::world::SIX_OFFSETS {
-        let neighbor_pos = *global_block_pos + *offset;
+    for offset in &SIX_OFFSETS {
+        let neighbor_pos = *local_block_pos + *offset;
 
         // Check if the block exists at the neighboring position
-        if let Some(block) = world_map.get_block_by_coordinates(&neighbor_pos) {
+        if let Some(block) = input.get_block(&neighbor_pos) {
             let vis = block.id.get_visibility();
             match vis {
                 BlockTransparency::Solid => {}
-                BlockTransparency::Decoration => return false,
+                BlockTransparency::Decoration | BlockTransparency::Invisible => return false,
                 BlockTransparency::Liquid => {
                     if vis != *block_visibility {
                         return false;
                     }
                 }
                 BlockTransparency::Transparent => {
-                    if *block_id != block.id {
+                    if *block_id != block.id || input.shows_through(&block.id) {
                         return false;
                     }
                 }
@@ -220,38 +316,32 @@ pub fn rotate_vertices(v: &[f32; 3], direction: &BlockDirection) -> [f32; 3] {
 }
 
 fn render_face(
-    local_vertices: &mut Vec<[f32; 3]>,
-    local_indices: &mut Vec<u32>,
-    local_normals: &mut Vec<[f32; 3]>,
-    local_uvs: &mut Vec<[f32; 2]>,
-    local_colors: &mut Vec<[f32; 4]>,
-    indices_offset: &mut u32,
+    creator: &mut MeshCreator,
     face: &Face,
     uv_coords: &UvCoords,
     color_multiplier: f32,
     alpha: f32,
 ) {
-    local_vertices.extend(face.vertices.iter());
+    creator.vertices.extend(face.vertices.iter());
 
-    local_indices.extend(face.indices.iter().map(|x| x + *indices_offset));
-    *indices_offset += face.vertices.len() as u32;
+    let indices_offset = creator.indices_offset;
+    creator
+        .indices
+        .extend(face.indices.iter().map(|x| x + indices_offset));
+    creator.indices_offset += face.vertices.len() as u32;
 
-    local_normals.extend(face.normals.iter());
+    creator.normals.extend(face.normals.iter());
 
-    let colors = face.colors.iter();
-    let mut new_colors = vec![];
-    for color in colors {
-        new_colors.push([
+    creator.colors.extend(face.colors.iter().map(|color| {
+        [
             color[0] * color_multiplier,
             color[1] * color_multiplier,
             color[2] * color_multiplier,
             alpha,
-        ]);
-    }
-
-    local_colors.extend(new_colors);
+        ]
+    }));
 
-    local_uvs.extend(face.uvs.iter().map(|uv| {
+    creator.uvs.extend(face.uvs.iter().map(|uv| {
         // !!! DO NOT REMOVE THE FLOAT OFFSET !!!
         // It removes seams between blocks in chunk meshes
         [
@@ -262,8 +352,8 @@ fn render_face(
 }
 
 fn should_render_face(
-    world_map: &ClientWorldMap,
-    global_block_pos: &IVec3,
+    input: &ChunkMeshingInput,
+    local_block_pos: &IVec3,
     direction: &FaceDirection,
     block_visibility: &BlockTransparency,
 ) -> bool {
@@ -277,12 +367,15 @@ fn should_render_face(
         FaceDirection::Inset => return true,
     };
 
-    if let Some(block) = world_map.get_block_by_coordinates(&(*global_block_pos + offset)) {
+    if let Some(block) = input.get_block(&(*local_block_pos + offset)) {
         let vis = block.id.get_visibility();
         match vis {
             BlockTransparency::Solid => false,
-            BlockTransparency::Decoration => true,
-            BlockTransparency::Transparent | BlockTransparency::Liquid => *block_visibility != vis,
+            BlockTransparency::Decoration | BlockTransparency::Invisible => true,
+            BlockTransparency::Transparent => {
+                *block_visibility != vis || input.shows_through(&block.id)
+            }
+            BlockTransparency::Liquid => *block_visibility != vis,
         }
     } else {
         true
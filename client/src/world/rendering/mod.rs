@@ -1,9 +1,18 @@
+pub mod block_damage;
+pub mod export;
 pub mod materials;
+pub mod mesh_cache;
 pub mod meshing;
+pub mod outline;
 pub mod render;
 pub mod render_distance;
+pub mod tint;
+pub mod video;
 pub mod voxel;
 
+pub use block_damage::*;
 pub use materials::*;
+pub use outline::*;
 pub use render::*;
 pub use render_distance::*;
+pub use video::*;
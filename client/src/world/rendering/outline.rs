@@ -0,0 +1,111 @@
+use bevy::{
+    math::bounding::{Aabb3d, BoundingVolume},
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+
+use crate::world::VideoSettings;
+use crate::GameState;
+
+/// Blocks the outline sticks out of the targeted box, on top of its depth bias
+const OUTLINE_OFFSET: f32 = 0.002;
+/// Pulls the outline towards the camera, so that it never fights with the faces of the block
+const OUTLINE_DEPTH_BIAS: f32 = 64.0;
+
+/// Edges of a box drawn by `block_outline.wgsl`, which discards the rest of the faces
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone, PartialEq)]
+pub struct BlockOutlineMaterial {
+    #[uniform(0)]
+    pub color: LinearRgba,
+    #[uniform(0)]
+    pub center: Vec3,
+    /// Width of the edges, in blocks
+    #[uniform(0)]
+    pub thickness: f32,
+    #[uniform(0)]
+    pub half_size: Vec3,
+}
+
+impl Material for BlockOutlineMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/block_outline.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn depth_bias(&self) -> f32 {
+        OUTLINE_DEPTH_BIAS
+    }
+}
+
+/// Box of the block aimed at, set by the block interactions. Partial blocks give their own box
+#[derive(Resource, Default, Debug)]
+pub struct TargetedBlockOutline(pub Option<Aabb3d>);
+
+#[derive(Component)]
+pub struct BlockOutlineMarker;
+
+pub fn setup_block_outline(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<BlockOutlineMaterial>>,
+) {
+    commands.spawn((
+        BlockOutlineMarker,
+        Mesh3d(meshes.add(Cuboid::from_size(Vec3::ONE))),
+        MeshMaterial3d(materials.add(BlockOutlineMaterial {
+            color: LinearRgba::WHITE,
+            center: Vec3::ZERO,
+            thickness: 0.0,
+            half_size: Vec3::ZERO,
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+        NotShadowCaster,
+        NotShadowReceiver,
+        StateScoped(GameState::Game),
+    ));
+}
+
+/// Moves the outline to the targeted block, with the color and thickness of the video settings
+pub fn block_outline_system(
+    outline: Single<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &MeshMaterial3d<BlockOutlineMaterial>,
+        ),
+        With<BlockOutlineMarker>,
+    >,
+    mut materials: ResMut<Assets<BlockOutlineMaterial>>,
+    target: Res<TargetedBlockOutline>,
+    video: Res<VideoSettings>,
+) {
+    let (mut transform, mut visibility, material) = outline.into_inner();
+    let Some(bbox) = target.0 else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Visible;
+
+    let center = Vec3::from(bbox.center());
+    let half_size = Vec3::from(bbox.half_size()) + OUTLINE_OFFSET;
+    transform.translation = center;
+    transform.scale = half_size * 2.0;
+
+    let wanted = BlockOutlineMaterial {
+        color: video.outline_color.color().into(),
+        center,
+        thickness: video.outline_thickness,
+        half_size,
+    };
+    // Only written when it changes, each write uploads the material again
+    if materials.get(&material.0) != Some(&wanted) {
+        if let Some(current) = materials.get_mut(&material.0) {
+            *current = wanted;
+        }
+    }
+}
@@ -8,10 +8,7 @@ use bevy::{
     prelude::*,
     tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
 };
-use shared::{
-    world::{global_block_to_chunk_pos, SIX_OFFSETS},
-    CHUNK_SIZE,
-};
+use shared::{world::global_block_to_chunk_pos, CHUNK_SIZE};
 
 use crate::{
     world::{self, MaterialResource, QueuedEvents, WorldRenderRequestUpdateEvent},
@@ -20,7 +17,9 @@ use crate::{
 
 use crate::world::{ClientChunk, ClientWorldMap};
 
-use super::meshing::ChunkMeshResponse;
+use super::mesh_cache::{hash_uv_map, MeshCache, MeshCacheKey};
+use super::meshing::{generate_chunk_mesh, ChunkMeshResponse, ChunkMeshingInput};
+use super::video::VideoSettings;
 
 #[derive(Debug)]
 pub struct MeshingTask {
@@ -34,6 +33,38 @@ pub struct QueuedMeshes {
     pub meshes: Vec<MeshingTask>,
 }
 
+/// Seconds a newly shown chunk takes to rise into place
+const CHUNK_RISE_DURATION: f32 = 0.3;
+/// Blocks below its place a newly shown chunk starts from
+const CHUNK_RISE_DEPTH: f32 = 4.;
+
+/// Raises a chunk into place the first time it is shown, instead of having it pop into existence
+#[derive(Component)]
+pub struct ChunkRiseAnimation {
+    timer: Timer,
+    target: Vec3,
+}
+
+/// Replaces the mesh asset of the chunk with the new one, keeping its handle and entity
+fn update_mesh_in_place(
+    chunk: &ClientChunk,
+    meshes: &mut Assets<Mesh>,
+    new_mesh: Mesh,
+) -> Option<Mesh> {
+    let Some(mesh) = chunk
+        .mesh
+        .as_ref()
+        .and_then(|handle| meshes.get_mut(handle))
+    else {
+        return Some(new_mesh);
+    };
+    if mesh.primitive_topology() != new_mesh.primitive_topology() {
+        return Some(new_mesh);
+    }
+    *mesh = new_mesh;
+    None
+}
+
 fn update_chunk(
     chunk: &mut ClientChunk,
     chunk_pos: &IVec3,
@@ -41,45 +72,70 @@ fn update_chunk(
     commands: &mut Commands,
     meshes: &mut Assets<Mesh>,
     new_meshes: ChunkMeshResponse,
+    animate: bool,
 ) {
-    // If the
+    // Reuse the existing entity and mesh asset whenever possible, re-meshing happens a lot (fluids, block edits...)
+    let new_solid_mesh = match new_meshes.solid_mesh {
+        Some(new_solid_mesh) if chunk.entity.is_some() => {
+            match update_mesh_in_place(chunk, meshes, new_solid_mesh) {
+                Some(new_solid_mesh) => Some(new_solid_mesh),
+                None => return,
+            }
+        }
+        new_solid_mesh => new_solid_mesh,
+    };
 
     let solid_texture = material_resource
         .global_materials
         .get(&world::GlobalMaterial::Blocks)
         .unwrap();
 
-    if chunk.entity.is_some() {
-        commands.entity(chunk.entity.unwrap()).despawn();
-        chunk.entity = None;
+    // Chunks which were already shown are not animated again when their entity gets replaced
+    let was_shown = chunk.entity.is_some();
+    if let Some(entity) = chunk.entity.take() {
+        commands.entity(entity).despawn();
+        chunk.mesh = None;
     }
 
-    if chunk.entity.is_none() {
-        // Offset the chunk's position by half a block so that blocks are centered
-        let chunk_t = Transform::from_xyz(
-            (chunk_pos.x * CHUNK_SIZE) as f32,
-            (chunk_pos.y * CHUNK_SIZE) as f32,
-            (chunk_pos.z * CHUNK_SIZE) as f32,
-        );
-
-        let new_entity = commands
-            .spawn((chunk_t, Visibility::Visible))
-            .with_children(|root| {
-                if let Some(new_solid_mesh) = new_meshes.solid_mesh {
-                    root.spawn((
-                        StateScoped(GameState::Game),
-                        Mesh3d(meshes.add(new_solid_mesh)),
-                        MeshMaterial3d(solid_texture.clone()),
-                    ));
-                }
-            })
-            .id();
+    let Some(new_solid_mesh) = new_solid_mesh else {
+        return;
+    };
 
-        chunk.entity = Some(new_entity);
+    // Offset the chunk's position by half a block so that blocks are centered
+    let chunk_t = Transform::from_xyz(
+        (chunk_pos.x * CHUNK_SIZE) as f32,
+        (chunk_pos.y * CHUNK_SIZE) as f32,
+        (chunk_pos.z * CHUNK_SIZE) as f32,
+    );
+
+    let mesh = meshes.add(new_solid_mesh);
+    let mut new_entity = commands.spawn((chunk_t, Visibility::Visible));
+    new_entity.with_children(|root| {
+        root.spawn((
+            StateScoped(GameState::Game),
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(solid_texture.clone()),
+        ));
+    });
+    if animate && !was_shown {
+        new_entity.insert((
+            ChunkRiseAnimation {
+                timer: Timer::from_seconds(CHUNK_RISE_DURATION, TimerMode::Once),
+                target: chunk_t.translation,
+            },
+            chunk_t.with_translation(chunk_t.translation - Vec3::Y * CHUNK_RISE_DEPTH),
+        ));
     }
-    // debug!("ClientChunk updated : len={}", chunk.map.len());
+    let new_entity = new_entity.id();
+
+    chunk.entity = Some(new_entity);
+    chunk.mesh = Some(mesh);
 }
 
+/// Number of chunks waiting for their mesh, shown in the debug overlay
+#[derive(Resource, Default)]
+pub struct PendingMeshes(pub usize);
+
 pub fn world_render_system(
     mut world_map: ResMut<ClientWorldMap>,
     material_resource: Res<MaterialResource>,
@@ -89,7 +145,10 @@ pub fn world_render_system(
     mut meshes: ResMut<Assets<Mesh>>,
     mut commands: Commands,
     mut first_chunk_received: ResMut<FirstChunkReceived>,
+    mesh_cache: Res<MeshCache>,
     player_pos: Query<&Transform, With<CurrentPlayerMarker>>,
+    mut pending_meshes: ResMut<PendingMeshes>,
+    video: Res<VideoSettings>,
 ) {
     for event in ev_render.read() {
         queued_events.events.insert(*event);
@@ -105,27 +164,18 @@ pub fn world_render_system(
     let events = queued_events.events.clone();
 
     if !events.is_empty() {
-        let start = std::time::Instant::now();
-
-        // Clone map only once, then share it as read-only across all meshing threads
-        let map_ptr = Arc::new(world_map.clone());
-        let delta = start.elapsed();
-        info!("cloning map for render, took {:?}", delta);
-
         let uvs = Arc::new(material_resource.blocks.as_ref().unwrap().uvs.clone());
-
-        let mut chunks_to_reload: HashSet<IVec3> = HashSet::new();
+        let uvs_hash = hash_uv_map(&uvs);
 
         // Using a set so same chunks are not reloaded multiple times
-        // Accumulate chunks to render
-        for event in &events {
-            let WorldRenderRequestUpdateEvent::ChunkToReload(target_chunk_pos) = event;
-
-            chunks_to_reload.insert(*target_chunk_pos);
-            for offset in &SIX_OFFSETS {
-                chunks_to_reload.insert(*target_chunk_pos + *offset);
-            }
-        }
+        // Neighbours whose border changed are already part of the requests
+        let chunks_to_reload: HashSet<IVec3> = events
+            .iter()
+            .map(|event| {
+                let WorldRenderRequestUpdateEvent::ChunkToReload(target_chunk_pos) = event;
+                *target_chunk_pos
+            })
+            .collect();
 
         let player_pos = player_pos
             .single()
@@ -145,26 +195,36 @@ pub fn world_render_system(
         });
 
         for pos in chunks_to_reload {
-            if let Some(chunk) = world_map.map.get(&pos) {
-                // If chunk is empty, ignore it
-                if chunk.map.is_empty() {
-                    continue;
+            // Only the chunk and the borders of its neighbours are sent to the meshing thread
+            let Some(input) = ChunkMeshingInput::new(&world_map, pos, video.fancy_leaves) else {
+                continue;
+            };
+            // If chunk is empty, ignore it
+            if input.is_empty() {
+                continue;
+            }
+
+            let uvs_clone = Arc::clone(&uvs);
+            let cache = mesh_cache.clone();
+            let t = pool.spawn(async move {
+                let key = MeshCacheKey {
+                    chunk_pos: pos,
+                    content_hash: input.content_hash(uvs_hash),
+                };
+                if let Some(cached) = cache.get(&key) {
+                    return cached;
                 }
 
-                // Define variables to move to the thread
-                let map_clone = Arc::clone(&map_ptr);
-                let uvs_clone = Arc::clone(&uvs);
-                let ch = chunk.clone();
-                let t = pool.spawn(async move {
-                    world::meshing::generate_chunk_mesh(&map_clone, &ch, &pos, &uvs_clone)
-                });
+                let response = generate_chunk_mesh(&input, &uvs_clone);
+                cache.insert(key, &response);
+                response
+            });
 
-                queued_meshes.meshes.push(MeshingTask {
-                    chunk_pos: pos,
-                    mesh_request_ts: Instant::now(),
-                    thread: t,
-                });
-            }
+            queued_meshes.meshes.push(MeshingTask {
+                chunk_pos: pos,
+                mesh_request_ts: Instant::now(),
+                thread: t,
+            });
         }
         first_chunk_received.0 = true;
     }
@@ -192,6 +252,7 @@ pub fn world_render_system(
                     &mut commands,
                     &mut meshes,
                     new_meshes,
+                    video.chunk_animation,
                 );
                 false
             } else {
@@ -204,4 +265,21 @@ pub fn world_render_system(
     });
 
     queued_events.events.clear();
+    pending_meshes.0 = queued_meshes.meshes.len();
+}
+
+/// Moves the newly shown chunks up into place, easing out as they get there
+pub fn chunk_rise_system(
+    mut commands: Commands,
+    mut chunks: Query<(Entity, &mut ChunkRiseAnimation, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut animation, mut transform) in chunks.iter_mut() {
+        animation.timer.tick(time.delta());
+        let remaining = 1. - animation.timer.fraction();
+        transform.translation = animation.target - Vec3::Y * CHUNK_RISE_DEPTH * remaining.powi(3);
+        if animation.timer.finished() {
+            commands.entity(entity).remove::<ChunkRiseAnimation>();
+        }
+    }
 }
@@ -24,32 +24,44 @@ pub fn render_distance_update_system(
         render_distance.chunks = DEFAULT_RENDER_DISTANCE_CHUNKS;
     }
 
+    let player_position = player_transform.single().ok().map(|t| t.translation);
+
     if is_action_just_pressed(GameAction::RenderDistanceMinus, &keyboard_input, &key_map) {
-        render_distance.chunks = 1.max(render_distance.chunks - 1);
+        change_render_distance(&mut render_distance, -1, player_position, &mut ev_writer);
+    }
+
+    if is_action_just_pressed(GameAction::RenderDistancePlus, &keyboard_input, &key_map) {
+        change_render_distance(&mut render_distance, 1, player_position, &mut ev_writer);
+    }
+}
+
+/// Grows or shrinks the render distance by `delta` chunks, asking for the chunks which became visible
+pub fn change_render_distance(
+    render_distance: &mut RenderDistance,
+    delta: i32,
+    player_position: Option<Vec3>,
+    ev_writer: &mut EventWriter<WorldRenderRequestUpdateEvent>,
+) {
+    let old_distance = render_distance.distance();
+    render_distance.chunks = render_distance.chunks.saturating_add_signed(delta).max(1);
+    let new_distance = render_distance.distance();
+
+    if delta < 0 {
         info!("Reducing render distance to {}", render_distance.chunks);
         // TODO: we actually need to despawn far away chunks, but probably should be done elsewhere.
+        return;
     }
 
-    if is_action_just_pressed(GameAction::RenderDistancePlus, &keyboard_input, &key_map) {
-        let old_distance = render_distance.distance();
-        render_distance.chunks += 1;
-        let new_distance = render_distance.distance();
-        info!("Increasing render distance to {}", render_distance.chunks);
-        if let Ok(player_transform) = player_transform.single() {
-            ask_for_chunks(
-                ev_writer,
-                player_transform.translation,
-                old_distance,
-                new_distance,
-            );
-        } else {
-            debug!("Player position not found");
-        }
+    info!("Increasing render distance to {}", render_distance.chunks);
+    if let Some(player_position) = player_position {
+        ask_for_chunks(ev_writer, player_position, old_distance, new_distance);
+    } else {
+        debug!("Player position not found");
     }
 }
 
 fn ask_for_chunks(
-    mut ev_writer: EventWriter<WorldRenderRequestUpdateEvent>,
+    ev_writer: &mut EventWriter<WorldRenderRequestUpdateEvent>,
     player_position: Vec3,
     old_distance: f32,
     new_distance: f32,
@@ -69,7 +81,7 @@ fn ask_for_chunks(
                 // Some distances to diagonal chunks will be > new_distance;
                 // we could be smarter about the i,j,k loop but being lazy for now.
                 if chunk_distance <= new_distance && chunk_distance > old_distance {
-                    info!("Requesting reload of chunk at index {}", chunk_index);
+                    debug!("Requesting reload of chunk at index {}", chunk_index);
                     // TODO: this doesn't actually work.
                     ev_writer.write(WorldRenderRequestUpdateEvent::ChunkToReload(chunk_index));
                 }
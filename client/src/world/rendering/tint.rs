@@ -0,0 +1,69 @@
+use bevy::math::IVec3;
+use shared::{
+    world::{biome_index, block_to_chunk_coord, block_to_local_coord, BiomeType},
+    CHUNK_SIZE,
+};
+
+use crate::constants::GRASS_COLOR;
+use crate::world::ClientWorldMap;
+
+/// Columns around each column whose biomes are averaged, so that colors fade across biome borders
+const TINT_BLEND_RADIUS: i32 = 3;
+
+/// Color of grass and leaves in each biome
+fn biome_tint(biome: BiomeType) -> [f32; 4] {
+    match biome {
+        BiomeType::Plains => GRASS_COLOR,
+        BiomeType::FlowerPlains => [0.2, 1.0, 0.25, 1.0],
+        BiomeType::Forest => [0.05, 0.75, 0.2, 1.0],
+        BiomeType::MediumMountain => [0.25, 0.75, 0.35, 1.0],
+        BiomeType::HighMountainGrass => [0.4, 0.7, 0.5, 1.0],
+        BiomeType::Desert => [0.75, 0.8, 0.3, 1.0],
+        BiomeType::IcePlain => [0.5, 0.8, 0.65, 1.0],
+        BiomeType::ShallowOcean | BiomeType::Ocean | BiomeType::DeepOcean => [0.15, 0.85, 0.4, 1.0],
+    }
+}
+
+/// Tint of each block column of the chunk, ordered as `biome_index`, blended with the columns around it.
+/// Columns of neighbouring chunks which are not loaded are left out of the average
+pub(crate) fn column_tints(world_map: &ClientWorldMap, chunk_pos: IVec3) -> Vec<[f32; 4]> {
+    let biome_at = |x: i32, z: i32| -> Option<BiomeType> {
+        let chunk = world_map.map.get(&IVec3::new(
+            chunk_pos.x + block_to_chunk_coord(x),
+            chunk_pos.y,
+            chunk_pos.z + block_to_chunk_coord(z),
+        ))?;
+        chunk
+            .biomes
+            .get(biome_index(
+                block_to_local_coord(x),
+                block_to_local_coord(z),
+            ))
+            .copied()
+    };
+
+    let mut tints = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let mut sum = [0.; 4];
+            let mut count = 0;
+            for dz in -TINT_BLEND_RADIUS..=TINT_BLEND_RADIUS {
+                for dx in -TINT_BLEND_RADIUS..=TINT_BLEND_RADIUS {
+                    let Some(biome) = biome_at(x + dx, z + dz) else {
+                        continue;
+                    };
+                    for (total, channel) in sum.iter_mut().zip(biome_tint(biome)) {
+                        *total += channel;
+                    }
+                    count += 1;
+                }
+            }
+            tints.push(if count == 0 {
+                GRASS_COLOR
+            } else {
+                sum.map(|total| total / count as f32)
+            });
+        }
+    }
+    tints
+}
@@ -0,0 +1,292 @@
+use bevy::{
+    pbr::ScreenSpaceAmbientOcclusion,
+    prelude::*,
+    render::view::{ColorGrading, ColorGradingGlobal, ColorGradingSection},
+    window::{MonitorSelection, PresentMode, PrimaryWindow, VideoModeSelection, WindowMode},
+};
+use ron::{from_str, ser::PrettyConfig};
+use serde::{Deserialize, Serialize};
+use shared::GameFolderPaths;
+use std::{fs, path::PathBuf};
+
+use crate::camera::{CameraController, DEFAULT_FOV};
+use crate::constants::VIDEO_SETTINGS_PATH;
+use crate::world::{ClientWorldMap, WorldRenderRequestUpdateEvent};
+
+pub const MIN_FOV: f32 = 30.0;
+pub const MAX_FOV: f32 = 110.0;
+/// Exposure change, in stops
+pub const MIN_BRIGHTNESS: f32 = -2.0;
+pub const MAX_BRIGHTNESS: f32 = 2.0;
+pub const MIN_GAMMA: f32 = 0.5;
+pub const MAX_GAMMA: f32 = 2.0;
+/// Bounds of `VideoSettings::outline_thickness`
+pub const MIN_OUTLINE_THICKNESS: f32 = 0.005;
+pub const MAX_OUTLINE_THICKNESS: f32 = 0.05;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowModeSetting {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl WindowModeSetting {
+    pub fn next(&self) -> Self {
+        match self {
+            WindowModeSetting::Windowed => WindowModeSetting::Borderless,
+            WindowModeSetting::Borderless => WindowModeSetting::Fullscreen,
+            WindowModeSetting::Fullscreen => WindowModeSetting::Windowed,
+        }
+    }
+
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::Borderless => {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+            }
+            WindowModeSetting::Fullscreen => {
+                WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShadowQuality {
+    Off,
+    #[default]
+    Low,
+    High,
+}
+
+impl ShadowQuality {
+    pub fn next(&self) -> Self {
+        match self {
+            ShadowQuality::Off => ShadowQuality::Low,
+            ShadowQuality::Low => ShadowQuality::High,
+            ShadowQuality::High => ShadowQuality::Off,
+        }
+    }
+
+    /// Size in pixels of the shadow map of the sun and the moon
+    pub fn map_size(&self) -> usize {
+        match self {
+            ShadowQuality::Off | ShadowQuality::Low => 2048,
+            ShadowQuality::High => 4096,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutlineColor {
+    #[default]
+    White,
+    Black,
+    Yellow,
+    Cyan,
+}
+
+impl OutlineColor {
+    pub fn next(&self) -> Self {
+        match self {
+            OutlineColor::White => OutlineColor::Black,
+            OutlineColor::Black => OutlineColor::Yellow,
+            OutlineColor::Yellow => OutlineColor::Cyan,
+            OutlineColor::Cyan => OutlineColor::White,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            OutlineColor::White => Color::WHITE,
+            OutlineColor::Black => Color::BLACK,
+            OutlineColor::Yellow => Color::srgb(1.0, 0.9, 0.2),
+            OutlineColor::Cyan => Color::srgb(0.2, 0.9, 1.0),
+        }
+    }
+}
+
+/// Sets of the expensive features, `Custom` once one of them was changed on its own
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphicsPreset {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Custom,
+}
+
+impl GraphicsPreset {
+    pub fn next(&self) -> Self {
+        match self {
+            GraphicsPreset::Low => GraphicsPreset::Medium,
+            GraphicsPreset::Medium => GraphicsPreset::High,
+            GraphicsPreset::High | GraphicsPreset::Custom => GraphicsPreset::Low,
+        }
+    }
+}
+
+/// How the game is displayed, saved in the game folder and applied as soon as it changes
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct VideoSettings {
+    pub window_mode: WindowModeSetting,
+    pub vsync: bool,
+    /// Vertical field of view, in degrees
+    pub fov: f32,
+    /// Exposure change, in stops
+    pub brightness: f32,
+    pub gamma: f32,
+    pub preset: GraphicsPreset,
+    pub ambient_occlusion: bool,
+    /// Draws the leaves hidden behind other leaves, which show through the gaps of the texture
+    pub fancy_leaves: bool,
+    /// Fraction of the particles which are spawned, from 0 to 1
+    pub particles: f32,
+    pub shadows: ShadowQuality,
+    /// Raises the chunks into place when they are first shown
+    pub chunk_animation: bool,
+    /// Color of the outline of the targeted block
+    pub outline_color: OutlineColor,
+    /// Width of the edges of the targeted block outline, in blocks
+    pub outline_thickness: f32,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowModeSetting::Windowed,
+            vsync: true,
+            fov: DEFAULT_FOV,
+            brightness: 0.0,
+            gamma: 1.0,
+            preset: GraphicsPreset::Medium,
+            ambient_occlusion: false,
+            fancy_leaves: true,
+            particles: 1.0,
+            shadows: ShadowQuality::Low,
+            chunk_animation: true,
+            outline_color: OutlineColor::White,
+            outline_thickness: 0.01,
+        }
+    }
+}
+
+impl VideoSettings {
+    /// Sets all the expensive features at once, `Custom` leaves them as they are
+    pub fn apply_preset(&mut self, preset: GraphicsPreset) {
+        self.preset = preset;
+        let (ambient_occlusion, fancy_leaves, particles, shadows) = match preset {
+            GraphicsPreset::Low => (false, false, 0.25, ShadowQuality::Off),
+            GraphicsPreset::Medium => (false, true, 1.0, ShadowQuality::Low),
+            GraphicsPreset::High => (true, true, 1.0, ShadowQuality::High),
+            GraphicsPreset::Custom => return,
+        };
+        self.ambient_occlusion = ambient_occlusion;
+        self.fancy_leaves = fancy_leaves;
+        self.particles = particles;
+        self.shadows = shadows;
+    }
+
+    fn color_grading(&self) -> ColorGrading {
+        let section = ColorGradingSection {
+            gamma: self.gamma,
+            ..default()
+        };
+        ColorGrading {
+            global: ColorGradingGlobal {
+                exposure: self.brightness,
+                ..default()
+            },
+            shadows: section,
+            midtones: section,
+            highlights: section,
+        }
+    }
+}
+
+fn video_settings_path(paths: &GameFolderPaths) -> PathBuf {
+    paths.game_folder_path.join(VIDEO_SETTINGS_PATH)
+}
+
+pub fn load_video_settings(paths: &GameFolderPaths) -> VideoSettings {
+    let Ok(content) = fs::read_to_string(video_settings_path(paths)) else {
+        return VideoSettings::default();
+    };
+
+    match from_str::<VideoSettings>(&content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Invalid video settings, using the defaults: {}", e);
+            VideoSettings::default()
+        }
+    }
+}
+
+pub fn save_video_settings(settings: &VideoSettings, paths: &GameFolderPaths) {
+    let path = video_settings_path(paths);
+
+    match ron::ser::to_string_pretty(settings, PrettyConfig::new()) {
+        Ok(serialized) => {
+            if let Err(e) = fs::write(&path, serialized) {
+                error!("Error while saving video settings to {:?}: {}", path, e);
+            }
+        }
+        Err(e) => error!("Failed to serialize video settings: {}", e),
+    }
+}
+
+/// Applies the video settings whenever they change, and to the camera when it is spawned
+pub fn apply_video_settings_system(
+    video: Res<VideoSettings>,
+    mut commands: Commands,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    cameras: Query<Entity, With<CameraController>>,
+    new_cameras: Query<(), Added<CameraController>>,
+    world_map: Res<ClientWorldMap>,
+    mut ev_render: EventWriter<WorldRenderRequestUpdateEvent>,
+    mut fancy_leaves: Local<Option<bool>>,
+) {
+    if !video.is_changed() && new_cameras.is_empty() {
+        return;
+    }
+
+    if let Ok(mut window) = windows.single_mut() {
+        let mode = video.window_mode.window_mode();
+        if window.mode != mode {
+            window.mode = mode;
+        }
+        let present_mode = if video.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+        if window.present_mode != present_mode {
+            window.present_mode = present_mode;
+        }
+    }
+
+    for entity in cameras.iter() {
+        let mut camera = commands.entity(entity);
+        camera.insert(video.color_grading());
+        // Ambient occlusion reads the depth and normals of the scene, which multisampling does not provide
+        if video.ambient_occlusion {
+            camera.insert((ScreenSpaceAmbientOcclusion::default(), Msaa::Off));
+        } else {
+            camera
+                .remove::<ScreenSpaceAmbientOcclusion>()
+                .insert(Msaa::default());
+        }
+    }
+
+    // The leaves of every chunk are meshed again with or without their hidden faces
+    if fancy_leaves.is_some_and(|fancy| fancy != video.fancy_leaves) {
+        for pos in world_map.map.keys() {
+            ev_render.write(WorldRenderRequestUpdateEvent::ChunkToReload(*pos));
+        }
+    }
+    *fancy_leaves = Some(video.fancy_leaves);
+}
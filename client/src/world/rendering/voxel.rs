@@ -1,4 +1,4 @@
-use crate::constants::GRASS_COLOR;
+use crate::constants::FIRE_COLOR;
 use shared::world::{BlockData, BlockId};
 
 /// Specifies which position in the voxel this face occupies
@@ -38,8 +38,8 @@ pub struct VoxelShape {
 }
 
 impl VoxelShape {
-    /// Creates a VoxelShape based on the given BlockData
-    pub fn create_from_block(block: &BlockData) -> VoxelShape {
+    /// Creates a VoxelShape based on the given BlockData, grass and leaves taking the `tint` of their biome
+    pub fn create_from_block(block: &BlockData, tint: [f32; 4]) -> VoxelShape {
         match block.id {
             BlockId::Grass => {
                 let mut shape = Self::full_cube(block);
@@ -49,7 +49,7 @@ impl VoxelShape {
                     if index == 0 {
                         face.texture += "Top";
                         for col in face.colors.iter_mut() {
-                            *col = GRASS_COLOR;
+                            *col = tint;
                         }
                     }
                 }
@@ -65,10 +65,10 @@ impl VoxelShape {
             BlockId::OakLeaves | BlockId::SpruceLeaves => {
                 let mut shape = Self::full_cube(block);
 
-                // Apply leaves color
+                // Apply biome color to leaves
                 for face in shape.faces.iter_mut() {
                     for col in face.colors.iter_mut() {
-                        *col = GRASS_COLOR;
+                        *col = tint;
                     }
                 }
 
@@ -88,10 +88,34 @@ impl VoxelShape {
             BlockId::TallGrass => {
                 let mut shape = Self::flora(block);
 
-                // Apply grass color to TallGrass
+                // Apply biome color to TallGrass
                 for face in shape.faces.iter_mut() {
                     for col in face.colors.iter_mut() {
-                        *col = GRASS_COLOR;
+                        *col = tint;
+                    }
+                }
+
+                shape
+            }
+            BlockId::SnowLayer => {
+                let mut shape = Self::full_cube(block);
+                let height = block.height();
+                for face in shape.faces.iter_mut() {
+                    face.texture = "Snow".into();
+                    for vertex in face.vertices.iter_mut() {
+                        vertex[1] *= height;
+                    }
+                }
+                // The top is lower than the block above, so it shows whatever is there
+                shape.faces[0].direction = FaceDirection::Inset;
+                shape
+            }
+            BlockId::Fire => {
+                let mut shape = Self::flora(block);
+
+                for face in shape.faces.iter_mut() {
+                    for col in face.colors.iter_mut() {
+                        *col = FIRE_COLOR;
                     }
                 }
 
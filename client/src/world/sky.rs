@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+    render::{
+        mesh::VertexAttributeValues,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::player::CurrentPlayerMarker;
+use crate::world::celestial::{daylight, sun_direction, CelestialRoot};
+use crate::world::materials::MaterialResource;
+use crate::world::time::ClientTime;
+use crate::world::GlobalMaterial;
+
+/// The dome stays inside the far plane of the camera, with the stars, the sun and the moon in front of it
+const SKY_RADIUS: f32 = 800.;
+const STARS_RADIUS: f32 = 780.;
+/// Rings and segments of the dome, the gradient is interpolated between its vertices
+const SKY_RINGS: u32 = 16;
+const SKY_SEGMENTS: u32 = 32;
+
+const STARS_TEXTURE_WIDTH: u32 = 1024;
+const STARS_TEXTURE_HEIGHT: u32 = 512;
+const STAR_COUNT: usize = 1500;
+const STARS_SEED: u64 = 0x57A125;
+
+const DAY_ZENITH_COLOR: Srgba = Srgba::rgb(0.25, 0.5, 0.95);
+const DAY_HORIZON_COLOR: Srgba = Srgba::rgb(0.7, 0.8, 0.95);
+const NIGHT_ZENITH_COLOR: Srgba = Srgba::rgb(0.0, 0.0, 0.02);
+const NIGHT_HORIZON_COLOR: Srgba = Srgba::rgb(0.02, 0.03, 0.08);
+const SUNSET_COLOR: Srgba = Srgba::rgb(1.0, 0.45, 0.15);
+/// How tightly the sunrise and sunset glow gathers around the sun, higher is tighter
+const SUNSET_SPREAD: i32 = 4;
+
+/// Colored by the time of day, follows the player without turning
+#[derive(Component)]
+pub struct SkyDome;
+
+/// Turns with the sun and the moon, only seen at night
+#[derive(Component)]
+pub struct Starfield;
+
+/// Color of the sky on the horizon at `time`, which distant things fade into
+pub fn horizon_color(time: u64) -> Srgba {
+    NIGHT_HORIZON_COLOR.mix(&DAY_HORIZON_COLOR, daylight(time))
+}
+
+/// Strength of the sunrise and sunset glow, strongest when the sun touches the horizon
+fn sunset_strength(sun: Vec3) -> f32 {
+    (1.0 - sun.y.abs() * 4.0).max(0.0)
+}
+
+/// Color of the sky in the direction `direction`, of length 1, at `time`
+fn sky_color(direction: Vec3, time: u64) -> Srgba {
+    let day = daylight(time);
+    let zenith = NIGHT_ZENITH_COLOR.mix(&DAY_ZENITH_COLOR, day);
+    let height = direction.y.max(0.0);
+    let color = horizon_color(time).mix(&zenith, height.sqrt());
+
+    let sun = sun_direction(time);
+    let towards_sun = direction
+        .with_y(0.0)
+        .normalize_or_zero()
+        .dot(sun.with_y(0.0).normalize_or_zero())
+        .max(0.0);
+    let glow = sunset_strength(sun) * towards_sun.powi(SUNSET_SPREAD) * (1.0 - height);
+    color.mix(&SUNSET_COLOR, glow)
+}
+
+/// Texture of the sky in the folder of the textures, if it has one
+pub fn sky_texture(
+    asset_server: &AssetServer,
+    sky_path: &Path,
+    name: &str,
+) -> Option<Handle<Image>> {
+    let path = sky_path.join(name).with_extension("png");
+    if !path.exists() {
+        return None;
+    }
+    info!("Using the sky texture {}", path.display());
+    Some(asset_server.load(path.to_string_lossy().into_owned()))
+}
+
+/// Stars scattered at random, used when the textures do not come with their own
+pub fn starfield_image() -> Image {
+    let mut rng = StdRng::seed_from_u64(STARS_SEED);
+    let mut data = vec![0; (STARS_TEXTURE_WIDTH * STARS_TEXTURE_HEIGHT * 4) as usize];
+    for _ in 0..STAR_COUNT {
+        let x = rng.gen_range(0..STARS_TEXTURE_WIDTH);
+        let y = rng.gen_range(0..STARS_TEXTURE_HEIGHT);
+        let brightness = rng.gen_range(120..=255);
+        let index = ((y * STARS_TEXTURE_WIDTH + x) * 4) as usize;
+        data[index..index + 4].copy_from_slice(&[brightness, brightness, brightness, brightness]);
+    }
+
+    Image::new(
+        Extent3d {
+            width: STARS_TEXTURE_WIDTH,
+            height: STARS_TEXTURE_HEIGHT,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+pub fn setup_sky(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material_resource: Res<MaterialResource>,
+    player: Query<Entity, With<CurrentPlayerMarker>>,
+    celestial_root: Query<Entity, With<CelestialRoot>>,
+) {
+    let material = |material: GlobalMaterial| {
+        material_resource
+            .global_materials
+            .get(&material)
+            .expect("Sky material not found !")
+            .clone()
+    };
+
+    let dome = commands
+        .spawn((
+            SkyDome,
+            Mesh3d(meshes.add(Sphere::new(SKY_RADIUS).mesh().uv(SKY_SEGMENTS, SKY_RINGS))),
+            MeshMaterial3d(material(GlobalMaterial::Sky)),
+            Transform::default(),
+            NotShadowCaster,
+            NotShadowReceiver,
+        ))
+        .id();
+    commands
+        .entity(player.single().expect("Player should exist"))
+        .add_child(dome);
+
+    let stars = commands
+        .spawn((
+            Starfield,
+            Mesh3d(meshes.add(Sphere::new(STARS_RADIUS).mesh().uv(SKY_SEGMENTS, SKY_RINGS))),
+            MeshMaterial3d(material(GlobalMaterial::Stars)),
+            Transform::default(),
+            NotShadowCaster,
+            NotShadowReceiver,
+        ))
+        .id();
+    commands
+        .entity(
+            celestial_root
+                .single()
+                .expect("Celestial root should exist"),
+        )
+        .add_child(stars);
+}
+
+/// Paints the gradient of the dome and fades the stars in at night, once per tick of the day
+pub fn update_sky_system(
+    dome: Query<&Mesh3d, With<SkyDome>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    material_resource: Res<MaterialResource>,
+    time: Res<ClientTime>,
+    mut painted: Local<Option<(AssetId<Mesh>, u64)>>,
+) {
+    let Ok(dome) = dome.single() else {
+        return;
+    };
+    // A new dome is spawned each time a world is joined
+    if *painted == Some((dome.0.id(), time.0)) {
+        return;
+    }
+    let Some(mesh) = meshes.get_mut(&dome.0) else {
+        return;
+    };
+    *painted = Some((dome.0.id(), time.0));
+
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+    let colors: Vec<[f32; 4]> = positions
+        .iter()
+        .map(|position| {
+            let direction = Vec3::from_array(*position).normalize_or_zero();
+            LinearRgba::from(sky_color(direction, time.0)).to_f32_array()
+        })
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    if let Some(stars) = material_resource
+        .global_materials
+        .get(&GlobalMaterial::Stars)
+        .and_then(|handle| materials.get_mut(handle))
+    {
+        stars.base_color = Color::WHITE.with_alpha(1.0 - daylight(time.0));
+    }
+}
@@ -1,10 +1,31 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use shared::messages::TickRateUpdate;
+use shared::world::GameRules;
+use shared::TICKS_PER_SECOND;
 
 #[derive(Resource, Serialize, Deserialize, Debug, Clone)]
 pub struct ClientTime(pub u64);
 
-pub fn time_update_system(mut time: ResMut<ClientTime>) {
+/// Simulation speed of the server, the clock and the projectiles slow down or stop with it
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct ServerTickRate(pub TickRateUpdate);
+
+pub fn time_update_system(
+    mut time: ResMut<ClientTime>,
+    game_rules: Res<GameRules>,
+    tick_rate: Res<ServerTickRate>,
+    mut progress: Local<u64>,
+) {
+    if !game_rules.do_daylight_cycle || tick_rate.0.frozen {
+        return;
+    }
+    // Same pacing as the server, which skips ticks when its rate is lowered
+    *progress += tick_rate.0.rate;
+    if *progress < TICKS_PER_SECOND {
+        return;
+    }
+    *progress -= TICKS_PER_SECOND;
     time.0 += 1;
     // NOTE: time should eventually be periodically synced with the server to avoid drift using a NTP-like protocol
 }
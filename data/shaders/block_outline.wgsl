@@ -0,0 +1,22 @@
+// Outline of the targeted block: only the parts of the box close to two of its sides at once are drawn,
+// which are its edges, whatever the size of the box
+#import bevy_pbr::forward_io::VertexOutput
+
+struct BlockOutlineMaterial {
+    color: vec4<f32>,
+    center: vec3<f32>,
+    thickness: f32,
+    half_size: vec3<f32>,
+}
+
+@group(2) @binding(0) var<uniform> material: BlockOutlineMaterial;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let to_sides = material.half_size - abs(in.world_position.xyz - material.center);
+    let near = step(to_sides, vec3<f32>(material.thickness));
+    if near.x + near.y + near.z < 2.0 {
+        discard;
+    }
+    return material.color;
+}
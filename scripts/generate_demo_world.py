@@ -0,0 +1,186 @@
+#!/bin/python3
+
+"""Builds the demo world shipped in data/demo_world.tar.gz, a world archive like the ones of /backup.
+
+The save holds every chunk around the spawn, so the demo starts without generating
+anything. Past them the world is generated from its seed like any other world.
+Run from the root of the repository after changing the layout below.
+"""
+
+import os
+import tarfile
+import tempfile
+
+OUTPUT_ARCHIVE = os.path.join("data", "demo_world.tar.gz")
+WORLD_NAME = "demo_world"
+SEED = 20240601
+
+CHUNK_SIZE = 16
+MIN_Y = 32
+MAX_Y = 128
+# Chunk columns saved around the spawn, from -RADIUS to RADIUS - 1 on both axes
+CHUNK_RADIUS = 2
+GROUND_Y = 64
+
+
+def log(message):
+    """Log messages to the console."""
+    print(f"[INFO] {message}")
+
+
+def build_blocks():
+    """Returns the blocks of the demo as a dict of (x, y, z) -> (block id, layers)."""
+    blocks = {}
+    extent = CHUNK_RADIUS * CHUNK_SIZE
+
+    def put(x, y, z, block, layers=0):
+        blocks[(x, y, z)] = (block, layers)
+
+    def fill(x1, y1, z1, x2, y2, z2, block):
+        for x in range(min(x1, x2), max(x1, x2) + 1):
+            for y in range(min(y1, y2), max(y1, y2) + 1):
+                for z in range(min(z1, z2), max(z1, z2) + 1):
+                    put(x, y, z, block)
+
+    # Ground
+    for x in range(-extent, extent):
+        for z in range(-extent, extent):
+            put(x, MIN_Y, z, "Bedrock")
+            for y in range(MIN_Y + 1, GROUND_Y - 3):
+                put(x, y, z, "Stone")
+            for y in range(GROUND_Y - 3, GROUND_Y):
+                put(x, y, z, "Dirt")
+            put(x, GROUND_Y, z, "Grass")
+
+    # House
+    fill(-6, GROUND_Y + 1, 4, 2, GROUND_Y + 4, 12, "OakPlanks")
+    fill(-5, GROUND_Y + 1, 5, 1, GROUND_Y + 3, 11, None)
+    for x, z in [(-6, 4), (2, 4), (-6, 12), (2, 12)]:
+        fill(x, GROUND_Y + 1, z, x, GROUND_Y + 4, z, "OakLog")
+    fill(-2, GROUND_Y + 1, 4, -2, GROUND_Y + 2, 4, None)
+    for x in [-4, 0]:
+        put(x, GROUND_Y + 2, 4, "Glass")
+        put(x, GROUND_Y + 2, 12, "Glass")
+    put(-6, GROUND_Y + 2, 8, "Glass")
+    put(2, GROUND_Y + 2, 8, "Glass")
+    put(-2, GROUND_Y + 3, 8, "Light", 12)
+
+    # Trees
+    for tx, tz in [(10, 10), (-14, -8)]:
+        for y in range(GROUND_Y + 1, GROUND_Y + 6):
+            put(tx, y, tz, "OakLog")
+        for x in range(tx - 2, tx + 3):
+            for z in range(tz - 2, tz + 3):
+                for y in range(GROUND_Y + 4, GROUND_Y + 7):
+                    corner = abs(x - tx) == 2 and abs(z - tz) == 2
+                    if (x, z) != (tx, tz) and not corner:
+                        put(x, y, z, "OakLeaves")
+        put(tx, GROUND_Y + 7, tz, "OakLeaves")
+
+    # Pond
+    for x in range(6, 13):
+        for z in range(-10, -3):
+            if (x - 9) ** 2 + (z + 7) ** 2 <= 9:
+                put(x, GROUND_Y, z, "Water")
+                put(x, GROUND_Y - 1, z, "Sand")
+            elif (x - 9) ** 2 + (z + 7) ** 2 <= 13:
+                put(x, GROUND_Y, z, "Sand")
+
+    # Desert corner
+    fill(-20, GROUND_Y, 14, -12, GROUND_Y, 22, "Sand")
+    for x, z in [(-18, 16), (-14, 20)]:
+        fill(x, GROUND_Y + 1, z, x, GROUND_Y + 3, z, "Cactus")
+
+    # Snowy corner
+    fill(14, GROUND_Y, 14, 22, GROUND_Y, 22, "Snow")
+    for x in range(14, 23):
+        for z in range(14, 23):
+            put(x, GROUND_Y + 1, z, "SnowLayer", 1 + (x * 3 + z) % 4)
+    fill(18, GROUND_Y + 1, 18, 18, GROUND_Y + 5, 18, "SpruceLog")
+    fill(17, GROUND_Y + 4, 17, 19, GROUND_Y + 4, 19, "SpruceLeaves")
+    put(18, GROUND_Y + 6, 18, "SpruceLeaves")
+    put(18, GROUND_Y + 4, 18, "SpruceLog")
+    put(19, GROUND_Y + 1, 17, "Ice")
+
+    # Ore samples, on cobblestone pedestals
+    for i, ore in enumerate(["CoalOre", "IronOre", "GoldOre", "DiamondOre"]):
+        x = -14 + i * 3
+        put(x, GROUND_Y + 1, -18, "Cobblestone")
+        put(x, GROUND_Y + 2, -18, ore)
+
+    # Flowers and grass
+    for x in range(-extent + 2, extent - 2, 5):
+        for z in range(-extent + 3, extent - 3, 7):
+            if blocks.get((x, GROUND_Y, z), (None,))[0] != "Grass":
+                continue
+            if (x, GROUND_Y + 1, z) in blocks:
+                continue
+            put(x, GROUND_Y + 1, z, ["Dandelion", "Poppy", "TallGrass"][(x + z) % 3])
+
+    return {pos: block for pos, block in blocks.items() if block[0] is not None}
+
+
+def block_ron(block, layers):
+    layers_field = f",layers:{layers}" if layers else ""
+    return f"(id:{block},direction:Front,breaking_progress:0{layers_field})"
+
+
+def write_chunks(blocks, chunks_dir):
+    chunks = {}
+    for cx in range(-CHUNK_RADIUS, CHUNK_RADIUS):
+        for cz in range(-CHUNK_RADIUS, CHUNK_RADIUS):
+            for cy in range(MIN_Y // CHUNK_SIZE, (MAX_Y - 1) // CHUNK_SIZE + 1):
+                chunks[(cx, cy, cz)] = []
+
+    for (x, y, z), (block, layers) in sorted(blocks.items()):
+        chunk = (x // CHUNK_SIZE, y // CHUNK_SIZE, z // CHUNK_SIZE)
+        local = (x % CHUNK_SIZE, y % CHUNK_SIZE, z % CHUNK_SIZE)
+        chunks[chunk].append(f"({local[0]},{local[1]},{local[2]}):{block_ron(block, layers)}")
+
+    for (cx, cy, cz), entries in chunks.items():
+        # Empty chunks are saved too, so that they are not generated
+        content = (
+            "(map:{" + ",".join(entries) + "},ts:0,sent_to_clients:[],player_modified:true)"
+        )
+        with open(os.path.join(chunks_dir, f"{cx}_{cy}_{cz}.ron"), "w") as file:
+            file.write(content)
+
+    return len(chunks)
+
+
+def write_world(output_dir):
+    content = f"""(
+    mobs: {{}},
+    seed: ({SEED}),
+    name: "{WORLD_NAME}",
+    time: 0,
+    item_stacks: [],
+    gen_settings: (
+        min_y: {MIN_Y},
+        max_y: {MAX_Y},
+    ),
+)
+"""
+    with open(os.path.join(output_dir, "world.ron"), "w") as file:
+        file.write(content)
+
+
+def main():
+    with tempfile.TemporaryDirectory() as tmp:
+        world_dir = os.path.join(tmp, WORLD_NAME)
+        chunks_dir = os.path.join(world_dir, "chunks")
+        os.makedirs(chunks_dir)
+
+        blocks = build_blocks()
+        write_world(world_dir)
+        chunk_count = write_chunks(blocks, chunks_dir)
+
+        # All the entries are stored under a single folder named after the world
+        with tarfile.open(OUTPUT_ARCHIVE, "w:gz") as archive:
+            archive.add(world_dir, arcname=WORLD_NAME)
+
+    log(f"Demo world written to {OUTPUT_ARCHIVE}: {chunk_count} chunks, {len(blocks)} blocks")
+
+
+if __name__ == "__main__":
+    main()
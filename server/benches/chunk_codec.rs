@@ -0,0 +1,85 @@
+//! Serialization and compression of generated chunks with each candidate wire codec.
+//! The sizes are printed before the timings, `shared::codec::WIRE_CODEC` picks the codec in use
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use bevy::math::IVec3;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use server::{generate_chunk, WorldGenPreset};
+use shared::codec::WireCodec;
+use shared::world::{ServerChunk, WorldGenSettings};
+
+const SEED: u32 = 1234;
+/// Columns of chunks around spawn, as a player joining receives them
+const RADIUS: i32 = 1;
+
+/// The chunks of a few columns around spawn, sent together like in a `WorldUpdate`
+fn generated_chunks() -> HashMap<IVec3, ServerChunk> {
+    let limits = WorldGenSettings::default();
+    let preset = WorldGenPreset::default();
+
+    let mut chunks = HashMap::new();
+    for x in -RADIUS..=RADIUS {
+        for z in -RADIUS..=RADIUS {
+            for y in limits.chunk_y_range() {
+                let pos = IVec3::new(x, y, z);
+                chunks.insert(pos, generate_chunk(pos, SEED, limits, &preset).chunk);
+            }
+        }
+    }
+    chunks
+}
+
+fn chunk_codec(c: &mut Criterion) {
+    let chunks = generated_chunks();
+
+    for codec in WireCodec::candidates() {
+        let serialized = codec.serialize(&chunks).unwrap();
+        let compressed = codec.compress(&serialized).unwrap();
+        println!(
+            "{:<16} {} chunks: {} bytes serialized, {} bytes compressed",
+            codec.name(),
+            chunks.len(),
+            serialized.len(),
+            compressed.len()
+        );
+    }
+
+    let mut group = c.benchmark_group("chunk_codec");
+    for codec in WireCodec::candidates() {
+        let serialized = codec.serialize(&chunks).unwrap();
+        let compressed = codec.compress(&serialized).unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("serialize", codec.name()),
+            &chunks,
+            |b, chunks| b.iter(|| codec.serialize(black_box(chunks)).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("deserialize", codec.name()),
+            &serialized,
+            |b, bytes| {
+                b.iter(|| {
+                    codec
+                        .deserialize::<HashMap<IVec3, ServerChunk>>(black_box(bytes))
+                        .unwrap()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("compress", codec.name()),
+            &serialized,
+            |b, bytes| b.iter(|| codec.compress(black_box(bytes)).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("decompress", codec.name()),
+            &compressed,
+            |b, bytes| b.iter(|| codec.decompress(black_box(bytes)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, chunk_codec);
+criterion_main!(benches);
@@ -1,32 +1,57 @@
 use crate::{
+    logging::server_log_layers,
+    metrics::ServerMetrics,
     network::{
         cleanup::cleanup_all_players_from_world,
         dispatcher::{self, setup_resources_and_events},
+        lan::LanBroadcaster,
+        map_viewer::MapViewer,
+        tokens::{
+            load_server_identity, parse_private_key, serve_connect_tokens, GrantedNames, NamePins,
+        },
+    },
+    settings::{load_server_settings, ServerSettings},
+    world::{
+        crash::{handle_server_crash, install_panic_hook, mark_world_running, mark_world_stopped},
+        data::{CHUNKS_SAVE_DIR, SAVE_PATH},
+        generation::fill_missing_biomes,
+        journal::load_event_journal,
+        load_from_file::{load_chunks_data, load_world_data},
+        packs::{load_world_packs, PackTransfers},
+        preset::{load_world_gen_preset, WorldCacheId},
+        reload::ChunkFileWatcher,
+        save::{world_save_dir, SaveWorker},
+        tick_control::TickControl,
     },
-    world::{data::SAVE_PATH, load_from_file::load_world_data},
 };
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     prelude::*,
 };
 use bevy_app::ScheduleRunnerPlugin;
-use bevy_renet::{netcode::NetcodeServerTransport, RenetServerPlugin};
 use bevy_renet::{
-    netcode::{NetcodeServerPlugin, ServerAuthentication, ServerConfig},
+    netcode::{NetcodeServerTransport, ServerAuthentication, ServerConfig},
     renet::RenetServer,
+    RenetServerPlugin,
 };
 use serde::{Deserialize, Serialize};
 use shared::{
     get_shared_renet_config,
     messages::PlayerId,
-    world::{ServerChunkWorldMap, ServerWorldMap},
+    network::{
+        ActiveServerTransport, MemoryServerTransport, MultiSocketServerTransport, ServerTransport,
+        ServerTransportPlugin,
+    },
+    world::{ChunkUpdates, ServerChunkWorldMap, ServerWorldMap, SimulationRandom},
     GameFolderPaths, GameServerConfig, TICKS_PER_SECOND,
 };
 use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
 use std::time::{Duration, SystemTime};
 use std::{collections::HashMap, net::IpAddr};
 
-use std::net::{SocketAddr, UdpSocket};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
 
 #[derive(Resource, Serialize, Deserialize, Debug, Clone)]
 pub struct ServerTime(pub u64);
@@ -49,6 +74,8 @@ pub struct ServerLobby {
     pub players: HashMap<PlayerId, LobbyPlayer>,
 }
 
+pub const MAX_CLIENTS: usize = 64;
+
 #[allow(dead_code)]
 pub fn acquire_local_ephemeral_udp_socket(ip: IpAddr) -> UdpSocket {
     acquire_socket_by_port(ip, 0)
@@ -59,30 +86,157 @@ pub fn acquire_socket_by_port(ip: IpAddr, port: u16) -> UdpSocket {
     UdpSocket::bind(addr).unwrap()
 }
 
-pub fn add_netcode_network(app: &mut App, socket: UdpSocket) {
-    app.add_plugins(NetcodeServerPlugin);
+/// Binds the game port on each of the `bind_addresses` of the settings, on every IPv4 interface when there are none
+pub fn bind_game_sockets(addresses: &[IpAddr], port: u16) -> Result<Vec<UdpSocket>, String> {
+    let default = [IpAddr::V4(Ipv4Addr::UNSPECIFIED)];
+    let addresses = if addresses.is_empty() {
+        &default[..]
+    } else {
+        addresses
+    };
+
+    addresses
+        .iter()
+        .map(|ip| {
+            let addr = SocketAddr::new(*ip, port);
+            UdpSocket::bind(addr).map_err(|e| format!("Could not listen on {}: {}", addr, e))
+        })
+        .collect()
+}
+
+/// How the clients reach the server
+pub enum ServerListener {
+    /// Netcode over UDP, for players on other machines, with one socket per bound address
+    Udp(Vec<UdpSocket>),
+    /// Channels within the process, for the singleplayer client and tests.
+    /// Only built through the library, the server binary listens on UDP
+    #[allow(dead_code)]
+    Memory(MemoryServerTransport),
+}
+
+impl ServerListener {
+    fn describe(&self) -> String {
+        match self {
+            ServerListener::Udp(sockets) => local_addresses(sockets)
+                .iter()
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            ServerListener::Memory(_) => "in-memory channels".into(),
+        }
+    }
+}
+
+fn local_addresses(sockets: &[UdpSocket]) -> Vec<SocketAddr> {
+    sockets
+        .iter()
+        .filter_map(|socket| socket.local_addr().ok())
+        .collect()
+}
+
+pub fn add_network(
+    app: &mut App,
+    listener: ServerListener,
+    settings: &ServerSettings,
+    game_folder_paths: &GameFolderPaths,
+    world_dir: &Path,
+) -> Result<(), String> {
+    app.add_plugins(ServerTransportPlugin);
 
     let server = RenetServer::new(get_shared_renet_config());
 
-    let granted_addr = &socket.local_addr().unwrap();
+    let transport: Box<dyn ServerTransport> = match listener {
+        ServerListener::Udp(sockets) => {
+            let granted_names = GrantedNames::default();
+            app.insert_resource(granted_names.clone());
+            let name_pins = NamePins::load(world_dir)?;
+            netcode_transport(
+                sockets,
+                settings,
+                game_folder_paths,
+                &granted_names,
+                &name_pins,
+            )?
+        }
+        ServerListener::Memory(transport) => Box::new(transport),
+    };
+    app.insert_resource(server);
+    app.insert_resource(ActiveServerTransport(transport));
+    Ok(())
+}
+
+fn netcode_transport(
+    mut sockets: Vec<UdpSocket>,
+    settings: &ServerSettings,
+    game_folder_paths: &GameFolderPaths,
+    granted_names: &GrantedNames,
+    name_pins: &NamePins,
+) -> Result<Box<dyn ServerTransport>, String> {
+    let granted_addrs = local_addresses(&sockets);
+
+    let private_key = settings
+        .private_key
+        .as_deref()
+        .map(parse_private_key)
+        .transpose()
+        .map_err(|e| format!("Invalid private key in the server settings: {e}"))?;
+    let (authentication, public_addresses) = match private_key {
+        None => {
+            if !settings.ops.is_empty() {
+                warn!("The ops have no rights without a private key in the server settings: clients could pick the player id of an op");
+            }
+            (ServerAuthentication::Unsecure, granted_addrs.clone())
+        }
+        Some(private_key) => {
+            if settings.public_addresses.is_empty() {
+                return Err("The private key needs the public addresses of the server in the server settings".into());
+            }
+            (
+                ServerAuthentication::Secure { private_key },
+                settings.public_addresses.clone(),
+            )
+        }
+    };
+    // Clients only connect with a grant, even to servers which do not encrypt connections
+    serve_connect_tokens(
+        &granted_addrs,
+        private_key,
+        public_addresses.clone(),
+        &load_server_identity(game_folder_paths)?,
+        granted_names,
+        name_pins,
+    )?;
 
     let current_time: Duration = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap();
     let server_config = ServerConfig {
         current_time,
-        max_clients: 64,
+        max_clients: MAX_CLIENTS,
         protocol_id: shared::PROTOCOL_ID,
-        public_addresses: vec![*granted_addr],
-        authentication: ServerAuthentication::Unsecure,
+        public_addresses,
+        authentication,
     };
 
-    let transport = NetcodeServerTransport::new(server_config, socket).unwrap();
-    app.insert_resource(server);
-    app.insert_resource(transport);
+    if sockets.len() == 1 {
+        let socket = sockets.remove(0);
+        NetcodeServerTransport::new(server_config, socket)
+            .map(|transport| Box::new(transport) as Box<dyn ServerTransport>)
+            .map_err(|e| e.to_string())
+    } else {
+        MultiSocketServerTransport::new(server_config, sockets)
+            .map(|transport| Box::new(transport) as Box<dyn ServerTransport>)
+            .map_err(|e| e.to_string())
+    }
 }
 
-pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: GameFolderPaths) {
+/// Builds the server with its world loaded, without running it: `App::update` runs a single tick.
+/// Fails when the network settings are invalid
+pub fn build_server_app(
+    listener: ServerListener,
+    config: GameServerConfig,
+    game_folder_paths: GameFolderPaths,
+) -> Result<App, String> {
     let mut app = App::new();
     app.add_plugins(
         MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
@@ -93,23 +247,35 @@ pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: Game
     app.add_plugins(RenetServerPlugin);
     app.add_plugins(FrameTimeDiagnosticsPlugin::default());
     app.add_plugins(LogDiagnosticsPlugin::default());
-    app.add_plugins(bevy::log::LogPlugin::default());
 
-    app.insert_resource(ServerLobby::default());
+    // The log file layer reads both resources while the log plugin is built
+    let settings = load_server_settings(&game_folder_paths);
+    app.insert_resource(settings.clone());
     app.insert_resource(game_folder_paths.clone());
+    app.add_plugins(settings.log.log_plugin(server_log_layers));
 
-    let world_name = &config.world_name.clone();
+    app.insert_resource(ServerLobby::default());
 
-    app.insert_resource(config);
+    let world_name = &config.world_name.clone();
 
-    info!("Starting server on {}", socket.local_addr().unwrap());
+    info!("Starting server on {}", listener.describe());
+    let game_port = match &listener {
+        ServerListener::Udp(sockets) => local_addresses(sockets).first().map(|addr| addr.port()),
+        ServerListener::Memory(_) => None,
+    };
 
-    add_netcode_network(&mut app, socket);
+    add_network(
+        &mut app,
+        listener,
+        &settings,
+        &game_folder_paths,
+        &world_save_dir(&game_folder_paths, world_name),
+    )?;
 
     setup_resources_and_events(&mut app);
 
     // Load world from files
-    let world_data = match load_world_data(world_name, &game_folder_paths) {
+    let world_data = match load_world_data(&config, &game_folder_paths) {
         Ok(data) => data,
         Err(err) => {
             error!(
@@ -119,25 +285,81 @@ pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: Game
             panic!()
         }
     };
+    app.insert_resource(config);
+
+    // Chunks from older saves are stored in world.ron, they are marked dirty
+    // so that the next save moves them to their own files
+    let mut chunks = ServerChunkWorldMap {
+        dirty_chunks: world_data.map.keys().copied().collect(),
+        map: world_data.map,
+        chunks_to_update: ChunkUpdates::default(),
+        heightmap: HashMap::new(),
+        gen_settings: world_data.gen_settings,
+        pending_blocks: world_data.pending_blocks,
+        spawn_platform_pending: world_data.spawn_platform_pending,
+        removed_chunks: default(),
+    };
+    chunks
+        .map
+        .extend(load_chunks_data(world_name, &game_folder_paths));
+    chunks.rebuild_heightmap();
+    fill_missing_biomes(&mut chunks, world_data.seed.0);
 
     let mut world_map = ServerWorldMap {
         name: world_data.name,
-        chunks: ServerChunkWorldMap {
-            map: world_data.map,
-            chunks_to_update: Vec::new(),
-        },
+        chunks,
         players: HashMap::new(),
         mobs: world_data.mobs,
         item_stacks: world_data.item_stacks,
         time: world_data.time,
+        day_time: world_data.day_time.unwrap_or(world_data.time),
+        game_rules: world_data.game_rules,
+        weather: world_data.weather,
+        claims: world_data.claims,
+        teams: world_data.teams,
+        containers: world_data.containers,
+        block_ticks: world_data.block_ticks,
+        projectiles: HashMap::new(),
     };
 
+    // Saves made before mobs had health
+    for mob in world_map.mobs.values_mut() {
+        if mob.health <= 0.0 {
+            mob.health = mob.kind.max_health();
+        }
+    }
+
     cleanup_all_players_from_world(&mut world_map);
 
+    let preset = load_world_gen_preset(world_name, &game_folder_paths);
+    let packs = load_world_packs(world_name, &game_folder_paths);
+    app.insert_resource(WorldCacheId::new(
+        &world_map.name,
+        world_data.seed,
+        world_map.chunks.gen_settings,
+        &preset,
+        &packs.infos(),
+    ));
+
     // Insert world_map and seed into ressources
     app.insert_resource(world_map);
+    app.insert_resource(SimulationRandom::from(world_data.seed));
     app.insert_resource(world_data.seed);
+    app.insert_resource(preset);
     app.insert_resource(ServerTime(world_data.time));
+    app.init_resource::<TickControl>();
+    app.insert_resource(packs);
+    app.init_resource::<PackTransfers>();
+    app.insert_resource(load_event_journal(
+        world_name,
+        &game_folder_paths,
+        &settings,
+    ));
+    app.insert_resource(SaveWorker::spawn());
+    app.insert_resource(ChunkFileWatcher::new(
+        world_save_dir(&game_folder_paths, world_name).join(CHUNKS_SAVE_DIR),
+        settings.watch_chunk_files,
+    ));
 
     // Create save folder if does not already exist
     let save_folder = format!(
@@ -154,7 +376,51 @@ pub fn init(socket: UdpSocket, config: GameServerConfig, game_folder_paths: Game
         panic!();
     }
 
+    if let Some(viewer) = settings
+        .map_viewer_port
+        .and_then(|port| MapViewer::start(settings.map_viewer_address, port))
+    {
+        app.insert_resource(viewer);
+    }
+
+    // Players in the same process have nothing to discover
+    let lan_port = game_port.filter(|_| settings.lan_broadcast);
+    if let Some(broadcaster) = lan_port.and_then(LanBroadcaster::start) {
+        app.insert_resource(broadcaster);
+    }
+
+    let metrics = ServerMetrics::default();
+    if let Some(port) = settings.metrics_port {
+        metrics.serve(settings.metrics_address, port);
+    }
+    app.insert_resource(metrics);
+
     dispatcher::register_systems(&mut app);
 
-    app.run();
+    Ok(app)
+}
+
+/// Runs the server until it stops, fails without starting when the network settings are invalid
+pub fn init(
+    listener: ServerListener,
+    config: GameServerConfig,
+    game_folder_paths: GameFolderPaths,
+) -> Result<(), String> {
+    install_panic_hook();
+
+    let world_dir = world_save_dir(&game_folder_paths, &config.world_name);
+    let mut app = build_server_app(listener, config, game_folder_paths)?;
+    mark_world_running(&world_dir);
+
+    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| app.run())) {
+        handle_server_crash(app.world_mut());
+        panic::resume_unwind(panic);
+    }
+
+    // Let the pending saves finish before the server goes down
+    if let Some(save_worker) = app.world_mut().remove_resource::<SaveWorker>() {
+        save_worker.shutdown();
+    }
+    mark_world_stopped(&world_dir);
+    Ok(())
 }
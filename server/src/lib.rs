@@ -1,6 +1,15 @@
 mod init;
+mod logging;
+mod metrics;
 mod mob;
 mod network;
+mod settings;
 mod world;
 
-pub use init::{acquire_local_ephemeral_udp_socket, init};
+pub use init::{
+    acquire_local_ephemeral_udp_socket, bind_game_sockets, build_server_app, init, ServerListener,
+};
+pub use world::backup::extract_world_archive;
+pub use world::generation::{generate_chunk, sample_surface, ColumnSample};
+pub use world::packs::extract_pack_archive;
+pub use world::preset::WorldGenPreset;
@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use bevy::log::tracing::{field::Field, Event, Subscriber};
+use bevy::log::tracing_subscriber::{field::Visit, fmt, layer::Context, Layer};
+use bevy::log::BoxedLayer;
+use bevy::prelude::*;
+use shared::GameFolderPaths;
+
+use crate::settings::ServerSettings;
+
+pub const LOGS_PATH: &str = "logs";
+/// A log file is rotated once it gets bigger than this
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+/// Lines kept in memory for the crash reports
+const RECENT_LOGS_SIZE: usize = 200;
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// `server.log` in the logs folder, the older ones being `server.1.log`, `server.2.log`...
+/// Every start of the server begins a new file.
+struct RotatingLogFile {
+    folder: PathBuf,
+    kept: u32,
+    file: File,
+    written: u64,
+}
+
+fn log_file_path(folder: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        folder.join("server.log")
+    } else {
+        folder.join(format!("server.{}.log", index))
+    }
+}
+
+impl RotatingLogFile {
+    fn open(folder: PathBuf, kept: u32) -> io::Result<Self> {
+        fs::create_dir_all(&folder)?;
+        rotate_log_files(&folder, kept)?;
+        let file = File::create(log_file_path(&folder, 0))?;
+        Ok(Self {
+            folder,
+            kept,
+            file,
+            written: 0,
+        })
+    }
+}
+
+fn rotate_log_files(folder: &Path, kept: u32) -> io::Result<()> {
+    let oldest = log_file_path(folder, kept - 1);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+    for index in (1..kept).rev() {
+        let path = log_file_path(folder, index - 1);
+        if path.exists() {
+            fs::rename(path, log_file_path(folder, index))?;
+        }
+    }
+    Ok(())
+}
+
+impl Write for RotatingLogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_LOG_FILE_SIZE {
+            self.file.flush()?;
+            rotate_log_files(&self.folder, self.kept)?;
+            self.file = File::create(log_file_path(&self.folder, 0))?;
+            self.written = 0;
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// The last log lines, oldest first
+pub fn recent_logs() -> Vec<String> {
+    match RECENT_LOGS.lock() {
+        Ok(logs) => logs.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+struct MessageVisitor<'a>(&'a mut String);
+
+impl Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Keeps the last log lines in memory
+struct RecentLogsLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLogsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = format!("{} {}:", metadata.level(), metadata.target());
+        event.record(&mut MessageVisitor(&mut line));
+
+        if let Ok(mut logs) = RECENT_LOGS.lock() {
+            if logs.len() >= RECENT_LOGS_SIZE {
+                logs.pop_front();
+            }
+            logs.push_back(line);
+        }
+    }
+}
+
+/// Extra log layers of the server: the recent lines for crash reports, and the log files
+pub fn server_log_layers(app: &mut App) -> Option<BoxedLayer> {
+    Some(RecentLogsLayer.and_then(log_file_layer(app)).boxed())
+}
+
+/// Log layer writing to the log files, it reads the settings and game folder from the app
+fn log_file_layer(app: &mut App) -> Option<BoxedLayer> {
+    let settings = app.world().get_resource::<ServerSettings>()?;
+    if settings.log_files_kept == 0 {
+        return None;
+    }
+    let folder = app
+        .world()
+        .get_resource::<GameFolderPaths>()?
+        .game_folder_path
+        .join(LOGS_PATH);
+
+    match RotatingLogFile::open(folder.clone(), settings.log_files_kept) {
+        Ok(file) => Some(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .boxed(),
+        ),
+        Err(e) => {
+            // The logger is not ready yet
+            eprintln!("Could not open the log file in {}: {}", folder.display(), e);
+            None
+        }
+    }
+}
@@ -1,12 +1,25 @@
-use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
 
-use crate::init::acquire_socket_by_port;
-use clap::Parser;
-use shared::{get_game_folder_paths, GameServerConfig};
+use crate::init::{bind_game_sockets, ServerListener};
+use bevy_renet::netcode::{generate_random_bytes, NETCODE_KEY_BYTES};
+use clap::{Parser, Subcommand};
+use shared::{
+    get_game_folder_paths,
+    world::{WorldGenSettings, WorldSeed},
+    GameFolderPaths, GameServerConfig,
+};
+use world::anvil::import_anvil_world;
+use world::backup::{backup_file_name, create_world_archive, extract_world_archive};
+use world::data::SAVE_PATH;
+use world::save::world_save_dir;
+use world::trim::{trim_world_files, MIN_TRIM_RADIUS};
 
 mod init;
+mod logging;
+mod metrics;
 mod mob;
 mod network;
+mod settings;
 mod world;
 
 #[derive(Parser, Debug)]
@@ -15,23 +28,220 @@ struct Args {
     #[arg(short, long, default_value_t = 8000)]
     port: u16,
 
-    #[arg(short, long, default_value = "default")]
+    #[arg(short, long, default_value = "default", global = true)]
     world: String,
 
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     game_folder_path: Option<String>,
+
+    /// Seed used when creating a new world, ignored if the world already exists
+    #[arg(short, long)]
+    seed: Option<String>,
+
+    /// Lowest block height when creating a new world, ignored if the world already exists
+    #[arg(long, allow_negative_numbers = true)]
+    min_y: Option<i32>,
+
+    /// Height above the highest block when creating a new world, ignored if the world already exists
+    #[arg(long, allow_negative_numbers = true)]
+    max_y: Option<i32>,
+
+    /// Build a spawn platform with a starter chest when creating a new world
+    #[arg(long)]
+    spawn_platform: bool,
+
+    /// Generates and saves the chunks within this radius of spawn, in chunks, while the server runs
+    #[arg(long, value_name = "RADIUS")]
+    pregen: Option<u32>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Packs a world save into a .tar.gz archive, the world should not be running while exporting
+    ExportWorld {
+        /// Archive to create, defaults to `<world>-<date>.tar.gz` in the current directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Imports a world archive made by `export-world` or `/backup` into the saves folder
+    ImportWorld {
+        archive: PathBuf,
+
+        /// Replace the world if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Imports a Minecraft Java Edition world from its `region` folder as the world given with `--world`.
+    /// Blocks without an equivalent become debug blocks
+    ImportAnvil {
+        region_dir: PathBuf,
+
+        /// Replace the world if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Deletes the chunks farther than a radius from spawn that no player modified, the world should not be running.
+    /// They are generated again if players go there
+    Trim {
+        /// Distance from spawn in chunks, the chunks within it are kept
+        radius: u32,
+
+        /// Only count the chunks that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Prints a new random private key, to put in the server settings to encrypt the connections
+    GenerateKey,
 }
 
 fn main() {
     let args = Args::parse();
-    let socket = acquire_socket_by_port(std::net::IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), args.port);
+    let game_folder_paths = get_game_folder_paths(args.game_folder_path, None);
 
-    init::init(
-        socket,
+    match args.command {
+        Some(Command::ExportWorld { output }) => {
+            export_world(&args.world, output, &game_folder_paths);
+            return;
+        }
+        Some(Command::ImportWorld { archive, force }) => {
+            import_world(&archive, force, &game_folder_paths);
+            return;
+        }
+        Some(Command::ImportAnvil { region_dir, force }) => {
+            import_anvil(&args.world, &region_dir, force, &game_folder_paths);
+            return;
+        }
+        Some(Command::Trim { radius, dry_run }) => {
+            trim_world(&args.world, radius, dry_run, &game_folder_paths);
+            return;
+        }
+        Some(Command::GenerateKey) => {
+            let key: [u8; NETCODE_KEY_BYTES] = generate_random_bytes();
+            let hex: String = key.iter().map(|byte| format!("{byte:02x}")).collect();
+            println!("{hex}");
+            return;
+        }
+        None => {}
+    }
+
+    let settings = settings::load_server_settings(&game_folder_paths);
+    let sockets = match bind_game_sockets(&settings.bind_addresses, args.port) {
+        Ok(sockets) => sockets,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let started = init::init(
+        ServerListener::Udp(sockets),
         GameServerConfig {
             world_name: args.world,
             is_solo: false,
+            world_seed: args.seed.map(|seed| WorldSeed::from_text(&seed).0),
+            world_gen_settings: world_gen_settings(args.min_y, args.max_y),
+            spawn_platform: args.spawn_platform,
+            pregen_radius: args.pregen,
         },
-        get_game_folder_paths(args.game_folder_path, None),
+        game_folder_paths,
     );
+    if let Err(e) = started {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn world_gen_settings(min_y: Option<i32>, max_y: Option<i32>) -> Option<WorldGenSettings> {
+    if min_y.is_none() && max_y.is_none() {
+        return None;
+    }
+    let defaults = WorldGenSettings::default();
+    let settings = WorldGenSettings {
+        min_y: min_y.unwrap_or(defaults.min_y),
+        max_y: max_y.unwrap_or(defaults.max_y),
+    };
+    if settings.max_y <= settings.min_y {
+        eprintln!(
+            "The world height is empty: --max-y ({}) must be above --min-y ({})",
+            settings.max_y, settings.min_y
+        );
+        std::process::exit(1);
+    }
+    Some(settings)
+}
+
+fn export_world(world_name: &str, output: Option<PathBuf>, paths: &GameFolderPaths) {
+    let world_dir = paths.game_folder_path.join(SAVE_PATH).join(world_name);
+    if !world_dir.join("world.ron").exists() {
+        eprintln!("World {} not found in {}", world_name, world_dir.display());
+        std::process::exit(1);
+    }
+
+    let output = output.unwrap_or_else(|| PathBuf::from(backup_file_name(world_name)));
+    match create_world_archive(&world_dir, &output) {
+        Ok(()) => println!("World {} exported to {}", world_name, output.display()),
+        Err(e) => {
+            eprintln!("Could not export world {world_name}: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn import_world(archive: &Path, force: bool, paths: &GameFolderPaths) {
+    let saves_dir = paths.game_folder_path.join(SAVE_PATH);
+    match extract_world_archive(archive, &saves_dir, force) {
+        Ok(world_name) => println!("World {} imported into {}", world_name, saves_dir.display()),
+        Err(e) => {
+            eprintln!("Could not import {}: {}", archive.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn import_anvil(world_name: &str, region_dir: &Path, force: bool, paths: &GameFolderPaths) {
+    let world_dir = world_save_dir(paths, world_name);
+    match import_anvil_world(region_dir, &world_dir, world_name, force) {
+        Ok(import) => {
+            println!(
+                "World {} imported from {}: {} columns, {} chunks",
+                world_name,
+                region_dir.display(),
+                import.columns,
+                import.chunks
+            );
+            let mut unknown: Vec<_> = import.unknown_blocks.into_iter().collect();
+            unknown.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            if !unknown.is_empty() {
+                println!("Blocks imported as debug blocks, by number of sections:");
+                for (name, count) in unknown.iter().take(20) {
+                    println!("  {name}: {count}");
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Could not import {}: {}", region_dir.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn trim_world(world_name: &str, radius: u32, dry_run: bool, paths: &GameFolderPaths) {
+    if radius < MIN_TRIM_RADIUS {
+        eprintln!("The radius must be at least {MIN_TRIM_RADIUS} chunks");
+        std::process::exit(1);
+    }
+    let settings = settings::load_server_settings(paths);
+    match trim_world_files(world_name, paths, &settings, radius, dry_run) {
+        Ok(removed) if dry_run => {
+            println!("{removed} chunks of world {world_name} would be trimmed")
+        }
+        Ok(removed) => println!("Trimmed {removed} chunks of world {world_name}"),
+        Err(e) => {
+            eprintln!("Could not trim world {world_name}: {e}");
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use shared::{world::ServerWorldMap, TICKS_PER_SECOND};
+
+/// Time one tick may take without slowing the server down
+pub const TICK_BUDGET: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND);
+/// Ticks the profiler averages over, two seconds
+const PROFILED_TICKS: usize = 2 * TICKS_PER_SECOND as usize;
+/// Time a scraper has to send its request and read the answer
+const METRICS_IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Measures how long the recent ticks took, so that optional work can be put off when the server is busy
+#[derive(Resource, Default)]
+pub struct TickProfiler {
+    tick_start: Option<Instant>,
+    recent: VecDeque<Duration>,
+}
+
+impl TickProfiler {
+    pub fn average(&self) -> Duration {
+        if self.recent.is_empty() {
+            return Duration::ZERO;
+        }
+        self.recent.iter().sum::<Duration>() / self.recent.len() as u32
+    }
+
+    pub fn max(&self) -> Duration {
+        self.recent.iter().copied().max().unwrap_or_default()
+    }
+
+    /// Share of the tick budget used by the recent ticks on average, above 1 the server lags
+    pub fn load(&self) -> f32 {
+        self.average().as_secs_f32() / TICK_BUDGET.as_secs_f32()
+    }
+}
+
+/// Values served by the metrics endpoint, updated by the systems measuring them
+#[derive(Default, Debug, Clone)]
+pub struct MetricsSnapshot {
+    pub tick_avg: Duration,
+    pub tick_max: Duration,
+    pub players: usize,
+    pub loaded_chunks: usize,
+    pub dirty_chunks: usize,
+    pub saves: u64,
+    pub last_save_duration: Duration,
+    pub last_save_chunks: usize,
+    pub autosaves_delayed: u64,
+}
+
+impl MetricsSnapshot {
+    /// Prometheus text format
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, help: &str, value: f64| {
+            let _ = writeln!(out, "# HELP rustcraft_{name} {help}");
+            let _ = writeln!(out, "# TYPE rustcraft_{name} gauge");
+            let _ = writeln!(out, "rustcraft_{name} {value}");
+        };
+        metric(
+            "tick_seconds_avg",
+            "Average duration of the recent ticks",
+            self.tick_avg.as_secs_f64(),
+        );
+        metric(
+            "tick_seconds_max",
+            "Longest of the recent ticks",
+            self.tick_max.as_secs_f64(),
+        );
+        metric("players", "Players online", self.players as f64);
+        metric(
+            "loaded_chunks",
+            "Chunks held in memory",
+            self.loaded_chunks as f64,
+        );
+        metric(
+            "dirty_chunks",
+            "Chunks modified since they were last saved",
+            self.dirty_chunks as f64,
+        );
+        metric("saves_total", "World saves written", self.saves as f64);
+        metric(
+            "last_save_seconds",
+            "Time the last world save took to write",
+            self.last_save_duration.as_secs_f64(),
+        );
+        metric(
+            "last_save_chunks",
+            "Chunks written by the last world save",
+            self.last_save_chunks as f64,
+        );
+        metric(
+            "autosaves_delayed_total",
+            "Autosaves put off because ticks were too long",
+            self.autosaves_delayed as f64,
+        );
+        out
+    }
+}
+
+/// Shared with the thread serving the metrics endpoint
+#[derive(Resource, Default, Clone)]
+pub struct ServerMetrics(pub Arc<Mutex<MetricsSnapshot>>);
+
+impl ServerMetrics {
+    pub fn update(&self, f: impl FnOnce(&mut MetricsSnapshot)) {
+        if let Ok(mut snapshot) = self.0.lock() {
+            f(&mut snapshot);
+        }
+    }
+
+    /// Serves the metrics over HTTP on `address` and `port`, whatever the requested path.
+    /// Each connection is handled on its own thread, so that a client which stays silent holds up nobody else
+    pub fn serve(&self, address: IpAddr, port: u16) {
+        let addr = SocketAddr::new(address, port);
+        let listener = match TcpListener::bind(addr) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Could not start the metrics endpoint on {}: {}", addr, e);
+                return;
+            }
+        };
+        info!("Metrics available on http://{}/metrics", addr);
+
+        let metrics = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let metrics = metrics.clone();
+                thread::spawn(move || metrics.answer(stream));
+            }
+        });
+    }
+
+    fn answer(&self, mut stream: TcpStream) {
+        if stream
+            .set_read_timeout(Some(METRICS_IO_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(METRICS_IO_TIMEOUT)))
+            .is_err()
+        {
+            return;
+        }
+
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+        let body = match self.0.lock() {
+            Ok(snapshot) => snapshot.render(),
+            Err(_) => String::new(),
+        };
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes());
+    }
+}
+
+pub fn tick_start_system(mut profiler: ResMut<TickProfiler>) {
+    profiler.tick_start = Some(Instant::now());
+}
+
+pub fn tick_end_system(
+    mut profiler: ResMut<TickProfiler>,
+    metrics: Res<ServerMetrics>,
+    world_map: Res<ServerWorldMap>,
+) {
+    let Some(start) = profiler.tick_start.take() else {
+        return;
+    };
+    if profiler.recent.len() == PROFILED_TICKS {
+        profiler.recent.pop_front();
+    }
+    profiler.recent.push_back(start.elapsed());
+
+    metrics.update(|snapshot| {
+        snapshot.tick_avg = profiler.average();
+        snapshot.tick_max = profiler.max();
+        snapshot.players = world_map.players.len();
+        snapshot.loaded_chunks = world_map.chunks.map.len();
+        snapshot.dirty_chunks = world_map.chunks.dirty_chunks.len();
+    });
+}
@@ -1,17 +1,154 @@
+use std::collections::HashMap;
+
 use bevy::{
-    math::{bounding::Aabb3d, ops::atan2, Quat, Vec3},
+    math::{bounding::Aabb3d, ops::atan2, IVec3, Quat, Vec3},
     time::{Fixed, Time},
 };
 use bevy_ecs::system::{Res, ResMut};
 use shared::{
-    players::constants::{GRAVITY, JUMP_VELOCITY, SPEED},
-    world::{MobAction, MobTarget, ServerWorldMap, WorldMap},
+    messages::PlayerId,
+    players::{
+        constants::{GRAVITY, JUMP_VELOCITY, SPEED},
+        Player,
+    },
+    world::{
+        allowed_motion, MobAction, MobId, MobTarget, ServerChunkWorldMap, ServerMob,
+        ServerWorldMap, WorldMap, COLLISION_SKIN,
+    },
+};
+
+use crate::{
+    init::ServerTime,
+    world::spatial::{SpatialEntity, SpatialIndex},
 };
 
-pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Time<Fixed>>) {
+use super::{combat::MELEE_RANGE, pathfinding::find_path};
+
+/// Hostile mobs notice players closer than this
+const HOSTILE_FOLLOW_RANGE: f32 = 32.0;
+/// Paths are recomputed this often, as the chased player keeps moving
+const PATH_REFRESH_TICKS: u64 = 20;
+/// Ranged mobs stay between those distances from their target
+const RANGED_MIN_DISTANCE: f32 = 8.0;
+const RANGED_MAX_DISTANCE: f32 = 14.0;
+/// Ranged mobs switch the side they strafe to that often
+const STRAFE_SWITCH_TICKS: u64 = 60;
+/// Pets start following their owner when further than this, and teleport next to them past the second distance
+const PET_FOLLOW_DISTANCE: f32 = 4.0;
+const PET_TELEPORT_DISTANCE: f32 = 24.0;
+
+fn block_position(position: Vec3) -> IVec3 {
+    position.floor().as_ivec3()
+}
+
+/// Hostile mobs chase the closest player in range, and stop when there is none
+fn update_hostile_target(mob: &mut ServerMob, index: &SpatialIndex) {
+    let closest = index
+        .query_radius(mob.position, HOSTILE_FOLLOW_RANGE)
+        .filter_map(|(entity, position)| match entity {
+            SpatialEntity::Player(id) => Some((id, position.distance(mob.position))),
+            _ => None,
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+
+    mob.target = match closest {
+        Some((id, _)) => MobTarget::Player(id),
+        None => {
+            mob.path.clear();
+            MobTarget::None
+        }
+    };
+}
+
+/// Pets follow their owner around, or stay where they are when sitting or when the owner is offline
+fn update_pet(mob: &mut ServerMob, owner: PlayerId, players: &HashMap<PlayerId, Player>) {
+    let owner_position = players
+        .get(&owner)
+        .map(|player| player.position)
+        .filter(|_| !mob.sitting);
+
+    let Some(owner_position) = owner_position else {
+        mob.target = MobTarget::None;
+        mob.action = MobAction::Idle;
+        mob.path.clear();
+        return;
+    };
+
+    let distance = mob.position.distance(owner_position);
+    if distance > PET_TELEPORT_DISTANCE {
+        mob.position = owner_position;
+        mob.velocity = Vec3::ZERO;
+        mob.path.clear();
+    }
+
+    mob.target = MobTarget::Player(owner);
+    mob.action = if distance > PET_FOLLOW_DISTANCE {
+        MobAction::Walk
+    } else {
+        MobAction::Idle
+    };
+}
+
+/// Ranged mobs get closer when too far from their target, back off when too close, and strafe around it otherwise
+fn ranged_goal(
+    mob: &mut ServerMob,
+    mob_id: MobId,
+    chunks: &ServerChunkWorldMap,
+    target: Vec3,
+    tick: u64,
+) -> Vec3 {
+    let to_target = (target - mob.position).with_y(0.0);
+    let distance = to_target.length();
+
+    if distance > RANGED_MAX_DISTANCE {
+        return follow_path(mob, chunks, target, tick);
+    }
+    mob.path.clear();
+
+    let away = -to_target.normalize_or_zero();
+    if distance < RANGED_MIN_DISTANCE {
+        return mob.position + away * 2.0;
+    }
+
+    let side = if (tick / STRAFE_SWITCH_TICKS + mob_id as u64).is_multiple_of(2) {
+        1.0
+    } else {
+        -1.0
+    };
+    mob.position + Vec3::new(-away.z, 0.0, away.x) * side * 2.0
+}
+
+/// Returns the next point to walk to in order to reach `target`, following the mob path
+fn follow_path(mob: &mut ServerMob, chunks: &ServerChunkWorldMap, target: Vec3, tick: u64) -> Vec3 {
+    let current = block_position(mob.position);
+
+    if mob.path.is_empty() || tick.is_multiple_of(PATH_REFRESH_TICKS) {
+        mob.path = find_path(chunks, current, block_position(target));
+    }
+
+    while let Some(next) = mob.path.first() {
+        if next.x == current.x && next.z == current.z {
+            mob.path.remove(0);
+        } else {
+            break;
+        }
+    }
+
+    match mob.path.first() {
+        Some(next) => Vec3::new(next.x as f32 + 0.5, next.y as f32, next.z as f32 + 0.5),
+        None => target,
+    }
+}
+
+pub fn mob_behavior_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    delta: Res<Time<Fixed>>,
+    time: Res<ServerTime>,
+    index: Res<SpatialIndex>,
+) {
     let mut mobs = world_map.mobs.clone();
 
-    for (_mob_id, mob) in mobs.iter_mut() {
+    for (mob_id, mob) in mobs.iter_mut() {
         //log::info!("Mob is at position: {:?}", mob.position);
         if (mob.position.x.is_nan() || mob.position.y.is_nan() || mob.position.z.is_nan())
             || (mob.velocity.x.is_nan() || mob.velocity.y.is_nan() || mob.velocity.z.is_nan())
@@ -20,7 +157,13 @@ pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Tim
             // TODO: FIX mob position
             return;
         }
-        let target = match mob.target {
+        if let Some(owner) = mob.owner {
+            update_pet(mob, owner, &world_map.players);
+        } else if mob.kind.is_hostile() {
+            update_hostile_target(mob, &index);
+        }
+
+        let chased = match mob.target {
             MobTarget::Position(pos) => pos,
             MobTarget::None => continue,
             MobTarget::Player(id) => {
@@ -33,6 +176,24 @@ pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Tim
             MobTarget::Mob(id) => world_map.mobs.get(&id).unwrap().position,
         };
 
+        let target = if mob.kind.is_ranged() {
+            mob.action = MobAction::Walk;
+            ranged_goal(mob, *mob_id, &world_map.chunks, chased, time.0)
+        } else if mob.owner.is_some() && matches!(mob.action, MobAction::Walk) {
+            follow_path(mob, &world_map.chunks, chased, time.0)
+        } else if mob.owner.is_some() {
+            chased
+        } else if mob.kind.is_hostile() {
+            mob.action = if mob.position.distance(chased) < MELEE_RANGE {
+                MobAction::Attack
+            } else {
+                MobAction::Walk
+            };
+            follow_path(mob, &world_map.chunks, chased, time.0)
+        } else {
+            chased
+        };
+
         // same gravity management as the player
         let dir = (target - mob.position).normalize();
         let delta = delta.delta_secs();
@@ -40,31 +201,37 @@ pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Tim
             mob.velocity.y += GRAVITY * delta;
         }
 
-        let new_y = mob.position.y + mob.velocity.y;
-        let new_vec = &Vec3::new(mob.position.x, new_y, mob.position.z);
         let max_velocity = 0.9;
         if mob.velocity.y > max_velocity {
             mob.velocity.y = max_velocity;
         }
-        //log::debug!("New vec: {:?}", new_vec);
-        //log::debug!(
-        //    "Mob AABB: {:?}",
-        //    Aabb3d::new(*new_vec, Vec3::new(mob.width, mob.height, mob.deepth) / 2.0)
-        //);
-        if world_map.chunks.check_collision_box(&Aabb3d::new(
-            *new_vec,
-            Vec3::new(mob.width, mob.height, mob.deepth) / 2.0,
-        )) {
-            mob.on_ground = true;
-            mob.velocity.y = 0.0;
+
+        // Resting mobs probe the ground right below them, to know whether they still stand on it
+        let motion_y = if mob.velocity.y == 0.0 {
+            -2.0 * COLLISION_SKIN
         } else {
-            mob.position.y = new_y;
-            mob.on_ground = false;
+            mob.velocity.y
+        };
+        let hitbox = Aabb3d::new(
+            mob.position,
+            Vec3::new(mob.width, mob.height, mob.deepth) / 2.0,
+        );
+        let (allowed, hit) = allowed_motion(&world_map.chunks, &hitbox, Vec3::Y * motion_y);
+        mob.position += allowed;
+        match hit {
+            Some(hit) => {
+                mob.on_ground = hit.normal.y > 0.0;
+                mob.velocity.y = 0.0;
+            }
+            None => mob.on_ground = false,
         }
 
         match mob.action {
-            MobAction::Walk | MobAction::Attack => {
-                let speed = SPEED * delta;
+            MobAction::Attack => {
+                mob.rotation = Quat::from_rotation_y(atan2(dir.x, dir.z));
+            }
+            MobAction::Walk => {
+                let speed = SPEED * mob.effects.speed_multiplier() * delta;
                 let new_x = mob.position.x + dir.x * speed;
                 let new_z = mob.position.z + dir.z * speed;
                 let new_vec = &Vec3::new(new_x, mob.position.y, new_z);
@@ -120,18 +287,22 @@ pub fn mob_behavior_system(mut world_map: ResMut<ServerWorldMap>, delta: Res<Tim
                 mob.rotation = Quat::from_rotation_y(atan2(dir.x, dir.z));
 
                 // If reached destination, start idling
-                if mob.position.distance(target) < 0.5 {
+                if !mob.kind.is_hostile() && mob.position.distance(target) < 0.5 {
                     mob.action = MobAction::Flee;
                 }
             }
-            MobAction::Flee => {
-                if mob.position.distance(target) < 15.0 {
-                    mob.position -= dir * delta;
-                    mob.rotation = Quat::from_rotation_y(atan2(-dir.x, -dir.z));
-                }
+            MobAction::Flee if mob.position.distance(target) < 15.0 => {
+                mob.position -= dir * delta;
+                mob.rotation = Quat::from_rotation_y(atan2(-dir.x, -dir.z));
             }
             _ => {}
         }
+
+        // Ranged mobs keep an eye on their target whichever way they walk
+        if mob.kind.is_ranged() {
+            let look = chased - mob.position;
+            mob.rotation = Quat::from_rotation_y(atan2(look.x, look.z));
+        }
     }
 
     world_map.mobs = mobs;
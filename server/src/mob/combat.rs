@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{
+        mob::{MobDespawnEvent, MobHitEvent},
+        PlayerId, ServerToClientMessage,
+    },
+    players::{DamageSource, StatusEffectKind},
+    world::{
+        aim_at_moving_target, is_daytime, BlockHitbox, ItemStack, MobAction, MobId, MobTarget,
+        Projectile, ProjectileKind, RandomStream, ServerChunkWorldMap, ServerWorldMap,
+        SimulationRandom, WorldMap,
+    },
+    TICKS_PER_SECOND,
+};
+
+use crate::{
+    init::ServerTime,
+    network::extensions::SendGameMessageExtension,
+    world::{
+        health::PlayerDamageEvent,
+        journal::{EventJournal, JournalEvent},
+        packs::WorldPacks,
+        projectiles::spawn_projectile,
+        spawn_platform::roll_loot,
+        stacks::spawn_item_stack,
+    },
+};
+
+/// Hostile mobs hit players closer than this
+pub const MELEE_RANGE: f32 = 1.5;
+const MOB_ATTACK_DAMAGE: f32 = 3.0;
+const MOB_ATTACK_COOLDOWN_TICKS: u32 = 20;
+
+/// Ranged mobs shoot at players closer than this
+const RANGED_ATTACK_RANGE: f32 = 16.0;
+const RANGED_ATTACK_COOLDOWN_TICKS: u32 = 40;
+/// Projectiles leave ranged mobs from about their eyes
+const RANGED_SHOOT_HEIGHT: f32 = 0.6;
+
+/// Players can hit mobs up to this distance, a bit more than the interaction distance to absorb latency
+const PLAYER_ATTACK_REACH: f32 = 8.0;
+const PLAYER_ATTACK_DAMAGE: f32 = 4.0;
+
+/// Damage taken every second by mobs burning in daylight
+const BURN_DAMAGE: f32 = 1.0;
+
+/// Samples the segment between two points, looking for a block blocking the view
+fn has_line_of_sight(chunks: &ServerChunkWorldMap, from: Vec3, to: Vec3) -> bool {
+    let steps = (from.distance(to) * 2.0).ceil() as u32;
+    (1..steps).all(|i| {
+        let point = from.lerp(to, i as f32 / steps as f32);
+        chunks
+            .get_block_by_coordinates(&point.floor().as_ivec3())
+            .is_none_or(|block| matches!(block.get_hitbox(), BlockHitbox::None))
+    })
+}
+
+#[derive(Event, Debug)]
+pub struct MobAttackRequestEvent {
+    pub client_id: PlayerId,
+    pub mob_id: MobId,
+}
+
+pub fn mob_combat_system(
+    mut events: EventReader<MobAttackRequestEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    mut ev_damage: EventWriter<PlayerDamageEvent>,
+    time: Res<ServerTime>,
+    (packs, random): (Res<WorldPacks>, Res<SimulationRandom>),
+    mut journal: ResMut<EventJournal>,
+) {
+    let world_map = world_map.as_mut();
+
+    for event in events.read() {
+        let Some(player) = world_map.players.get(&event.client_id) else {
+            continue;
+        };
+        let Some(mob) = world_map.mobs.get_mut(&event.mob_id) else {
+            continue;
+        };
+
+        if mob.position.distance(player.position) > PLAYER_ATTACK_REACH {
+            debug!(
+                "Player {} is too far to attack mob {}",
+                player.id, event.mob_id
+            );
+            continue;
+        }
+
+        let health = mob.health;
+        mob.damage(PLAYER_ATTACK_DAMAGE);
+        if mob.health < health {
+            server.send_game_message(
+                event.client_id,
+                ServerToClientMessage::MobHit(MobHitEvent {
+                    id: event.mob_id,
+                    damage: health - mob.health,
+                    health: mob.health,
+                }),
+            );
+        }
+        if health > 0.0 && mob.health <= 0.0 {
+            journal.record(
+                &player.name,
+                JournalEvent::MobKilled {
+                    kind: mob.kind,
+                    position: mob.position,
+                },
+            );
+        }
+    }
+
+    let daytime = is_daytime(world_map.day_time);
+    let burn_tick = time.0.is_multiple_of(TICKS_PER_SECOND);
+
+    let mut shots = Vec::new();
+
+    for (id, mob) in world_map.mobs.iter_mut() {
+        mob.attack_cooldown = mob.attack_cooldown.saturating_sub(1);
+
+        if let (true, MobTarget::Player(player_id)) = (mob.kind.is_ranged(), mob.target) {
+            let eyes = mob.position + Vec3::Y * RANGED_SHOOT_HEIGHT;
+            let target = world_map.players.get(&player_id).filter(|player| {
+                player.position.distance(eyes) < RANGED_ATTACK_RANGE
+                    && has_line_of_sight(&world_map.chunks, eyes, player.position)
+            });
+
+            if let (Some(player), 0) = (target, mob.attack_cooldown) {
+                let kind = ProjectileKind::Arrow;
+                let direction =
+                    aim_at_moving_target(eyes, player.position, player.velocity, kind.speed());
+                shots.push(Projectile {
+                    kind,
+                    position: eyes,
+                    velocity: direction * kind.speed(),
+                    source: DamageSource::Mob {
+                        id: *id,
+                        kind: mob.kind,
+                    },
+                    age: 0.0,
+                });
+                mob.attack_cooldown = RANGED_ATTACK_COOLDOWN_TICKS;
+            }
+        }
+
+        if let (MobAction::Attack, MobTarget::Player(player_id)) = (mob.action, mob.target) {
+            let in_range = world_map
+                .players
+                .get(&player_id)
+                .is_some_and(|player| player.position.distance(mob.position) < MELEE_RANGE);
+
+            if in_range && mob.attack_cooldown == 0 {
+                ev_damage.write(PlayerDamageEvent {
+                    player_id,
+                    amount: MOB_ATTACK_DAMAGE,
+                    source: DamageSource::Mob {
+                        id: *id,
+                        kind: mob.kind,
+                    },
+                });
+                mob.attack_cooldown = MOB_ATTACK_COOLDOWN_TICKS;
+            }
+        }
+
+        let head = (mob.position + Vec3::Y * mob.height / 2.0)
+            .floor()
+            .as_ivec3();
+        let burning_in_daylight =
+            mob.kind.burns_in_daylight() && daytime && world_map.chunks.is_exposed_to_sky(&head);
+        if burning_in_daylight && burn_tick {
+            mob.damage(BURN_DAMAGE);
+        }
+        // The fire effect hurts on its own, this only shows the flames
+        mob.on_fire = burning_in_daylight || mob.effects.has(StatusEffectKind::Fire);
+    }
+
+    let mut ids = random.for_tick(RandomStream::EntityIds, time.0);
+    for projectile in shots {
+        spawn_projectile(world_map, &mut server, projectile, &mut ids);
+    }
+
+    let dead_mobs: Vec<MobId> = world_map
+        .mobs
+        .iter()
+        .filter(|(_, mob)| mob.health <= 0.0)
+        .map(|(id, _)| *id)
+        .collect();
+
+    for id in dead_mobs {
+        let mob = world_map.mobs.remove(&id).unwrap();
+        info!(
+            "Mob {} {} died at {:?}",
+            mob.display_name(),
+            id,
+            mob.position
+        );
+
+        // Data packs of the world can replace the drops of each kind of mob
+        let mut rng = random.for_entity(RandomStream::MobLoot, time.0, id);
+        let drops: Vec<ItemStack> = match packs.loot_tables.get(&mob.kind) {
+            Some(table) => roll_loot(table, &mut rng).inner.into_values().collect(),
+            None => mob
+                .kind
+                .get_drops(&mut rng)
+                .into_iter()
+                .map(|(item_id, nb)| ItemStack {
+                    item_id,
+                    item_type: item_id.get_default_type(),
+                    nb,
+                })
+                .collect(),
+        };
+        for stack in drops {
+            spawn_item_stack(&mut world_map.item_stacks, stack, mob.position, &mut rng);
+        }
+
+        server.broadcast_game_message(ServerToClientMessage::MobDespawn(MobDespawnEvent { id }));
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use serde::{Deserialize, Serialize};
+use shared::{
+    messages::{mob::MobDespawnEvent, PlayerId, ServerToClientMessage},
+    world::{MobId, ServerWorldMap},
+    GameFolderPaths, GameServerConfig,
+};
+
+use crate::{
+    network::extensions::SendGameMessageExtension,
+    settings::{save_server_settings, ServerSettings},
+    world::protection::is_player_op,
+};
+
+const DIFFICULTY_USAGE: &str = "Usage: /difficulty [peaceful|easy|normal|hard]";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    /// No hostile mobs, the ones around despawn
+    Peaceful,
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "peaceful" => Some(Difficulty::Peaceful),
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// Scales the damage mobs deal to players
+    pub fn mob_damage_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Peaceful => 0.0,
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        }
+    }
+
+    /// Hostile mobs allowed to roam around each player
+    pub fn hostile_mobs_per_player(&self) -> usize {
+        match self {
+            Difficulty::Peaceful => 0,
+            Difficulty::Easy => 2,
+            Difficulty::Normal => 4,
+            Difficulty::Hard => 6,
+        }
+    }
+
+    /// Chance for each hostile spawn attempt to go ahead
+    pub fn hostile_spawn_chance(&self) -> f64 {
+        match self {
+            Difficulty::Peaceful => 0.0,
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal | Difficulty::Hard => 1.0,
+        }
+    }
+}
+
+/// Handles `/difficulty`, anyone can read it but only ops can change it.
+/// The new difficulty is written to the settings file so that it survives restarts
+pub fn difficulty_command(
+    world_map: &ServerWorldMap,
+    settings: &mut ServerSettings,
+    config: &GameServerConfig,
+    game_folder_paths: &GameFolderPaths,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to use /difficulty".to_string();
+    };
+    let name = match args {
+        [] => return format!("The difficulty is {}", settings.difficulty.name()),
+        [name] => name,
+        _ => return DIFFICULTY_USAGE.to_string(),
+    };
+    let Some(difficulty) = Difficulty::from_name(name) else {
+        return DIFFICULTY_USAGE.to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can change the difficulty".to_string();
+    }
+
+    info!(
+        "{} set the difficulty to {}",
+        sender.name,
+        difficulty.name()
+    );
+    settings.difficulty = difficulty;
+    save_server_settings(game_folder_paths, settings);
+    format!("The difficulty is now {}", difficulty.name())
+}
+
+/// Hostile mobs vanish as soon as the difficulty is peaceful
+pub fn peaceful_despawn_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    settings: Res<ServerSettings>,
+) {
+    if settings.difficulty != Difficulty::Peaceful {
+        return;
+    }
+    let hostiles: Vec<MobId> = world_map
+        .mobs
+        .iter()
+        .filter(|(_, mob)| mob.kind.is_hostile())
+        .map(|(id, _)| *id)
+        .collect();
+
+    for id in hostiles {
+        world_map.mobs.remove(&id);
+        server.broadcast_game_message(ServerToClientMessage::MobDespawn(MobDespawnEvent { id }));
+    }
+}
@@ -1,20 +1,37 @@
 pub mod behavior;
+pub mod combat;
+pub mod difficulty;
+pub mod pathfinding;
+pub mod spawning;
+pub mod taming;
+pub mod trading;
 
 use bevy::prelude::*;
-use shared::world::{MobAction, MobKind, MobTarget, ServerMob, ServerWorldMap, WorldMap};
-use ulid::Ulid;
+use rand::Rng;
+use shared::world::{
+    new_entity_id, MobId, MobKind, MobTarget, RandomStream, ServerMob, ServerWorldMap,
+    SimulationRandom, WorldMap,
+};
 
 use crate::init::ServerTime;
 
-fn create_new_mob_id() -> u128 {
-    Ulid::new().0
+/// Id of a mob about to be added to the world, drawn from `rng` so that replays give it the same id
+pub(crate) fn create_new_mob_id(world_map: &ServerWorldMap, rng: &mut impl Rng) -> MobId {
+    new_entity_id(rng, |id| world_map.mobs.contains_key(&id))
 }
 
-pub fn manage_mob_spawning_system(mut world_map: ResMut<ServerWorldMap>, time: Res<ServerTime>) {
-    if time.0 == 100 && !world_map.players.is_empty() {
+pub fn manage_mob_spawning_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    time: Res<ServerTime>,
+    random: Res<SimulationRandom>,
+) {
+    if time.0 == 100 && world_map.game_rules.mob_spawning && !world_map.players.is_empty() {
         debug!("Should spawn mob");
 
-        let id = create_new_mob_id();
+        let id = create_new_mob_id(
+            &world_map,
+            &mut random.for_tick(RandomStream::EntityIds, time.0),
+        );
         info!(
             "Height: {}",
             world_map
@@ -28,18 +45,11 @@ pub fn manage_mob_spawning_system(mut world_map: ResMut<ServerWorldMap>, time: R
             0.0,
         );
 
-        let mob = ServerMob {
-            kind: MobKind::Fox,
+        let mob = ServerMob::new(
+            MobKind::Fox,
             position,
-            target: MobTarget::Player(*world_map.players.keys().next().unwrap()),
-            action: MobAction::Walk,
-            rotation: Quat::IDENTITY,
-            height: 1.0,
-            width: 1.0,
-            deepth: 1.5,
-            on_ground: true,
-            velocity: Vec3::ZERO,
-        };
+            MobTarget::Player(*world_map.players.keys().next().unwrap()),
+        );
 
         info!("Spawning new mob on server: {:?}", mob);
 
@@ -0,0 +1,119 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::math::IVec3;
+use shared::world::{BlockHitbox, ServerChunkWorldMap, WorldMap};
+
+/// Gives up on targets which can't be reached after exploring that many blocks
+const MAX_EXPLORED_NODES: usize = 512;
+/// Highest drop a mob accepts to walk down from
+const MAX_FALL_HEIGHT: i32 = 3;
+
+const HORIZONTAL_OFFSETS: [IVec3; 4] = [
+    IVec3::new(1, 0, 0),
+    IVec3::new(-1, 0, 0),
+    IVec3::new(0, 0, 1),
+    IVec3::new(0, 0, -1),
+];
+
+fn is_solid(chunks: &ServerChunkWorldMap, pos: IVec3) -> bool {
+    chunks
+        .get_block_by_coordinates(&pos)
+        .is_some_and(|block| !matches!(block.get_hitbox(), BlockHitbox::None))
+}
+
+/// A mob of two blocks high can stand at `pos`
+pub fn is_standable(chunks: &ServerChunkWorldMap, pos: IVec3) -> bool {
+    is_solid(chunks, pos - IVec3::Y) && !is_solid(chunks, pos) && !is_solid(chunks, pos + IVec3::Y)
+}
+
+fn neighbors(chunks: &ServerChunkWorldMap, pos: IVec3) -> Vec<IVec3> {
+    let mut result = Vec::new();
+
+    for offset in HORIZONTAL_OFFSETS {
+        let next = pos + offset;
+
+        // Jumping on a block needs some free room above the mob head
+        if is_standable(chunks, next + IVec3::Y) && !is_solid(chunks, pos + IVec3::Y * 2) {
+            result.push(next + IVec3::Y);
+            continue;
+        }
+
+        if is_standable(chunks, next) {
+            result.push(next);
+            continue;
+        }
+
+        if is_solid(chunks, next) || is_solid(chunks, next + IVec3::Y) {
+            continue;
+        }
+        for drop in 1..=MAX_FALL_HEIGHT {
+            let below = next - IVec3::Y * drop;
+            if is_standable(chunks, below) {
+                result.push(below);
+                break;
+            }
+            if is_solid(chunks, below) {
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+fn heuristic(a: IVec3, b: IVec3) -> i32 {
+    let d = (a - b).abs();
+    d.x + d.y + d.z
+}
+
+/// A* search over the block grid, returns the blocks to walk through to go from `start` to `goal` (`start` excluded)
+///
+/// When the goal can't be reached, the path leads to the explored block closest to it
+pub fn find_path(chunks: &ServerChunkWorldMap, start: IVec3, goal: IVec3) -> Vec<IVec3> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<IVec3, IVec3> = HashMap::new();
+    let mut costs: HashMap<IVec3, i32> = HashMap::new();
+
+    let mut closest = start;
+    let mut closest_distance = heuristic(start, goal);
+
+    open.push(Reverse((closest_distance, start.to_array())));
+    costs.insert(start, 0);
+
+    while let Some(Reverse((_, current))) = open.pop() {
+        let current = IVec3::from_array(current);
+
+        let distance = heuristic(current, goal);
+        if distance < closest_distance {
+            closest = current;
+            closest_distance = distance;
+        }
+        if current == goal || costs.len() > MAX_EXPLORED_NODES {
+            break;
+        }
+
+        let cost = costs[&current] + 1;
+        for next in neighbors(chunks, current) {
+            if costs.get(&next).is_some_and(|known| *known <= cost) {
+                continue;
+            }
+            costs.insert(next, cost);
+            came_from.insert(next, current);
+            open.push(Reverse((cost + heuristic(next, goal), next.to_array())));
+        }
+    }
+
+    let mut path = vec![closest];
+    while let Some(previous) = came_from.get(path.last().unwrap()) {
+        if *previous == start {
+            break;
+        }
+        path.push(*previous);
+    }
+    if closest == start {
+        path.clear();
+    }
+    path.reverse();
+    path
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use rand::Rng;
+use shared::world::{
+    is_daytime, BiomeType, BlockId, MobKind, MobTarget, RandomStream, ServerMob, ServerWorldMap,
+    SimulationRandom, WorldMap,
+};
+
+use crate::{
+    init::ServerTime,
+    settings::ServerSettings,
+    world::{afk::PlayerActivity, spatial::SpatialIndex},
+};
+
+use super::{create_new_mob_id, pathfinding::is_standable};
+
+/// One spawn attempt is made around each player that often
+const SPAWN_ATTEMPT_TICKS: u64 = 40;
+/// Hostile mobs appear far enough not to pop in front of players, but close enough to find them
+const MIN_SPAWN_DISTANCE: f32 = 24.0;
+const MAX_SPAWN_DISTANCE: f32 = 40.0;
+/// Wild (untamed) passive mobs of each kind roaming around each player
+const PASSIVE_MOBS: [(MobKind, usize); 2] = [(MobKind::Wolf, 2), (MobKind::Villager, 1)];
+
+/// Wolves roam the woods and the mountains, villagers the open plains
+fn spawns_in(kind: MobKind, biome: BiomeType) -> bool {
+    match kind {
+        MobKind::Wolf => matches!(
+            biome,
+            BiomeType::Forest | BiomeType::MediumMountain | BiomeType::HighMountainGrass
+        ),
+        MobKind::Villager => matches!(biome, BiomeType::Plains | BiomeType::FlowerPlains),
+        _ => true,
+    }
+}
+
+/// Hostile mobs only spawn in the dark: at night, or anywhere hidden from the sky
+fn can_spawn_hostile_at(world_map: &ServerWorldMap, pos: IVec3) -> bool {
+    is_standable(&world_map.chunks, pos)
+        && (!is_daytime(world_map.day_time) || !world_map.chunks.is_exposed_to_sky(&pos))
+}
+
+/// Mobs only spawn around the players at their keyboard, so that farms do not run unattended
+fn spawn_centers(world_map: &ServerWorldMap, activity: &PlayerActivity) -> Vec<Vec3> {
+    world_map
+        .players
+        .values()
+        .filter(|player| !activity.is_afk(player.id))
+        .map(|player| player.position)
+        .collect()
+}
+
+pub fn hostile_mob_spawning_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    time: Res<ServerTime>,
+    index: Res<SpatialIndex>,
+    activity: Res<PlayerActivity>,
+    settings: Res<ServerSettings>,
+    random: Res<SimulationRandom>,
+) {
+    if !time.0.is_multiple_of(SPAWN_ATTEMPT_TICKS) || !world_map.game_rules.mob_spawning {
+        return;
+    }
+    let centers = spawn_centers(&world_map, &activity);
+    if centers.is_empty() {
+        return;
+    }
+
+    let hostile_count = world_map
+        .mobs
+        .values()
+        .filter(|mob| mob.kind.is_hostile())
+        .count();
+    if hostile_count >= centers.len() * settings.difficulty.hostile_mobs_per_player() {
+        return;
+    }
+
+    let mut rng = random.for_tick(RandomStream::HostileSpawning, time.0);
+
+    for center in centers {
+        if !rng.gen_bool(settings.difficulty.hostile_spawn_chance()) {
+            continue;
+        }
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let distance = rng.gen_range(MIN_SPAWN_DISTANCE..MAX_SPAWN_DISTANCE);
+        let column = center + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+
+        let ground = world_map.chunks.get_height_ground(column);
+        let pos = IVec3::new(column.x.floor() as i32, ground + 1, column.z.floor() as i32);
+
+        if !can_spawn_hostile_at(&world_map, pos) {
+            continue;
+        }
+
+        let position = Vec3::new(pos.x as f32 + 0.5, pos.y as f32 + 1.0, pos.z as f32 + 0.5);
+        // The spot may be far from this player but right next to another one
+        if index
+            .players_in_radius(position, MIN_SPAWN_DISTANCE)
+            .next()
+            .is_some()
+        {
+            continue;
+        }
+
+        let kind = if rng.gen_bool(0.5) {
+            MobKind::Zombie
+        } else {
+            MobKind::Skeleton
+        };
+        let mob = ServerMob::new(kind, position, MobTarget::None);
+        debug!("Spawning {:?} at {:?}", kind, position);
+        let id = create_new_mob_id(&world_map, &mut rng);
+        world_map.mobs.insert(id, mob);
+    }
+}
+
+/// Passive mobs spawn during the day on grass of their biomes, tamed wolves do not count towards the limit
+pub fn passive_mob_spawning_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    time: Res<ServerTime>,
+    activity: Res<PlayerActivity>,
+    random: Res<SimulationRandom>,
+) {
+    if !time.0.is_multiple_of(SPAWN_ATTEMPT_TICKS)
+        || !world_map.game_rules.mob_spawning
+        || !is_daytime(world_map.day_time)
+    {
+        return;
+    }
+    let centers = spawn_centers(&world_map, &activity);
+    if centers.is_empty() {
+        return;
+    }
+
+    let mut rng = random.for_tick(RandomStream::PassiveSpawning, time.0);
+
+    for (kind, max_per_player) in PASSIVE_MOBS {
+        let wild = world_map
+            .mobs
+            .values()
+            .filter(|mob| mob.kind == kind && mob.owner.is_none())
+            .count();
+        if wild >= centers.len() * max_per_player {
+            continue;
+        }
+
+        for center in centers.iter() {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let distance = rng.gen_range(MIN_SPAWN_DISTANCE..MAX_SPAWN_DISTANCE);
+            let column = *center + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+
+            let ground = world_map.chunks.get_height_ground(column);
+            let pos = IVec3::new(column.x.floor() as i32, ground + 1, column.z.floor() as i32);
+
+            let on_grass = world_map
+                .chunks
+                .get_block_by_coordinates(&(pos - IVec3::Y))
+                .is_some_and(|block| block.id == BlockId::Grass);
+            let in_biome = world_map
+                .chunks
+                .get_biome(&pos)
+                .is_some_and(|biome| spawns_in(kind, biome));
+            if !on_grass || !in_biome || !is_standable(&world_map.chunks, pos) {
+                continue;
+            }
+
+            let position = Vec3::new(pos.x as f32 + 0.5, pos.y as f32 + 1.0, pos.z as f32 + 0.5);
+            let mob = ServerMob::new(kind, position, MobTarget::None);
+            debug!("Spawning {:?} at {:?}", kind, position);
+            let id = create_new_mob_id(&world_map, &mut rng);
+            world_map.mobs.insert(id, mob);
+        }
+    }
+}
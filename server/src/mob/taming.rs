@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use rand::Rng;
+use shared::{
+    messages::PlayerId,
+    world::{MobId, RandomStream, ServerWorldMap, SimulationRandom},
+};
+
+use crate::init::ServerTime;
+
+use super::trading::send_trade_offers;
+
+/// Players can interact with mobs up to this distance, a bit more than the interaction distance to absorb latency
+pub(crate) const MOB_INTERACTION_REACH: f32 = 8.0;
+/// Chance for each fed item to tame the mob
+const TAMING_CHANCE: f64 = 1.0 / 3.0;
+
+#[derive(Event, Debug)]
+pub struct MobInteractRequestEvent {
+    pub client_id: PlayerId,
+    pub mob_id: MobId,
+    pub hotbar_slot: u32,
+}
+
+/// Feeding its taming item to a wild mob may tame it, and owners make their pets sit or stand up.
+/// Traders open their trading window instead.
+pub fn handle_mob_interactions_system(
+    mut events: EventReader<MobInteractRequestEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    (time, random): (Res<ServerTime>, Res<SimulationRandom>),
+) {
+    let world_map = world_map.as_mut();
+
+    for event in events.read() {
+        let Some(player) = world_map.players.get_mut(&event.client_id) else {
+            continue;
+        };
+        let Some(mob) = world_map.mobs.get_mut(&event.mob_id) else {
+            continue;
+        };
+
+        if mob.position.distance(player.position) > MOB_INTERACTION_REACH {
+            continue;
+        }
+
+        if !mob.trades.is_empty() {
+            send_trade_offers(&mut server, event.client_id, event.mob_id, mob);
+            continue;
+        }
+
+        match mob.owner {
+            Some(owner) if owner == player.id => {
+                mob.sitting = !mob.sitting;
+                mob.path.clear();
+            }
+            Some(_) => {}
+            None => {
+                let Some(taming_item) = mob.kind.taming_item() else {
+                    continue;
+                };
+                let held = player.inventory.get(event.hotbar_slot);
+                if held.is_none_or(|stack| stack.item_id != taming_item) {
+                    continue;
+                }
+                if player
+                    .inventory
+                    .take_from_slot(event.hotbar_slot, 1)
+                    .is_err()
+                {
+                    continue;
+                }
+
+                if random
+                    .for_entity(RandomStream::Taming, time.0, event.mob_id)
+                    .gen_bool(TAMING_CHANCE)
+                {
+                    info!(
+                        "Player {} tamed {:?} {}",
+                        player.name, mob.kind, event.mob_id
+                    );
+                    mob.owner = Some(player.id);
+                }
+            }
+        }
+    }
+}
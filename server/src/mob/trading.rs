@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{mob::TradeOffersEvent, PlayerId, ServerToClientMessage},
+    players::Player,
+    world::{
+        ItemStack, MobId, RandomStream, ServerItemStack, ServerMob, ServerWorldMap, SimRng,
+        SimulationRandom, Trade,
+    },
+    TICKS_PER_SECOND,
+};
+
+use crate::{
+    init::ServerTime, network::extensions::SendGameMessageExtension, world::stacks::give_or_drop,
+};
+
+use super::taming::MOB_INTERACTION_REACH;
+
+/// Traders make all their trades available again that often
+const TRADE_RESTOCK_TICKS: u64 = 5 * 60 * TICKS_PER_SECOND;
+
+#[derive(Event, Debug)]
+pub struct TradeRequestEvent {
+    pub client_id: PlayerId,
+    pub mob_id: MobId,
+    pub offer: usize,
+}
+
+/// Opens the trading window of the player, or refreshes it
+pub fn send_trade_offers(
+    server: &mut RenetServer,
+    client_id: PlayerId,
+    mob_id: MobId,
+    mob: &ServerMob,
+) {
+    server.send_game_message(
+        client_id,
+        ServerToClientMessage::TradeOffers(TradeOffersEvent {
+            mob_id,
+            offers: mob.trades.clone(),
+        }),
+    );
+}
+
+/// Takes the payment from the player and gives them the result, what does not fit in their inventory
+/// is dropped at their feet. Returns false if they cannot pay
+fn apply_trade(
+    player: &mut Player,
+    trade: &Trade,
+    item_stacks: &mut Vec<ServerItemStack>,
+    ids: &mut SimRng,
+) -> bool {
+    let (cost_item, cost_nb) = trade.cost;
+    if player.inventory.remove_exact(cost_item, cost_nb).is_err() {
+        return false;
+    }
+
+    let (result_item, result_nb) = trade.result;
+    let result = ItemStack {
+        item_id: result_item,
+        item_type: result_item.get_default_type(),
+        nb: result_nb,
+    };
+    give_or_drop(player, result, item_stacks, ids);
+    true
+}
+
+pub fn handle_trades_system(
+    mut events: EventReader<TradeRequestEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    time: Res<ServerTime>,
+    random: Res<SimulationRandom>,
+) {
+    let world_map = world_map.as_mut();
+
+    for event in events.read() {
+        let Some(player) = world_map.players.get_mut(&event.client_id) else {
+            continue;
+        };
+        let Some(mob) = world_map.mobs.get_mut(&event.mob_id) else {
+            continue;
+        };
+        if mob.position.distance(player.position) > MOB_INTERACTION_REACH {
+            continue;
+        }
+
+        let accepted = match mob.trades.get_mut(event.offer) {
+            Some(offer) if !offer.is_sold_out() => {
+                let mut ids =
+                    random.for_entity(RandomStream::EntityIds, time.0, event.client_id as u128);
+                let accepted =
+                    apply_trade(player, &offer.trade, &mut world_map.item_stacks, &mut ids);
+                if accepted {
+                    offer.uses += 1;
+                }
+                accepted
+            }
+            _ => false,
+        };
+        if accepted {
+            debug!(
+                "Player {} traded with {} (offer {})",
+                player.name, event.mob_id, event.offer
+            );
+        } else {
+            debug!(
+                "Refused trade {} of {} for player {}",
+                event.offer, event.mob_id, player.name
+            );
+        }
+
+        // Refused trades also get an answer, the window may show outdated offers
+        send_trade_offers(&mut server, event.client_id, event.mob_id, mob);
+    }
+}
+
+pub fn restock_trades_system(mut world_map: ResMut<ServerWorldMap>, time: Res<ServerTime>) {
+    if time.0 == 0 || !time.0.is_multiple_of(TRADE_RESTOCK_TICKS) {
+        return;
+    }
+    for mob in world_map.mobs.values_mut() {
+        for offer in mob.trades.iter_mut() {
+            offer.uses = 0;
+        }
+    }
+}
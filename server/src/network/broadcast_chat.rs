@@ -1,5 +1,10 @@
 use bevy::prelude::*;
-use shared::messages::ChatConversation;
+use bevy_renet::renet::RenetServer;
+use shared::messages::{ChatConversation, FullChatMessage, ServerToClientMessage};
+
+use super::extensions::SendGameMessageExtension;
+
+pub const SERVER_CHAT_AUTHOR: &str = "Server";
 
 #[derive(Event)]
 pub struct ChatMessageEvent;
@@ -8,3 +13,40 @@ pub fn setup_chat_resources(app: &mut App) {
     app.insert_resource(ChatConversation { ..default() });
     app.add_event::<ChatMessageEvent>();
 }
+
+/// Adds a message authored by the server itself, `ChatMessageEvent` must be sent afterwards
+pub fn push_server_chat_message(conversation: &mut ChatConversation, content: String) {
+    let timestamp: u64 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    info!("[chat] {}", content);
+
+    conversation.messages.push(FullChatMessage {
+        author: SERVER_CHAT_AUTHOR.into(),
+        content,
+        timestamp,
+        recipients: Vec::new(),
+    });
+}
+
+pub fn broadcast_chat_system(
+    mut events: EventReader<ChatMessageEvent>,
+    mut server: ResMut<RenetServer>,
+    conversation: Res<ChatConversation>,
+) {
+    // Several messages may arrive on the same tick, the whole conversation is only sent once
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    // Team messages are left out of the conversation of the other players
+    for client_id in server.clients_id() {
+        server.send_game_message(
+            client_id,
+            ServerToClientMessage::ChatConversation(conversation.visible_to(client_id)),
+        );
+    }
+}
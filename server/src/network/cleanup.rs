@@ -1,5 +1,5 @@
 use bevy_ecs::event::EventWriter;
-use shared::{messages::PlayerId, world::ServerWorldMap};
+use shared::{messages::PlayerId, players::Player, world::ServerWorldMap};
 
 use crate::world::save::SaveRequestEvent;
 
@@ -7,21 +7,24 @@ pub fn cleanup_all_players_from_world(world_map: &mut ServerWorldMap) {
     for p in world_map.players.values_mut() {
         p.last_input_processed = 0;
     }
-    for (_, chunk) in world_map.chunks.map.iter_mut() {
+    for chunk in world_map.chunks.map.values_mut() {
         chunk.sent_to_clients.clear();
     }
 }
 
+/// Removes a disconnected player, returning them if they were in the world
 pub fn cleanup_player_from_world(
     world_map: &mut ServerWorldMap,
     player_id: &PlayerId,
     save_event_writer: &mut EventWriter<SaveRequestEvent>,
-) {
-    if world_map.players.remove(player_id).is_some() {
+) -> Option<Player> {
+    let player = world_map.players.remove(player_id);
+    if player.is_some() {
         save_event_writer.write(SaveRequestEvent::Player(*player_id));
     }
 
-    for (_, chunk) in world_map.chunks.map.iter_mut() {
+    for chunk in world_map.chunks.map.values_mut() {
         chunk.sent_to_clients.retain(|&id| id != *player_id);
     }
+    player
 }
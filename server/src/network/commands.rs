@@ -0,0 +1,624 @@
+use bevy::prelude::*;
+use shared::{
+    lang::translatable,
+    messages::{ChatConversation, PlayerId, RecipeUnlockEvent},
+    players::GameMode,
+    world::{
+        global_block_to_chunk_pos, ChunkClaim, ItemId, ItemStack, RandomStream, RecipeId,
+        ServerWorldMap, SimRng, SimulationRandom,
+    },
+    GameFolderPaths, GameServerConfig,
+};
+
+use crate::{
+    init::ServerTime,
+    mob::difficulty::difficulty_command,
+    settings::ServerSettings,
+    world::{
+        effects::effect_command,
+        entity_data::{data_command, summon_command},
+        gamerules::{gamerule_command, GameRulesChangedEvent},
+        journal::{history_command, EventJournal},
+        ores::ore_density_report,
+        pregen::{cancel_pregeneration, start_pregeneration, Pregeneration},
+        preset::WorldGenPreset,
+        protection::{claim_position, is_in_spawn_protection, is_player_op},
+        recipes::set_recipes_unlocked,
+        reload::{reload_changed_chunks, ChunkFileWatcher},
+        rollback::rollback_command,
+        save::SaveRequestEvent,
+        stacks::give_or_drop,
+        tick_control::{tick_command, TickControl},
+        trim::{trim_world, MIN_TRIM_RADIUS},
+        weather::weather_command,
+        BlockInteractionEvent,
+    },
+};
+
+use super::{
+    broadcast_chat::{push_server_chat_message, ChatMessageEvent},
+    teams::{team_command, TeamsChangedEvent},
+};
+
+/// A chat message starting with `/`, handled by the server instead of being broadcast
+#[derive(Event, Debug)]
+pub struct ChatCommandEvent {
+    pub client_id: PlayerId,
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// Returns `None` if the message is not a command
+pub fn parse_chat_command(client_id: PlayerId, content: &str) -> Option<ChatCommandEvent> {
+    let mut words = content.strip_prefix('/')?.split_whitespace();
+    let name = words.next()?.to_lowercase();
+
+    Some(ChatCommandEvent {
+        client_id,
+        name,
+        args: words.map(String::from).collect(),
+    })
+}
+
+pub fn handle_chat_commands_system(
+    mut events: EventReader<ChatCommandEvent>,
+    mut ev_save_request: EventWriter<SaveRequestEvent>,
+    mut chat_conversation: ResMut<ChatConversation>,
+    mut ev_chat: EventWriter<ChatMessageEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut settings: ResMut<ServerSettings>,
+    config: Res<GameServerConfig>,
+    mut ev_teams: EventWriter<TeamsChangedEvent>,
+    preset: Res<WorldGenPreset>,
+    mut ev_recipes: EventWriter<RecipeUnlockEvent>,
+    mut pregen: ResMut<Pregeneration>,
+    mut ev_rules: EventWriter<GameRulesChangedEvent>,
+    game_folder_paths: Res<GameFolderPaths>,
+    mut chunk_watcher: ResMut<ChunkFileWatcher>,
+    mut tick_control: ResMut<TickControl>,
+    (time, random, mut journal, mut ev_block): (
+        Res<ServerTime>,
+        Res<SimulationRandom>,
+        ResMut<EventJournal>,
+        EventWriter<BlockInteractionEvent>,
+    ),
+) {
+    for command in events.read() {
+        info!(
+            "Command received from {}: /{} {:?}",
+            command.client_id, command.name, command.args
+        );
+        let mut entity_ids =
+            random.for_entity(RandomStream::EntityIds, time.0, command.client_id as u128);
+
+        let reply = match command.name.as_str() {
+            "save-all" => save_command(
+                &world_map,
+                &settings,
+                &config,
+                command.client_id,
+                SaveRequestEvent::World,
+                &mut ev_save_request,
+            ),
+            "backup" => save_command(
+                &world_map,
+                &settings,
+                &config,
+                command.client_id,
+                SaveRequestEvent::Backup,
+                &mut ev_save_request,
+            ),
+            "claim" => claim_chunk(&mut world_map, &settings, &config, command.client_id),
+            "unclaim" => unclaim_chunk(&mut world_map, &settings, &config, command.client_id),
+            "team" => team_command(
+                &mut world_map,
+                command.client_id,
+                &command.args,
+                &mut ev_teams,
+            ),
+            "ores" => ore_stats(&world_map, &settings, &config, &preset, command.client_id),
+            "give" => give_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+                &mut entity_ids,
+            ),
+            "recipe" => recipe_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+                &mut ev_recipes,
+            ),
+            "gamemode" => game_mode_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+            ),
+            "pregen" => pregen_command(
+                &world_map,
+                &settings,
+                &config,
+                &mut pregen,
+                command.client_id,
+                &command.args,
+            ),
+            "trim" => trim_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+                &mut ev_save_request,
+            ),
+            "reloadchunks" => reload_chunks_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &mut chunk_watcher,
+            ),
+            "summon" => summon_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+                &mut entity_ids,
+            ),
+            "data" => data_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+            ),
+            "gamerule" => gamerule_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+                &mut ev_rules,
+            ),
+            "difficulty" => difficulty_command(
+                &world_map,
+                &mut settings,
+                &config,
+                &game_folder_paths,
+                command.client_id,
+                &command.args,
+            ),
+            "effect" => effect_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+            ),
+            "weather" => weather_command(
+                &mut world_map,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+                &mut random.for_tick(RandomStream::Weather, time.0),
+            ),
+            "chunkinfo" => chunk_info(&world_map, &settings, &config, command.client_id),
+            "history" => history_command(
+                &world_map,
+                &journal,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+            ),
+            "rollback" => rollback_command(
+                &mut world_map,
+                &mut journal,
+                &mut ev_block,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+            ),
+            "tick" => tick_command(
+                &world_map,
+                &mut tick_control,
+                &settings,
+                &config,
+                command.client_id,
+                &command.args,
+            ),
+            _ => format!("Unknown command: /{}", command.name),
+        };
+
+        push_server_chat_message(&mut chat_conversation, reply);
+        ev_chat.write(ChatMessageEvent);
+    }
+}
+
+/// Claims the chunk column the player is standing in
+fn claim_chunk(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+) -> String {
+    let Some(player) = world_map.players.get(&client_id) else {
+        return "You must be in the world to claim a chunk".to_string();
+    };
+
+    let block_pos = player.position.floor().as_ivec3();
+    if is_in_spawn_protection(settings, block_pos) && !is_player_op(settings, config, &player.name)
+    {
+        return "The spawn area cannot be claimed".to_string();
+    }
+
+    let claim_pos = claim_position(block_pos);
+    if let Some(claim) = world_map.claims.get(&claim_pos) {
+        return if claim.owner == client_id {
+            "You already own this chunk".to_string()
+        } else {
+            format!("This chunk is already claimed by {}", claim.owner_name)
+        };
+    }
+
+    let claim = ChunkClaim {
+        owner: client_id,
+        owner_name: player.name.clone(),
+    };
+    let reply = format!("{} claimed chunk {:?}", claim.owner_name, claim_pos);
+    world_map.claims.insert(claim_pos, claim);
+    reply
+}
+
+/// Releases the claim of the chunk column the player is standing in, ops can release any claim
+fn unclaim_chunk(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+) -> String {
+    let Some(player) = world_map.players.get(&client_id) else {
+        return "You must be in the world to unclaim a chunk".to_string();
+    };
+
+    let claim_pos = claim_position(player.position.floor().as_ivec3());
+    let Some(claim) = world_map.claims.get(&claim_pos) else {
+        return "This chunk is not claimed".to_string();
+    };
+
+    if claim.owner != client_id && !is_player_op(settings, config, &player.name) {
+        return format!("This chunk belongs to {}", claim.owner_name);
+    }
+
+    world_map.claims.remove(&claim_pos);
+    format!("Chunk {:?} is not claimed anymore", claim_pos)
+}
+
+/// Ore density of the generated chunks, to tune the world generation preset
+fn ore_stats(
+    world_map: &ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    preset: &WorldGenPreset,
+    client_id: PlayerId,
+) -> String {
+    let Some(player) = world_map.players.get(&client_id) else {
+        return "You must be in the world to see the ore statistics".to_string();
+    };
+    if !is_player_op(settings, config, &player.name) {
+        return "Only ops can see the ore statistics".to_string();
+    }
+
+    ore_density_report(&world_map.chunks, preset).join("\n")
+}
+
+/// Saves or backs up the world, the server administrator decides when that happens
+fn save_command(
+    world_map: &ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    request: SaveRequestEvent,
+    ev_save_request: &mut EventWriter<SaveRequestEvent>,
+) -> String {
+    let Some(player) = world_map.players.get(&client_id) else {
+        return "You must be in the world to save it".to_string();
+    };
+    if !is_player_op(settings, config, &player.name) {
+        return "Only ops can save the world".to_string();
+    }
+
+    let reply = match request {
+        SaveRequestEvent::Backup => "Creating a backup of the world...",
+        _ => "Saving the world...",
+    };
+    ev_save_request.write(request);
+    reply.to_string()
+}
+
+/// Save and network state of the chunk the player is standing in
+fn chunk_info(
+    world_map: &ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+) -> String {
+    let Some(player) = world_map.players.get(&client_id) else {
+        return "You must be in the world to inspect a chunk".to_string();
+    };
+    if !is_player_op(settings, config, &player.name) {
+        return "Only ops can inspect chunks".to_string();
+    }
+
+    let chunk_pos = global_block_to_chunk_pos(&player.position.floor().as_ivec3());
+    let Some(chunk) = world_map.chunks.map.get(&chunk_pos) else {
+        return format!("Chunk {chunk_pos} is not loaded");
+    };
+    let sent_to: Vec<String> = chunk
+        .sent_to_clients
+        .iter()
+        .map(|id| {
+            world_map
+                .players
+                .get(id)
+                .map_or_else(|| id.to_string(), |player| player.name.clone())
+        })
+        .collect();
+    let pending_update = match world_map.chunks.chunks_to_update.reasons(&chunk_pos) {
+        Some(reasons) => format!("{reasons:?}"),
+        None => "none".to_string(),
+    };
+
+    [
+        format!(
+            "Chunk {chunk_pos}: {} blocks, ts {}, player modified {}",
+            chunk.map.len(),
+            chunk.ts,
+            chunk.player_modified
+        ),
+        format!(
+            "Unsaved changes: {}, pending update: {pending_update}",
+            world_map.chunks.dirty_chunks.contains(&chunk_pos)
+        ),
+        format!("Sent to: [{}]", sent_to.join(", ")),
+    ]
+    .join("\n")
+}
+
+const GIVE_USAGE: &str = "Usage: /give <player> <item> [count]";
+/// Most items `/give` hands out at once
+const MAX_GIVE_COUNT: u32 = 64;
+
+/// Gives items to a player, what does not fit in their inventory is dropped at their feet
+fn give_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+    ids: &mut SimRng,
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to give items".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can give items".to_string();
+    }
+
+    let (target, item, count) = match args {
+        [target, item] => (target, item, 1),
+        [target, item, count] => match count.parse::<u32>() {
+            Ok(count) if (1..=MAX_GIVE_COUNT).contains(&count) => (target, item, count),
+            _ => return format!("The count must be between 1 and {MAX_GIVE_COUNT}"),
+        },
+        _ => return GIVE_USAGE.to_string(),
+    };
+    let Some(item_id) = ItemId::from_name(item) else {
+        return format!("Unknown item {item}");
+    };
+    let world_map = &mut *world_map;
+    let Some(player) = world_map
+        .players
+        .values_mut()
+        .find(|player| player.name == *target)
+    else {
+        return format!("Player {target} is not in the world");
+    };
+
+    let stack = ItemStack {
+        item_id,
+        item_type: item_id.get_default_type(),
+        nb: count,
+    };
+    give_or_drop(player, stack, &mut world_map.item_stacks, ids);
+    translatable(
+        "commands.give.success",
+        &[&count.to_string(), &item_id.translation_key(), &player.name],
+    )
+}
+
+const RECIPE_USAGE: &str = "Usage: /recipe <give|take> <player> <recipe|*>";
+
+/// Unlocks or locks recipes for a player, whatever items they got
+fn recipe_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+    ev_recipes: &mut EventWriter<RecipeUnlockEvent>,
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to manage recipes".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can manage recipes".to_string();
+    }
+
+    let [action, target, recipe] = args else {
+        return RECIPE_USAGE.to_string();
+    };
+    let unlocked = match action.as_str() {
+        "give" => true,
+        "take" => false,
+        _ => return RECIPE_USAGE.to_string(),
+    };
+    let recipes = if recipe == "*" {
+        RecipeId::ALL.to_vec()
+    } else {
+        match RecipeId::from_name(recipe) {
+            Some(recipe) => vec![recipe],
+            None => return format!("Unknown recipe {}", recipe),
+        }
+    };
+    let Some(player) = world_map
+        .players
+        .values_mut()
+        .find(|player| player.name == *target)
+    else {
+        return format!("Player {} is not in the world", target);
+    };
+
+    let changed = set_recipes_unlocked(player, recipes, unlocked);
+    if changed.is_empty() {
+        return format!("Nothing changed for {}", player.name);
+    }
+    let reply = format!(
+        "{} {} recipes for {}: {:?}",
+        if unlocked { "Unlocked" } else { "Locked" },
+        changed.len(),
+        player.name,
+        changed
+    );
+    ev_recipes.write(RecipeUnlockEvent {
+        player_id: player.id,
+        recipes: changed,
+        unlocked,
+    });
+    reply
+}
+
+const GAME_MODE_USAGE: &str = "Usage: /gamemode <survival|creative|spectator> [player]";
+
+/// Changes the game mode of the sender, or of another player
+fn game_mode_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to change game modes".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can change game modes".to_string();
+    }
+
+    let (mode, target) = match args {
+        [mode] => (mode, sender.name.clone()),
+        [mode, target] => (mode, target.clone()),
+        _ => return GAME_MODE_USAGE.to_string(),
+    };
+    let Some(mode) = GameMode::from_name(mode) else {
+        return GAME_MODE_USAGE.to_string();
+    };
+    let Some(player) = world_map
+        .players
+        .values_mut()
+        .find(|player| player.name == target)
+    else {
+        return format!("Player {} is not in the world", target);
+    };
+
+    player.game_mode = mode;
+    format!("{} is now in {:?} mode", player.name, mode)
+}
+
+const PREGEN_USAGE: &str = "Usage: /pregen <radius in chunks|cancel>";
+
+/// Generates the chunks around spawn ahead of time, or stops doing so
+fn pregen_command(
+    world_map: &ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    pregen: &mut Pregeneration,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to pregenerate chunks".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can pregenerate chunks".to_string();
+    }
+
+    match args {
+        [arg] if arg == "cancel" => cancel_pregeneration(pregen),
+        [radius] => match radius.parse::<u32>() {
+            Ok(radius) => start_pregeneration(pregen, &world_map.chunks, radius),
+            Err(_) => PREGEN_USAGE.to_string(),
+        },
+        _ => PREGEN_USAGE.to_string(),
+    }
+}
+
+/// Loads again the chunks whose save files were edited on disk
+fn reload_chunks_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    watcher: &mut ChunkFileWatcher,
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to reload chunks".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can reload chunks".to_string();
+    }
+
+    match reload_changed_chunks(world_map, watcher) {
+        0 => "No chunk was edited on disk".to_string(),
+        reloaded => format!("Reloaded {reloaded} chunks edited on disk"),
+    }
+}
+
+/// Removes the chunks far from spawn that no player changed, they are generated again if visited
+fn trim_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+    ev_save_request: &mut EventWriter<SaveRequestEvent>,
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to trim it".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can trim the world".to_string();
+    }
+
+    let radius = match args {
+        [radius] => radius.parse::<u32>().ok(),
+        _ => None,
+    };
+    let Some(radius) = radius.filter(|radius| *radius >= MIN_TRIM_RADIUS) else {
+        return format!("Usage: /trim <radius in chunks, at least {MIN_TRIM_RADIUS}>");
+    };
+
+    let removed = trim_world(world_map, settings, radius);
+    if removed > 0 {
+        ev_save_request.write(SaveRequestEvent::World);
+    }
+    format!("Trimmed {removed} unmodified chunks farther than {radius} chunks from spawn")
+}
@@ -1,21 +1,67 @@
 use crate::init::{LobbyPlayer, ServerLobby, ServerTime};
+use crate::metrics::{tick_end_system, tick_start_system, TickProfiler};
 use crate::mob::behavior::mob_behavior_system;
+use crate::mob::combat::{mob_combat_system, MobAttackRequestEvent};
+use crate::mob::difficulty::peaceful_despawn_system;
+use crate::mob::spawning::{hostile_mob_spawning_system, passive_mob_spawning_system};
+use crate::mob::taming::{handle_mob_interactions_system, MobInteractRequestEvent};
+use crate::mob::trading::{handle_trades_system, restock_trades_system, TradeRequestEvent};
 use crate::network::broadcast_chat::*;
 use crate::network::cleanup::cleanup_player_from_world;
+use crate::network::commands::{handle_chat_commands_system, parse_chat_command, ChatCommandEvent};
+use crate::network::teams::{
+    broadcast_teams_system, chat_author_name, team_chat_message, TeamsChangedEvent,
+};
+use crate::network::tokens::GrantedNames;
 use crate::world;
+use crate::world::afk::{afk_detection_system, PlayerActivity};
+use crate::world::autosave::{autosave_system, AutosaveScheduler};
 use crate::world::background_generation::background_world_generation_system;
-use crate::world::broadcast_world::broadcast_world_state;
+use crate::world::broadcast_world::{broadcast_world_state, AwaitedChunkCaches};
+use crate::world::effects::status_effects_system;
+use crate::world::emotes::{handle_emotes_system, PlayerEmoteRequestEvent};
+use crate::world::fire::{fire_block_ticks_system, fire_damage_system};
+use crate::world::gamerules::{broadcast_game_rules_system, GameRulesChangedEvent};
+use crate::world::health::{apply_player_damage_system, PlayerDamageEvent};
+use crate::world::hints::{hint_triggers_system, PlayerHintRequestEvent};
+use crate::world::item_use::{handle_item_use_system, PlayerItemUseEvent};
+use crate::world::journal::journal_flush_system;
 use crate::world::load_from_file::load_player_data;
-use crate::world::save::SaveRequestEvent;
+use crate::world::packs::{send_pack_chunks_system, PackTransfers, WorldPacks};
+use crate::world::pregen::{pregen_from_config_system, pregeneration_system, Pregeneration};
+use crate::world::preset::WorldCacheId;
+use crate::world::projectiles::simulate_projectiles_system;
+use crate::world::pushback::entity_pushback_system;
+use crate::world::recipes::{
+    detect_obtained_items_system, send_recipe_unlocks_system, unlock_recipes_system,
+    ItemObtainedEvent, ObtainedItems,
+};
+use crate::world::reload::chunk_file_watch_system;
+use crate::world::save::{report_world_saves_system, SaveRequestEvent};
 use crate::world::simulation::{handle_player_inputs_system, PlayerInputsEvent};
+use crate::world::snow::snow_random_ticks_system;
+use crate::world::spatial::{update_spatial_index_system, SpatialIndex};
+use crate::world::spawn_platform::build_spawn_platform_system;
+use crate::world::spectate::{
+    handle_spectate_requests_system, send_game_modes_system, spectators_follow_system,
+    PlayerSpectateRequestEvent,
+};
+use crate::world::stacks::item_stacks_pickup_system;
+use crate::world::streaming::count_spawn_area_chunks;
+use crate::world::tick_control::{
+    broadcast_tick_rate_system, simulation_ticking, tick_control_system, TickControl,
+};
+use crate::world::weather::{broadcast_weather_system, weather_system};
 use crate::world::BlockInteractionEvent;
 use bevy::prelude::*;
 use bevy_renet::renet::{RenetServer, ServerEvent};
 use shared::messages::{
-    AuthRegisterResponse, ChatConversation, ClientToServerMessage, FullChatMessage, PlayerSave,
-    PlayerSpawnEvent, ServerToClientMessage,
+    AuthRegisterResponse, CachedChunk, ChatConversation, ClientToServerMessage, FullChatMessage,
+    GameRulesUpdate, PlayerLeftEvent, PlayerSave, PlayerSpawnEvent, RecipeUnlockEvent,
+    ServerToClientMessage, TimeSyncResponse,
 };
 use shared::players::Player;
+use shared::utils::unix_time_ms;
 use shared::world::ServerWorldMap;
 use shared::{GameFolderPaths, GameServerConfig, TICKS_PER_SECOND};
 
@@ -24,50 +70,203 @@ use super::extensions::SendGameMessageExtension;
 pub fn setup_resources_and_events(app: &mut App) {
     app.add_event::<SaveRequestEvent>()
         .add_event::<BlockInteractionEvent>()
-        .add_event::<PlayerInputsEvent>();
+        .add_event::<PlayerInputsEvent>()
+        .add_event::<ChatCommandEvent>()
+        .add_event::<TeamsChangedEvent>()
+        .add_event::<GameRulesChangedEvent>()
+        .add_event::<PlayerEmoteRequestEvent>()
+        .add_event::<PlayerHintRequestEvent>()
+        .add_event::<PlayerSpectateRequestEvent>()
+        .add_event::<PlayerDamageEvent>()
+        .add_event::<PlayerItemUseEvent>()
+        .add_event::<MobAttackRequestEvent>()
+        .add_event::<MobInteractRequestEvent>()
+        .add_event::<TradeRequestEvent>()
+        .add_event::<ItemObtainedEvent>()
+        .add_event::<RecipeUnlockEvent>();
+
+    app.init_resource::<SpatialIndex>();
+    app.init_resource::<PlayerActivity>();
+    app.init_resource::<ObtainedItems>();
+    app.init_resource::<Pregeneration>();
+    app.init_resource::<TickProfiler>();
+    app.init_resource::<AutosaveScheduler>();
+    app.init_resource::<AwaitedChunkCaches>();
 
     setup_chat_resources(app);
 }
 
 pub fn register_systems(app: &mut App) {
-    // Chaining the two so that saves are always done on the same frame as the request
+    // Chaining them so that saves are always queued on the same frame as the request
     app.add_systems(
         Update,
-        (server_update_system, world::save::save_world_system).chain(),
+        (
+            server_update_system,
+            handle_chat_commands_system,
+            world::save::save_world_system,
+        )
+            .chain(),
+    );
+
+    app.add_systems(
+        Update,
+        (
+            report_world_saves_system,
+            broadcast_chat_system,
+            broadcast_teams_system,
+            broadcast_game_rules_system,
+        )
+            .chain(),
     );
 
     app.add_systems(Update, broadcast_world_state);
 
     app.add_systems(Update, world::handle_block_interactions);
+    app.add_systems(Update, fire_block_ticks_system.run_if(simulation_ticking));
+    app.add_systems(
+        Update,
+        (
+            weather_system.run_if(simulation_ticking),
+            broadcast_weather_system,
+            snow_random_ticks_system.run_if(simulation_ticking),
+        )
+            .chain(),
+    );
 
-    app.add_systems(Update, crate::mob::manage_mob_spawning_system);
+    app.add_systems(
+        Update,
+        (
+            crate::mob::manage_mob_spawning_system,
+            hostile_mob_spawning_system,
+            passive_mob_spawning_system,
+            peaceful_despawn_system,
+            restock_trades_system,
+        )
+            .run_if(simulation_ticking),
+    );
 
-    app.add_systems(Update, handle_player_inputs_system);
+    app.add_systems(
+        Update,
+        (
+            handle_player_inputs_system,
+            handle_spectate_requests_system,
+            spectators_follow_system,
+            update_spatial_index_system,
+            entity_pushback_system.run_if(simulation_ticking),
+            item_stacks_pickup_system.run_if(simulation_ticking),
+            handle_emotes_system,
+            handle_item_use_system,
+            handle_mob_interactions_system,
+            handle_trades_system,
+            mob_combat_system.run_if(simulation_ticking),
+            simulate_projectiles_system.run_if(simulation_ticking),
+            fire_damage_system.run_if(simulation_ticking),
+            status_effects_system.run_if(simulation_ticking),
+            apply_player_damage_system,
+        )
+            .chain(),
+    );
+
+    app.add_systems(
+        Update,
+        (
+            detect_obtained_items_system,
+            unlock_recipes_system,
+            send_recipe_unlocks_system,
+            hint_triggers_system,
+            send_game_modes_system,
+        )
+            .chain()
+            .after(apply_player_damage_system)
+            .after(handle_chat_commands_system),
+    );
+
+    app.add_systems(
+        Update,
+        autosave_system.after(world::save::save_world_system),
+    );
+    app.add_systems(First, (tick_start_system, tick_control_system));
+    app.add_systems(Update, broadcast_tick_rate_system);
+    app.add_systems(Last, tick_end_system);
 
     app.add_systems(Update, background_world_generation_system);
+    app.add_systems(Update, chunk_file_watch_system);
+    app.add_systems(Startup, pregen_from_config_system);
+    app.add_systems(Update, pregeneration_system);
+    app.add_systems(Update, build_spawn_platform_system);
 
-    app.add_systems(PostUpdate, update_server_time);
+    app.add_systems(Update, afk_detection_system);
+    app.add_systems(Update, journal_flush_system);
+    app.add_systems(Update, send_pack_chunks_system.after(server_update_system));
 
-    app.add_systems(FixedUpdate, mob_behavior_system);
+    app.add_systems(
+        Update,
+        super::map_viewer::broadcast_map_viewer_system
+            .run_if(resource_exists::<super::map_viewer::MapViewer>),
+    );
+    app.add_systems(
+        Update,
+        super::lan::lan_broadcast_system.run_if(resource_exists::<super::lan::LanBroadcaster>),
+    );
+
+    app.add_systems(PostUpdate, update_server_time.run_if(simulation_ticking));
+
+    app.add_systems(FixedUpdate, mob_behavior_system.run_if(simulation_ticking));
 }
 
 fn server_update_system(
     mut server_events: EventReader<ServerEvent>,
-    (mut server, mut chat_conversation, mut lobby): (
+    (mut server, mut chat_conversation, mut lobby, time): (
         ResMut<RenetServer>,
         ResMut<ChatConversation>,
         ResMut<ServerLobby>,
+        Res<ServerTime>,
     ),
-    (mut ev_chat, mut ev_app_exit, mut ev_save_request, mut ev_player_inputs): (
+    (
+        mut ev_chat,
+        mut ev_app_exit,
+        mut ev_save_request,
+        mut ev_player_inputs,
+        mut ev_command,
+        (mut ev_emote, mut ev_hint, mut ev_spectate),
+        (mut ev_attack_mob, mut ev_interact_mob, mut ev_trade),
+    ): (
         EventWriter<ChatMessageEvent>,
         EventWriter<AppExit>,
         EventWriter<SaveRequestEvent>,
         EventWriter<PlayerInputsEvent>,
+        EventWriter<ChatCommandEvent>,
+        (
+            EventWriter<PlayerEmoteRequestEvent>,
+            EventWriter<PlayerHintRequestEvent>,
+            EventWriter<PlayerSpectateRequestEvent>,
+        ),
+        (
+            EventWriter<MobAttackRequestEvent>,
+            EventWriter<MobInteractRequestEvent>,
+            EventWriter<TradeRequestEvent>,
+        ),
     ),
     config: Res<GameServerConfig>,
     mut world_map: ResMut<ServerWorldMap>,
-    time: Res<ServerTime>,
     game_folder_paths: Res<GameFolderPaths>,
+    (
+        activity,
+        tick_control,
+        packs,
+        mut pack_transfers,
+        world_id,
+        mut awaited_caches,
+        granted_names,
+    ): (
+        Res<PlayerActivity>,
+        Res<TickControl>,
+        Res<WorldPacks>,
+        ResMut<PackTransfers>,
+        Res<WorldCacheId>,
+        ResMut<AwaitedChunkCaches>,
+        Option<Res<GrantedNames>>,
+    ),
 ) {
     for event in server_events.read() {
         debug!("event received");
@@ -78,7 +277,21 @@ fn server_update_system(
             ServerEvent::ClientDisconnected { client_id, reason } => {
                 info!("Player {} disconnected: {}", client_id, reason);
                 lobby.players.remove(client_id);
-                cleanup_player_from_world(&mut world_map, client_id, &mut ev_save_request);
+                let Some(player) =
+                    cleanup_player_from_world(&mut world_map, client_id, &mut ev_save_request)
+                else {
+                    continue;
+                };
+
+                push_server_chat_message(
+                    &mut chat_conversation,
+                    format!("{} left the game", player.name),
+                );
+                ev_chat.write(ChatMessageEvent);
+                server.broadcast_game_message(ServerToClientMessage::PlayerLeft(PlayerLeftEvent {
+                    id: player.id,
+                    name: player.name,
+                }));
             }
         }
     }
@@ -86,9 +299,25 @@ fn server_update_system(
     for client_id in server.clients_id() {
         while let Some(Ok(message)) = server.receive_game_message(client_id) {
             match message {
-                ClientToServerMessage::AuthRegisterRequest(auth_req) => {
+                ClientToServerMessage::AuthRegisterRequest(mut auth_req) => {
                     info!("Auth request received {:?}", auth_req);
 
+                    // Ops are recognized by name, so clients coming through grants play under the name they were granted
+                    if let Some(granted_names) = &granted_names {
+                        let Some(granted_name) = granted_names.take(client_id) else {
+                            warn!("Player {} registered without a grant", client_id);
+                            server.disconnect(client_id);
+                            continue;
+                        };
+                        if granted_name != auth_req.username {
+                            warn!(
+                                "Player {} was granted the name {} but registered as {}",
+                                client_id, granted_name, auth_req.username
+                            );
+                            auth_req.username = granted_name;
+                        }
+                    }
+
                     if lobby.players.values().any(|v| v.name == auth_req.username) {
                         debug!("Username already in map: {}", &auth_req.username);
                         return;
@@ -114,6 +343,9 @@ fn server_update_system(
                                 is_flying: data.is_flying,
                                 position: data.position,
                                 camera_transform: data.camera_transform,
+                                unlocked_recipes: data.unlocked_recipes,
+                                game_mode: data.game_mode,
+                                seen_hints: data.seen_hints,
                                 name: auth_req.username.clone(),
                                 ..default()
                             },
@@ -137,7 +369,9 @@ fn server_update_system(
                                 position: player.position,
                                 camera_transform: player.camera_transform,
                                 is_flying: player.is_flying,
+                                ..default()
                             },
+                            afk: activity.is_afk(*id),
                         })
                         .collect();
 
@@ -145,34 +379,69 @@ fn server_update_system(
                     let auth_res = AuthRegisterResponse {
                         username: auth_req.username,
                         session_token: client_id,
-                        tick: time.0,
+                        tick: world_map.day_time,
                         timestamp_ms,
                         players: all_player_spawn_events,
+                        spawn_chunks: count_spawn_area_chunks(
+                            &world_map.chunks,
+                            world_map.players[&client_id].position,
+                        ),
+                        height_limits: world_map.chunks.gen_settings,
+                        packs: packs.infos(),
+                        world_id: world_id.0.clone(),
                     };
 
                     server.send_game_message(client_id, auth_res.into());
-
-                    // Send message to all players that a new one spawned
-                    for (id, player) in lobby.players.iter() {
-                        let spawn_message = PlayerSpawnEvent {
-                            id: *id,
-                            name: player.name.clone(),
-                            data: PlayerSave {
-                                position: registered_player.position,
-                                camera_transform: registered_player.camera_transform,
-                                is_flying: registered_player.is_flying,
-                            },
-                        };
-
-                        let spawn_message_wrapped =
-                            ServerToClientMessage::PlayerSpawn(spawn_message);
-
-                        info!("Sending spawn order {:?}", spawn_message_wrapped);
-                        server.broadcast_game_message(spawn_message_wrapped);
+                    awaited_caches.0.insert(client_id, time.0);
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::TeamsUpdate(world_map.teams.clone()),
+                    );
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::GameRules(GameRulesUpdate {
+                            rules: world_map.game_rules,
+                            day_time: world_map.day_time,
+                        }),
+                    );
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::Weather(world_map.weather.weather),
+                    );
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::TickRate(tick_control.state),
+                    );
+
+                    let joined = ServerToClientMessage::PlayerJoined(PlayerSpawnEvent {
+                        id: client_id,
+                        name: registered_player.name.clone(),
+                        data: PlayerSave {
+                            position: registered_player.position,
+                            camera_transform: registered_player.camera_transform,
+                            is_flying: registered_player.is_flying,
+                            ..default()
+                        },
+                        afk: false,
+                    });
+                    for id in lobby.players.keys().filter(|id| **id != client_id) {
+                        server.send_game_message(*id, joined.clone());
                     }
+
+                    push_server_chat_message(
+                        &mut chat_conversation,
+                        format!("{} joined the game", registered_player.name),
+                    );
+                    ev_chat.write(ChatMessageEvent);
                 }
                 ClientToServerMessage::ChatMessage(chat_msg) => {
                     info!("Chat message received: {:?}", &chat_msg);
+
+                    if let Some(command) = parse_chat_command(client_id, &chat_msg.content) {
+                        ev_command.write(command);
+                        continue;
+                    }
+
                     let current_timestamp: u64 = std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
                         .unwrap()
@@ -180,11 +449,20 @@ fn server_update_system(
 
                     let current_author = lobby.players.get(&client_id).unwrap();
 
-                    chat_conversation.messages.push(FullChatMessage {
-                        author: current_author.name.clone(),
+                    let message = team_chat_message(
+                        &world_map,
+                        client_id,
+                        &current_author.name,
+                        &chat_msg.content,
+                        current_timestamp,
+                    )
+                    .unwrap_or_else(|| FullChatMessage {
+                        author: chat_author_name(&world_map, client_id, &current_author.name),
                         content: chat_msg.content,
                         timestamp: current_timestamp,
+                        recipients: Vec::new(),
                     });
+                    chat_conversation.messages.push(message);
                     ev_chat.write(ChatMessageEvent);
                 }
                 ClientToServerMessage::Exit => {
@@ -222,14 +500,89 @@ fn server_update_system(
                         ev_save_request.write(SaveRequestEvent::Player(client_id));
                     }
                 }
+                ClientToServerMessage::Emote(emote) => {
+                    ev_emote.write(PlayerEmoteRequestEvent { client_id, emote });
+                }
+                ClientToServerMessage::Hint(hint) => {
+                    ev_hint.write(PlayerHintRequestEvent { client_id, hint });
+                }
+                ClientToServerMessage::Spectate(target) => {
+                    ev_spectate.write(PlayerSpectateRequestEvent { client_id, target });
+                }
+                ClientToServerMessage::AttackMob(mob_id) => {
+                    ev_attack_mob.write(MobAttackRequestEvent { client_id, mob_id });
+                }
+                ClientToServerMessage::TimeSync(request) => {
+                    server.send_game_message(
+                        client_id,
+                        ServerToClientMessage::TimeSync(TimeSyncResponse {
+                            client_send_ms: request.client_send_ms,
+                            server_time_ms: unix_time_ms(),
+                        }),
+                    );
+                }
+                ClientToServerMessage::InteractMob(request) => {
+                    ev_interact_mob.write(MobInteractRequestEvent {
+                        client_id,
+                        mob_id: request.mob_id,
+                        hotbar_slot: request.hotbar_slot,
+                    });
+                }
+                ClientToServerMessage::Trade(request) => {
+                    ev_trade.write(TradeRequestEvent {
+                        client_id,
+                        mob_id: request.mob_id,
+                        offer: request.offer,
+                    });
+                }
+                ClientToServerMessage::PackRequest(requests) => {
+                    let queue = pack_transfers.0.entry(client_id).or_default();
+                    for request in requests {
+                        if packs.chunk(&request.hash, request.offset).is_none() {
+                            warn!(
+                                "Player {} asked for unknown pack {} from {}",
+                                client_id, request.hash, request.offset
+                            );
+                            continue;
+                        }
+                        info!(
+                            "Sending pack {} to player {} from {}",
+                            request.hash, client_id, request.offset
+                        );
+                        queue.push_back(request);
+                    }
+                }
+                ClientToServerMessage::CachedChunks(batch) => {
+                    if batch.last {
+                        awaited_caches.0.remove(&client_id);
+                    }
+                    // Chunks the client already has as they are now count as sent
+                    let mut up_to_date = 0;
+                    for CachedChunk { pos, ts } in batch.chunks {
+                        let Some(chunk) = world_map.chunks.map.get_mut(&pos) else {
+                            continue;
+                        };
+                        if chunk.ts == ts && !chunk.sent_to_clients.contains(&client_id) {
+                            chunk.sent_to_clients.push(client_id);
+                            up_to_date += 1;
+                        }
+                    }
+                    info!(
+                        "Player {} has {} chunks cached and up to date",
+                        client_id, up_to_date
+                    );
+                }
             }
         }
     }
 }
 
-fn update_server_time(mut time: ResMut<ServerTime>) {
+fn update_server_time(mut time: ResMut<ServerTime>, mut world_map: ResMut<ServerWorldMap>) {
     if time.0.is_multiple_of(5 * TICKS_PER_SECOND) {
         debug!("Server time: {}", time.0);
     }
     time.0 += 1;
+    if world_map.game_rules.do_daylight_cycle {
+        world_map.day_time += 1;
+    }
 }
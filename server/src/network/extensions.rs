@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use bevy_renet::renet::{ClientId, RenetServer};
 use shared::{
+    codec::CodecError,
     get_customized_client_to_server_channels,
     messages::{ClientToServerMessage, ServerToClientMessage},
     utils::format_bytes,
@@ -13,12 +14,12 @@ pub trait SendGameMessageExtension {
     fn receive_game_message(
         &mut self,
         client_id: ClientId,
-    ) -> Option<Result<ClientToServerMessage, Box<bincode::ErrorKind>>>;
+    ) -> Option<Result<ClientToServerMessage, CodecError>>;
     fn receive_game_message_by_channel(
         &mut self,
         client_id: ClientId,
         channel: u8,
-    ) -> Option<Result<ClientToServerMessage, Box<bincode::ErrorKind>>>;
+    ) -> Option<Result<ClientToServerMessage, CodecError>>;
 }
 
 impl SendGameMessageExtension for RenetServer {
@@ -46,7 +47,7 @@ impl SendGameMessageExtension for RenetServer {
         &mut self,
         client_id: ClientId,
         channel: u8,
-    ) -> Option<Result<ClientToServerMessage, Box<bincode::ErrorKind>>> {
+    ) -> Option<Result<ClientToServerMessage, CodecError>> {
         let payload = self.receive_message(client_id, channel);
         if let Some(payload) = payload {
             // debug!("Received payload: {:?}", payload);
@@ -69,7 +70,7 @@ impl SendGameMessageExtension for RenetServer {
     fn receive_game_message(
         &mut self,
         client_id: ClientId,
-    ) -> Option<Result<ClientToServerMessage, Box<bincode::ErrorKind>>> {
+    ) -> Option<Result<ClientToServerMessage, CodecError>> {
         let channels = get_customized_client_to_server_channels();
         for channel in channels {
             let res = self.receive_game_message_by_channel(client_id, channel.channel_id);
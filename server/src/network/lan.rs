@@ -0,0 +1,69 @@
+use std::net::{Ipv4Addr, UdpSocket};
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    network::{LanBeacon, LAN_DISCOVERY_PORT},
+    world::ServerWorldMap,
+};
+
+use crate::init::MAX_CLIENTS;
+
+/// Seconds between two beacons
+const LAN_BEACON_INTERVAL: f32 = 1.5;
+
+/// Tells the clients of the local network that the server is there, so that it shows in their multiplayer menu
+#[derive(Resource)]
+pub struct LanBroadcaster {
+    socket: UdpSocket,
+    /// Game port, sent in the beacons
+    port: u16,
+    timer: Timer,
+}
+
+impl LanBroadcaster {
+    /// `None` if the socket could not be set up for broadcasting
+    pub fn start(port: u16) -> Option<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .and_then(|socket| socket.set_broadcast(true).map(|_| socket));
+        match socket {
+            Ok(socket) => {
+                info!("Announcing the server on the local network");
+                Some(Self {
+                    socket,
+                    port,
+                    timer: Timer::from_seconds(LAN_BEACON_INTERVAL, TimerMode::Repeating),
+                })
+            }
+            Err(e) => {
+                error!("Could not announce the server on the local network: {}", e);
+                None
+            }
+        }
+    }
+}
+
+pub fn lan_broadcast_system(
+    mut broadcaster: ResMut<LanBroadcaster>,
+    world_map: Res<ServerWorldMap>,
+    server: Res<RenetServer>,
+    time: Res<Time>,
+) {
+    if !broadcaster.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let beacon = LanBeacon {
+        protocol_id: shared::PROTOCOL_ID,
+        name: world_map.name.clone(),
+        port: broadcaster.port,
+        players: server.connected_clients() as u32,
+        max_players: MAX_CLIENTS as u32,
+    };
+    if let Err(e) = broadcaster.socket.send_to(
+        &beacon.to_bytes(),
+        (Ipv4Addr::BROADCAST, LAN_DISCOVERY_PORT),
+    ) {
+        debug!("Could not broadcast the LAN beacon: {}", e);
+    }
+}
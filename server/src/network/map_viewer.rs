@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use shared::{
+    world::{ServerChunkWorldMap, ServerWorldMap, WorldMap},
+    CHUNK_SIZE,
+};
+use tungstenite::Message;
+
+/// Time between two updates sent to the viewers
+const MAP_VIEWER_UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+/// Updates waiting to be sent to a viewer, one lagging behind gets the whole map once it caught up
+const VIEWER_QUEUE_SIZE: usize = 4;
+/// Time a browser has to send its request or take an update, so that idle connections do not pile up
+const VIEWER_IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+const TILE_MESSAGE: u8 = 1;
+const PLAYERS_MESSAGE: u8 = 2;
+
+const MAP_VIEWER_PAGE: &str = include_str!("map_viewer.html");
+
+type Batch = Arc<Vec<Vec<u8>>>;
+
+struct ViewerHandle {
+    sender: SyncSender<Batch>,
+    /// Set for new viewers and the ones which missed an update
+    needs_full_map: bool,
+}
+
+/// Read-only web map of the world: the page is served over HTTP, and the surface of loaded chunks
+/// and player positions are streamed to it over a WebSocket
+#[derive(Resource)]
+pub struct MapViewer {
+    /// Filled by the listener thread as browsers connect
+    joining: Arc<Mutex<Vec<SyncSender<Batch>>>>,
+    viewers: Vec<ViewerHandle>,
+    /// Hashes of the tiles last sent, to only send the ones which changed
+    sent_tiles: HashMap<IVec2, u64>,
+    timer: Timer,
+}
+
+impl MapViewer {
+    /// Starts listening on `address` and `port`, `None` if they could not be bound.
+    /// Each connection is handled on its own thread, so that a browser which stays silent holds up nobody else
+    pub fn start(address: IpAddr, port: u16) -> Option<Self> {
+        let addr = SocketAddr::new(address, port);
+        let listener = match TcpListener::bind(addr) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Could not start the map viewer on {}: {}", addr, e);
+                return None;
+            }
+        };
+        info!("Map viewer available on http://{}/", addr);
+
+        let joining = Arc::new(Mutex::new(Vec::new()));
+        let joining_clone = Arc::clone(&joining);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let joining = Arc::clone(&joining_clone);
+                thread::spawn(move || handle_connection(stream, &joining));
+            }
+        });
+
+        Some(Self {
+            joining,
+            viewers: Vec::new(),
+            sent_tiles: HashMap::new(),
+            timer: Timer::new(MAP_VIEWER_UPDATE_INTERVAL, TimerMode::Repeating),
+        })
+    }
+}
+
+fn is_websocket_upgrade(stream: &TcpStream) -> bool {
+    let mut head = [0u8; 2048];
+    let Ok(read) = stream.peek(&mut head) else {
+        return false;
+    };
+    String::from_utf8_lossy(&head[..read])
+        .to_ascii_lowercase()
+        .contains("upgrade: websocket")
+}
+
+fn handle_connection(mut stream: TcpStream, joining: &Arc<Mutex<Vec<SyncSender<Batch>>>>) {
+    if stream
+        .set_read_timeout(Some(VIEWER_IO_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(VIEWER_IO_TIMEOUT)))
+        .is_err()
+    {
+        return;
+    }
+
+    if !is_websocket_upgrade(&stream) {
+        // Anything else than the WebSocket gets the page
+        let mut request = [0u8; 2048];
+        let _ = stream.read(&mut request);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            MAP_VIEWER_PAGE.len(),
+            MAP_VIEWER_PAGE
+        );
+        let _ = stream.write_all(response.as_bytes());
+        return;
+    }
+
+    let (sender, receiver) = mpsc::sync_channel(VIEWER_QUEUE_SIZE);
+    joining.lock().unwrap().push(sender);
+    run_viewer(stream, receiver);
+}
+
+/// Sends the updates to one browser until it goes away
+fn run_viewer(stream: TcpStream, receiver: Receiver<Batch>) {
+    let peer = stream.peer_addr().ok();
+    let mut socket = match tungstenite::accept(stream) {
+        Ok(socket) => socket,
+        Err(e) => {
+            debug!("Map viewer handshake failed: {}", e);
+            return;
+        }
+    };
+    info!("Map viewer connected from {:?}", peer);
+
+    for batch in receiver {
+        for message in batch.iter() {
+            if socket.send(Message::binary(message.clone())).is_err() {
+                info!("Map viewer disconnected from {:?}", peer);
+                return;
+            }
+        }
+    }
+}
+
+/// Colors of the topmost block of each column of the chunk column, row by row
+fn surface_tile(chunks: &ServerChunkWorldMap, column: IVec2) -> Vec<u8> {
+    let mut tile = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE * 3) as usize);
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let block_x = column.x * CHUNK_SIZE + x;
+            let block_z = column.y * CHUNK_SIZE + z;
+            let color = chunks
+                .get_surface_height(block_x, block_z)
+                .and_then(|y| chunks.get_block_by_coordinates(&IVec3::new(block_x, y, block_z)))
+                .map_or([0, 0, 0], |block| block.id.map_color());
+            tile.extend_from_slice(&color);
+        }
+    }
+    tile
+}
+
+fn tile_message(column: IVec2, tile: &[u8]) -> Vec<u8> {
+    let mut message = vec![TILE_MESSAGE];
+    message.extend_from_slice(&column.x.to_le_bytes());
+    message.extend_from_slice(&column.y.to_le_bytes());
+    message.extend_from_slice(tile);
+    message
+}
+
+fn players_message(world_map: &ServerWorldMap) -> Vec<u8> {
+    let mut message = vec![PLAYERS_MESSAGE];
+    let count = world_map.players.len().min(u16::MAX as usize) as u16;
+    message.extend_from_slice(&count.to_le_bytes());
+    for player in world_map.players.values().take(count as usize) {
+        let name = &player.name.as_bytes()[..player.name.len().min(u8::MAX as usize)];
+        message.extend_from_slice(&player.position.x.to_le_bytes());
+        message.extend_from_slice(&player.position.z.to_le_bytes());
+        message.push(name.len() as u8);
+        message.extend_from_slice(name);
+    }
+    message
+}
+
+/// Sends the tiles which changed and the player positions to the connected browsers
+pub fn broadcast_map_viewer_system(
+    mut viewer: ResMut<MapViewer>,
+    world_map: Res<ServerWorldMap>,
+    time: Res<Time>,
+) {
+    let viewer = viewer.as_mut();
+    let joining: Vec<_> = viewer.joining.lock().unwrap().drain(..).collect();
+
+    if !viewer.timer.tick(time.delta()).just_finished() && joining.is_empty() {
+        return;
+    }
+    if viewer.viewers.is_empty() && joining.is_empty() {
+        return;
+    }
+
+    let mut all_tiles = Vec::with_capacity(world_map.chunks.heightmap.len() + 1);
+    let mut changed_tiles = Vec::new();
+    for &column_pos in world_map.chunks.heightmap.keys() {
+        let tile = surface_tile(&world_map.chunks, column_pos);
+
+        let mut hasher = DefaultHasher::new();
+        tile.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let message = tile_message(column_pos, &tile);
+        if viewer.sent_tiles.insert(column_pos, hash) != Some(hash) {
+            changed_tiles.push(message.clone());
+        }
+        all_tiles.push(message);
+    }
+
+    let players = players_message(&world_map);
+    all_tiles.push(players.clone());
+    changed_tiles.push(players);
+
+    let full: Batch = Arc::new(all_tiles);
+    let changes: Batch = Arc::new(changed_tiles);
+
+    viewer
+        .viewers
+        .extend(joining.into_iter().map(|sender| ViewerHandle {
+            sender,
+            needs_full_map: true,
+        }));
+
+    // Viewers which are gone are dropped, the ones too slow to keep up will get the whole map later
+    viewer.viewers.retain_mut(|handle| {
+        let batch = if handle.needs_full_map {
+            &full
+        } else {
+            &changes
+        };
+        match handle.sender.try_send(Arc::clone(batch)) {
+            Ok(()) => {
+                handle.needs_full_map = false;
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                handle.needs_full_map = true;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    });
+}
@@ -1,4 +1,9 @@
 pub mod broadcast_chat;
 pub mod cleanup;
+pub mod commands;
 pub mod dispatcher;
 pub mod extensions;
+pub mod lan;
+pub mod map_viewer;
+pub mod teams;
+pub mod tokens;
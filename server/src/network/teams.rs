@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{FullChatMessage, PlayerId, ServerToClientMessage},
+    players::{TeamColor, MAX_TEAM_NAME_LENGTH},
+    world::ServerWorldMap,
+};
+
+use super::{broadcast_chat::SERVER_CHAT_AUTHOR, extensions::SendGameMessageExtension};
+
+/// Sent whenever the teams are modified, so that clients get the new team list
+#[derive(Event)]
+pub struct TeamsChangedEvent;
+
+/// Chat messages starting with it are only sent to the team of their author
+pub const TEAM_CHAT_PREFIX: char = '#';
+
+const TEAM_USAGE: &str =
+    "Usage: /team create <name> [color] | invite <player> | join <name> | leave | color <color> | friendlyfire <on|off> | shareclaims <on|off> | list";
+
+/// Handles `/team <subcommand>`, returns the reply sent in the chat
+pub fn team_command(
+    world_map: &mut ServerWorldMap,
+    client_id: PlayerId,
+    args: &[String],
+    ev_teams: &mut EventWriter<TeamsChangedEvent>,
+) -> String {
+    let Some(player_name) = world_map.players.get(&client_id).map(|p| p.name.clone()) else {
+        return "You must be in the world to manage teams".to_string();
+    };
+    let teams = &mut world_map.teams;
+    let arg = |i: usize| args.get(i).map(String::as_str);
+
+    match (arg(0), arg(1)) {
+        (Some("create"), Some(name)) => {
+            if !is_valid_team_name(name) {
+                return format!(
+                    "Team names must be 1 to {} letters, digits, '-' or '_'",
+                    MAX_TEAM_NAME_LENGTH
+                );
+            }
+            let color = match arg(2) {
+                Some(color) => match TeamColor::from_name(color) {
+                    Some(color) => color,
+                    None => return unknown_color_reply(color),
+                },
+                None => TeamColor::default(),
+            };
+            if !teams.create(name, color) {
+                return format!("Team {} already exists", name);
+            }
+            teams.join(name, client_id);
+            ev_teams.write(TeamsChangedEvent);
+            format!("{} created team {}", player_name, name)
+        }
+        (Some("invite"), Some(invited_name)) => {
+            let Some(invited_id) = world_map
+                .players
+                .values()
+                .find(|player| player.name == invited_name)
+                .map(|player| player.id)
+            else {
+                return format!("Unknown player {}", invited_name);
+            };
+            let Some(team_name) = teams
+                .get_player_team(client_id)
+                .map(|team| team.name.clone())
+            else {
+                return "You are not in a team".to_string();
+            };
+            if !teams.invite(&team_name, invited_id) {
+                return format!("{} already is in team {}", invited_name, team_name);
+            }
+            ev_teams.write(TeamsChangedEvent);
+            format!(
+                "{} invited {} to team {}, /team join {} to accept",
+                player_name, invited_name, team_name, team_name
+            )
+        }
+        (Some("join"), Some(name)) => {
+            if teams.get(name).is_none() {
+                return format!("Team {} does not exist", name);
+            }
+            if !teams.accept(name, client_id) {
+                return format!("Team {} did not invite you", name);
+            }
+            ev_teams.write(TeamsChangedEvent);
+            format!(
+                "{} joined team {}",
+                player_name,
+                teams.get(name).unwrap().name
+            )
+        }
+        (Some("leave"), None) => match teams.leave(client_id) {
+            Some(team_name) => {
+                ev_teams.write(TeamsChangedEvent);
+                format!("{} left team {}", player_name, team_name)
+            }
+            None => "You are not in a team".to_string(),
+        },
+        (Some("color"), Some(color)) => {
+            let Some(color) = TeamColor::from_name(color) else {
+                return unknown_color_reply(color);
+            };
+            let Some(team) = teams.get_player_team_mut(client_id) else {
+                return "You are not in a team".to_string();
+            };
+            team.color = color;
+            ev_teams.write(TeamsChangedEvent);
+            format!("Team {} is now {}", team.name, color.name())
+        }
+        (Some(setting @ ("friendlyfire" | "shareclaims")), Some(value)) => {
+            let enabled = match value {
+                "on" => true,
+                "off" => false,
+                _ => return TEAM_USAGE.to_string(),
+            };
+            let Some(team) = teams.get_player_team_mut(client_id) else {
+                return "You are not in a team".to_string();
+            };
+            if setting == "friendlyfire" {
+                team.friendly_fire = enabled;
+            } else {
+                team.share_claims = enabled;
+            }
+            ev_teams.write(TeamsChangedEvent);
+            format!("Team {}: {} {}", team.name, setting, value)
+        }
+        (Some("list"), None) => {
+            if teams.teams.is_empty() {
+                return "There are no teams".to_string();
+            }
+            let mut names: Vec<String> = teams
+                .teams
+                .values()
+                .map(|team| format!("{} ({})", team.name, team.members.len()))
+                .collect();
+            names.sort();
+            format!("Teams: {}", names.join(", "))
+        }
+        _ => TEAM_USAGE.to_string(),
+    }
+}
+
+fn is_valid_team_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_TEAM_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn unknown_color_reply(color: &str) -> String {
+    let colors: Vec<&str> = TeamColor::ALL.iter().map(TeamColor::name).collect();
+    format!(
+        "Unknown color {}, available colors: {}",
+        color,
+        colors.join(", ")
+    )
+}
+
+/// Message starting with `TEAM_CHAT_PREFIX`, only shown to the teammates of its author.
+/// Returns `None` for the messages everyone sees.
+pub fn team_chat_message(
+    world_map: &ServerWorldMap,
+    client_id: PlayerId,
+    name: &str,
+    content: &str,
+    timestamp: u64,
+) -> Option<FullChatMessage> {
+    let content = content.strip_prefix(TEAM_CHAT_PREFIX)?.trim();
+
+    Some(match world_map.teams.get_player_team(client_id) {
+        Some(team) => FullChatMessage {
+            author: format!("[{}] {} (team)", team.name, name),
+            content: content.to_string(),
+            timestamp,
+            recipients: team.members.clone(),
+        },
+        // Not sent to everyone by mistake
+        None => FullChatMessage {
+            author: SERVER_CHAT_AUTHOR.into(),
+            content: format!(
+                "You are not in a team, remove the {} to talk to everyone",
+                TEAM_CHAT_PREFIX
+            ),
+            timestamp,
+            recipients: vec![client_id],
+        },
+    })
+}
+
+/// Author shown in the chat for a player, prefixed by their team
+pub fn chat_author_name(world_map: &ServerWorldMap, client_id: PlayerId, name: &str) -> String {
+    match world_map.teams.get_player_team(client_id) {
+        Some(team) => format!("[{}] {}", team.name, name),
+        None => name.to_string(),
+    }
+}
+
+pub fn broadcast_teams_system(
+    mut events: EventReader<TeamsChangedEvent>,
+    mut server: ResMut<RenetServer>,
+    world_map: Res<ServerWorldMap>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    server.broadcast_game_message(ServerToClientMessage::TeamsUpdate(world_map.teams.clone()));
+}
@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use bevy::prelude::*;
+use bevy_renet::netcode::{ConnectToken, NETCODE_KEY_BYTES};
+use shared::network::tokens::{answer_grant_request, grant_server_config, GrantServerConfig};
+use shared::network::{ConnectGrant, TlsIdentity};
+use shared::GameFolderPaths;
+
+/// Seconds a client has to use its connect token
+const TOKEN_EXPIRE_SECS: u64 = 30;
+/// Seconds without packets after which an encrypted connection is dropped
+const CONNECTION_TIMEOUT_SECS: i32 = 15;
+/// Connect grant requests answered at the same time, the others are turned down until some are done
+const MAX_PENDING_GRANTS: usize = 64;
+/// File of the game folder holding the certificate of the server, along with its key
+pub const SERVER_IDENTITY_FILE: &str = "server_identity.ron";
+/// Time a client has to register once it got its grant, after which the name it was granted is forgotten
+const GRANTED_NAME_LIFETIME: Duration = Duration::from_secs(60);
+/// File of the world save directory holding the player id each name is pinned to
+pub const NAME_PINS_FILE: &str = "name_pins.ron";
+
+/// Name each player id was granted under, which is the only name the client can register as.
+/// Filled by the threads handing out grants, emptied as the clients register
+#[derive(Resource, Clone, Default)]
+pub struct GrantedNames(Arc<Mutex<HashMap<u64, (String, Instant)>>>);
+
+impl GrantedNames {
+    fn insert(&self, client_id: u64, name: &str) {
+        let mut names = self.0.lock().unwrap();
+        names.retain(|_, (_, granted_at)| granted_at.elapsed() < GRANTED_NAME_LIFETIME);
+        names.insert(client_id, (name.to_string(), Instant::now()));
+    }
+
+    /// Name the client was granted, if it got a grant lately. Each grant is only used once
+    pub fn take(&self, client_id: u64) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(&client_id)
+            .filter(|(_, granted_at)| granted_at.elapsed() < GRANTED_NAME_LIFETIME)
+            .map(|(name, _)| name)
+    }
+}
+
+/// Player id each name was first granted to. The player id comes from the certificate of the client,
+/// so a name belongs to the first certificate which played under it: ops being recognized by name,
+/// nobody else can get a grant for the name of an op. Written to the world save directory as names are pinned
+#[derive(Clone)]
+pub struct NamePins {
+    pins: Arc<Mutex<BTreeMap<String, u64>>>,
+    path: PathBuf,
+}
+
+impl NamePins {
+    /// Reads the names pinned in the world saved in `world_dir`, none if the world is new
+    pub fn load(world_dir: &Path) -> Result<Self, String> {
+        let path = world_dir.join(NAME_PINS_FILE);
+        let pins = match fs::read_to_string(&path) {
+            Ok(contents) => ron::from_str(&contents)
+                .map_err(|e| format!("Invalid {}: {}", path.display(), e))?,
+            Err(_) => BTreeMap::new(),
+        };
+        Ok(Self {
+            pins: Arc::new(Mutex::new(pins)),
+            path,
+        })
+    }
+
+    /// Pins `name` to `client_id` the first time it is granted, fails if it is pinned to another player id
+    fn pin(&self, name: &str, client_id: u64) -> Result<(), String> {
+        let mut pins = self.pins.lock().unwrap();
+        match pins.get(name) {
+            Some(pinned) if *pinned == client_id => return Ok(()),
+            Some(_) => return Err(format!("the name {name} belongs to another certificate")),
+            None => {}
+        }
+        pins.insert(name.to_string(), client_id);
+
+        // Kept in memory even if it cannot be written, the name stays protected until the server stops
+        let written = ron::to_string(&*pins)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = self.path.parent() {
+                    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                fs::write(&self.path, contents).map_err(|e| e.to_string())
+            });
+        if let Err(e) = written {
+            error!("Could not write {}: {}", self.path.display(), e);
+        }
+        Ok(())
+    }
+}
+
+/// Reads the private key of the server settings, written as 64 hexadecimal characters
+pub fn parse_private_key(text: &str) -> Result<[u8; NETCODE_KEY_BYTES], String> {
+    let text = text.trim();
+    if text.len() != NETCODE_KEY_BYTES * 2 {
+        return Err(format!(
+            "expected {} hexadecimal characters, got {}",
+            NETCODE_KEY_BYTES * 2,
+            text.len()
+        ));
+    }
+
+    let mut key = [0u8; NETCODE_KEY_BYTES];
+    for (i, byte) in key.iter_mut().enumerate() {
+        let digits = text.get(i * 2..i * 2 + 2).unwrap_or_default();
+        *byte = u8::from_str_radix(digits, 16)
+            .map_err(|_| format!("{:?} is not a hexadecimal byte", digits))?;
+    }
+    Ok(key)
+}
+
+/// Reads the certificate the clients recognize the server by, creating it the first time
+pub fn load_server_identity(game_folder_paths: &GameFolderPaths) -> Result<TlsIdentity, String> {
+    let path = game_folder_paths
+        .game_folder_path
+        .join(SERVER_IDENTITY_FILE);
+    if let Ok(contents) = fs::read_to_string(&path) {
+        return ron::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e));
+    }
+
+    let identity = TlsIdentity::generate()?;
+    let contents = ron::to_string(&identity).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    info!(
+        "Created the certificate of the server in {}, its fingerprint is {}",
+        path.display(),
+        identity.fingerprint()
+    );
+    Ok(identity)
+}
+
+/// Hands out connect grants over TLS on each address the game is bound to, see `shared::network::request_connect_grant`.
+/// With a private key, the tokens send the clients to `public_addresses`, which must be the ones they can reach the server on.
+/// Each request is answered on its own thread, so slow clients do not hold up the others.
+/// The names the grants are for are recorded in `granted_names`, names pinned to another certificate are refused
+pub fn serve_connect_tokens(
+    game_addresses: &[SocketAddr],
+    private_key: Option<[u8; NETCODE_KEY_BYTES]>,
+    public_addresses: Vec<SocketAddr>,
+    identity: &TlsIdentity,
+    granted_names: &GrantedNames,
+    name_pins: &NamePins,
+) -> Result<(), String> {
+    let config = grant_server_config(identity)?;
+    let pending = Arc::new(AtomicUsize::new(0));
+    for addr in game_addresses {
+        let listener = match TcpListener::bind(addr) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!(
+                    "Could not hand out connect grants on {}, no client will be able to join there: {}",
+                    addr, e
+                );
+                continue;
+            }
+        };
+        info!(
+            "Handing out connect grants on {}, certificate fingerprint {}",
+            addr,
+            identity.fingerprint()
+        );
+
+        let config = config.clone();
+        let public_addresses = public_addresses.clone();
+        let pending = pending.clone();
+        let granted_names = granted_names.clone();
+        let name_pins = name_pins.clone();
+        thread::spawn(move || {
+            serve_listener(
+                listener,
+                config,
+                private_key,
+                public_addresses,
+                pending,
+                granted_names,
+                name_pins,
+            )
+        });
+    }
+    Ok(())
+}
+
+fn serve_listener(
+    listener: TcpListener,
+    config: Arc<GrantServerConfig>,
+    private_key: Option<[u8; NETCODE_KEY_BYTES]>,
+    public_addresses: Vec<SocketAddr>,
+    pending: Arc<AtomicUsize>,
+    granted_names: GrantedNames,
+    name_pins: NamePins,
+) {
+    for stream in listener.incoming().flatten() {
+        if pending.fetch_add(1, Ordering::SeqCst) >= MAX_PENDING_GRANTS {
+            pending.fetch_sub(1, Ordering::SeqCst);
+            debug!("Too many connect grant requests at once, one is turned down");
+            continue;
+        }
+
+        let config = config.clone();
+        let public_addresses = public_addresses.clone();
+        let pending = pending.clone();
+        let granted_names = granted_names.clone();
+        let name_pins = name_pins.clone();
+        thread::spawn(move || {
+            let peer = stream.peer_addr();
+            let answer = answer_grant_request(config, stream, |client_id, name| {
+                name_pins.pin(name, client_id)?;
+                let grant = make_grant(client_id, private_key.as_ref(), &public_addresses)?;
+                granted_names.insert(client_id, name);
+                Ok(grant)
+            });
+            if let Err(e) = answer {
+                debug!("Could not hand out a connect grant to {:?}: {}", peer, e);
+            }
+            pending.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+fn make_grant(
+    client_id: u64,
+    private_key: Option<&[u8; NETCODE_KEY_BYTES]>,
+    public_addresses: &[SocketAddr],
+) -> Result<ConnectGrant, String> {
+    let Some(private_key) = private_key else {
+        return Ok(ConnectGrant::Unsecure { client_id });
+    };
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    ConnectToken::generate(
+        current_time,
+        shared::PROTOCOL_ID,
+        TOKEN_EXPIRE_SECS,
+        client_id,
+        CONNECTION_TIMEOUT_SECS,
+        public_addresses.to_vec(),
+        None,
+        private_key,
+    )
+    .map(|token| ConnectGrant::Secure(Box::new(token)))
+    .map_err(|e| format!("Could not generate a connect token: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::network::request_connect_grant;
+
+    /// Grant service for a world saved in `world_dir`, on a local port
+    fn serve(world_dir: &Path) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let config = grant_server_config(&TlsIdentity::generate().unwrap()).unwrap();
+        let name_pins = NamePins::load(world_dir).unwrap();
+        thread::spawn(move || {
+            serve_listener(
+                listener,
+                config,
+                None,
+                vec![addr],
+                Arc::new(AtomicUsize::new(0)),
+                GrantedNames::default(),
+                name_pins,
+            )
+        });
+        addr
+    }
+
+    #[test]
+    fn name_of_an_op_is_refused_to_another_certificate() {
+        let world_dir =
+            std::env::temp_dir().join(format!("rustcraft-name-pins-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&world_dir);
+        let op = TlsIdentity::generate().unwrap();
+        let impostor = TlsIdentity::generate().unwrap();
+
+        let addr = serve(&world_dir);
+        assert!(request_connect_grant(addr, "Admin", &op, None).is_ok());
+        assert!(request_connect_grant(addr, "Admin", &impostor, None).is_err());
+        assert!(request_connect_grant(addr, "Admin", &op, None).is_ok());
+        assert!(request_connect_grant(addr, "Guest", &impostor, None).is_ok());
+
+        // The name stays pinned once the server restarts
+        let addr = serve(&world_dir);
+        assert!(request_connect_grant(addr, "Admin", &impostor, None).is_err());
+        assert!(request_connect_grant(addr, "Admin", &op, None).is_ok());
+
+        fs::remove_dir_all(&world_dir).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use shared::{logging::LogSettings, GameFolderPaths};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use crate::mob::difficulty::Difficulty;
+
+pub const SERVER_SETTINGS_FILE: &str = "server_settings.ron";
+
+/// Settings of the server administrator, shared by every world of the game folder
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// Distance in blocks around the world spawn where only ops may edit blocks, 0 disables it
+    pub spawn_protection_radius: u32,
+    /// Names of the players allowed to bypass protections and run admin commands.
+    /// Only taken into account with a private key, each name belonging to the first client which played under it
+    pub ops: Vec<String>,
+    /// Port of the read-only web map of the world, it is not served when unset
+    pub map_viewer_port: Option<u16>,
+    /// Address the map viewer listens on. Only this machine can reach the default one, `0.0.0.0` opens it to the network
+    pub map_viewer_address: IpAddr,
+    pub log: LogSettings,
+    /// Log files kept in the `logs` folder, the oldest one is removed when a new one starts, 0 disables them
+    pub log_files_kept: u32,
+    /// Players sending no input for that long are shown as AFK, 0 disables it
+    pub afk_after_secs: u64,
+    /// AFK players idle for that long are kicked, only when the server is full. Unset never kicks
+    pub afk_kick_after_secs: Option<u64>,
+    /// Chunk columns this close to spawn, in chunks, are generated and kept even when no player is around,
+    /// so that farms and contraptions at spawn keep working. 0 disables them
+    pub spawn_chunks_radius: u32,
+    /// Columns of chunks sent to each player around them, in chunks. The 3x3 columns around a joining player come first,
+    /// they enter the world once they have them, and the others follow ring by ring
+    pub stream_radius: u32,
+    /// Reloads the chunk files changed by external map editors while the server runs, and sends them to the players.
+    /// Without it, `/reloadchunks` does it on demand
+    pub watch_chunk_files: bool,
+    /// Minutes between two autosaves of the world and the players, 0 disables them
+    pub autosave_interval_mins: u64,
+    /// Port of the Prometheus metrics endpoint, with tick times and save durations. It is not served when unset
+    pub metrics_port: Option<u16>,
+    /// Address the metrics endpoint listens on. Only this machine can reach the default one, `0.0.0.0` opens it to the network
+    pub metrics_address: IpAddr,
+    /// Scales the damage and the spawning of hostile mobs, peaceful has none. Changed in game with `/difficulty`
+    pub difficulty: Difficulty,
+    /// Key encrypting the connections and signing the connect tokens, as 64 hexadecimal characters.
+    /// Unset, connections are not encrypted, which the clients are told when they ask to connect,
+    /// and clients can pass for any player so there are no ops
+    pub private_key: Option<String>,
+    /// Addresses the players reach the server on, such as its public IP and the game port, required by the private key
+    pub public_addresses: Vec<SocketAddr>,
+    /// Announces the server to the players of the local network, who see it in their multiplayer menu
+    pub lan_broadcast: bool,
+    /// Addresses the game listens on, with the port given on the command line. IPv6 ones such as `::` work too,
+    /// and listing several binds each of them. Empty listens on every IPv4 interface
+    pub bind_addresses: Vec<IpAddr>,
+    /// Days the entries of the event journal of the world are kept, read with `/history`. 0 keeps them forever
+    pub journal_retention_days: u32,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            spawn_protection_radius: 16,
+            ops: Vec::new(),
+            map_viewer_port: None,
+            map_viewer_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            log: LogSettings::default(),
+            log_files_kept: 5,
+            afk_after_secs: 300,
+            afk_kick_after_secs: None,
+            spawn_chunks_radius: 2,
+            stream_radius: 4,
+            watch_chunk_files: false,
+            autosave_interval_mins: 5,
+            metrics_port: None,
+            metrics_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            difficulty: Difficulty::Normal,
+            private_key: None,
+            public_addresses: Vec::new(),
+            lan_broadcast: false,
+            bind_addresses: Vec::new(),
+            journal_retention_days: 30,
+        }
+    }
+}
+
+impl ServerSettings {
+    pub fn is_op(&self, player_name: &str) -> bool {
+        self.ops.iter().any(|op| op == player_name)
+    }
+}
+
+/// Reads the settings file, writing the default one if it does not exist yet.
+/// It runs before the logger is set up, so problems are reported on stderr
+pub fn load_server_settings(game_folder_paths: &GameFolderPaths) -> ServerSettings {
+    let path = game_folder_paths
+        .game_folder_path
+        .join(SERVER_SETTINGS_FILE);
+
+    if let Ok(contents) = fs::read_to_string(&path) {
+        match ron::from_str::<ServerSettings>(&contents) {
+            Ok(settings) => return settings,
+            Err(err) => {
+                eprintln!(
+                    "Invalid server settings file {}, using defaults : {}",
+                    path.display(),
+                    err
+                );
+                return ServerSettings::default();
+            }
+        }
+    }
+
+    let settings = ServerSettings::default();
+    save_server_settings(game_folder_paths, &settings);
+    settings
+}
+
+/// Writes the settings file, for the defaults on first start and for the settings changed in game
+pub fn save_server_settings(game_folder_paths: &GameFolderPaths, settings: &ServerSettings) {
+    let path = game_folder_paths
+        .game_folder_path
+        .join(SERVER_SETTINGS_FILE);
+
+    match ron::ser::to_string_pretty(settings, PrettyConfig::default()) {
+        Ok(contents) => {
+            if let Err(err) = fs::write(&path, contents) {
+                eprintln!(
+                    "Could not write server settings to {} : {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+        Err(err) => eprintln!("Could not serialize server settings : {}", err),
+    }
+}
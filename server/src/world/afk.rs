@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{NetworkAction, PlayerAfkEvent, PlayerFrameInput, PlayerId, ServerToClientMessage},
+    players::Player,
+    world::ServerWorldMap,
+};
+
+use crate::{
+    init::MAX_CLIENTS, network::extensions::SendGameMessageExtension, settings::ServerSettings,
+};
+
+/// When each player last did something, and who is away
+#[derive(Resource, Default, Debug)]
+pub struct PlayerActivity {
+    last_active: HashMap<PlayerId, Duration>,
+    afk: HashSet<PlayerId>,
+}
+
+impl PlayerActivity {
+    pub fn is_afk(&self, player_id: PlayerId) -> bool {
+        self.afk.contains(&player_id)
+    }
+
+    /// Returns true if the player was AFK until now
+    pub fn mark_active(&mut self, player_id: PlayerId, now: Duration) -> bool {
+        self.last_active.insert(player_id, now);
+        self.afk.remove(&player_id)
+    }
+}
+
+/// Whether an input shows the player is at their keyboard, the client sends inputs every frame even when idle
+fn is_meaningful_input(input: &PlayerFrameInput, player: &Player) -> bool {
+    // Auto-jump is a setting held every frame, not something the player does
+    input
+        .inputs
+        .iter()
+        .any(|action| *action != NetworkAction::AutoJump)
+        || input.camera.rotation != player.camera_transform.rotation
+}
+
+fn broadcast_afk(server: &mut RenetServer, player_id: PlayerId, afk: bool) {
+    server.broadcast_game_message(ServerToClientMessage::PlayerAfk(PlayerAfkEvent {
+        player_id,
+        afk,
+    }));
+}
+
+/// Marks the players idle for too long as AFK, and kicks the ones idle even longer when the server is full
+pub fn afk_detection_system(
+    mut activity: ResMut<PlayerActivity>,
+    world_map: Res<ServerWorldMap>,
+    settings: Res<ServerSettings>,
+    mut server: ResMut<RenetServer>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    let activity = activity.as_mut();
+    activity
+        .last_active
+        .retain(|id, _| world_map.players.contains_key(id));
+    activity.afk.retain(|id| world_map.players.contains_key(id));
+
+    let server_full = server.clients_id().len() >= MAX_CLIENTS;
+    let mut to_kick = Vec::new();
+
+    for player in world_map.players.values() {
+        let last_active = *activity.last_active.entry(player.id).or_insert(now);
+        let idle = now.saturating_sub(last_active);
+
+        if settings.afk_after_secs > 0
+            && idle >= Duration::from_secs(settings.afk_after_secs)
+            && activity.afk.insert(player.id)
+        {
+            info!("Player {} is AFK", player.name);
+            broadcast_afk(&mut server, player.id, true);
+        }
+
+        if server_full
+            && activity.afk.contains(&player.id)
+            && settings
+                .afk_kick_after_secs
+                .is_some_and(|secs| idle >= Duration::from_secs(secs))
+        {
+            to_kick.push(player);
+        }
+    }
+
+    for player in to_kick {
+        info!("Kicking {}, AFK on a full server", player.name);
+        server.disconnect(player.id);
+    }
+}
+
+/// Called with every input a player sends, clears their AFK status when they come back
+pub fn record_player_activity(
+    activity: &mut PlayerActivity,
+    server: &mut RenetServer,
+    input: &PlayerFrameInput,
+    player: &Player,
+    now: Duration,
+) {
+    if !is_meaningful_input(input, player) {
+        return;
+    }
+    if activity.mark_active(player.id, now) {
+        info!("Player {} is back", player.name);
+        broadcast_afk(server, player.id, false);
+    }
+}
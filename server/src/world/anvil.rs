@@ -0,0 +1,480 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::Path;
+
+use bevy::prelude::*;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use shared::world::{
+    BlockData, BlockDirection, BlockId, ServerChunk, WorldGenSettings, WorldSeed, MAX_LIGHT_LEVEL,
+};
+
+use super::{
+    data::CHUNKS_SAVE_DIR,
+    save::{chunk_file_name, save_chunk_data, save_world_data, WorldData},
+};
+
+// Minecraft Java Edition worlds store their chunks in region files, `region/r.<x>.<z>.mca`, each holding 32x32 columns.
+// A column is an NBT compound with sections of 16x16x16 blocks, their block states are indices in a palette
+// packed in longs. https://minecraft.wiki/w/Region_file_format and https://minecraft.wiki/w/Chunk_format
+
+const SECTOR_BYTES: usize = 4096;
+const REGION_COLUMNS: usize = 32;
+/// Before this version (20w17a), palette indices could span two longs
+const NON_SPANNING_DATA_VERSION: i32 = 2529;
+/// Nested lists and compounds deeper than this are refused, no chunk comes close
+const MAX_NBT_DEPTH: usize = 512;
+
+/// What an import brought into the world
+#[derive(Default)]
+pub struct AnvilImport {
+    pub columns: usize,
+    pub chunks: usize,
+    /// Minecraft blocks without an equivalent, imported as debug blocks, with the number of sections they were in
+    pub unknown_blocks: BTreeMap<String, usize>,
+}
+
+/// Converts the region files of a Minecraft world into the chunk files of a new world in `world_dir`.
+/// The heights of the Minecraft world since 1.18 are kept, older worlds fit in them.
+/// An existing world is only replaced with `force`
+#[allow(dead_code)]
+pub fn import_anvil_world(
+    region_dir: &Path,
+    world_dir: &Path,
+    world_name: &str,
+    force: bool,
+) -> Result<AnvilImport, Box<dyn std::error::Error>> {
+    if world_dir.join("world.ron").exists() {
+        if !force {
+            return Err(
+                format!("world {world_name} already exists, use --force to replace it").into(),
+            );
+        }
+        std::fs::remove_dir_all(world_dir)?;
+    }
+
+    let chunks_dir = world_dir.join(CHUNKS_SAVE_DIR);
+    std::fs::create_dir_all(&chunks_dir)?;
+
+    let limits = WorldGenSettings::default();
+    let mut import = AnvilImport::default();
+
+    for entry in std::fs::read_dir(region_dir)?.flatten() {
+        let path = entry.path();
+        let Some(region) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_region_file_name)
+        else {
+            continue;
+        };
+
+        let data = std::fs::read(&path)?;
+        for (index, column) in region_columns(&data) {
+            let column_pos = region * REGION_COLUMNS as i32
+                + IVec2::new(
+                    (index % REGION_COLUMNS) as i32,
+                    (index / REGION_COLUMNS) as i32,
+                );
+            let root = match column.and_then(|bytes| read_nbt(&bytes)) {
+                Ok(root) => root,
+                Err(e) => {
+                    warn!(
+                        "Skipping column {} of {}: {}",
+                        column_pos,
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            for (chunk_pos, chunk) in convert_column(&root, column_pos, limits, &mut import) {
+                save_chunk_data(&chunk, &chunks_dir.join(chunk_file_name(&chunk_pos)))?;
+                import.chunks += 1;
+            }
+            import.columns += 1;
+        }
+    }
+
+    if import.columns == 0 {
+        return Err(format!("no region file found in {}", region_dir.display()).into());
+    }
+
+    let world_data = WorldData {
+        name: world_name.to_string(),
+        seed: WorldSeed(rand::random()),
+        gen_settings: limits,
+        ..default()
+    };
+    save_world_data(
+        &world_data,
+        &world_dir.join("world.ron").display().to_string(),
+    )?;
+
+    Ok(import)
+}
+
+/// Region coordinates of a `r.<x>.<z>.mca` file
+fn parse_region_file_name(file_name: &str) -> Option<IVec2> {
+    let mut parts = file_name
+        .strip_prefix("r.")?
+        .strip_suffix(".mca")?
+        .split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(IVec2::new(x, z))
+}
+
+/// The uncompressed NBT of each column present in a region file, with its index in the region
+fn region_columns(data: &[u8]) -> impl Iterator<Item = (usize, Result<Vec<u8>, String>)> + '_ {
+    (0..REGION_COLUMNS * REGION_COLUMNS).filter_map(move |index| {
+        let location = data.get(index * 4..index * 4 + 4)?;
+        let offset = u32::from_be_bytes([0, location[0], location[1], location[2]]) as usize;
+        if offset == 0 {
+            return None;
+        }
+        Some((index, read_column(data, offset * SECTOR_BYTES)))
+    })
+}
+
+fn read_column(data: &[u8], start: usize) -> Result<Vec<u8>, String> {
+    let header = data.get(start..start + 5).ok_or("truncated region file")?;
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let compression = header[4];
+    let payload = data
+        .get(start + 5..start + 4 + length)
+        .ok_or("truncated region file")?;
+
+    let mut bytes = Vec::new();
+    let read = match compression {
+        1 => GzDecoder::new(payload).read_to_end(&mut bytes),
+        2 => ZlibDecoder::new(payload).read_to_end(&mut bytes),
+        3 => {
+            bytes.extend_from_slice(payload);
+            Ok(bytes.len())
+        }
+        compression if compression & 128 != 0 => {
+            return Err("stored in an external .mcc file, which is not supported".into())
+        }
+        compression => return Err(format!("unknown compression {compression}")),
+    };
+    read.map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// The chunks of a column within the height limits. Missing sections become empty chunks,
+/// so that the server does not generate terrain in them
+fn convert_column(
+    root: &Tag,
+    column: IVec2,
+    limits: WorldGenSettings,
+    import: &mut AnvilImport,
+) -> Vec<(IVec3, ServerChunk)> {
+    let data_version = root.get("DataVersion").and_then(Tag::as_int).unwrap_or(0);
+    // Columns saved before 1.18 are nested in a `Level` compound
+    let level = root.get("Level").unwrap_or(root);
+    let sections = level
+        .get("sections")
+        .or_else(|| level.get("Sections"))
+        .and_then(Tag::as_list)
+        .unwrap_or_default();
+
+    let mut chunks: HashMap<i32, ServerChunk> = HashMap::new();
+    for section in sections {
+        let Some(y) = section.get("Y").and_then(Tag::as_int) else {
+            continue;
+        };
+        if !limits.chunk_y_range().contains(&y) {
+            continue;
+        }
+        let (palette, states) = match section.get("block_states") {
+            Some(states) => (states.get("palette"), states.get("data")),
+            None => (section.get("Palette"), section.get("BlockStates")),
+        };
+        let Some(palette) = palette.and_then(Tag::as_list) else {
+            continue;
+        };
+
+        let blocks: Vec<Option<BlockData>> = palette
+            .iter()
+            .map(|state| convert_block_state(state, import))
+            .collect();
+        let indices = palette_indices(
+            states.and_then(Tag::as_long_array).unwrap_or_default(),
+            blocks.len(),
+            data_version < NON_SPANNING_DATA_VERSION,
+        );
+
+        let chunk = chunks.entry(y).or_default();
+        for (i, palette_index) in indices.into_iter().enumerate() {
+            if let Some(Some(block)) = blocks.get(palette_index) {
+                let local = IVec3::new((i & 15) as i32, (i >> 8) as i32, ((i >> 4) & 15) as i32);
+                chunk.map.insert(local, *block);
+            }
+        }
+    }
+
+    limits
+        .chunk_y_range()
+        .map(|y| {
+            let mut chunk = chunks.remove(&y).unwrap_or_default();
+            // Imported chunks are never trimmed, the biomes are filled by the server
+            chunk.player_modified = true;
+            (IVec3::new(column.x, y, column.y), chunk)
+        })
+        .collect()
+}
+
+/// Palette index of each of the 4096 blocks of a section, in YZX order
+fn palette_indices(data: &[i64], palette_len: usize, spanning: bool) -> Vec<usize> {
+    const SECTION_BLOCKS: usize = 4096;
+    if palette_len <= 1 || data.is_empty() {
+        return vec![0; SECTION_BLOCKS];
+    }
+
+    let bits = ((usize::BITS - (palette_len - 1).leading_zeros()) as usize).max(4);
+    let mask = (1u64 << bits) - 1;
+    let per_long = 64 / bits;
+
+    (0..SECTION_BLOCKS)
+        .map(|i| {
+            let value = if spanning {
+                let bit = i * bits;
+                let (long, shift) = (bit / 64, bit % 64);
+                let low = data.get(long).copied().unwrap_or(0) as u64 >> shift;
+                let high = if shift + bits > 64 {
+                    (data.get(long + 1).copied().unwrap_or(0) as u64) << (64 - shift)
+                } else {
+                    0
+                };
+                low | high
+            } else {
+                let long = data.get(i / per_long).copied().unwrap_or(0) as u64;
+                long >> ((i % per_long) * bits)
+            };
+            (value & mask) as usize
+        })
+        .collect()
+}
+
+/// Our block for a Minecraft block state, `None` for air. Blocks without an equivalent become debug blocks
+fn convert_block_state(state: &Tag, import: &mut AnvilImport) -> Option<BlockData> {
+    let name = state.get("Name").and_then(Tag::as_str).unwrap_or_default();
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+    let id = match name {
+        "air" | "cave_air" | "void_air" => return None,
+        "snow" => {
+            let layers = state
+                .get("Properties")
+                .and_then(|properties| properties.get("layers"))
+                .and_then(Tag::as_str)
+                .and_then(|layers| layers.parse().ok())
+                .unwrap_or(1);
+            return Some(BlockData::layered(BlockId::SnowLayer, layers));
+        }
+        "light" => {
+            let level = state
+                .get("Properties")
+                .and_then(|properties| properties.get("level"))
+                .and_then(Tag::as_str)
+                .and_then(|level| level.parse().ok())
+                .unwrap_or(MAX_LIGHT_LEVEL);
+            return Some(BlockData::light(level));
+        }
+        "grass_block" => BlockId::Grass,
+        "dirt" | "coarse_dirt" | "rooted_dirt" | "podzol" | "mycelium" | "farmland"
+        | "dirt_path" | "grass_path" => BlockId::Dirt,
+        "stone" | "granite" | "diorite" | "andesite" | "deepslate" | "tuff" | "calcite"
+        | "smooth_stone" => BlockId::Stone,
+        "cobblestone" | "mossy_cobblestone" | "cobbled_deepslate" => BlockId::Cobblestone,
+        "spruce_log" | "spruce_wood" => BlockId::SpruceLog,
+        "spruce_leaves" => BlockId::SpruceLeaves,
+        "sand" | "red_sand" | "sandstone" | "red_sandstone" => BlockId::Sand,
+        "cactus" => BlockId::Cactus,
+        "ice" | "packed_ice" | "blue_ice" | "frosted_ice" => BlockId::Ice,
+        "glass" | "glass_pane" => BlockId::Glass,
+        "bedrock" => BlockId::Bedrock,
+        "dandelion" => BlockId::Dandelion,
+        "poppy" => BlockId::Poppy,
+        "grass" | "short_grass" | "tall_grass" | "fern" | "large_fern" => BlockId::TallGrass,
+        "snow_block" | "powder_snow" => BlockId::Snow,
+        "water" | "bubble_column" => BlockId::Water,
+        "coal_ore" | "deepslate_coal_ore" => BlockId::CoalOre,
+        "iron_ore" | "deepslate_iron_ore" => BlockId::IronOre,
+        "gold_ore" | "deepslate_gold_ore" => BlockId::GoldOre,
+        "diamond_ore" | "deepslate_diamond_ore" => BlockId::DiamondOre,
+        "chest" | "trapped_chest" => BlockId::Chest,
+        "fire" => BlockId::Fire,
+        "structure_void" => BlockId::StructureVoid,
+        "barrier" => BlockId::Barrier,
+        name if name.ends_with("_log") || name.ends_with("_wood") => BlockId::OakLog,
+        name if name.ends_with("_planks") => BlockId::OakPlanks,
+        name if name.ends_with("_leaves") => BlockId::OakLeaves,
+        name if name.ends_with("stained_glass") => BlockId::Glass,
+        _ => {
+            *import.unknown_blocks.entry(name.to_string()).or_default() += 1;
+            BlockId::Debug
+        }
+    };
+    Some(BlockData::new(id, BlockDirection::Front))
+}
+
+/// The parts of the NBT format chunks need, the other tags are read and dropped
+enum Tag {
+    Int(i32),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    LongArray(Vec<i64>),
+    Other,
+}
+
+impl Tag {
+    fn get(&self, key: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.get(key),
+            _ => None,
+        }
+    }
+
+    fn as_int(&self) -> Option<i32> {
+        match self {
+            Tag::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    fn as_long_array(&self) -> Option<&[i64]> {
+        match self {
+            Tag::LongArray(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the root compound of uncompressed NBT
+fn read_nbt(bytes: &[u8]) -> Result<Tag, String> {
+    let mut reader = NbtReader { bytes, pos: 0 };
+    if reader.u8()? != 10 {
+        return Err("the root tag is not a compound".into());
+    }
+    reader.string()?;
+    reader.payload(10, 0)
+}
+
+struct NbtReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> NbtReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or("truncated NBT")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Length of an array or list, negative ones are empty
+    fn len(&mut self) -> Result<usize, String> {
+        Ok(self.i32()?.max(0) as usize)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as usize;
+        // Modified UTF-8 only differs for characters block names do not use
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn payload(&mut self, tag_type: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_NBT_DEPTH {
+            return Err("NBT nested too deep".into());
+        }
+        Ok(match tag_type {
+            1 => Tag::Int(self.take(1)?[0] as i8 as i32),
+            2 => Tag::Int(i16::from_be_bytes(self.take(2)?.try_into().unwrap()) as i32),
+            3 => Tag::Int(self.i32()?),
+            4 | 6 => {
+                self.take(8)?;
+                Tag::Other
+            }
+            5 => {
+                self.take(4)?;
+                Tag::Other
+            }
+            7 => {
+                let len = self.len()?;
+                self.take(len)?;
+                Tag::Other
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_type = self.u8()?;
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.payload(item_type, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = HashMap::new();
+                loop {
+                    let entry_type = self.u8()?;
+                    if entry_type == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.insert(name, self.payload(entry_type, depth + 1)?);
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let len = self.len()?;
+                self.take(len * 4)?;
+                Tag::Other
+            }
+            12 => {
+                let len = self.len()?;
+                let longs = self.take(len * 8)?;
+                Tag::LongArray(
+                    longs
+                        .chunks_exact(8)
+                        .map(|long| i64::from_be_bytes(long.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+            // Lists of nothing are typed as end tags
+            0 => Tag::Other,
+            tag_type => return Err(format!("unknown NBT tag {tag_type}")),
+        })
+    }
+}
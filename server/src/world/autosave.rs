@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use shared::{
+    messages::PlayerId,
+    world::{ServerWorldMap, WorldSeed},
+    GameFolderPaths,
+};
+
+use crate::{
+    init::ServerTime,
+    metrics::{ServerMetrics, TickProfiler},
+    settings::ServerSettings,
+};
+
+use super::save::{partial_world_save_job, save_player, SaveWorker};
+
+/// Autosaves are put off while the recent ticks use more than this share of the tick budget
+const BUSY_TICK_LOAD: f32 = 0.8;
+/// Past this delay the autosave runs however busy the server is
+const MAX_AUTOSAVE_DELAY: Duration = Duration::from_secs(60);
+/// Modified chunks copied per tick, bigger autosaves are split over several ticks
+const AUTOSAVE_CHUNKS_PER_TICK: usize = 256;
+
+/// Progress of the autosave being made, if any
+struct PendingAutosave {
+    due_at: Duration,
+    delayed: bool,
+    /// Players left to save, one per tick
+    players: Vec<PlayerId>,
+    /// Chunks left to save out of the ones modified when the autosave started, the ones modified
+    /// in the meantime wait for the next autosave if they are not saved along
+    chunks_left: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct AutosaveScheduler {
+    last_autosave: Duration,
+    pending: Option<PendingAutosave>,
+}
+
+/// Saves the modified chunks and the players every `autosave_interval_mins`, spreading the work over
+/// several ticks and waiting for the ticks to get shorter when the server is busy
+pub fn autosave_system(
+    mut scheduler: ResMut<AutosaveScheduler>,
+    mut world_map: ResMut<ServerWorldMap>,
+    settings: Res<ServerSettings>,
+    profiler: Res<TickProfiler>,
+    metrics: Res<ServerMetrics>,
+    save_worker: Res<SaveWorker>,
+    (world_seed, server_time, game_folder_path): (
+        Res<WorldSeed>,
+        Res<ServerTime>,
+        Res<GameFolderPaths>,
+    ),
+    time: Res<Time>,
+) {
+    if settings.autosave_interval_mins == 0 {
+        return;
+    }
+    let now = time.elapsed();
+    let interval = Duration::from_secs(settings.autosave_interval_mins * 60);
+
+    if scheduler.pending.is_none() {
+        if now < scheduler.last_autosave + interval {
+            return;
+        }
+        scheduler.pending = Some(PendingAutosave {
+            due_at: now,
+            delayed: false,
+            players: world_map.players.keys().copied().collect(),
+            chunks_left: world_map.chunks.dirty_chunks.len(),
+        });
+    }
+    let Some(pending) = scheduler.pending.as_mut() else {
+        return;
+    };
+
+    if profiler.load() > BUSY_TICK_LOAD && now < pending.due_at + MAX_AUTOSAVE_DELAY {
+        if !pending.delayed {
+            debug!(
+                "Ticks use {:.0}% of their budget, putting off the autosave",
+                profiler.load() * 100.
+            );
+            pending.delayed = true;
+            metrics.update(|snapshot| snapshot.autosaves_delayed += 1);
+        }
+        return;
+    }
+
+    if let Some(id) = pending.players.pop() {
+        save_player(&world_map, id, &game_folder_path);
+        return;
+    }
+
+    let mut job = partial_world_save_job(
+        &world_map,
+        &world_seed,
+        &server_time,
+        &game_folder_path,
+        AUTOSAVE_CHUNKS_PER_TICK,
+    );
+    job.autosave = true;
+    let saved: Vec<IVec3> = job.chunks.iter().map(|(pos, _)| *pos).collect();
+    // When the queue is full, the same chunks are tried again next tick
+    if !save_worker.try_queue(job) {
+        return;
+    }
+
+    let chunks = &mut world_map.chunks;
+    for pos in saved.iter() {
+        chunks.dirty_chunks.remove(pos);
+    }
+    chunks.removed_chunks.clear();
+
+    pending.chunks_left = pending.chunks_left.saturating_sub(saved.len());
+    if pending.chunks_left == 0 || chunks.dirty_chunks.is_empty() {
+        scheduler.pending = None;
+        scheduler.last_autosave = now;
+    }
+}
@@ -1,23 +1,39 @@
 use bevy::prelude::*;
 use shared::world::{ServerWorldMap, WorldSeed};
 
-use crate::world::generation::generate_chunk;
+use crate::settings::ServerSettings;
+use crate::world::{generation::generate_and_insert_chunk, preset::WorldGenPreset};
 
-use super::broadcast_world::{get_all_active_chunks, BROADCAST_RENDER_DISTANCE};
+use super::spawn_chunks::missing_spawn_chunks;
+use super::streaming::{streaming_order, SPAWN_AREA_RADIUS};
 
 pub fn background_world_generation_system(
     mut world_map: ResMut<ServerWorldMap>,
     seed: Res<WorldSeed>,
+    preset: Res<WorldGenPreset>,
+    settings: Res<ServerSettings>,
 ) {
-    let all_chunks = get_all_active_chunks(&world_map.players, BROADCAST_RENDER_DISTANCE);
+    let limits = world_map.chunks.gen_settings;
+    let positions: Vec<Vec3> = world_map.players.values().map(|p| p.position).collect();
+    // The spawn areas of the players come first so that joining players get in quickly, then the rings streamed to them
+    let mut all_chunks: Vec<IVec3> = positions
+        .iter()
+        .flat_map(|position| streaming_order(*position, SPAWN_AREA_RADIUS as u32, limits))
+        .chain(positions.iter().flat_map(|position| {
+            streaming_order(*position, settings.stream_radius, limits)
+                .filter(|(_, in_spawn_area)| !in_spawn_area)
+        }))
+        .map(|(chunk, _)| chunk)
+        .collect();
+    // Spawn chunks come after the ones around players, they are generated even when nobody is online
+    all_chunks.extend(missing_spawn_chunks(&settings, &world_map.chunks, limits));
     let mut generated = 0;
     for c in all_chunks {
         let chunk = world_map.chunks.map.get(&c);
 
         if chunk.is_none() {
-            let chunk = generate_chunk(c, seed.0);
+            generate_and_insert_chunk(&mut world_map.chunks, c, seed.0, &preset);
             info!("Generated chunk: {:?}", c);
-            world_map.chunks.map.insert(c, chunk);
             generated += 1;
         }
 
@@ -0,0 +1,124 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+
+/// Returns a file name like `my_world-20250102-153000.tar.gz` (UTC time)
+pub fn backup_file_name(world_name: &str) -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // Convert days since epoch to a civil date (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{}-{:04}{:02}{:02}-{:02}{:02}{:02}.tar.gz",
+        world_name,
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        (secs_of_day / 60) % 60,
+        secs_of_day % 60
+    )
+}
+
+/// Packs a world save directory into a `.tar.gz` archive.\
+/// All entries are stored under a single folder named after the world
+pub fn create_world_archive(
+    world_dir: &Path,
+    archive_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let world_name = world_dir
+        .file_name()
+        .ok_or("Invalid world directory")?
+        .to_owned();
+
+    if let Some(parent) = archive_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Written next to the final file first, so an interrupted backup is never mistaken for a valid one
+    let tmp_path = archive_path.with_extension("tmp");
+    let file = File::create(&tmp_path)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    builder.append_dir_all(&world_name, world_dir)?;
+    builder.into_inner()?.finish()?.sync_all()?;
+    std::fs::rename(tmp_path, archive_path)?;
+
+    Ok(())
+}
+
+/// Extracts a world archive made by `create_world_archive` into the saves directory.\
+/// Returns the name of the imported world
+pub fn extract_world_archive(
+    archive_path: &Path,
+    saves_dir: &Path,
+    overwrite: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // First pass: make sure the archive contains exactly one world, and nothing outside of it
+    let mut world_name: Option<String> = None;
+    let mut has_world_file = false;
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?.into_owned();
+
+        let mut components = path.components();
+        let root = match components.next() {
+            Some(Component::Normal(root)) => root.to_string_lossy().to_string(),
+            _ => return Err(format!("Unexpected path in archive: {}", path.display()).into()),
+        };
+        if components.any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(format!("Unexpected path in archive: {}", path.display()).into());
+        }
+
+        match &world_name {
+            Some(name) if *name != root => {
+                return Err("The archive contains more than one world".into());
+            }
+            None => world_name = Some(root.clone()),
+            _ => {}
+        }
+
+        if path == PathBuf::from(&root).join("world.ron") {
+            has_world_file = true;
+        }
+    }
+
+    let world_name = world_name.ok_or("The archive is empty")?;
+    if !has_world_file {
+        return Err("The archive does not contain a world.ron file".into());
+    }
+
+    let target_dir = saves_dir.join(&world_name);
+    if target_dir.exists() {
+        if !overwrite {
+            return Err(format!(
+                "World {} already exists in {}",
+                world_name,
+                saves_dir.display()
+            )
+            .into());
+        }
+        std::fs::remove_dir_all(&target_dir)?;
+    }
+
+    std::fs::create_dir_all(saves_dir)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+    archive.unpack(saves_dir)?;
+
+    Ok(world_name)
+}
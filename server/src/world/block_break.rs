@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::Rng;
+use shared::{
+    players::{blocks::BrokenBlock, GameMode, Inventory, InventoryError, Player},
+    world::{BlockId, DropModifier, ItemId, ItemStack, ServerItemStack},
+};
+
+use super::stacks::spawn_item_stack;
+
+/// Where the drops of a broken block come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropSource {
+    Nothing,
+    /// A single item, whatever the drop table of the block says
+    Item(ItemId),
+    /// A draw from the drop table of the block
+    DropTable,
+}
+
+/// Where the drops of a block broken by a player holding `held` come from.
+/// Nothing in creative mode, nor without the tool the block requires.
+pub fn block_drop_source(block: BlockId, held: Option<ItemId>, game_mode: GameMode) -> DropSource {
+    if game_mode == GameMode::Creative {
+        return DropSource::Nothing;
+    }
+
+    let tool = held.and_then(|item| item.tool());
+    if block.required_tool().is_some() && !tool.is_some_and(|tool| tool.can_harvest(block)) {
+        return DropSource::Nothing;
+    }
+
+    if tool.and_then(|tool| tool.modifier) == Some(DropModifier::SilkTouch) {
+        if let Some(item) = block.silk_touch_drop() {
+            return DropSource::Item(item);
+        }
+    }
+
+    DropSource::DropTable
+}
+
+/// What a block drops when broken by a player holding `held`, see `block_drop_source`
+pub fn block_break_drops(
+    block: BlockId,
+    held: Option<ItemId>,
+    game_mode: GameMode,
+    rng: &mut impl Rng,
+) -> Vec<(ItemId, u32)> {
+    match block_drop_source(block, held, game_mode) {
+        DropSource::Nothing => Vec::new(),
+        DropSource::Item(item) => vec![(item, 1)],
+        DropSource::DropTable => block.get_drops(1, rng),
+    }
+}
+
+/// Gives the drops of a block a player just broke, what does not fit in their inventory falls on the ground.
+/// The content of broken containers is spilled too, even in creative mode.
+pub fn handle_broken_block(
+    player: &mut Player,
+    broken: BrokenBlock,
+    hotbar_slot: u32,
+    containers: &mut HashMap<IVec3, Inventory>,
+    item_stacks: &mut Vec<ServerItemStack>,
+    rng: &mut impl Rng,
+) {
+    let center = broken.position.as_vec3() + Vec3::splat(0.5);
+
+    if let Some(content) = containers.remove(&broken.position) {
+        for stack in content.inner.into_values() {
+            spawn_item_stack(item_stacks, stack, center, rng);
+        }
+    }
+
+    let held = player.inventory.get(hotbar_slot).map(|stack| stack.item_id);
+    for (item_id, nb) in block_break_drops(broken.id, held, player.game_mode, rng) {
+        let stack = ItemStack {
+            item_id,
+            item_type: item_id.get_default_type(),
+            nb,
+        };
+        match player.inventory.try_insert(stack) {
+            Ok(()) => debug!(
+                "Player {} received drop {:?} x{} from breaking block {:?}",
+                player.name, item_id, nb, broken.id
+            ),
+            Err(InventoryError::Full { remaining }) => spawn_item_stack(
+                item_stacks,
+                ItemStack {
+                    nb: remaining,
+                    ..stack
+                },
+                center,
+                rng,
+            ),
+            Err(e) => warn!("Player {} lost drop {:?}: {}", player.name, item_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn survival(block: BlockId, held: Option<ItemId>) -> DropSource {
+        block_drop_source(block, held, GameMode::Survival)
+    }
+
+    #[test]
+    fn stone_needs_a_pickaxe() {
+        assert_eq!(survival(BlockId::Stone, None), DropSource::Nothing);
+        assert_eq!(
+            survival(BlockId::Stone, Some(ItemId::Dirt)),
+            DropSource::Nothing
+        );
+        assert_eq!(
+            survival(BlockId::Stone, Some(ItemId::WoodenPickaxe)),
+            DropSource::DropTable
+        );
+
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(
+            block_break_drops(
+                BlockId::Stone,
+                Some(ItemId::WoodenPickaxe),
+                GameMode::Survival,
+                &mut rng
+            ),
+            vec![(ItemId::Cobblestone, 1)]
+        );
+    }
+
+    #[test]
+    fn ores_need_a_pickaxe_of_their_tier() {
+        let tiers = [
+            (BlockId::CoalOre, ItemId::WoodenPickaxe),
+            (BlockId::IronOre, ItemId::StonePickaxe),
+            (BlockId::GoldOre, ItemId::IronPickaxe),
+            (BlockId::DiamondOre, ItemId::IronPickaxe),
+        ];
+        let pickaxes = [
+            ItemId::WoodenPickaxe,
+            ItemId::StonePickaxe,
+            ItemId::IronPickaxe,
+            ItemId::DiamondPickaxe,
+        ];
+
+        for (ore, weakest) in tiers {
+            let weakest_tier = weakest.tool().unwrap().tier;
+            for pickaxe in pickaxes {
+                let expected = if pickaxe.tool().unwrap().tier >= weakest_tier {
+                    DropSource::DropTable
+                } else {
+                    DropSource::Nothing
+                };
+                assert_eq!(
+                    survival(ore, Some(pickaxe)),
+                    expected,
+                    "{ore:?} with {pickaxe:?}"
+                );
+            }
+            assert_eq!(survival(ore, None), DropSource::Nothing);
+        }
+    }
+
+    #[test]
+    fn soft_blocks_drop_by_hand() {
+        assert_eq!(survival(BlockId::Dirt, None), DropSource::DropTable);
+        assert_eq!(
+            survival(BlockId::OakLog, Some(ItemId::Snowball)),
+            DropSource::DropTable
+        );
+    }
+
+    #[test]
+    fn creative_mode_drops_nothing() {
+        for held in [
+            None,
+            Some(ItemId::DiamondPickaxe),
+            Some(ItemId::GoldenPickaxe),
+        ] {
+            for block in [BlockId::Dirt, BlockId::Stone, BlockId::DiamondOre] {
+                assert_eq!(
+                    block_drop_source(block, held, GameMode::Creative),
+                    DropSource::Nothing
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn golden_pickaxe_keeps_blocks_whole() {
+        let golden = Some(ItemId::GoldenPickaxe);
+        assert_eq!(
+            survival(BlockId::Stone, golden),
+            DropSource::Item(ItemId::Stone)
+        );
+        assert_eq!(
+            survival(BlockId::Grass, golden),
+            DropSource::Item(ItemId::Grass)
+        );
+        // Blocks without a silk touch drop give their usual drops
+        assert_eq!(survival(BlockId::CoalOre, golden), DropSource::DropTable);
+        // It only digs as deep as a wooden pickaxe
+        assert_eq!(survival(BlockId::IronOre, golden), DropSource::Nothing);
+    }
+}
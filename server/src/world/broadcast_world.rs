@@ -1,5 +1,8 @@
 use crate::init::ServerTime;
 use crate::network::extensions::SendGameMessageExtension;
+use crate::settings::ServerSettings;
+use crate::world::spatial::SpatialIndex;
+use crate::world::streaming::{streaming_order, RING_CHUNKS_PER_TICK, SPAWN_AREA_CHUNKS_PER_TICK};
 use bevy::math::IVec3;
 use bevy::prelude::*;
 use bevy_ecs::system::ResMut;
@@ -8,17 +11,28 @@ use shared::messages::mob::MobUpdateEvent;
 use shared::messages::{ItemStackUpdateEvent, PlayerId, ServerToClientMessage, WorldUpdate};
 use shared::players::Player;
 use shared::world::{
-    world_position_to_chunk_position, ServerChunk, ServerChunkWorldMap, ServerWorldMap,
+    world_position_to_chunk_position, DirtyReason, MobId, ServerChunk, ServerChunkWorldMap,
+    ServerMob, ServerWorldMap,
 };
-use shared::CHUNK_SIZE;
+use shared::{CHUNK_SIZE, TICKS_PER_SECOND};
 use std::collections::HashMap;
 
 pub const BROADCAST_RENDER_DISTANCE: i32 = 1;
+/// Chunks are streamed anyway to the clients which did not tell which ones they have cached in time
+const CACHED_CHUNKS_TIMEOUT_TICKS: u64 = 10 * TICKS_PER_SECOND;
+
+/// Players whose list of cached chunks has not fully arrived yet, with the tick they joined at.
+/// No chunk is streamed to them meanwhile, they would otherwise get again the ones they have
+#[derive(Resource, Default)]
+pub struct AwaitedChunkCaches(pub HashMap<PlayerId, u64>);
 
 pub fn broadcast_world_state(
     mut server: ResMut<RenetServer>,
     time: Res<ServerTime>,
     mut world_map: ResMut<ServerWorldMap>,
+    index: Res<SpatialIndex>,
+    settings: Res<ServerSettings>,
+    mut awaited_caches: ResMut<AwaitedChunkCaches>,
 ) {
     let ts = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -27,10 +41,29 @@ pub fn broadcast_world_state(
 
     let world_map = world_map.as_mut();
 
-    let mobs = world_map.mobs.clone();
+    let mobs = &world_map.mobs;
     let players = &mut world_map.players;
     let chunks = &mut world_map.chunks;
 
+    // Edited chunks are sent again to every client, once however many times they changed.
+    // Their timestamp tells the clients caching them that their copy is outdated
+    for (chunk_pos, reasons) in chunks.chunks_to_update.drain() {
+        if reasons.contains(DirtyReason::Blocks) {
+            if let Some(chunk) = chunks.map.get_mut(&chunk_pos) {
+                chunk.ts = ts;
+                chunk.sent_to_clients.clear();
+            }
+        }
+    }
+
+    awaited_caches.0.retain(|id, joined_at| {
+        if time.0 < *joined_at + CACHED_CHUNKS_TIMEOUT_TICKS {
+            return players.contains_key(id);
+        }
+        warn!("Player {} did not send its cached chunks in time", id);
+        false
+    });
+
     for client in server.clients_id().iter_mut() {
         let player = players.get_mut(client);
         let player = match player {
@@ -38,25 +71,34 @@ pub fn broadcast_world_state(
             None => continue,
         };
 
-        for (id, mob) in mobs.iter() {
-            if mob.position.distance(player.position)
-                < (BROADCAST_RENDER_DISTANCE * CHUNK_SIZE) as f32
-            {
-                server.send_game_message(
-                    *client,
-                    ServerToClientMessage::MobUpdate(MobUpdateEvent {
-                        id: *id,
-                        mob: mob.clone(),
-                    }),
-                );
-            }
+        // Clients are only told about the mobs around them
+        let nearby_mobs: HashMap<MobId, ServerMob> = index
+            .mobs_in_radius(
+                player.position,
+                (BROADCAST_RENDER_DISTANCE * CHUNK_SIZE) as f32,
+            )
+            .filter_map(|id| mobs.get(&id).map(|mob| (id, mob.clone())))
+            .collect();
+
+        for (id, mob) in nearby_mobs.iter() {
+            server.send_game_message(
+                *client,
+                ServerToClientMessage::MobUpdate(MobUpdateEvent {
+                    id: *id,
+                    mob: mob.clone(),
+                }),
+            );
         }
 
         let msg = WorldUpdate {
             tick: time.0,
             time: ts,
-            new_map: get_world_map_chunks_to_send(chunks, players, &player),
-            mobs: mobs.clone(),
+            new_map: if awaited_caches.0.contains_key(client) {
+                HashMap::new()
+            } else {
+                get_world_map_chunks_to_send(chunks, &player, settings.stream_radius)
+            },
+            mobs: nearby_mobs,
             item_stacks: get_items_stacks(),
         };
 
@@ -68,33 +110,24 @@ pub fn broadcast_world_state(
 
         server.send_game_message(*client, message);
     }
-
-    // Clear the list of chunks that needed updates after broadcasting to all clients
-    chunks.chunks_to_update.clear();
 }
 
+/// The next chunks the player has not received, the spawn area first and then ring by ring.
+/// Ring chunks only go out once the spawn area has been sent, a few at a time
 fn get_world_map_chunks_to_send(
     chunks: &mut ServerChunkWorldMap,
-    players: &HashMap<PlayerId, Player>,
     player: &Player,
+    stream_radius: u32,
 ) -> HashMap<IVec3, ServerChunk> {
-    // Send only chunks in render distance
     let mut map: HashMap<IVec3, ServerChunk> = HashMap::new();
 
-    let active_chunks = get_all_active_chunks(players, BROADCAST_RENDER_DISTANCE);
-
-    // First, handle chunks that need to be updated (re-sent due to modifications)
-    for &chunk_pos in &chunks.chunks_to_update {
-        if active_chunks.contains(&chunk_pos) {
-            if let Some(chunk) = chunks.map.get_mut(&chunk_pos) {
-                // Clear sent_to_clients list so the chunk will be re-sent to all players
-                chunk.sent_to_clients.clear();
-            }
-        }
-    }
-
-    for c in active_chunks {
-        if map.len() >= 10 {
+    for (c, in_spawn_area) in streaming_order(player.position, stream_radius, chunks.gen_settings) {
+        let limit = if in_spawn_area {
+            SPAWN_AREA_CHUNKS_PER_TICK
+        } else {
+            RING_CHUNKS_PER_TICK
+        };
+        if map.len() >= limit {
             break;
         }
 
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use shared::{
+    messages::{NetworkAction, PlayerFrameInput},
+    players::{blocks::INTERACTION_DISTANCE, Inventory, InventoryError, Player},
+    world::{raycast, ItemStack, ServerChunkWorldMap},
+};
+
+use super::journal::{EventJournal, JournalEvent};
+
+/// Moves the items of the container a player right-clicks into their inventory, the ones that do not fit stay in it.
+/// Sneaking puts the stack of the selected hotbar slot in the container instead.
+/// Called before the item use simulation, which tells whether the button was already held.
+pub fn open_targeted_container(
+    containers: &mut HashMap<IVec3, Inventory>,
+    chunks: &ServerChunkWorldMap,
+    player: &mut Player,
+    input: &PlayerFrameInput,
+    journal: &mut EventJournal,
+) {
+    if !input.inputs.contains(&NetworkAction::RightClick) || player.item_use.was_held {
+        return;
+    }
+    let Some(hit) = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+    else {
+        return;
+    };
+    if !hit.block.id.is_container()
+        || (hit.position.as_vec3() + Vec3::splat(0.5)).distance(player.position)
+            > INTERACTION_DISTANCE
+    {
+        return;
+    }
+    if input.inputs.contains(&NetworkAction::SneakOrFlyDown) {
+        put_held_stack(
+            containers.entry(hit.position).or_default(),
+            player,
+            input.hotbar_slot,
+            hit.position,
+            journal,
+        );
+        return;
+    }
+    let Some(container) = containers.get_mut(&hit.position) else {
+        return;
+    };
+
+    let mut slots: Vec<u32> = container.inner.keys().copied().collect();
+    slots.sort_unstable();
+    for slot in slots {
+        let Some(stack) = container.inner.remove(&slot) else {
+            continue;
+        };
+        let mut taken = stack.nb;
+        if let Err(InventoryError::Full { remaining }) = player.inventory.try_insert(stack) {
+            taken -= remaining;
+            container.inner.insert(
+                slot,
+                ItemStack {
+                    nb: remaining,
+                    ..stack
+                },
+            );
+        }
+        if taken > 0 {
+            journal.record(
+                &player.name,
+                JournalEvent::ContainerTaken {
+                    position: hit.position,
+                    item: stack.item_id,
+                    nb: taken,
+                },
+            );
+        }
+    }
+    debug!(
+        "Player {} opened the container at {:?}",
+        player.name, hit.position
+    );
+}
+
+/// Puts the stack of a hotbar slot in the container at `position`, what does not fit stays in the slot
+fn put_held_stack(
+    container: &mut Inventory,
+    player: &mut Player,
+    slot: u32,
+    position: IVec3,
+    journal: &mut EventJournal,
+) {
+    let Some(held) = player.inventory.get(slot).copied() else {
+        return;
+    };
+    let Ok(stack) = player.inventory.take_from_slot(slot, held.nb) else {
+        return;
+    };
+    let mut put = stack.nb;
+    if let Err(InventoryError::Full { remaining }) = container.try_insert(stack) {
+        put -= remaining;
+        // The slot was emptied above, the rest goes back to it
+        player.inventory.set_slot(
+            slot,
+            ItemStack {
+                nb: remaining,
+                ..stack
+            },
+        );
+    }
+    if put > 0 {
+        journal.record(
+            &player.name,
+            JournalEvent::ContainerPut {
+                position,
+                item: stack.item_id,
+                nb: put,
+            },
+        );
+    }
+}
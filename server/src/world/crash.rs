@@ -0,0 +1,143 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use shared::world::{ServerWorldMap, WorldSeed};
+use shared::GameFolderPaths;
+
+use crate::init::ServerTime;
+use crate::logging::recent_logs;
+use crate::world::save::{
+    save_player_data, world_save_dir, world_save_job, write_save_job, SaveWorker,
+};
+
+/// Present in the world folder while the server runs, finding it at startup means the last run did not stop cleanly
+const RUNNING_MARKER_FILE: &str = "server.running";
+const CRASH_REPORTS_DIR: &str = "crash_reports";
+
+/// Message and location of the last panic, filled by the panic hook
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Records the panics so that the crash report can include them, the default hook still prints them
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let thread = std::thread::current();
+            let description = format!(
+                "thread '{}' panicked at {}: {}",
+                thread.name().unwrap_or("<unnamed>"),
+                info.location()
+                    .map(|location| location.to_string())
+                    .unwrap_or_default(),
+                info.payload_as_str().unwrap_or("<non-string payload>")
+            );
+            if let Ok(mut last_panic) = LAST_PANIC.lock() {
+                *last_panic = Some(description);
+            }
+            default_hook(info);
+        }));
+    });
+}
+
+/// Creates the running marker of the world, warning if the previous run crashed or was killed
+pub fn mark_world_running(world_dir: &Path) {
+    let marker = world_dir.join(RUNNING_MARKER_FILE);
+    if marker.exists() {
+        warn!(
+            "The world was not shut down cleanly last time, changes since its last save may be lost (crash reports are in {})",
+            world_dir.join(CRASH_REPORTS_DIR).display()
+        );
+    }
+    let started_at = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    if let Err(e) = fs::write(
+        &marker,
+        format!("pid={}\nstarted_at={}\n", std::process::id(), started_at),
+    ) {
+        warn!("Could not write {}: {}", marker.display(), e);
+    }
+}
+
+pub fn mark_world_stopped(world_dir: &Path) {
+    let _ = fs::remove_file(world_dir.join(RUNNING_MARKER_FILE));
+}
+
+/// Saves what can be saved after the server loop panicked, then writes a crash report.
+/// The running marker is left in place so the next startup reports the crash.
+pub fn handle_server_crash(world: &mut World) {
+    error!("The server crashed, attempting an emergency save");
+
+    // Pending saves go first, so that they do not overwrite the emergency one
+    if let Some(save_worker) = world.remove_resource::<SaveWorker>() {
+        save_worker.shutdown();
+    }
+
+    let (Some(world_map), Some(seed), Some(time), Some(paths)) = (
+        world.get_resource::<ServerWorldMap>(),
+        world.get_resource::<WorldSeed>(),
+        world.get_resource::<ServerTime>(),
+        world.get_resource::<GameFolderPaths>(),
+    ) else {
+        error!("The world is not loaded, nothing to save");
+        return;
+    };
+
+    let world_dir = world_save_dir(paths, &world_map.name);
+    let report = write_save_job(world_save_job(world_map, seed, time, paths));
+    let save_result = match &report.error {
+        None => format!("{} chunks saved", report.chunks_saved),
+        Some(e) => format!(
+            "save failed, {} chunks could not be written: {}",
+            report.failed_chunks.len(),
+            e
+        ),
+    };
+    info!("Emergency save: {}", save_result);
+
+    let mut players_failed = 0;
+    for (id, player) in world_map.players.iter() {
+        let path = world_dir.join("players").join(format!("{}.ron", id));
+        if let Err(e) = save_player_data(player, &path.display().to_string()) {
+            error!("Could not save player {}: {}", id, e);
+            players_failed += 1;
+        }
+    }
+
+    let panic = LAST_PANIC
+        .lock()
+        .ok()
+        .and_then(|panic| panic.clone())
+        .unwrap_or_else(|| "unknown panic".into());
+    let content = format!(
+        "Server crash\n\nPanic: {}\nTick: {}\nEmergency save: {}\nPlayers saved: {}/{}\n\nRecent log:\n{}\n",
+        panic,
+        time.0,
+        save_result,
+        world_map.players.len() - players_failed,
+        world_map.players.len(),
+        recent_logs().join("\n")
+    );
+
+    match write_crash_report(&world_dir, &content) {
+        Ok(path) => error!("Crash report written to {}", path.display()),
+        Err(e) => error!("Could not write the crash report: {}", e),
+    }
+}
+
+fn write_crash_report(world_dir: &Path, content: &str) -> std::io::Result<PathBuf> {
+    let dir = world_dir.join(CRASH_REPORTS_DIR);
+    fs::create_dir_all(&dir)?;
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = dir.join(format!("crash-{}.txt", timestamp));
+    fs::write(&path, content)?;
+    Ok(path)
+}
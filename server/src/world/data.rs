@@ -1 +1,3 @@
 pub const SAVE_PATH: &str = "saves/";
+pub const CHUNKS_SAVE_DIR: &str = "chunks";
+pub const BACKUPS_PATH: &str = "backups/";
@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+use shared::{
+    messages::PlayerId,
+    players::{DamageSource, StatusEffectKind, MAX_PLAYER_HEALTH},
+    world::ServerWorldMap,
+    GameServerConfig, TICKS_PER_SECOND,
+};
+
+use crate::{
+    settings::ServerSettings,
+    world::{health::PlayerDamageEvent, protection::is_player_op},
+};
+
+/// Duration of an effect given without one
+const DEFAULT_EFFECT_SECONDS: u32 = 30;
+
+/// Health change dealt by an effect, poison stops at one health point
+fn effect_damage(kind: StatusEffectKind, change: f32, health: f32) -> f32 {
+    let damage = -change;
+    match kind {
+        StatusEffectKind::Poison => damage.min(health - 1.0),
+        _ => damage,
+    }
+}
+
+/// Ticks the status effects of the players and the mobs, healing and hurting them
+pub fn status_effects_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    mut ev_damage: EventWriter<PlayerDamageEvent>,
+) {
+    let world_map = world_map.as_mut();
+
+    for player in world_map.players.values_mut() {
+        if player.effects.is_empty() {
+            continue;
+        }
+        for (kind, change) in player.effects.tick() {
+            if change > 0.0 {
+                player.health = (player.health + change).min(MAX_PLAYER_HEALTH);
+                continue;
+            }
+            let amount = effect_damage(kind, change, player.health);
+            if amount > 0.0 {
+                ev_damage.write(PlayerDamageEvent {
+                    player_id: player.id,
+                    amount,
+                    source: DamageSource::Effect(kind),
+                });
+            }
+        }
+    }
+
+    for mob in world_map.mobs.values_mut() {
+        if mob.effects.is_empty() {
+            continue;
+        }
+        for (kind, change) in mob.effects.tick() {
+            if change > 0.0 {
+                mob.health = (mob.health + change).min(mob.kind.max_health());
+                continue;
+            }
+            let amount = effect_damage(kind, change, mob.health);
+            if amount > 0.0 {
+                mob.damage(amount);
+            }
+        }
+    }
+}
+
+const EFFECT_USAGE: &str =
+    "Usage: /effect give <player> <effect> [seconds] [amplifier] | /effect clear <player> [effect]";
+
+/// Gives a status effect to a player or clears their effects
+pub fn effect_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to give effects".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can give effects".to_string();
+    }
+
+    let (action, target, rest) = match args {
+        [action, target, rest @ ..] => (action.as_str(), target, rest),
+        _ => return EFFECT_USAGE.to_string(),
+    };
+    let Some(player) = world_map
+        .players
+        .values_mut()
+        .find(|player| player.name == *target)
+    else {
+        return format!("Player {} is not in the world", target);
+    };
+
+    match (action, rest) {
+        ("give", [effect, options @ ..]) if options.len() <= 2 => {
+            let Some(kind) = StatusEffectKind::from_name(effect) else {
+                return format!("Unknown effect {}", effect);
+            };
+            let seconds = match options.first().map(|s| s.parse::<u32>()) {
+                None => DEFAULT_EFFECT_SECONDS,
+                Some(Ok(seconds)) if seconds > 0 => seconds,
+                Some(_) => return EFFECT_USAGE.to_string(),
+            };
+            let amplifier = match options.get(1).map(|s| s.parse::<u8>()) {
+                None => 0,
+                Some(Ok(amplifier)) => amplifier,
+                Some(Err(_)) => return EFFECT_USAGE.to_string(),
+            };
+            player.effects.add(
+                kind,
+                amplifier,
+                seconds.saturating_mul(TICKS_PER_SECOND as u32),
+            );
+            format!(
+                "Gave {} {} {} for {} seconds",
+                player.name,
+                kind.name(),
+                amplifier as u32 + 1,
+                seconds
+            )
+        }
+        ("clear", []) => {
+            player.effects.clear();
+            format!("Cleared the effects of {}", player.name)
+        }
+        ("clear", [effect]) => {
+            let Some(kind) = StatusEffectKind::from_name(effect) else {
+                return format!("Unknown effect {}", effect);
+            };
+            if player.effects.remove(kind) {
+                format!("Removed {} from {}", kind.name(), player.name)
+            } else {
+                format!("{} has no {} effect", player.name, kind.name())
+            }
+        }
+        _ => EFFECT_USAGE.to_string(),
+    }
+}
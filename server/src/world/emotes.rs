@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{
+        Emote, EmoteEvent, NetworkAction, PlayerFrameInput, PlayerId, ServerToClientMessage,
+    },
+    players::{collision::check_player_collision, Player},
+    world::{ServerWorldMap, WorldMap},
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+
+/// Players further away than this do not receive emote events
+const EMOTE_VIEW_DISTANCE: f32 = 64.0;
+
+#[derive(Event, Debug)]
+pub struct PlayerEmoteRequestEvent {
+    pub client_id: PlayerId,
+    /// `None` cancels the current emote
+    pub emote: Option<Emote>,
+}
+
+/// Whether an input makes a sitting player stand up
+pub fn input_cancels_sitting(input: &PlayerFrameInput) -> bool {
+    [
+        NetworkAction::MoveForward,
+        NetworkAction::MoveBackward,
+        NetworkAction::MoveLeft,
+        NetworkAction::MoveRight,
+        NetworkAction::JumpOrFlyUp,
+        NetworkAction::SneakOrFlyDown,
+        NetworkAction::ToggleFlyMode,
+    ]
+    .iter()
+    .any(|action| input.inputs.contains(action))
+}
+
+/// Moves a player to the center of the block they stand on.
+/// Returns false if there is no block beneath them.
+fn park_player_on_block_beneath(player: &mut Player, world_map: &impl WorldMap) -> bool {
+    let feet = player.position - Vec3::new(0.0, player.height / 2.0 + 0.05, 0.0);
+    let block_pos = feet.floor().as_ivec3();
+    if world_map.get_block_by_coordinates(&block_pos).is_none() {
+        return false;
+    }
+
+    let parked = Vec3::new(
+        block_pos.x as f32 + 0.5,
+        block_pos.y as f32 + 1.0 + player.height / 2.0,
+        block_pos.z as f32 + 0.5,
+    );
+    if !check_player_collision(&parked, player, world_map) {
+        player.position = parked;
+    }
+    player.velocity = Vec3::ZERO;
+    player.is_flying = false;
+    true
+}
+
+pub fn handle_emotes_system(
+    mut events: EventReader<PlayerEmoteRequestEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+) {
+    let world_map = world_map.as_mut();
+
+    for event in events.read() {
+        let Some(player) = world_map.players.get_mut(&event.client_id) else {
+            continue;
+        };
+
+        if event.emote == Some(Emote::Sit)
+            && !park_player_on_block_beneath(player, &world_map.chunks)
+        {
+            debug!("Player {} cannot sit in the air", player.id);
+            continue;
+        }
+
+        if event.emote.is_none() && player.emote.is_none() {
+            continue;
+        }
+        // Timed emotes end on their own, only the looping ones are remembered
+        player.emote = event.emote.filter(|emote| emote.duration_secs().is_none());
+
+        let origin = player.position;
+        let message = EmoteEvent {
+            player_id: event.client_id,
+            emote: event.emote,
+        };
+        for other in world_map.players.values() {
+            if other.position.distance(origin) <= EMOTE_VIEW_DISTANCE {
+                server.send_game_message(other.id, ServerToClientMessage::Emote(message.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use shared::{
+    messages::PlayerId,
+    world::{
+        EntityMetadata, MetadataValue, MobId, MobKind, MobTarget, ServerMob, ServerWorldMap,
+        SimRng, WorldMap,
+    },
+    GameServerConfig,
+};
+
+use crate::{mob::create_new_mob_id, settings::ServerSettings, world::protection::is_player_op};
+
+/// `/data` works on the closest mob or item stack within this distance of the sender
+const DATA_TARGET_RANGE: f32 = 8.0;
+
+const SUMMON_USAGE: &str =
+    "Usage: /summon <fox|zombie|skeleton|wolf|villager> [key=value|tag=<tag>]...";
+const DATA_USAGE: &str = "Usage: /data get [key] | get block <x> <y> <z> | get entity <id> | set <key> <value> | remove <key> | tag <tag> | untag <tag>";
+
+/// Reads the `key=value` arguments of `/summon`, `tag=<tag>` adds a tag instead of a value
+fn parse_metadata_args(args: &[String]) -> Result<EntityMetadata, String> {
+    let mut metadata = EntityMetadata::default();
+    for arg in args {
+        let Some((key, value)) = arg.split_once('=') else {
+            return Err(format!("Expected key=value, got {arg}"));
+        };
+        if key.is_empty() || value.is_empty() {
+            return Err(format!("Expected key=value, got {arg}"));
+        }
+        if key == "tag" {
+            metadata.tags.insert(value.to_string());
+        } else {
+            metadata.set(key, MetadataValue::parse(value));
+        }
+    }
+    Ok(metadata)
+}
+
+/// Spawns a mob at the position of the sender, with the given metadata
+pub fn summon_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+    ids: &mut SimRng,
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to summon mobs".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can summon mobs".to_string();
+    }
+    let Some(kind) = args.first().and_then(|kind| MobKind::from_name(kind)) else {
+        return SUMMON_USAGE.to_string();
+    };
+    let metadata = match parse_metadata_args(&args[1..]) {
+        Ok(metadata) => metadata,
+        Err(e) => return e,
+    };
+
+    let mut mob = ServerMob::new(kind, sender.position, MobTarget::None);
+    mob.metadata = metadata;
+    let reply = format!("Summoned {} at {:.0}", mob.display_name(), mob.position);
+    info!("{} summoned {:?} at {:?}", sender.name, kind, mob.position);
+    let id = create_new_mob_id(world_map, ids);
+    world_map.mobs.insert(id, mob);
+    reply
+}
+
+/// Prints the data of the block at a position, with the items it holds and its pending update
+fn inspect_block(world_map: &ServerWorldMap, args: &[String]) -> String {
+    let coords: Option<Vec<i32>> = args.iter().map(|arg| arg.parse().ok()).collect();
+    let Some([x, y, z]) = coords.as_deref() else {
+        return DATA_USAGE.to_string();
+    };
+    let (x, y, z) = (*x, *y, *z);
+    let position = IVec3::new(x, y, z);
+    let Some(block) = world_map.chunks.get_block_by_coordinates(&position) else {
+        return format!("No block at {x} {y} {z}");
+    };
+
+    let mut lines = vec![format!(
+        "{:?} at {x} {y} {z}: facing {:?}, breaking progress {}, layers {}",
+        block.id, block.direction, block.breaking_progress, block.layers
+    )];
+    if let Some(container) = world_map.containers.get(&position) {
+        let mut slots: Vec<_> = container.inner.iter().collect();
+        slots.sort_by_key(|(slot, _)| **slot);
+        let items: Vec<String> = slots
+            .iter()
+            .map(|(slot, stack)| format!("{slot}: {:?} x{}", stack.item_id, stack.nb))
+            .collect();
+        lines.push(format!("Holds [{}]", items.join(", ")));
+    }
+    if let Some(tick) = world_map.block_ticks.next_tick_of(&position) {
+        lines.push(format!("Update scheduled at tick {tick}"));
+    }
+    lines.join("\n")
+}
+
+/// Prints the state of the mob or item stack with this id
+fn inspect_entity(world_map: &ServerWorldMap, id: &str) -> String {
+    let Ok(id) = id.parse::<u128>() else {
+        return DATA_USAGE.to_string();
+    };
+    if let Some(mob) = world_map.mobs.get(&id) {
+        return format!(
+            "{} ({:?}) at {:.1}: health {}, action {:?}, target {:?}, owner {:?}, {}",
+            mob.display_name(),
+            mob.kind,
+            mob.position,
+            mob.health,
+            mob.action,
+            mob.target,
+            mob.owner,
+            mob.metadata
+        );
+    }
+    match world_map.item_stacks.iter().find(|stack| stack.id == id) {
+        Some(stack) => format!(
+            "{:?} x{} at {:.1}: dropped at {}, despawned {}, {}",
+            stack.stack.item_id,
+            stack.stack.nb,
+            stack.pos,
+            stack.timestamp,
+            stack.despawned,
+            stack.metadata
+        ),
+        None => format!("No mob or item with id {id}"),
+    }
+}
+
+/// Closest entity holding metadata around a position
+enum DataTarget<'a> {
+    Mob(MobId, &'a mut ServerMob),
+    ItemStack(u128, &'a mut EntityMetadata, String),
+}
+
+impl DataTarget<'_> {
+    fn metadata(&mut self) -> &mut EntityMetadata {
+        match self {
+            DataTarget::Mob(_, mob) => &mut mob.metadata,
+            DataTarget::ItemStack(_, metadata, _) => metadata,
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            DataTarget::Mob(_, mob) => mob.display_name(),
+            DataTarget::ItemStack(_, _, name) => name.clone(),
+        }
+    }
+
+    /// Id to inspect the entity with `/data get entity`
+    fn id(&self) -> u128 {
+        match self {
+            DataTarget::Mob(id, _) => *id,
+            DataTarget::ItemStack(id, _, _) => *id,
+        }
+    }
+}
+
+fn closest_target(world_map: &mut ServerWorldMap, position: Vec3) -> Option<DataTarget<'_>> {
+    let mob = world_map
+        .mobs
+        .iter_mut()
+        .map(|(id, mob)| (mob.position.distance(position), (*id, mob)))
+        .filter(|(distance, _)| *distance <= DATA_TARGET_RANGE)
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+    let stack = world_map
+        .item_stacks
+        .iter_mut()
+        .filter(|stack| !stack.despawned)
+        .map(|stack| (stack.pos.distance(position), stack))
+        .filter(|(distance, _)| *distance <= DATA_TARGET_RANGE)
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+
+    match (mob, stack) {
+        (Some((mob_distance, (id, mob))), Some((stack_distance, _)))
+            if mob_distance <= stack_distance =>
+        {
+            Some(DataTarget::Mob(id, mob))
+        }
+        (_, Some((_, stack))) => {
+            let name = format!("{:?} x{}", stack.stack.item_id, stack.stack.nb);
+            Some(DataTarget::ItemStack(stack.id, &mut stack.metadata, name))
+        }
+        (Some((_, (id, mob))), None) => Some(DataTarget::Mob(id, mob)),
+        (None, None) => None,
+    }
+}
+
+/// Reads or changes the metadata of the closest mob or item stack, or prints the data of a block or entity
+pub fn data_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to edit entity data".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can edit entity data".to_string();
+    }
+    match (
+        args.first().map(String::as_str),
+        args.get(1).map(String::as_str),
+    ) {
+        (Some("get"), Some("block")) => return inspect_block(world_map, &args[2..]),
+        (Some("get"), Some("entity")) if args.len() == 3 => {
+            return inspect_entity(world_map, &args[2]);
+        }
+        _ => {}
+    }
+    let position = sender.position;
+    let Some(mut target) = closest_target(world_map, position) else {
+        return format!("No mob or item within {DATA_TARGET_RANGE} blocks");
+    };
+    let name = target.name();
+    let id = target.id();
+    let metadata = target.metadata();
+    let arg = |i: usize| args.get(i).map(String::as_str);
+
+    match (arg(0), arg(1), arg(2)) {
+        (Some("get"), None, None) => format!("{name} (id {id}): {metadata}"),
+        (Some("get"), Some(key), None) => match metadata.get(key) {
+            Some(value) => format!("{name}: {key}={value}"),
+            None => format!("{name} has no {key}"),
+        },
+        (Some("set"), Some(key), Some(_)) => {
+            // Values can hold spaces, like custom names
+            let value = MetadataValue::parse(&args[2..].join(" "));
+            let reply = format!("Set {key}={value} on {name}");
+            metadata.set(key, value);
+            reply
+        }
+        (Some("remove"), Some(key), None) => match metadata.remove(key) {
+            Some(_) => format!("Removed {key} from {name}"),
+            None => format!("{name} has no {key}"),
+        },
+        (Some("tag"), Some(tag), None) => {
+            if metadata.tags.insert(tag.to_string()) {
+                format!("Tagged {name} with {tag}")
+            } else {
+                format!("{name} is already tagged with {tag}")
+            }
+        }
+        (Some("untag"), Some(tag), None) => {
+            if metadata.tags.remove(tag) {
+                format!("Removed tag {tag} from {name}")
+            } else {
+                format!("{name} is not tagged with {tag}")
+            }
+        }
+        _ => DATA_USAGE.to_string(),
+    }
+}
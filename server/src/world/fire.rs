@@ -0,0 +1,205 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use rand::Rng;
+use shared::{
+    players::{StatusEffectKind, StatusEffects},
+    world::{
+        global_block_to_chunk_pos, BlockData, BlockDirection, BlockHitbox, BlockId,
+        BlockTickScheduler, RandomStream, ServerChunkWorldMap, ServerWorldMap, SimRng,
+        SimulationRandom, WorldMap, SIX_OFFSETS,
+    },
+    TICKS_PER_SECOND,
+};
+
+use crate::init::ServerTime;
+
+/// Ticks between two updates of a fire, picked at random
+const FIRE_TICK_DELAY: Range<u64> = TICKS_PER_SECOND..2 * TICKS_PER_SECOND;
+/// Chance out of 100 for a fire to go out at each of its updates
+const BURN_OUT_ODDS: u32 = 15;
+/// Chance out of 100 for a block destroyed by a fire to be replaced by another one
+const BURNT_BLOCK_FIRE_ODDS: u32 = 50;
+/// Seconds an entity keeps burning once out of the fire
+const ENTITY_BURN_SECONDS: u32 = 4;
+
+/// Plans the next update of the fire at `position`
+pub fn schedule_fire_tick(
+    block_ticks: &mut BlockTickScheduler,
+    position: IVec3,
+    now: u64,
+    random: &SimulationRandom,
+) {
+    let delay = random
+        .at_block(RandomStream::FireDelay, now, position)
+        .gen_range(FIRE_TICK_DELAY);
+    block_ticks.schedule(position, now + delay);
+}
+
+fn is_loaded(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    chunks
+        .map
+        .contains_key(&global_block_to_chunk_pos(&position))
+}
+
+/// Blocks in chunks which are not loaded are seen as air
+fn block_at(chunks: &ServerChunkWorldMap, position: IVec3) -> Option<BlockId> {
+    if !is_loaded(chunks, position) {
+        return None;
+    }
+    chunks.get_block_by_coordinates(&position).map(|b| b.id)
+}
+
+/// Fires stand on top of full blocks, or next to a block which burns
+fn can_hold_fire(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    let on_ground = block_at(chunks, position - IVec3::Y)
+        .is_some_and(|id| matches!(id.get_hitbox(), BlockHitbox::FullBlock));
+    on_ground
+        || SIX_OFFSETS
+            .iter()
+            .any(|offset| block_at(chunks, position + *offset).is_some_and(|id| id.is_flammable()))
+}
+
+fn is_next_to_water(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    SIX_OFFSETS
+        .iter()
+        .any(|offset| block_at(chunks, position + *offset) == Some(BlockId::Water))
+}
+
+fn ignite(
+    chunks: &mut ServerChunkWorldMap,
+    block_ticks: &mut BlockTickScheduler,
+    position: IVec3,
+    now: u64,
+    random: &SimulationRandom,
+) {
+    chunks.set_block(
+        &position,
+        BlockData::new(BlockId::Fire, BlockDirection::Front),
+    );
+    schedule_fire_tick(block_ticks, position, now, random);
+}
+
+/// Burns the flammable blocks around a fire and lights the air next to them
+fn spread_fire(
+    chunks: &mut ServerChunkWorldMap,
+    block_ticks: &mut BlockTickScheduler,
+    position: IVec3,
+    now: u64,
+    random: &SimulationRandom,
+    rng: &mut SimRng,
+) {
+    for offset in SIX_OFFSETS {
+        let neighbour = position + offset;
+        if !is_loaded(chunks, neighbour) {
+            continue;
+        }
+
+        match block_at(chunks, neighbour) {
+            Some(id) => {
+                if rng.gen_range(0..100) >= id.burn_odds() as u32 {
+                    continue;
+                }
+                if rng.gen_range(0..100) < BURNT_BLOCK_FIRE_ODDS {
+                    ignite(chunks, block_ticks, neighbour, now, random);
+                } else {
+                    chunks.remove_block_by_coordinates(&neighbour);
+                }
+            }
+            None => {
+                let odds = SIX_OFFSETS
+                    .iter()
+                    .filter_map(|o| block_at(chunks, neighbour + *o))
+                    .map(|id| id.ignite_odds())
+                    .max()
+                    .unwrap_or(0);
+                if rng.gen_range(0..100) < odds as u32 {
+                    ignite(chunks, block_ticks, neighbour, now, random);
+                }
+            }
+        }
+    }
+}
+
+/// Updates the fires due this tick: they go out, or burn and spread when the `doFireTick` rule is on.
+/// Rain puts out the fires under the open sky
+pub fn fire_block_ticks_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    time: Res<ServerTime>,
+    random: Res<SimulationRandom>,
+) {
+    let world_map = world_map.as_mut();
+    let due = world_map.block_ticks.take_due(time.0);
+    if due.is_empty() {
+        return;
+    }
+
+    let chunks = &mut world_map.chunks;
+    let block_ticks = &mut world_map.block_ticks;
+    let spreads = world_map.game_rules.do_fire_tick;
+    let weather = world_map.weather;
+
+    for position in due {
+        let mut rng = random.at_block(RandomStream::FireTick, time.0, position);
+        // Fires put out or trimmed away in the meantime
+        if !is_loaded(chunks, position) || block_at(chunks, position) != Some(BlockId::Fire) {
+            continue;
+        }
+
+        let is_rained_on = chunks.get_surface_height(position.x, position.z) == Some(position.y)
+            && chunks
+                .get_biome(&position)
+                .is_some_and(|biome| weather.is_raining_in(biome));
+        if !can_hold_fire(chunks, position)
+            || is_next_to_water(chunks, position)
+            || is_rained_on
+            || rng.gen_range(0..100) < BURN_OUT_ODDS
+        {
+            chunks.remove_block_by_coordinates(&position);
+            continue;
+        }
+
+        if spreads {
+            spread_fire(chunks, block_ticks, position, time.0, &random, &mut rng);
+        }
+        schedule_fire_tick(block_ticks, position, time.0, &random);
+    }
+}
+
+/// Sets entities standing in a fire alight, water puts them out
+fn burn_entity(
+    chunks: &ServerChunkWorldMap,
+    effects: &mut StatusEffects,
+    position: Vec3,
+    height: f32,
+) {
+    let feet = (position - Vec3::Y * (height / 2.0 - 0.1))
+        .floor()
+        .as_ivec3();
+    if !is_loaded(chunks, feet) {
+        return;
+    }
+    match block_at(chunks, feet) {
+        Some(BlockId::Fire) => effects.add(
+            StatusEffectKind::Fire,
+            0,
+            ENTITY_BURN_SECONDS * TICKS_PER_SECOND as u32,
+        ),
+        Some(BlockId::Water) => {
+            effects.remove(StatusEffectKind::Fire);
+        }
+        _ => {}
+    }
+}
+
+pub fn fire_damage_system(mut world_map: ResMut<ServerWorldMap>) {
+    let world_map = world_map.as_mut();
+    let chunks = &world_map.chunks;
+
+    for player in world_map.players.values_mut() {
+        burn_entity(chunks, &mut player.effects, player.position, player.height);
+    }
+    for mob in world_map.mobs.values_mut() {
+        burn_entity(chunks, &mut mob.effects, mob.position, mob.height);
+    }
+}
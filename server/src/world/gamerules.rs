@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{GameRulesUpdate, PlayerId, ServerToClientMessage},
+    world::{GameRule, ServerWorldMap},
+    GameServerConfig,
+};
+
+use crate::{
+    network::extensions::SendGameMessageExtension, settings::ServerSettings,
+    world::protection::is_player_op,
+};
+
+/// Sent whenever a game rule changes, so that clients stop predicting what the rules no longer allow
+#[derive(Event)]
+pub struct GameRulesChangedEvent;
+
+const GAMERULE_USAGE: &str = "Usage: /gamerule [rule] [true|false]";
+
+/// Handles `/gamerule`, anyone can read the rules but only ops can change them
+pub fn gamerule_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+    ev_rules: &mut EventWriter<GameRulesChangedEvent>,
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to use game rules".to_string();
+    };
+    let rules = &mut world_map.game_rules;
+    let arg = |i: usize| args.get(i).map(String::as_str);
+
+    match (arg(0), arg(1), arg(2)) {
+        (None, _, _) => {
+            let list: Vec<String> = GameRule::ALL
+                .iter()
+                .map(|rule| format!("{}={}", rule.name(), rules.get(*rule)))
+                .collect();
+            format!("Game rules: {}", list.join(", "))
+        }
+        (Some(name), value, None) => {
+            let Some(rule) = GameRule::from_name(name) else {
+                return format!("Unknown game rule: {name}");
+            };
+            let Some(value) = value else {
+                return format!("{} is {}", rule.name(), rules.get(rule));
+            };
+            let Ok(value) = value.parse::<bool>() else {
+                return GAMERULE_USAGE.to_string();
+            };
+            if !is_player_op(settings, config, &sender.name) {
+                return "Only ops can change game rules".to_string();
+            }
+            info!("{} set game rule {} to {}", sender.name, rule.name(), value);
+            rules.set(rule, value);
+            ev_rules.write(GameRulesChangedEvent);
+            format!("Game rule {} is now {}", rule.name(), value)
+        }
+        _ => GAMERULE_USAGE.to_string(),
+    }
+}
+
+pub fn broadcast_game_rules_system(
+    mut events: EventReader<GameRulesChangedEvent>,
+    mut server: ResMut<RenetServer>,
+    world_map: Res<ServerWorldMap>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    server.broadcast_game_message(ServerToClientMessage::GameRules(GameRulesUpdate {
+        rules: world_map.game_rules,
+        day_time: world_map.day_time,
+    }));
+}
@@ -3,11 +3,262 @@ use noise::{NoiseFn, Perlin};
 use shared::{world::*, CHUNK_SIZE};
 use std::collections::HashMap;
 
-fn generate_tree(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, trunk: BlockId, leaves: BlockId) {
+use super::{ores::ores_stage, preset::WorldGenPreset};
+
+pub(crate) const SEA_LEVEL: i32 = 62;
+const TERRAIN_SCALE: f64 = 0.1;
+const BIOME_SCALE: f64 = 0.01;
+const CAVE_SCALE: f64 = 0.06;
+/// Caves follow the places where the noise is close to zero, this is how close it must be
+const CAVE_WIDTH: f64 = 0.07;
+/// Caves stay this many blocks under the surface
+const CAVE_ROOF_DEPTH: i32 = 8;
+/// Layers of the biome's sub-surface block under its surface block
+const SUB_SURFACE_DEPTH: i32 = 4;
+const RIVER_SCALE: f64 = 0.005;
+/// Rivers follow the places where the noise is close to zero, this is how close it must be
+const RIVER_WIDTH: f64 = 0.025;
+/// The land slopes down to the river where the noise is this close to zero
+const RIVER_BANK_WIDTH: f64 = 0.06;
+/// Blocks of water under the sea level in the middle of a river
+const RIVER_DEPTH: i32 = 3;
+/// Each square cell of this many blocks may hold one lake
+const LAKE_CELL_SIZE: i32 = 96;
+/// Chance for a cell to hold a lake
+const LAKE_CHANCE: f32 = 0.35;
+const LAKE_RADIUS: std::ops::Range<f32> = 5.0..11.0;
+/// Blocks of water in the middle of a lake
+const LAKE_DEPTH: i32 = 4;
+/// Scale of the noise bending the shore of the lakes
+const LAKE_SHORE_SCALE: f64 = 0.15;
+/// How far the shore moves away from or towards the center, relative to the radius
+const LAKE_SHORE_BEND: f32 = 0.25;
+
+// Small splitmix64 generator seeded from the world seed and the chunk position.
+// Used instead of `rand` so that a chunk always generates the same way,
+// whatever the platform or the order in which chunks are generated
+pub(crate) struct ChunkRng(u64);
+
+impl ChunkRng {
+    pub(crate) fn new(seed: u32, chunk_pos: IVec3) -> Self {
+        let mut state = seed as u64;
+        for v in [chunk_pos.x, chunk_pos.y, chunk_pos.z] {
+            state = (state ^ v as u32 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+        Self(state)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    // Uniform float in [0, 1)
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+struct TerrainNoise {
+    perlin: Perlin,
+    temp_perlin: Perlin,
+    humidity_perlin: Perlin,
+    cave_perlin: Perlin,
+    river_perlin: Perlin,
+    lake_perlin: Perlin,
+    seed: u32,
+}
+
+/// Lake filling a hollow dug in the ground, above the sea level
+struct Lake {
+    center: IVec2,
+    radius: f32,
+    water_level: i32,
+}
+
+impl TerrainNoise {
+    fn new(seed: u32) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            temp_perlin: Perlin::new(seed.wrapping_add(1)),
+            humidity_perlin: Perlin::new(seed.wrapping_add(2)),
+            cave_perlin: Perlin::new(seed.wrapping_add(3)),
+            river_perlin: Perlin::new(seed.wrapping_add(4)),
+            lake_perlin: Perlin::new(seed.wrapping_add(5)),
+            seed,
+        }
+    }
+
+    fn is_cave_at(&self, position: IVec3) -> bool {
+        let value = self.cave_perlin.get([
+            position.x as f64 * CAVE_SCALE,
+            position.y as f64 * CAVE_SCALE,
+            position.z as f64 * CAVE_SCALE,
+        ]);
+        value.abs() < CAVE_WIDTH
+    }
+
+    fn biome_at(&self, x: i32, z: i32) -> BiomeType {
+        let temperature = (self
+            .temp_perlin
+            .get([x as f64 * BIOME_SCALE, z as f64 * BIOME_SCALE])
+            + 1.0)
+            / 2.0;
+        let humidity = (self
+            .humidity_perlin
+            .get([x as f64 * BIOME_SCALE, z as f64 * BIOME_SCALE])
+            + 1.0)
+            / 2.0;
+        determine_biome(temperature, humidity)
+    }
+
+    fn height_at(&self, x: i32, z: i32) -> i32 {
+        interpolated_height(
+            x,
+            z,
+            BIOME_SCALE,
+            &self.perlin,
+            &self.temp_perlin,
+            &self.humidity_perlin,
+            TERRAIN_SCALE,
+        )
+    }
+
+    fn river_at(&self, x: i32, z: i32) -> f64 {
+        self.river_perlin
+            .get([x as f64 * RIVER_SCALE, z as f64 * RIVER_SCALE])
+            .abs()
+    }
+
+    /// The lake of the cell holding (x, z), if any.
+    /// Lakes sit on flat enough ground away from the rivers, so that their water stays in the hollow.
+    fn lake_in_cell(&self, x: i32, z: i32) -> Option<Lake> {
+        let cell = IVec2::new(x, z).div_euclid(IVec2::splat(LAKE_CELL_SIZE));
+        let mut rng = ChunkRng::new(self.seed, IVec3::new(cell.x, i32::MIN, cell.y));
+        if rng.next_f32() >= LAKE_CHANCE {
+            return None;
+        }
+
+        let radius = LAKE_RADIUS.start + rng.next_f32() * (LAKE_RADIUS.end - LAKE_RADIUS.start);
+        // The whole lake and its shore stay inside its cell
+        let margin = (LAKE_RADIUS.end * (1.0 + LAKE_SHORE_BEND)).ceil() as i32 + 1;
+        let span = (LAKE_CELL_SIZE - 2 * margin) as f32;
+        let offset = IVec2::new(
+            margin + (rng.next_f32() * span) as i32,
+            margin + (rng.next_f32() * span) as i32,
+        );
+        let center = cell * LAKE_CELL_SIZE + offset;
+
+        let water_level = self.height_at(center.x, center.y);
+        let biome = self.biome_at(center.x, center.y);
+        if water_level <= SEA_LEVEL
+            || biome == BiomeType::Desert
+            || self.river_at(center.x, center.y) < RIVER_BANK_WIDTH * 2.0
+        {
+            return None;
+        }
+
+        let reach = (radius * (1.0 + LAKE_SHORE_BEND)).ceil() as i32 + 1;
+        let is_hollow = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .iter()
+            .map(|&direction| center + direction * reach)
+            .all(|shore| self.height_at(shore.x, shore.y) >= water_level);
+        is_hollow.then_some(Lake {
+            center,
+            radius,
+            water_level,
+        })
+    }
+
+    /// Surface height and level of the water above it, once rivers and lakes are dug into the terrain
+    fn surface_at(&self, x: i32, z: i32) -> (i32, i32) {
+        let mut height = self.height_at(x, z);
+
+        if let Some(lake) = self.lake_in_cell(x, z) {
+            let shore = 1.0
+                + LAKE_SHORE_BEND
+                    * self
+                        .lake_perlin
+                        .get([x as f64 * LAKE_SHORE_SCALE, z as f64 * LAKE_SHORE_SCALE])
+                        as f32;
+            let distance =
+                IVec2::new(x, z).as_vec2().distance(lake.center.as_vec2()) / (lake.radius * shore);
+            if distance < 1.0 {
+                let depth = ((1.0 - distance * distance) * LAKE_DEPTH as f32).round() as i32;
+                height = height.min(lake.water_level - depth.max(1));
+                return (height, lake.water_level);
+            }
+        }
+
+        let river = self.river_at(x, z);
+        if river < RIVER_WIDTH {
+            let depth = ((1.0 - river / RIVER_WIDTH) * RIVER_DEPTH as f64).round() as i32;
+            height = height.min(SEA_LEVEL - depth.max(1));
+        } else if river < RIVER_BANK_WIDTH && height > SEA_LEVEL + 1 {
+            let slope = (RIVER_BANK_WIDTH - river) / (RIVER_BANK_WIDTH - RIVER_WIDTH);
+            let bank = SEA_LEVEL + 1;
+            height = (height as f64 + (bank - height) as f64 * slope).round() as i32;
+        }
+        (height, SEA_LEVEL)
+    }
+}
+
+/// Surface data of a single world column, as computed by the terrain generator
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnSample {
+    pub height: i32,
+    pub biome: BiomeType,
+    pub is_underwater: bool,
+    /// Top of the water over the column, the sea level or the level of a lake
+    pub water_level: i32,
+}
+
+/// Samples the terrain surface of a `size` x `size` square centered on `center` (x, z).\
+/// Uses the same noise as `generate_chunk`, so it can be used to preview a seed without generating any chunk.\
+/// Samples are returned row by row, along x first
+#[allow(dead_code)]
+pub fn sample_surface(seed: u32, center: IVec2, size: u32) -> Vec<ColumnSample> {
+    let noise = TerrainNoise::new(seed);
+    let half = size as i32 / 2;
+
+    let mut samples = Vec::with_capacity((size * size) as usize);
+    for dz in 0..size as i32 {
+        for dx in 0..size as i32 {
+            let x = center.x - half + dx;
+            let z = center.y - half + dz;
+            let (height, water_level) = noise.surface_at(x, z);
+            samples.push(ColumnSample {
+                height,
+                biome: noise.biome_at(x, z),
+                is_underwater: height < water_level,
+                water_level,
+            });
+        }
+    }
+    samples
+}
+
+fn generate_tree(
+    blocks: &mut FeatureBlocks,
+    rng: &mut ChunkRng,
+    x: i32,
+    y: i32,
+    z: i32,
+    trunk: BlockId,
+    leaves: BlockId,
+) {
     // create trunk
-    let trunk_height = 3 + rand::random::<u8>() % 3; // random height between 3 and 5
+    let trunk_height = 3 + (rng.next_u32() % 3) as u8; // random height between 3 and 5
     for dy in 0..trunk_height {
-        chunk.map.insert(
+        blocks.insert(
             IVec3::new(x, y + dy as i32, z),
             BlockData::new(trunk, BlockDirection::Front),
         );
@@ -21,10 +272,10 @@ fn generate_tree(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, trunk: BlockId
             for offset_z in -2i32..=2i32 {
                 let cond1 = (offset_x.abs() + offset_z.abs()) < 3 - layer;
                 let cond2 = (offset_x.abs() + offset_z.abs()) == 3 - layer
-                    && rand::random::<f32>() < 0.2
+                    && rng.next_f32() < 0.2
                     && layer < 2;
                 if cond1 || cond2 {
-                    chunk.map.insert(
+                    blocks.insert(
                         IVec3::new(x + offset_x, current_y, z + offset_z),
                         BlockData::new(leaves, BlockDirection::Front),
                     );
@@ -32,7 +283,7 @@ fn generate_tree(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, trunk: BlockId
             }
         }
     }
-    chunk.map.insert(
+    blocks.insert(
         IVec3::new(x, y + trunk_height as i32 - 1, z),
         BlockData::new(trunk, BlockDirection::Front),
     );
@@ -41,41 +292,42 @@ fn generate_tree(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, trunk: BlockId
 }
 
 fn generate_big_tree(
-    chunk: &mut ServerChunk,
+    blocks: &mut FeatureBlocks,
+    rng: &mut ChunkRng,
     x: i32,
     y: i32,
     z: i32,
     trunk: BlockId,
     leaves: BlockId,
 ) {
-    let trunk_height = 4 + rand::random::<u8>() % 3; // random height between 4 and 7
+    let trunk_height = 4 + (rng.next_u32() % 3) as u8; // random height between 4 and 7
     let leaf_start_y = y + trunk_height as i32 - 2;
     // add branches
     for _ in 1..3 {
-        let branch_x = x + rand::random::<i32>() % 2;
-        let branch_z = z + rand::random::<i32>() % 2;
-        let branch_y = std::cmp::max(leaf_start_y - 1 - rand::random::<i32>() % 2, 2);
-        let prof = rand::random::<u8>() % 2 + 1;
+        let branch_x = x + rng.next_u32() as i32 % 2;
+        let branch_z = z + rng.next_u32() as i32 % 2;
+        let branch_y = std::cmp::max(leaf_start_y - 1 - rng.next_u32() as i32 % 2, 2);
+        let prof = (rng.next_u32() % 2) as u8 + 1;
         for dx in 0..prof {
-            chunk.map.insert(
+            blocks.insert(
                 IVec3::new(branch_x + dx as i32, branch_y, branch_z + 1),
                 BlockData::new(leaves, BlockDirection::Front),
             );
-            chunk.map.insert(
+            blocks.insert(
                 IVec3::new(branch_x + dx as i32, branch_y, branch_z - 1),
                 BlockData::new(leaves, BlockDirection::Front),
             );
-            chunk.map.insert(
+            blocks.insert(
                 IVec3::new(branch_x + dx as i32, branch_y + 1, branch_z),
                 BlockData::new(leaves, BlockDirection::Front),
             );
 
-            chunk.map.insert(
+            blocks.insert(
                 IVec3::new(branch_x + dx as i32, branch_y, branch_z),
                 BlockData::new(trunk, BlockDirection::Front),
             );
         }
-        chunk.map.insert(
+        blocks.insert(
             IVec3::new(branch_x + prof as i32, branch_y, branch_z),
             BlockData::new(leaves, BlockDirection::Front),
         );
@@ -83,7 +335,7 @@ fn generate_big_tree(
     // create trunk
 
     for dy in 0..trunk_height {
-        chunk.map.insert(
+        blocks.insert(
             IVec3::new(x, y + dy as i32, z),
             BlockData::new(trunk, BlockDirection::Front),
         );
@@ -96,7 +348,7 @@ fn generate_big_tree(
         for offset_x in -2i32..=2i32 {
             for offset_z in -2i32..=2i32 {
                 if !(offset_x == 0 && offset_z == 0 || offset_x.abs() == 2 && offset_z.abs() == 2) {
-                    chunk.map.insert(
+                    blocks.insert(
                         IVec3::new(x + offset_x, current_y, z + offset_z),
                         BlockData::new(leaves, BlockDirection::Front),
                     );
@@ -106,7 +358,7 @@ fn generate_big_tree(
     }
 
     // add one leaf block at the top of the trunk
-    chunk.map.insert(
+    blocks.insert(
         IVec3::new(x, leaf_start_y + 2, z),
         BlockData::new(leaves, BlockDirection::Front),
     );
@@ -118,10 +370,10 @@ fn generate_big_tree(
             for offset_z in -2i32..=2i32 {
                 let cond1 = (offset_x.abs() + offset_z.abs()) < 3 - layer;
                 let cond2 = (offset_x.abs() + offset_z.abs()) == 3 - layer
-                    && rand::random::<f32>() < 0.2
+                    && rng.next_f32() < 0.2
                     && layer < 2;
                 if cond1 || cond2 {
-                    chunk.map.insert(
+                    blocks.insert(
                         IVec3::new(x + offset_x, current_y, z + offset_z),
                         BlockData::new(leaves, BlockDirection::Front),
                     );
@@ -131,10 +383,17 @@ fn generate_big_tree(
     }
 }
 
-fn generate_cactus(chunk: &mut ServerChunk, x: i32, y: i32, z: i32, cactus: BlockId) {
-    let cactus_height = 2 + rand::random::<u8>() % 2;
+fn generate_cactus(
+    blocks: &mut FeatureBlocks,
+    rng: &mut ChunkRng,
+    x: i32,
+    y: i32,
+    z: i32,
+    cactus: BlockId,
+) {
+    let cactus_height = 2 + (rng.next_u32() % 2) as u8;
     for dy in 0..cactus_height {
-        chunk.map.insert(
+        blocks.insert(
             IVec3::new(x, y + dy as i32, z),
             BlockData::new(cactus, BlockDirection::Front),
         );
@@ -247,187 +506,509 @@ fn interpolated_height(
     interpolated_height.round() as i32
 }
 
-pub fn generate_chunk(chunk_pos: IVec3, seed: u32) -> ServerChunk {
-    let perlin = Perlin::new(seed);
-    let temp_perlin = Perlin::new(seed + 1);
-    let humidity_perlin = Perlin::new(seed + 2);
+/// Biome and surface height of one block column of the chunk being generated
+struct Column {
+    biome_type: BiomeType,
+    biome: Biome,
+    terrain_height: i32,
+    water_level: i32,
+}
 
-    let scale = 0.1;
-    let biome_scale = 0.01;
-    let cx = chunk_pos.x;
-    let cy = chunk_pos.y;
-    let cz = chunk_pos.z;
+/// Blocks spilled by features into chunks other than the one they grew in, with positions local to those chunks
+pub type SpilledBlocks = HashMap<IVec3, Vec<(IVec3, BlockData)>>;
 
-    let mut chunk = ServerChunk {
-        map: HashMap::new(),
-        ts: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64,
-        sent_to_clients: vec![],
-    };
+pub struct GeneratedChunk {
+    pub chunk: ServerChunk,
+    pub spilled: SpilledBlocks,
+}
 
+/// Where features write their blocks, positions are local to the chunk being generated
+/// but may be outside of it, these blocks are kept apart for the neighbors
+struct FeatureBlocks<'a> {
+    chunk_pos: IVec3,
+    chunk: &'a mut ServerChunk,
+    limits: WorldGenSettings,
+    spilled: SpilledBlocks,
+}
+
+impl FeatureBlocks<'_> {
+    fn is_inside(local_pos: IVec3) -> bool {
+        local_pos.cmpge(IVec3::ZERO).all() && local_pos.cmplt(IVec3::splat(CHUNK_SIZE)).all()
+    }
+
+    fn contains(&self, local_pos: IVec3) -> bool {
+        if Self::is_inside(local_pos) {
+            return self.chunk.map.contains_key(&local_pos);
+        }
+        let global_pos = chunk_offset_to_global_pos(&self.chunk_pos, &local_pos);
+        self.spilled
+            .get(&global_block_to_chunk_pos(&global_pos))
+            .is_some_and(|blocks| {
+                let target = global_block_to_local_offset(&global_pos);
+                blocks.iter().any(|(pos, _)| *pos == target)
+            })
+    }
+
+    fn insert(&mut self, local_pos: IVec3, block: BlockData) {
+        let global_pos = chunk_offset_to_global_pos(&self.chunk_pos, &local_pos);
+        if !self.limits.contains_y(global_pos.y) {
+            return;
+        }
+        if Self::is_inside(local_pos) {
+            self.chunk.map.insert(local_pos, block);
+        } else {
+            self.spilled
+                .entry(global_block_to_chunk_pos(&global_pos))
+                .or_default()
+                .push((global_block_to_local_offset(&global_pos), block));
+        }
+    }
+}
+
+fn sample_columns(noise: &TerrainNoise, chunk_pos: IVec3, limits: WorldGenSettings) -> Vec<Column> {
+    let mut columns = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
     for dx in 0..CHUNK_SIZE {
         for dz in 0..CHUNK_SIZE {
-            let x = CHUNK_SIZE * cx + dx;
-            let z = CHUNK_SIZE * cz + dz;
-
-            // calculate temperature and humidity
-            let temperature =
-                (temp_perlin.get([x as f64 * biome_scale, z as f64 * biome_scale]) + 1.0) / 2.0;
-            let humidity =
-                (humidity_perlin.get([x as f64 * biome_scale, z as f64 * biome_scale]) + 1.0) / 2.0;
-
-            // get biome regarding the two values
-            let biome_type = determine_biome(temperature, humidity);
-            let biome = get_biome_data(biome_type);
-
-            // get terrain height
-            let terrain_height = interpolated_height(
-                x,
-                z,
-                biome_scale,
-                &perlin,
-                &temp_perlin,
-                &humidity_perlin,
-                scale,
-            );
+            let x = CHUNK_SIZE * chunk_pos.x + dx;
+            let z = CHUNK_SIZE * chunk_pos.z + dz;
+            let biome_type = noise.biome_at(x, z);
+            let (terrain_height, water_level) = noise.surface_at(x, z);
+            columns.push(Column {
+                biome_type,
+                biome: get_biome_data(biome_type),
+                // Leaves room for one block of flora under the top of the world
+                terrain_height: terrain_height.clamp(limits.min_y, limits.max_y - 2),
+                water_level,
+            });
+        }
+    }
+    columns
+}
 
-            // generate blocs
-            for dy in 0..CHUNK_SIZE {
-                let y = CHUNK_SIZE * cy + dy;
+/// Biomes of the block columns of a chunk, ordered as `biome_index` expects
+fn chunk_biomes(noise: &TerrainNoise, chunk_pos: IVec3) -> Vec<BiomeType> {
+    let mut biomes = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+    for dz in 0..CHUNK_SIZE {
+        for dx in 0..CHUNK_SIZE {
+            biomes
+                .push(noise.biome_at(CHUNK_SIZE * chunk_pos.x + dx, CHUNK_SIZE * chunk_pos.z + dz));
+        }
+    }
+    biomes
+}
+
+/// Gives their biomes to the chunks saved before biomes were stored
+pub fn fill_missing_biomes(chunks: &mut ServerChunkWorldMap, seed: u32) {
+    let noise = TerrainNoise::new(seed);
+    let mut filled = 0;
+    for (chunk_pos, chunk) in chunks.map.iter_mut() {
+        if chunk.biomes.is_empty() {
+            chunk.biomes = chunk_biomes(&noise, *chunk_pos);
+            chunks.dirty_chunks.insert(*chunk_pos);
+            filled += 1;
+        }
+    }
+    if filled > 0 {
+        info!(
+            "Computed the biomes of {} chunks saved without them",
+            filled
+        );
+    }
+}
+
+/// Columns are stored along z first
+fn column_at(columns: &[Column], dx: i32, dz: i32) -> &Column {
+    &columns[(dx * CHUNK_SIZE + dz) as usize]
+}
 
-                if y > terrain_height && y > 62 {
+/// Stone up to the surface, bedrock at the bottom of the world and water up to the sea level or the lake level.\
+/// Water freezes at its surface in the cold biomes
+fn base_terrain_stage(
+    chunk: &mut ServerChunk,
+    columns: &[Column],
+    chunk_pos: IVec3,
+    limits: WorldGenSettings,
+) {
+    for dx in 0..CHUNK_SIZE {
+        for dz in 0..CHUNK_SIZE {
+            let column = column_at(columns, dx, dz);
+            let terrain_height = column.terrain_height;
+            let water_level = column.water_level;
+            let is_frozen = column.biome_type == BiomeType::IcePlain;
+            for dy in 0..CHUNK_SIZE {
+                let y = CHUNK_SIZE * chunk_pos.y + dy;
+                if (y > terrain_height && y > water_level) || y >= limits.max_y {
                     break;
                 }
+                if y < limits.min_y {
+                    continue;
+                }
 
-                let block = if y == 0 {
+                let block = if y == limits.min_y {
                     BlockId::Bedrock
-                } else if y < terrain_height - 4 {
+                } else if y <= terrain_height {
                     BlockId::Stone
-                } else if y < terrain_height {
-                    biome.sub_surface_block
-                } else if y == terrain_height {
-                    biome.surface_block
-                } else if y <= 62 {
+                } else if y == water_level && is_frozen {
+                    BlockId::Ice
+                } else {
                     BlockId::Water
+                };
+                chunk.map.insert(
+                    IVec3::new(dx, dy, dz),
+                    BlockData::new(block, BlockDirection::Front),
+                );
+            }
+        }
+    }
+}
+
+/// Digs caves in the stone, far enough under the surface for the ground and the sea floor to stay whole
+fn carving_stage(
+    chunk: &mut ServerChunk,
+    columns: &[Column],
+    chunk_pos: IVec3,
+    noise: &TerrainNoise,
+) {
+    for dx in 0..CHUNK_SIZE {
+        for dz in 0..CHUNK_SIZE {
+            let terrain_height = column_at(columns, dx, dz).terrain_height;
+            for dy in 0..CHUNK_SIZE {
+                let local_pos = IVec3::new(dx, dy, dz);
+                let position = chunk_offset_to_global_pos(&chunk_pos, &local_pos);
+                if position.y >= terrain_height - CAVE_ROOF_DEPTH {
+                    break;
+                }
+                let is_stone = chunk
+                    .map
+                    .get(&local_pos)
+                    .is_some_and(|block| block.id == BlockId::Stone);
+                if is_stone && noise.is_cave_at(position) {
+                    chunk.map.remove(&local_pos);
+                }
+            }
+        }
+    }
+}
+
+/// Turns the top of the stone into the blocks of the biome, the ground under water is sand
+fn surface_stage(chunk: &mut ServerChunk, columns: &[Column], chunk_pos: IVec3) {
+    for dx in 0..CHUNK_SIZE {
+        for dz in 0..CHUNK_SIZE {
+            let column = column_at(columns, dx, dz);
+            let bottom = column.terrain_height - SUB_SURFACE_DEPTH;
+            for y in bottom..=column.terrain_height {
+                let dy = y - CHUNK_SIZE * chunk_pos.y;
+                let Some(block) = chunk.map.get_mut(&IVec3::new(dx, dy, dz)) else {
+                    continue;
+                };
+                if block.id != BlockId::Stone {
+                    continue;
+                }
+                block.id = if y == column.terrain_height && y < column.water_level {
+                    BlockId::Sand
+                } else if y == column.terrain_height {
+                    column.biome.surface_block
                 } else {
-                    panic!();
+                    column.biome.sub_surface_block
                 };
+            }
+        }
+    }
+}
 
-                let block_pos = IVec3::new(dx, dy, dz);
+/// Flora, trees and cacti growing on the surface above the water
+fn features_stage(
+    blocks: &mut FeatureBlocks,
+    columns: &[Column],
+    chunk_pos: IVec3,
+    rng: &mut ChunkRng,
+) {
+    for dx in 0..CHUNK_SIZE {
+        for dz in 0..CHUNK_SIZE {
+            let column = column_at(columns, dx, dz);
+            let biome_type = column.biome_type;
+            let terrain_height = column.terrain_height;
+            let dy = terrain_height - CHUNK_SIZE * chunk_pos.y;
+            if terrain_height <= column.water_level || !(0..CHUNK_SIZE).contains(&dy) {
+                continue;
+            }
 
-                chunk
-                    .map
-                    .insert(block_pos, BlockData::new(block, BlockDirection::Front));
-
-                // Add flora in biomes
-                if y == terrain_height && terrain_height > 62 {
-                    let above_surface_pos = IVec3::new(dx, terrain_height + 1, dz);
-
-                    // Add flowers
-                    let flower_chance = rand::random::<f32>();
-                    match biome_type {
-                        BiomeType::FlowerPlains => {
-                            // High probability for flowers in Flower Plains
-                            if flower_chance < 0.1 {
-                                let flower_type = if rand::random::<f32>() < 0.5 {
-                                    BlockId::Dandelion
-                                } else {
-                                    BlockId::Poppy
-                                };
-
-                                chunk.map.insert(
-                                    block_pos.with_y(block_pos.y + 1),
-                                    BlockData::new(flower_type, BlockDirection::Front),
-                                );
-                            }
-                        }
-                        BiomeType::Plains | BiomeType::Forest | BiomeType::MediumMountain => {
-                            // Low probability for flowers in Plains, Forest, Medium Mountain
-                            if flower_chance < 0.02 {
-                                let flower_type = if rand::random::<f32>() < 0.5 {
-                                    BlockId::Dandelion
-                                } else {
-                                    BlockId::Poppy
-                                };
-
-                                chunk.map.insert(
-                                    block_pos.with_y(block_pos.y + 1),
-                                    BlockData::new(flower_type, BlockDirection::Front),
-                                );
-                            }
-                        }
-                        _ => {}
+            let block_pos = IVec3::new(dx, dy, dz);
+            let above_surface_pos = block_pos.with_y(dy + 1);
+
+            // Add flowers
+            let flower_chance = rng.next_f32();
+            match biome_type {
+                BiomeType::FlowerPlains => {
+                    // High probability for flowers in Flower Plains
+                    if flower_chance < 0.1 {
+                        let flower_type = if rng.next_f32() < 0.5 {
+                            BlockId::Dandelion
+                        } else {
+                            BlockId::Poppy
+                        };
+
+                        blocks.insert(
+                            above_surface_pos,
+                            BlockData::new(flower_type, BlockDirection::Front),
+                        );
                     }
+                }
+                // Low probability for flowers in Plains, Forest, Medium Mountain
+                BiomeType::Plains | BiomeType::Forest | BiomeType::MediumMountain
+                    if flower_chance < 0.02 =>
+                {
+                    let flower_type = if rng.next_f32() < 0.5 {
+                        BlockId::Dandelion
+                    } else {
+                        BlockId::Poppy
+                    };
+
+                    blocks.insert(
+                        above_surface_pos,
+                        BlockData::new(flower_type, BlockDirection::Front),
+                    );
+                }
+                _ => {}
+            }
 
-                    // Add tall grass
-                    if biome_type != BiomeType::HighMountainGrass
-                        && biome_type != BiomeType::Desert
-                        && biome_type != BiomeType::IcePlain
-                    {
-                        let tall_grass_chance = rand::random::<f32>();
-                        if tall_grass_chance < 0.10 {
-                            chunk.map.insert(
-                                block_pos.with_y(block_pos.y + 1),
-                                BlockData::new(BlockId::TallGrass, BlockDirection::Front),
-                            );
-                        }
-                    }
+            // Add tall grass
+            if biome_type != BiomeType::HighMountainGrass
+                && biome_type != BiomeType::Desert
+                && biome_type != BiomeType::IcePlain
+            {
+                let tall_grass_chance = rng.next_f32();
+                if tall_grass_chance < 0.10 {
+                    blocks.insert(
+                        above_surface_pos,
+                        BlockData::new(BlockId::TallGrass, BlockDirection::Front),
+                    );
+                }
+            }
 
-                    // Add trees
-                    let tree_chance = rand::random::<f32>();
-                    match biome_type {
-                        BiomeType::Forest => {
-                            // High probability for trees in Forest
-                            if tree_chance < 0.06 && !chunk.map.contains_key(&above_surface_pos) {
-                                if tree_chance < 0.01 {
-                                    generate_big_tree(
-                                        &mut chunk,
-                                        dx,
-                                        dy + 1,
-                                        dz,
-                                        BlockId::OakLog,
-                                        BlockId::OakLeaves,
-                                    );
-                                } else {
-                                    generate_tree(
-                                        &mut chunk,
-                                        dx,
-                                        dy + 1,
-                                        dz,
-                                        BlockId::OakLog,
-                                        BlockId::OakLeaves,
-                                    );
-                                }
-                            }
-                        }
-                        BiomeType::FlowerPlains | BiomeType::MediumMountain => {
-                            // Medium probability for trees in Flower Plains and Medium Mountain
-                            if tree_chance < 0.02 && !chunk.map.contains_key(&above_surface_pos) {
-                                generate_tree(
-                                    &mut chunk,
-                                    dx,
-                                    dy + 1,
-                                    dz,
-                                    BlockId::OakLog,
-                                    BlockId::OakLeaves,
-                                );
-                            }
+            // Add trees
+            let tree_chance = rng.next_f32();
+            match biome_type {
+                BiomeType::Forest => {
+                    // High probability for trees in Forest
+                    if tree_chance < 0.06 && !blocks.contains(above_surface_pos) {
+                        if tree_chance < 0.01 {
+                            generate_big_tree(
+                                blocks,
+                                rng,
+                                dx,
+                                dy + 1,
+                                dz,
+                                BlockId::OakLog,
+                                BlockId::OakLeaves,
+                            );
+                        } else {
+                            generate_tree(
+                                blocks,
+                                rng,
+                                dx,
+                                dy + 1,
+                                dz,
+                                BlockId::OakLog,
+                                BlockId::OakLeaves,
+                            );
                         }
-                        _ => {}
                     }
+                }
+                // Medium probability for trees in Flower Plains and Medium Mountain
+                BiomeType::FlowerPlains | BiomeType::MediumMountain
+                    if tree_chance < 0.02 && !blocks.contains(above_surface_pos) =>
+                {
+                    generate_tree(
+                        blocks,
+                        rng,
+                        dx,
+                        dy + 1,
+                        dz,
+                        BlockId::OakLog,
+                        BlockId::OakLeaves,
+                    );
+                }
+                _ => {}
+            }
 
-                    // Add cactus in Desert
-                    if biome_type == BiomeType::Desert {
-                        let cactus_chance = rand::random::<f32>();
-                        if cactus_chance < 0.01 && !chunk.map.contains_key(&above_surface_pos) {
-                            generate_cactus(&mut chunk, dx, dy + 1, dz, BlockId::Cactus);
-                        }
-                    }
+            // Add cactus in Desert
+            if biome_type == BiomeType::Desert {
+                let cactus_chance = rng.next_f32();
+                if cactus_chance < 0.01 && !blocks.contains(above_surface_pos) {
+                    generate_cactus(blocks, rng, dx, dy + 1, dz, BlockId::Cactus);
                 }
             }
         }
     }
-    chunk
+}
+
+/// Runs the generation stages of a chunk: base terrain, carving, surface, ores and features.\
+/// Chunks outside the height limits are generated empty, so that players flying above the world still have chunks around them
+pub fn generate_chunk(
+    chunk_pos: IVec3,
+    seed: u32,
+    limits: WorldGenSettings,
+    preset: &WorldGenPreset,
+) -> GeneratedChunk {
+    let noise = TerrainNoise::new(seed);
+    let mut rng = ChunkRng::new(seed, chunk_pos);
+
+    let mut chunk = ServerChunk {
+        map: HashMap::new(),
+        ts: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64,
+        sent_to_clients: vec![],
+        player_modified: false,
+        biomes: chunk_biomes(&noise, chunk_pos),
+    };
+
+    if !limits.chunk_y_range().contains(&chunk_pos.y) {
+        return GeneratedChunk {
+            chunk,
+            spilled: HashMap::new(),
+        };
+    }
+
+    let columns = sample_columns(&noise, chunk_pos, limits);
+    base_terrain_stage(&mut chunk, &columns, chunk_pos, limits);
+    carving_stage(&mut chunk, &columns, chunk_pos, &noise);
+    surface_stage(&mut chunk, &columns, chunk_pos);
+    ores_stage(&mut chunk, chunk_pos, seed, preset);
+
+    let mut blocks = FeatureBlocks {
+        chunk_pos,
+        chunk: &mut chunk,
+        limits,
+        spilled: HashMap::new(),
+    };
+    features_stage(&mut blocks, &columns, chunk_pos, &mut rng);
+    let spilled = blocks.spilled;
+
+    GeneratedChunk { chunk, spilled }
+}
+
+/// Generates a chunk and adds it to the world, see `insert_generated_chunk`
+pub fn generate_and_insert_chunk(
+    chunks: &mut ServerChunkWorldMap,
+    chunk_pos: IVec3,
+    seed: u32,
+    preset: &WorldGenPreset,
+) {
+    let generated = generate_chunk(chunk_pos, seed, chunks.gen_settings, preset);
+    insert_generated_chunk(chunks, chunk_pos, generated);
+}
+
+/// Adds a generated chunk to the world along with the blocks its neighbors' features left for it.\
+/// The blocks its own features spill are written into the neighbors already generated, or kept until they are.
+/// Spilled blocks only fill empty spots, they never replace terrain or what players built.
+pub fn insert_generated_chunk(
+    chunks: &mut ServerChunkWorldMap,
+    chunk_pos: IVec3,
+    generated: GeneratedChunk,
+) {
+    let GeneratedChunk { mut chunk, spilled } = generated;
+
+    if let Some(pending) = chunks.pending_blocks.remove(&chunk_pos) {
+        for (local_pos, block) in pending {
+            chunk.map.entry(local_pos).or_insert(block);
+        }
+    }
+    chunks.insert_chunk(chunk_pos, chunk);
+    chunks.dirty_chunks.insert(chunk_pos);
+
+    for (neighbor_pos, blocks) in spilled {
+        let Some(neighbor) = chunks.map.get(&neighbor_pos) else {
+            chunks
+                .pending_blocks
+                .entry(neighbor_pos)
+                .or_default()
+                .extend(blocks);
+            continue;
+        };
+        let blocks: Vec<(IVec3, Option<BlockData>)> = blocks
+            .into_iter()
+            .filter(|(local_pos, _)| !neighbor.map.contains_key(local_pos))
+            .map(|(local_pos, block)| (local_pos, Some(block)))
+            .collect();
+        if !blocks.is_empty() {
+            chunks.set_chunk_blocks(neighbor_pos, &blocks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::messages::packs::pack_hash;
+
+    /// Chunks of the default preset whose content is checked, with the digest they had when generated last.
+    /// A change of the generation shows up here: update the digests once the new terrain is the expected one
+    const GOLDEN_CHUNKS: [(u32, IVec3, &str); 5] = [
+        (
+            0,
+            IVec3::new(0, 4, 0),
+            "4a06a82a301826bf93544db62e4453ba203675d3829520b82687f3f554f4167f",
+        ),
+        (
+            42,
+            IVec3::new(-3, 3, 5),
+            "42024bdc29f526defe913acea029922375686b94464b7dc9ecb0373dc1e16ee4",
+        ),
+        (
+            42,
+            IVec3::new(7, -2, -2),
+            "f07943957a9badd2fb16582dd00376514259bf724511836eddd73c2d87f0c630",
+        ),
+        (
+            123_456_789,
+            IVec3::new(-20, 4, -13),
+            "4fbd60d1a7fc3a7a3de1ea2fe6aa70582999b6516538aa88caddeaa9659c8090",
+        ),
+        (
+            123_456_789,
+            IVec3::new(11, 3, 30),
+            "7d54a81a4a37d06acb51f1cfe513c20eb18aed6e07c5c3f4c33c03bd6c5137be",
+        ),
+    ];
+
+    /// Digest of the blocks, biomes and spilled blocks of a generated chunk, whatever the order of its maps
+    fn chunk_digest(generated: &GeneratedChunk) -> String {
+        let mut blocks: Vec<([i32; 3], BlockData)> = generated
+            .chunk
+            .map
+            .iter()
+            .map(|(pos, block)| (pos.to_array(), *block))
+            .collect();
+        blocks.sort_by_key(|(pos, _)| *pos);
+
+        let mut spilled: Vec<([i32; 3], [i32; 3], BlockData)> = generated
+            .spilled
+            .iter()
+            .flat_map(|(chunk_pos, blocks)| {
+                blocks
+                    .iter()
+                    .map(|(pos, block)| (chunk_pos.to_array(), pos.to_array(), *block))
+            })
+            .collect();
+        spilled.sort_by_key(|(chunk_pos, pos, _)| (*chunk_pos, *pos));
+
+        let bytes = bincode::serialize(&(blocks, &generated.chunk.biomes, spilled)).unwrap();
+        pack_hash(&bytes)
+    }
+
+    #[test]
+    fn generated_chunks_match_golden_digests() {
+        let preset = WorldGenPreset::default();
+        for (seed, chunk_pos, digest) in GOLDEN_CHUNKS {
+            let generated = generate_chunk(chunk_pos, seed, WorldGenSettings::default(), &preset);
+            assert_eq!(
+                chunk_digest(&generated),
+                digest,
+                "chunk {chunk_pos} of seed {seed}"
+            );
+        }
+    }
 }
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{ChatConversation, PlayerDeathEvent, PlayerId, ServerToClientMessage},
+    players::{fall_damage, DamageSource, GameMode, Player, MAX_PLAYER_HEALTH},
+    world::{GameRules, RandomStream, ServerWorldMap, SimulationRandom},
+    WORLD_SPAWN_POSITION,
+};
+
+use super::{
+    journal::{EventJournal, JournalEvent},
+    stacks::spawn_item_stack,
+};
+
+use crate::{
+    init::ServerTime,
+    network::{
+        broadcast_chat::{push_server_chat_message, ChatMessageEvent},
+        extensions::SendGameMessageExtension,
+    },
+    settings::ServerSettings,
+};
+
+#[derive(Event, Debug, Clone)]
+pub struct PlayerDamageEvent {
+    pub player_id: PlayerId,
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+/// Turns what happened to a player during its movement simulation into damage events
+pub fn take_environment_damage(
+    player: &mut Player,
+    game_rules: &GameRules,
+    ev_damage: &mut EventWriter<PlayerDamageEvent>,
+) {
+    if std::mem::take(&mut player.fell_into_void) {
+        ev_damage.write(PlayerDamageEvent {
+            player_id: player.id,
+            amount: MAX_PLAYER_HEALTH,
+            source: DamageSource::Void,
+        });
+    }
+
+    let damage = fall_damage(std::mem::take(&mut player.landing_speed));
+    if damage > 0.0 && !player.is_flying && game_rules.fall_damage {
+        ev_damage.write(PlayerDamageEvent {
+            player_id: player.id,
+            amount: damage,
+            source: DamageSource::Fall,
+        });
+    }
+}
+
+pub fn apply_player_damage_system(
+    mut events: EventReader<PlayerDamageEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    mut chat_conversation: ResMut<ChatConversation>,
+    mut ev_chat: EventWriter<ChatMessageEvent>,
+    settings: Res<ServerSettings>,
+    mut journal: ResMut<EventJournal>,
+    (time, random): (Res<ServerTime>, Res<SimulationRandom>),
+) {
+    let world_map = world_map.as_mut();
+
+    for event in events.read() {
+        if let DamageSource::Player { id, .. } = &event.source {
+            if !world_map.teams.can_damage(*id, event.player_id) {
+                continue;
+            }
+        }
+
+        let amount = match event.source {
+            DamageSource::Mob { .. } => event.amount * settings.difficulty.mob_damage_multiplier(),
+            _ => event.amount,
+        };
+        if amount <= 0.0 {
+            continue;
+        }
+
+        let Some(player) = world_map.players.get_mut(&event.player_id) else {
+            continue;
+        };
+        if player.game_mode == GameMode::Spectator {
+            continue;
+        }
+
+        player.health -= amount;
+        debug!(
+            "Player {} took {} damage from {:?}, {} health left",
+            player.id, amount, event.source, player.health
+        );
+
+        if player.health > 0.0 {
+            continue;
+        }
+
+        push_server_chat_message(
+            &mut chat_conversation,
+            event.source.death_message(&player.name),
+        );
+        ev_chat.write(ChatMessageEvent);
+
+        if let DamageSource::Player { name, .. } = &event.source {
+            journal.record(
+                name,
+                JournalEvent::PlayerKilled {
+                    victim: player.name.clone(),
+                    position: player.position,
+                },
+            );
+        }
+
+        server.broadcast_game_message(ServerToClientMessage::PlayerDeath(PlayerDeathEvent {
+            victim_id: player.id,
+            victim_name: player.name.clone(),
+            source: event.source.clone(),
+        }));
+
+        if !world_map.game_rules.keep_inventory {
+            let mut ids = random.for_entity(RandomStream::EntityIds, time.0, player.id as u128);
+            for (_, stack) in player.inventory.inner.drain() {
+                spawn_item_stack(&mut world_map.item_stacks, stack, player.position, &mut ids);
+            }
+        }
+
+        player.health = MAX_PLAYER_HEALTH;
+        player.position = WORLD_SPAWN_POSITION;
+        player.velocity = Vec3::ZERO;
+        player.emote = None;
+        player.effects.clear();
+    }
+}
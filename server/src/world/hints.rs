@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{Hint, PlayerId, ServerToClientMessage},
+    players::Player,
+    world::{is_daytime, GameRules, ServerWorldMap},
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+use crate::world::recipes::ItemObtainedEvent;
+
+#[derive(Event, Debug)]
+pub struct PlayerHintRequestEvent {
+    pub client_id: PlayerId,
+    pub hint: Hint,
+}
+
+/// Sends a hint to a player unless they already saw it or hints are turned off, then remembers it
+pub fn show_hint(
+    server: &mut RenetServer,
+    player: &mut Player,
+    game_rules: &GameRules,
+    hint: Hint,
+) {
+    if !game_rules.show_hints || !player.seen_hints.insert(hint) {
+        return;
+    }
+    debug!("Showing hint {:?} to player {}", hint, player.name);
+    server.send_game_message(player.id, ServerToClientMessage::Hint(hint));
+}
+
+/// Shows the hints matching what each player is doing, and the ones their client asked for
+pub fn hint_triggers_system(
+    mut ev_obtained: EventReader<ItemObtainedEvent>,
+    mut ev_requests: EventReader<PlayerHintRequestEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+) {
+    let world_map = world_map.as_mut();
+    let game_rules = &world_map.game_rules;
+    let night = !is_daytime(world_map.day_time);
+
+    for player in world_map.players.values_mut() {
+        if player.inventory.inner.is_empty() {
+            show_hint(&mut server, player, game_rules, Hint::CollectWood);
+        }
+        if night {
+            show_hint(&mut server, player, game_rules, Hint::Night);
+        }
+    }
+
+    for event in ev_obtained.read() {
+        if let Some(player) = world_map.players.get_mut(&event.player_id) {
+            show_hint(&mut server, player, game_rules, Hint::OpenInventory);
+        }
+    }
+
+    for event in ev_requests.read() {
+        if !event.hint.is_client_triggered() {
+            debug!(
+                "Player {} asked for the server-only hint {:?}",
+                event.client_id, event.hint
+            );
+            continue;
+        }
+        if let Some(player) = world_map.players.get_mut(&event.client_id) {
+            show_hint(&mut server, player, game_rules, event.hint);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{ItemUseEvent, PlayerId, ServerToClientMessage},
+    players::{item_use::ItemUseEffect, DamageSource, Player, MAX_PLAYER_HEALTH},
+    world::{
+        ItemId, ItemUseKind, Projectile, ProjectileKind, RandomStream, ServerWorldMap,
+        SimulationRandom,
+    },
+};
+
+use crate::{init::ServerTime, network::extensions::SendGameMessageExtension};
+
+use super::projectiles::spawn_projectile;
+
+/// Players further away than this do not see others eating or drawing their bow
+const ITEM_USE_VIEW_DISTANCE: f32 = 64.0;
+/// Projectiles leave from a bit in front of the eyes so that they do not hit the shooter
+const THROW_OFFSET: f32 = 0.5;
+const EYE_HEIGHT: f32 = 0.7;
+
+#[derive(Event, Debug)]
+pub struct PlayerItemUseEvent {
+    pub player_id: PlayerId,
+    pub effect: ItemUseEffect,
+}
+
+fn throw_projectile(player: &Player, kind: ProjectileKind, power: f32) -> Projectile {
+    let direction = player.camera_transform.forward();
+    Projectile {
+        kind,
+        position: player.position + Vec3::Y * EYE_HEIGHT + direction * THROW_OFFSET,
+        velocity: direction * kind.speed() * power,
+        source: DamageSource::Player {
+            id: player.id,
+            name: player.name.clone(),
+        },
+        age: 0.0,
+    }
+}
+
+pub fn handle_item_use_system(
+    mut events: EventReader<PlayerItemUseEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    time: Res<ServerTime>,
+    random: Res<SimulationRandom>,
+) {
+    let world_map = world_map.as_mut();
+
+    for event in events.read() {
+        let Some(player) = world_map.players.get_mut(&event.player_id) else {
+            continue;
+        };
+        let mut ids = random.for_entity(RandomStream::EntityIds, time.0, event.player_id as u128);
+
+        let (animation, projectile) = match event.effect {
+            ItemUseEffect::Started(active) => (Some(active.item), None),
+            ItemUseEffect::Cancelled { .. } => (None, None),
+            ItemUseEffect::Used { item } => {
+                if let Some(health) = item.food_health() {
+                    player.health = (player.health + health).min(MAX_PLAYER_HEALTH);
+                }
+                let projectile = (item == ItemId::Snowball)
+                    .then(|| throw_projectile(player, ProjectileKind::Snowball, 1.0));
+                (None, projectile)
+            }
+            ItemUseEffect::Released { item, charge } => {
+                let projectile = (item == ItemId::Bow)
+                    .then(|| throw_projectile(player, ProjectileKind::Arrow, charge));
+                (None, projectile)
+            }
+            ItemUseEffect::OnCooldown { item } => {
+                debug!(
+                    "Player {} tried to use {:?} while it cools down",
+                    player.id, item
+                );
+                continue;
+            }
+        };
+
+        // Instant uses have no animation to start or stop
+        if event.effect.item().use_kind() == Some(ItemUseKind::Instant) {
+            if let Some(projectile) = projectile {
+                spawn_projectile(world_map, &mut server, projectile, &mut ids);
+            }
+            continue;
+        }
+
+        let origin = player.position;
+        let message = ItemUseEvent {
+            player_id: event.player_id,
+            item: animation,
+        };
+        for other in world_map.players.values() {
+            if other.position.distance(origin) <= ITEM_USE_VIEW_DISTANCE {
+                server.send_game_message(other.id, ServerToClientMessage::ItemUse(message.clone()));
+            }
+        }
+
+        if let Some(projectile) = projectile {
+            spawn_projectile(world_map, &mut server, projectile, &mut ids);
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use bevy::prelude::*;
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use shared::{
+    messages::PlayerId,
+    utils::unix_time_ms,
+    world::{BlockId, ItemId, MobKind, ServerWorldMap},
+    GameFolderPaths, GameServerConfig, TICKS_PER_SECOND,
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+
+use crate::{init::ServerTime, settings::ServerSettings};
+
+use super::{protection::is_player_op, save::world_save_dir};
+
+/// Log of the world events, in the save folder of the world.
+/// Each entry is its length as 4 little endian bytes followed by the entry in bincode
+pub const JOURNAL_FILE: &str = "journal.log";
+/// Ticks between two writes of the new entries to the log
+const JOURNAL_FLUSH_TICKS: u64 = 5 * TICKS_PER_SECOND;
+/// Ticks between two removals of the expired entries, which rewrite the log
+const JOURNAL_COMPACTION_TICKS: u64 = 60 * 60 * TICKS_PER_SECOND;
+/// Entries shown by `/history`, the most recent ones
+const HISTORY_LENGTH: usize = 10;
+
+const HISTORY_USAGE: &str = "Usage: /history <x> <y> <z>";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum JournalEvent {
+    BlockBroken {
+        position: IVec3,
+        block: BlockId,
+    },
+    BlockPlaced {
+        position: IVec3,
+        block: BlockId,
+    },
+    /// Items taken out of the container at `position`
+    ContainerTaken {
+        position: IVec3,
+        item: ItemId,
+        nb: u32,
+    },
+    /// Items put in the container at `position`
+    ContainerPut {
+        position: IVec3,
+        item: ItemId,
+        nb: u32,
+    },
+    MobKilled {
+        kind: MobKind,
+        position: Vec3,
+    },
+    PlayerKilled {
+        victim: String,
+        position: Vec3,
+    },
+    /// The revertible events of `player` since `since` (Unix time in seconds) were reverted
+    RolledBack {
+        player: String,
+        since: u64,
+    },
+}
+
+impl JournalEvent {
+    /// Block the event happened at, `None` for the events about the whole world
+    pub fn block_position(&self) -> Option<IVec3> {
+        match self {
+            JournalEvent::BlockBroken { position, .. }
+            | JournalEvent::BlockPlaced { position, .. }
+            | JournalEvent::ContainerTaken { position, .. }
+            | JournalEvent::ContainerPut { position, .. } => Some(*position),
+            JournalEvent::MobKilled { position, .. }
+            | JournalEvent::PlayerKilled { position, .. } => Some(position.floor().as_ivec3()),
+            JournalEvent::RolledBack { .. } => None,
+        }
+    }
+
+    /// Block changes and container transactions can be reverted by a rollback
+    pub fn is_revertible(&self) -> bool {
+        matches!(
+            self,
+            JournalEvent::BlockBroken { .. }
+                | JournalEvent::BlockPlaced { .. }
+                | JournalEvent::ContainerTaken { .. }
+                | JournalEvent::ContainerPut { .. }
+        )
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            JournalEvent::BlockBroken { block, .. } => format!("broke {block:?}"),
+            JournalEvent::BlockPlaced { block, .. } => format!("placed {block:?}"),
+            JournalEvent::ContainerTaken { item, nb, .. } => format!("took {item:?} x{nb}"),
+            JournalEvent::ContainerPut { item, nb, .. } => format!("put {item:?} x{nb}"),
+            JournalEvent::MobKilled { kind, .. } => format!("killed a {kind:?}"),
+            JournalEvent::PlayerKilled { victim, .. } => format!("killed {victim}"),
+            JournalEvent::RolledBack { player, .. } => format!("rolled back {player}"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    /// Unix time in seconds
+    pub time: u64,
+    /// Name of the player behind the event
+    pub actor: String,
+    pub event: JournalEvent,
+}
+
+/// Append-only history of what the players did to the world, to find out who griefed what.
+/// Every entry kept is in memory too, the retention of the settings bounds them
+#[derive(Resource)]
+pub struct EventJournal {
+    path: PathBuf,
+    entries: Vec<JournalEntry>,
+    /// Entries at the end of `entries` not written to the log yet
+    unwritten: usize,
+    /// Entries older than this are removed when compacting, `None` keeps them all
+    retention_secs: Option<u64>,
+}
+
+pub fn now_secs() -> u64 {
+    unix_time_ms() / 1000
+}
+
+fn write_entry(writer: &mut impl Write, entry: &JournalEntry) -> io::Result<()> {
+    let bytes = bincode::options()
+        .serialize(entry)
+        .map_err(io::Error::other)?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)
+}
+
+/// Reads the entries of a log, an entry cut short by a crash ends it
+fn read_entries(data: &[u8]) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+    let mut rest = data;
+    while rest.len() >= 4 {
+        let len = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let Some(bytes) = rest.get(4..4 + len) else {
+            warn!("The event journal ends with a partial entry, it is dropped");
+            break;
+        };
+        match bincode::options().deserialize(bytes) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => warn!("Invalid entry in the event journal: {}", err),
+        }
+        rest = &rest[4 + len..];
+    }
+    entries
+}
+
+impl EventJournal {
+    pub fn record(&mut self, actor: &str, event: JournalEvent) {
+        self.entries.push(JournalEntry {
+            time: now_secs(),
+            actor: actor.to_string(),
+            event,
+        });
+        self.unwritten += 1;
+    }
+
+    /// Events at `position`, the most recent first
+    pub fn history(&self, position: IVec3) -> impl Iterator<Item = &JournalEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(move |entry| entry.event.block_position() == Some(position))
+    }
+
+    /// Whether a later rollback already reverted the entry
+    pub fn is_rolled_back(&self, entry: &JournalEntry) -> bool {
+        entry.event.is_revertible()
+            && self.entries.iter().any(|rollback| match &rollback.event {
+                JournalEvent::RolledBack { player, since } => {
+                    *player == entry.actor && (*since..=rollback.time).contains(&entry.time)
+                }
+                _ => false,
+            })
+    }
+
+    /// Entries of `player` since `since` not reverted yet, the most recent first
+    pub fn revertible_entries(&self, player: &str, since: u64) -> Vec<JournalEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .take_while(|entry| entry.time >= since)
+            .filter(|entry| entry.actor == player && entry.event.is_revertible())
+            .filter(|entry| !self.is_rolled_back(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Appends the new entries to the log
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.unwritten == 0 {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut writer = BufWriter::new(file);
+        for entry in &self.entries[self.entries.len() - self.unwritten..] {
+            write_entry(&mut writer, entry)?;
+        }
+        writer.flush()?;
+        self.unwritten = 0;
+        Ok(())
+    }
+
+    /// Removes the expired entries and rewrites the log without them
+    pub fn compact(&mut self) -> io::Result<()> {
+        let Some(retention_secs) = self.retention_secs else {
+            return self.flush();
+        };
+        let oldest = now_secs().saturating_sub(retention_secs);
+        let expired = self
+            .entries
+            .iter()
+            .take_while(|entry| entry.time < oldest)
+            .count();
+        if expired == 0 {
+            return self.flush();
+        }
+
+        self.entries.drain(..expired);
+        let compacting = self.path.with_extension("log.compacting");
+        let mut writer = BufWriter::new(File::create(&compacting)?);
+        for entry in &self.entries {
+            write_entry(&mut writer, entry)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&compacting, &self.path)?;
+        self.unwritten = 0;
+        info!("Removed {} expired entries from the event journal", expired);
+        Ok(())
+    }
+}
+
+/// Writes the last entries when the server stops
+impl Drop for EventJournal {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            error!("Could not write the event journal: {}", err);
+        }
+    }
+}
+
+/// Reads the event journal of the world and removes its expired entries
+pub fn load_event_journal(
+    world_name: &str,
+    game_folder_paths: &GameFolderPaths,
+    settings: &ServerSettings,
+) -> EventJournal {
+    let path = world_save_dir(game_folder_paths, world_name).join(JOURNAL_FILE);
+    let entries = fs::read(&path)
+        .map(|data| read_entries(&data))
+        .unwrap_or_default();
+    info!("Loaded {} entries from the event journal", entries.len());
+
+    let mut journal = EventJournal {
+        path,
+        entries,
+        unwritten: 0,
+        retention_secs: (settings.journal_retention_days > 0)
+            .then(|| settings.journal_retention_days as u64 * 24 * 60 * 60),
+    };
+    if let Err(err) = journal.compact() {
+        error!("Could not compact the event journal: {}", err);
+    }
+    journal
+}
+
+/// Writes the new entries every few seconds, and compacts the log every hour
+pub fn journal_flush_system(mut journal: ResMut<EventJournal>, time: Res<ServerTime>) {
+    let result = if time.0 > 0 && time.0.is_multiple_of(JOURNAL_COMPACTION_TICKS) {
+        journal.compact()
+    } else if time.0.is_multiple_of(JOURNAL_FLUSH_TICKS) {
+        journal.flush()
+    } else {
+        return;
+    };
+    if let Err(err) = result {
+        error!("Could not write the event journal: {}", err);
+    }
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s ago"),
+        60..3600 => format!("{}min ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Handles `/history <x> <y> <z>`, ops only as it tells what other players did
+pub fn history_command(
+    world_map: &ServerWorldMap,
+    journal: &EventJournal,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to read its history".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can read the history of the world".to_string();
+    }
+    let coords: Option<Vec<i32>> = args.iter().map(|arg| arg.parse().ok()).collect();
+    let Some([x, y, z]) = coords.as_deref() else {
+        return HISTORY_USAGE.to_string();
+    };
+    let position = IVec3::new(*x, *y, *z);
+
+    let now = now_secs();
+    let lines: Vec<String> = journal
+        .history(position)
+        .take(HISTORY_LENGTH)
+        .map(|entry| {
+            format!(
+                "{} {} {}{}",
+                format_age(now.saturating_sub(entry.time)),
+                entry.actor,
+                entry.event.describe(),
+                if journal.is_rolled_back(entry) {
+                    " (rolled back)"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        return format!("Nothing happened at {x} {y} {z}");
+    }
+    format!("History of {x} {y} {z}:\n{}", lines.join("\n"))
+}
@@ -2,18 +2,22 @@ use bevy::prelude::*;
 use ron::de::from_str;
 use shared::messages::{PlayerId, PlayerSave};
 use shared::world::data::WorldSeed;
-use shared::GameFolderPaths;
+use shared::world::ServerChunk;
+use shared::{GameFolderPaths, GameServerConfig};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use crate::world::data::SAVE_PATH;
+use crate::world::data::{CHUNKS_SAVE_DIR, SAVE_PATH};
 use crate::world::save::WorldData;
 use std::path::PathBuf;
 
+/// Reads the world named in the config, the other options of the config are used if it does not exist yet
 pub fn load_world_data(
-    file_name: &str,
+    config: &GameServerConfig,
     game_folder_paths: &GameFolderPaths,
 ) -> Result<WorldData, Box<dyn std::error::Error>> {
+    let file_name = &config.world_name;
     let file_path: PathBuf = game_folder_paths
         .game_folder_path
         .join(SAVE_PATH)
@@ -25,10 +29,12 @@ pub fn load_world_data(
             "World data file not found: {}. Generating default world and seed.",
             file_path.display()
         );
-        let seed = WorldSeed(rand::random::<u32>());
+        let seed = WorldSeed(config.world_seed.unwrap_or_else(rand::random::<u32>));
         return Ok(WorldData {
             name: file_name.to_string(),
             seed,
+            gen_settings: config.world_gen_settings.unwrap_or_default(),
+            spawn_platform_pending: config.spawn_platform,
             ..default()
         });
     }
@@ -41,6 +47,67 @@ pub fn load_world_data(
     Ok(world_data)
 }
 
+/// Loads every chunk saved in the world's chunks folder, unreadable files are skipped
+pub fn load_chunks_data(
+    world_name: &str,
+    game_folder_paths: &GameFolderPaths,
+) -> HashMap<IVec3, ServerChunk> {
+    let chunks_dir: PathBuf = game_folder_paths
+        .game_folder_path
+        .join(SAVE_PATH)
+        .join(world_name)
+        .join(CHUNKS_SAVE_DIR);
+
+    let mut chunks = HashMap::new();
+    let Ok(entries) = fs::read_dir(&chunks_dir) else {
+        return chunks;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(chunk_pos) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_chunk_file_name)
+        else {
+            continue;
+        };
+
+        match load_chunk_file(&path) {
+            Ok(chunk) => {
+                chunks.insert(chunk_pos, chunk);
+            }
+            Err(e) => error!("Could not load chunk file {}: {}", path.display(), e),
+        }
+    }
+
+    info!(
+        "Loaded {} chunks from {}",
+        chunks.len(),
+        chunks_dir.display()
+    );
+
+    chunks
+}
+
+pub fn load_chunk_file(path: &Path) -> Result<ServerChunk, String> {
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| from_str::<ServerChunk>(&contents).map_err(|e| e.to_string()))
+}
+
+// Inverse of `save::chunk_file_name`
+pub fn parse_chunk_file_name(file_name: &str) -> Option<IVec3> {
+    let mut coords = file_name.strip_suffix(".ron")?.split('_');
+    let x = coords.next()?.parse().ok()?;
+    let y = coords.next()?.parse().ok()?;
+    let z = coords.next()?.parse().ok()?;
+    if coords.next().is_some() {
+        return None;
+    }
+    Some(IVec3::new(x, y, z))
+}
+
 pub fn load_player_data(
     world_name: &str,
     player_id: &PlayerId,
@@ -71,5 +138,8 @@ pub fn load_player_data(
         position: Vec3::new(0., 80., 0.),
         camera_transform: Transform::default(),
         is_flying: false,
+        unlocked_recipes: Default::default(),
+        game_mode: Default::default(),
+        seen_hints: Default::default(),
     }
 }
@@ -1,31 +1,75 @@
+pub mod afk;
+pub mod anvil;
+pub mod autosave;
 pub mod background_generation;
+pub mod backup;
+pub mod block_break;
 pub mod broadcast_world;
+pub mod containers;
+pub mod crash;
 pub(crate) mod data;
+pub mod effects;
+pub mod emotes;
+pub mod entity_data;
+pub mod fire;
+pub mod gamerules;
 pub mod generation;
+pub mod health;
+pub mod hints;
+pub mod item_use;
+pub mod journal;
 pub mod load_from_file;
+pub mod ores;
+pub mod packs;
+pub mod pregen;
+pub mod preset;
+pub mod projectiles;
+pub mod protection;
+pub mod pushback;
+pub mod recipes;
+pub mod reload;
+pub mod rollback;
 pub mod save;
 pub mod simulation;
+pub mod snow;
+pub mod spatial;
+pub mod spawn_chunks;
+pub mod spawn_platform;
+pub mod spectate;
 pub mod stacks;
+pub mod streaming;
+pub mod tick_control;
+pub mod trim;
+pub mod weather;
 
 use bevy::prelude::Event;
 use bevy::prelude::EventReader;
 use bevy::prelude::IVec3;
 use bevy::prelude::ResMut;
 use bevy::prelude::*;
-use shared::world::{BlockData, ItemStack, ServerItemStack, ServerWorldMap, WorldMap};
-use ulid::Ulid;
+use shared::world::{
+    BlockData, ItemStack, RandomStream, ServerWorldMap, SimulationRandom, WorldMap,
+};
+use stacks::spawn_item_stack;
+
+use crate::init::ServerTime;
 
 #[derive(Event, Debug)]
 pub struct BlockInteractionEvent {
     pub position: IVec3,
     pub block_type: Option<BlockData>, // None = delete, Some = add
+    /// Whether a deleted block leaves its drops, reverted edits do not
+    pub drops: bool,
 }
 
 pub fn handle_block_interactions(
     mut world_map: ResMut<ServerWorldMap>,
     mut events: EventReader<BlockInteractionEvent>,
+    time: Res<ServerTime>,
+    random: Res<SimulationRandom>,
 ) {
     for event in events.read() {
+        world_map.chunks.mark_player_modified(&event.position);
         match &event.block_type {
             Some(block) => {
                 world_map.chunks.set_block(&event.position, *block);
@@ -33,28 +77,27 @@ pub fn handle_block_interactions(
             }
             None => {
                 debug!("Getting block by coordinates at {:?}", event.position);
-                for (id, nb) in world_map
-                    .chunks
-                    .get_block_by_coordinates(&event.position)
-                    .unwrap()
-                    .id
-                    .get_drops(1)
-                {
-                    world_map.item_stacks.push(ServerItemStack {
-                        id: Ulid::new().0,
-                        despawned: false,
-                        stack: ItemStack {
-                            item_id: id,
-                            item_type: id.get_default_type(),
-                            nb,
-                        },
-                        pos: Vec3::new(
-                            event.position.x as f32,
-                            event.position.y as f32,
-                            event.position.z as f32,
-                        ),
-                        timestamp: 0,
-                    });
+                let Some(block) = world_map.chunks.get_block_by_coordinates(&event.position) else {
+                    continue;
+                };
+                let mut rng = random.at_block(RandomStream::BlockDrops, time.0, event.position);
+                let drops = if event.drops {
+                    block.id.get_drops(1, &mut rng)
+                } else {
+                    Default::default()
+                };
+                for (id, nb) in drops {
+                    let stack = ItemStack {
+                        item_id: id,
+                        item_type: id.get_default_type(),
+                        nb,
+                    };
+                    spawn_item_stack(
+                        &mut world_map.item_stacks,
+                        stack,
+                        event.position.as_vec3(),
+                        &mut rng,
+                    );
                 }
 
                 world_map
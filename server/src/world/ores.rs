@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use shared::{
+    world::{BlockId, ServerChunk, ServerChunkWorldMap},
+    CHUNK_SIZE,
+};
+use std::collections::BTreeMap;
+
+use super::{generation::ChunkRng, preset::WorldGenPreset};
+
+/// How the veins of an ore spread along the height of the world
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OreHeight {
+    /// Any height between the two, equally likely
+    Uniform { min_y: i32, max_y: i32 },
+    /// Most common at `peak_y`, less and less towards the two ends
+    Triangle { min_y: i32, peak_y: i32, max_y: i32 },
+}
+
+impl OreHeight {
+    /// Picks a height from a number in [0, 1)
+    fn sample(&self, u: f32) -> i32 {
+        match *self {
+            Self::Uniform { min_y, max_y } => min_y + ((max_y - min_y) as f32 * u) as i32,
+            Self::Triangle {
+                min_y,
+                peak_y,
+                max_y,
+            } => {
+                let (min, peak, max) = (min_y as f32, peak_y as f32, max_y as f32);
+                if max <= min {
+                    return min_y;
+                }
+                let split = (peak - min) / (max - min);
+                let y = if u < split {
+                    min + (u * (max - min) * (peak - min)).sqrt()
+                } else {
+                    max - ((1.0 - u) * (max - min) * (max - peak)).sqrt()
+                };
+                y as i32
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OreSettings {
+    /// Block placed by the veins, it only replaces stone
+    pub block: BlockId,
+    /// Blocks in a vein, fewer if it goes through caves or out of the stone
+    pub vein_size: u32,
+    /// Average number of veins in a chunk column
+    pub veins_per_column: f32,
+    pub height: OreHeight,
+}
+
+/// Ores used when the preset file does not set them
+pub fn default_ores() -> Vec<OreSettings> {
+    vec![
+        OreSettings {
+            block: BlockId::CoalOre,
+            vein_size: 12,
+            veins_per_column: 20.0,
+            height: OreHeight::Triangle {
+                min_y: 0,
+                peak_y: 96,
+                max_y: 192,
+            },
+        },
+        OreSettings {
+            block: BlockId::IronOre,
+            vein_size: 8,
+            veins_per_column: 12.0,
+            height: OreHeight::Triangle {
+                min_y: -32,
+                peak_y: 16,
+                max_y: 80,
+            },
+        },
+        OreSettings {
+            block: BlockId::GoldOre,
+            vein_size: 6,
+            veins_per_column: 4.0,
+            height: OreHeight::Triangle {
+                min_y: -64,
+                peak_y: -16,
+                max_y: 32,
+            },
+        },
+        OreSettings {
+            block: BlockId::DiamondOre,
+            vein_size: 4,
+            veins_per_column: 2.0,
+            height: OreHeight::Uniform {
+                min_y: -64,
+                max_y: 16,
+            },
+        },
+    ]
+}
+
+/// Veins get their own generator for each chunk column, so that every chunk of the column and of its neighbors sees the same veins
+const ORE_SEED_OFFSET: u32 = 0x4F52_4500;
+
+const VEIN_STEPS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// Blocks of the veins started in a chunk column, in world coordinates
+fn column_veins(seed: u32, ore_index: usize, ore: &OreSettings, column: IVec2) -> Vec<IVec3> {
+    let mut rng = ChunkRng::new(
+        seed.wrapping_add(ORE_SEED_OFFSET)
+            .wrapping_add(ore_index as u32),
+        IVec3::new(column.x, 0, column.y),
+    );
+    let veins = ore.veins_per_column.max(0.0);
+    let mut count = veins as u32;
+    if rng.next_f32() < veins.fract() {
+        count += 1;
+    }
+
+    let mut blocks = Vec::new();
+    for _ in 0..count {
+        let mut position = IVec3::new(
+            column.x * CHUNK_SIZE + (rng.next_f32() * CHUNK_SIZE as f32) as i32,
+            ore.height.sample(rng.next_f32()),
+            column.y * CHUNK_SIZE + (rng.next_f32() * CHUNK_SIZE as f32) as i32,
+        );
+        for _ in 0..ore.vein_size {
+            blocks.push(position);
+            position += VEIN_STEPS[(rng.next_u64() % 6) as usize];
+        }
+    }
+    blocks
+}
+
+/// Turns the stone of the chunk into ores. Veins may start in a neighboring column,
+/// every chunk places the part of them inside itself so they are never cut at the borders.
+pub fn ores_stage(chunk: &mut ServerChunk, chunk_pos: IVec3, seed: u32, preset: &WorldGenPreset) {
+    let chunk_min = chunk_pos * CHUNK_SIZE;
+    let chunk_max = chunk_min + IVec3::splat(CHUNK_SIZE);
+
+    for (ore_index, ore) in preset.ores.iter().enumerate() {
+        for offset_x in -1..=1 {
+            for offset_z in -1..=1 {
+                let column = IVec2::new(chunk_pos.x + offset_x, chunk_pos.z + offset_z);
+                for position in column_veins(seed, ore_index, ore, column) {
+                    if position.cmplt(chunk_min).any() || position.cmpge(chunk_max).any() {
+                        continue;
+                    }
+                    if let Some(block) = chunk.map.get_mut(&(position - chunk_min)) {
+                        if block.id == BlockId::Stone {
+                            block.id = ore.block;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Number of blocks of each ore of the preset in the generated chunks, with the stone they replaced
+pub fn ore_density_report(chunks: &ServerChunkWorldMap, preset: &WorldGenPreset) -> Vec<String> {
+    let mut counts: BTreeMap<BlockId, u64> = preset.ores.iter().map(|ore| (ore.block, 0)).collect();
+    let mut stone = 0u64;
+    for chunk in chunks.map.values() {
+        for block in chunk.map.values() {
+            if block.id == BlockId::Stone {
+                stone += 1;
+            } else if let Some(count) = counts.get_mut(&block.id) {
+                *count += 1;
+            }
+        }
+    }
+
+    let chunk_count = chunks.map.len().max(1) as f64;
+    let underground = (stone + counts.values().sum::<u64>()).max(1) as f64;
+    let mut lines = vec![format!(
+        "Ores in {} generated chunks, {} stone blocks:",
+        chunks.map.len(),
+        stone
+    )];
+    for (block, count) in counts {
+        lines.push(format!(
+            "{:?}: {} blocks, {:.1} per chunk, {:.2}% of the stone",
+            block,
+            count,
+            count as f64 / chunk_count,
+            count as f64 * 100.0 / underground
+        ));
+    }
+    lines
+}
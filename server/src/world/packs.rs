@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, RenetServer};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use shared::{
+    messages::{
+        packs::{
+            pack_hash, PackChunk, PackDownloadRequest, PackInfo, PackKind, MAX_PACK_SIZE,
+            PACK_CHUNK_SIZE,
+        },
+        ServerToClientMessage,
+    },
+    world::MobKind,
+    GameFolderPaths,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    io::Read,
+    path::{Component, Path},
+};
+use tar::EntryType;
+
+use crate::network::extensions::SendGameMessageExtension;
+
+use super::{save::world_save_dir, spawn_platform::LootEntry};
+
+/// Lists the packs of a world, in its save folder
+pub const PACKS_MANIFEST_FILE: &str = "packs.ron";
+/// Folder of the world holding the pack archives
+pub const PACKS_DIR: &str = "packs/";
+/// File of a data pack replacing the drops of mobs
+pub const LOOT_TABLES_FILE: &str = "loot_tables.ron";
+/// Chunks of pack sent to each downloading player per tick, to leave room for the game messages
+const PACK_CHUNKS_PER_TICK: usize = 8;
+/// Most bytes the files of a pack may take once unpacked, so that a small archive cannot fill the disk
+const MAX_UNPACKED_PACK_SIZE: u64 = 4 * MAX_PACK_SIZE;
+
+/// A line of `packs.ron`
+#[derive(Deserialize, Debug)]
+struct PackDeclaration {
+    /// Archive in the `packs/` folder of the world, as `.tar.gz`
+    file: String,
+    kind: PackKind,
+    #[serde(default)]
+    required: bool,
+}
+
+/// Packs attached to the world, sent to the players accepting them
+#[derive(Resource, Default)]
+pub struct WorldPacks {
+    packs: Vec<(PackInfo, Vec<u8>)>,
+    /// Drops of the mobs, replacing the built-in ones, from the data packs
+    pub loot_tables: HashMap<MobKind, Vec<LootEntry>>,
+}
+
+impl WorldPacks {
+    pub fn infos(&self) -> Vec<PackInfo> {
+        self.packs.iter().map(|(info, _)| info.clone()).collect()
+    }
+
+    /// Part of the pack starting at `offset`, `None` past its end or for an unknown pack
+    pub fn chunk(&self, hash: &str, offset: u64) -> Option<PackChunk> {
+        let (info, data) = self.packs.iter().find(|(info, _)| info.hash == hash)?;
+        let start = usize::try_from(offset)
+            .ok()
+            .filter(|start| *start < data.len())?;
+        let end = (start + PACK_CHUNK_SIZE).min(data.len());
+        Some(PackChunk {
+            hash: info.hash.clone(),
+            offset,
+            data: data[start..end].to_vec(),
+        })
+    }
+}
+
+/// Packs being sent to each player, in the order they asked for them
+#[derive(Resource, Default)]
+pub struct PackTransfers(pub HashMap<ClientId, VecDeque<PackDownloadRequest>>);
+
+/// Streams a few chunks of the requested packs to each player, forgetting the players that left
+pub fn send_pack_chunks_system(
+    mut server: ResMut<RenetServer>,
+    mut transfers: ResMut<PackTransfers>,
+    packs: Res<WorldPacks>,
+) {
+    transfers.0.retain(|client_id, queue| {
+        if !server.is_connected(*client_id) {
+            return false;
+        }
+        for _ in 0..PACK_CHUNKS_PER_TICK {
+            let Some(request) = queue.front_mut() else {
+                break;
+            };
+            match packs.chunk(&request.hash, request.offset) {
+                Some(chunk) => {
+                    request.offset += chunk.data.len() as u64;
+                    server.send_game_message(*client_id, ServerToClientMessage::PackChunk(chunk));
+                }
+                None => {
+                    queue.pop_front();
+                }
+            }
+        }
+        !queue.is_empty()
+    });
+}
+
+/// Reads the packs declared in `packs.ron`, leaving out the missing, invalid or too big ones
+pub fn load_world_packs(world_name: &str, game_folder_paths: &GameFolderPaths) -> WorldPacks {
+    let world_dir = world_save_dir(game_folder_paths, world_name);
+    let mut packs = WorldPacks::default();
+    let Ok(contents) = fs::read_to_string(world_dir.join(PACKS_MANIFEST_FILE)) else {
+        return packs;
+    };
+    let declarations = match ron::from_str::<Vec<PackDeclaration>>(&contents) {
+        Ok(declarations) => declarations,
+        Err(err) => {
+            error!(
+                "Invalid {}, no pack is attached: {}",
+                PACKS_MANIFEST_FILE, err
+            );
+            return packs;
+        }
+    };
+
+    for declaration in declarations {
+        let path = world_dir.join(PACKS_DIR).join(&declaration.file);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(err) => {
+                error!("Could not read pack {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        if data.len() as u64 > MAX_PACK_SIZE {
+            error!(
+                "Pack {} is bigger than {} bytes, it is left out",
+                path.display(),
+                MAX_PACK_SIZE
+            );
+            continue;
+        }
+
+        if declaration.kind == PackKind::Data {
+            let tables = read_pack_file(&data, LOOT_TABLES_FILE).and_then(|contents| {
+                contents
+                    .map(|contents| {
+                        ron::from_str::<HashMap<MobKind, Vec<LootEntry>>>(&contents)
+                            .map_err(|err| err.to_string())
+                    })
+                    .transpose()
+            });
+            match tables {
+                Ok(Some(tables)) => packs.loot_tables.extend(tables),
+                Ok(None) => {}
+                Err(err) => {
+                    error!("Invalid data pack {}: {}", path.display(), err);
+                    continue;
+                }
+            }
+        }
+
+        let info = PackInfo {
+            name: declaration.file,
+            kind: declaration.kind,
+            size: data.len() as u64,
+            hash: pack_hash(&data),
+            required: declaration.required,
+        };
+        info!(
+            "Attached {:?} pack {} ({})",
+            info.kind, info.name, info.hash
+        );
+        packs.packs.push((info, data));
+    }
+    packs
+}
+
+/// Content of a file at the root of a pack archive, `None` if the pack has none
+pub fn read_pack_file(data: &[u8], name: &str) -> Result<Option<String>, String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(data));
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        if entry.path().map_err(|e| e.to_string())? == Path::new(name) {
+            let mut contents = String::new();
+            entry
+                .read_to_string(&mut contents)
+                .map_err(|e| e.to_string())?;
+            return Ok(Some(contents));
+        }
+    }
+    Ok(None)
+}
+
+/// Extracts a pack archive into `target_dir`, refusing the entries that would land outside of it,
+/// links, which later entries could write through, and archives unpacking to more than `MAX_UNPACKED_PACK_SIZE`.
+/// Only the client uses it, through the library
+#[allow(dead_code)]
+pub fn extract_pack_archive(data: &[u8], target_dir: &Path) -> Result<(), String> {
+    extract_archive(data, target_dir, MAX_UNPACKED_PACK_SIZE)
+}
+
+fn extract_archive(data: &[u8], target_dir: &Path, max_size: u64) -> Result<(), String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(data));
+    let mut unpacked_size = 0u64;
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path().map_err(|e| e.to_string())?.into_owned();
+        if path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(format!("Unexpected path in pack: {}", path.display()));
+        }
+        let entry_type = entry.header().entry_type();
+        if !matches!(entry_type, EntryType::Regular | EntryType::Directory) {
+            return Err(format!(
+                "Unexpected entry in pack: {} is a {:?}",
+                path.display(),
+                entry_type
+            ));
+        }
+
+        unpacked_size += entry.size();
+        if unpacked_size > max_size {
+            return Err(format!("Pack unpacks to more than {} bytes", max_size));
+        }
+        if !entry.unpack_in(target_dir).map_err(|e| e.to_string())? {
+            return Err(format!("Unexpected path in pack: {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::path::PathBuf;
+
+    /// Empty folder of its own for each test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rustcraft-packs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn archive(build: impl FnOnce(&mut tar::Builder<GzEncoder<Vec<u8>>>)) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        build(&mut builder);
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn file_header(size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(EntryType::Regular);
+        header.set_size(size);
+        header.set_mode(0o644);
+        header
+    }
+
+    #[test]
+    fn files_are_extracted() {
+        let dir = test_dir("files");
+        let data = archive(|builder| {
+            let contents = b"texture";
+            builder
+                .append_data(
+                    &mut file_header(contents.len() as u64),
+                    "blocks/stone.png",
+                    &contents[..],
+                )
+                .unwrap();
+        });
+
+        extract_pack_archive(&data, &dir).unwrap();
+        assert_eq!(fs::read(dir.join("blocks/stone.png")).unwrap(), b"texture");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn links_are_refused() {
+        let dir = test_dir("links");
+        let data = archive(|builder| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, "escape", "/tmp").unwrap();
+            let contents = b"outside";
+            builder
+                .append_data(
+                    &mut file_header(contents.len() as u64),
+                    "escape/file",
+                    &contents[..],
+                )
+                .unwrap();
+        });
+
+        assert!(extract_pack_archive(&data, &dir).is_err());
+        assert!(fs::symlink_metadata(dir.join("escape")).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn oversized_packs_are_refused() {
+        let dir = test_dir("oversized");
+        let data = archive(|builder| {
+            for name in ["first.bin", "second.bin"] {
+                builder
+                    .append_data(&mut file_header(600), name, std::io::repeat(0).take(600))
+                    .unwrap();
+            }
+        });
+
+        assert!(extract_archive(&data, &dir, 1000).is_err());
+        assert!(dir.join("first.bin").exists());
+        assert!(!dir.join("second.bin").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use shared::{
+    messages::ChatConversation,
+    world::{ServerChunkWorldMap, ServerWorldMap, WorldSeed},
+    GameServerConfig,
+};
+
+use crate::network::broadcast_chat::{push_server_chat_message, ChatMessageEvent};
+
+use super::{
+    generation::{generate_chunk, insert_generated_chunk, GeneratedChunk},
+    preset::WorldGenPreset,
+    save::SaveRequestEvent,
+};
+
+/// Largest radius accepted, in chunks around spawn
+pub const MAX_PREGEN_RADIUS: u32 = 64;
+/// Chunks generated at the same time on the async compute pool
+const MAX_TASKS_IN_FLIGHT: usize = 4;
+/// Generated chunks added to the world per tick, so that pregeneration does not starve the live tick
+const MAX_INSERTS_PER_TICK: usize = 2;
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+struct PregenJob {
+    radius: u32,
+    queue: VecDeque<IVec3>,
+    tasks: Vec<(IVec3, Task<GeneratedChunk>)>,
+    total: usize,
+    done: usize,
+    started_at: Instant,
+    last_report: Instant,
+}
+
+/// Chunks around spawn being generated ahead of time, so that players exploring them later do not wait for them
+#[derive(Resource, Default)]
+pub struct Pregeneration {
+    job: Option<PregenJob>,
+}
+
+/// Queues the generation of the chunks missing within `radius` chunks of spawn, returns a message for the requester
+pub fn start_pregeneration(
+    pregen: &mut Pregeneration,
+    chunks: &ServerChunkWorldMap,
+    radius: u32,
+) -> String {
+    if let Some(job) = &pregen.job {
+        return format!(
+            "Pregeneration already running ({}/{} chunks)",
+            job.done, job.total
+        );
+    }
+    if radius > MAX_PREGEN_RADIUS {
+        return format!("The radius can be at most {MAX_PREGEN_RADIUS} chunks");
+    }
+
+    let radius_i = radius as i32;
+    let mut positions: Vec<IVec3> = chunks
+        .gen_settings
+        .chunk_y_range()
+        .flat_map(|y| {
+            (-radius_i..=radius_i)
+                .flat_map(move |x| (-radius_i..=radius_i).map(move |z| IVec3::new(x, y, z)))
+        })
+        .filter(|pos| !chunks.map.contains_key(pos))
+        .collect();
+    // Closest chunks first, they are the most likely to be visited soon
+    positions.sort_by_key(|pos| (pos.x.abs().max(pos.z.abs()), pos.y, pos.x, pos.z));
+
+    if positions.is_empty() {
+        return format!("All chunks within {radius} chunks of spawn are already generated");
+    }
+
+    let now = Instant::now();
+    let total = positions.len();
+    pregen.job = Some(PregenJob {
+        radius,
+        queue: positions.into(),
+        tasks: Vec::new(),
+        total,
+        done: 0,
+        started_at: now,
+        last_report: now,
+    });
+    format!("Pregenerating {total} chunks within {radius} chunks of spawn")
+}
+
+/// Drops the chunks not generated yet, the ones being generated are discarded
+pub fn cancel_pregeneration(pregen: &mut Pregeneration) -> String {
+    match pregen.job.take() {
+        Some(job) => format!(
+            "Pregeneration cancelled after {}/{} chunks",
+            job.done, job.total
+        ),
+        None => "No pregeneration is running".to_string(),
+    }
+}
+
+/// Starts the pregeneration asked for on the command line
+pub fn pregen_from_config_system(
+    config: Res<GameServerConfig>,
+    world_map: Res<ServerWorldMap>,
+    mut pregen: ResMut<Pregeneration>,
+) {
+    if let Some(radius) = config.pregen_radius {
+        info!(
+            "{}",
+            start_pregeneration(&mut pregen, &world_map.chunks, radius)
+        );
+    }
+}
+
+pub fn pregeneration_system(
+    mut pregen: ResMut<Pregeneration>,
+    mut world_map: ResMut<ServerWorldMap>,
+    seed: Res<WorldSeed>,
+    preset: Res<WorldGenPreset>,
+    mut chat_conversation: ResMut<ChatConversation>,
+    mut ev_chat: EventWriter<ChatMessageEvent>,
+    mut ev_save: EventWriter<SaveRequestEvent>,
+) {
+    let Some(job) = pregen.job.as_mut() else {
+        return;
+    };
+    let chunks = &mut world_map.chunks;
+
+    // Finished chunks are added first, a few of them per tick
+    let mut inserted = 0;
+    let mut index = 0;
+    while index < job.tasks.len() && inserted < MAX_INSERTS_PER_TICK {
+        if !job.tasks[index].1.is_finished() {
+            index += 1;
+            continue;
+        }
+        let (chunk_pos, task) = job.tasks.swap_remove(index);
+        let generated = block_on(task);
+        // Players may have walked there in the meantime
+        if !chunks.map.contains_key(&chunk_pos) {
+            insert_generated_chunk(chunks, chunk_pos, generated);
+        }
+        job.done += 1;
+        inserted += 1;
+    }
+
+    let pool = AsyncComputeTaskPool::get();
+    while job.tasks.len() < MAX_TASKS_IN_FLIGHT {
+        let Some(chunk_pos) = job.queue.pop_front() else {
+            break;
+        };
+        if chunks.map.contains_key(&chunk_pos) {
+            job.done += 1;
+            continue;
+        }
+        let seed = seed.0;
+        let limits = chunks.gen_settings;
+        let preset = preset.clone();
+        let task = pool.spawn(async move { generate_chunk(chunk_pos, seed, limits, &preset) });
+        job.tasks.push((chunk_pos, task));
+    }
+
+    let finished = job.queue.is_empty() && job.tasks.is_empty();
+    let now = Instant::now();
+    if finished {
+        let message = format!(
+            "Pregenerated {} chunks within {} chunks of spawn in {}s",
+            job.total,
+            job.radius,
+            job.started_at.elapsed().as_secs()
+        );
+        push_server_chat_message(&mut chat_conversation, message);
+        ev_chat.write(ChatMessageEvent);
+        ev_save.write(SaveRequestEvent::World);
+        pregen.job = None;
+    } else if now.duration_since(job.last_report) >= PROGRESS_REPORT_INTERVAL {
+        job.last_report = now;
+        let message = format!(
+            "Pregenerating: {}/{} chunks ({}%)",
+            job.done,
+            job.total,
+            job.done * 100 / job.total
+        );
+        push_server_chat_message(&mut chat_conversation, message);
+        ev_chat.write(ChatMessageEvent);
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use shared::{
+    messages::packs::{pack_hash, PackInfo},
+    world::{WorldGenSettings, WorldSeed},
+    GameFolderPaths,
+};
+use std::fs;
+
+use super::{
+    ores::{default_ores, OreSettings},
+    save::world_save_dir,
+    spawn_platform::{default_starter_chest, LootEntry},
+};
+
+pub const WORLD_GEN_PRESET_FILE: &str = "worldgen.ron";
+
+/// Generation settings of a world that can be edited in its save folder, the new chunks use them
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct WorldGenPreset {
+    pub ores: Vec<OreSettings>,
+    /// Loot table of the chest on the spawn platform, for the worlds created with one
+    pub starter_chest: Vec<LootEntry>,
+}
+
+impl Default for WorldGenPreset {
+    fn default() -> Self {
+        Self {
+            ores: default_ores(),
+            starter_chest: default_starter_chest(),
+        }
+    }
+}
+
+/// Identifies the world to the clients caching its chunks. It changes along with the generation preset,
+/// the height limits and the packs, so that chunks generated differently are not mistaken for the cached ones
+#[derive(Resource, Debug, Clone)]
+pub struct WorldCacheId(pub String);
+
+impl WorldCacheId {
+    pub fn new(
+        name: &str,
+        seed: WorldSeed,
+        settings: WorldGenSettings,
+        preset: &WorldGenPreset,
+        packs: &[PackInfo],
+    ) -> Self {
+        let mut fingerprint = ron::to_string(&(settings, preset)).unwrap_or_default();
+        for pack in packs {
+            fingerprint.push_str(&pack.hash);
+        }
+        let fingerprint = pack_hash(fingerprint.as_bytes());
+        Self(format!("{}-{}-{}", name, seed.0, &fingerprint[..16]))
+    }
+}
+
+/// Reads the preset of the world, writing the default one if it does not exist yet
+pub fn load_world_gen_preset(
+    world_name: &str,
+    game_folder_paths: &GameFolderPaths,
+) -> WorldGenPreset {
+    let world_dir = world_save_dir(game_folder_paths, world_name);
+    let path = world_dir.join(WORLD_GEN_PRESET_FILE);
+
+    if let Ok(contents) = fs::read_to_string(&path) {
+        return match ron::from_str::<WorldGenPreset>(&contents) {
+            Ok(preset) => preset,
+            Err(err) => {
+                error!(
+                    "Invalid world generation preset {}, using defaults : {}",
+                    path.display(),
+                    err
+                );
+                WorldGenPreset::default()
+            }
+        };
+    }
+
+    let preset = WorldGenPreset::default();
+    let written = ron::ser::to_string_pretty(&preset, PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            fs::create_dir_all(&world_dir)
+                .and_then(|_| fs::write(&path, contents))
+                .map_err(|err| err.to_string())
+        });
+    if let Err(err) = written {
+        warn!(
+            "Could not write the default world generation preset to {} : {}",
+            path.display(),
+            err
+        );
+    }
+    preset
+}
@@ -0,0 +1,138 @@
+use bevy::math::bounding::{Aabb3d, IntersectsVolume};
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use rand::Rng;
+use shared::{
+    messages::{
+        projectile::{ProjectileDespawnEvent, ProjectileSpawnEvent},
+        PlayerId, ServerToClientMessage,
+    },
+    players::DamageSource,
+    world::{new_entity_id, MobId, Projectile, ServerWorldMap, WorldMap},
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+
+use super::health::PlayerDamageEvent;
+
+/// Hit tests are done every that many blocks along the projectile path, so that fast projectiles do not go through thin targets
+const HIT_TEST_STEP: f32 = 0.25;
+const PROJECTILE_HALF_SIZE: f32 = 0.05;
+
+/// Launches a projectile, its id is drawn from `ids`
+pub fn spawn_projectile(
+    world_map: &mut ServerWorldMap,
+    server: &mut RenetServer,
+    projectile: Projectile,
+    ids: &mut impl Rng,
+) {
+    let id = new_entity_id(ids, |id| world_map.projectiles.contains_key(&id));
+    server.broadcast_game_message(ServerToClientMessage::ProjectileSpawn(
+        ProjectileSpawnEvent {
+            id,
+            projectile: projectile.clone(),
+        },
+    ));
+    world_map.projectiles.insert(id, projectile);
+}
+
+enum ProjectileHit {
+    Block,
+    Player(PlayerId),
+    Mob(MobId),
+}
+
+fn find_hit(
+    world_map: &ServerWorldMap,
+    projectile: &Projectile,
+    point: Vec3,
+) -> Option<ProjectileHit> {
+    let hitbox = Aabb3d::new(point, Vec3::splat(PROJECTILE_HALF_SIZE));
+
+    if world_map.chunks.check_collision_box(&hitbox) {
+        return Some(ProjectileHit::Block);
+    }
+
+    for player in world_map.players.values() {
+        if matches!(projectile.source, DamageSource::Player { id, .. } if id == player.id) {
+            continue;
+        }
+        let player_box = Aabb3d::new(
+            player.position,
+            Vec3::new(player.width, player.height, player.width) / 2.0,
+        );
+        if player_box.intersects(&hitbox) {
+            return Some(ProjectileHit::Player(player.id));
+        }
+    }
+
+    for (id, mob) in world_map.mobs.iter() {
+        if matches!(projectile.source, DamageSource::Mob { id: shooter, .. } if shooter == *id) {
+            continue;
+        }
+        let mob_box = Aabb3d::new(
+            mob.position,
+            Vec3::new(mob.width, mob.height, mob.deepth) / 2.0,
+        );
+        if mob_box.intersects(&hitbox) {
+            return Some(ProjectileHit::Mob(*id));
+        }
+    }
+
+    None
+}
+
+pub fn simulate_projectiles_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    mut ev_damage: EventWriter<PlayerDamageEvent>,
+    time: Res<Time>,
+) {
+    let world_map = world_map.as_mut();
+    let delta = time.delta_secs();
+
+    let mut projectiles = std::mem::take(&mut world_map.projectiles);
+    let mut finished = Vec::new();
+
+    for (id, projectile) in projectiles.iter_mut() {
+        let start = projectile.position;
+        projectile.step(delta);
+
+        let travelled = start.distance(projectile.position);
+        let steps = (travelled / HIT_TEST_STEP).ceil().max(1.0) as u32;
+
+        let hit = (1..=steps).find_map(|i| {
+            let point = start.lerp(projectile.position, i as f32 / steps as f32);
+            find_hit(world_map, projectile, point)
+        });
+
+        match hit {
+            Some(ProjectileHit::Player(player_id)) => {
+                ev_damage.write(PlayerDamageEvent {
+                    player_id,
+                    amount: projectile.kind.damage(),
+                    source: projectile.source.clone(),
+                });
+            }
+            Some(ProjectileHit::Mob(mob_id)) => {
+                if let Some(mob) = world_map.mobs.get_mut(&mob_id) {
+                    mob.damage(projectile.kind.damage());
+                }
+            }
+            Some(ProjectileHit::Block) | None => {}
+        }
+
+        if hit.is_some() || projectile.age > projectile.kind.lifetime_secs() {
+            finished.push(*id);
+        }
+    }
+
+    for id in finished {
+        projectiles.remove(&id);
+        server.broadcast_game_message(ServerToClientMessage::ProjectileDespawn(
+            ProjectileDespawnEvent { id },
+        ));
+    }
+
+    world_map.projectiles = projectiles;
+}
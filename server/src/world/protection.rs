@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use shared::GameServerConfig;
+use shared::{
+    messages::{NetworkAction, PlayerFrameInput},
+    players::{Player, Teams},
+    world::{
+        block_to_chunk_coord, raycast, ChunkClaim, FaceDirectionExt, ServerChunkWorldMap, WorldMap,
+    },
+};
+use std::{collections::HashMap, fmt};
+
+use crate::settings::ServerSettings;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtectionDenial {
+    SpawnProtection,
+    Claimed { owner_name: String },
+}
+
+impl fmt::Display for ProtectionDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtectionDenial::SpawnProtection => {
+                write!(f, "The spawn area is protected")
+            }
+            ProtectionDenial::Claimed { owner_name } => {
+                write!(f, "This area is claimed by {owner_name}")
+            }
+        }
+    }
+}
+
+/// In solo games the only player can edit everything.
+/// Without a private key clients choose their player id, and with it the name they play under, so nobody is an op
+pub fn is_player_op(
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    player_name: &str,
+) -> bool {
+    config.is_solo || (settings.private_key.is_some() && settings.is_op(player_name))
+}
+
+/// Chunk column containing a block, used as the key of the claims map
+pub fn claim_position(block_pos: IVec3) -> IVec2 {
+    IVec2::new(
+        block_to_chunk_coord(block_pos.x),
+        block_to_chunk_coord(block_pos.z),
+    )
+}
+
+/// Whether a block lies in the protected square around the world spawn (0, 0)
+pub fn is_in_spawn_protection(settings: &ServerSettings, block_pos: IVec3) -> bool {
+    let radius = settings.spawn_protection_radius as i32;
+    radius > 0 && block_pos.x.abs() <= radius && block_pos.z.abs() <= radius
+}
+
+pub fn can_edit_block(
+    claims: &HashMap<IVec2, ChunkClaim>,
+    teams: &Teams,
+    settings: &ServerSettings,
+    player: &Player,
+    is_op: bool,
+    block_pos: IVec3,
+) -> Result<(), ProtectionDenial> {
+    if is_op {
+        return Ok(());
+    }
+
+    if is_in_spawn_protection(settings, block_pos) {
+        return Err(ProtectionDenial::SpawnProtection);
+    }
+
+    match claims.get(&claim_position(block_pos)) {
+        Some(claim) if claim.owner != player.id && !teams.shares_claims(claim.owner, player.id) => {
+            Err(ProtectionDenial::Claimed {
+                owner_name: claim.owner_name.clone(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Removes the block interactions of an input that target protected blocks.
+/// The targeted chunks are sent again so that the client prediction is rolled back.
+pub fn filter_protected_actions(
+    chunks: &mut ServerChunkWorldMap,
+    claims: &HashMap<IVec2, ChunkClaim>,
+    teams: &Teams,
+    settings: &ServerSettings,
+    player: &Player,
+    is_op: bool,
+    input: &mut PlayerFrameInput,
+) {
+    if is_op
+        || !(input.inputs.contains(&NetworkAction::LeftClick)
+            || input.inputs.contains(&NetworkAction::RightClick))
+    {
+        return;
+    }
+
+    let Some(hit) = raycast::raycast(chunks, &input.camera, &player.position, input.view_mode)
+    else {
+        return;
+    };
+
+    for (action, target) in [
+        (NetworkAction::LeftClick, hit.position),
+        (
+            NetworkAction::RightClick,
+            hit.position + hit.face.to_ivec3(),
+        ),
+    ] {
+        if !input.inputs.contains(&action) {
+            continue;
+        }
+
+        if let Err(err) = can_edit_block(claims, teams, settings, player, is_op, target) {
+            debug!(
+                "Player {} is not allowed to edit block {:?}: {}",
+                player.id, target, err
+            );
+            input.inputs.remove(&action);
+            chunks.mark_block_for_update(&target);
+        }
+    }
+}
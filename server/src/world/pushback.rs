@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use bevy::math::bounding::Aabb3d;
+use bevy::prelude::*;
+use shared::{
+    players::collision::{apply_player_pushback, entity_pushback, player_half_size},
+    world::{allowed_motion, ServerWorldMap},
+};
+
+use super::spatial::{SpatialEntity, SpatialIndex};
+
+/// Only entities closer than this can overlap, as no hitbox is bigger
+const PUSHBACK_QUERY_RADIUS: f32 = 2.5;
+
+/// Pushes apart players and mobs whose hitboxes overlap, each of them getting half of the push
+pub fn entity_pushback_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    index: Res<SpatialIndex>,
+    time: Res<Time>,
+) {
+    let world_map = world_map.as_mut();
+    let delta = time.delta_secs();
+
+    let entities: Vec<(SpatialEntity, Vec3, Vec3)> = world_map
+        .players
+        .values()
+        .map(|player| {
+            (
+                SpatialEntity::Player(player.id),
+                player.position,
+                player_half_size(player),
+            )
+        })
+        .chain(world_map.mobs.iter().map(|(id, mob)| {
+            (
+                SpatialEntity::Mob(*id),
+                mob.position,
+                Vec3::new(mob.width, mob.height, mob.deepth) / 2.0,
+            )
+        }))
+        .collect();
+
+    let slots: HashMap<SpatialEntity, usize> = entities
+        .iter()
+        .enumerate()
+        .map(|(i, (entity, _, _))| (*entity, i))
+        .collect();
+
+    let mut pushes = vec![Vec3::ZERO; entities.len()];
+    for (i, &(_, position, half_size)) in entities.iter().enumerate() {
+        for (neighbour, _) in index.query_radius(position, PUSHBACK_QUERY_RADIUS) {
+            // Each pair is handled once, from its first entity
+            let Some(&j) = slots.get(&neighbour).filter(|j| **j > i) else {
+                continue;
+            };
+            let (_, other_position, other_half_size) = entities[j];
+
+            let push = entity_pushback(position, half_size, other_position, other_half_size, delta);
+            pushes[i] += push / 2.0;
+            pushes[j] -= push / 2.0;
+        }
+    }
+
+    for ((entity, _, half_size), push) in entities.into_iter().zip(pushes) {
+        if push == Vec3::ZERO {
+            continue;
+        }
+
+        match entity {
+            SpatialEntity::Player(id) => {
+                if let Some(player) = world_map.players.get_mut(&id) {
+                    apply_player_pushback(player, push, &world_map.chunks);
+                }
+            }
+            SpatialEntity::Mob(id) => {
+                let Some(mob) = world_map.mobs.get_mut(&id) else {
+                    continue;
+                };
+                let hitbox = Aabb3d::new(mob.position, half_size);
+                let (allowed, _) = allowed_motion(&world_map.chunks, &hitbox, push.with_y(0.0));
+                mob.position += allowed;
+            }
+            SpatialEntity::ItemStack(_) => {}
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{PlayerId, RecipeUnlockEvent, ServerToClientMessage},
+    players::Player,
+    world::{ItemId, RecipeId, ServerWorldMap},
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+
+/// Written the first time a player holds an item since they joined
+#[derive(Event, Debug)]
+pub struct ItemObtainedEvent {
+    pub player_id: PlayerId,
+    pub item: ItemId,
+}
+
+/// Items each connected player has held at some point, to tell which ones are new
+#[derive(Resource, Default, Debug)]
+pub struct ObtainedItems(HashMap<PlayerId, HashSet<ItemId>>);
+
+/// Looks at the inventories which got items this frame (pickups, trades, commands...), and at the whole
+/// inventory of the players who just joined
+pub fn detect_obtained_items_system(
+    mut obtained: ResMut<ObtainedItems>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut ev_obtained: EventWriter<ItemObtainedEvent>,
+) {
+    let world_map = world_map.as_mut();
+    obtained
+        .0
+        .retain(|id, _| world_map.players.contains_key(id));
+
+    for player in world_map.players.values_mut() {
+        let changed = player.inventory.take_changed();
+        if !changed && obtained.0.contains_key(&player.id) {
+            continue;
+        }
+        let known = obtained.0.entry(player.id).or_default();
+        for stack in player.inventory.inner.values() {
+            if known.insert(stack.item_id) {
+                ev_obtained.write(ItemObtainedEvent {
+                    player_id: player.id,
+                    item: stack.item_id,
+                });
+            }
+        }
+    }
+}
+
+/// Unlocks or locks recipes for a player, returns the ones that actually changed
+pub fn set_recipes_unlocked(
+    player: &mut Player,
+    recipes: impl IntoIterator<Item = RecipeId>,
+    unlocked: bool,
+) -> Vec<RecipeId> {
+    recipes
+        .into_iter()
+        .filter(|recipe| {
+            if unlocked {
+                player.unlocked_recipes.insert(*recipe)
+            } else {
+                player.unlocked_recipes.remove(recipe)
+            }
+        })
+        .collect()
+}
+
+pub fn unlock_recipes_system(
+    mut events: EventReader<ItemObtainedEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut ev_unlock: EventWriter<RecipeUnlockEvent>,
+) {
+    for event in events.read() {
+        let Some(player) = world_map.players.get_mut(&event.player_id) else {
+            continue;
+        };
+        let candidates = RecipeId::ALL
+            .into_iter()
+            .filter(|recipe| recipe.unlocked_by().contains(&event.item));
+        let recipes = set_recipes_unlocked(player, candidates, true);
+        if recipes.is_empty() {
+            continue;
+        }
+
+        debug!("Player {} unlocked {:?}", player.name, recipes);
+        ev_unlock.write(RecipeUnlockEvent {
+            player_id: event.player_id,
+            recipes,
+            unlocked: true,
+        });
+    }
+}
+
+/// Tells players about their recipe changes, they show them as toasts
+pub fn send_recipe_unlocks_system(
+    mut events: EventReader<RecipeUnlockEvent>,
+    mut server: ResMut<RenetServer>,
+) {
+    for event in events.read() {
+        server.send_game_message(
+            event.player_id,
+            ServerToClientMessage::RecipeUnlock(event.clone()),
+        );
+    }
+}
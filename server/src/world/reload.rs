@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use shared::world::{DirtyReason, ServerWorldMap};
+
+use super::load_from_file::{load_chunk_file, parse_chunk_file_name};
+
+/// Seconds between two scans of the chunk files when they are watched
+const WATCH_INTERVAL: f32 = 2.0;
+
+/// Notices the chunk files changed by external map editors, so that they can be loaded into the running world.
+/// Files are compared by modification time with the last scan
+#[derive(Resource)]
+pub struct ChunkFileWatcher {
+    chunks_dir: PathBuf,
+    modified: HashMap<IVec3, SystemTime>,
+    /// Set when the files are scanned on their own, otherwise only `/reloadchunks` scans them
+    timer: Option<Timer>,
+}
+
+impl ChunkFileWatcher {
+    pub fn new(chunks_dir: PathBuf, watch: bool) -> Self {
+        let mut watcher = Self {
+            chunks_dir,
+            modified: HashMap::new(),
+            timer: watch.then(|| Timer::from_seconds(WATCH_INTERVAL, TimerMode::Repeating)),
+        };
+        watcher.changed_files();
+        if watch {
+            info!(
+                "Watching {} for edited chunks",
+                watcher.chunks_dir.display()
+            );
+        }
+        watcher
+    }
+
+    /// Chunk files written or modified since the last scan, deleted ones are ignored
+    fn changed_files(&mut self) -> Vec<(IVec3, PathBuf)> {
+        let Ok(entries) = fs::read_dir(&self.chunks_dir) else {
+            return Vec::new();
+        };
+
+        let mut changed = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(chunk_pos) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(parse_chunk_file_name)
+            else {
+                continue;
+            };
+            let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            if self.modified.insert(chunk_pos, modified) != Some(modified) {
+                changed.push((chunk_pos, path));
+            }
+        }
+        changed
+    }
+}
+
+/// Loads the chunk files changed on disk into the world and sends them to the players again, returns how many were reloaded.
+/// Files matching the chunk in memory are the server's own saves. A chunk also modified in game since the last save
+/// keeps the game's version, the next save overwrites the file
+pub fn reload_changed_chunks(
+    world_map: &mut ServerWorldMap,
+    watcher: &mut ChunkFileWatcher,
+) -> usize {
+    let chunks = &mut world_map.chunks;
+    let mut reloaded = 0;
+
+    for (chunk_pos, path) in watcher.changed_files() {
+        let chunk = match load_chunk_file(&path) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                warn!("Could not reload chunk file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        if let Some(current) = chunks.map.get(&chunk_pos) {
+            if current.map == chunk.map {
+                continue;
+            }
+            if chunks.dirty_chunks.contains(&chunk_pos) {
+                warn!(
+                    "Chunk {:?} was edited both on disk and in game, keeping the game's version",
+                    chunk_pos
+                );
+                continue;
+            }
+        }
+
+        chunks.insert_chunk(chunk_pos, chunk);
+        chunks.chunks_to_update.mark(chunk_pos, DirtyReason::Blocks);
+        reloaded += 1;
+    }
+
+    if reloaded > 0 {
+        info!("Reloaded {} chunks edited on disk", reloaded);
+    }
+    reloaded
+}
+
+pub fn chunk_file_watch_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    mut watcher: ResMut<ChunkFileWatcher>,
+    time: Res<Time>,
+) {
+    let Some(timer) = watcher.timer.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    reload_changed_chunks(&mut world_map, &mut watcher);
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use shared::{
+    messages::PlayerId,
+    players::{Inventory, InventoryError},
+    world::{BlockData, BlockDirection, BlockId, ItemId, ItemStack, ServerWorldMap, WorldMap},
+    GameServerConfig,
+};
+use std::collections::HashMap;
+
+use crate::settings::ServerSettings;
+
+use super::{
+    journal::{now_secs, EventJournal, JournalEvent},
+    protection::is_player_op,
+    BlockInteractionEvent,
+};
+
+const ROLLBACK_USAGE: &str = "Usage: /rollback <player> <duration>, like 30m, 2h or 1d";
+
+/// Seconds of a duration like `90s`, `30m`, `2h` or `1d`
+fn parse_duration(text: &str) -> Option<u64> {
+    let split = text.len().checked_sub(1)?;
+    let (value, unit) = text.split_at(split);
+    let value: u64 = value.parse().ok()?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    value.checked_mul(unit_secs)
+}
+
+/// Handles `/rollback <player> <duration>`, reverting the block changes and container transactions
+/// of the player, the most recent first. Blocks changed again by someone else since are left as they are.
+/// The blocks go through `BlockInteractionEvent`, so the chunks are sent again to the players like for any edit
+pub fn rollback_command(
+    world_map: &mut ServerWorldMap,
+    journal: &mut EventJournal,
+    ev_block: &mut EventWriter<BlockInteractionEvent>,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to roll back players".to_string();
+    };
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can roll back players".to_string();
+    }
+    let sender_name = sender.name.clone();
+    let [target, duration] = args else {
+        return ROLLBACK_USAGE.to_string();
+    };
+    let Some(duration) = parse_duration(duration) else {
+        return ROLLBACK_USAGE.to_string();
+    };
+    let since = now_secs().saturating_sub(duration);
+
+    // Blocks as the edits already decided will leave them, the events are only applied later in the frame
+    let mut planned: HashMap<IVec3, Option<BlockId>> = HashMap::new();
+    let mut blocks = 0;
+    let mut transactions = 0;
+    let mut partial_transactions = 0;
+    for entry in journal.revertible_entries(target, since) {
+        match entry.event {
+            JournalEvent::BlockPlaced { position, block } => {
+                let current = *planned.entry(position).or_insert_with(|| {
+                    world_map
+                        .chunks
+                        .get_block_by_coordinates(&position)
+                        .map(|block| block.id)
+                });
+                if current != Some(block) {
+                    continue;
+                }
+                ev_block.write(BlockInteractionEvent {
+                    position,
+                    block_type: None,
+                    drops: false,
+                });
+                planned.insert(position, None);
+                blocks += 1;
+            }
+            JournalEvent::BlockBroken { position, block } => {
+                let current = *planned.entry(position).or_insert_with(|| {
+                    world_map
+                        .chunks
+                        .get_block_by_coordinates(&position)
+                        .map(|block| block.id)
+                });
+                if current.is_some() {
+                    continue;
+                }
+                ev_block.write(BlockInteractionEvent {
+                    position,
+                    block_type: Some(BlockData::new(block, BlockDirection::Front)),
+                    drops: false,
+                });
+                planned.insert(position, Some(block));
+                blocks += 1;
+            }
+            JournalEvent::ContainerTaken { position, item, nb }
+            | JournalEvent::ContainerPut { position, item, nb } => {
+                if !is_planned_container(world_map, &planned, position) {
+                    continue;
+                }
+                let Some(player) = world_map
+                    .players
+                    .values_mut()
+                    .find(|player| player.name == *target)
+                else {
+                    partial_transactions += 1;
+                    continue;
+                };
+                let container = world_map.containers.entry(position).or_default();
+                let moved = match entry.event {
+                    JournalEvent::ContainerTaken { .. } => {
+                        move_items(&mut player.inventory, container, item, nb)
+                    }
+                    _ => move_items(container, &mut player.inventory, item, nb),
+                };
+                if moved < nb {
+                    partial_transactions += 1;
+                }
+                if moved > 0 {
+                    transactions += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    journal.record(
+        &sender_name,
+        JournalEvent::RolledBack {
+            player: target.clone(),
+            since,
+        },
+    );
+    info!(
+        "{} rolled back {} block changes and {} container transactions of {}",
+        sender_name, blocks, transactions, target
+    );
+    let mut message = format!(
+        "Rolled back {} block changes and {} container transactions of {}",
+        blocks, transactions, target
+    );
+    if partial_transactions > 0 {
+        message.push_str(&format!(
+            ", {partial_transactions} of them only in part as the items were moved since or {target} is offline"
+        ));
+    }
+    message
+}
+
+/// Whether the block at `position` is a container, once the block changes already planned are applied
+fn is_planned_container(
+    world_map: &ServerWorldMap,
+    planned: &HashMap<IVec3, Option<BlockId>>,
+    position: IVec3,
+) -> bool {
+    planned
+        .get(&position)
+        .copied()
+        .unwrap_or_else(|| {
+            world_map
+                .chunks
+                .get_block_by_coordinates(&position)
+                .map(|block| block.id)
+        })
+        .is_some_and(|block| block.is_container())
+}
+
+/// Moves back up to `nb` items, only the ones still in `from`: the others were stored or dropped
+/// elsewhere since and would be duplicated. What does not fit in `to` stays in `from`.
+/// Returns how many items were moved
+fn move_items(from: &mut Inventory, to: &mut Inventory, item: ItemId, nb: u32) -> u32 {
+    let available = from.count_of(item).min(nb);
+    if available == 0 || from.remove_exact(item, available).is_err() {
+        return 0;
+    }
+    let stack = ItemStack {
+        item_id: item,
+        item_type: item.get_default_type(),
+        nb: available,
+    };
+    match to.try_insert(stack) {
+        Err(InventoryError::Full { remaining }) => {
+            let _ = from.try_insert(ItemStack {
+                nb: remaining,
+                ..stack
+            });
+            available - remaining
+        }
+        _ => available,
+    }
+}
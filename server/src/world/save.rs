@@ -1,94 +1,432 @@
 use crate::init::ServerTime;
+use crate::metrics::ServerMetrics;
+use crate::network::broadcast_chat::{push_server_chat_message, ChatMessageEvent};
 use bevy::prelude::*;
 use ron::ser::PrettyConfig;
-use shared::messages::PlayerId;
-use shared::players::Player;
+use shared::messages::{ChatConversation, PlayerId};
+use shared::players::{Inventory, Player, Teams};
+use shared::world::BlockData;
+use shared::world::ChunkClaim;
 use shared::world::MobId;
 use shared::world::ServerChunk;
 use shared::world::ServerItemStack;
 use shared::world::ServerMob;
 use shared::world::ServerWorldMap;
+use shared::world::WorldGenSettings;
 use shared::world::WorldSeed;
+use shared::world::{BlockTickScheduler, GameRules, WeatherState};
 use shared::GameFolderPaths;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use std::{fs::File, io::Write, path::Path};
 
 #[derive(Event)]
 pub enum SaveRequestEvent {
     World,
     Player(PlayerId),
+    /// Saves the world, then archives its save directory
+    Backup,
 }
 
-use crate::world::data::SAVE_PATH;
+use crate::world::backup::{backup_file_name, create_world_archive};
+use crate::world::data::{BACKUPS_PATH, CHUNKS_SAVE_DIR, SAVE_PATH};
+
+// Maximum number of world snapshots waiting to be written to disk
+const SAVE_QUEUE_SIZE: usize = 2;
 
 #[derive(serde::Serialize, serde::Deserialize, Default)]
 pub struct WorldData {
+    /// Chunks are saved in their own files, this is only filled by saves made before that
+    #[serde(default)]
     pub map: HashMap<IVec3, ServerChunk>,
     pub mobs: HashMap<MobId, ServerMob>,
     pub seed: WorldSeed,
     pub name: String,
     pub time: u64,
     pub item_stacks: Vec<ServerItemStack>,
+    #[serde(default)]
+    pub claims: HashMap<IVec2, ChunkClaim>,
+    #[serde(default)]
+    pub teams: Teams,
+    /// Worlds saved before the height limits were configurable were generated between 0 and 256
+    #[serde(default = "WorldGenSettings::legacy")]
+    pub gen_settings: WorldGenSettings,
+    #[serde(default)]
+    pub pending_blocks: HashMap<IVec3, Vec<(IVec3, BlockData)>>,
+    #[serde(default)]
+    pub spawn_platform_pending: bool,
+    #[serde(default)]
+    pub containers: HashMap<IVec3, Inventory>,
+    /// Worlds saved before the daylight cycle could be stopped used `time` as the time of day
+    #[serde(default)]
+    pub day_time: Option<u64>,
+    #[serde(default)]
+    pub game_rules: GameRules,
+    #[serde(default)]
+    pub block_ticks: BlockTickScheduler,
+    #[serde(default)]
+    pub weather: WeatherState,
+}
+
+/// Snapshot of the world taken on the main thread, written to disk by the save worker
+pub struct SaveJob {
+    pub world_dir: PathBuf,
+    pub world_data: WorldData,
+    /// Only the chunks modified since the last save
+    pub chunks: Vec<(IVec3, ServerChunk)>,
+    /// Chunks trimmed since the last save, their files are deleted
+    pub removed_chunks: Vec<IVec3>,
+    /// If set, the world directory is archived there once saved
+    pub backup_path: Option<PathBuf>,
+    /// Autosaves are not announced in the chat
+    pub autosave: bool,
+}
+
+pub struct SaveReport {
+    pub world_name: String,
+    pub chunks_saved: usize,
+    pub duration: Duration,
+    /// Chunks that could not be written, they are marked dirty again
+    pub failed_chunks: Vec<IVec3>,
+    pub error: Option<String>,
+    pub backup_path: Option<PathBuf>,
+    pub autosave: bool,
+}
+
+#[derive(Resource)]
+pub struct SaveWorker {
+    sender: SyncSender<SaveJob>,
+    reports: Mutex<Receiver<SaveReport>>,
+    thread: JoinHandle<()>,
+}
+
+impl SaveWorker {
+    pub fn spawn() -> Self {
+        let (sender, jobs) = mpsc::sync_channel::<SaveJob>(SAVE_QUEUE_SIZE);
+        let (report_sender, reports) = mpsc::channel();
+
+        let thread = std::thread::Builder::new()
+            .name("world-save".into())
+            .spawn(move || {
+                for job in jobs {
+                    let report = write_save_job(job);
+                    if report_sender.send(report).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("Could not spawn the world save thread");
+
+        Self {
+            sender,
+            reports: Mutex::new(reports),
+            thread,
+        }
+    }
+
+    /// Waits for all queued saves to be written, must be called before the server process exits
+    pub fn shutdown(self) {
+        drop(self.sender);
+        if self.thread.join().is_err() {
+            error!("The world save thread panicked");
+        }
+    }
+
+    /// Queues a snapshot without blocking, returns false if the queue is full
+    pub fn try_queue(&self, job: SaveJob) -> bool {
+        match self.sender.try_send(job) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => false,
+            Err(TrySendError::Disconnected(_)) => {
+                error!("The world save thread is not running anymore");
+                false
+            }
+        }
+    }
+}
+
+pub fn chunk_file_name(chunk_pos: &IVec3) -> String {
+    format!("{}_{}_{}.ron", chunk_pos.x, chunk_pos.y, chunk_pos.z)
+}
+
+pub fn world_save_dir(game_folder_path: &GameFolderPaths, world_name: &str) -> PathBuf {
+    game_folder_path
+        .game_folder_path
+        .join(SAVE_PATH)
+        .join(world_name)
+}
+
+/// Snapshot of the world to save, only the chunks modified since the last save are copied
+/// as the rest of the world is small
+pub fn world_save_job(
+    world_map: &ServerWorldMap,
+    world_seed: &WorldSeed,
+    time: &ServerTime,
+    game_folder_path: &GameFolderPaths,
+) -> SaveJob {
+    partial_world_save_job(world_map, world_seed, time, game_folder_path, usize::MAX)
+}
+
+/// Like `world_save_job` with at most `max_chunks` of the modified chunks, the other ones stay dirty
+/// once it is queued
+pub fn partial_world_save_job(
+    world_map: &ServerWorldMap,
+    world_seed: &WorldSeed,
+    time: &ServerTime,
+    game_folder_path: &GameFolderPaths,
+    max_chunks: usize,
+) -> SaveJob {
+    let chunks = world_map
+        .chunks
+        .dirty_chunks
+        .iter()
+        .take(max_chunks)
+        .filter_map(|pos| world_map.chunks.map.get(pos).map(|c| (*pos, c.clone())))
+        .collect::<Vec<_>>();
+
+    SaveJob {
+        world_dir: world_save_dir(game_folder_path, &world_map.name),
+        world_data: WorldData {
+            map: HashMap::new(),
+            mobs: world_map.mobs.clone(),
+            item_stacks: world_map.item_stacks.clone(),
+            name: world_map.name.clone(),
+            seed: *world_seed,
+            time: time.0,
+            claims: world_map.claims.clone(),
+            teams: world_map.teams.clone(),
+            gen_settings: world_map.chunks.gen_settings,
+            pending_blocks: world_map.chunks.pending_blocks.clone(),
+            spawn_platform_pending: world_map.chunks.spawn_platform_pending,
+            containers: world_map.containers.clone(),
+            day_time: Some(world_map.day_time),
+            game_rules: world_map.game_rules,
+            block_ticks: world_map.block_ticks.clone(),
+            weather: world_map.weather,
+        },
+        chunks,
+        // Trimmed chunks may have been generated again since
+        removed_chunks: world_map
+            .chunks
+            .removed_chunks
+            .iter()
+            .filter(|pos| !world_map.chunks.map.contains_key(pos))
+            .copied()
+            .collect(),
+        backup_path: None,
+        autosave: false,
+    }
 }
 
 pub fn save_world_system(
-    world_map: Res<ServerWorldMap>,
+    mut world_map: ResMut<ServerWorldMap>,
     world_seed: Res<WorldSeed>,
     game_folder_path: Res<GameFolderPaths>,
     time: Res<ServerTime>,
+    save_worker: Res<SaveWorker>,
     mut event: EventReader<SaveRequestEvent>,
+    mut chat_conversation: ResMut<ChatConversation>,
+    mut ev_chat: EventWriter<ChatMessageEvent>,
 ) {
     // Reads all events to prevent them from being queued forever and repeatedly request a save
     let mut save_requested = false;
+    let mut backup_requested = false;
     for ev in event.read() {
         save_requested = true;
 
+        if let SaveRequestEvent::Backup = ev {
+            backup_requested = true;
+        }
+
         if let SaveRequestEvent::Player(id) = ev {
-            if let Some(player) = world_map.players.get(id) {
-                // define save file path
-                let save_file_path = format!(
-                    "{}{}/players/{}.ron",
-                    game_folder_path.game_folder_path.join(SAVE_PATH).display(),
-                    world_map.name,
-                    id
-                );
+            save_player(&world_map, *id, &game_folder_path);
+        }
+    }
 
-                if let Err(err) = save_player_data(player, &save_file_path) {
-                    error!(
-                        "[{}] Could not save data for player {} : {}",
-                        world_map.name, id, err
-                    );
-                } else {
-                    info!("[{}] Player {} data saved successfully", world_map.name, id);
-                }
-            }
+    if !save_requested {
+        return;
+    }
+
+    let mut job = world_save_job(&world_map, &world_seed, &time, &game_folder_path);
+    job.backup_path = backup_requested.then(|| {
+        game_folder_path
+            .game_folder_path
+            .join(BACKUPS_PATH)
+            .join(backup_file_name(&world_map.name))
+    });
+
+    let nb_chunks = job.chunks.len();
+    if save_worker.try_queue(job) {
+        debug!("Queued world save with {} dirty chunks", nb_chunks);
+        world_map.chunks.dirty_chunks.clear();
+        world_map.chunks.removed_chunks.clear();
+    } else {
+        // Dirty chunks are kept, so they will be part of the next save
+        warn!(
+            "World save queue is full, skipping this save ({} dirty chunks kept)",
+            nb_chunks
+        );
+        if backup_requested {
+            push_server_chat_message(
+                &mut chat_conversation,
+                "Backup skipped, previous saves are still being written".into(),
+            );
+            ev_chat.write(ChatMessageEvent);
         }
     }
+}
 
-    // If a save was requested by the user
-    if save_requested {
-        let world_data = WorldData {
-            map: world_map.chunks.map.clone(),
-            mobs: world_map.mobs.clone(),
-            item_stacks: world_map.item_stacks.clone(),
-            name: world_map.name.clone(),
-            seed: *world_seed,
-            time: time.0,
-        };
+/// Writes the data of an online player to its file, errors are logged
+pub fn save_player(world_map: &ServerWorldMap, id: PlayerId, game_folder_path: &GameFolderPaths) {
+    let Some(player) = world_map.players.get(&id) else {
+        return;
+    };
+    let save_file_path = format!(
+        "{}{}/players/{}.ron",
+        game_folder_path.game_folder_path.join(SAVE_PATH).display(),
+        world_map.name,
+        id
+    );
 
-        // define save file path
-        let save_file_path = format!(
-            "{}{}/world.ron",
-            game_folder_path.game_folder_path.join(SAVE_PATH).display(),
-            world_map.name
+    if let Err(err) = save_player_data(player, &save_file_path) {
+        error!(
+            "[{}] Could not save data for player {} : {}",
+            world_map.name, id, err
         );
+    } else {
+        info!("[{}] Player {} data saved successfully", world_map.name, id);
+    }
+}
+
+// Runs on the save thread, or on the main one for emergency saves
+pub fn write_save_job(job: SaveJob) -> SaveReport {
+    let start = Instant::now();
+    let world_name = job.world_data.name.clone();
+    let mut failed_chunks = vec![];
+    let mut error = None;
+
+    let chunks_dir = job.world_dir.join(CHUNKS_SAVE_DIR);
+    if let Err(e) = std::fs::create_dir_all(&chunks_dir) {
+        return SaveReport {
+            world_name,
+            chunks_saved: 0,
+            duration: start.elapsed(),
+            failed_chunks: job.chunks.iter().map(|(pos, _)| *pos).collect(),
+            error: Some(e.to_string()),
+            backup_path: None,
+            autosave: job.autosave,
+        };
+    }
+
+    for (pos, chunk) in job.chunks.iter() {
+        if let Err(e) = save_chunk_data(chunk, &chunks_dir.join(chunk_file_name(pos))) {
+            failed_chunks.push(*pos);
+            error = Some(e.to_string());
+        }
+    }
+
+    // Jobs are written in order, so a chunk trimmed after an older save was queued stays deleted
+    for pos in job.removed_chunks.iter() {
+        match std::fs::remove_file(chunks_dir.join(chunk_file_name(pos))) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => error = Some(e.to_string()),
+            _ => {}
+        }
+    }
+
+    if let Err(e) = save_world_data(
+        &job.world_data,
+        &job.world_dir.join("world.ron").display().to_string(),
+    ) {
+        error = Some(e.to_string());
+    }
+
+    // Never archive a partially written world
+    let backup_path = match (&job.backup_path, &error) {
+        (Some(path), None) => match create_world_archive(&job.world_dir, path) {
+            Ok(()) => Some(path.clone()),
+            Err(e) => {
+                error = Some(format!("backup failed: {e}"));
+                None
+            }
+        },
+        _ => None,
+    };
+
+    SaveReport {
+        world_name,
+        chunks_saved: job.chunks.len() - failed_chunks.len(),
+        duration: start.elapsed(),
+        failed_chunks,
+        error,
+        backup_path,
+        autosave: job.autosave,
+    }
+}
+
+pub fn report_world_saves_system(
+    save_worker: Res<SaveWorker>,
+    mut world_map: ResMut<ServerWorldMap>,
+    mut chat_conversation: ResMut<ChatConversation>,
+    mut ev_chat: EventWriter<ChatMessageEvent>,
+    metrics: Res<ServerMetrics>,
+) {
+    let Ok(reports) = save_worker.reports.lock() else {
+        return;
+    };
 
-        // save seed and world data
-        if let Err(e) = save_world_data(&world_data, &save_file_path) {
-            error!("Failed to save world data: {}", e);
-        } else {
-            info!("World data saved successfully! Name: {}", world_map.name);
+    while let Ok(report) = reports.try_recv() {
+        world_map
+            .chunks
+            .dirty_chunks
+            .extend(report.failed_chunks.iter().copied());
+        metrics.update(|snapshot| {
+            snapshot.saves += 1;
+            snapshot.last_save_duration = report.duration;
+            snapshot.last_save_chunks = report.chunks_saved;
+        });
+
+        if report.autosave && report.error.is_none() {
+            info!(
+                "Autosaved {} chunks of {} in {:?}",
+                report.chunks_saved, report.world_name, report.duration
+            );
+            continue;
         }
+
+        let content = match &report.error {
+            None => {
+                info!(
+                    "World data saved successfully! Name: {}, {} chunks written in {:?}",
+                    report.world_name, report.chunks_saved, report.duration
+                );
+                format!(
+                    "World saved ({} chunks in {} ms)",
+                    report.chunks_saved,
+                    report.duration.as_millis()
+                )
+            }
+            Some(e) => {
+                error!("Failed to save world data: {}", e);
+                format!("World save failed: {e}")
+            }
+        };
+
+        push_server_chat_message(&mut chat_conversation, content);
+
+        if let Some(path) = &report.backup_path {
+            info!("World backup written to {}", path.display());
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            push_server_chat_message(
+                &mut chat_conversation,
+                format!("Backup created: {file_name}"),
+            );
+        }
+
+        ev_chat.write(ChatMessageEvent);
     }
 }
 
@@ -104,13 +442,26 @@ pub fn save_world_data(
 
     // serialize combined data (map + seed)
     let serialized = ron::ser::to_string_pretty(world_data, pretty_config)?;
-    let path = Path::new(file_path);
-    let mut file = File::create(path)?;
-    file.write_all(serialized.as_bytes())?;
-    info!("World data saved to {}", file_path);
+    write_file_atomically(Path::new(file_path), serialized.as_bytes())?;
+    debug!("World data saved to {}", file_path);
     Ok(())
 }
 
+pub fn save_chunk_data(chunk: &ServerChunk, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let serialized = ron::ser::to_string(chunk)?;
+    write_file_atomically(path, serialized.as_bytes())?;
+    Ok(())
+}
+
+// Writes to a temporary file first, so that a crash while saving never leaves a truncated file behind
+fn write_file_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("ron.tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    std::fs::rename(tmp_path, path)
+}
+
 pub fn save_player_data(
     player: &Player,
     file_path: &str,
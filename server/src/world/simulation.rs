@@ -4,39 +4,116 @@ use bevy::{
 };
 use bevy_renet::renet::{ClientId, RenetServer};
 use shared::{
-    messages::{NetworkAction, PlayerFrameInput, PlayerUpdateEvent},
-    players::{blocks::CallerType, simulation::simulate_player_actions},
-    world::{ServerWorldMap, WorldSeed},
+    messages::{
+        BlockBreakingProgress, BlockEditResult, NetworkAction, PlayerFrameInput, PlayerUpdateEvent,
+        ServerToClientMessage,
+    },
+    players::{
+        blocks::{BlockChange, CallerType, INTERACTION_DISTANCE},
+        item_use::simulate_item_use,
+        simulation::simulate_player_actions,
+        GameMode, HOTBAR_SLOTS,
+    },
+    utils::unix_time_ms,
+    world::{
+        BlockId, RandomStream, ServerChunkWorldMap, ServerWorldMap, SimulationRandom, WorldMap,
+        WorldSeed,
+    },
+    GameServerConfig,
 };
 
-use crate::{network::extensions::SendGameMessageExtension, world::generation::generate_chunk};
+use super::afk::{record_player_activity, PlayerActivity};
+use super::block_break::handle_broken_block;
+use super::containers::open_targeted_container;
+use super::emotes::{input_cancels_sitting, PlayerEmoteRequestEvent};
+use super::fire::schedule_fire_tick;
+use super::health::{take_environment_damage, PlayerDamageEvent};
+use super::item_use::PlayerItemUseEvent;
+use super::journal::{EventJournal, JournalEvent};
+use super::preset::WorldGenPreset;
+
+use crate::{
+    init::ServerTime,
+    network::extensions::SendGameMessageExtension,
+    settings::ServerSettings,
+    world::{
+        generation::generate_and_insert_chunk,
+        protection::{filter_protected_actions, is_player_op},
+    },
+};
 
 use super::broadcast_world::get_all_active_chunks;
 
+/// Inputs older than this are dropped, a client catching up after a freeze would otherwise replay seconds of movement
+const INPUT_MAX_AGE_MS: u64 = 2000;
+/// Inputs timestamped further ahead of the server clock come from a client whose clock is wrong
+const INPUT_MAX_LEAD_MS: u64 = 1000;
+/// Players further than this from a block being broken are not told about its progress
+const BLOCK_DAMAGE_BROADCAST_DISTANCE: f32 = 48.;
+/// Predicted edits further than this are not answered. A placed block is one block further than the one aimed at
+const PREDICTED_EDIT_REACH: f32 = INTERACTION_DISTANCE + 1.;
+
 #[derive(Event, Debug)]
 pub struct PlayerInputsEvent {
     pub client_id: ClientId,
     pub input: PlayerFrameInput,
 }
 
+/// Answer to the edit the client predicted with `input`, made from `player_position`.
+/// Positions out of reach get none: the client could otherwise read any loaded block, such as ores behind walls
+fn predicted_edit_result(
+    chunks: &ServerChunkWorldMap,
+    player_position: Vec3,
+    input: &PlayerFrameInput,
+) -> Option<BlockEditResult> {
+    let position = input.predicted_edit?;
+    if (position.as_vec3() + Vec3::splat(0.5)).distance(player_position) > PREDICTED_EDIT_REACH {
+        return None;
+    }
+    Some(BlockEditResult {
+        input_time_ms: input.time_ms,
+        position,
+        block: chunks.get_block_by_coordinates(&position).copied(),
+    })
+}
+
+/// Inputs are processed in order once each, and only if their timestamp is close to the server time
+fn is_input_in_window(input: &PlayerFrameInput, last_processed_ms: u64, now_ms: u64) -> bool {
+    input.time_ms > last_processed_ms
+        && input.time_ms + INPUT_MAX_AGE_MS >= now_ms
+        && input.time_ms <= now_ms + INPUT_MAX_LEAD_MS
+}
+
 pub fn handle_player_inputs_system(
     mut events: EventReader<PlayerInputsEvent>,
     mut world_map: ResMut<ServerWorldMap>,
     mut server: ResMut<RenetServer>,
-    seed: Res<WorldSeed>,
+    (seed, preset, random): (Res<WorldSeed>, Res<WorldGenPreset>, Res<SimulationRandom>),
+    settings: Res<ServerSettings>,
+    config: Res<GameServerConfig>,
+    mut ev_emote: EventWriter<PlayerEmoteRequestEvent>,
+    mut ev_damage: EventWriter<PlayerDamageEvent>,
+    mut ev_item_use: EventWriter<PlayerItemUseEvent>,
+    (time, server_time): (Res<Time>, Res<ServerTime>),
+    (mut activity, mut journal): (ResMut<PlayerActivity>, ResMut<EventJournal>),
 ) {
     let world_map = world_map.as_mut();
     let players = &mut world_map.players;
     let chunks = &mut world_map.chunks;
+    let claims = &world_map.claims;
+    let teams = &world_map.teams;
+    let containers = &mut world_map.containers;
+    let item_stacks = &mut world_map.item_stacks;
+    let game_rules = &world_map.game_rules;
+    let block_ticks = &mut world_map.block_ticks;
 
     let active_chunks = get_all_active_chunks(players, 1);
     for c in active_chunks {
         let chunk = chunks.map.get(&c);
 
         if chunk.is_none() {
-            let chunk = generate_chunk(c, seed.0);
+            generate_and_insert_chunk(chunks, c, seed.0, &preset);
             info!("Generated chunk: {:?}", c);
-            chunks.map.insert(c, chunk);
         }
     }
 
@@ -45,23 +122,170 @@ pub fn handle_player_inputs_system(
         player_actions.insert(*client_id, HashSet::new());
     }
 
+    let mut damaged_blocks = Vec::new();
+    let now_ms = unix_time_ms();
     for ev in events.read() {
         let player = players.get_mut(&ev.client_id).unwrap();
 
-        simulate_player_actions(player, chunks, &ev.input.clone(), CallerType::Server);
+        if !is_input_in_window(&ev.input, player.last_input_processed, now_ms) {
+            shared::warn_throttled!(
+                "Dropped input of player {} at {} ms, server time is {} ms",
+                player.id,
+                ev.input.time_ms,
+                now_ms
+            );
+            continue;
+        }
+
+        if player.emote.is_some() && input_cancels_sitting(&ev.input) {
+            ev_emote.write(PlayerEmoteRequestEvent {
+                client_id: ev.client_id,
+                emote: None,
+            });
+        }
+
+        record_player_activity(
+            &mut activity,
+            &mut server,
+            &ev.input,
+            player,
+            time.elapsed(),
+        );
+
+        let mut input = ev.input.clone();
+        let is_op = is_player_op(&settings, &config, &player.name);
+        filter_protected_actions(chunks, claims, teams, &settings, player, is_op, &mut input);
+        // Spectators only watch, even ops
+        if player.game_mode == GameMode::Spectator {
+            input.inputs.remove(&NetworkAction::LeftClick);
+            input.inputs.remove(&NetworkAction::RightClick);
+        }
+
+        // The edits are checked from where the player stood before moving
+        let edit_origin = player.position;
+        if let Some(change) = simulate_player_actions(player, chunks, &input, CallerType::Server) {
+            chunks.mark_player_modified(&change.position());
+            match change {
+                BlockChange::Broken(broken) => {
+                    journal.record(
+                        &player.name,
+                        JournalEvent::BlockBroken {
+                            position: broken.position,
+                            block: broken.id,
+                        },
+                    );
+                    let mut rng =
+                        random.at_block(RandomStream::BlockDrops, server_time.0, broken.position);
+                    handle_broken_block(
+                        player,
+                        broken,
+                        input.hotbar_slot,
+                        containers,
+                        item_stacks,
+                        &mut rng,
+                    );
+                }
+                BlockChange::Placed { position, id } => {
+                    journal.record(
+                        &player.name,
+                        JournalEvent::BlockPlaced {
+                            position,
+                            block: id,
+                        },
+                    );
+                    if id == BlockId::Fire {
+                        schedule_fire_tick(block_ticks, position, server_time.0, &random);
+                    }
+                }
+                BlockChange::Damaged { position, progress } => {
+                    damaged_blocks.push(BlockBreakingProgress { position, progress });
+                }
+            }
+        }
+        // The client rolls its prediction back if the edit was refused
+        if let Some(result) = predicted_edit_result(chunks, edit_origin, &input) {
+            server.send_game_message(player.id, ServerToClientMessage::BlockEditResult(result));
+        }
+        open_targeted_container(containers, chunks, player, &input, &mut journal);
+        // Timed on the server clock, the one of the client cannot be trusted with cooldowns
+        if let Some(effect) = simulate_item_use(player, &input, time.elapsed().as_millis() as u64) {
+            ev_item_use.write(PlayerItemUseEvent {
+                player_id: player.id,
+                effect,
+            });
+        }
+        take_environment_damage(player, game_rules, &mut ev_damage);
 
         player.last_input_processed = ev.input.time_ms;
+        player.hotbar_slot = ev.input.hotbar_slot.min(HOTBAR_SLOTS - 1);
+    }
+
+    for damage in damaged_blocks {
+        let center = damage.position.as_vec3() + Vec3::splat(0.5);
+        for player in players.values() {
+            if player.position.distance(center) <= BLOCK_DAMAGE_BROADCAST_DISTANCE {
+                server.send_game_message(
+                    player.id,
+                    ServerToClientMessage::BlockBreakingProgress(damage),
+                );
+            }
+        }
     }
 
     for player in players.values() {
-        server.broadcast_game_message(shared::messages::ServerToClientMessage::PlayerUpdate(
-            PlayerUpdateEvent {
-                id: player.id,
-                position: player.position,
-                orientation: player.camera_transform.rotation,
-                last_ack_time: player.last_input_processed,
-                inventory: player.inventory.clone(),
-            },
-        ));
+        server.broadcast_game_message(ServerToClientMessage::PlayerUpdate(PlayerUpdateEvent {
+            id: player.id,
+            position: player.position,
+            orientation: player.camera_transform.rotation,
+            last_ack_time: player.last_input_processed,
+            inventory: player.inventory.clone(),
+            hotbar_slot: player.hotbar_slot,
+            health: player.health,
+            effects: player.effects.clone(),
+            emote: player.emote,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::world::{BlockData, BlockDirection};
+
+    fn edit_at(position: IVec3) -> PlayerFrameInput {
+        PlayerFrameInput {
+            time_ms: 1000,
+            predicted_edit: Some(position),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn predicted_edit_in_reach_is_answered_with_the_server_block() {
+        let mut chunks = ServerChunkWorldMap::default();
+        let position = IVec3::new(3, 0, 0);
+        chunks.set_block(
+            &position,
+            BlockData::new(BlockId::Stone, BlockDirection::Front),
+        );
+
+        let result = predicted_edit_result(&chunks, Vec3::new(0.5, 1.5, 0.5), &edit_at(position))
+            .expect("the edit is in reach");
+        assert_eq!(result.input_time_ms, 1000);
+        assert_eq!(result.position, position);
+        assert_eq!(result.block.map(|block| block.id), Some(BlockId::Stone));
+    }
+
+    #[test]
+    fn predicted_edit_out_of_reach_gets_no_answer() {
+        let mut chunks = ServerChunkWorldMap::default();
+        let ore = IVec3::new(40, -20, 0);
+        chunks.set_block(
+            &ore,
+            BlockData::new(BlockId::DiamondOre, BlockDirection::Front),
+        );
+
+        assert!(predicted_edit_result(&chunks, Vec3::new(0.5, 1.5, 0.5), &edit_at(ore)).is_none());
+        assert!(predicted_edit_result(&chunks, Vec3::ZERO, &PlayerFrameInput::default()).is_none());
     }
 }
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+use rand::Rng;
+use shared::{
+    world::{
+        global_block_to_chunk_pos, positions_in_region, BlockData, BlockHitbox, BlockId,
+        RandomStream, ServerChunkWorldMap, ServerWorldMap, SimulationRandom, WorldMap,
+    },
+    CHUNK_SIZE,
+};
+
+use crate::init::ServerTime;
+
+use super::broadcast_world::get_all_active_chunks;
+
+/// Block columns picked at random in each chunk column around the players, every tick
+const RANDOM_TICKS_PER_COLUMN: u32 = 2;
+/// Layers snow piles up to on its own
+const MAX_SNOW_LAYERS: u8 = 3;
+/// Snow melts this close to a light source
+const MELT_LIGHT_RADIUS: i32 = 2;
+
+fn is_near_light(chunks: &ServerChunkWorldMap, position: IVec3) -> bool {
+    let reach = IVec3::splat(MELT_LIGHT_RADIUS);
+    positions_in_region(position - reach, position + reach).any(|around| {
+        chunks.map.contains_key(&global_block_to_chunk_pos(&around))
+            && chunks
+                .get_block_by_coordinates(&around)
+                .is_some_and(|block| block.id.is_light_source())
+    })
+}
+
+/// Updates the top of a block column: snow piles up while it snows in cold biomes,
+/// and melts in the other biomes or near light sources
+fn snow_random_tick(
+    chunks: &mut ServerChunkWorldMap,
+    position: IVec3,
+    is_snowing: bool,
+    is_cold: bool,
+) {
+    let Some(top) = chunks.get_block_by_coordinates(&position).copied() else {
+        return;
+    };
+
+    if top.id == BlockId::SnowLayer {
+        if !is_cold || is_near_light(chunks, position) {
+            if top.layers > 1 {
+                chunks.set_block(
+                    &position,
+                    BlockData::layered(BlockId::SnowLayer, top.layers - 1),
+                );
+            } else {
+                chunks.remove_block_by_coordinates(&position);
+            }
+        } else if is_snowing && top.layers < MAX_SNOW_LAYERS {
+            chunks.set_block(
+                &position,
+                BlockData::layered(BlockId::SnowLayer, top.layers + 1),
+            );
+        }
+        return;
+    }
+
+    let above = position + IVec3::Y;
+    let can_hold_snow =
+        matches!(top.get_hitbox(), BlockHitbox::FullBlock) && top.id != BlockId::Ice;
+    if is_snowing
+        && can_hold_snow
+        && chunks.height_limits().contains_y(above.y)
+        && !is_near_light(chunks, above)
+    {
+        chunks.set_block(&above, BlockData::layered(BlockId::SnowLayer, 1));
+    }
+}
+
+/// Random ticks of the surface blocks around the players, making snow pile up and melt
+pub fn snow_random_ticks_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    time: Res<ServerTime>,
+    random: Res<SimulationRandom>,
+) {
+    let world_map = world_map.as_mut();
+    let weather = world_map.weather;
+    let chunks = &mut world_map.chunks;
+
+    let mut columns: Vec<IVec2> = get_all_active_chunks(&world_map.players, 1)
+        .into_iter()
+        .map(|chunk| IVec2::new(chunk.x, chunk.z))
+        .collect();
+    columns.sort_by_key(|column| column.to_array());
+    columns.dedup();
+
+    for column in columns {
+        let mut rng = random.at_block(
+            RandomStream::Snow,
+            time.0,
+            IVec3::new(column.x, 0, column.y),
+        );
+        for _ in 0..RANDOM_TICKS_PER_COLUMN {
+            let x = column.x * CHUNK_SIZE + rng.gen_range(0..CHUNK_SIZE);
+            let z = column.y * CHUNK_SIZE + rng.gen_range(0..CHUNK_SIZE);
+            let Some(y) = chunks.get_surface_height(x, z) else {
+                continue;
+            };
+            let position = IVec3::new(x, y, z);
+            let Some(biome) = chunks.get_biome(&position) else {
+                continue;
+            };
+            snow_random_tick(
+                chunks,
+                position,
+                weather.is_snowing_in(biome),
+                biome.is_cold(),
+            );
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::math::bounding::Aabb3d;
+use bevy::prelude::*;
+use shared::{
+    messages::PlayerId,
+    world::{
+        aabb_chunk_range, positions_in_region, world_position_to_chunk_position, MobId,
+        ServerWorldMap,
+    },
+};
+
+/// Anything living in the world at some position
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpatialEntity {
+    Player(PlayerId),
+    Mob(MobId),
+    ItemStack(u128),
+}
+
+/// Entities of the world bucketed by the chunk they are in, to find the ones near a point without going through all of them
+#[derive(Resource, Default, Debug)]
+pub struct SpatialIndex {
+    buckets: HashMap<IVec3, HashSet<SpatialEntity>>,
+    positions: HashMap<SpatialEntity, Vec3>,
+}
+
+impl SpatialIndex {
+    /// Records the new position of an entity, it only changes bucket when it enters another chunk
+    pub fn update(&mut self, entity: SpatialEntity, position: Vec3) {
+        let chunk = world_position_to_chunk_position(position);
+
+        if let Some(previous) = self.positions.insert(entity, position) {
+            let previous_chunk = world_position_to_chunk_position(previous);
+            if previous_chunk == chunk {
+                return;
+            }
+            self.remove_from_bucket(entity, previous_chunk);
+        }
+
+        self.buckets.entry(chunk).or_default().insert(entity);
+    }
+
+    pub fn remove(&mut self, entity: SpatialEntity) {
+        if let Some(position) = self.positions.remove(&entity) {
+            self.remove_from_bucket(entity, world_position_to_chunk_position(position));
+        }
+    }
+
+    fn remove_from_bucket(&mut self, entity: SpatialEntity, chunk: IVec3) {
+        if let Some(bucket) = self.buckets.get_mut(&chunk) {
+            bucket.remove(&entity);
+            if bucket.is_empty() {
+                self.buckets.remove(&chunk);
+            }
+        }
+    }
+
+    /// Entities whose position is inside the box
+    pub fn query_aabb(&self, aabb: Aabb3d) -> impl Iterator<Item = (SpatialEntity, Vec3)> + '_ {
+        let (min, max) = aabb_chunk_range(&aabb);
+
+        positions_in_region(min, max)
+            .filter_map(|chunk| self.buckets.get(&chunk))
+            .flatten()
+            .map(|entity| (*entity, self.positions[entity]))
+            .filter(move |(_, position)| {
+                let position = Vec3A::from(*position);
+                position.cmpge(aabb.min).all() && position.cmple(aabb.max).all()
+            })
+    }
+
+    /// Entities closer than `radius` to `center`
+    pub fn query_radius(
+        &self,
+        center: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = (SpatialEntity, Vec3)> + '_ {
+        self.query_aabb(Aabb3d::new(center, Vec3::splat(radius)))
+            .filter(move |(_, position)| position.distance_squared(center) <= radius * radius)
+    }
+
+    pub fn players_in_radius(
+        &self,
+        center: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = PlayerId> + '_ {
+        self.query_radius(center, radius)
+            .filter_map(|(entity, _)| match entity {
+                SpatialEntity::Player(id) => Some(id),
+                _ => None,
+            })
+    }
+
+    pub fn mobs_in_radius(&self, center: Vec3, radius: f32) -> impl Iterator<Item = MobId> + '_ {
+        self.query_radius(center, radius)
+            .filter_map(|(entity, _)| match entity {
+                SpatialEntity::Mob(id) => Some(id),
+                _ => None,
+            })
+    }
+
+    pub fn item_stacks_in_radius(
+        &self,
+        center: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = u128> + '_ {
+        self.query_radius(center, radius)
+            .filter_map(|(entity, _)| match entity {
+                SpatialEntity::ItemStack(id) => Some(id),
+                _ => None,
+            })
+    }
+}
+
+/// Players, mobs and item stacks of the world with their position
+fn world_entities(world_map: &ServerWorldMap) -> impl Iterator<Item = (SpatialEntity, Vec3)> + '_ {
+    world_map
+        .players
+        .values()
+        .map(|player| (SpatialEntity::Player(player.id), player.position))
+        .chain(
+            world_map
+                .mobs
+                .iter()
+                .map(|(id, mob)| (SpatialEntity::Mob(*id), mob.position)),
+        )
+        .chain(
+            world_map
+                .item_stacks
+                .iter()
+                .filter(|stack| !stack.despawned)
+                .map(|stack| (SpatialEntity::ItemStack(stack.id), stack.pos)),
+        )
+}
+
+/// Brings the index up to date with the world map, only touching the entities that moved, spawned or left
+pub fn update_spatial_index_system(
+    world_map: Res<ServerWorldMap>,
+    mut index: ResMut<SpatialIndex>,
+) {
+    let mut alive = 0;
+    for (entity, position) in world_entities(&world_map) {
+        alive += 1;
+        if index.positions.get(&entity) != Some(&position) {
+            index.update(entity, position);
+        }
+    }
+
+    // Every entity of the world is indexed now, any extra one has left it
+    if index.positions.len() == alive {
+        return;
+    }
+    let alive: HashSet<SpatialEntity> = world_entities(&world_map)
+        .map(|(entity, _)| entity)
+        .collect();
+    let gone: Vec<SpatialEntity> = index
+        .positions
+        .keys()
+        .filter(|entity| !alive.contains(entity))
+        .copied()
+        .collect();
+    for entity in gone {
+        index.remove(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use shared::players::Player;
+
+    fn player(id: PlayerId, position: Vec3) -> Player {
+        Player {
+            id,
+            position,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn index_follows_moved_and_removed_entities() {
+        let mut world = World::new();
+        let mut world_map = ServerWorldMap::default();
+        world_map.players.insert(1, player(1, Vec3::ZERO));
+        world_map.players.insert(2, player(2, Vec3::ZERO));
+        world.insert_resource(world_map);
+        world.init_resource::<SpatialIndex>();
+        world.run_system_once(update_spatial_index_system).unwrap();
+
+        let mut world_map = world.resource_mut::<ServerWorldMap>();
+        world_map.players.get_mut(&1).unwrap().position = Vec3::new(100., 0., 0.);
+        world_map.players.remove(&2);
+        world.run_system_once(update_spatial_index_system).unwrap();
+
+        let index = world.resource::<SpatialIndex>();
+        assert_eq!(index.players_in_radius(Vec3::ZERO, 8.).count(), 0);
+        assert_eq!(
+            index
+                .players_in_radius(Vec3::new(100., 0., 0.), 8.)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(index.positions.len(), 1);
+    }
+}
@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+use shared::world::{ServerChunkWorldMap, WorldGenSettings};
+
+use crate::settings::ServerSettings;
+
+/// Whether a chunk column is one of the spawn chunks, which are generated even with no player around and never trimmed
+pub fn is_spawn_column(settings: &ServerSettings, column: IVec2) -> bool {
+    let radius = settings.spawn_chunks_radius as i32;
+    radius > 0 && column.x.abs() <= radius && column.y.abs() <= radius
+}
+
+/// Spawn chunks not generated yet, closest to spawn first
+pub fn missing_spawn_chunks<'a>(
+    settings: &'a ServerSettings,
+    chunks: &'a ServerChunkWorldMap,
+    limits: WorldGenSettings,
+) -> impl Iterator<Item = IVec3> + 'a {
+    let radius = settings.spawn_chunks_radius as i32;
+    (0..=radius)
+        .flat_map(move |ring| {
+            (-ring..=ring).flat_map(move |x| {
+                (-ring..=ring)
+                    .filter(move |z| x.abs() == ring || z.abs() == ring)
+                    .flat_map(move |z| limits.chunk_y_range().map(move |y| IVec3::new(x, y, z)))
+            })
+        })
+        .filter(move |pos| is_spawn_column(settings, pos.xz()) && !chunks.map.contains_key(pos))
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use shared::{
+    players::Inventory,
+    world::{
+        global_block_to_chunk_pos, positions_in_region, BlockData, BlockDirection, BlockId, ItemId,
+        ItemStack, RandomStream, ServerWorldMap, SimulationRandom, WorldMap, WorldSeed,
+    },
+};
+
+use super::{generation::sample_surface, preset::WorldGenPreset};
+
+/// Blocks from the spawn to the edges of the platform
+const PLATFORM_RADIUS: i32 = 2;
+/// Air left above the platform, trees and grass growing there are removed
+const PLATFORM_CLEARANCE: i32 = 3;
+const PLATFORM_BLOCK: BlockId = BlockId::OakPlanks;
+const CHEST_OFFSET: IVec3 = IVec3::new(PLATFORM_RADIUS, 1, PLATFORM_RADIUS);
+
+/// One line of a loot table, the item is left out if the roll fails
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LootEntry {
+    pub item: ItemId,
+    pub min: u32,
+    pub max: u32,
+    /// Between 0 and 1
+    pub chance: f32,
+}
+
+pub fn default_starter_chest() -> Vec<LootEntry> {
+    let entry = |item, min, max, chance| LootEntry {
+        item,
+        min,
+        max,
+        chance,
+    };
+    vec![
+        entry(ItemId::OakLog, 4, 8, 1.0),
+        entry(ItemId::WoodenPickaxe, 1, 1, 1.0),
+        entry(ItemId::Cobblestone, 8, 16, 1.0),
+        entry(ItemId::RottenFlesh, 2, 5, 1.0),
+        entry(ItemId::Bow, 1, 1, 0.5),
+        entry(ItemId::Arrow, 8, 16, 0.5),
+        entry(ItemId::IronOre, 1, 3, 0.5),
+    ]
+}
+
+/// Rolls a loot table into the inventory of a container
+pub fn roll_loot(table: &[LootEntry], rng: &mut impl Rng) -> Inventory {
+    let mut inventory = Inventory::new();
+    for entry in table {
+        if rng.gen::<f32>() >= entry.chance {
+            continue;
+        }
+        let nb = rng.gen_range(entry.min..=entry.max.max(entry.min));
+        if nb == 0 {
+            continue;
+        }
+        let stack = ItemStack {
+            item_id: entry.item,
+            item_type: entry.item.get_default_type(),
+            nb,
+        };
+        if let Err(err) = inventory.try_insert(stack) {
+            warn!("Loot table does not fit in a chest: {}", err);
+        }
+    }
+    inventory
+}
+
+/// Where the floor of the platform goes, just above the ground or the sea at the spawn
+fn platform_center(world_map: &ServerWorldMap, seed: &WorldSeed) -> IVec3 {
+    let limits = world_map.chunks.gen_settings;
+    let ground = sample_surface(seed.0, IVec2::ZERO, 1)[0];
+    let floor = ground.height.max(ground.water_level) + 1;
+    IVec3::new(
+        0,
+        floor.clamp(limits.min_y, limits.max_y - PLATFORM_CLEARANCE - 1),
+        0,
+    )
+}
+
+/// Builds the spawn platform and its starter chest once every chunk under them is generated.
+/// It only happens once per world, the save remembers it was built.
+pub fn build_spawn_platform_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    seed: Res<WorldSeed>,
+    preset: Res<WorldGenPreset>,
+    random: Res<SimulationRandom>,
+) {
+    if !world_map.chunks.spawn_platform_pending {
+        return;
+    }
+
+    let center = platform_center(&world_map, &seed);
+    let min = center - IVec3::new(PLATFORM_RADIUS, 0, PLATFORM_RADIUS);
+    let max = center + IVec3::new(PLATFORM_RADIUS, PLATFORM_CLEARANCE, PLATFORM_RADIUS);
+    let all_generated = positions_in_region(min, max).all(|position| {
+        world_map
+            .chunks
+            .map
+            .contains_key(&global_block_to_chunk_pos(&position))
+    });
+    if !all_generated {
+        return;
+    }
+
+    let floor = BlockData::new(PLATFORM_BLOCK, BlockDirection::Front);
+    let chest_pos = center + CHEST_OFFSET;
+    let chunks = &mut world_map.chunks;
+    chunks.fill_region(min, max.with_y(center.y), Some(floor));
+    chunks.fill_region(min + IVec3::Y, max, None);
+    chunks.set_block(
+        &chest_pos,
+        BlockData::new(BlockId::Chest, BlockDirection::Front),
+    );
+    chunks.spawn_platform_pending = false;
+
+    // Part of the world rather than of a tick, the same seed always fills the chest the same way
+    let mut rng = random.at_block(RandomStream::ChestLoot, 0, chest_pos);
+    world_map
+        .containers
+        .insert(chest_pos, roll_loot(&preset.starter_chest, &mut rng));
+    info!("Built the spawn platform at {:?}", center);
+}
@@ -0,0 +1,85 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{PlayerId, ServerToClientMessage},
+    players::GameMode,
+    world::ServerWorldMap,
+};
+
+use crate::network::extensions::SendGameMessageExtension;
+
+#[derive(Event, Debug)]
+pub struct PlayerSpectateRequestEvent {
+    pub client_id: PlayerId,
+    /// `None` stops following anyone
+    pub target: Option<PlayerId>,
+}
+
+/// Tells each player their game mode when they join and whenever it changes
+pub fn send_game_modes_system(
+    world_map: Res<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    mut known: Local<HashMap<PlayerId, GameMode>>,
+) {
+    known.retain(|id, _| world_map.players.contains_key(id));
+
+    for player in world_map.players.values() {
+        if known.insert(player.id, player.game_mode) != Some(player.game_mode) {
+            server.send_game_message(player.id, ServerToClientMessage::GameMode(player.game_mode));
+        }
+    }
+}
+
+pub fn handle_spectate_requests_system(
+    mut events: EventReader<PlayerSpectateRequestEvent>,
+    mut world_map: ResMut<ServerWorldMap>,
+) {
+    for event in events.read() {
+        let target = event
+            .target
+            .filter(|target| *target != event.client_id && world_map.players.contains_key(target));
+        let Some(player) = world_map.players.get_mut(&event.client_id) else {
+            continue;
+        };
+        if player.game_mode != GameMode::Spectator {
+            debug!(
+                "Player {} cannot spectate outside spectator mode",
+                player.id
+            );
+            continue;
+        }
+        debug!("Player {} now spectates {:?}", player.id, target);
+        player.spectating = target;
+    }
+}
+
+/// Moves the spectators along with the players they follow, so that they get the chunks around them.
+/// They stop following players who leave, and stop altogether when they leave spectator mode.
+pub fn spectators_follow_system(mut world_map: ResMut<ServerWorldMap>) {
+    let followed: Vec<(PlayerId, Option<(Vec3, Transform)>)> = world_map
+        .players
+        .values()
+        .filter_map(|player| {
+            let target = player.spectating?;
+            let view = world_map
+                .players
+                .get(&target)
+                .map(|target| (target.position, target.camera_transform));
+            Some((player.id, view))
+        })
+        .collect();
+
+    for (id, view) in followed {
+        let Some(player) = world_map.players.get_mut(&id) else {
+            continue;
+        };
+        match view {
+            Some((position, camera_transform)) if player.game_mode == GameMode::Spectator => {
+                player.position = position;
+                player.camera_transform = camera_transform;
+                player.velocity = Vec3::ZERO;
+            }
+            _ => player.spectating = None,
+        }
+    }
+}
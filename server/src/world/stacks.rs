@@ -1,9 +1,139 @@
-// use bevy::prelude::*;
-// use shared::world::ServerWorldMap;
-
-// pub fn item_stacks_system(mut world_map: ResMut<ServerWorldMap>) {
-//     let current_ts = std::time::SystemTime::now()
-//         .duration_since(std::time::UNIX_EPOCH)
-//         .unwrap()
-//         .as_millis() as u64;
-// }
+use bevy::prelude::*;
+use rand::Rng;
+use shared::players::{InventoryError, Player};
+use shared::world::{new_entity_id, ItemStack, ServerItemStack, ServerWorldMap};
+
+use super::spatial::SpatialIndex;
+
+/// Players pick up the item stacks lying closer than this
+const PICKUP_RADIUS: f32 = 1.5;
+
+/// Drops a stack on the ground, its id is drawn from `ids`
+pub fn spawn_item_stack(
+    item_stacks: &mut Vec<ServerItemStack>,
+    stack: ItemStack,
+    pos: Vec3,
+    ids: &mut impl Rng,
+) {
+    let id = new_entity_id(ids, |id| item_stacks.iter().any(|stack| stack.id == id));
+    item_stacks.push(ServerItemStack {
+        id,
+        despawned: false,
+        stack,
+        pos,
+        timestamp: 0,
+        metadata: default(),
+    });
+}
+
+/// Gives items to a player, what does not fit in their inventory is dropped at their feet
+pub fn give_or_drop(
+    player: &mut Player,
+    stack: ItemStack,
+    item_stacks: &mut Vec<ServerItemStack>,
+    ids: &mut impl Rng,
+) {
+    match player.inventory.try_insert(stack) {
+        Ok(()) => {}
+        Err(InventoryError::Full { remaining }) => {
+            debug!(
+                "Inventory of {} is full, dropping {:?} x{}",
+                player.name, stack.item_id, remaining
+            );
+            spawn_item_stack(
+                item_stacks,
+                ItemStack {
+                    nb: remaining,
+                    ..stack
+                },
+                player.position,
+                ids,
+            );
+        }
+        Err(e) => warn!("Player {} lost {:?}: {}", player.name, stack, e),
+    }
+}
+
+pub fn item_stacks_pickup_system(mut world_map: ResMut<ServerWorldMap>, index: Res<SpatialIndex>) {
+    let world_map = world_map.as_mut();
+
+    for player in world_map.players.values_mut() {
+        for id in index.item_stacks_in_radius(player.position, PICKUP_RADIUS) {
+            let Some(stack) = world_map
+                .item_stacks
+                .iter_mut()
+                .find(|stack| stack.id == id && !stack.despawned)
+            else {
+                continue;
+            };
+
+            match player.inventory.try_insert(stack.stack) {
+                Ok(()) => {
+                    debug!("Player {} picked up {:?}", player.name, stack.stack);
+                    stack.despawned = true;
+                }
+                // What did not fit stays on the ground
+                Err(InventoryError::Full { remaining }) => stack.stack.nb = remaining,
+                Err(e) => warn!(
+                    "Player {} could not pick up {:?}: {}",
+                    player.name, stack.stack, e
+                ),
+            }
+        }
+    }
+
+    world_map.item_stacks.retain(|stack| !stack.despawned);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+    use shared::world::ItemId;
+
+    #[test]
+    fn items_fitting_in_the_inventory_are_not_dropped() {
+        let mut player = Player::default();
+        let mut item_stacks = Vec::new();
+
+        give_or_drop(
+            &mut player,
+            ItemStack::new(ItemId::Dirt, 100),
+            &mut item_stacks,
+            &mut StdRng::seed_from_u64(0),
+        );
+
+        assert_eq!(player.inventory.count_of(ItemId::Dirt), 100);
+        assert!(item_stacks.is_empty());
+    }
+
+    #[test]
+    fn overflow_is_dropped_at_the_feet_of_the_player() {
+        let mut player = Player {
+            position: Vec3::new(3.0, 70.0, -2.0),
+            ..default()
+        };
+        // Fills every slot, then leaves room for 4 items in the first one
+        let overflow = player
+            .inventory
+            .try_insert(ItemStack::new(ItemId::Dirt, u32::MAX));
+        assert!(overflow.is_err());
+        player.inventory.take_from_slot(0, 4).unwrap();
+        let mut item_stacks = Vec::new();
+
+        give_or_drop(
+            &mut player,
+            ItemStack::new(ItemId::Dirt, 10),
+            &mut item_stacks,
+            &mut StdRng::seed_from_u64(0),
+        );
+
+        assert_eq!(
+            player.inventory.get(0),
+            Some(&ItemStack::new(ItemId::Dirt, 64))
+        );
+        assert_eq!(item_stacks.len(), 1);
+        assert_eq!(item_stacks[0].stack, ItemStack::new(ItemId::Dirt, 6));
+        assert_eq!(item_stacks[0].pos, player.position);
+    }
+}
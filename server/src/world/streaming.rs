@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use shared::world::{world_position_to_chunk_position, ServerChunkWorldMap, WorldGenSettings};
+
+/// Columns around a joining player sent before they enter the world, the 3x3 around them
+pub const SPAWN_AREA_RADIUS: i32 = 1;
+/// Chunks of the spawn area sent to a player per tick, at full priority
+pub const SPAWN_AREA_CHUNKS_PER_TICK: usize = 32;
+/// Chunks of the outer rings sent to a player per tick, once the spawn area is sent
+pub const RING_CHUNKS_PER_TICK: usize = 8;
+
+/// Chunks around a player in the order they are streamed to them: the full columns of the spawn area, then the
+/// columns ring by ring up to `radius` away. Within a ring, the layers closest to the player come first.
+/// The flag tells the chunks of the spawn area
+pub fn streaming_order(
+    position: Vec3,
+    radius: u32,
+    limits: WorldGenSettings,
+) -> impl Iterator<Item = (IVec3, bool)> {
+    let center = world_position_to_chunk_position(position);
+    let radius = (radius as i32).max(SPAWN_AREA_RADIUS);
+
+    let spawn_area = (0..=SPAWN_AREA_RADIUS).map(|ring| (ring, true));
+    let rings = (SPAWN_AREA_RADIUS + 1..=radius).map(|ring| (ring, false));
+    spawn_area
+        .chain(rings)
+        .flat_map(move |(ring, in_spawn_area)| {
+            let mut chunks: Vec<IVec3> = ring_columns(center.xz(), ring)
+                .flat_map(|column| {
+                    limits
+                        .chunk_y_range()
+                        .map(move |y| IVec3::new(column.x, y, column.y))
+                })
+                .collect();
+            chunks.sort_by_key(|chunk| (chunk.y - center.y).abs());
+            chunks.into_iter().map(move |chunk| (chunk, in_spawn_area))
+        })
+}
+
+/// Columns exactly `ring` columns away from `center`, the center itself for 0
+fn ring_columns(center: IVec2, ring: i32) -> impl Iterator<Item = IVec2> {
+    (-ring..=ring).flat_map(move |x| {
+        (-ring..=ring)
+            .filter(move |z| x.abs() == ring || z.abs() == ring)
+            .map(move |z| center + IVec2::new(x, z))
+    })
+}
+
+/// Existing chunks of the spawn area of a player at `position`, the client enters the world once it has them all
+pub fn count_spawn_area_chunks(chunks: &ServerChunkWorldMap, position: Vec3) -> u32 {
+    streaming_order(position, SPAWN_AREA_RADIUS as u32, chunks.gen_settings)
+        .filter(|(chunk, _)| chunks.map.contains_key(chunk))
+        .count() as u32
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use shared::{
+    messages::{PlayerId, ServerToClientMessage, TickRateUpdate},
+    world::ServerWorldMap,
+    GameServerConfig, TICKS_PER_SECOND,
+};
+
+use crate::{
+    network::extensions::SendGameMessageExtension, settings::ServerSettings,
+    world::protection::is_player_op,
+};
+
+const TICK_USAGE: &str = "Usage: /tick [freeze|unfreeze|step [count]|rate <tps>]";
+
+/// Frozen or slowed down simulation, set with `/tick`. The network, the chat and the
+/// player inputs keep running every tick so that the world can be inspected meanwhile
+#[derive(Resource, Default)]
+pub struct TickControl {
+    pub state: TickRateUpdate,
+    /// Ticks still to simulate while frozen
+    steps: u64,
+    /// Builds up by the rate every tick, a tick is simulated each time it reaches `TICKS_PER_SECOND`
+    progress: u64,
+    /// Whether the world is simulated during the current tick
+    ticking: bool,
+}
+
+/// Decides at the start of each tick whether the world is simulated during it
+pub fn tick_control_system(mut control: ResMut<TickControl>) {
+    let control = &mut *control;
+    control.ticking = if control.state.frozen {
+        let step = control.steps > 0;
+        control.steps = control.steps.saturating_sub(1);
+        step
+    } else {
+        control.progress += control.state.rate;
+        let due = control.progress >= TICKS_PER_SECOND;
+        if due {
+            control.progress -= TICKS_PER_SECOND;
+        }
+        due
+    };
+}
+
+/// Run condition of the systems simulating the world: block ticks, mobs, items, projectiles and the clock
+pub fn simulation_ticking(control: Res<TickControl>) -> bool {
+    control.ticking
+}
+
+/// Tells the clients when `/tick` changes the simulation speed
+pub fn broadcast_tick_rate_system(
+    control: Res<TickControl>,
+    mut server: ResMut<RenetServer>,
+    mut last_sent: Local<Option<TickRateUpdate>>,
+) {
+    if *last_sent == Some(control.state) {
+        return;
+    }
+    *last_sent = Some(control.state);
+    server.broadcast_game_message(ServerToClientMessage::TickRate(control.state));
+}
+
+/// Handles `/tick`, anyone can read the simulation speed but only ops can change it
+pub fn tick_command(
+    world_map: &ServerWorldMap,
+    control: &mut TickControl,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to use /tick".to_string();
+    };
+    let arg = |i: usize| args.get(i).map(String::as_str);
+
+    if arg(0).is_none() {
+        return if control.state.frozen {
+            "The simulation is frozen".to_string()
+        } else {
+            format!(
+                "The simulation runs at {} ticks per second",
+                control.state.rate
+            )
+        };
+    }
+    if !is_player_op(settings, config, &sender.name) {
+        return "Only ops can change the simulation speed".to_string();
+    }
+
+    match (arg(0), arg(1), arg(2)) {
+        (Some("freeze"), None, _) => {
+            info!("{} froze the simulation", sender.name);
+            control.state.frozen = true;
+            control.steps = 0;
+            "The simulation is frozen, use /tick step to advance it".to_string()
+        }
+        (Some("unfreeze"), None, _) => {
+            info!("{} unfroze the simulation", sender.name);
+            control.state.frozen = false;
+            format!(
+                "The simulation runs at {} ticks per second",
+                control.state.rate
+            )
+        }
+        (Some("step"), count, None) => {
+            if !control.state.frozen {
+                return "The simulation must be frozen to step it".to_string();
+            }
+            let count = match count.map(str::parse::<u64>) {
+                None => 1,
+                Some(Ok(count)) if count > 0 => count,
+                Some(_) => return TICK_USAGE.to_string(),
+            };
+            control.steps += count;
+            format!("Stepping {count} ticks")
+        }
+        (Some("rate"), Some(rate), None) => match rate.parse::<u64>() {
+            Ok(rate) if (1..=TICKS_PER_SECOND).contains(&rate) => {
+                info!(
+                    "{} set the simulation to {} ticks per second",
+                    sender.name, rate
+                );
+                control.state.rate = rate;
+                control.progress = 0;
+                format!("The simulation runs at {rate} ticks per second")
+            }
+            _ => format!("The rate must be between 1 and {TICKS_PER_SECOND} ticks per second"),
+        },
+        _ => TICK_USAGE.to_string(),
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use shared::{
+    world::{world_position_to_chunk_position, ChunkClaim, ServerChunk, ServerWorldMap},
+    GameFolderPaths,
+};
+
+use crate::settings::ServerSettings;
+
+use super::{
+    data::{CHUNKS_SAVE_DIR, SAVE_PATH},
+    load_from_file::load_chunks_data,
+    save::{chunk_file_name, WorldData},
+    spawn_chunks::is_spawn_column,
+};
+
+/// Columns this close to spawn, in chunks, are always kept
+pub const MIN_TRIM_RADIUS: u32 = 2;
+/// Columns this close to an online player, in chunks, are kept
+const PLAYER_KEEP_RADIUS: i32 = 8;
+
+/// Chunk columns farther than `radius` chunks from spawn in which no player changed anything.
+/// Columns are trimmed whole, so that the heightmap and the generated terrain stay consistent
+fn trimmable_columns<'a>(
+    chunks: impl Iterator<Item = (&'a IVec3, &'a ServerChunk)>,
+    claims: &HashMap<IVec2, ChunkClaim>,
+    radius: u32,
+    kept: impl Fn(IVec2) -> bool,
+) -> HashSet<IVec2> {
+    let radius = radius.max(MIN_TRIM_RADIUS) as i32;
+    let mut modified = HashSet::new();
+    let mut columns = HashSet::new();
+    for (pos, chunk) in chunks {
+        let column = IVec2::new(pos.x, pos.z);
+        if chunk.player_modified {
+            modified.insert(column);
+        }
+        columns.insert(column);
+    }
+
+    columns
+        .into_iter()
+        .filter(|column| column.x.abs().max(column.y.abs()) > radius)
+        .filter(|column| !modified.contains(column) && !claims.contains_key(column))
+        .filter(|column| !kept(*column))
+        .collect()
+}
+
+/// Removes the untouched columns far from spawn and from the online players, returns how many chunks were removed.
+/// Their files are deleted by the next save, spawn chunks are always kept
+pub fn trim_world(world_map: &mut ServerWorldMap, settings: &ServerSettings, radius: u32) -> usize {
+    let player_columns: Vec<IVec2> = world_map
+        .players
+        .values()
+        .map(|player| world_position_to_chunk_position(player.position).xz())
+        .collect();
+    let kept = |column: IVec2| {
+        is_spawn_column(settings, column)
+            || player_columns.iter().any(|player| {
+                let d = (column - *player).abs();
+                d.x.max(d.y) <= PLAYER_KEEP_RADIUS
+            })
+    };
+
+    let columns = trimmable_columns(world_map.chunks.map.iter(), &world_map.claims, radius, kept);
+
+    let before = world_map.chunks.map.len();
+    for column in columns {
+        world_map.chunks.remove_column(column);
+    }
+    before - world_map.chunks.map.len()
+}
+
+/// Deletes the chunk files of a world which is not running, returns how many chunks were (or would be) removed
+#[allow(dead_code)]
+pub fn trim_world_files(
+    world_name: &str,
+    paths: &GameFolderPaths,
+    settings: &ServerSettings,
+    radius: u32,
+    dry_run: bool,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let world_dir = paths.game_folder_path.join(SAVE_PATH).join(world_name);
+    let world_data: WorldData =
+        ron::from_str(&std::fs::read_to_string(world_dir.join("world.ron"))?)?;
+    let chunks = load_chunks_data(world_name, paths);
+
+    let columns = trimmable_columns(chunks.iter(), &world_data.claims, radius, |column| {
+        is_spawn_column(settings, column)
+    });
+    let removed: Vec<&IVec3> = chunks
+        .keys()
+        .filter(|pos| columns.contains(&IVec2::new(pos.x, pos.z)))
+        .collect();
+
+    if !dry_run {
+        let chunks_dir = world_dir.join(CHUNKS_SAVE_DIR);
+        for pos in removed.iter() {
+            std::fs::remove_file(chunks_dir.join(chunk_file_name(pos)))?;
+        }
+    }
+    Ok(removed.len())
+}
@@ -0,0 +1,116 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use bevy_renet::renet::RenetServer;
+use rand::Rng;
+use shared::{
+    messages::{PlayerId, ServerToClientMessage},
+    world::{RandomStream, ServerWorldMap, SimulationRandom, Weather},
+    GameServerConfig, TICKS_PER_SECOND,
+};
+
+use crate::{
+    init::ServerTime, network::extensions::SendGameMessageExtension, settings::ServerSettings,
+    world::protection::is_player_op,
+};
+
+/// Ticks the sky stays clear, picked at random
+const CLEAR_DURATION: Range<u64> = 5 * 60 * TICKS_PER_SECOND..15 * 60 * TICKS_PER_SECOND;
+/// Ticks precipitation lasts, picked at random
+const PRECIPITATION_DURATION: Range<u64> = 2 * 60 * TICKS_PER_SECOND..5 * 60 * TICKS_PER_SECOND;
+
+const WEATHER_USAGE: &str = "Usage: /weather [clear|precipitation] [seconds]";
+
+fn random_duration(weather: Weather, rng: &mut impl Rng) -> u64 {
+    let range = match weather {
+        Weather::Clear => CLEAR_DURATION,
+        Weather::Precipitation => PRECIPITATION_DURATION,
+    };
+    rng.gen_range(range)
+}
+
+/// Switches between clear skies and precipitation once the current weather is over
+pub fn weather_system(
+    mut world_map: ResMut<ServerWorldMap>,
+    time: Res<ServerTime>,
+    random: Res<SimulationRandom>,
+) {
+    let mut rng = random.for_tick(RandomStream::Weather, time.0);
+    let state = &mut world_map.weather;
+    if state.remaining_ticks == 0 {
+        state.remaining_ticks = random_duration(state.weather, &mut rng);
+        return;
+    }
+
+    state.remaining_ticks -= 1;
+    if state.remaining_ticks == 0 {
+        state.weather = match state.weather {
+            Weather::Clear => Weather::Precipitation,
+            Weather::Precipitation => Weather::Clear,
+        };
+        state.remaining_ticks = random_duration(state.weather, &mut rng);
+        info!("The weather is now {}", state.weather.name());
+    }
+}
+
+/// Tells the clients when the weather changes, whether it ran out or was set with `/weather`
+pub fn broadcast_weather_system(
+    world_map: Res<ServerWorldMap>,
+    mut server: ResMut<RenetServer>,
+    mut last_sent: Local<Option<Weather>>,
+) {
+    let weather = world_map.weather.weather;
+    if *last_sent == Some(weather) {
+        return;
+    }
+    *last_sent = Some(weather);
+    server.broadcast_game_message(ServerToClientMessage::Weather(weather));
+}
+
+/// Handles `/weather`, anyone can read the weather but only ops can change it
+pub fn weather_command(
+    world_map: &mut ServerWorldMap,
+    settings: &ServerSettings,
+    config: &GameServerConfig,
+    client_id: PlayerId,
+    args: &[String],
+    rng: &mut impl Rng,
+) -> String {
+    let Some(sender) = world_map.players.get(&client_id) else {
+        return "You must be in the world to use the weather".to_string();
+    };
+    let arg = |i: usize| args.get(i).map(String::as_str);
+
+    match (arg(0), arg(1), arg(2)) {
+        (None, _, _) => {
+            let state = world_map.weather;
+            format!(
+                "The weather is {} for {}s",
+                state.weather.name(),
+                state.remaining_ticks / TICKS_PER_SECOND
+            )
+        }
+        (Some(name), seconds, None) => {
+            let Some(weather) = Weather::from_name(name) else {
+                return WEATHER_USAGE.to_string();
+            };
+            let duration = match seconds.map(str::parse::<u64>) {
+                None => random_duration(weather, rng),
+                Some(Ok(seconds)) if seconds > 0 => seconds * TICKS_PER_SECOND,
+                Some(_) => return WEATHER_USAGE.to_string(),
+            };
+            if !is_player_op(settings, config, &sender.name) {
+                return "Only ops can change the weather".to_string();
+            }
+            info!("{} set the weather to {}", sender.name, weather.name());
+            world_map.weather.weather = weather;
+            world_map.weather.remaining_ticks = duration;
+            format!(
+                "The weather is now {} for {}s",
+                weather.name(),
+                duration / TICKS_PER_SECOND
+            )
+        }
+        _ => WEATHER_USAGE.to_string(),
+    }
+}
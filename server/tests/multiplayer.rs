@@ -0,0 +1,477 @@
+//! Runs a server with a few clients in the same process, over the in-memory transport
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy_renet::renet::{ClientId, RenetClient};
+use server::{build_server_app, ServerListener};
+use shared::messages::{
+    AuthRegisterRequest, AuthRegisterResponse, CachedChunksBatch, ClientToServerMessage,
+    NetworkAction, PlayerFrameInput, ServerToClientMessage,
+};
+use shared::network::{
+    ClientTransport, MemoryClientTransport, MemoryConnector, MemoryServerTransport,
+};
+use shared::players::{movement::simulate_player_movement, Player, ViewMode};
+use shared::utils::unix_time_ms;
+use shared::world::{
+    world_position_to_chunk_position, ServerChunkWorldMap, ServerWorldMap, WorldMap,
+};
+use shared::{
+    game_message_to_payload, get_shared_renet_config, payload_to_game_message,
+    ChannelResolvableExt, GameFolderPaths, GameServerConfig, CHUNK_SIZE, STC_AUTH_CHANNEL,
+    STC_CHUNK_DATA_CHANNEL, STC_PACK_CHANNEL, STC_STANDARD_CHANNEL, TICKS_PER_SECOND,
+};
+
+const TICK: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND);
+/// Generating the spawn area takes a while in debug builds
+const TIMEOUT: Duration = Duration::from_secs(60);
+/// Lets players edit blocks at the spawn, where the tests take place
+const TEST_SERVER_SETTINGS: &str = "(spawn_protection_radius: 0)";
+
+/// Server app along with the folder its world is saved in, removed once the test is over
+struct TestServer {
+    app: App,
+    connector: MemoryConnector,
+    folder: PathBuf,
+}
+
+impl TestServer {
+    fn start(name: &str) -> Self {
+        let folder =
+            std::env::temp_dir().join(format!("rustcraft-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("server_settings.ron"), TEST_SERVER_SETTINGS).unwrap();
+
+        let (transport, connector) = MemoryServerTransport::new();
+        let mut app = build_server_app(
+            ServerListener::Memory(transport),
+            GameServerConfig {
+                world_name: name.into(),
+                is_solo: false,
+                world_seed: Some(42),
+                world_gen_settings: None,
+                spawn_platform: false,
+                pregen_radius: None,
+            },
+            GameFolderPaths {
+                game_folder_path: folder.clone(),
+                assets_folder_path: folder.join("assets"),
+            },
+        )
+        .unwrap();
+        app.finish();
+        app.cleanup();
+        // Mobs would push the players around, away from what their clients predict
+        app.world_mut()
+            .resource_mut::<ServerWorldMap>()
+            .game_rules
+            .mob_spawning = false;
+
+        Self {
+            app,
+            connector,
+            folder,
+        }
+    }
+}
+
+impl TestServer {
+    fn world_map(&mut self) -> Mut<'_, ServerWorldMap> {
+        self.app.world_mut().resource_mut::<ServerWorldMap>()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.folder);
+    }
+}
+
+/// Input the client sends on one tick of its script
+#[derive(Clone)]
+struct ScriptedInput {
+    actions: HashSet<NetworkAction>,
+    camera: Transform,
+    view_mode: ViewMode,
+}
+
+impl ScriptedInput {
+    fn new(actions: &[NetworkAction], camera: Transform, view_mode: ViewMode) -> Self {
+        Self {
+            actions: actions.iter().cloned().collect(),
+            camera,
+            view_mode,
+        }
+    }
+
+    fn idle() -> Self {
+        Self::new(&[], Transform::default(), ViewMode::FirstPerson)
+    }
+}
+
+/// Headless client which keeps track of what the server sent it, and plays scripted inputs
+/// predicting its movement like the game client does
+struct TestClient {
+    id: ClientId,
+    name: String,
+    client: RenetClient,
+    transport: MemoryClientTransport,
+    auth_sent: bool,
+    auth: Option<AuthRegisterResponse>,
+    joined: Vec<ClientId>,
+    chunks: ServerChunkWorldMap,
+    /// Inputs still to send, one per tick
+    script: VecDeque<ScriptedInput>,
+    /// Predicted player, moved by each input as it is sent and put back where the server says on mispredictions
+    player: Player,
+    /// Inputs sent which the server did not acknowledge yet, along with the predicted position
+    unacknowledged: Vec<PlayerFrameInput>,
+    last_input_ms: u64,
+    /// Acknowledged inputs whose predicted position differed from the one of the server
+    mispredictions: u32,
+    /// Position the server last reported for the player, with the last input it had processed then
+    server_position: Option<(u64, Vec3)>,
+}
+
+impl TestClient {
+    fn connect(server: &TestServer, id: ClientId, name: &str) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            client: RenetClient::new(get_shared_renet_config()),
+            transport: server.connector.connect(id),
+            auth_sent: false,
+            auth: None,
+            joined: vec![],
+            chunks: ServerChunkWorldMap::default(),
+            script: VecDeque::new(),
+            player: Player::default(),
+            unacknowledged: vec![],
+            last_input_ms: 0,
+            mispredictions: 0,
+            server_position: None,
+        }
+    }
+
+    fn has_spawn_area(&self) -> bool {
+        self.auth
+            .as_ref()
+            .is_some_and(|auth| self.chunks.map.len() as u32 >= auth.spawn_chunks)
+    }
+
+    /// Queues the same input for `ticks` ticks
+    fn script(&mut self, ticks: usize, input: ScriptedInput) {
+        self.script.extend(std::iter::repeat_n(input, ticks));
+    }
+
+    /// Whether the script ran out and the server processed every input of it
+    fn script_done(&self) -> bool {
+        self.script.is_empty() && self.unacknowledged.is_empty()
+    }
+
+    fn send(&mut self, message: ClientToServerMessage) {
+        self.client
+            .send_message(message.get_channel_id(), game_message_to_payload(message));
+    }
+
+    /// Sends the next input of the script, moving the predicted player with it
+    fn play_script(&mut self) {
+        if self.auth.is_none() {
+            return;
+        }
+        let Some(scripted) = self.script.pop_front() else {
+            return;
+        };
+
+        // The server drops inputs which are not newer than the last one
+        self.last_input_ms = unix_time_ms().max(self.last_input_ms + 1);
+        let mut input = PlayerFrameInput {
+            time_ms: self.last_input_ms,
+            delta_ms: TICK.as_millis() as u64,
+            inputs: scripted.actions,
+            camera: scripted.camera,
+            hotbar_slot: 0,
+            view_mode: scripted.view_mode,
+            ..default()
+        };
+        simulate_player_movement(&mut self.player, &self.chunks, &input);
+        input.position = self.player.position;
+
+        self.unacknowledged.push(input.clone());
+        self.send(ClientToServerMessage::PlayerInputs(vec![input]));
+    }
+
+    fn update(&mut self) {
+        self.client.update(TICK);
+        self.transport.receive(TICK, &mut self.client).unwrap();
+
+        if self.client.is_connected() && !self.auth_sent {
+            self.auth_sent = true;
+            self.send(ClientToServerMessage::AuthRegisterRequest(
+                AuthRegisterRequest {
+                    username: self.name.clone(),
+                },
+            ));
+        }
+
+        for channel in [
+            STC_AUTH_CHANNEL,
+            STC_STANDARD_CHANNEL,
+            STC_CHUNK_DATA_CHANNEL,
+            STC_PACK_CHANNEL,
+        ] {
+            while let Some(payload) = self.client.receive_message(channel) {
+                let message: ServerToClientMessage = payload_to_game_message(&payload).unwrap();
+                self.handle(message);
+            }
+        }
+
+        self.play_script();
+        self.transport.send(&mut self.client).unwrap();
+    }
+
+    fn handle(&mut self, message: ServerToClientMessage) {
+        match message {
+            ServerToClientMessage::AuthRegisterResponse(response) => {
+                self.joined
+                    .extend(response.players.iter().map(|player| player.id));
+                if let Some(spawn) = response.players.iter().find(|player| player.id == self.id) {
+                    self.player = Player {
+                        id: self.id,
+                        name: self.name.clone(),
+                        position: spawn.data.position,
+                        is_flying: spawn.data.is_flying,
+                        ..default()
+                    };
+                }
+                self.auth = Some(response);
+                // No chunk is cached, the server streams them all
+                self.send(ClientToServerMessage::CachedChunks(CachedChunksBatch {
+                    chunks: vec![],
+                    last: true,
+                }));
+            }
+            ServerToClientMessage::PlayerJoined(player) => {
+                self.joined.push(player.id);
+            }
+            ServerToClientMessage::WorldUpdate(update) => {
+                for (pos, chunk) in update.new_map {
+                    self.chunks.insert_chunk(pos, chunk);
+                }
+            }
+            ServerToClientMessage::PlayerUpdate(update) if update.id == self.id => {
+                self.server_position = Some((update.last_ack_time, update.position));
+                self.reconcile(update.last_ack_time, update.position);
+            }
+            _ => {}
+        }
+    }
+
+    /// Same as the game client: on a misprediction the player goes where the server says,
+    /// and the inputs the server did not process yet are replayed from there
+    fn reconcile(&mut self, last_ack_time: u64, position: Vec3) {
+        let Some(acked) = self
+            .unacknowledged
+            .iter()
+            .position(|input| input.time_ms == last_ack_time)
+        else {
+            return;
+        };
+
+        if self.unacknowledged[acked].position != position {
+            self.mispredictions += 1;
+            self.player.position = position;
+            for input in self.unacknowledged[acked + 1..].iter_mut() {
+                simulate_player_movement(&mut self.player, &self.chunks, input);
+                input.position = self.player.position;
+            }
+        }
+        self.unacknowledged.drain(..=acked);
+    }
+}
+
+/// Runs the server and the clients tick by tick until `done` holds for every client
+fn run_until(
+    server: &mut TestServer,
+    clients: &mut [TestClient],
+    done: impl Fn(&TestClient) -> bool,
+) {
+    let start = Instant::now();
+    while !clients.iter().all(&done) {
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "Timed out, the clients are at {:?}",
+            clients
+                .iter()
+                .map(|client| (
+                    &client.name,
+                    client.auth.is_some(),
+                    client.chunks.map.len(),
+                    client.script.len(),
+                    client.unacknowledged.len()
+                ))
+                .collect::<Vec<_>>()
+        );
+        for client in clients.iter_mut() {
+            client.update();
+        }
+        server.app.update();
+    }
+}
+
+/// Runs a few more ticks, for the messages still on their way to arrive
+fn settle(server: &mut TestServer, clients: &mut [TestClient]) {
+    for _ in 0..TICKS_PER_SECOND {
+        for client in clients.iter_mut() {
+            client.update();
+        }
+        server.app.update();
+    }
+}
+
+#[test]
+fn clients_join_and_receive_the_spawn_area() {
+    let mut server = TestServer::start("join");
+    let mut clients: Vec<TestClient> = (1..=3)
+        .map(|id| TestClient::connect(&server, id, &format!("Player-{id}")))
+        .collect();
+
+    run_until(&mut server, &mut clients, |client| {
+        client.joined.len() == 3 && client.has_spawn_area()
+    });
+
+    for (client, id) in clients.iter().zip(1..) {
+        let auth = client.auth.as_ref().unwrap();
+        assert_eq!(auth.session_token, id);
+        assert_eq!(auth.username, client.name);
+        assert!(auth.spawn_chunks > 0);
+
+        // Each player sees itself and the two others
+        let mut joined = client.joined.clone();
+        joined.sort();
+        assert_eq!(joined, vec![1, 2, 3]);
+
+        // The column the player spawns in is among the first sent
+        let spawn = auth
+            .players
+            .iter()
+            .find(|player| player.id == id)
+            .unwrap()
+            .data
+            .position;
+        let spawn_chunk = world_position_to_chunk_position(spawn);
+        assert!(client
+            .chunks
+            .map
+            .keys()
+            .any(|chunk| chunk.xz() == spawn_chunk.xz()));
+    }
+}
+
+/// Camera looking down, a little ahead so that the ray does not run along the block edges
+fn looking_down() -> Transform {
+    Transform::default().looking_to(Vec3::new(0.1, -1.0, 0.2), Vec3::Y)
+}
+
+/// Blocks of `before` which are gone from `after`, in the chunks both have
+fn removed_blocks(before: &ServerChunkWorldMap, after: &ServerChunkWorldMap) -> Vec<IVec3> {
+    let mut removed = Vec::new();
+    for (chunk_pos, chunk) in before.map.iter() {
+        if !after.map.contains_key(chunk_pos) {
+            continue;
+        }
+        for local_pos in chunk.map.keys() {
+            let pos = *chunk_pos * CHUNK_SIZE + *local_pos;
+            if after.get_block_by_coordinates(&pos).is_none() {
+                removed.push(pos);
+            }
+        }
+    }
+    removed.sort_by_key(|pos| pos.to_array());
+    removed
+}
+
+#[test]
+fn broken_blocks_reach_the_other_clients() {
+    let mut server = TestServer::start("block-edit");
+    let mut clients = vec![
+        TestClient::connect(&server, 1, "Miner"),
+        TestClient::connect(&server, 2, "Watcher"),
+    ];
+    run_until(&mut server, &mut clients, TestClient::has_spawn_area);
+
+    // The miner falls from the spawn height onto the ground, then digs the block under its feet
+    clients[0].script(80, ScriptedInput::idle());
+    run_until(&mut server, &mut clients, |client| client.script_done());
+    let before = clients[1].chunks.clone();
+
+    let dig = ScriptedInput::new(
+        &[NetworkAction::LeftClick],
+        looking_down(),
+        ViewMode::ThirdPerson,
+    );
+    // Breaking a block takes 30 ticks or more
+    clients[0].script(80, dig);
+    run_until(&mut server, &mut clients, |client| {
+        client.script_done() && !removed_blocks(&before, &client.chunks).is_empty()
+    });
+    settle(&mut server, &mut clients);
+
+    let removed = removed_blocks(&before, &clients[1].chunks);
+    let miner = server.world_map().players[&1].position;
+    let world_map = server.world_map();
+    for pos in removed.iter() {
+        assert!(world_map.chunks.get_block_by_coordinates(pos).is_none());
+        assert!(
+            pos.as_vec3().distance(miner) < 8.0,
+            "{pos} broken far from {miner}"
+        );
+    }
+    // Both players see the same world
+    assert_eq!(removed, removed_blocks(&before, &clients[0].chunks));
+}
+
+#[test]
+fn predicted_movement_matches_the_server_and_is_reconciled() {
+    let mut server = TestServer::start("movement");
+    let mut clients = vec![TestClient::connect(&server, 1, "Walker")];
+    run_until(&mut server, &mut clients, TestClient::has_spawn_area);
+    let spawn = clients[0].player.position;
+
+    // Falls onto the ground, then walks forward
+    let walk = ScriptedInput::new(
+        &[NetworkAction::MoveForward],
+        Transform::default(),
+        ViewMode::FirstPerson,
+    );
+    clients[0].script(80, ScriptedInput::idle());
+    clients[0].script(40, walk.clone());
+    run_until(&mut server, &mut clients, |client| client.script_done());
+
+    let walker = &clients[0];
+    let (_, server_position) = walker.server_position.unwrap();
+    assert_eq!(walker.mispredictions, 0);
+    assert_eq!(walker.player.position, server_position);
+    assert!(server_position.y < spawn.y, "did not fall from {spawn}");
+    assert!(
+        server_position.xz().distance(spawn.xz()) > 1.0,
+        "did not walk"
+    );
+
+    // The server moves the player behind the back of the client, which has to catch up
+    server.world_map().players.get_mut(&1).unwrap().position += Vec3::new(2.0, 3.0, 0.0);
+    clients[0].script(40, walk);
+    run_until(&mut server, &mut clients, |client| client.script_done());
+
+    let walker = &clients[0];
+    let (_, server_position) = walker.server_position.unwrap();
+    assert!(walker.mispredictions > 0);
+    assert_eq!(walker.player.position, server_position);
+    assert!(server_position.x > spawn.x + 1.0);
+}
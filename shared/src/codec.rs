@@ -0,0 +1,117 @@
+use std::fmt;
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Zstd level of the wire codec, low levels compress chunks almost as well for a fraction of the time
+const ZSTD_LEVEL: i32 = 3;
+
+/// How a message is turned into bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Serialization {
+    Bincode,
+    Bitcode,
+    Postcard,
+}
+
+/// How the serialized bytes are compressed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Lz4,
+    Zstd,
+}
+
+/// Format of the game messages on the wire, chunks make up most of the bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WireCodec {
+    pub serialization: Serialization,
+    pub compression: Compression,
+}
+
+/// Codec of the messages between clients and servers, both must be built with the same one.
+/// Compare the candidates with `cargo bench -p server --bench chunk_codec` before changing it
+pub const WIRE_CODEC: WireCodec = WireCodec {
+    serialization: Serialization::Bincode,
+    compression: Compression::Lz4,
+};
+
+#[derive(Debug)]
+pub struct CodecError(pub String);
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+fn codec_error(e: impl fmt::Display) -> CodecError {
+    CodecError(e.to_string())
+}
+
+impl WireCodec {
+    /// Every combination of serialization and compression, for the benchmarks
+    pub fn candidates() -> Vec<WireCodec> {
+        let mut codecs = Vec::new();
+        for serialization in [
+            Serialization::Bincode,
+            Serialization::Bitcode,
+            Serialization::Postcard,
+        ] {
+            for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+                codecs.push(WireCodec {
+                    serialization,
+                    compression,
+                });
+            }
+        }
+        codecs
+    }
+
+    /// Such as `bincode+lz4`
+    pub fn name(&self) -> String {
+        format!("{:?}+{:?}", self.serialization, self.compression).to_lowercase()
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        match self.serialization {
+            Serialization::Bincode => bincode::options().serialize(value).map_err(codec_error),
+            Serialization::Bitcode => bitcode::serialize(value).map_err(codec_error),
+            Serialization::Postcard => postcard::to_allocvec(value).map_err(codec_error),
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        match self.serialization {
+            Serialization::Bincode => bincode::options().deserialize(bytes).map_err(codec_error),
+            Serialization::Bitcode => bitcode::deserialize(bytes).map_err(codec_error),
+            Serialization::Postcard => postcard::from_bytes(bytes).map_err(codec_error),
+        }
+    }
+
+    pub fn compress(&self, bytes: &[u8]) -> Result<Vec<u8>, CodecError> {
+        match self.compression {
+            Compression::None => Ok(bytes.to_vec()),
+            Compression::Lz4 => lz4::block::compress(bytes, None, true).map_err(codec_error),
+            Compression::Zstd => zstd::bulk::compress(bytes, ZSTD_LEVEL).map_err(codec_error),
+        }
+    }
+
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, CodecError> {
+        match self.compression {
+            Compression::None => Ok(bytes.to_vec()),
+            Compression::Lz4 => lz4::block::decompress(bytes, None).map_err(codec_error),
+            Compression::Zstd => zstd::stream::decode_all(bytes).map_err(codec_error),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        self.compress(&self.serialize(value)?)
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CodecError> {
+        self.deserialize(&self.decompress(bytes)?)
+    }
+}
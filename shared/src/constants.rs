@@ -2,7 +2,10 @@ use bevy::prelude::*;
 
 pub const PROTOCOL_ID: u64 = 0;
 pub const TICKS_PER_SECOND: u64 = 20;
+pub const DAY_DURATION_IN_TICKS: u64 = 20 * 60; // 20 ticks per second * 60 seconds = 1 minute
 pub const CHUNK_SIZE: i32 = 16;
+/// Players appear there when they respawn, compasses point to it
+pub const WORLD_SPAWN_POSITION: Vec3 = Vec3::new(0.0, 80.0, 0.0);
 pub const MAX_INVENTORY_SLOTS: u32 = 4 * 9;
 pub const HALF_BLOCK: Vec3 = Vec3 {
     x: 0.5,
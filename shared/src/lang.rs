@@ -0,0 +1,66 @@
+use std::fmt::Debug;
+
+use crate::{
+    players::StatusEffectKind,
+    world::{BlockId, ItemId, MobKind},
+};
+
+/// Opens a translatable part of a server message, see `translatable`
+pub const TRANSLATABLE_START: char = '{';
+/// Closes a translatable part of a server message
+pub const TRANSLATABLE_END: char = '}';
+/// Separates the key of a translatable part from its arguments
+pub const TRANSLATABLE_SEPARATOR: char = '|';
+
+/// Key of a registry entry in the localization tables, like `item.oak_planks` for `ItemId::OakPlanks`
+fn registry_key(category: &str, id: &impl Debug) -> String {
+    let mut key = format!("{category}.");
+    for (i, c) in format!("{id:?}").chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            key.push('_');
+        }
+        key.push(c.to_ascii_lowercase());
+    }
+    key
+}
+
+impl BlockId {
+    pub fn translation_key(&self) -> String {
+        registry_key("block", self)
+    }
+}
+
+impl ItemId {
+    pub fn translation_key(&self) -> String {
+        registry_key("item", self)
+    }
+}
+
+impl MobKind {
+    pub fn translation_key(&self) -> String {
+        registry_key("entity", self)
+    }
+}
+
+impl StatusEffectKind {
+    pub fn translation_key(&self) -> String {
+        format!("effect.{}", self.name())
+    }
+}
+
+/// Part of a server message translated by each client, as `{key|arg|...}`.
+/// Arguments that are translation keys themselves are translated too, the others are kept as is
+pub fn translatable(key: &str, args: &[&str]) -> String {
+    let mut text = format!("{TRANSLATABLE_START}{key}");
+    for arg in args {
+        // The markers would end the part early, names holding them lose them
+        let arg: String = arg
+            .chars()
+            .filter(|c| ![TRANSLATABLE_END, TRANSLATABLE_SEPARATOR].contains(c))
+            .collect();
+        text.push(TRANSLATABLE_SEPARATOR);
+        text.push_str(&arg);
+    }
+    text.push(TRANSLATABLE_END);
+    text
+}
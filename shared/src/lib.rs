@@ -3,10 +3,14 @@ use std::{path::PathBuf, time::Duration};
 use bevy_ecs::resource::Resource;
 use bevy_log::debug;
 use bevy_renet::renet::{ChannelConfig, ConnectionConfig, SendType};
-use bincode::Options;
+use codec::{CodecError, WIRE_CODEC};
 
+pub mod codec;
 pub mod constants;
+pub mod lang;
+pub mod logging;
 pub mod messages;
+pub mod network;
 pub mod players;
 pub mod utils;
 pub mod world;
@@ -30,6 +34,14 @@ pub struct SpecialFlag {
 pub struct GameServerConfig {
     pub world_name: String,
     pub is_solo: bool,
+    /// Seed to use if the world does not exist yet, a random one is picked otherwise
+    pub world_seed: Option<u32>,
+    /// Height limits to use if the world does not exist yet, the defaults otherwise
+    pub world_gen_settings: Option<world::WorldGenSettings>,
+    /// Build a spawn platform with a starter chest if the world does not exist yet
+    pub spawn_platform: bool,
+    /// Generates the chunks within this radius of spawn, in chunks, when the server starts
+    pub pregen_radius: Option<u32>,
 }
 
 const MAX_MEMORY: usize = 128 * 1024 * 1024;
@@ -61,6 +73,7 @@ pub fn get_customized_client_to_server_channels() -> Vec<ChannelConfig> {
 pub const STC_STANDARD_CHANNEL: u8 = 0;
 pub const STC_CHUNK_DATA_CHANNEL: u8 = 1;
 pub const STC_AUTH_CHANNEL: u8 = 2;
+pub const STC_PACK_CHANNEL: u8 = 3;
 
 pub fn get_customized_server_to_client_channels() -> Vec<ChannelConfig> {
     vec![
@@ -85,6 +98,13 @@ pub fn get_customized_server_to_client_channels() -> Vec<ChannelConfig> {
                 resend_time: RESEND_TIME,
             },
         },
+        ChannelConfig {
+            channel_id: STC_PACK_CHANNEL,
+            max_memory_usage_bytes: MAX_MEMORY,
+            send_type: SendType::ReliableOrdered {
+                resend_time: RESEND_TIME,
+            },
+        },
     ]
 }
 
@@ -97,8 +117,8 @@ pub fn get_shared_renet_config() -> ConnectionConfig {
 }
 
 pub fn game_message_to_payload<T: serde::Serialize>(message: T) -> Vec<u8> {
-    let payload = bincode::options().serialize(&message).unwrap();
-    let output = lz4::block::compress(&payload, None, true).unwrap();
+    let payload = WIRE_CODEC.serialize(&message).unwrap();
+    let output = WIRE_CODEC.compress(&payload).unwrap();
     if payload.len() > 1024 {
         debug!(
             "Original payload size: {}",
@@ -114,9 +134,8 @@ pub fn game_message_to_payload<T: serde::Serialize>(message: T) -> Vec<u8> {
 
 pub fn payload_to_game_message<T: serde::de::DeserializeOwned>(
     payload: &[u8],
-) -> Result<T, bincode::Error> {
-    let decompressed_payload = lz4::block::decompress(payload, None)?;
-    bincode::options().deserialize(&decompressed_payload)
+) -> Result<T, CodecError> {
+    WIRE_CODEC.decode(payload)
 }
 
 pub fn get_game_folder_paths(
@@ -169,6 +188,7 @@ impl ChannelResolvableExt for ServerToClientMessage {
         match self {
             ServerToClientMessage::WorldUpdate(_) => STC_CHUNK_DATA_CHANNEL,
             ServerToClientMessage::AuthRegisterResponse(_) => STC_AUTH_CHANNEL,
+            ServerToClientMessage::PackChunk(_) => STC_PACK_CHANNEL,
             _ => STC_STANDARD_CHANNEL,
         }
     }
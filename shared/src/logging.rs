@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use bevy::app::App;
+use bevy_log::{BoxedLayer, Level, LogPlugin, DEFAULT_FILTER};
+use serde::{Deserialize, Serialize};
+
+#[doc(hidden)]
+pub use bevy_log::warn as __warn;
+
+/// Minimum time between two messages of a throttled log call
+pub const LOG_THROTTLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Log levels of the game, read from the client and server settings.
+/// `RUST_LOG` still takes precedence when it is set.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LogSettings {
+    /// Level of the modules without a filter of their own: error, warn, info, debug or trace
+    pub level: String,
+    /// Level per module path, e.g. `"shared::world": "warn"`
+    pub filters: BTreeMap<String, String>,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".into(),
+            filters: BTreeMap::new(),
+        }
+    }
+}
+
+impl LogSettings {
+    pub fn level(&self) -> Level {
+        Level::from_str(&self.level).unwrap_or_else(|_| {
+            // The logger is not ready yet
+            eprintln!("Unknown log level {:?}, using info", self.level);
+            Level::INFO
+        })
+    }
+
+    /// Directives added to the level, in the `RUST_LOG` syntax
+    pub fn filter(&self) -> String {
+        let mut filter = DEFAULT_FILTER.to_string();
+        for (module, level) in self.filters.iter() {
+            filter.push_str(&format!(",{}={}", module, level));
+        }
+        filter
+    }
+
+    pub fn log_plugin(&self, custom_layer: fn(&mut App) -> Option<BoxedLayer>) -> LogPlugin {
+        LogPlugin {
+            filter: self.filter(),
+            level: self.level(),
+            custom_layer,
+        }
+    }
+}
+
+fn uptime_ms() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+/// State of one throttled log call site, see [`warn_throttled`](crate::warn_throttled)
+pub struct LogThrottle {
+    /// Uptime of the last message plus one, 0 if none was logged yet
+    last_logged_ms: AtomicU64,
+    suppressed: AtomicU32,
+}
+
+impl Default for LogThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogThrottle {
+    pub const fn new() -> Self {
+        Self {
+            last_logged_ms: AtomicU64::new(0),
+            suppressed: AtomicU32::new(0),
+        }
+    }
+
+    /// Returns the number of messages dropped since the last one if this one should be logged
+    pub fn allow(&self) -> Option<u32> {
+        let now = uptime_ms() + 1;
+        let last = self.last_logged_ms.load(Ordering::Relaxed);
+        if last != 0 && now - last < LOG_THROTTLE_INTERVAL.as_millis() as u64 {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.last_logged_ms.store(now, Ordering::Relaxed);
+        Some(self.suppressed.swap(0, Ordering::Relaxed))
+    }
+}
+
+/// `warn!` for messages which can repeat every frame: at most one message every
+/// [`LOG_THROTTLE_INTERVAL`] is logged per call site, with the count of the dropped ones
+#[macro_export]
+macro_rules! warn_throttled {
+    ($($arg:tt)+) => {{
+        static THROTTLE: $crate::logging::LogThrottle = $crate::logging::LogThrottle::new();
+        if let Some(suppressed) = THROTTLE.allow() {
+            if suppressed > 0 {
+                $crate::logging::__warn!(
+                    "{} ({} similar messages suppressed)",
+                    format_args!($($arg)+),
+                    suppressed
+                );
+            } else {
+                $crate::logging::__warn!($($arg)+);
+            }
+        }
+    }};
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use super::{ClientToServerMessage, PlayerSpawnEvent, ServerToClientMessage};
+use crate::world::WorldGenSettings;
+
+use super::{packs::PackInfo, ClientToServerMessage, PlayerSpawnEvent, ServerToClientMessage};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct AuthRegisterRequest {
@@ -17,9 +19,18 @@ impl From<AuthRegisterRequest> for ClientToServerMessage {
 pub struct AuthRegisterResponse {
     pub username: String,
     pub session_token: u64,
+    /// Time of day on the server, the client keeps it going to draw the sky
     pub tick: u64,
     pub timestamp_ms: u64,
     pub players: Vec<PlayerSpawnEvent>, // all players (including the new one)
+    /// Chunks of the spawn area, the 3x3 columns around the player that the server sends first.
+    /// The client shows its loading progress until it has them, the farther chunks are streamed afterwards
+    pub spawn_chunks: u32,
+    pub height_limits: WorldGenSettings,
+    /// Packs attached to the world, the player accepts or declines them before joining
+    pub packs: Vec<PackInfo>,
+    /// Identifies the world played on the server, the client keeps its cached chunks apart for each one
+    pub world_id: String,
 }
 
 impl From<AuthRegisterResponse> for ServerToClientMessage {
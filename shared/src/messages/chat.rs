@@ -1,6 +1,8 @@
 use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
 
+use super::PlayerId;
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ChatMessageRequest {
     pub content: String,
@@ -11,9 +13,32 @@ pub struct FullChatMessage {
     pub content: String,
     pub author: String,
     pub timestamp: u64,
+    /// Players the message is shown to, everyone when empty
+    #[serde(default)]
+    pub recipients: Vec<PlayerId>,
+}
+
+impl FullChatMessage {
+    pub fn is_visible_to(&self, player_id: PlayerId) -> bool {
+        self.recipients.is_empty() || self.recipients.contains(&player_id)
+    }
 }
 
 #[derive(Resource, Default, Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ChatConversation {
     pub messages: Vec<FullChatMessage>,
 }
+
+impl ChatConversation {
+    /// The conversation as a player sees it, without the messages meant for others
+    pub fn visible_to(&self, player_id: PlayerId) -> ChatConversation {
+        ChatConversation {
+            messages: self
+                .messages
+                .iter()
+                .filter(|message| message.is_visible_to(player_id))
+                .cloned()
+                .collect(),
+        }
+    }
+}
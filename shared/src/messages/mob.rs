@@ -1,10 +1,46 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::world::{MobId, ServerMob};
+use crate::world::{MobId, ServerMob, TradeOffer};
 
 #[derive(Event, Serialize, Deserialize, Debug, Clone)]
 pub struct MobUpdateEvent {
     pub id: MobId,
     pub mob: ServerMob,
 }
+
+/// Sent when a mob dies so that clients remove its model
+#[derive(Event, Serialize, Deserialize, Debug, Clone)]
+pub struct MobDespawnEvent {
+    pub id: MobId,
+}
+
+/// Sent to a player whose attack landed on a mob, with what it took off and what the mob has left
+#[derive(Event, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct MobHitEvent {
+    pub id: MobId,
+    pub damage: f32,
+    pub health: f32,
+}
+
+/// Sent when a player uses the item in their hand on a mob
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct MobInteractRequest {
+    pub mob_id: MobId,
+    pub hotbar_slot: u32,
+}
+
+/// Sent to a player interacting with a trader, and again after each of their trades
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TradeOffersEvent {
+    pub mob_id: MobId,
+    pub offers: Vec<TradeOffer>,
+}
+
+/// Sent when a player picks a trade in the trading window, the server checks it can be made
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TradeRequest {
+    pub mob_id: MobId,
+    /// Index of the trade in the offers of the mob
+    pub offer: usize,
+}
@@ -1,16 +1,30 @@
 mod auth;
 mod chat;
 pub mod mob;
+pub mod packs;
 pub mod player;
+pub mod projectile;
+mod time;
 mod world;
 
 pub use auth::*;
 pub use chat::*;
-use mob::MobUpdateEvent;
+use mob::{
+    MobDespawnEvent, MobHitEvent, MobInteractRequest, MobUpdateEvent, TradeOffersEvent,
+    TradeRequest,
+};
+use packs::{PackChunk, PackDownloadRequest};
 pub use player::*;
+use projectile::{ProjectileDespawnEvent, ProjectileSpawnEvent};
 use serde::{Deserialize, Serialize};
+pub use time::*;
 pub use world::*;
 
+use crate::{
+    players::{GameMode, Teams},
+    world::{MobId, Weather},
+};
+
 pub type PlayerId = u64;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -20,6 +34,20 @@ pub enum ClientToServerMessage {
     Exit,
     PlayerInputs(Vec<PlayerFrameInput>),
     SaveWorldRequest,
+    Emote(Option<Emote>),
+    AttackMob(MobId),
+    InteractMob(MobInteractRequest),
+    TimeSync(TimeSyncRequest),
+    Trade(TradeRequest),
+    /// Asks for a hint the client noticed the player needs, the server only sends it if it was never seen
+    Hint(Hint),
+    /// Follows another player while in spectator mode, `None` stops following them
+    Spectate(Option<PlayerId>),
+    /// Downloads the packs the player accepted and does not have yet
+    PackRequest(Vec<PackDownloadRequest>),
+    /// Chunks the client has in its cache, the server only sends those which were updated since.
+    /// Always sent after authentication, even when nothing is cached
+    CachedChunks(CachedChunksBatch),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -27,7 +55,33 @@ pub enum ServerToClientMessage {
     AuthRegisterResponse(AuthRegisterResponse),
     ChatConversation(ChatConversation),
     WorldUpdate(WorldUpdate),
-    PlayerSpawn(PlayerSpawnEvent),
+    /// Sent to the players already there when a new one joins, the newcomer gets everyone in its auth response
+    PlayerJoined(PlayerSpawnEvent),
+    PlayerLeft(PlayerLeftEvent),
+    PlayerAfk(PlayerAfkEvent),
+    TimeSync(TimeSyncResponse),
     MobUpdate(MobUpdateEvent),
     PlayerUpdate(PlayerUpdateEvent),
+    TeamsUpdate(Teams),
+    GameRules(GameRulesUpdate),
+    /// Sent after authentication and whenever the weather changes, clients only render it
+    Weather(Weather),
+    BlockEditResult(BlockEditResult),
+    BlockBreakingProgress(BlockBreakingProgress),
+    Emote(EmoteEvent),
+    ItemUse(ItemUseEvent),
+    PlayerDeath(PlayerDeathEvent),
+    MobDespawn(MobDespawnEvent),
+    /// Confirms to the attacker that its attack landed
+    MobHit(MobHitEvent),
+    ProjectileSpawn(ProjectileSpawnEvent),
+    ProjectileDespawn(ProjectileDespawnEvent),
+    RecipeUnlock(RecipeUnlockEvent),
+    TradeOffers(TradeOffersEvent),
+    Hint(Hint),
+    /// Sent after authentication and whenever the game mode of the player changes
+    GameMode(GameMode),
+    TickRate(TickRateUpdate),
+    /// Streamed on its own channel while the client joins
+    PackChunk(PackChunk),
 }
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// Packs bigger than this are refused by the server and the clients
+pub const MAX_PACK_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackKind {
+    /// Textures replacing the ones of the client, in `blocks/` and `items/` folders
+    Resource,
+    /// Content read by the server, like the loot tables of `loot_tables.ron`
+    Data,
+}
+
+/// A pack attached to the world, announced to the players after they authenticate
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PackInfo {
+    pub name: String,
+    pub kind: PackKind,
+    /// Size of the archive in bytes
+    pub size: u64,
+    /// Hash of the archive, see `pack_hash`
+    pub hash: String,
+    /// Players declining it cannot play on the server
+    pub required: bool,
+}
+
+/// Packs are sent in parts of this size, a few of them per tick
+pub const PACK_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Asks for a pack from `offset`, to resume a download stopped midway
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PackDownloadRequest {
+    pub hash: String,
+    pub offset: u64,
+}
+
+/// Part of a pack asked for by a client
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackChunk {
+    pub hash: String,
+    pub offset: u64,
+    pub data: Vec<u8>,
+}
+
+/// Hash identifying the content of a pack, as hexadecimal
+pub fn pack_hash(data: &[u8]) -> String {
+    blake3::hash(data).to_hex().to_string()
+}
+
+/// Whether `hash` looks like one made by `pack_hash`: 64 lowercase hexadecimal characters.
+/// The client names files after the hashes sent by the server, so any other text could point outside of its cache
+pub fn is_valid_pack_hash(hash: &str) -> bool {
+    hash.len() == 2 * blake3::OUT_LEN
+        && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_hashes_are_valid() {
+        assert!(is_valid_pack_hash(&pack_hash(b"pack")));
+    }
+
+    #[test]
+    fn paths_and_malformed_hashes_are_rejected() {
+        let hash = pack_hash(b"pack");
+        assert!(!is_valid_pack_hash(""));
+        assert!(!is_valid_pack_hash("../../.."));
+        assert!(!is_valid_pack_hash(&format!("../{}", &hash[3..])));
+        assert!(!is_valid_pack_hash(&hash.to_uppercase()));
+        assert!(!is_valid_pack_hash(&hash[1..]));
+        assert!(!is_valid_pack_hash(&format!("{hash}0")));
+    }
+}
@@ -1,9 +1,11 @@
 use bevy::prelude::*;
 use bevy_platform::collections::HashSet;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 use super::PlayerId;
-use crate::players::{Inventory, ViewMode};
+use crate::players::{DamageSource, GameMode, Inventory, StatusEffects, ViewMode};
+use crate::world::{ItemId, RecipeId};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Eq, Hash)]
 pub enum NetworkAction {
@@ -16,6 +18,89 @@ pub enum NetworkAction {
     ToggleFlyMode,
     LeftClick,
     RightClick,
+    /// Sent every frame while the auto-jump option is on, so that the server climbs the same steps
+    AutoJump,
+    /// Puts the targeted block in the hotbar slot of the input, in creative
+    PickBlock,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum Emote {
+    Wave,
+    /// Lasts until the player moves again
+    Sit,
+    Point,
+}
+
+impl Emote {
+    pub const ALL: [Emote; 3] = [Emote::Wave, Emote::Sit, Emote::Point];
+
+    /// How long the animation plays, `None` if it loops until cancelled
+    pub fn duration_secs(&self) -> Option<f32> {
+        match self {
+            Emote::Wave => Some(2.0),
+            Emote::Sit => None,
+            Emote::Point => Some(1.5),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Emote::Wave => "Wave",
+            Emote::Sit => "Sit",
+            Emote::Point => "Point",
+        }
+    }
+}
+
+/// Contextual tip shown once to each player as a toast
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Hash)]
+pub enum Hint {
+    CollectWood,
+    OpenInventory,
+    Crafting,
+    Night,
+}
+
+impl Hint {
+    #[cfg(feature = "client")]
+    pub fn text(&self) -> &'static str {
+        match self {
+            Hint::CollectWood => "Collect wood by holding left click on a tree",
+            Hint::OpenInventory => "Press E to open your inventory",
+            Hint::Crafting => "Put items in the crafting grid of your inventory to craft",
+            Hint::Night => "Monsters come out at night, build a shelter",
+        }
+    }
+
+    /// Whether clients may ask for this hint, the others are only sent on the server's own triggers
+    pub fn is_client_triggered(&self) -> bool {
+        matches!(self, Hint::Crafting)
+    }
+}
+
+/// Sent to the players near the one starting or stopping an emote
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct EmoteEvent {
+    pub player_id: PlayerId,
+    /// `None` when the current emote is cancelled
+    pub emote: Option<Emote>,
+}
+
+/// Sent to the players near the one starting or stopping to charge or consume an item
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ItemUseEvent {
+    pub player_id: PlayerId,
+    /// `None` once the use is over
+    pub item: Option<ItemId>,
+}
+
+/// Broadcast when a player dies, before they respawn
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PlayerDeathEvent {
+    pub victim_id: PlayerId,
+    pub victim_name: String,
+    pub source: DamageSource,
 }
 
 #[derive(Serialize, Deserialize, Default, PartialEq, Debug, Clone)]
@@ -23,6 +108,13 @@ pub struct PlayerSave {
     pub position: Vec3,
     pub camera_transform: Transform,
     pub is_flying: bool,
+    /// Only read from the save file, like the game mode, other players are not told about it
+    #[serde(default)]
+    pub unlocked_recipes: BTreeSet<RecipeId>,
+    #[serde(default)]
+    pub game_mode: GameMode,
+    #[serde(default)]
+    pub seen_hints: BTreeSet<Hint>,
 }
 
 #[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -30,6 +122,29 @@ pub struct PlayerSpawnEvent {
     pub id: PlayerId,
     pub name: String,
     pub data: PlayerSave,
+    pub afk: bool,
+}
+
+/// Sent to a player when recipes are unlocked for them, or taken back by an admin
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct RecipeUnlockEvent {
+    pub player_id: PlayerId,
+    pub recipes: Vec<RecipeId>,
+    pub unlocked: bool,
+}
+
+/// Broadcast when a player goes AFK or comes back
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PlayerAfkEvent {
+    pub player_id: PlayerId,
+    pub afk: bool,
+}
+
+/// Broadcast when a player disconnects, for the others to remove them
+#[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PlayerLeftEvent {
+    pub id: PlayerId,
+    pub name: String,
 }
 
 #[derive(Event, Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -39,6 +154,12 @@ pub struct PlayerUpdateEvent {
     pub orientation: Quat,
     pub last_ack_time: u64,
     pub inventory: Inventory,
+    /// Slot of the item in hand, for the other players to see it
+    pub hotbar_slot: u32,
+    pub health: f32,
+    pub effects: StatusEffects,
+    /// Looping emote of the player, for those who see them after it started
+    pub emote: Option<Emote>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -51,4 +172,6 @@ pub struct PlayerFrameInput {
     pub view_mode: ViewMode,
     #[serde(skip)]
     pub position: Vec3,
+    /// Block the client already changed while predicting this input, the server answers with its own version of it
+    pub predicted_edit: Option<IVec3>,
 }
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::world::{Projectile, ProjectileId};
+
+/// Clients simulate projectiles on their own from their launch state, until told they hit something
+#[derive(Event, Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectileSpawnEvent {
+    pub id: ProjectileId,
+    pub projectile: Projectile,
+}
+
+#[derive(Event, Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectileDespawnEvent {
+    pub id: ProjectileId,
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::TICKS_PER_SECOND;
+
+/// Sent by clients every few seconds to estimate the offset between their clock and the server one
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct TimeSyncRequest {
+    pub client_send_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct TimeSyncResponse {
+    /// Copied from the request, so that the client knows the round trip time
+    pub client_send_ms: u64,
+    pub server_time_ms: u64,
+}
+
+/// How fast the server simulates the world, changed with `/tick` to debug it.
+/// Sent after authentication and whenever it changes, clients slow down what they predict to match
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct TickRateUpdate {
+    /// Nothing is simulated but the steps asked for, players can still move and chat
+    pub frozen: bool,
+    /// Ticks simulated per second while not frozen, at most `TICKS_PER_SECOND`
+    pub rate: u64,
+}
+
+impl Default for TickRateUpdate {
+    fn default() -> Self {
+        Self {
+            frozen: false,
+            rate: TICKS_PER_SECOND,
+        }
+    }
+}
+
+impl TickRateUpdate {
+    /// Speed of the simulation relative to the normal one, 0 while frozen
+    pub fn time_scale(&self) -> f32 {
+        if self.frozen {
+            0.
+        } else {
+            self.rate as f32 / TICKS_PER_SECOND as f32
+        }
+    }
+}
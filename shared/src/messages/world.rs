@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::world::{ItemStack, MobId, ServerChunk, ServerMob};
+use crate::world::{BlockData, GameRules, ItemStack, MobId, ServerChunk, ServerMob};
 use bevy::{
     math::{IVec3, Vec3},
     prelude::Event,
@@ -18,6 +18,45 @@ pub struct WorldUpdate {
     pub item_stacks: Vec<ItemStackUpdateEvent>,
 }
 
+/// A chunk the client has on disk from a previous session, along with the timestamp of the last update it holds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CachedChunk {
+    pub pos: IVec3,
+    pub ts: u64,
+}
+
+/// Part of the list of chunks the client has cached, the server waits for the last one before streaming chunks
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CachedChunksBatch {
+    pub chunks: Vec<CachedChunk>,
+    pub last: bool,
+}
+
+/// Sent after authentication and whenever a game rule changes, along with the time of day as
+/// the client advances it on its own unless the daylight cycle is stopped
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameRulesUpdate {
+    pub rules: GameRules,
+    pub day_time: u64,
+}
+
+/// Answer to an input whose block edit the client predicted, with the block the server has there once
+/// the input is processed. It differs from the prediction when the server refused the edit
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockEditResult {
+    pub input_time_ms: u64,
+    pub position: IVec3,
+    pub block: Option<BlockData>,
+}
+
+/// Breaking progress of a block a player is hitting, sent to the players around so that they all see it crack.
+/// A progress of 0 means the block is not damaged
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Event)]
+pub struct BlockBreakingProgress {
+    pub position: IVec3,
+    pub progress: u8,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone, Event)]
 pub struct ItemStackUpdateEvent {
     pub id: u128,
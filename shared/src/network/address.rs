@@ -0,0 +1,65 @@
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// Port of the game when the address typed by the player has none
+pub const DEFAULT_GAME_PORT: u16 = 8000;
+
+/// Reads a server address as typed by the player: `1.2.3.4`, `::1`, `[::1]:8000`, `example.com:8000`...
+/// Host names are resolved, the addresses come back with IPv6 and IPv4 ones alternating,
+/// so that the client can fall back to the other family when the first address is unreachable
+pub fn resolve_server_address(text: &str) -> Result<Vec<SocketAddr>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("The server address is empty".into());
+    }
+
+    if let Ok(addr) = text.parse::<SocketAddr>() {
+        return Ok(vec![addr]);
+    }
+    // IP without a port, IPv6 ones may be written with or without brackets
+    let bare = text.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, DEFAULT_GAME_PORT)]);
+    }
+
+    let (host, port) = match text.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| format!("{:?} is not a valid port number", port))?;
+            (host, port)
+        }
+        None => (text, DEFAULT_GAME_PORT),
+    };
+    if host.is_empty() || host.contains(':') {
+        return Err(format!("{:?} is not a valid server address", text));
+    }
+
+    let resolved: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .collect();
+    if resolved.is_empty() {
+        return Err(format!("{} has no IPv4 or IPv6 address", host));
+    }
+
+    let (v6, v4): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        resolved.into_iter().partition(SocketAddr::is_ipv6);
+    let mut v6 = v6.into_iter();
+    let mut v4 = v4.into_iter();
+    let mut addresses = Vec::new();
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => addresses.extend(a.into_iter().chain(b)),
+        }
+    }
+    Ok(addresses)
+}
+
+/// Socket for talking to `server_addr`, bound to an unspecified address of the same family
+pub fn bind_client_socket(server_addr: SocketAddr) -> std::io::Result<UdpSocket> {
+    match server_addr {
+        SocketAddr::V4(_) => UdpSocket::bind("0.0.0.0:0"),
+        SocketAddr::V6(_) => UdpSocket::bind("[::]:0"),
+    }
+}
@@ -0,0 +1,33 @@
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+/// Port the servers broadcast their beacons to, and the clients listen on
+pub const LAN_DISCOVERY_PORT: u16 = 4446;
+/// Beacons start with it, so that other programs broadcasting on the port are ignored
+const LAN_BEACON_MAGIC: &[u8; 4] = b"RCLB";
+
+/// Broadcast regularly by the servers opened to the local network
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LanBeacon {
+    pub protocol_id: u64,
+    pub name: String,
+    /// Game port, the address is the one the beacon came from
+    pub port: u16,
+    pub players: u32,
+    pub max_players: u32,
+}
+
+impl LanBeacon {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = LAN_BEACON_MAGIC.to_vec();
+        bytes.extend(bincode::options().serialize(self).unwrap_or_default());
+        bytes
+    }
+
+    /// `None` for packets which are not beacons, or beacons of servers running another version
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let payload = bytes.strip_prefix(LAN_BEACON_MAGIC)?;
+        let beacon: Self = bincode::options().deserialize(payload).ok()?;
+        (beacon.protocol_id == crate::PROTOCOL_ID).then_some(beacon)
+    }
+}
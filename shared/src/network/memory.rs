@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bevy_log::warn;
+use bevy_renet::renet::{ClientId, RenetClient, RenetServer};
+
+use super::{ClientTransport, ServerTransport};
+
+type Packet = Vec<u8>;
+
+/// Both directions of a connection, the receivers are behind a mutex so that transports can be resources
+struct MemoryChannels {
+    sender: Sender<Packet>,
+    receiver: Mutex<Receiver<Packet>>,
+}
+
+impl MemoryChannels {
+    fn pair() -> (Self, Self) {
+        let (to_server, from_client) = channel();
+        let (to_client, from_server) = channel();
+        (
+            Self {
+                sender: to_server,
+                receiver: Mutex::new(from_server),
+            },
+            Self {
+                sender: to_client,
+                receiver: Mutex::new(from_client),
+            },
+        )
+    }
+
+    /// Packets which arrived, `Err` once the other end is gone
+    fn drain(&mut self) -> Result<Vec<Packet>, TryRecvError> {
+        let receiver = self.receiver.get_mut().unwrap();
+        let mut packets = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(packet) => packets.push(packet),
+                Err(TryRecvError::Empty) => return Ok(packets),
+                Err(TryRecvError::Disconnected) if packets.is_empty() => {
+                    return Err(TryRecvError::Disconnected)
+                }
+                Err(TryRecvError::Disconnected) => return Ok(packets),
+            }
+        }
+    }
+}
+
+/// Opens connections to a `MemoryServerTransport`, from any thread
+#[derive(Debug, Clone)]
+pub struct MemoryConnector(Sender<(ClientId, MemoryChannels)>);
+
+impl MemoryConnector {
+    /// The server sees the client on its next update
+    pub fn connect(&self, client_id: ClientId) -> MemoryClientTransport {
+        let (client, server) = MemoryChannels::pair();
+        if self.0.send((client_id, server)).is_err() {
+            warn!(
+                "The in-memory server is gone, client {} cannot connect",
+                client_id
+            );
+        }
+        MemoryClientTransport {
+            channels: Some(client),
+        }
+    }
+}
+
+/// Clients running in the same process as the server, like the singleplayer one
+pub struct MemoryServerTransport {
+    incoming: Mutex<Receiver<(ClientId, MemoryChannels)>>,
+    clients: HashMap<ClientId, MemoryChannels>,
+}
+
+impl MemoryServerTransport {
+    pub fn new() -> (Self, MemoryConnector) {
+        let (connector, incoming) = channel();
+        (
+            Self {
+                incoming: Mutex::new(incoming),
+                clients: HashMap::new(),
+            },
+            MemoryConnector(connector),
+        )
+    }
+}
+
+impl ServerTransport for MemoryServerTransport {
+    fn receive(&mut self, _delta: Duration, server: &mut RenetServer) -> Result<(), String> {
+        while let Ok((client_id, channels)) = self.incoming.get_mut().unwrap().try_recv() {
+            self.clients.insert(client_id, channels);
+            server.add_connection(client_id);
+        }
+
+        for client_id in server.disconnections_id() {
+            self.clients.remove(&client_id);
+            server.remove_connection(client_id);
+        }
+
+        let mut gone = Vec::new();
+        for (client_id, channels) in self.clients.iter_mut() {
+            match channels.drain() {
+                Ok(packets) => {
+                    for packet in packets {
+                        let _ = server.process_packet_from(&packet, *client_id);
+                    }
+                }
+                Err(_) => gone.push(*client_id),
+            }
+        }
+        for client_id in gone {
+            self.clients.remove(&client_id);
+            server.remove_connection(client_id);
+        }
+
+        Ok(())
+    }
+
+    fn send(&mut self, server: &mut RenetServer) {
+        for (client_id, channels) in self.clients.iter() {
+            let Ok(packets) = server.get_packets_to_send(*client_id) else {
+                continue;
+            };
+            for packet in packets {
+                // A client who left is removed on the next update
+                let _ = channels.sender.send(packet);
+            }
+        }
+    }
+
+    fn disconnect_all(&mut self, server: &mut RenetServer) {
+        server.disconnect_all();
+        self.send(server);
+        for (client_id, _) in self.clients.drain() {
+            server.remove_connection(client_id);
+        }
+    }
+}
+
+/// Client end of a connection opened with a `MemoryConnector`
+pub struct MemoryClientTransport {
+    /// `None` once disconnected, which the server notices on its next update
+    channels: Option<MemoryChannels>,
+}
+
+impl ClientTransport for MemoryClientTransport {
+    fn receive(&mut self, _delta: Duration, client: &mut RenetClient) -> Result<(), String> {
+        if client.is_disconnected() {
+            self.channels = None;
+        }
+        let Some(channels) = self.channels.as_mut() else {
+            return Ok(());
+        };
+
+        // Nothing to negotiate, the connection is up as soon as it exists
+        client.set_connected();
+        match channels.drain() {
+            Ok(packets) => {
+                for packet in packets {
+                    client.process_packet(&packet);
+                }
+                Ok(())
+            }
+            Err(_) => {
+                self.channels = None;
+                client.disconnect_due_to_transport();
+                Err("the server closed the connection".into())
+            }
+        }
+    }
+
+    fn send(&mut self, client: &mut RenetClient) -> Result<(), String> {
+        let Some(channels) = self.channels.as_ref() else {
+            return Ok(());
+        };
+        for packet in client.get_packets_to_send() {
+            if channels.sender.send(packet).is_err() {
+                return Err("the server closed the connection".into());
+            }
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        self.channels = None;
+    }
+}
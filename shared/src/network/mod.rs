@@ -0,0 +1,167 @@
+pub mod address;
+pub mod lan;
+pub mod memory;
+pub mod tokens;
+pub mod udp;
+
+pub use address::*;
+pub use lan::*;
+pub use memory::*;
+pub use tokens::{request_connect_grant, ConnectGrant, TlsIdentity};
+pub use udp::MultiSocketServerTransport;
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_renet::{
+    renet::{RenetClient, RenetServer},
+    RenetClientPlugin, RenetReceive, RenetSend, RenetServerPlugin,
+};
+
+/// Moves the packets of the client to and from the server, whatever carries them.
+/// Netcode over UDP and in-memory channels are the only backends, there is no web one
+pub trait ClientTransport: Send + Sync + 'static {
+    /// Hands the packets which arrived to the client, before it reads its messages
+    fn receive(&mut self, delta: Duration, client: &mut RenetClient) -> Result<(), String>;
+    /// Sends the packets the client queued during the frame
+    fn send(&mut self, client: &mut RenetClient) -> Result<(), String>;
+    fn disconnect(&mut self);
+}
+
+/// Moves the packets of the server to and from its clients, whatever carries them.
+/// Same backends as `ClientTransport`
+pub trait ServerTransport: Send + Sync + 'static {
+    /// Hands the packets which arrived to the server and tells it about the clients who came or left
+    fn receive(&mut self, delta: Duration, server: &mut RenetServer) -> Result<(), String>;
+    /// Sends the packets the server queued for each client during the frame
+    fn send(&mut self, server: &mut RenetServer);
+    fn disconnect_all(&mut self, server: &mut RenetServer);
+}
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct ActiveClientTransport(pub Box<dyn ClientTransport>);
+
+#[derive(Resource, Deref, DerefMut)]
+pub struct ActiveServerTransport(pub Box<dyn ServerTransport>);
+
+#[derive(Event, Debug)]
+pub struct TransportError(pub String);
+
+/// Runs the transport put in `ActiveClientTransport` around the updates of the `RenetClient`
+pub struct ClientTransportPlugin;
+
+/// Runs the transport put in `ActiveServerTransport` around the updates of the `RenetServer`
+pub struct ServerTransportPlugin;
+
+impl Plugin for ClientTransportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransportError>();
+
+        app.add_systems(
+            PreUpdate,
+            client_receive_system
+                .in_set(RenetReceive)
+                .run_if(resource_exists::<ActiveClientTransport>)
+                .run_if(resource_exists::<RenetClient>)
+                .after(RenetClientPlugin::update_system),
+        );
+        app.add_systems(
+            PostUpdate,
+            client_send_system
+                .in_set(RenetSend)
+                .run_if(resource_exists::<ActiveClientTransport>)
+                .run_if(resource_exists::<RenetClient>),
+        );
+        app.add_systems(
+            Last,
+            client_disconnect_on_exit_system.run_if(resource_exists::<ActiveClientTransport>),
+        );
+    }
+}
+
+impl Plugin for ServerTransportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TransportError>();
+
+        app.add_systems(
+            PreUpdate,
+            server_receive_system
+                .in_set(RenetReceive)
+                .run_if(resource_exists::<ActiveServerTransport>)
+                .run_if(resource_exists::<RenetServer>)
+                .after(RenetServerPlugin::update_system)
+                .before(RenetServerPlugin::emit_server_events_system),
+        );
+        app.add_systems(
+            PostUpdate,
+            server_send_system
+                .in_set(RenetSend)
+                .run_if(resource_exists::<ActiveServerTransport>)
+                .run_if(resource_exists::<RenetServer>),
+        );
+        app.add_systems(
+            Last,
+            server_disconnect_on_exit_system
+                .run_if(resource_exists::<ActiveServerTransport>)
+                .run_if(resource_exists::<RenetServer>),
+        );
+    }
+}
+
+fn client_receive_system(
+    mut transport: ResMut<ActiveClientTransport>,
+    mut client: ResMut<RenetClient>,
+    time: Res<Time>,
+    mut errors: EventWriter<TransportError>,
+) {
+    if let Err(e) = transport.receive(time.delta(), &mut client) {
+        errors.write(TransportError(e));
+    }
+}
+
+fn client_send_system(
+    mut transport: ResMut<ActiveClientTransport>,
+    mut client: ResMut<RenetClient>,
+    mut errors: EventWriter<TransportError>,
+) {
+    if let Err(e) = transport.send(&mut client) {
+        errors.write(TransportError(e));
+    }
+}
+
+fn client_disconnect_on_exit_system(
+    exit: EventReader<AppExit>,
+    mut transport: ResMut<ActiveClientTransport>,
+) {
+    if !exit.is_empty() {
+        transport.disconnect();
+    }
+}
+
+fn server_receive_system(
+    mut transport: ResMut<ActiveServerTransport>,
+    mut server: ResMut<RenetServer>,
+    time: Res<Time>,
+    mut errors: EventWriter<TransportError>,
+) {
+    if let Err(e) = transport.receive(time.delta(), &mut server) {
+        errors.write(TransportError(e));
+    }
+}
+
+fn server_send_system(
+    mut transport: ResMut<ActiveServerTransport>,
+    mut server: ResMut<RenetServer>,
+) {
+    transport.send(&mut server);
+}
+
+fn server_disconnect_on_exit_system(
+    exit: EventReader<AppExit>,
+    mut transport: ResMut<ActiveServerTransport>,
+    mut server: ResMut<RenetServer>,
+) {
+    if !exit.is_empty() {
+        transport.disconnect_all(&mut server);
+    }
+}
@@ -0,0 +1,458 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bevy_renet::netcode::{ClientAuthentication, ConnectToken};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime},
+    server::danger::{ClientCertVerified, ClientCertVerifier},
+    ClientConfig, ClientConnection, DigitallySignedStruct, DistinguishedName, ServerConfig,
+    ServerConnection, SignatureScheme, StreamOwned,
+};
+use serde::{Deserialize, Serialize};
+
+/// TLS settings of a server handing out grants
+pub use rustls::ServerConfig as GrantServerConfig;
+
+// Connect grants, handed out by the servers over TLS on the TCP port of the game.
+// The client sends the name it plays under, and gets back a connect token for the player id
+// bound to its certificate and that name, or word that the server does not encrypt connections
+
+/// Time a client has to get its grant, however slowly either side sends
+pub const CONNECT_GRANT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Name the certificates are made for. Servers are recognized by the fingerprint of their certificate, not by name
+const CERTIFICATE_NAME: &str = "rustcraft";
+/// Longest name, in bytes, a client can ask a grant for
+const MAX_GRANT_NAME_BYTES: usize = 64;
+
+/// Self-signed certificate and its key, which a server or a client is recognized by when exchanging grants
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TlsIdentity {
+    certificate: Vec<u8>,
+    /// PKCS#8 private key of the certificate
+    key: Vec<u8>,
+}
+
+impl TlsIdentity {
+    pub fn generate() -> Result<Self, String> {
+        let key = rcgen::KeyPair::generate().map_err(|e| e.to_string())?;
+        let certificate = rcgen::CertificateParams::new(vec![CERTIFICATE_NAME.to_string()])
+            .and_then(|params| params.self_signed(&key))
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            certificate: certificate.der().to_vec(),
+            key: key.serialize_der(),
+        })
+    }
+
+    pub fn fingerprint(&self) -> String {
+        certificate_fingerprint(&self.certificate)
+    }
+
+    fn certificate_chain(&self) -> Vec<CertificateDer<'static>> {
+        vec![CertificateDer::from(self.certificate.clone())]
+    }
+
+    fn private_key(&self) -> PrivateKeyDer<'static> {
+        PrivatePkcs8KeyDer::from(self.key.clone()).into()
+    }
+}
+
+/// Clients remember servers by this digest of their certificate
+pub fn certificate_fingerprint(certificate: &[u8]) -> String {
+    blake3::hash(certificate).to_hex().to_string()
+}
+
+/// Player id of a client holding `certificate` and playing as `name`, nobody without its key can get a grant for it
+pub fn client_id_of(certificate: &[u8], name: &str) -> u64 {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(certificate);
+    hasher.update(name.as_bytes());
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&hasher.finalize().as_bytes()[..8]);
+    u64::from_le_bytes(id)
+}
+
+/// What a server answers to a client asking to connect
+pub enum ConnectGrant {
+    /// Servers with a private key encrypt the connections
+    Secure(Box<ConnectToken>),
+    /// Servers without a private key say so, rather than leaving clients to guess it from a missing token
+    Unsecure { client_id: u64 },
+}
+
+impl ConnectGrant {
+    pub fn client_id(&self) -> u64 {
+        match self {
+            ConnectGrant::Secure(token) => token.client_id,
+            ConnectGrant::Unsecure { client_id } => *client_id,
+        }
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            ConnectGrant::Secure(token) => {
+                writer.write_all(&[1])?;
+                token.write(writer)
+            }
+            ConnectGrant::Unsecure { client_id } => {
+                writer.write_all(&[0])?;
+                writer.write_all(&client_id.to_le_bytes())
+            }
+        }
+    }
+
+    pub fn read(reader: &mut impl Read) -> io::Result<Self> {
+        let mut kind = [0u8; 1];
+        reader.read_exact(&mut kind)?;
+        match kind[0] {
+            0 => {
+                let mut client_id = [0u8; 8];
+                reader.read_exact(&mut client_id)?;
+                Ok(ConnectGrant::Unsecure {
+                    client_id: u64::from_le_bytes(client_id),
+                })
+            }
+            1 => ConnectToken::read(reader)
+                .map(|token| ConnectGrant::Secure(Box::new(token)))
+                .map_err(|e| io::Error::other(e.to_string())),
+            kind => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown grant kind {kind}"),
+            )),
+        }
+    }
+
+    /// How the client connects to `server_addr` with this grant
+    pub fn into_authentication(self, server_addr: SocketAddr) -> ClientAuthentication {
+        match self {
+            ConnectGrant::Secure(connect_token) => ClientAuthentication::Secure {
+                connect_token: *connect_token,
+            },
+            ConnectGrant::Unsecure { client_id } => ClientAuthentication::Unsecure {
+                server_addr,
+                client_id,
+                user_data: None,
+                protocol_id: crate::PROTOCOL_ID,
+            },
+        }
+    }
+}
+
+/// TCP stream giving up once its deadline has passed. A plain read timeout would let the other
+/// side hold the connection for as long as it likes by sending a byte at a time
+struct DeadlineStream {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl DeadlineStream {
+    fn new(stream: TcpStream, timeout: Duration) -> Self {
+        Self {
+            stream,
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    fn remaining(&self) -> io::Result<Duration> {
+        self.deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "connect grant timed out"))
+    }
+}
+
+impl Read for DeadlineStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(Some(self.remaining()?))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for DeadlineStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.set_write_timeout(Some(self.remaining()?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Asks the server at `server_addr` for a grant to play as `name`. When `pinned` is set, the server must present
+/// the certificate of this fingerprint, the one it had the previous times.
+/// Returns the grant along with the fingerprint of the certificate of the server, for the client to pin it.
+/// Blocks for up to `CONNECT_GRANT_TIMEOUT`
+pub fn request_connect_grant(
+    server_addr: SocketAddr,
+    name: &str,
+    identity: &TlsIdentity,
+    pinned: Option<&str>,
+) -> Result<(ConnectGrant, String), String> {
+    if name.len() > MAX_GRANT_NAME_BYTES {
+        return Err(format!(
+            "the name is longer than {MAX_GRANT_NAME_BYTES} bytes"
+        ));
+    }
+
+    let provider = Arc::new(ring::default_provider());
+    let verifier = Arc::new(PinnedServerCertificate {
+        provider: provider.clone(),
+        pinned: pinned.map(str::to_string),
+        seen: Mutex::new(None),
+    });
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_client_auth_cert(identity.certificate_chain(), identity.private_key())
+        .map_err(|e| e.to_string())?;
+    let server_name = ServerName::try_from(CERTIFICATE_NAME).map_err(|e| e.to_string())?;
+    let connection =
+        ClientConnection::new(Arc::new(config), server_name).map_err(|e| e.to_string())?;
+
+    let stream = TcpStream::connect_timeout(&server_addr, CONNECT_GRANT_TIMEOUT)
+        .map_err(|e| format!("no connect grant service: {e}"))?;
+    let mut stream = StreamOwned::new(
+        connection,
+        DeadlineStream::new(stream, CONNECT_GRANT_TIMEOUT),
+    );
+    stream
+        .write_all(&[name.len() as u8])
+        .and_then(|_| stream.write_all(name.as_bytes()))
+        .and_then(|_| stream.flush())
+        .map_err(|e| e.to_string())?;
+    let grant = ConnectGrant::read(&mut stream).map_err(|e| e.to_string())?;
+
+    let fingerprint = verifier
+        .seen
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("the server presented no certificate")?;
+    Ok((grant, fingerprint))
+}
+
+/// TLS settings of a server handing out grants. Any client certificate is accepted:
+/// it only proves which player ids the client can get grants for
+pub fn grant_server_config(identity: &TlsIdentity) -> Result<Arc<ServerConfig>, String> {
+    let provider = Arc::new(ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| e.to_string())?
+        .with_client_cert_verifier(Arc::new(AnyClientCertificate { provider }))
+        .with_single_cert(identity.certificate_chain(), identity.private_key())
+        .map_err(|e| e.to_string())?;
+    Ok(Arc::new(config))
+}
+
+/// Answers the client which connected on `stream` with the grant `grant` makes for its player id and the name
+/// it asked for, the player id being returned once the grant is sent. Blocks for up to `CONNECT_GRANT_TIMEOUT`
+pub fn answer_grant_request(
+    config: Arc<ServerConfig>,
+    stream: TcpStream,
+    grant: impl FnOnce(u64, &str) -> Result<ConnectGrant, String>,
+) -> Result<u64, String> {
+    let connection = ServerConnection::new(config).map_err(|e| e.to_string())?;
+    let mut stream = StreamOwned::new(
+        connection,
+        DeadlineStream::new(stream, CONNECT_GRANT_TIMEOUT),
+    );
+    let name = read_grant_request(&mut stream).map_err(|e| e.to_string())?;
+    let client_id = match stream.conn.peer_certificates() {
+        Some([certificate, ..]) => client_id_of(certificate, &name),
+        _ => return Err("the client presented no certificate".into()),
+    };
+
+    grant(client_id, &name)?
+        .write(&mut stream)
+        .map_err(|e| e.to_string())?;
+    stream.conn.send_close_notify();
+    stream.flush().map_err(|e| e.to_string())?;
+    Ok(client_id)
+}
+
+/// Reads the name a client asks a grant for
+fn read_grant_request(reader: &mut impl Read) -> io::Result<String> {
+    let mut len = [0u8; 1];
+    reader.read_exact(&mut len)?;
+    let len = len[0] as usize;
+    if len > MAX_GRANT_NAME_BYTES {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "name too long"));
+    }
+    let mut name = vec![0u8; len];
+    reader.read_exact(&mut name)?;
+    String::from_utf8(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[derive(Debug)]
+struct PinnedServerCertificate {
+    provider: Arc<CryptoProvider>,
+    pinned: Option<String>,
+    seen: Mutex<Option<String>>,
+}
+
+impl ServerCertVerifier for PinnedServerCertificate {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let fingerprint = certificate_fingerprint(end_entity);
+        if self
+            .pinned
+            .as_ref()
+            .is_some_and(|pinned| *pinned != fingerprint)
+        {
+            return Err(rustls::Error::General(
+                "the server certificate changed since the last connection, someone may be impersonating it".into(),
+            ));
+        }
+        *self.seen.lock().unwrap() = Some(fingerprint);
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[derive(Debug)]
+struct AnyClientCertificate {
+    provider: Arc<CryptoProvider>,
+}
+
+impl ClientCertVerifier for AnyClientCertificate {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Answers one grant request on a local port, with an unsecure grant for the id of the client
+    fn serve_once(identity: &TlsIdentity) -> (SocketAddr, thread::JoinHandle<Result<u64, String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let config = grant_server_config(identity).unwrap();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+            answer_grant_request(config, stream, |client_id, _| {
+                Ok(ConnectGrant::Unsecure { client_id })
+            })
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn grant_is_bound_to_the_client_certificate() {
+        let server = TlsIdentity::generate().unwrap();
+        let client = TlsIdentity::generate().unwrap();
+        let (addr, handle) = serve_once(&server);
+
+        let (grant, fingerprint) = request_connect_grant(addr, "Alice", &client, None).unwrap();
+
+        assert_eq!(fingerprint, server.fingerprint());
+        assert_eq!(
+            grant.client_id(),
+            client_id_of(&client.certificate, "Alice")
+        );
+        assert_eq!(handle.join().unwrap(), Ok(grant.client_id()));
+    }
+
+    #[test]
+    fn changed_server_certificate_is_refused() {
+        let server = TlsIdentity::generate().unwrap();
+        let client = TlsIdentity::generate().unwrap();
+        let (addr, handle) = serve_once(&server);
+
+        let pinned = TlsIdentity::generate().unwrap().fingerprint();
+        assert!(request_connect_grant(addr, "Alice", &client, Some(&pinned)).is_err());
+        assert!(handle.join().unwrap().is_err());
+    }
+}
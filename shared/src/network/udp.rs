@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use bevy_log::error;
+use bevy_renet::{
+    netcode::{NetcodeClientTransport, NetcodeServerTransport, ServerConfig},
+    renet::{RenetClient, RenetServer},
+};
+use renetcode::{NetcodeServer, ServerResult, NETCODE_MAX_PACKET_BYTES};
+
+use super::{ClientTransport, ServerTransport};
+
+// Netcode over UDP, used to reach servers over the network
+
+/// Addresses which are not connected clients kept in the routes of `MultiSocketServerTransport`,
+/// mostly clients in the middle of the handshake
+const NETCODE_PENDING_ROUTES: usize = 256;
+
+impl ClientTransport for NetcodeClientTransport {
+    fn receive(&mut self, delta: Duration, client: &mut RenetClient) -> Result<(), String> {
+        self.update(delta, client).map_err(|e| e.to_string())
+    }
+
+    fn send(&mut self, client: &mut RenetClient) -> Result<(), String> {
+        self.send_packets(client).map_err(|e| e.to_string())
+    }
+
+    fn disconnect(&mut self) {
+        NetcodeClientTransport::disconnect(self);
+    }
+}
+
+impl ServerTransport for NetcodeServerTransport {
+    fn receive(&mut self, delta: Duration, server: &mut RenetServer) -> Result<(), String> {
+        self.update(delta, server).map_err(|e| e.to_string())
+    }
+
+    fn send(&mut self, server: &mut RenetServer) {
+        self.send_packets(server);
+    }
+
+    fn disconnect_all(&mut self, server: &mut RenetServer) {
+        NetcodeServerTransport::disconnect_all(self, server);
+    }
+}
+
+/// Netcode over several sockets, for servers bound to more than one address.
+/// `NetcodeServerTransport` owns a single socket, and two of them cannot share a `RenetServer`:
+/// each one sends the packets of every client, including those of the other
+pub struct MultiSocketServerTransport {
+    sockets: Vec<UdpSocket>,
+    netcode_server: NetcodeServer,
+    /// Socket each client address last sent from, the answers leave through it
+    routes: HashMap<SocketAddr, usize>,
+    buffer: [u8; NETCODE_MAX_PACKET_BYTES],
+}
+
+impl MultiSocketServerTransport {
+    pub fn new(server_config: ServerConfig, sockets: Vec<UdpSocket>) -> io::Result<Self> {
+        for socket in &sockets {
+            socket.set_nonblocking(true)?;
+        }
+        Ok(Self {
+            sockets,
+            netcode_server: NetcodeServer::new(server_config),
+            routes: HashMap::new(),
+            buffer: [0; NETCODE_MAX_PACKET_BYTES],
+        })
+    }
+}
+
+/// Applies the outcome of netcode to the server, answering through the socket the address came from
+fn handle_server_result(
+    server_result: ServerResult,
+    sockets: &[UdpSocket],
+    routes: &mut HashMap<SocketAddr, usize>,
+    server: &mut RenetServer,
+) {
+    let send_packet = |packet: &[u8], addr: SocketAddr| {
+        let Some(socket) = routes.get(&addr).and_then(|index| sockets.get(*index)) else {
+            return;
+        };
+        if let Err(e) = socket.send_to(packet, addr) {
+            error!("Failed to send packet to {}: {}", addr, e);
+        }
+    };
+
+    match server_result {
+        ServerResult::None => {}
+        ServerResult::PacketToSend { addr, payload } => send_packet(payload, addr),
+        ServerResult::Payload { client_id, payload } => {
+            if let Err(e) = server.process_packet_from(payload, client_id) {
+                error!("Error while processing payload for {}: {}", client_id, e);
+            }
+        }
+        ServerResult::ClientConnected {
+            client_id,
+            addr,
+            payload,
+            ..
+        } => {
+            server.add_connection(client_id);
+            send_packet(payload, addr);
+        }
+        ServerResult::ClientDisconnected {
+            client_id,
+            addr,
+            payload,
+        } => {
+            server.remove_connection(client_id);
+            if let Some(payload) = payload {
+                send_packet(payload, addr);
+            }
+            routes.remove(&addr);
+        }
+    }
+}
+
+impl ServerTransport for MultiSocketServerTransport {
+    fn receive(&mut self, delta: Duration, server: &mut RenetServer) -> Result<(), String> {
+        self.netcode_server.update(delta);
+
+        for (index, socket) in self.sockets.iter().enumerate() {
+            loop {
+                match socket.recv_from(&mut self.buffer) {
+                    Ok((len, addr)) => {
+                        self.routes.insert(addr, index);
+                        let server_result = self
+                            .netcode_server
+                            .process_packet(addr, &mut self.buffer[..len]);
+                        handle_server_result(
+                            server_result,
+                            &self.sockets,
+                            &mut self.routes,
+                            server,
+                        );
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => break,
+                    Err(ref e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+        }
+
+        for client_id in self.netcode_server.clients_id() {
+            let server_result = self.netcode_server.update_client(client_id);
+            handle_server_result(server_result, &self.sockets, &mut self.routes, server);
+        }
+
+        for client_id in server.disconnections_id() {
+            let server_result = self.netcode_server.disconnect(client_id);
+            handle_server_result(server_result, &self.sockets, &mut self.routes, server);
+        }
+
+        // Addresses which sent garbage or never finished connecting
+        let netcode_server = &self.netcode_server;
+        let connected: Vec<SocketAddr> = netcode_server
+            .clients_id_iter()
+            .filter_map(|client_id| netcode_server.client_addr(client_id))
+            .collect();
+        if self.routes.len() > connected.len() + NETCODE_PENDING_ROUTES {
+            self.routes.retain(|addr, _| connected.contains(addr));
+        }
+
+        Ok(())
+    }
+
+    fn send(&mut self, server: &mut RenetServer) {
+        'clients: for client_id in server.clients_id() {
+            let Ok(packets) = server.get_packets_to_send(client_id) else {
+                continue;
+            };
+            for packet in packets {
+                match self
+                    .netcode_server
+                    .generate_payload_packet(client_id, &packet)
+                {
+                    Ok((addr, payload)) => {
+                        let Some(socket) = self
+                            .routes
+                            .get(&addr)
+                            .and_then(|index| self.sockets.get(*index))
+                        else {
+                            continue 'clients;
+                        };
+                        if let Err(e) = socket.send_to(payload, addr) {
+                            error!(
+                                "Failed to send packet to client {} ({}): {}",
+                                client_id, addr, e
+                            );
+                            continue 'clients;
+                        }
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to encrypt payload packet for client {}: {}",
+                            client_id, e
+                        );
+                        continue 'clients;
+                    }
+                }
+            }
+        }
+    }
+
+    fn disconnect_all(&mut self, server: &mut RenetServer) {
+        for client_id in self.netcode_server.clients_id() {
+            let server_result = self.netcode_server.disconnect(client_id);
+            handle_server_result(server_result, &self.sockets, &mut self.routes, server);
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use crate::{
     messages::{NetworkAction, PlayerFrameInput},
-    players::Player,
-    world::{raycast, BlockData, BlockDirection, FaceDirectionExt, ItemStack, ItemType, WorldMap},
+    players::{GameMode, Player, HOTBAR_SLOTS},
+    world::{
+        raycast, BlockData, BlockDirection, BlockId, FaceDirectionExt, ItemId, ItemStack, ItemType,
+        WorldMap, MAX_LIGHT_LEVEL,
+    },
 };
-use bevy::math::{NormedVectorSpace, Vec3};
+use bevy::math::{IVec3, NormedVectorSpace, Vec3};
 use bevy_log::info;
 
 #[derive(Debug, Clone, Copy)]
@@ -21,27 +24,93 @@ impl CallerType {
     }
 }
 
-const INTERACTION_DISTANCE: f32 = 5.0;
+pub const INTERACTION_DISTANCE: f32 = 5.0;
 const CUBE_SIZE: f32 = 1.0;
 
+/// A block a player finished breaking, its drops are given by the server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrokenBlock {
+    pub position: IVec3,
+    pub id: BlockId,
+}
+
+/// A change a player made to the world during a frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockChange {
+    Broken(BrokenBlock),
+    Placed {
+        position: IVec3,
+        id: BlockId,
+    },
+    /// Hit without being broken yet, only its breaking progress changed
+    Damaged {
+        position: IVec3,
+        progress: u8,
+    },
+}
+
+impl BlockChange {
+    pub fn position(&self) -> IVec3 {
+        match self {
+            BlockChange::Broken(broken) => broken.position,
+            BlockChange::Placed { position, .. } | BlockChange::Damaged { position, .. } => {
+                *position
+            }
+        }
+    }
+}
+
 pub fn simulate_player_block_interactions(
     player: &mut Player,
     world_map: &mut impl WorldMap,
     action: &PlayerFrameInput,
     caller_type: CallerType,
-) {
+) -> Option<BlockChange> {
+    let mut change = None;
     // TODO: make sure that only one interaction is processed per game tick (instead of per frame like now)
     for network_action in &action.inputs {
         match network_action {
             NetworkAction::LeftClick => {
-                handle_block_breaking(player, world_map, action, caller_type);
+                if let Some(hit) = handle_block_breaking(player, world_map, action, caller_type) {
+                    change = Some(hit);
+                }
             }
             NetworkAction::RightClick => {
-                handle_block_placement(player, world_map, action, caller_type);
+                if let Some(placed) = handle_block_placement(player, world_map, action, caller_type)
+                {
+                    change = Some(placed);
+                }
             }
+            NetworkAction::PickBlock => handle_pick_block(player, &*world_map, action),
             _ => {}
         }
     }
+    change
+}
+
+/// Fills the hotbar slot of the input with the targeted block, creative players can take any block.
+/// The client only sends it when the block is not in the hotbar already, it then just selects its slot
+fn handle_pick_block(player: &mut Player, world_map: &impl WorldMap, action: &PlayerFrameInput) {
+    if player.game_mode != GameMode::Creative || action.hotbar_slot >= HOTBAR_SLOTS {
+        return;
+    }
+    let Some(item) = raycast::raycast(
+        world_map,
+        &action.camera,
+        &player.position,
+        action.view_mode,
+    )
+    .and_then(|hit| ItemId::from_block(hit.block.id)) else {
+        return;
+    };
+    player.inventory.set_slot(
+        action.hotbar_slot,
+        ItemStack {
+            item_id: item,
+            item_type: item.get_default_type(),
+            nb: item.get_max_stack(),
+        },
+    );
 }
 
 fn handle_block_breaking(
@@ -49,7 +118,7 @@ fn handle_block_breaking(
     world_map: &mut impl WorldMap,
     action: &PlayerFrameInput,
     caller_type: CallerType,
-) {
+) -> Option<BlockChange> {
     let block_position = raycast::raycast(
         world_map,
         &action.camera,
@@ -73,7 +142,7 @@ fn handle_block_breaking(
             player.position,
             action.view_mode
         );
-        return;
+        return None;
     }
 
     let block_pos = block_position.unwrap().position;
@@ -96,7 +165,7 @@ fn handle_block_breaking(
             block_pos,
             distance
         );
-        return;
+        return None;
     }
 
     let block = world_map.get_block_mut_by_coordinates(&block_pos);
@@ -107,10 +176,21 @@ fn handle_block_breaking(
             player.id,
             block_pos
         );
-        return;
+        return None;
     }
     let block = block.unwrap();
 
+    if block.id.is_creative_only() && player.game_mode != GameMode::Creative {
+        log::warn!(
+            "{} Player {} tried to break {:?} at {:?} outside of creative mode",
+            caller_type.as_str(),
+            player.id,
+            block.id,
+            block_pos
+        );
+        return None;
+    }
+
     // Try to break the block
     block.breaking_progress += 1;
 
@@ -129,24 +209,12 @@ fn handle_block_breaking(
         );
 
         world_map.remove_block_by_coordinates(&block_pos);
-        // Add drops to player inventory
-        for (item_id, nb) in block_id.get_drops(1) {
-            player.inventory.add_item_to_inventory(ItemStack {
-                item_id,
-                item_type: item_id.get_default_type(),
-                nb,
-            });
-            info!(
-                "{} Player {} received drop {:?} x{} from breaking block {:?}",
-                caller_type.as_str(),
-                player.id,
-                item_id,
-                nb,
-                block_id
-            );
-        }
+        Some(BlockChange::Broken(BrokenBlock {
+            position: block_pos,
+            id: block_id,
+        }))
     } else {
-        world_map.mark_block_for_update(&block_pos);
+        // The progress is sent on its own, the chunk does not need to be sent again for it
         info!(
             "{} Player {} is breaking block {:?} at position {:?} (progress: {}/{})",
             caller_type.as_str(),
@@ -156,6 +224,10 @@ fn handle_block_breaking(
             breaking_progress,
             break_time
         );
+        Some(BlockChange::Damaged {
+            position: block_pos,
+            progress: breaking_progress,
+        })
     }
 }
 
@@ -164,7 +236,7 @@ fn handle_block_placement(
     world_map: &mut impl WorldMap,
     action: &PlayerFrameInput,
     caller_type: CallerType,
-) {
+) -> Option<BlockChange> {
     let raycast_response = raycast::raycast(
         world_map,
         &action.camera,
@@ -188,7 +260,7 @@ fn handle_block_placement(
             player.position,
             action.view_mode
         );
-        return;
+        return None;
     }
 
     let raycast_response = raycast_response.unwrap();
@@ -204,6 +276,38 @@ fn handle_block_placement(
         face_direction
     );
 
+    // Using a light on a light block dims it by one level, from the darkest back to the brightest
+    let holds_light = player
+        .inventory
+        .get(action.hotbar_slot)
+        .is_some_and(|item| item.item_id == ItemId::Light);
+    if holds_light && player.game_mode == GameMode::Creative {
+        if let Some(level) = world_map
+            .get_block_by_coordinates(&collision_pos)
+            .filter(|block| block.id == BlockId::Light)
+            .map(|block| block.light_level())
+        {
+            let level = if level <= 1 {
+                MAX_LIGHT_LEVEL
+            } else {
+                level - 1
+            };
+            world_map.set_block(&collision_pos, BlockData::light(level));
+            return Some(BlockChange::Placed {
+                position: collision_pos,
+                id: BlockId::Light,
+            });
+        }
+    }
+
+    // Containers are opened by the server instead
+    if world_map
+        .get_block_by_coordinates(&collision_pos)
+        .is_some_and(|block| block.id.is_container())
+    {
+        return None;
+    }
+
     let face = raycast_response.face.to_ivec3();
 
     let block_to_create_pos = collision_pos + face;
@@ -229,7 +333,17 @@ fn handle_block_placement(
             collision_pos,
             distance
         );
-        return;
+        return None;
+    }
+
+    if !world_map.height_limits().contains_y(block_to_create_pos.y) {
+        log::warn!(
+            "{} Player {} tried to place block at {:?} but it's outside the world height",
+            caller_type.as_str(),
+            player.id,
+            block_to_create_pos
+        );
+        return None;
     }
 
     // Check if there's already a block at that position
@@ -243,7 +357,7 @@ fn handle_block_placement(
             player.id,
             block_to_create_pos
         );
-        return;
+        return None;
     }
 
     let delta = player.position - target_cube_center;
@@ -268,7 +382,7 @@ fn handle_block_placement(
             player.id,
             block_to_create_pos
         );
-        return;
+        return None;
     }
 
     let inventory_slot = action.hotbar_slot;
@@ -281,18 +395,57 @@ fn handle_block_placement(
             player.id,
             inventory_slot
         );
-        return;
+        return None;
     }
 
     // Try to get item from player's inventory
-    if let Some(&item) = player.inventory.inner.get(&inventory_slot) {
+    if let Some(&item) = player.inventory.get(inventory_slot) {
+        // Flint and steel lights a fire and is kept
+        if item.item_id == ItemId::FlintAndSteel {
+            world_map.set_block(
+                &block_to_create_pos,
+                BlockData::new(BlockId::Fire, BlockDirection::Front),
+            );
+            log::info!(
+                "{} Player {} lit a fire at position {:?}",
+                caller_type.as_str(),
+                player.id,
+                block_to_create_pos
+            );
+            return Some(BlockChange::Placed {
+                position: block_to_create_pos,
+                id: BlockId::Fire,
+            });
+        }
+
         // Check if the item has a block counterpart
         if let ItemType::Block(block_id) = item.item_type {
+            if block_id.is_creative_only() && player.game_mode != GameMode::Creative {
+                log::warn!(
+                    "{} Player {} tried to place {:?} outside of creative mode",
+                    caller_type.as_str(),
+                    player.id,
+                    block_id
+                );
+                return None;
+            }
+
             // Remove item from inventory
-            player.inventory.remove_item_from_stack(inventory_slot, 1);
+            if let Err(e) = player.inventory.take_from_slot(inventory_slot, 1) {
+                log::warn!(
+                    "{} Player {} could not place block: {}",
+                    caller_type.as_str(),
+                    player.id,
+                    e
+                );
+                return None;
+            }
 
-            // Place the block
-            let block = BlockData::new(block_id, BlockDirection::Front);
+            // Place the block, light blocks start at full brightness
+            let block = match block_id {
+                BlockId::Light => BlockData::light(MAX_LIGHT_LEVEL),
+                _ => BlockData::new(block_id, BlockDirection::Front),
+            };
             world_map.set_block(&block_to_create_pos, block);
 
             log::info!(
@@ -302,6 +455,10 @@ fn handle_block_placement(
                 block_id,
                 block_to_create_pos
             );
+            return Some(BlockChange::Placed {
+                position: block_to_create_pos,
+                id: block_id,
+            });
         } else {
             log::warn!(
                 "{} Player {} tried to place item {:?} but it's not a block",
@@ -318,4 +475,5 @@ fn handle_block_placement(
             inventory_slot
         );
     }
+    None
 }
@@ -1,6 +1,6 @@
 use bevy::math::{bounding::Aabb3d, Vec3};
 
-use crate::world::WorldMap;
+use crate::world::{allowed_motion, WorldMap};
 
 use super::Player;
 
@@ -14,3 +14,41 @@ pub fn check_player_collision(
         Vec3::new(player.width, player.height, player.width) / 2.0,
     ))
 }
+
+/// Horizontal speed, relative to the overlap depth, at which overlapping entities are pushed apart
+pub const ENTITY_PUSHBACK_SPEED: f32 = 8.0;
+
+/// Half extents of the hitbox of a player
+pub fn player_half_size(player: &Player) -> Vec3 {
+    Vec3::new(player.width, player.height, player.width) / 2.0
+}
+
+/// Horizontal displacement pushing an entity out of another one it overlaps, over `delta_t` seconds
+///
+/// The other entity is pushed by the opposite displacement, so that crowds spread out evenly
+pub fn entity_pushback(
+    position: Vec3,
+    half_size: Vec3,
+    other_position: Vec3,
+    other_half_size: Vec3,
+    delta_t: f32,
+) -> Vec3 {
+    let offset = position - other_position;
+    let overlap = half_size + other_half_size - offset.abs();
+    if overlap.x <= 0.0 || overlap.y <= 0.0 || overlap.z <= 0.0 {
+        return Vec3::ZERO;
+    }
+
+    // Entities standing exactly at the same spot still need to be split up, in a deterministic way
+    let away = offset.with_y(0.0).try_normalize().unwrap_or(Vec3::X);
+    let depth = overlap.x.min(overlap.z);
+
+    away * (depth * ENTITY_PUSHBACK_SPEED * delta_t).min(depth / 2.0)
+}
+
+/// Moves the player by a pushback displacement, stopping against the blocks in the way
+pub fn apply_player_pushback(player: &mut Player, push: Vec3, world_map: &impl WorldMap) {
+    let hitbox = Aabb3d::new(player.position, player_half_size(player));
+    let (allowed, _) = allowed_motion(world_map, &hitbox, push.with_y(0.0));
+    player.position += allowed;
+}
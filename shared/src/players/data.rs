@@ -3,123 +3,35 @@ use bevy::{
     prelude::*,
     prelude::{Component, Resource, Transform},
 };
-use bevy_platform::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 use crate::{
     constants::DEFAULT_RENDER_DISTANCE_CHUNKS,
-    messages::PlayerId,
-    world::{ItemId, ItemStack, ItemType},
-    CHUNK_SIZE, MAX_INVENTORY_SLOTS,
+    messages::{Emote, Hint, PlayerId},
+    players::{item_use::ItemUseState, Inventory, StatusEffects, MAX_PLAYER_HEALTH},
+    world::RecipeId,
+    CHUNK_SIZE,
 };
 
-#[derive(Debug, Resource, Clone, Serialize, Deserialize, PartialEq)]
-pub struct Inventory {
-    pub inner: HashMap<u32, ItemStack>,
-}
-
-impl Default for Inventory {
-    fn default() -> Self {
-        Self::new()
-    }
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    /// Broken blocks drop nothing
+    Creative,
+    /// Cannot touch the world nor be hurt, and can watch through the eyes of other players
+    Spectator,
 }
 
-impl Inventory {
-    pub fn new() -> Self {
-        Self {
-            inner: HashMap::new(),
-        }
-    }
-
-    // Ajoute un item à l'inventaire du joueur
-    pub fn add_item_to_inventory(&mut self, mut stack: ItemStack) {
-        for i in 0..MAX_INVENTORY_SLOTS {
-            let item_option = self.inner.get(&i);
-
-            if let Some(existing_item) = item_option {
-                // If not item of right type or stack already full : pass
-                if existing_item.item_id != stack.item_id
-                    || existing_item.nb >= stack.item_id.get_max_stack()
-                {
-                    continue;
-                }
-
-                stack.nb += existing_item.nb;
-            }
-
-            let inserted_stack = ItemStack {
-                item_id: stack.item_id,
-                item_type: stack.item_type,
-                nb: if stack.nb >= stack.item_id.get_max_stack() {
-                    stack.item_id.get_max_stack()
-                } else {
-                    stack.nb
-                },
-            };
-            stack.nb -= inserted_stack.nb;
-
-            // Push inserted items in right inventory slot
-            self.inner.insert(i, inserted_stack);
-
-            // If no more items to add, end loop
-            if stack.nb == 0 {
-                break;
-            }
-        }
-
-        // Problem : if inventory full, items disappear
-    }
-
-    /// Add items to stack at specified position\
-    /// Stacks cannot exceed MAX_ITEM_STACK number of items\
-    /// Returns number of items really added to the stack
-    pub fn add_item_to_stack(
-        &mut self,
-        stack: u32,
-        mut nb: u32,
-        id: ItemId,
-        item_type: ItemType,
-    ) -> u32 {
-        let item_option = self.inner.get(&stack);
-        let mut new_item = ItemStack {
-            item_id: id,
-            nb,
-            item_type,
-        };
-
-        if let Some(item) = item_option {
-            if nb + item.nb > item.item_id.get_max_stack() {
-                nb = item.item_id.get_max_stack() - item.nb;
-            }
-            new_item.nb = nb + item.nb;
-        }
-        self.inner.insert(stack, new_item);
-        nb
-    }
-
-    /// Removes items from stack at specified position\
-    /// Stacks cannot have < 0 number of items\
-    /// Returns number of items really removed from the stack
-    pub fn remove_item_from_stack(&mut self, stack: u32, mut nb: u32) -> u32 {
-        let item_option = self.inner.get(&stack);
-
-        if let Some(&item) = item_option {
-            if nb >= item.nb {
-                nb = item.nb;
-                self.inner.remove(&stack);
-            } else {
-                self.inner.insert(
-                    stack,
-                    ItemStack {
-                        item_id: item.item_id,
-                        nb: item.nb - nb,
-                        item_type: item.item_type,
-                    },
-                );
-            }
-            return nb;
+impl GameMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "survival" => Some(Self::Survival),
+            "creative" => Some(Self::Creative),
+            "spectator" => Some(Self::Spectator),
+            _ => None,
         }
-        0
     }
 }
 
@@ -136,6 +48,29 @@ pub struct Player {
     pub height: f32,
     pub width: f32,
     pub last_input_processed: u64,
+    pub emote: Option<Emote>,
+    pub health: f32,
+    /// Vertical speed of the last landing, taken by the server to apply fall damage
+    pub landing_speed: f32,
+    /// Set when the player fell below the world and was moved back to the spawn
+    pub fell_into_void: bool,
+    #[serde(default)]
+    pub unlocked_recipes: BTreeSet<RecipeId>,
+    #[serde(default)]
+    pub game_mode: GameMode,
+    #[serde(default)]
+    pub effects: StatusEffects,
+    /// Hints already shown to the player, which are never shown again
+    #[serde(default)]
+    pub seen_hints: BTreeSet<Hint>,
+    #[serde(skip)]
+    pub item_use: ItemUseState,
+    /// Player followed by this spectator
+    #[serde(skip)]
+    pub spectating: Option<PlayerId>,
+    /// Hotbar slot of the item in hand, shown to the other players
+    #[serde(skip)]
+    pub hotbar_slot: u32,
 }
 
 impl Player {
@@ -152,6 +87,17 @@ impl Player {
             height: 1.8,
             width: 0.8,
             last_input_processed: 0,
+            emote: None,
+            health: MAX_PLAYER_HEALTH,
+            landing_speed: 0.0,
+            fell_into_void: false,
+            unlocked_recipes: BTreeSet::new(),
+            game_mode: GameMode::Survival,
+            effects: StatusEffects::default(),
+            seen_hints: BTreeSet::new(),
+            item_use: ItemUseState::default(),
+            spectating: None,
+            hotbar_slot: 0,
         }
     }
 
@@ -175,6 +121,17 @@ impl Default for Player {
             height: 1.8,
             width: 0.8,
             last_input_processed: 0,
+            emote: None,
+            health: MAX_PLAYER_HEALTH,
+            landing_speed: 0.0,
+            fell_into_void: false,
+            unlocked_recipes: BTreeSet::new(),
+            game_mode: GameMode::Survival,
+            effects: StatusEffects::default(),
+            seen_hints: BTreeSet::new(),
+            item_use: ItemUseState::default(),
+            spectating: None,
+            hotbar_slot: 0,
         }
     }
 }
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::TICKS_PER_SECOND;
+
+/// Highest amplifier an effect can have, stronger ones are clamped to it
+pub const MAX_EFFECT_AMPLIFIER: u8 = 4;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StatusEffectKind {
+    /// Hurts over time, but never kills
+    Poison,
+    /// Heals over time
+    Regeneration,
+    Speed,
+    Slowness,
+    /// Hurts over time, whatever the amplifier
+    Fire,
+}
+
+impl StatusEffectKind {
+    pub const ALL: [StatusEffectKind; 5] = [
+        StatusEffectKind::Poison,
+        StatusEffectKind::Regeneration,
+        StatusEffectKind::Speed,
+        StatusEffectKind::Slowness,
+        StatusEffectKind::Fire,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusEffectKind::Poison => "poison",
+            StatusEffectKind::Regeneration => "regeneration",
+            StatusEffectKind::Speed => "speed",
+            StatusEffectKind::Slowness => "slowness",
+            StatusEffectKind::Fire => "fire",
+        }
+    }
+
+    /// Ticks between two health changes and the change itself, for the effects changing health
+    fn health_change(&self, amplifier: u8) -> Option<(u32, f32)> {
+        let second = TICKS_PER_SECOND as u32;
+        match self {
+            StatusEffectKind::Poison => Some(((second / (amplifier as u32 + 1)).max(1), -1.0)),
+            StatusEffectKind::Regeneration => Some(((second / (amplifier as u32 + 1)).max(1), 1.0)),
+            StatusEffectKind::Fire => Some((second, -1.0)),
+            StatusEffectKind::Speed | StatusEffectKind::Slowness => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StatusEffect {
+    /// 0 for the first level of the effect
+    pub amplifier: u8,
+    pub remaining_ticks: u32,
+    /// Ticks since the effect was applied, refreshing it does not reset them
+    #[serde(default)]
+    pub elapsed_ticks: u32,
+}
+
+/// Effects a player or a mob is under, ticked by the server and sent to the clients.
+/// Movement reads them the same way on both sides, so that speed effects are predicted.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct StatusEffects(pub BTreeMap<StatusEffectKind, StatusEffect>);
+
+impl StatusEffects {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn has(&self, kind: StatusEffectKind) -> bool {
+        self.0.contains_key(&kind)
+    }
+
+    pub fn get(&self, kind: StatusEffectKind) -> Option<&StatusEffect> {
+        self.0.get(&kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (StatusEffectKind, &StatusEffect)> {
+        self.0.iter().map(|(kind, effect)| (*kind, effect))
+    }
+
+    /// Applies an effect, an active one of the same kind is only replaced by a stronger or a longer one
+    pub fn add(&mut self, kind: StatusEffectKind, amplifier: u8, duration_ticks: u32) {
+        let effect = StatusEffect {
+            amplifier: amplifier.min(MAX_EFFECT_AMPLIFIER),
+            remaining_ticks: duration_ticks,
+            elapsed_ticks: 0,
+        };
+        match self.0.get_mut(&kind) {
+            Some(active) if active.amplifier > effect.amplifier => {}
+            Some(active) if active.amplifier == effect.amplifier => {
+                active.remaining_ticks = active.remaining_ticks.max(duration_ticks);
+            }
+            _ => {
+                self.0.insert(kind, effect);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, kind: StatusEffectKind) -> bool {
+        self.0.remove(&kind).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Scales the walking speed, speed and slowness add up
+    pub fn speed_multiplier(&self) -> f32 {
+        let level = |kind| self.get(kind).map_or(0.0, |e| e.amplifier as f32 + 1.0);
+        (1.0 + 0.2 * level(StatusEffectKind::Speed) - 0.15 * level(StatusEffectKind::Slowness))
+            .max(0.0)
+    }
+
+    /// Advances the effects by a tick, removing the expired ones.
+    /// Returns the health changes due this tick, negative for damage
+    pub fn tick(&mut self) -> Vec<(StatusEffectKind, f32)> {
+        let mut changes = Vec::new();
+        for (kind, effect) in self.0.iter_mut() {
+            effect.remaining_ticks = effect.remaining_ticks.saturating_sub(1);
+            effect.elapsed_ticks += 1;
+            if let Some((period, change)) = kind.health_change(effect.amplifier) {
+                if effect.elapsed_ticks.is_multiple_of(period) {
+                    changes.push((*kind, change));
+                }
+            }
+        }
+        self.0.retain(|_, effect| effect.remaining_ticks > 0);
+        changes
+    }
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lang::translatable,
+    messages::PlayerId,
+    players::StatusEffectKind,
+    world::{MobId, MobKind},
+};
+
+pub const MAX_PLAYER_HEALTH: f32 = 20.0;
+
+/// Landing slower than this does not hurt, about a 4 blocks fall
+pub const SAFE_LANDING_SPEED: f32 = 11.0;
+pub const FALL_DAMAGE_PER_SPEED: f32 = 1.0;
+
+/// What caused some damage, carried along with it up to the death message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DamageSource {
+    Fall,
+    Void,
+    Mob { id: MobId, kind: MobKind },
+    Player { id: PlayerId, name: String },
+    Effect(StatusEffectKind),
+}
+
+impl DamageSource {
+    /// Chat message announcing the death of `victim`, translated by the clients
+    pub fn death_message(&self, victim: &str) -> String {
+        match self {
+            DamageSource::Fall => translatable("death.fall", &[victim]),
+            DamageSource::Void => translatable("death.void", &[victim]),
+            DamageSource::Mob { kind, .. } => {
+                translatable("death.mob", &[victim, &kind.translation_key()])
+            }
+            DamageSource::Player { name, .. } => translatable("death.player", &[victim, name]),
+            DamageSource::Effect(kind) => {
+                translatable(&format!("death.{}", kind.translation_key()), &[victim])
+            }
+        }
+    }
+
+    /// Translation key or name shown on the left side of a kill feed entry
+    #[cfg(feature = "client")]
+    pub fn attacker_name(&self) -> Option<String> {
+        match self {
+            DamageSource::Fall | DamageSource::Void | DamageSource::Effect(_) => None,
+            DamageSource::Mob { kind, .. } => Some(kind.translation_key()),
+            DamageSource::Player { name, .. } => Some(name.clone()),
+        }
+    }
+
+    /// Translation key of the cause of death, for the deaths without an attacker
+    #[cfg(feature = "client")]
+    pub fn cause_key(&self) -> String {
+        match self {
+            DamageSource::Fall => "damage.fall".to_string(),
+            DamageSource::Void => "damage.void".to_string(),
+            DamageSource::Effect(kind) => kind.translation_key(),
+            DamageSource::Mob { kind, .. } => kind.translation_key(),
+            DamageSource::Player { .. } => "damage.player".to_string(),
+        }
+    }
+}
+
+pub fn fall_damage(landing_speed: f32) -> f32 {
+    ((landing_speed - SAFE_LANDING_SPEED) * FALL_DAMAGE_PER_SPEED).max(0.0)
+}
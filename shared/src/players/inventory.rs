@@ -0,0 +1,545 @@
+use std::fmt;
+use std::ops::Range;
+
+use bevy::prelude::Resource;
+use bevy_platform::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    world::{ItemId, ItemStack, ItemType},
+    MAX_INVENTORY_SLOTS,
+};
+
+pub const HOTBAR_SLOTS: u32 = 9;
+pub const ARMOR_SLOTS: u32 = 4;
+
+/// Group of inventory slots with the same purpose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlotRange {
+    Hotbar,
+    Main,
+    Armor,
+    OffHand,
+}
+
+impl SlotRange {
+    pub const ALL: [SlotRange; 4] = [Self::Hotbar, Self::Main, Self::Armor, Self::OffHand];
+
+    pub fn slots(&self) -> Range<u32> {
+        match self {
+            Self::Hotbar => 0..HOTBAR_SLOTS,
+            Self::Main => HOTBAR_SLOTS..MAX_INVENTORY_SLOTS,
+            Self::Armor => MAX_INVENTORY_SLOTS..MAX_INVENTORY_SLOTS + ARMOR_SLOTS,
+            Self::OffHand => {
+                MAX_INVENTORY_SLOTS + ARMOR_SLOTS..MAX_INVENTORY_SLOTS + ARMOR_SLOTS + 1
+            }
+        }
+    }
+
+    /// Range the slot belongs to, `None` if it is not a slot of the inventory
+    pub fn of(slot: u32) -> Option<SlotRange> {
+        Self::ALL
+            .into_iter()
+            .find(|range| range.slots().contains(&slot))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InventoryError {
+    InvalidSlot(u32),
+    EmptySlot(u32),
+    /// Only part of the items fitted, the others are left out
+    Full {
+        remaining: u32,
+    },
+    NotEnoughItems {
+        item: ItemId,
+        requested: u32,
+        available: u32,
+    },
+    /// The slot already holds another item
+    ItemMismatch {
+        slot: u32,
+    },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSlot(slot) => write!(f, "slot {} does not exist", slot),
+            Self::EmptySlot(slot) => write!(f, "slot {} is empty", slot),
+            Self::Full { remaining } => {
+                write!(f, "inventory is full, {} items did not fit", remaining)
+            }
+            Self::NotEnoughItems {
+                item,
+                requested,
+                available,
+            } => write!(
+                f,
+                "{} {:?} requested but only {} available",
+                requested, item, available
+            ),
+            Self::ItemMismatch { slot } => write!(f, "slot {} holds another item", slot),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+#[derive(Debug, Resource, Clone, Serialize, Deserialize)]
+pub struct Inventory {
+    pub inner: HashMap<u32, ItemStack>,
+    /// Set by the methods adding items, so that the server only looks at the inventories which changed.
+    /// Writing to `inner` directly is not tracked. Neither sent nor saved
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl PartialEq for Inventory {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn check_slot(slot: u32) -> Result<(), InventoryError> {
+    match SlotRange::of(slot) {
+        Some(_) => Ok(()),
+        None => Err(InventoryError::InvalidSlot(slot)),
+    }
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            inner: HashMap::new(),
+            changed: false,
+        }
+    }
+
+    /// Whether items were added since the last call
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Replaces the content of a slot
+    pub fn set_slot(&mut self, slot: u32, stack: ItemStack) {
+        self.inner.insert(slot, stack);
+        self.changed = true;
+    }
+
+    pub fn get(&self, slot: u32) -> Option<&ItemStack> {
+        self.inner.get(&slot)
+    }
+
+    /// Number of items of this kind in the whole inventory
+    pub fn count_of(&self, item: ItemId) -> u32 {
+        self.inner
+            .values()
+            .filter(|stack| stack.item_id == item)
+            .map(|stack| stack.nb)
+            .sum()
+    }
+
+    /// Puts the items in the hotbar and main slots, completing the existing stacks first.
+    /// If they do not all fit, the ones that fit are kept and the others are reported in the error.
+    pub fn try_insert(&mut self, stack: ItemStack) -> Result<(), InventoryError> {
+        let max_stack = stack.item_id.get_max_stack();
+        let mut remaining = stack.nb;
+        let slots = SlotRange::Hotbar.slots().chain(SlotRange::Main.slots());
+        self.changed = true;
+
+        for slot in slots.clone() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(existing) = self.inner.get_mut(&slot) {
+                if existing.item_id == stack.item_id && existing.nb < max_stack {
+                    let added = remaining.min(max_stack - existing.nb);
+                    existing.nb += added;
+                    remaining -= added;
+                }
+            }
+        }
+
+        for slot in slots {
+            if remaining == 0 {
+                break;
+            }
+            if !self.inner.contains_key(&slot) {
+                let added = remaining.min(max_stack);
+                self.inner.insert(
+                    slot,
+                    ItemStack {
+                        item_id: stack.item_id,
+                        item_type: stack.item_type,
+                        nb: added,
+                    },
+                );
+                remaining -= added;
+            }
+        }
+
+        if remaining > 0 {
+            return Err(InventoryError::Full { remaining });
+        }
+        Ok(())
+    }
+
+    /// Removes exactly `nb` items of this kind from anywhere in the inventory, nothing is removed if there are not enough
+    pub fn remove_exact(&mut self, item: ItemId, nb: u32) -> Result<(), InventoryError> {
+        let available = self.count_of(item);
+        if available < nb {
+            return Err(InventoryError::NotEnoughItems {
+                item,
+                requested: nb,
+                available,
+            });
+        }
+
+        let mut slots: Vec<u32> = self
+            .inner
+            .iter()
+            .filter(|(_, stack)| stack.item_id == item)
+            .map(|(slot, _)| *slot)
+            .collect();
+        // The main slots are emptied before the hotbar
+        slots.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut remaining = nb;
+        for slot in slots {
+            if remaining == 0 {
+                break;
+            }
+            let Some(stack) = self.inner.get_mut(&slot) else {
+                continue;
+            };
+            let removed = remaining.min(stack.nb);
+            stack.nb -= removed;
+            remaining -= removed;
+            if stack.nb == 0 {
+                self.inner.remove(&slot);
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes exactly `nb` items from a slot
+    pub fn take_from_slot(&mut self, slot: u32, nb: u32) -> Result<ItemStack, InventoryError> {
+        check_slot(slot)?;
+        let stack = self
+            .inner
+            .get_mut(&slot)
+            .ok_or(InventoryError::EmptySlot(slot))?;
+        if stack.nb < nb {
+            return Err(InventoryError::NotEnoughItems {
+                item: stack.item_id,
+                requested: nb,
+                available: stack.nb,
+            });
+        }
+
+        stack.nb -= nb;
+        let taken = ItemStack { nb, ..*stack };
+        if stack.nb == 0 {
+            self.inner.remove(&slot);
+        }
+        Ok(taken)
+    }
+
+    /// Moves up to `nb` items from one slot to another, which must be empty or hold the same item.
+    /// Returns how many were moved, fewer than asked if the target stack got full.
+    pub fn move_between(&mut self, from: u32, to: u32, nb: u32) -> Result<u32, InventoryError> {
+        check_slot(from)?;
+        check_slot(to)?;
+        let source = *self
+            .inner
+            .get(&from)
+            .ok_or(InventoryError::EmptySlot(from))?;
+        if source.nb < nb {
+            return Err(InventoryError::NotEnoughItems {
+                item: source.item_id,
+                requested: nb,
+                available: source.nb,
+            });
+        }
+        if from == to {
+            return Ok(0);
+        }
+
+        let in_target = match self.inner.get(&to) {
+            Some(target) if target.item_id != source.item_id => {
+                return Err(InventoryError::ItemMismatch { slot: to })
+            }
+            Some(target) => target.nb,
+            None => 0,
+        };
+        let moved = nb.min(source.item_id.get_max_stack().saturating_sub(in_target));
+        if moved == 0 {
+            return Ok(0);
+        }
+
+        self.take_from_slot(from, moved)?;
+        self.inner.insert(
+            to,
+            ItemStack {
+                nb: in_target + moved,
+                ..source
+            },
+        );
+        Ok(moved)
+    }
+
+    /// Add items to stack at specified position\
+    /// Stacks cannot exceed the max stack size of their item, nor mix different items\
+    /// Returns number of items really added to the stack
+    pub fn add_item_to_stack(
+        &mut self,
+        stack: u32,
+        nb: u32,
+        id: ItemId,
+        item_type: ItemType,
+    ) -> u32 {
+        let in_stack = match self.inner.get(&stack) {
+            Some(item) if item.item_id != id => return 0,
+            Some(item) => item.nb,
+            None => 0,
+        };
+        let added = nb.min(id.get_max_stack().saturating_sub(in_stack));
+        if added == 0 {
+            return 0;
+        }
+
+        self.changed = true;
+        self.inner.insert(
+            stack,
+            ItemStack {
+                item_id: id,
+                nb: in_stack + added,
+                item_type,
+            },
+        );
+        added
+    }
+
+    /// Removes items from stack at specified position\
+    /// Stacks cannot have < 0 number of items\
+    /// Returns number of items really removed from the stack
+    pub fn remove_item_from_stack(&mut self, stack: u32, mut nb: u32) -> u32 {
+        let item_option = self.inner.get(&stack);
+
+        if let Some(&item) = item_option {
+            if nb >= item.nb {
+                nb = item.nb;
+                self.inner.remove(&stack);
+            } else {
+                self.inner.insert(
+                    stack,
+                    ItemStack {
+                        item_id: item.item_id,
+                        nb: item.nb - nb,
+                        item_type: item.item_type,
+                    },
+                );
+            }
+            return nb;
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inventory whose hotbar and main slots all hold a full stack of dirt
+    fn full_of_dirt() -> Inventory {
+        let mut inventory = Inventory::new();
+        for slot in SlotRange::Hotbar.slots().chain(SlotRange::Main.slots()) {
+            inventory.set_slot(slot, ItemStack::new(ItemId::Dirt, 64));
+        }
+        inventory
+    }
+
+    #[test]
+    fn insert_completes_stacks_then_splits_across_slots() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(3, ItemStack::new(ItemId::Stone, 60));
+
+        assert_eq!(
+            inventory.try_insert(ItemStack::new(ItemId::Stone, 100)),
+            Ok(())
+        );
+
+        assert_eq!(inventory.get(3), Some(&ItemStack::new(ItemId::Stone, 64)));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Stone, 64)));
+        assert_eq!(inventory.get(1), Some(&ItemStack::new(ItemId::Stone, 32)));
+        assert_eq!(inventory.count_of(ItemId::Stone), 160);
+    }
+
+    #[test]
+    fn insert_into_full_inventory_reports_what_did_not_fit() {
+        let mut inventory = full_of_dirt();
+        inventory.set_slot(0, ItemStack::new(ItemId::Dirt, 60));
+
+        assert_eq!(
+            inventory.try_insert(ItemStack::new(ItemId::Dirt, 10)),
+            Err(InventoryError::Full { remaining: 6 })
+        );
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Dirt, 64)));
+        // Armor and off hand slots are not filled by insertion
+        assert!(SlotRange::Armor
+            .slots()
+            .chain(SlotRange::OffHand.slots())
+            .all(|slot| inventory.get(slot).is_none()));
+    }
+
+    #[test]
+    fn remove_exact_takes_from_main_slots_first() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(0, ItemStack::new(ItemId::Coal, 10));
+        inventory.set_slot(20, ItemStack::new(ItemId::Coal, 5));
+
+        assert_eq!(inventory.remove_exact(ItemId::Coal, 7), Ok(()));
+
+        assert_eq!(inventory.get(20), None);
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Coal, 8)));
+    }
+
+    #[test]
+    fn remove_exact_removes_nothing_without_enough_items() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(0, ItemStack::new(ItemId::Coal, 10));
+        inventory.set_slot(20, ItemStack::new(ItemId::Coal, 5));
+        let before = inventory.clone();
+
+        assert_eq!(
+            inventory.remove_exact(ItemId::Coal, 16),
+            Err(InventoryError::NotEnoughItems {
+                item: ItemId::Coal,
+                requested: 16,
+                available: 15,
+            })
+        );
+        assert_eq!(inventory, before);
+    }
+
+    #[test]
+    fn take_from_slot_empties_the_slot() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(2, ItemStack::new(ItemId::Bone, 3));
+
+        assert_eq!(
+            inventory.take_from_slot(2, 2),
+            Ok(ItemStack::new(ItemId::Bone, 2))
+        );
+        assert_eq!(
+            inventory.take_from_slot(2, 2),
+            Err(InventoryError::NotEnoughItems {
+                item: ItemId::Bone,
+                requested: 2,
+                available: 1,
+            })
+        );
+        assert_eq!(
+            inventory.take_from_slot(2, 1),
+            Ok(ItemStack::new(ItemId::Bone, 1))
+        );
+        assert_eq!(inventory.get(2), None);
+        assert_eq!(
+            inventory.take_from_slot(2, 1),
+            Err(InventoryError::EmptySlot(2))
+        );
+    }
+
+    #[test]
+    fn move_between_refuses_another_item() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(0, ItemStack::new(ItemId::Dirt, 10));
+        inventory.set_slot(1, ItemStack::new(ItemId::Sand, 10));
+        let before = inventory.clone();
+
+        assert_eq!(
+            inventory.move_between(0, 1, 5),
+            Err(InventoryError::ItemMismatch { slot: 1 })
+        );
+        assert_eq!(inventory, before);
+    }
+
+    #[test]
+    fn move_between_stops_when_the_target_is_full() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(0, ItemStack::new(ItemId::Dirt, 10));
+        inventory.set_slot(1, ItemStack::new(ItemId::Dirt, 60));
+
+        assert_eq!(inventory.move_between(0, 1, 10), Ok(4));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Dirt, 6)));
+        assert_eq!(inventory.get(1), Some(&ItemStack::new(ItemId::Dirt, 64)));
+
+        assert_eq!(inventory.move_between(0, 1, 6), Ok(0));
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Dirt, 6)));
+    }
+
+    #[test]
+    fn move_between_the_same_slot_moves_nothing() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(4, ItemStack::new(ItemId::Dirt, 10));
+
+        assert_eq!(inventory.move_between(4, 4, 10), Ok(0));
+        assert_eq!(inventory.get(4), Some(&ItemStack::new(ItemId::Dirt, 10)));
+    }
+
+    #[test]
+    fn out_of_range_slots_are_refused() {
+        let outside = MAX_INVENTORY_SLOTS + ARMOR_SLOTS + 1;
+        assert_eq!(SlotRange::of(outside), None);
+
+        let mut inventory = Inventory::new();
+        inventory.set_slot(0, ItemStack::new(ItemId::Dirt, 10));
+
+        assert_eq!(
+            inventory.take_from_slot(outside, 1),
+            Err(InventoryError::InvalidSlot(outside))
+        );
+        assert_eq!(
+            inventory.move_between(0, outside, 1),
+            Err(InventoryError::InvalidSlot(outside))
+        );
+        assert_eq!(
+            inventory.move_between(outside, 0, 1),
+            Err(InventoryError::InvalidSlot(outside))
+        );
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Dirt, 10)));
+    }
+
+    #[test]
+    fn add_to_stack_stops_at_the_max_stack_size() {
+        let mut inventory = Inventory::new();
+        let dirt = ItemId::Dirt.get_default_type();
+
+        // An empty slot used to take every item given to it
+        assert_eq!(inventory.add_item_to_stack(0, 100, ItemId::Dirt, dirt), 64);
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Dirt, 64)));
+        assert_eq!(inventory.add_item_to_stack(0, 1, ItemId::Dirt, dirt), 0);
+
+        let bow = ItemId::Bow.get_default_type();
+        assert_eq!(inventory.add_item_to_stack(1, 3, ItemId::Bow, bow), 1);
+        assert_eq!(inventory.get(1), Some(&ItemStack::new(ItemId::Bow, 1)));
+    }
+
+    #[test]
+    fn add_to_stack_keeps_other_items() {
+        let mut inventory = Inventory::new();
+        inventory.set_slot(0, ItemStack::new(ItemId::Sand, 10));
+
+        let dirt = ItemId::Dirt.get_default_type();
+        assert_eq!(inventory.add_item_to_stack(0, 5, ItemId::Dirt, dirt), 0);
+        assert_eq!(inventory.get(0), Some(&ItemStack::new(ItemId::Sand, 10)));
+    }
+}
@@ -0,0 +1,185 @@
+use bevy_platform::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    messages::{NetworkAction, PlayerFrameInput},
+    players::Player,
+    world::{ItemId, ItemUseKind},
+};
+
+/// An item being charged or consumed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ActiveItemUse {
+    pub slot: u32,
+    pub item: ItemId,
+    pub kind: ItemUseKind,
+    pub started_ms: u64,
+}
+
+/// Where a player is in the use of their held item.
+/// Times are in the clock given to `simulate_item_use`, the server uses its own so that clients cannot speed uses up.
+#[derive(Debug, Clone, Default)]
+pub struct ItemUseState {
+    pub active: Option<ActiveItemUse>,
+    /// When each item can be used again
+    pub cooldowns: HashMap<ItemId, u64>,
+    /// Uses start when the button gets pressed, holding it does not repeat them
+    pub was_held: bool,
+}
+
+impl ItemUseState {
+    pub fn is_on_cooldown(&self, item: ItemId, now_ms: u64) -> bool {
+        self.cooldowns
+            .get(&item)
+            .is_some_and(|ready_at| *ready_at > now_ms)
+    }
+
+    fn start_cooldown(&mut self, item: ItemId, now_ms: u64) {
+        self.cooldowns.retain(|_, ready_at| *ready_at > now_ms);
+        let cooldown = item.use_cooldown_ms();
+        if cooldown > 0 {
+            self.cooldowns.insert(item, now_ms + cooldown);
+        }
+    }
+}
+
+/// What changed in the item use of a player during one input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemUseEffect {
+    Started(ActiveItemUse),
+    Cancelled {
+        item: ItemId,
+    },
+    /// An instant item was used or a consumable was finished, one was taken from the inventory
+    Used {
+        item: ItemId,
+    },
+    /// A charged item was released, `charge` goes from 0 to 1
+    Released {
+        item: ItemId,
+        charge: f32,
+    },
+    /// The use was refused, the item is still cooling down
+    OnCooldown {
+        item: ItemId,
+    },
+}
+
+impl ItemUseEffect {
+    pub fn item(&self) -> ItemId {
+        match *self {
+            Self::Started(active) => active.item,
+            Self::Cancelled { item }
+            | Self::Used { item }
+            | Self::Released { item, .. }
+            | Self::OnCooldown { item } => item,
+        }
+    }
+}
+
+fn finish_use(player: &mut Player, item: ItemId, now_ms: u64) {
+    player.item_use.active = None;
+    player.item_use.start_cooldown(item, now_ms);
+}
+
+pub fn simulate_item_use(
+    player: &mut Player,
+    action: &PlayerFrameInput,
+    now_ms: u64,
+) -> Option<ItemUseEffect> {
+    let held = action.inputs.contains(&NetworkAction::RightClick);
+    let just_pressed = held && !player.item_use.was_held;
+    player.item_use.was_held = held;
+
+    if let Some(active) = player.item_use.active {
+        let item = active.item;
+        let still_in_hand = action.hotbar_slot == active.slot
+            && player
+                .inventory
+                .get(active.slot)
+                .is_some_and(|stack| stack.item_id == item);
+        if !still_in_hand {
+            player.item_use.active = None;
+            return Some(ItemUseEffect::Cancelled { item });
+        }
+
+        let elapsed = now_ms.saturating_sub(active.started_ms);
+        return match active.kind {
+            ItemUseKind::Consume { duration_ms } => {
+                if !held {
+                    player.item_use.active = None;
+                    Some(ItemUseEffect::Cancelled { item })
+                } else if elapsed >= duration_ms {
+                    finish_use(player, item, now_ms);
+                    player.inventory.take_from_slot(active.slot, 1).ok()?;
+                    Some(ItemUseEffect::Used { item })
+                } else {
+                    None
+                }
+            }
+            ItemUseKind::Charge {
+                min_charge_ms,
+                full_charge_ms,
+            } => {
+                if held {
+                    return None;
+                }
+                player.item_use.active = None;
+                if elapsed < min_charge_ms {
+                    return Some(ItemUseEffect::Cancelled { item });
+                }
+                if let Some(ammo) = item.ammo() {
+                    if player.inventory.remove_exact(ammo, 1).is_err() {
+                        return Some(ItemUseEffect::Cancelled { item });
+                    }
+                }
+                finish_use(player, item, now_ms);
+                Some(ItemUseEffect::Released {
+                    item,
+                    charge: (elapsed as f32 / full_charge_ms as f32).min(1.0),
+                })
+            }
+            // Instant uses are never kept active
+            ItemUseKind::Instant => {
+                player.item_use.active = None;
+                None
+            }
+        };
+    }
+
+    if !just_pressed {
+        return None;
+    }
+    let item = player.inventory.get(action.hotbar_slot)?.item_id;
+    let kind = item.use_kind()?;
+    if player.item_use.is_on_cooldown(item, now_ms) {
+        return Some(ItemUseEffect::OnCooldown { item });
+    }
+
+    match kind {
+        ItemUseKind::Instant => {
+            player
+                .inventory
+                .take_from_slot(action.hotbar_slot, 1)
+                .ok()?;
+            finish_use(player, item, now_ms);
+            Some(ItemUseEffect::Used { item })
+        }
+        ItemUseKind::Charge { .. } | ItemUseKind::Consume { .. } => {
+            if item
+                .ammo()
+                .is_some_and(|ammo| player.inventory.count_of(ammo) == 0)
+            {
+                return None;
+            }
+            let active = ActiveItemUse {
+                slot: action.hotbar_slot,
+                item,
+                kind,
+                started_ms: now_ms,
+            };
+            player.item_use.active = Some(active);
+            Some(ItemUseEffect::Started(active))
+        }
+    }
+}
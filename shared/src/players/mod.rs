@@ -2,7 +2,16 @@ pub mod blocks;
 pub mod collision;
 pub mod constants;
 mod data;
+mod effects;
+mod health;
+mod inventory;
+pub mod item_use;
 pub mod movement;
 pub mod simulation;
+mod teams;
 
 pub use data::*;
+pub use effects::*;
+pub use health::*;
+pub use inventory::*;
+pub use teams::*;
@@ -1,12 +1,12 @@
 use crate::{
     messages::{NetworkAction, PlayerFrameInput},
     players::{
-        collision::check_player_collision,
+        collision::player_half_size,
         constants::{FLY_SPEED_MULTIPLIER, GRAVITY, JUMP_VELOCITY, MAX_VERTICAL_SPEED, SPEED},
     },
-    world::WorldMap,
+    world::{allowed_motion, collision_manifolds, SweepHit, WorldMap, COLLISION_SKIN},
 };
-use bevy::prelude::*;
+use bevy::{math::bounding::Aabb3d, prelude::*};
 
 use super::Player;
 
@@ -32,18 +32,20 @@ pub fn simulate_player_movement(
 
     let direction = get_desired_direction(player, action);
     let is_jumping = action.is_pressed(NetworkAction::JumpOrFlyUp);
+    let auto_jump = action.is_pressed(NetworkAction::AutoJump);
 
     if player.is_flying {
         fly(player, direction, delta_t);
     } else {
-        fly_not(player, is_jumping, direction, delta_t, world_map);
+        fly_not(player, is_jumping, auto_jump, direction, delta_t, world_map);
     }
 
     // If the player is below the world, reset their position
-    const FALL_LIMIT: f32 = -50.0;
-    if player.position.y < FALL_LIMIT {
+    const FALL_LIMIT: f32 = 50.0;
+    if player.position.y < (world_map.height_limits().min_y as f32) - FALL_LIMIT {
         player.position = Vec3::new(0.0, 100.0, 0.0);
         player.velocity.y = 0.0;
+        player.fell_into_void = true;
     }
 }
 
@@ -96,50 +98,62 @@ fn get_desired_direction(player: &mut Player, action: &PlayerFrameInput) -> Vec3
 
 fn fly(player: &mut Player, direction: Vec3, delta_t: f32) {
     player.position += direction * (SPEED * FLY_SPEED_MULTIPLIER * delta_t);
-    player.velocity.y = 0.0;
+    player.velocity = (direction * SPEED * FLY_SPEED_MULTIPLIER).with_y(0.0);
     player.on_ground = false;
 }
 
 fn fly_not(
     player: &mut Player,
     is_jumping: bool,
+    auto_jump: bool,
     direction: Vec3,
     delta_t: f32,
     world_map: &impl WorldMap,
 ) {
-    let delta_xz = SPEED * delta_t;
-
-    // Attempt to move the player in the desired direction horizontally
-    let new_x = player.position.x + direction.x * delta_xz;
-    let new_z = player.position.z + direction.z * delta_xz;
+    let delta_xz = SPEED * player.effects.speed_multiplier() * delta_t;
 
     player.velocity.y = player
         .velocity
         .y
-        .max(-MAX_VERTICAL_SPEED)
-        .min(MAX_VERTICAL_SPEED);
-    let new_y = player.position.y + player.velocity.y * delta_t;
+        .clamp(-MAX_VERTICAL_SPEED, MAX_VERTICAL_SPEED);
 
-    let new_vec_x = &player.position.with_x(new_x);
-    let new_vec_y = &player.position.with_y(new_y);
-    let new_vec_z = &player.position.with_z(new_z);
+    depenetrate(player, world_map);
 
-    // If a block is detected in the new position, don't move the player on this axis
-    if !check_player_collision(new_vec_x, player, world_map) {
-        player.position.x = new_x;
-    }
+    // Moves axis by axis up to the blocks in the way, so that the player slides along walls
+    // Horizontal velocity does not drive the movement, it is kept for whoever needs to anticipate it
+    let moved_x = move_player_along(player, Vec3::X * direction.x * delta_xz, world_map).0;
+    player.velocity.x = moved_x.x / delta_t.max(f32::EPSILON);
 
-    if check_player_collision(new_vec_y, player, world_map) {
-        player.on_ground = true;
-        player.velocity.y = 0.0;
+    // Resting players probe the ground right below them, to know whether they still stand on it
+    let motion_y = if player.velocity.y == 0.0 {
+        -2.0 * COLLISION_SKIN
     } else {
-        player.position.y = new_y;
-        player.on_ground = false;
-    }
-
-    if !check_player_collision(new_vec_z, player, world_map) {
-        player.position.z = new_z;
-    }
+        player.velocity.y * delta_t
+    };
+    let (_, hit_y) = move_player_along(player, Vec3::Y * motion_y, world_map);
+    match hit_y {
+        Some(hit) if hit.normal.y > 0.0 => {
+            if !player.on_ground {
+                player.landing_speed = -player.velocity.y;
+            }
+            player.on_ground = true;
+            player.velocity.y = 0.0;
+        }
+        Some(_) => {
+            // Bumped the head into a ceiling
+            player.on_ground = false;
+            player.velocity.y = 0.0;
+        }
+        None => {
+            player.on_ground = false;
+        }
+    }
+
+    let moved_z = move_player_along(player, Vec3::Z * direction.z * delta_xz, world_map).0;
+    player.velocity.z = moved_z.z / delta_t.max(f32::EPSILON);
+
+    let is_jumping = is_jumping
+        || (auto_jump && player.on_ground && has_step_ahead(player, direction, world_map));
 
     // TODO: short-hops
     // Handle jumping (if on the ground) and gravity, only if not flying
@@ -153,6 +167,35 @@ fn fly_not(
     }
 }
 
+/// How far in front of the player auto-jump looks for a step to climb
+const AUTO_JUMP_PROBE_DISTANCE: f32 = 0.3;
+
+/// Whether the player walks into a block one step high, with enough room above it to climb it
+fn has_step_ahead(player: &Player, direction: Vec3, world_map: &impl WorldMap) -> bool {
+    let horizontal = direction.with_y(0.0);
+    if horizontal == Vec3::ZERO {
+        return false;
+    }
+    let probe = horizontal.normalize() * AUTO_JUMP_PROBE_DISTANCE;
+    let half_size = player_half_size(player);
+
+    let hitbox = Aabb3d::new(player.position, half_size);
+    let blocked = allowed_motion(world_map, &hitbox, probe)
+        .1
+        .is_some_and(|hit| hit.normal.y == 0.0);
+    if !blocked {
+        return false;
+    }
+
+    // Checks the player can rise by a block, then go forward once up there
+    let rise = Vec3::Y * (1.0 + COLLISION_SKIN);
+    if allowed_motion(world_map, &hitbox, rise).1.is_some() {
+        return false;
+    }
+    let raised = Aabb3d::new(player.position + rise, half_size);
+    allowed_motion(world_map, &raised, probe).1.is_none()
+}
+
 trait IsPressed {
     fn is_pressed(&self, action: NetworkAction) -> bool;
 }
@@ -162,3 +205,31 @@ impl IsPressed for PlayerFrameInput {
         self.inputs.contains(&action)
     }
 }
+
+/// Moves the player along `motion`, stopping right before the first block in the way
+fn move_player_along(
+    player: &mut Player,
+    motion: Vec3,
+    world_map: &impl WorldMap,
+) -> (Vec3, Option<SweepHit>) {
+    if motion == Vec3::ZERO {
+        return (Vec3::ZERO, None);
+    }
+
+    let hitbox = Aabb3d::new(player.position, player_half_size(player));
+    let (allowed, hit) = allowed_motion(world_map, &hitbox, motion);
+    player.position += allowed;
+    (allowed, hit)
+}
+
+/// Pushes the player out of the blocks it overlaps, e.g. when a block was placed inside it
+fn depenetrate(player: &mut Player, world_map: &impl WorldMap) {
+    let hitbox = Aabb3d::new(player.position, player_half_size(player));
+    let deepest = collision_manifolds(world_map, &hitbox)
+        .into_iter()
+        .max_by(|a, b| a.penetration.total_cmp(&b.penetration));
+
+    if let Some(manifold) = deepest {
+        player.position += manifold.normal * (manifold.penetration + COLLISION_SKIN);
+    }
+}
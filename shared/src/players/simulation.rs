@@ -1,7 +1,7 @@
 use crate::{
     messages::PlayerFrameInput,
     players::{
-        blocks::{simulate_player_block_interactions, CallerType},
+        blocks::{simulate_player_block_interactions, BlockChange, CallerType},
         movement::simulate_player_movement,
         Player,
     },
@@ -13,7 +13,7 @@ pub fn simulate_player_actions(
     world_map: &mut impl WorldMap,
     action: &PlayerFrameInput,
     caller_type: CallerType,
-) {
+) -> Option<BlockChange> {
     // if !action.inputs.is_empty() {
     // debug!(
     //     "Simulating player actions for player {} -> {:?}",
@@ -26,6 +26,7 @@ pub fn simulate_player_actions(
     // debug!("Player position before = {:?}", player.position);
     // debug!("Player view mode = {:?}", action.view_mode);
 
-    simulate_player_block_interactions(player, world_map, action, caller_type);
+    let change = simulate_player_block_interactions(player, world_map, action, caller_type);
     simulate_player_movement(player, world_map, action);
+    change
 }
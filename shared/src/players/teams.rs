@@ -0,0 +1,249 @@
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::messages::PlayerId;
+
+pub const MAX_TEAM_NAME_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TeamColor {
+    #[default]
+    White,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Aqua,
+    Blue,
+    Purple,
+    Pink,
+    Gray,
+}
+
+impl TeamColor {
+    pub const ALL: [TeamColor; 10] = [
+        TeamColor::White,
+        TeamColor::Red,
+        TeamColor::Orange,
+        TeamColor::Yellow,
+        TeamColor::Green,
+        TeamColor::Aqua,
+        TeamColor::Blue,
+        TeamColor::Purple,
+        TeamColor::Pink,
+        TeamColor::Gray,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|color| color.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TeamColor::White => "white",
+            TeamColor::Red => "red",
+            TeamColor::Orange => "orange",
+            TeamColor::Yellow => "yellow",
+            TeamColor::Green => "green",
+            TeamColor::Aqua => "aqua",
+            TeamColor::Blue => "blue",
+            TeamColor::Purple => "purple",
+            TeamColor::Pink => "pink",
+            TeamColor::Gray => "gray",
+        }
+    }
+
+    /// sRGB components, used for name tags and the player list
+    #[cfg(feature = "client")]
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            TeamColor::White => [255, 255, 255],
+            TeamColor::Red => [230, 50, 50],
+            TeamColor::Orange => [255, 150, 30],
+            TeamColor::Yellow => [250, 220, 60],
+            TeamColor::Green => [70, 200, 70],
+            TeamColor::Aqua => [60, 210, 220],
+            TeamColor::Blue => [70, 110, 240],
+            TeamColor::Purple => [160, 80, 220],
+            TeamColor::Pink => [240, 120, 190],
+            TeamColor::Gray => [150, 150, 150],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Team {
+    pub name: String,
+    pub color: TeamColor,
+    pub members: Vec<PlayerId>,
+    /// Whether members can hurt each other
+    pub friendly_fire: bool,
+    /// Whether members can edit the chunks claimed by other members
+    pub share_claims: bool,
+    /// Players a member invited, only they can join the team
+    #[serde(default)]
+    pub invited: Vec<PlayerId>,
+}
+
+impl Team {
+    pub fn new(name: String, color: TeamColor) -> Self {
+        Self {
+            name,
+            color,
+            members: Vec::new(),
+            friendly_fire: false,
+            share_claims: true,
+            invited: Vec::new(),
+        }
+    }
+}
+
+/// Teams of the world, indexed by their lowercase name.
+/// Owned by the server world map and replicated as is to the clients.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, Resource)]
+pub struct Teams {
+    pub teams: HashMap<String, Team>,
+}
+
+impl Teams {
+    pub fn get(&self, name: &str) -> Option<&Team> {
+        self.teams.get(&name.to_lowercase())
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Team> {
+        self.teams.get_mut(&name.to_lowercase())
+    }
+
+    pub fn get_player_team(&self, player_id: PlayerId) -> Option<&Team> {
+        self.teams
+            .values()
+            .find(|team| team.members.contains(&player_id))
+    }
+
+    pub fn get_player_team_mut(&mut self, player_id: PlayerId) -> Option<&mut Team> {
+        self.teams
+            .values_mut()
+            .find(|team| team.members.contains(&player_id))
+    }
+
+    /// Creates an empty team, returns false if the name is already taken
+    pub fn create(&mut self, name: &str, color: TeamColor) -> bool {
+        let key = name.to_lowercase();
+        if self.teams.contains_key(&key) {
+            return false;
+        }
+        self.teams.insert(key, Team::new(name.to_string(), color));
+        true
+    }
+
+    /// Moves a player to a team, leaving their previous one. Returns false if the team does not exist.
+    pub fn join(&mut self, name: &str, player_id: PlayerId) -> bool {
+        if self.get(name).is_none() {
+            return false;
+        }
+        self.leave(player_id);
+        if let Some(team) = self.get_mut(name) {
+            team.members.push(player_id);
+        }
+        true
+    }
+
+    /// Lets a player join a team. Returns false if the team does not exist or the player already is a member.
+    pub fn invite(&mut self, name: &str, player_id: PlayerId) -> bool {
+        let Some(team) = self.get_mut(name) else {
+            return false;
+        };
+        if team.members.contains(&player_id) {
+            return false;
+        }
+        if !team.invited.contains(&player_id) {
+            team.invited.push(player_id);
+        }
+        true
+    }
+
+    /// Moves a player to a team which invited them, the invitation is used up.
+    /// Returns false if the team does not exist or did not invite the player.
+    pub fn accept(&mut self, name: &str, player_id: PlayerId) -> bool {
+        let Some(team) = self.get_mut(name) else {
+            return false;
+        };
+        let Some(index) = team.invited.iter().position(|id| *id == player_id) else {
+            return false;
+        };
+        team.invited.remove(index);
+        self.join(name, player_id)
+    }
+
+    /// Removes a player from their team, empty teams are deleted.
+    /// Returns the name of the team that was left.
+    pub fn leave(&mut self, player_id: PlayerId) -> Option<String> {
+        let team = self.get_player_team_mut(player_id)?;
+        team.members.retain(|id| *id != player_id);
+        let name = team.name.clone();
+        if team.members.is_empty() {
+            self.teams.remove(&name.to_lowercase());
+        }
+        Some(name)
+    }
+
+    pub fn are_teammates(&self, a: PlayerId, b: PlayerId) -> bool {
+        self.get_player_team(a)
+            .is_some_and(|team| team.members.contains(&b))
+    }
+
+    pub fn can_damage(&self, attacker: PlayerId, victim: PlayerId) -> bool {
+        match self.get_player_team(attacker) {
+            Some(team) if team.members.contains(&victim) => team.friendly_fire,
+            _ => true,
+        }
+    }
+
+    /// Whether `player_id` may edit a chunk claimed by `owner`
+    pub fn shares_claims(&self, owner: PlayerId, player_id: PlayerId) -> bool {
+        match self.get_player_team(owner) {
+            Some(team) => team.share_claims && team.members.contains(&player_id),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn teams_with(name: &str, owner: PlayerId) -> Teams {
+        let mut teams = Teams::default();
+        assert!(teams.create(name, TeamColor::Red));
+        assert!(teams.join(name, owner));
+        teams
+    }
+
+    #[test]
+    fn only_invited_players_can_join() {
+        let mut teams = teams_with("Miners", 1);
+        assert!(!teams.accept("miners", 2));
+        assert!(!teams.are_teammates(1, 2));
+
+        assert!(teams.invite("Miners", 2));
+        assert!(teams.accept("miners", 2));
+        assert!(teams.are_teammates(1, 2));
+        assert!(teams.get("Miners").unwrap().invited.is_empty());
+    }
+
+    #[test]
+    fn invitations_are_used_once() {
+        let mut teams = teams_with("Miners", 1);
+        assert!(teams.invite("Miners", 2));
+        assert!(teams.accept("Miners", 2));
+
+        // Leaving the team does not keep the way back open
+        teams.leave(2);
+        assert!(!teams.accept("Miners", 2));
+        assert!(!teams.invite("Miners", 1));
+        assert!(!teams.invite("Builders", 2));
+    }
+}
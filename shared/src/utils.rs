@@ -1,5 +1,13 @@
 use bevy::prelude::*;
 
+/// Milliseconds since the UNIX epoch on this machine, which may disagree with the clock of the other side
+pub fn unix_time_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 pub fn format_bytes(bytes: u64) -> String {
     const UNITS: [&str; 5] = ["bytes", "kB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use bevy::math::IVec3;
+use serde::{Deserialize, Serialize};
+
+/// Blocks waiting to be updated at a given server tick, like fires burning out.
+/// Saved with the world so that the pending updates survive a restart.
+#[derive(Default, Clone, Serialize, Deserialize, Debug)]
+pub struct BlockTickScheduler {
+    ticks: BTreeMap<u64, Vec<IVec3>>,
+}
+
+impl BlockTickScheduler {
+    pub fn schedule(&mut self, position: IVec3, tick: u64) {
+        self.ticks.entry(tick).or_default().push(position);
+    }
+
+    /// Takes the blocks due at or before `now`, oldest first
+    pub fn take_due(&mut self, now: u64) -> Vec<IVec3> {
+        let later = self.ticks.split_off(&(now + 1));
+        let due = std::mem::replace(&mut self.ticks, later);
+        due.into_values().flatten().collect()
+    }
+
+    /// First tick an update of the block at `position` is due, if one is scheduled
+    pub fn next_tick_of(&self, position: &IVec3) -> Option<u64> {
+        self.ticks
+            .iter()
+            .find(|(_, positions)| positions.contains(position))
+            .map(|(tick, _)| *tick)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+}
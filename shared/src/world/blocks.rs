@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use crate::HALF_BLOCK;
 
 use super::{GameElementId, ItemId};
@@ -42,9 +40,19 @@ pub enum BlockId {
     SpruceLeaves,
     SpruceLog,
     Water,
+    CoalOre,
+    IronOre,
+    GoldOre,
+    DiamondOre,
+    Chest,
+    Fire,
+    SnowLayer,
+    StructureVoid,
+    Barrier,
+    Light,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum BlockDirection {
     Front,
     Right,
@@ -52,12 +60,22 @@ pub enum BlockDirection {
     Left,
 }
 
+/// Layers stacked in a layered block as tall as a full block
+pub const LAYERS_PER_BLOCK: u8 = 8;
+
+/// Brightest level a light block can be set to
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+
 /// Data associated with a given `BlockId`
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct BlockData {
     pub id: BlockId,
     pub direction: BlockDirection,
     pub breaking_progress: u8,
+    /// Height of layered blocks like snow layers, in `LAYERS_PER_BLOCK`ths of a block,
+    /// or light level of light blocks. Unused by the other blocks
+    #[serde(default)]
+    pub layers: u8,
 }
 
 impl BlockData {
@@ -66,12 +84,78 @@ impl BlockData {
             id,
             direction,
             breaking_progress: 0,
+            layers: 0,
+        }
+    }
+
+    /// A layered block with `layers` layers, kept between 1 and `LAYERS_PER_BLOCK`
+    pub fn layered(id: BlockId, layers: u8) -> Self {
+        BlockData {
+            layers: layers.clamp(1, LAYERS_PER_BLOCK),
+            ..Self::new(id, BlockDirection::Front)
+        }
+    }
+
+    /// A light block shining at `level`, kept between 1 and `MAX_LIGHT_LEVEL`
+    pub fn light(level: u8) -> Self {
+        BlockData {
+            layers: level.clamp(1, MAX_LIGHT_LEVEL),
+            ..Self::new(BlockId::Light, BlockDirection::Front)
+        }
+    }
+
+    /// Light given off by the block, from 0 to `MAX_LIGHT_LEVEL`
+    pub fn light_level(&self) -> u8 {
+        match self.id {
+            BlockId::Light => self.layers.min(MAX_LIGHT_LEVEL),
+            BlockId::Fire => MAX_LIGHT_LEVEL,
+            _ => 0,
         }
     }
 
+    #[cfg(feature = "client")]
     pub fn get_breaking_level(&self) -> u8 {
         ((self.breaking_progress as u16 * 10) / self.id.get_break_time() as u16) as u8
     }
+
+    /// Height of the block from its bottom, between 0 and 1
+    pub fn height(&self) -> f32 {
+        if self.id.is_layered() {
+            self.layers as f32 / LAYERS_PER_BLOCK as f32
+        } else {
+            1.0
+        }
+    }
+
+    /// Collision box of the block, layered blocks are one layer lower than they look so that a single layer can be walked over
+    pub fn get_hitbox(&self) -> BlockHitbox {
+        if !self.id.is_layered() {
+            return self.id.get_hitbox();
+        }
+        let height = self.layers.saturating_sub(1) as f32 / LAYERS_PER_BLOCK as f32;
+        layer_hitbox(height)
+    }
+
+    pub fn get_ray_hitbox(&self) -> BlockHitbox {
+        if !self.id.is_layered() {
+            return self.id.get_ray_hitbox();
+        }
+        layer_hitbox(self.height())
+    }
+}
+
+/// Box covering the bottom of a block up to `height`
+fn layer_hitbox(height: f32) -> BlockHitbox {
+    if height <= 0.0 {
+        return BlockHitbox::None;
+    }
+    if height >= 1.0 {
+        return BlockHitbox::FullBlock;
+    }
+    BlockHitbox::Aabb(Aabb3d::new(
+        Vec3A::new(0.5, height / 2.0, 0.5),
+        Vec3A::new(0.5, height / 2.0, 0.5),
+    ))
 }
 
 pub enum BlockTags {
@@ -79,12 +163,15 @@ pub enum BlockTags {
     Stone,
 }
 
+#[cfg(feature = "client")]
 #[derive(PartialEq, Eq, Debug)]
 pub enum BlockTransparency {
     Transparent,
     Liquid,
     Solid,
     Decoration,
+    /// Technical blocks which are never meshed
+    Invisible,
 }
 
 pub enum BlockHitbox {
@@ -96,22 +183,80 @@ pub enum BlockHitbox {
 impl BlockId {
     pub fn get_hitbox(&self) -> BlockHitbox {
         match *self {
-            Self::Water | Self::TallGrass | Self::Poppy | Self::Dandelion => BlockHitbox::None,
+            Self::Water
+            | Self::TallGrass
+            | Self::Poppy
+            | Self::Dandelion
+            | Self::Fire
+            | Self::SnowLayer
+            | Self::StructureVoid
+            | Self::Light => BlockHitbox::None,
             _ => BlockHitbox::FullBlock,
         }
     }
 
+    /// Blocks whose height is given by the layers of their `BlockData`
+    pub fn is_layered(&self) -> bool {
+        matches!(*self, Self::SnowLayer)
+    }
+
+    /// Blocks giving off light, which melts the snow around them
+    pub fn is_light_source(&self) -> bool {
+        matches!(*self, Self::Fire | Self::Light)
+    }
+
+    /// Technical blocks which only players in creative mode may place
+    pub fn is_creative_only(&self) -> bool {
+        matches!(*self, Self::StructureVoid | Self::Barrier | Self::Light)
+    }
+
     pub fn get_ray_hitbox(&self) -> BlockHitbox {
         match *self {
             Self::Water => BlockHitbox::None,
-            Self::TallGrass | Self::Poppy | Self::Dandelion => BlockHitbox::Aabb(Aabb3d::new(
-                Vec3A::splat(0.5).with_y(0.3),
-                Vec3A::splat(0.3),
-            )),
+            Self::TallGrass | Self::Poppy | Self::Dandelion | Self::Fire => BlockHitbox::Aabb(
+                Aabb3d::new(Vec3A::splat(0.5).with_y(0.3), Vec3A::splat(0.3)),
+            ),
             _ => BlockHitbox::FullBlock,
         }
     }
 
+    /// Color of the block seen from above on top-down maps
+    pub fn map_color(&self) -> [u8; 3] {
+        match *self {
+            Self::Dirt => [134, 96, 67],
+            Self::Debug => [255, 0, 255],
+            Self::Grass => [95, 159, 53],
+            Self::Stone => [125, 125, 125],
+            Self::OakLog => [102, 81, 51],
+            Self::OakPlanks => [162, 130, 78],
+            Self::OakLeaves => [60, 120, 30],
+            Self::Sand => [219, 207, 163],
+            Self::Cactus => [85, 130, 50],
+            Self::Ice => [160, 190, 250],
+            Self::Glass => [200, 220, 230],
+            Self::Bedrock => [50, 50, 50],
+            Self::Dandelion => [240, 220, 40],
+            Self::Poppy => [200, 40, 30],
+            Self::TallGrass => [100, 165, 60],
+            Self::Cobblestone => [110, 110, 110],
+            Self::Snow => [245, 250, 250],
+            Self::SpruceLeaves => [45, 85, 45],
+            Self::SpruceLog => [75, 55, 35],
+            Self::Water => [50, 90, 200],
+            Self::CoalOre => [90, 90, 90],
+            Self::IronOre => [150, 130, 115],
+            Self::GoldOre => [170, 150, 80],
+            Self::DiamondOre => [110, 160, 160],
+            Self::Chest => [150, 105, 50],
+            Self::Fire => [230, 120, 20],
+            Self::SnowLayer => [245, 250, 250],
+            Self::StructureVoid => [0, 0, 0],
+            Self::Barrier => [0, 0, 0],
+            Self::Light => [0, 0, 0],
+        }
+    }
+
+    #[cfg(feature = "client")]
     pub fn is_biome_colored() -> bool {
         false
     }
@@ -136,10 +281,19 @@ impl BlockId {
             Self::Snow => 9,
             Self::SpruceLeaves => 2,
             Self::SpruceLog => 10,
+            Self::CoalOre => 12,
+            Self::IronOre => 15,
+            Self::GoldOre => 15,
+            Self::DiamondOre => 18,
+            Self::Chest => 10,
+            Self::Fire => 1,
+            Self::SnowLayer => 1,
+            Self::StructureVoid | Self::Barrier | Self::Light => 1,
             _ => 100,
         }
     }
 
+    #[cfg(feature = "client")]
     pub fn get_color(&self) -> [f32; 4] {
         match *self {
             Self::Grass => [0.1, 1.0, 0.25, 1.],
@@ -147,8 +301,9 @@ impl BlockId {
         }
     }
 
-    pub fn get_drops(&self, nb_drops: u32) -> HashMap<ItemId, u32> {
-        let mut drops = HashMap::new();
+    /// Items dropped and their number, in the order of the drop table so that the same draws give the same drops
+    pub fn get_drops(&self, nb_drops: u32, rng: &mut impl Rng) -> Vec<(ItemId, u32)> {
+        let mut drops: Vec<(ItemId, u32)> = Vec::new();
         let table = self.get_drop_table();
 
         if table.is_empty() {
@@ -164,10 +319,13 @@ impl BlockId {
 
         // Choose drop items
         for _ in 0..nb_drops {
-            let mut nb = rand::thread_rng().gen_range(0..total);
+            let mut nb = rng.gen_range(0..total);
             for item in table.iter() {
                 if nb < item.0 {
-                    drops.insert(item.1, *drops.get(&item.1).unwrap_or(&0) + item.2);
+                    match drops.iter_mut().find(|(id, _)| *id == item.1) {
+                        Some((_, count)) => *count += item.2,
+                        None => drops.push((item.1, item.2)),
+                    }
                 } else {
                     nb -= item.0;
                 }
@@ -181,7 +339,7 @@ impl BlockId {
     pub fn get_drop_table(&self) -> Vec<(u32, ItemId, u32)> {
         match *self {
             BlockId::Dirt | BlockId::Grass => vec![(1, ItemId::Dirt, 1)],
-            BlockId::Stone => vec![(1, ItemId::Cobblestone, 1)],
+            BlockId::Stone | BlockId::Cobblestone => vec![(1, ItemId::Cobblestone, 1)],
             BlockId::Sand => vec![(1, ItemId::Sand, 1)],
             BlockId::Cactus => vec![(1, ItemId::Cactus, 1)],
             BlockId::OakLog => vec![(1, ItemId::OakLog, 1)],
@@ -192,11 +350,47 @@ impl BlockId {
             BlockId::TallGrass => vec![(1, ItemId::TallGrass, 1)],
             BlockId::SpruceLog => vec![(1, ItemId::SpruceLog, 1)],
             BlockId::Snow => vec![(1, ItemId::Snowball, 4)],
+            BlockId::SnowLayer => vec![(1, ItemId::Snowball, 1)],
+            BlockId::CoalOre => vec![(1, ItemId::Coal, 1)],
+            BlockId::IronOre => vec![(1, ItemId::IronOre, 1)],
+            BlockId::GoldOre => vec![(1, ItemId::GoldOre, 1)],
+            BlockId::DiamondOre => vec![(1, ItemId::Diamond, 1)],
+            BlockId::Chest => vec![(1, ItemId::Chest, 1)],
             BlockId::Water => vec![],
             _ => vec![],
         }
     }
 
+    /// Chance out of 100 for a fire next to the block to spread to it, 0 for blocks which do not burn
+    pub fn ignite_odds(&self) -> u8 {
+        match *self {
+            Self::OakPlanks | Self::OakLog | Self::SpruceLog => 5,
+            Self::OakLeaves | Self::SpruceLeaves => 30,
+            Self::TallGrass | Self::Dandelion | Self::Poppy => 60,
+            _ => 0,
+        }
+    }
+
+    /// Chance out of 100 for a fire next to the block to destroy it
+    pub fn burn_odds(&self) -> u8 {
+        match *self {
+            Self::OakPlanks => 20,
+            Self::OakLog | Self::SpruceLog => 5,
+            Self::OakLeaves | Self::SpruceLeaves => 60,
+            Self::TallGrass | Self::Dandelion | Self::Poppy => 100,
+            _ => 0,
+        }
+    }
+
+    pub fn is_flammable(&self) -> bool {
+        self.ignite_odds() > 0
+    }
+
+    /// Blocks holding items, stored in the containers of the world
+    pub fn is_container(&self) -> bool {
+        matches!(*self, Self::Chest)
+    }
+
     pub fn get_tags(&self) -> Vec<BlockTags> {
         match *self {
             BlockId::Stone => vec![BlockTags::Stone, BlockTags::Solid],
@@ -204,11 +398,15 @@ impl BlockId {
         }
     }
 
+    #[cfg(feature = "client")]
     pub fn get_visibility(&self) -> BlockTransparency {
         match *self {
-            Self::Dandelion | Self::Poppy | Self::TallGrass => BlockTransparency::Decoration,
+            Self::Dandelion | Self::Poppy | Self::TallGrass | Self::Fire | Self::SnowLayer => {
+                BlockTransparency::Decoration
+            }
             Self::Glass | Self::OakLeaves | Self::SpruceLeaves => BlockTransparency::Transparent,
             Self::Water => BlockTransparency::Liquid,
+            Self::StructureVoid | Self::Barrier | Self::Light => BlockTransparency::Invisible,
             _ => BlockTransparency::Solid,
         }
     }
@@ -216,7 +414,7 @@ impl BlockId {
     pub fn get_interaction_box(&self, position: &IVec3) -> Aabb3d {
         let pos = Vec3::new(position.x as f32, position.y as f32, position.z as f32);
         match *self {
-            Self::Dandelion | Self::Poppy | Self::TallGrass => {
+            Self::Dandelion | Self::Poppy | Self::TallGrass | Self::Fire => {
                 Aabb3d::new(pos - Vec3::new(0f32, 0.25, 0f32), HALF_BLOCK / 2.0)
             }
             _ => Aabb3d::new(pos, HALF_BLOCK),
@@ -0,0 +1,181 @@
+use bevy::math::{bounding::Aabb3d, IVec3, Vec3, Vec3A};
+
+use super::{aabb_block_range, positions_in_region, BlockHitbox, WorldMap};
+
+/// Distance kept between a moving box and the blocks it hits, so that it does not end up touching them
+pub const COLLISION_SKIN: f32 = 1e-3;
+
+/// Describes how a box overlaps one block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionManifold {
+    /// Position of the block being overlapped
+    pub block: IVec3,
+    /// Face of the block to push the box through to separate them, the shortest way out
+    pub normal: Vec3,
+    /// Distance to move the box along the normal to separate them
+    pub penetration: f32,
+}
+
+/// First block met by a box moving along some motion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepHit {
+    pub block: IVec3,
+    /// Face of the block which was hit
+    pub normal: Vec3,
+    /// Fraction of the motion done before touching the block, between 0 and 1
+    pub time: f32,
+}
+
+/// Hitbox of the block at `pos` in world coordinates, if it has one
+pub fn block_collision_box(world_map: &(impl WorldMap + ?Sized), pos: IVec3) -> Option<Aabb3d> {
+    let block = world_map.get_block_by_coordinates(&pos)?;
+    let offset = Vec3A::from(pos.as_vec3());
+    match block.get_hitbox() {
+        BlockHitbox::FullBlock => Some(Aabb3d {
+            min: offset,
+            max: offset + Vec3A::ONE,
+        }),
+        BlockHitbox::Aabb(hitbox) => Some(Aabb3d {
+            min: offset + hitbox.min,
+            max: offset + hitbox.max,
+        }),
+        BlockHitbox::None => None,
+    }
+}
+
+/// Positions of all the blocks the box touches
+fn blocks_in_box(hitbox: &Aabb3d) -> impl Iterator<Item = IVec3> {
+    let (min, max) = aabb_block_range(hitbox);
+    positions_in_region(min, max)
+}
+
+fn axis_normal(axis: usize, sign: f32) -> Vec3 {
+    let mut normal = Vec3::ZERO;
+    normal[axis] = sign;
+    normal
+}
+
+/// Lists the blocks overlapped by the box, with the shortest way out of each of them
+pub fn collision_manifolds(
+    world_map: &(impl WorldMap + ?Sized),
+    hitbox: &Aabb3d,
+) -> Vec<CollisionManifold> {
+    let mut manifolds = Vec::new();
+
+    for pos in blocks_in_box(hitbox) {
+        let Some(block_box) = block_collision_box(world_map, pos) else {
+            continue;
+        };
+
+        let mut best: Option<CollisionManifold> = None;
+        for axis in 0..3 {
+            // Distance to push the box towards +axis and -axis to get out of the block
+            let positive = block_box.max[axis] - hitbox.min[axis];
+            let negative = hitbox.max[axis] - block_box.min[axis];
+            if positive <= 0.0 || negative <= 0.0 {
+                best = None;
+                break;
+            }
+
+            let (penetration, sign) = if positive < negative {
+                (positive, 1.0)
+            } else {
+                (negative, -1.0)
+            };
+            if best.is_none_or(|best| penetration < best.penetration) {
+                best = Some(CollisionManifold {
+                    block: pos,
+                    normal: axis_normal(axis, sign),
+                    penetration,
+                });
+            }
+        }
+
+        manifolds.extend(best);
+    }
+
+    manifolds
+}
+
+/// Time and face at which the moving box enters the target box, if it does during the motion
+fn sweep_against(moving: &Aabb3d, motion: Vec3, target: &Aabb3d) -> Option<(f32, Vec3)> {
+    let mut entry = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vec3::ZERO;
+
+    for axis in 0..3 {
+        let velocity = motion[axis];
+        if velocity == 0.0 {
+            if moving.max[axis] <= target.min[axis] || moving.min[axis] >= target.max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let (enter_distance, exit_distance) = if velocity > 0.0 {
+            (
+                target.min[axis] - moving.max[axis],
+                target.max[axis] - moving.min[axis],
+            )
+        } else {
+            (
+                target.max[axis] - moving.min[axis],
+                target.min[axis] - moving.max[axis],
+            )
+        };
+
+        let axis_entry = enter_distance / velocity;
+        if axis_entry > entry {
+            entry = axis_entry;
+            normal = axis_normal(axis, -velocity.signum());
+        }
+        exit = exit.min(exit_distance / velocity);
+    }
+
+    // Boxes which already overlap are not hit by the motion, their manifolds tell how to separate them
+    if entry > exit || !(0.0..=1.0).contains(&entry) {
+        return None;
+    }
+    Some((entry, normal))
+}
+
+/// Moves the box along `motion` and returns the first block it runs into
+pub fn sweep_aabb(
+    world_map: &(impl WorldMap + ?Sized),
+    hitbox: &Aabb3d,
+    motion: Vec3,
+) -> Option<SweepHit> {
+    let motion_a = Vec3A::from(motion);
+    let swept = Aabb3d {
+        min: hitbox.min.min(hitbox.min + motion_a),
+        max: hitbox.max.max(hitbox.max + motion_a),
+    };
+
+    blocks_in_box(&swept)
+        .filter_map(|pos| {
+            let block_box = block_collision_box(world_map, pos)?;
+            let (time, normal) = sweep_against(hitbox, motion, &block_box)?;
+            Some(SweepHit {
+                block: pos,
+                normal,
+                time,
+            })
+        })
+        .min_by(|a, b| a.time.total_cmp(&b.time))
+}
+
+/// How far the box can go along `motion` before touching a block, keeping a small skin between them
+pub fn allowed_motion(
+    world_map: &(impl WorldMap + ?Sized),
+    hitbox: &Aabb3d,
+    motion: Vec3,
+) -> (Vec3, Option<SweepHit>) {
+    match sweep_aabb(world_map, hitbox, motion) {
+        Some(hit) => {
+            let length = motion.length();
+            let travelled = (hit.time * length - COLLISION_SKIN).max(0.0);
+            (motion.normalize_or_zero() * travelled, Some(hit))
+        }
+        None => (motion, None),
+    }
+}
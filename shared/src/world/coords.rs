@@ -0,0 +1,201 @@
+//! Conversions between world positions, block positions and chunk indices.
+//! Every conversion rounds towards negative infinity, so that blocks at negative coordinates
+//! belong to the chunk below them and not to chunk 0.
+
+use bevy::math::{bounding::Aabb3d, IVec3, Vec3};
+
+use crate::CHUNK_SIZE;
+
+/// Index of the chunk holding the block coordinate, along one axis
+pub fn block_to_chunk_coord(x: i32) -> i32 {
+    x.div_euclid(CHUNK_SIZE)
+}
+
+/// Offset of the block coordinate inside its chunk, along one axis, in `0..CHUNK_SIZE`
+pub fn block_to_local_coord(x: i32) -> i32 {
+    x.rem_euclid(CHUNK_SIZE)
+}
+
+pub fn global_block_to_chunk_pos(global_block_pos: &IVec3) -> IVec3 {
+    IVec3::new(
+        block_to_chunk_coord(global_block_pos.x),
+        block_to_chunk_coord(global_block_pos.y),
+        block_to_chunk_coord(global_block_pos.z),
+    )
+}
+
+pub fn global_block_to_local_offset(global_block_pos: &IVec3) -> IVec3 {
+    IVec3::new(
+        block_to_local_coord(global_block_pos.x),
+        block_to_local_coord(global_block_pos.y),
+        block_to_local_coord(global_block_pos.z),
+    )
+}
+
+pub fn chunk_offset_to_global_pos(chunk_pos: &IVec3, local_block_offset: &IVec3) -> IVec3 {
+    *chunk_pos * CHUNK_SIZE + *local_block_offset
+}
+
+/// Block containing a point of the world
+pub fn world_position_to_block_position(v: Vec3) -> IVec3 {
+    v.floor().as_ivec3()
+}
+
+// TODO: rename chunk_pos(ition) to chunk_index
+pub fn world_position_to_chunk_position(v: Vec3) -> IVec3 {
+    global_block_to_chunk_pos(&world_position_to_block_position(v))
+}
+
+pub fn block_vec3_to_chunk_v3_coord(v: Vec3) -> Vec3 {
+    world_position_to_chunk_position(v).as_vec3()
+}
+
+pub fn chunk_index_to_world_position(chunk_index: &IVec3) -> Vec3 {
+    chunk_index.as_vec3() * CHUNK_SIZE as f32
+}
+
+/// Iterates over the positions of an inclusive box, x first then y then z
+pub fn positions_in_region(min: IVec3, max: IVec3) -> impl Iterator<Item = IVec3> {
+    (min.x..=max.x).flat_map(move |x| {
+        (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| IVec3::new(x, y, z)))
+    })
+}
+
+/// Inclusive range of the blocks a box touches
+pub fn aabb_block_range(aabb: &Aabb3d) -> (IVec3, IVec3) {
+    (
+        world_position_to_block_position(aabb.min.into()),
+        world_position_to_block_position(aabb.max.into()),
+    )
+}
+
+/// Inclusive range of the chunks a box touches
+pub fn aabb_chunk_range(aabb: &Aabb3d) -> (IVec3, IVec3) {
+    (
+        world_position_to_chunk_position(aabb.min.into()),
+        world_position_to_chunk_position(aabb.max.into()),
+    )
+}
+
+/// Inclusive range of the chunks holding the blocks of an inclusive region
+pub fn region_chunk_range(min: IVec3, max: IVec3) -> (IVec3, IVec3) {
+    (
+        global_block_to_chunk_pos(&min),
+        global_block_to_chunk_pos(&max),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn block_pos() -> impl Strategy<Value = IVec3> {
+        any::<[i32; 3]>().prop_map(IVec3::from_array)
+    }
+
+    /// Every chunk whose blocks all fit in an `i32`
+    fn chunk_and_local_pos() -> impl Strategy<Value = (IVec3, IVec3)> {
+        let chunk = block_to_chunk_coord(i32::MIN)..=block_to_chunk_coord(i32::MAX);
+        let local = 0..CHUNK_SIZE;
+        (
+            (chunk.clone(), chunk.clone(), chunk),
+            (local.clone(), local.clone(), local),
+        )
+            .prop_map(|((cx, cy, cz), (lx, ly, lz))| {
+                (IVec3::new(cx, cy, cz), IVec3::new(lx, ly, lz))
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn block_splits_into_chunk_and_local_and_back(block in block_pos()) {
+            let chunk = global_block_to_chunk_pos(&block);
+            let local = global_block_to_local_offset(&block);
+            prop_assert!(local.cmpge(IVec3::ZERO).all() && local.cmplt(IVec3::splat(CHUNK_SIZE)).all());
+            prop_assert_eq!(chunk_offset_to_global_pos(&chunk, &local), block);
+        }
+
+        #[test]
+        fn chunk_and_local_join_into_block_and_back((chunk, local) in chunk_and_local_pos()) {
+            let block = chunk_offset_to_global_pos(&chunk, &local);
+            prop_assert_eq!(global_block_to_chunk_pos(&block), chunk);
+            prop_assert_eq!(global_block_to_local_offset(&block), local);
+        }
+
+        #[test]
+        fn world_position_lies_in_its_block_and_chunk(
+            x in -100_000.0f32..100_000.0,
+            y in -100_000.0f32..100_000.0,
+            z in -100_000.0f32..100_000.0,
+        ) {
+            let position = Vec3::new(x, y, z);
+            let block = world_position_to_block_position(position);
+            prop_assert!(block.as_vec3().cmple(position).all());
+            prop_assert!((block + IVec3::ONE).as_vec3().cmpgt(position).all());
+
+            let chunk = world_position_to_chunk_position(position);
+            prop_assert_eq!(chunk, global_block_to_chunk_pos(&block));
+            let chunk_origin = chunk_index_to_world_position(&chunk);
+            prop_assert!(chunk_origin.cmple(position).all());
+            prop_assert!((chunk_origin + CHUNK_SIZE as f32).cmpgt(position).all());
+        }
+    }
+
+    #[test]
+    fn conversions_hold_at_the_ends_of_the_i32_range() {
+        let min_chunk = IVec3::splat(i32::MIN / CHUNK_SIZE);
+        let max_chunk = IVec3::splat(i32::MAX / CHUNK_SIZE);
+
+        for block in [IVec3::MIN, IVec3::MAX, IVec3::new(i32::MIN, 0, i32::MAX)] {
+            let chunk = global_block_to_chunk_pos(&block);
+            let local = global_block_to_local_offset(&block);
+            assert_eq!(chunk_offset_to_global_pos(&chunk, &local), block);
+        }
+        assert_eq!(global_block_to_chunk_pos(&IVec3::MIN), min_chunk);
+        assert_eq!(global_block_to_local_offset(&IVec3::MIN), IVec3::ZERO);
+        assert_eq!(global_block_to_chunk_pos(&IVec3::MAX), max_chunk);
+        assert_eq!(
+            global_block_to_local_offset(&IVec3::MAX),
+            IVec3::splat(CHUNK_SIZE - 1)
+        );
+
+        assert_eq!(
+            region_chunk_range(IVec3::MIN, IVec3::MAX),
+            (min_chunk, max_chunk)
+        );
+        assert_eq!(
+            positions_in_region(IVec3::MAX, IVec3::MAX).collect::<Vec<_>>(),
+            vec![IVec3::MAX]
+        );
+        assert_eq!(
+            positions_in_region(IVec3::MIN, IVec3::MIN).collect::<Vec<_>>(),
+            vec![IVec3::MIN]
+        );
+
+        // Floats are exact this far out only for multiples of 128
+        let aabb = Aabb3d::new(Vec3::splat(i32::MIN as f32 + 128.0), Vec3::splat(128.0));
+        assert_eq!(
+            aabb_block_range(&aabb),
+            (IVec3::MIN, IVec3::splat(i32::MIN + 256))
+        );
+        assert_eq!(
+            aabb_chunk_range(&aabb),
+            (min_chunk, min_chunk + 256 / CHUNK_SIZE)
+        );
+    }
+
+    #[test]
+    fn negative_blocks_belong_to_the_chunk_below() {
+        let block = IVec3::new(-1, -CHUNK_SIZE, -CHUNK_SIZE - 1);
+        assert_eq!(global_block_to_chunk_pos(&block), IVec3::new(-1, -1, -2));
+        assert_eq!(
+            global_block_to_local_offset(&block),
+            IVec3::new(CHUNK_SIZE - 1, 0, CHUNK_SIZE - 1)
+        );
+        assert_eq!(
+            world_position_to_chunk_position(Vec3::new(-0.5, 0.5, -0.01)),
+            IVec3::new(-1, 0, -1)
+        );
+    }
+}
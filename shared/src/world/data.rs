@@ -1,20 +1,24 @@
 use crate::messages::PlayerId;
-use crate::players::Player;
+use crate::players::{Inventory, Player, Teams};
 use crate::world::{
-    block_to_chunk_coord, global_block_to_chunk_pos, global_block_to_local_offset, BlockHitbox,
-    BlockId,
+    aabb_block_range, block_to_chunk_coord, chunk_offset_to_global_pos, global_block_to_chunk_pos,
+    global_block_to_local_offset, positions_in_region, world_position_to_block_position,
+    world_position_to_chunk_position, BlockHitbox, BlockId, BlockTickScheduler, ChunkUpdates,
+    ColumnHeightmap, DirtyReason, GameRules, WeatherState,
 };
 use crate::CHUNK_SIZE;
 
-use bevy::math::{bounding::Aabb3d, IVec3, Vec3};
+use bevy::math::{bounding::Aabb3d, IVec2, IVec3, Vec3};
 use bevy_ecs::resource::Resource;
-use bevy_log::info;
-use bevy_log::warn;
+use bevy_log::debug;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::ops::RangeInclusive;
 
-use super::{BlockData, ItemId, ItemType, MobId, ServerMob};
+use super::{
+    BlockData, EntityMetadata, ItemId, ItemType, MobId, Projectile, ProjectileId, ServerMob,
+};
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct ServerItemStack {
@@ -23,6 +27,8 @@ pub struct ServerItemStack {
     pub stack: ItemStack,
     pub pos: Vec3,
     pub timestamp: u64,
+    #[serde(default)]
+    pub metadata: EntityMetadata,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
@@ -31,6 +37,29 @@ pub struct ServerChunk {
     /// Timestamp marking the last update this chunk has received
     pub ts: u64,
     pub sent_to_clients: Vec<PlayerId>,
+    /// Set once a player placed or broke a block in it, such chunks are never trimmed.
+    /// Chunks saved before this was tracked count as modified
+    #[serde(default = "legacy_player_modified")]
+    pub player_modified: bool,
+    /// Biome of each block column of the chunk, see `biome_index`.
+    /// Empty for chunks saved before biomes were stored, until the server fills them
+    #[serde(default)]
+    pub biomes: Vec<BiomeType>,
+}
+
+fn legacy_player_modified() -> bool {
+    true
+}
+
+/// Index in the biomes of a chunk of the block column at these coordinates local to the chunk
+pub fn biome_index(local_x: i32, local_z: i32) -> usize {
+    (local_z * CHUNK_SIZE + local_x) as usize
+}
+
+/// Biome of the column holding `position` among the biomes of its chunk
+pub fn column_biome(biomes: &[BiomeType], position: &IVec3) -> Option<BiomeType> {
+    let local = global_block_to_local_offset(position);
+    biomes.get(biome_index(local.x, local.z)).copied()
 }
 
 // #[derive(Resource)]
@@ -44,17 +73,110 @@ pub struct ServerWorldMap {
     pub mobs: HashMap<MobId, ServerMob>,
     pub item_stacks: Vec<ServerItemStack>,
     pub time: u64,
+    /// Time of day in ticks, it stands still while the `doDaylightCycle` rule is off
+    pub day_time: u64,
+    pub game_rules: GameRules,
+    pub weather: WeatherState,
+    /// Claimed chunk columns, indexed by their (x, z) chunk coordinates
+    pub claims: HashMap<IVec2, ChunkClaim>,
+    pub teams: Teams,
+    /// Items stored in the blocks holding some, like chests, indexed by the block position
+    pub containers: HashMap<IVec3, Inventory>,
+    /// Block updates scheduled by the server, indexed by the tick they are due
+    pub block_ticks: BlockTickScheduler,
+    /// Projectiles in flight, they are short-lived and never saved
+    #[serde(skip)]
+    pub projectiles: HashMap<ProjectileId, Projectile>,
+}
+
+/// A chunk column reserved by a player, only them and their team (if it shares claims) can edit its blocks
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ChunkClaim {
+    pub owner: PlayerId,
+    pub owner_name: String,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize, Debug)]
 pub struct ServerChunkWorldMap {
     pub map: HashMap<IVec3, ServerChunk>,
-    pub chunks_to_update: Vec<IVec3>,
+    /// Chunks to send to the clients again
+    pub chunks_to_update: ChunkUpdates,
+    /// Chunks modified or generated since the last world save
+    #[serde(skip)]
+    pub dirty_chunks: HashSet<IVec3>,
+    /// Surface of every chunk column, kept up to date by `insert_chunk`, `set_block` and `remove_block_by_coordinates`
+    #[serde(skip)]
+    pub heightmap: HashMap<IVec2, ColumnHeightmap>,
+    /// Saved with the rest of the world data
+    #[serde(skip)]
+    pub gen_settings: WorldGenSettings,
+    /// Blocks that features of generated chunks spilled into chunks not generated yet, saved with the rest of the world data
+    #[serde(skip)]
+    pub pending_blocks: HashMap<IVec3, Vec<(IVec3, BlockData)>>,
+    /// The spawn platform is built once the chunks under it are generated, saved with the rest of the world data
+    #[serde(skip)]
+    pub spawn_platform_pending: bool,
+    /// Chunks trimmed from the world, their files are deleted by the next save
+    #[serde(skip)]
+    pub removed_chunks: HashSet<IVec3>,
 }
 
 #[derive(Resource, Clone, Copy, Serialize, Deserialize, Default)]
 pub struct WorldSeed(pub u32);
 
+impl WorldSeed {
+    /// Numeric inputs are used as-is, any other text is hashed (FNV-1a) so that the same text always gives the same seed
+    pub fn from_text(text: &str) -> Self {
+        let text = text.trim();
+        if let Ok(seed) = text.parse::<u32>() {
+            return Self(seed);
+        }
+        let mut hash: u32 = 0x811C_9DC5;
+        for byte in text.bytes() {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+        Self(hash)
+    }
+}
+
+/// Vertical limits of a world, chosen when it is created
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct WorldGenSettings {
+    /// Lowest block, made of bedrock
+    pub min_y: i32,
+    /// Nothing is generated or placed at this height and above
+    pub max_y: i32,
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        Self {
+            min_y: -64,
+            max_y: 320,
+        }
+    }
+}
+
+impl WorldGenSettings {
+    /// Limits of the worlds saved before they could be configured
+    pub fn legacy() -> Self {
+        Self {
+            min_y: 0,
+            max_y: 256,
+        }
+    }
+
+    pub fn contains_y(&self, y: i32) -> bool {
+        (self.min_y..self.max_y).contains(&y)
+    }
+
+    /// Chunk y indices holding at least one block inside the limits
+    pub fn chunk_y_range(&self) -> RangeInclusive<i32> {
+        block_to_chunk_coord(self.min_y)..=block_to_chunk_coord(self.max_y - 1)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Copy, Default, PartialEq)]
 pub struct ItemStack {
     pub item_id: ItemId,
@@ -62,7 +184,18 @@ pub struct ItemStack {
     pub nb: u32,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+impl ItemStack {
+    /// Stack of `nb` items of this kind, with its default type
+    pub fn new(item_id: ItemId, nb: u32) -> Self {
+        Self {
+            item_id,
+            item_type: item_id.get_default_type(),
+            nb,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BiomeType {
     Plains,
     Forest,
@@ -76,6 +209,13 @@ pub enum BiomeType {
     DeepOcean,
 }
 
+impl BiomeType {
+    /// Biomes where precipitation falls as snow and snow does not melt
+    pub fn is_cold(&self) -> bool {
+        matches!(*self, Self::IcePlain | Self::HighMountainGrass)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Biome {
     pub biome_type: BiomeType,
@@ -160,14 +300,95 @@ pub fn get_biome_data(biome_type: BiomeType) -> Biome {
     }
 }
 
+/// Result of a bulk edit for one of the chunks it touched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkChangeSummary {
+    pub chunk_pos: IVec3,
+    /// Blocks written in the chunk
+    pub blocks_set: usize,
+    /// Blocks which were not already the same before the edit
+    pub blocks_changed: usize,
+}
+
+/// Splits blocks given with their global positions by chunk, with positions local to the chunk
+fn group_blocks_by_chunk(
+    blocks: &[(IVec3, Option<BlockData>)],
+) -> HashMap<IVec3, Vec<(IVec3, Option<BlockData>)>> {
+    let mut chunks: HashMap<IVec3, Vec<(IVec3, Option<BlockData>)>> = HashMap::new();
+    for (position, block) in blocks {
+        chunks
+            .entry(global_block_to_chunk_pos(position))
+            .or_default()
+            .push((global_block_to_local_offset(position), *block));
+    }
+    chunks
+}
+
 pub trait WorldMap {
     fn get_block_mut_by_coordinates(&mut self, position: &IVec3) -> Option<&mut BlockData>;
     fn get_block_by_coordinates(&self, position: &IVec3) -> Option<&BlockData>;
     fn remove_block_by_coordinates(&mut self, global_block_pos: &IVec3) -> Option<BlockData>;
     fn set_block(&mut self, position: &IVec3, block: BlockData);
 
+    /// Sets blocks of a single chunk, given with their positions local to it, `None` removes the block.
+    /// Returns how many of them were different before.
+    fn set_chunk_blocks(
+        &mut self,
+        chunk_pos: IVec3,
+        blocks: &[(IVec3, Option<BlockData>)],
+    ) -> usize {
+        let mut changed = 0;
+        for (local_pos, block) in blocks {
+            let position = chunk_offset_to_global_pos(&chunk_pos, local_pos);
+            if self.get_block_by_coordinates(&position) != block.as_ref() {
+                changed += 1;
+            }
+            match block {
+                Some(block) => self.set_block(&position, *block),
+                None => {
+                    self.remove_block_by_coordinates(&position);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Sets or removes many blocks at once, the edits are grouped by chunk so each chunk is updated once.
+    /// Like `set_block`, structure voids are written as they are: leaving blocks untouched is up to the caller
+    fn set_blocks_bulk(
+        &mut self,
+        blocks: &[(IVec3, Option<BlockData>)],
+    ) -> Vec<ChunkChangeSummary> {
+        group_blocks_by_chunk(blocks)
+            .into_iter()
+            .map(|(chunk_pos, chunk_blocks)| ChunkChangeSummary {
+                chunk_pos,
+                blocks_set: chunk_blocks.len(),
+                blocks_changed: self.set_chunk_blocks(chunk_pos, &chunk_blocks),
+            })
+            .collect()
+    }
+
+    /// Fills the box between two corners, both included, with one block, or empties it with `None`
+    fn fill_region(
+        &mut self,
+        min: IVec3,
+        max: IVec3,
+        block: Option<BlockData>,
+    ) -> Vec<ChunkChangeSummary> {
+        let blocks: Vec<(IVec3, Option<BlockData>)> =
+            positions_in_region(min.min(max), min.max(max))
+                .map(|position| (position, block))
+                .collect();
+        self.set_blocks_bulk(&blocks)
+    }
+
+    fn height_limits(&self) -> WorldGenSettings;
+
+    /// Y of the highest block at this column, the bottom of the world if there is none
     fn get_height_ground(&self, position: Vec3) -> i32 {
-        for y in (0..256).rev() {
+        let limits = self.height_limits();
+        for y in (limits.min_y..limits.max_y).rev() {
             if self
                 .get_block_by_coordinates(&IVec3::new(position.x as i32, y, position.z as i32))
                 .is_some()
@@ -175,17 +396,30 @@ pub trait WorldMap {
                 return y;
             }
         }
-        0
+        limits.min_y
+    }
+
+    /// No solid block stands between this position and the sky
+    fn is_exposed_to_sky(&self, position: &IVec3) -> bool {
+        for y in (position.y + 1)..self.height_limits().max_y {
+            if self
+                .get_block_by_coordinates(&IVec3::new(position.x, y, position.z))
+                .is_some_and(|block| matches!(block.get_hitbox(), BlockHitbox::FullBlock))
+            {
+                return false;
+            }
+        }
+        true
     }
 
     fn check_collision_box(&self, hitbox: &Aabb3d) -> bool {
         // Check all blocks inside the hitbox
-        // Manual flooring is needed for negative coordinates
-        for x in (hitbox.min.x.floor() as i32)..=(hitbox.max.x.floor() as i32) {
-            for y in (hitbox.min.y.floor() as i32)..=(hitbox.max.y.floor() as i32) {
-                for z in (hitbox.min.z.floor() as i32)..=(hitbox.max.z.floor() as i32) {
+        let (min, max) = aabb_block_range(hitbox);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
                     if let Some(block) = self.get_block_by_coordinates(&IVec3::new(x, y, z)) {
-                        match block.id.get_hitbox() {
+                        match block.get_hitbox() {
                             BlockHitbox::FullBlock => return true,
                             BlockHitbox::None => continue,
                             BlockHitbox::Aabb(block_hitbox) => {
@@ -206,12 +440,11 @@ pub trait WorldMap {
 
     fn get_surrounding_chunks(&self, position: Vec3, radius: i32) -> Vec<IVec3> {
         let mut chunks = Vec::new();
-        let x = position.x as i32;
-        let y = position.y as i32;
-        let z = position.z as i32;
-        let cx = block_to_chunk_coord(x);
-        let cy = block_to_chunk_coord(y);
-        let cz = block_to_chunk_coord(z);
+        let IVec3 {
+            x: cx,
+            y: cy,
+            z: cz,
+        } = world_position_to_chunk_position(position);
         for i in -radius..=radius {
             for j in -radius..=radius {
                 for k in -radius..=radius {
@@ -223,55 +456,36 @@ pub trait WorldMap {
     }
 
     fn mark_block_for_update(&mut self, position: &IVec3);
+
+    /// Biome of the block column holding `position`, `None` if its chunk is not loaded
+    fn get_biome(&self, position: &IVec3) -> Option<BiomeType>;
 }
 
 impl WorldMap for ServerChunkWorldMap {
     fn get_block_mut_by_coordinates(&mut self, position: &IVec3) -> Option<&mut BlockData> {
-        let x: i32 = position.x;
-        let y: i32 = position.y;
-        let z: i32 = position.z;
-        let cx: i32 = block_to_chunk_coord(x);
-        let cy: i32 = block_to_chunk_coord(y);
-        let cz: i32 = block_to_chunk_coord(z);
-        let chunk = self.map.get_mut(&IVec3::new(cx, cy, cz));
+        let chunk = self.map.get_mut(&global_block_to_chunk_pos(position));
         match chunk {
-            Some(chunk) => {
-                let sub_x: i32 = ((x % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-                let sub_y: i32 = ((y % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-                let sub_z: i32 = ((z % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-                chunk.map.get_mut(&IVec3::new(sub_x, sub_y, sub_z))
-            }
+            Some(chunk) => chunk.map.get_mut(&global_block_to_local_offset(position)),
             None => {
-                warn!("Chunk not found for block at {:?} (mut)", position);
+                crate::warn_throttled!("Chunk not found for block at {:?} (mut)", position);
                 None
             }
         }
     }
 
     fn get_block_by_coordinates(&self, position: &IVec3) -> Option<&BlockData> {
-        let x: i32 = position.x;
-        let y: i32 = position.y;
-        let z: i32 = position.z;
-        let cx: i32 = block_to_chunk_coord(x);
-        let cy: i32 = block_to_chunk_coord(y);
-        let cz: i32 = block_to_chunk_coord(z);
-        let chunk: Option<&ServerChunk> = self.map.get(&IVec3::new(cx, cy, cz));
+        let chunk: Option<&ServerChunk> = self.map.get(&global_block_to_chunk_pos(position));
         match chunk {
-            Some(chunk) => {
-                let sub_x: i32 = ((x % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-                let sub_y: i32 = ((y % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-                let sub_z: i32 = ((z % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-                chunk.map.get(&IVec3::new(sub_x, sub_y, sub_z))
-            }
+            Some(chunk) => chunk.map.get(&global_block_to_local_offset(position)),
             None => {
-                warn!("Chunk not found for block at {:?}", position);
+                crate::warn_throttled!("Chunk not found for block at {:?}", position);
                 None
             }
         }
     }
 
     fn remove_block_by_coordinates(&mut self, global_block_pos: &IVec3) -> Option<BlockData> {
-        info!("Trying to remove block at pos {:?}", global_block_pos);
+        debug!("Trying to remove block at pos {:?}", global_block_pos);
         let block: &BlockData = self.get_block_by_coordinates(global_block_pos)?;
         let kind: BlockData = *block;
 
@@ -282,35 +496,94 @@ impl WorldMap for ServerChunkWorldMap {
         chunk_map
             .map
             .remove(&global_block_to_local_offset(global_block_pos));
-        self.chunks_to_update.push(chunk_pos);
+        self.chunks_to_update.mark(chunk_pos, DirtyReason::Blocks);
+        self.dirty_chunks.insert(chunk_pos);
+        self.lower_surface_height(global_block_pos);
 
         Some(kind)
     }
 
     fn set_block(&mut self, position: &IVec3, block: BlockData) {
-        let x: i32 = position.x;
-        let y: i32 = position.y;
-        let z: i32 = position.z;
-        let cx: i32 = block_to_chunk_coord(x);
-        let cy: i32 = block_to_chunk_coord(y);
-        let cz: i32 = block_to_chunk_coord(z);
-        let chunk: &mut ServerChunk = self.map.entry(IVec3::new(cx, cy, cz)).or_default();
-        let sub_x: i32 = ((x % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-        let sub_y: i32 = ((y % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-        let sub_z: i32 = ((z % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE;
-
-        chunk.map.insert(IVec3::new(sub_x, sub_y, sub_z), block);
-        self.chunks_to_update.push(IVec3::new(cx, cy, cz));
+        let chunk_pos = global_block_to_chunk_pos(position);
+        let chunk: &mut ServerChunk = self.map.entry(chunk_pos).or_default();
+
+        chunk
+            .map
+            .insert(global_block_to_local_offset(position), block);
+        self.chunks_to_update.mark(chunk_pos, DirtyReason::Blocks);
+        self.dirty_chunks.insert(chunk_pos);
+        self.raise_surface_height(position);
+    }
+
+    fn set_chunk_blocks(
+        &mut self,
+        chunk_pos: IVec3,
+        blocks: &[(IVec3, Option<BlockData>)],
+    ) -> usize {
+        // Removing blocks from a chunk which does not exist yet must not create it
+        let chunk: &mut ServerChunk = if blocks.iter().any(|(_, block)| block.is_some()) {
+            self.map.entry(chunk_pos).or_default()
+        } else {
+            match self.map.get_mut(&chunk_pos) {
+                Some(chunk) => chunk,
+                None => return 0,
+            }
+        };
+        let changed = blocks
+            .iter()
+            .filter(|(local_pos, block)| {
+                let previous = match block {
+                    Some(block) => chunk.map.insert(*local_pos, *block),
+                    None => chunk.map.remove(local_pos),
+                };
+                previous != *block
+            })
+            .count();
+
+        self.chunks_to_update.mark(chunk_pos, DirtyReason::Blocks);
+        self.dirty_chunks.insert(chunk_pos);
+        for (local_pos, block) in blocks {
+            let position = chunk_offset_to_global_pos(&chunk_pos, local_pos);
+            match block {
+                Some(_) => self.raise_surface_height(&position),
+                None => self.lower_surface_height(&position),
+            }
+        }
+        changed
+    }
+
+    fn height_limits(&self) -> WorldGenSettings {
+        self.gen_settings
+    }
+
+    fn get_height_ground(&self, position: Vec3) -> i32 {
+        let block = world_position_to_block_position(position);
+        self.get_surface_height(block.x, block.z)
+            .unwrap_or(self.gen_settings.min_y)
+    }
+
+    fn is_exposed_to_sky(&self, position: &IVec3) -> bool {
+        match self.get_surface_height(position.x, position.z) {
+            Some(height) if height > position.y => {
+                // Only full blocks cast a shadow, the surface block may be a flower
+                ((position.y + 1)..=height).all(|y| {
+                    !self
+                        .get_block_by_coordinates(&IVec3::new(position.x, y, position.z))
+                        .is_some_and(|block| matches!(block.get_hitbox(), BlockHitbox::FullBlock))
+                })
+            }
+            _ => true,
+        }
     }
 
     fn mark_block_for_update(&mut self, position: &IVec3) {
-        let x: i32 = position.x;
-        let y: i32 = position.y;
-        let z: i32 = position.z;
-        let cx: i32 = block_to_chunk_coord(x);
-        let cy: i32 = block_to_chunk_coord(y);
-        let cz: i32 = block_to_chunk_coord(z);
-        self.chunks_to_update.push(IVec3::new(cx, cy, cz));
+        self.chunks_to_update
+            .mark(global_block_to_chunk_pos(position), DirtyReason::Blocks);
+    }
+
+    fn get_biome(&self, position: &IVec3) -> Option<BiomeType> {
+        let chunk = self.map.get(&global_block_to_chunk_pos(position))?;
+        column_biome(&chunk.biomes, position)
     }
 }
 
@@ -0,0 +1,95 @@
+use bevy_ecs::resource::Resource;
+use serde::{Deserialize, Serialize};
+
+/// Flags changing how the world behaves, saved with it and set by ops with `/gamerule`
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct GameRules {
+    /// Players keep their items when they die instead of dropping them
+    pub keep_inventory: bool,
+    pub mob_spawning: bool,
+    /// When off the time of day stays where it is
+    pub do_daylight_cycle: bool,
+    pub fall_damage: bool,
+    /// When off fire neither spreads nor burns the blocks around it
+    pub do_fire_tick: bool,
+    /// Tutorial hints for new players, veteran servers may turn them off
+    pub show_hints: bool,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            // Players always kept their items before the rule existed
+            keep_inventory: true,
+            mob_spawning: true,
+            do_daylight_cycle: true,
+            fall_damage: true,
+            do_fire_tick: true,
+            show_hints: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameRule {
+    KeepInventory,
+    MobSpawning,
+    DoDaylightCycle,
+    FallDamage,
+    DoFireTick,
+    ShowHints,
+}
+
+impl GameRule {
+    pub const ALL: [GameRule; 6] = [
+        GameRule::KeepInventory,
+        GameRule::MobSpawning,
+        GameRule::DoDaylightCycle,
+        GameRule::FallDamage,
+        GameRule::DoFireTick,
+        GameRule::ShowHints,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|rule| rule.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameRule::KeepInventory => "keepInventory",
+            GameRule::MobSpawning => "mobSpawning",
+            GameRule::DoDaylightCycle => "doDaylightCycle",
+            GameRule::FallDamage => "fallDamage",
+            GameRule::DoFireTick => "doFireTick",
+            GameRule::ShowHints => "showHints",
+        }
+    }
+}
+
+impl GameRules {
+    pub fn get(&self, rule: GameRule) -> bool {
+        match rule {
+            GameRule::KeepInventory => self.keep_inventory,
+            GameRule::MobSpawning => self.mob_spawning,
+            GameRule::DoDaylightCycle => self.do_daylight_cycle,
+            GameRule::FallDamage => self.fall_damage,
+            GameRule::DoFireTick => self.do_fire_tick,
+            GameRule::ShowHints => self.show_hints,
+        }
+    }
+
+    pub fn set(&mut self, rule: GameRule, value: bool) {
+        let flag = match rule {
+            GameRule::KeepInventory => &mut self.keep_inventory,
+            GameRule::MobSpawning => &mut self.mob_spawning,
+            GameRule::DoDaylightCycle => &mut self.do_daylight_cycle,
+            GameRule::FallDamage => &mut self.fall_damage,
+            GameRule::DoFireTick => &mut self.do_fire_tick,
+            GameRule::ShowHints => &mut self.show_hints,
+        };
+        *flag = value;
+    }
+}
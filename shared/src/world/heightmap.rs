@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use bevy::math::{IVec2, IVec3};
+
+use crate::world::{block_to_chunk_coord, block_to_local_coord, ServerChunk, ServerChunkWorldMap};
+use crate::CHUNK_SIZE;
+
+const COLUMN_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// Highest block of every block column of a chunk column
+#[derive(Clone, Debug)]
+pub struct ColumnHeightmap {
+    heights: [Option<i32>; COLUMN_AREA],
+}
+
+impl Default for ColumnHeightmap {
+    fn default() -> Self {
+        Self {
+            heights: [None; COLUMN_AREA],
+        }
+    }
+}
+
+fn column_index(local_x: i32, local_z: i32) -> usize {
+    (local_z * CHUNK_SIZE + local_x) as usize
+}
+
+/// Highest block of a block column inside a single chunk
+fn highest_block_in_chunk(chunk: &ServerChunk, local_x: i32, local_z: i32) -> Option<i32> {
+    (0..CHUNK_SIZE)
+        .rev()
+        .find(|y| chunk.map.contains_key(&IVec3::new(local_x, *y, local_z)))
+}
+
+/// Heightmap of a chunk column made of these chunks, given with their y index
+fn column_heightmap(mut chunks: Vec<(i32, &ServerChunk)>) -> ColumnHeightmap {
+    chunks.sort_by_key(|(y, _)| std::cmp::Reverse(*y));
+
+    let mut heightmap = ColumnHeightmap::default();
+    for local_z in 0..CHUNK_SIZE {
+        for local_x in 0..CHUNK_SIZE {
+            heightmap.heights[column_index(local_x, local_z)] =
+                chunks.iter().find_map(|(chunk_y, chunk)| {
+                    highest_block_in_chunk(chunk, local_x, local_z)
+                        .map(|y| chunk_y * CHUNK_SIZE + y)
+                });
+        }
+    }
+    heightmap
+}
+
+impl ServerChunkWorldMap {
+    /// Y of the highest loaded block at these block coordinates, `None` if the column is empty or not loaded
+    pub fn get_surface_height(&self, x: i32, z: i32) -> Option<i32> {
+        let column = IVec2::new(block_to_chunk_coord(x), block_to_chunk_coord(z));
+        self.heightmap.get(&column)?.heights
+            [column_index(block_to_local_coord(x), block_to_local_coord(z))]
+    }
+
+    /// Adds or replaces a chunk, keeping the heightmap up to date
+    pub fn insert_chunk(&mut self, chunk_pos: IVec3, chunk: ServerChunk) {
+        let column = IVec2::new(chunk_pos.x, chunk_pos.z);
+        if self.map.insert(chunk_pos, chunk).is_some() {
+            // The old blocks may have been the surface
+            let chunks = self.chunks_of_column(column);
+            let heightmap = column_heightmap(chunks);
+            self.heightmap.insert(column, heightmap);
+            return;
+        }
+
+        let chunk = &self.map[&chunk_pos];
+        let heightmap = self.heightmap.entry(column).or_default();
+        for local_z in 0..CHUNK_SIZE {
+            for local_x in 0..CHUNK_SIZE {
+                let Some(y) = highest_block_in_chunk(chunk, local_x, local_z) else {
+                    continue;
+                };
+                let y = chunk_pos.y * CHUNK_SIZE + y;
+                let height = &mut heightmap.heights[column_index(local_x, local_z)];
+                if height.is_none_or(|height| height < y) {
+                    *height = Some(y);
+                }
+            }
+        }
+    }
+
+    /// Flags the chunk of a block a player placed or broke, it will not be trimmed
+    pub fn mark_player_modified(&mut self, position: &IVec3) {
+        let chunk_pos = IVec3::new(
+            block_to_chunk_coord(position.x),
+            block_to_chunk_coord(position.y),
+            block_to_chunk_coord(position.z),
+        );
+        if let Some(chunk) = self.map.get_mut(&chunk_pos) {
+            if !chunk.player_modified {
+                chunk.player_modified = true;
+                self.dirty_chunks.insert(chunk_pos);
+            }
+        }
+    }
+
+    /// Removes a whole chunk column from the world, its files are deleted by the next save
+    pub fn remove_column(&mut self, column: IVec2) {
+        let positions: Vec<IVec3> = self
+            .map
+            .keys()
+            .filter(|pos| pos.x == column.x && pos.z == column.y)
+            .copied()
+            .collect();
+        for pos in positions {
+            self.map.remove(&pos);
+            self.dirty_chunks.remove(&pos);
+            self.removed_chunks.insert(pos);
+        }
+        self.heightmap.remove(&column);
+    }
+
+    /// Recomputes the heightmap from scratch, for chunks added to `map` directly
+    pub fn rebuild_heightmap(&mut self) {
+        let mut columns: HashMap<IVec2, Vec<(i32, &ServerChunk)>> = HashMap::new();
+        for (pos, chunk) in self.map.iter() {
+            columns
+                .entry(IVec2::new(pos.x, pos.z))
+                .or_default()
+                .push((pos.y, chunk));
+        }
+
+        self.heightmap = columns
+            .into_iter()
+            .map(|(column, chunks)| (column, column_heightmap(chunks)))
+            .collect();
+    }
+
+    fn chunks_of_column(&self, column: IVec2) -> Vec<(i32, &ServerChunk)> {
+        self.map
+            .iter()
+            .filter(|(pos, _)| pos.x == column.x && pos.z == column.y)
+            .map(|(pos, chunk)| (pos.y, chunk))
+            .collect()
+    }
+
+    /// Called once a block was placed
+    pub(crate) fn raise_surface_height(&mut self, position: &IVec3) {
+        let column = IVec2::new(
+            block_to_chunk_coord(position.x),
+            block_to_chunk_coord(position.z),
+        );
+        let height = &mut self.heightmap.entry(column).or_default().heights[column_index(
+            block_to_local_coord(position.x),
+            block_to_local_coord(position.z),
+        )];
+        if height.is_none_or(|height| height < position.y) {
+            *height = Some(position.y);
+        }
+    }
+
+    /// Called once a block was removed, only the surface block needs a new scan of its column
+    pub(crate) fn lower_surface_height(&mut self, position: &IVec3) {
+        if self.get_surface_height(position.x, position.z) != Some(position.y) {
+            return;
+        }
+
+        let (local_x, local_z) = (
+            block_to_local_coord(position.x),
+            block_to_local_coord(position.z),
+        );
+        let column = IVec2::new(
+            block_to_chunk_coord(position.x),
+            block_to_chunk_coord(position.z),
+        );
+        let mut chunks = self.chunks_of_column(column);
+        chunks.retain(|(chunk_y, _)| *chunk_y <= block_to_chunk_coord(position.y));
+        chunks.sort_by_key(|(y, _)| std::cmp::Reverse(*y));
+
+        let new_height = chunks.iter().find_map(|(chunk_y, chunk)| {
+            highest_block_in_chunk(chunk, local_x, local_z).map(|y| chunk_y * CHUNK_SIZE + y)
+        });
+
+        if let Some(heightmap) = self.heightmap.get_mut(&column) {
+            heightmap.heights[column_index(local_x, local_z)] = new_height;
+        }
+    }
+}
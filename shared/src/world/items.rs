@@ -37,11 +37,107 @@ pub enum ItemId {
     Snow,
     Snowball,
     SpruceLog,
+    RottenFlesh,
+    Bone,
+    Arrow,
+    Bow,
+    Coal,
+    IronOre,
+    GoldOre,
+    Diamond,
+    Chest,
+    WoodenPickaxe,
+    StonePickaxe,
+    IronPickaxe,
+    GoldenPickaxe,
+    DiamondPickaxe,
+    FlintAndSteel,
+    Compass,
+    Clock,
+    Map,
+    StructureVoid,
+    Barrier,
+    Light,
+}
+
+/// How an item is used while the use button is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemUseKind {
+    /// Used as soon as the button is pressed
+    Instant,
+    /// Charges while held and is used on release, releasing before `min_charge_ms` cancels
+    Charge {
+        min_charge_ms: u64,
+        full_charge_ms: u64,
+    },
+    /// Used once held for `duration_ms`, releasing earlier cancels
+    Consume { duration_ms: u64 },
 }
 
 impl ItemId {
+    pub const ALL: [ItemId; 39] = [
+        ItemId::Dirt,
+        ItemId::Grass,
+        ItemId::Stone,
+        ItemId::OakLog,
+        ItemId::OakPlanks,
+        ItemId::OakLeaves,
+        ItemId::Sand,
+        ItemId::Cactus,
+        ItemId::Ice,
+        ItemId::Glass,
+        ItemId::Bedrock,
+        ItemId::Dandelion,
+        ItemId::TallGrass,
+        ItemId::Poppy,
+        ItemId::Cobblestone,
+        ItemId::Snow,
+        ItemId::Snowball,
+        ItemId::SpruceLog,
+        ItemId::RottenFlesh,
+        ItemId::Bone,
+        ItemId::Arrow,
+        ItemId::Bow,
+        ItemId::Coal,
+        ItemId::IronOre,
+        ItemId::GoldOre,
+        ItemId::Diamond,
+        ItemId::Chest,
+        ItemId::WoodenPickaxe,
+        ItemId::StonePickaxe,
+        ItemId::IronPickaxe,
+        ItemId::GoldenPickaxe,
+        ItemId::DiamondPickaxe,
+        ItemId::FlintAndSteel,
+        ItemId::Compass,
+        ItemId::Clock,
+        ItemId::Map,
+        ItemId::StructureVoid,
+        ItemId::Barrier,
+        ItemId::Light,
+    ];
+
+    /// Parses the names used in commands, like `oak_planks`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let key = format!("item.{}", name.to_lowercase());
+        Self::ALL
+            .into_iter()
+            .find(|item| item.translation_key() == key)
+    }
+
+    /// Item placing the block, `None` for the blocks which cannot be held
+    pub fn from_block(block: BlockId) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|item| item.get_default_type() == ItemType::Block(block))
+    }
+
     pub fn get_max_stack(&self) -> u32 {
-        64
+        match *self {
+            Self::Bow | Self::FlintAndSteel | Self::Compass | Self::Clock | Self::Map => 1,
+            _ if self.tool().is_some() => 1,
+            _ => 64,
+        }
     }
 
     pub fn get_default_type(&self) -> ItemType {
@@ -63,8 +159,68 @@ impl ItemId {
             Self::Cobblestone => ItemType::Block(BlockId::Cobblestone),
             Self::Snow => ItemType::Block(BlockId::Snow),
             Self::SpruceLog => ItemType::Block(BlockId::SpruceLog),
+            Self::IronOre => ItemType::Block(BlockId::IronOre),
+            Self::GoldOre => ItemType::Block(BlockId::GoldOre),
+            Self::Chest => ItemType::Block(BlockId::Chest),
+            Self::StructureVoid => ItemType::Block(BlockId::StructureVoid),
+            Self::Barrier => ItemType::Block(BlockId::Barrier),
+            Self::Light => ItemType::Block(BlockId::Light),
+
+            Self::Snowball
+            | Self::RottenFlesh
+            | Self::Bone
+            | Self::Arrow
+            | Self::Bow
+            | Self::Coal
+            | Self::Diamond
+            | Self::Compass
+            | Self::Clock
+            | Self::Map => ItemType::Generic,
+
+            Self::WoodenPickaxe => ItemType::Tool { durability: 60 },
+            Self::StonePickaxe => ItemType::Tool { durability: 132 },
+            Self::IronPickaxe => ItemType::Tool { durability: 251 },
+            Self::GoldenPickaxe => ItemType::Tool { durability: 33 },
+            Self::DiamondPickaxe => ItemType::Tool { durability: 1562 },
+            Self::FlintAndSteel => ItemType::Tool { durability: 65 },
+        }
+    }
 
-            Self::Snowball => ItemType::Generic,
+    pub fn use_kind(&self) -> Option<ItemUseKind> {
+        match *self {
+            Self::Snowball => Some(ItemUseKind::Instant),
+            Self::Bow => Some(ItemUseKind::Charge {
+                min_charge_ms: 200,
+                full_charge_ms: 1000,
+            }),
+            Self::RottenFlesh => Some(ItemUseKind::Consume { duration_ms: 1600 }),
+            _ => None,
+        }
+    }
+
+    /// Time after a use before the item can be used again
+    pub fn use_cooldown_ms(&self) -> u64 {
+        match *self {
+            Self::Snowball => 250,
+            Self::Bow => 500,
+            Self::RottenFlesh => 500,
+            _ => 0,
+        }
+    }
+
+    /// Item taken from the inventory each time this one is used, if not the item itself
+    pub fn ammo(&self) -> Option<ItemId> {
+        match *self {
+            Self::Bow => Some(Self::Arrow),
+            _ => None,
+        }
+    }
+
+    /// Health restored by eating the item
+    pub fn food_health(&self) -> Option<f32> {
+        match *self {
+            Self::RottenFlesh => Some(2.0),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Name shown instead of the kind of the entity
+pub const CUSTOM_NAME_KEY: &str = "custom_name";
+/// Entities with this set to true take no damage
+pub const INVULNERABLE_KEY: &str = "invulnerable";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl MetadataValue {
+    /// Reads a value typed in a command, text is whatever is neither a boolean nor a number
+    pub fn parse(value: &str) -> Self {
+        if let Ok(value) = value.parse::<bool>() {
+            MetadataValue::Bool(value)
+        } else if let Ok(value) = value.parse::<i64>() {
+            MetadataValue::Int(value)
+        } else if let Ok(value) = value.parse::<f64>() {
+            MetadataValue::Float(value)
+        } else {
+            MetadataValue::Text(value.to_string())
+        }
+    }
+}
+
+impl fmt::Display for MetadataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataValue::Bool(value) => write!(f, "{value}"),
+            MetadataValue::Int(value) => write!(f, "{value}"),
+            MetadataValue::Float(value) => write!(f, "{value}"),
+            MetadataValue::Text(value) => write!(f, "\"{value}\""),
+        }
+    }
+}
+
+/// Free-form data attached to mobs and item stacks, saved with the world.
+/// Game logic and admin commands can tag entities or store values on them without adding fields to every entity
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct EntityMetadata {
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub values: BTreeMap<String, MetadataValue>,
+}
+
+impl EntityMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.values.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&MetadataValue> {
+        self.values.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, value: MetadataValue) {
+        self.values.insert(key.to_string(), value);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<MetadataValue> {
+        self.values.remove(key)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn custom_name(&self) -> Option<&str> {
+        match self.get(CUSTOM_NAME_KEY) {
+            Some(MetadataValue::Text(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        matches!(self.get(INVULNERABLE_KEY), Some(MetadataValue::Bool(true)))
+    }
+}
+
+impl fmt::Display for EntityMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no metadata");
+        }
+        let tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        write!(f, "tags: [{}], {}", tags.join(", "), values.join(", "))
+    }
+}
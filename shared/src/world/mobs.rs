@@ -1,13 +1,105 @@
-use bevy::math::{Quat, Vec3};
+use bevy::math::{IVec3, Quat, Vec3};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::messages::PlayerId;
+use crate::{messages::PlayerId, players::StatusEffects};
+
+use super::{EntityMetadata, ItemId, TradeOffer};
 
 pub type MobId = u128;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum MobKind {
     Fox,
+    Zombie,
+    Skeleton,
+    Wolf,
+    Villager,
+}
+
+impl MobKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "fox" => Some(Self::Fox),
+            "zombie" => Some(Self::Zombie),
+            "skeleton" => Some(Self::Skeleton),
+            "wolf" => Some(Self::Wolf),
+            "villager" => Some(Self::Villager),
+            _ => None,
+        }
+    }
+
+    pub fn max_health(&self) -> f32 {
+        match *self {
+            MobKind::Fox => 10.0,
+            MobKind::Wolf => 8.0,
+            MobKind::Zombie | MobKind::Skeleton | MobKind::Villager => 20.0,
+        }
+    }
+
+    /// Hostile mobs chase and attack the nearest player
+    pub fn is_hostile(&self) -> bool {
+        matches!(*self, MobKind::Zombie | MobKind::Skeleton)
+    }
+
+    /// Ranged mobs keep their distance and shoot projectiles instead of attacking in melee
+    pub fn is_ranged(&self) -> bool {
+        matches!(*self, MobKind::Skeleton)
+    }
+
+    /// Whether the mob catches fire when standing under the sky during the day
+    pub fn burns_in_daylight(&self) -> bool {
+        matches!(*self, MobKind::Zombie | MobKind::Skeleton)
+    }
+
+    /// Item to feed the mob with to tame it, `None` for mobs which can't be tamed
+    pub fn taming_item(&self) -> Option<ItemId> {
+        match *self {
+            MobKind::Wolf => Some(ItemId::Bone),
+            _ => None,
+        }
+    }
+
+    /// Size of the mob hitbox as `(width, height, depth)`
+    pub fn get_size(&self) -> (f32, f32, f32) {
+        match *self {
+            MobKind::Fox => (1.0, 1.0, 1.5),
+            MobKind::Zombie | MobKind::Skeleton | MobKind::Villager => (0.6, 1.8, 0.6),
+            MobKind::Wolf => (0.6, 0.85, 1.0),
+        }
+    }
+
+    /// Items dropped and their number, in the order of the loot table so that the same draws give the same drops
+    pub fn get_drops(&self, rng: &mut impl Rng) -> Vec<(ItemId, u32)> {
+        let mut drops: Vec<(ItemId, u32)> = Vec::new();
+
+        for (chance, item, min, max) in self.get_loot_table() {
+            if rng.gen_range(0..100) < chance {
+                let nb = rng.gen_range(min..=max);
+                if nb > 0 {
+                    match drops.iter_mut().find(|(id, _)| *id == item) {
+                        Some((_, count)) => *count += nb,
+                        None => drops.push((item, nb)),
+                    }
+                }
+            }
+        }
+        drops
+    }
+
+    /// Specifies what a mob drops when it dies
+    /// Entries are specified this way : `(percent_chance, corresponding_item, min_number, max_number)`
+    pub fn get_loot_table(&self) -> Vec<(u32, ItemId, u32, u32)> {
+        match *self {
+            MobKind::Fox | MobKind::Wolf | MobKind::Villager => vec![],
+            MobKind::Zombie => vec![(100, ItemId::RottenFlesh, 0, 2)],
+            MobKind::Skeleton => vec![
+                (100, ItemId::Bone, 0, 2),
+                (100, ItemId::Arrow, 0, 2),
+                (5, ItemId::Bow, 1, 1),
+            ],
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -38,4 +130,74 @@ pub struct ServerMob {
     pub on_ground: bool,
     pub velocity: Vec3,
     pub deepth: f32,
+    #[serde(default)]
+    pub health: f32,
+    #[serde(default)]
+    pub on_fire: bool,
+    /// Ticks left before the mob can attack again
+    #[serde(default)]
+    pub attack_cooldown: u32,
+    /// Blocks left to walk through to reach the target, recomputed by the server from time to time
+    #[serde(skip)]
+    pub path: Vec<IVec3>,
+    /// Player who tamed the mob
+    #[serde(default)]
+    pub owner: Option<PlayerId>,
+    /// Tamed mobs told to sit stay where they are instead of following their owner
+    #[serde(default)]
+    pub sitting: bool,
+    /// Trades of the mob, with how many times each was made since the last restock
+    #[serde(default)]
+    pub trades: Vec<TradeOffer>,
+    /// Custom name, tags and other values set by commands or game logic
+    #[serde(default)]
+    pub metadata: EntityMetadata,
+    #[serde(default)]
+    pub effects: StatusEffects,
+}
+
+impl ServerMob {
+    pub fn new(kind: MobKind, position: Vec3, target: MobTarget) -> Self {
+        let (width, height, deepth) = kind.get_size();
+        Self {
+            kind,
+            target,
+            action: MobAction::Walk,
+            position,
+            rotation: Quat::IDENTITY,
+            height,
+            width,
+            on_ground: true,
+            velocity: Vec3::ZERO,
+            deepth,
+            health: kind.max_health(),
+            on_fire: false,
+            attack_cooldown: 0,
+            path: Vec::new(),
+            owner: None,
+            sitting: false,
+            trades: kind
+                .trade_table()
+                .iter()
+                .copied()
+                .map(TradeOffer::new)
+                .collect(),
+            metadata: EntityMetadata::default(),
+            effects: StatusEffects::default(),
+        }
+    }
+
+    /// Removes health unless the mob is invulnerable
+    pub fn damage(&mut self, amount: f32) {
+        if !self.metadata.is_invulnerable() {
+            self.health -= amount;
+        }
+    }
+
+    /// Custom name of the mob if it has one, else its kind
+    pub fn display_name(&self) -> String {
+        self.metadata
+            .custom_name()
+            .map_or_else(|| format!("{:?}", self.kind), String::from)
+    }
 }
@@ -1,13 +1,39 @@
+pub mod block_ticks;
 pub mod blocks;
+pub mod collision;
+pub mod coords;
 pub mod data;
+pub mod gamerules;
+pub mod heightmap;
 pub mod items;
+pub mod metadata;
 pub mod mobs;
+pub mod projectiles;
+pub mod random;
 pub mod raycast;
+pub mod recipes;
+pub mod tools;
+pub mod trades;
+pub mod updates;
 mod utils;
+pub mod weather;
 
+pub use block_ticks::*;
 pub use blocks::*;
+pub use collision::*;
+pub use coords::*;
 pub use data::*;
+pub use gamerules::*;
+pub use heightmap::*;
 pub use items::*;
+pub use metadata::*;
 pub use mobs::*;
+pub use projectiles::*;
+pub use random::*;
 pub use raycast::*;
+pub use recipes::*;
+pub use tools::*;
+pub use trades::*;
+pub use updates::*;
 pub use utils::*;
+pub use weather::*;
@@ -0,0 +1,75 @@
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::players::DamageSource;
+
+pub type ProjectileId = u128;
+
+pub const PROJECTILE_GRAVITY: f32 = -20.0;
+/// Fraction of the velocity kept after one second in the air
+pub const PROJECTILE_DRAG: f32 = 0.8;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectileKind {
+    Arrow,
+    Snowball,
+}
+
+impl ProjectileKind {
+    pub fn speed(&self) -> f32 {
+        match *self {
+            ProjectileKind::Arrow => 30.0,
+            ProjectileKind::Snowball => 20.0,
+        }
+    }
+
+    pub fn damage(&self) -> f32 {
+        match *self {
+            ProjectileKind::Arrow => 3.0,
+            ProjectileKind::Snowball => 0.5,
+        }
+    }
+
+    /// Projectiles still flying after that long are removed
+    pub fn lifetime_secs(&self) -> f32 {
+        match *self {
+            ProjectileKind::Arrow | ProjectileKind::Snowball => 5.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Projectile {
+    pub kind: ProjectileKind,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    /// Who fired it, used both to avoid hitting the shooter and for death messages
+    pub source: DamageSource,
+    pub age: f32,
+}
+
+impl Projectile {
+    /// Moves the projectile along its ballistic trajectory, the same way on the server and on clients
+    pub fn step(&mut self, delta: f32) {
+        self.velocity.y += PROJECTILE_GRAVITY * delta;
+        self.velocity *= PROJECTILE_DRAG.powf(delta);
+        self.position += self.velocity * delta;
+        self.age += delta;
+    }
+}
+
+/// Direction to shoot in from `from` with a projectile of speed `speed` to hit a target at `target`
+/// moving at `target_velocity`, anticipating both the target movement and the projectile drop
+pub fn aim_at_moving_target(from: Vec3, target: Vec3, target_velocity: Vec3, speed: f32) -> Vec3 {
+    // Two passes are enough for the flight time estimate to settle at combat ranges
+    let mut predicted = target;
+    for _ in 0..2 {
+        let flight_time = from.distance(predicted) / speed;
+        predicted = target + target_velocity * flight_time;
+    }
+
+    let flight_time = from.distance(predicted) / speed;
+    let drop = 0.5 * PROJECTILE_GRAVITY * flight_time * flight_time;
+
+    (predicted - Vec3::Y * drop - from).normalize_or_zero()
+}
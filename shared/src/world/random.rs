@@ -0,0 +1,115 @@
+use bevy::prelude::*;
+use rand::{Error, Rng, RngCore};
+
+use super::WorldSeed;
+
+/// What the random numbers are drawn for, so that two draws keyed the same way do not get the same numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomStream {
+    FireTick,
+    FireDelay,
+    Snow,
+    Weather,
+    HostileSpawning,
+    PassiveSpawning,
+    Taming,
+    MobLoot,
+    BlockDrops,
+    ChestLoot,
+    /// Ids of the mobs, item stacks and projectiles created during the tick
+    EntityIds,
+}
+
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+fn mix(state: u64, value: u64) -> u64 {
+    (state ^ value).wrapping_mul(GOLDEN_GAMMA)
+}
+
+/// Small splitmix64 generator, the same key always gives the same numbers on every platform.
+/// Implements `RngCore` so that the helpers of `rand::Rng` can be used with it
+#[derive(Debug, Clone)]
+pub struct SimRng(u64);
+
+impl RngCore for SimRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(GOLDEN_GAMMA);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Source of the randomness of the simulation, seeded from the world seed.
+/// Generators are keyed by tick and by block or entity, so that replaying the same ticks draws the same numbers
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct SimulationRandom {
+    pub seed: u32,
+}
+
+impl From<WorldSeed> for SimulationRandom {
+    fn from(seed: WorldSeed) -> Self {
+        Self { seed: seed.0 }
+    }
+}
+
+impl SimulationRandom {
+    fn keyed(&self, stream: RandomStream, tick: u64, keys: &[u64]) -> SimRng {
+        let mut state = mix(self.seed as u64, stream as u64);
+        state = mix(state, tick);
+        for key in keys {
+            state = mix(state, *key);
+        }
+        SimRng(state)
+    }
+
+    /// Numbers drawn once per tick, like the weather
+    pub fn for_tick(&self, stream: RandomStream, tick: u64) -> SimRng {
+        self.keyed(stream, tick, &[])
+    }
+
+    pub fn at_block(&self, stream: RandomStream, tick: u64, position: IVec3) -> SimRng {
+        self.keyed(
+            stream,
+            tick,
+            &[
+                position.x as u32 as u64,
+                position.y as u32 as u64,
+                position.z as u32 as u64,
+            ],
+        )
+    }
+
+    /// Numbers of a mob, an item stack or a player, by its id
+    pub fn for_entity(&self, stream: RandomStream, tick: u64, id: u128) -> SimRng {
+        self.keyed(stream, tick, &[id as u64, (id >> 64) as u64])
+    }
+}
+
+/// Id for a new mob, item stack or projectile. Drawn from the simulation randomness so that replaying
+/// the same ticks creates the same ids, and drawn again while `taken` says it is already used
+pub fn new_entity_id(rng: &mut impl Rng, taken: impl Fn(u128) -> bool) -> u128 {
+    loop {
+        let id = rng.gen();
+        if !taken(id) {
+            return id;
+        }
+    }
+}
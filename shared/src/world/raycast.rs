@@ -126,7 +126,7 @@ pub fn raycast_from_source_position_and_direction(
     // Actual raycast loop
     while distance < 20.0 {
         if let Some(block) = world_map.get_block_by_coordinates(&voxel) {
-            match block.id.get_ray_hitbox() {
+            match block.get_ray_hitbox() {
                 BlockHitbox::FullBlock => {
                     return Some(RaycastResponse {
                         block: *block,
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use super::ItemId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RecipeId {
+    OakPlanks,
+    Chest,
+    Glass,
+    Snowball,
+    Arrow,
+    Bow,
+    WoodenPickaxe,
+    StonePickaxe,
+    IronPickaxe,
+    GoldenPickaxe,
+    DiamondPickaxe,
+    Compass,
+    Clock,
+    Map,
+}
+
+impl RecipeId {
+    pub const ALL: [RecipeId; 14] = [
+        Self::OakPlanks,
+        Self::Chest,
+        Self::Glass,
+        Self::Snowball,
+        Self::Arrow,
+        Self::Bow,
+        Self::WoodenPickaxe,
+        Self::StonePickaxe,
+        Self::IronPickaxe,
+        Self::GoldenPickaxe,
+        Self::DiamondPickaxe,
+        Self::Compass,
+        Self::Clock,
+        Self::Map,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|recipe| format!("{recipe:?}").eq_ignore_ascii_case(name))
+    }
+
+    /// Items consumed by the recipe, with their count
+    pub fn ingredients(&self) -> &'static [(ItemId, u32)] {
+        match *self {
+            Self::OakPlanks => &[(ItemId::OakLog, 1)],
+            Self::Chest => &[(ItemId::OakPlanks, 8)],
+            Self::Glass => &[(ItemId::Sand, 1), (ItemId::Coal, 1)],
+            Self::Snowball => &[(ItemId::Snow, 1)],
+            Self::Arrow => &[(ItemId::Bone, 1), (ItemId::OakPlanks, 1)],
+            Self::Bow => &[(ItemId::Bone, 3), (ItemId::OakPlanks, 3)],
+            Self::WoodenPickaxe => &[(ItemId::OakPlanks, 5)],
+            Self::StonePickaxe => &[(ItemId::Cobblestone, 3), (ItemId::OakPlanks, 2)],
+            Self::IronPickaxe => &[(ItemId::IronOre, 3), (ItemId::OakPlanks, 2)],
+            Self::GoldenPickaxe => &[(ItemId::GoldOre, 3), (ItemId::OakPlanks, 2)],
+            Self::DiamondPickaxe => &[(ItemId::Diamond, 3), (ItemId::OakPlanks, 2)],
+            Self::Compass => &[(ItemId::IronOre, 4), (ItemId::Coal, 1)],
+            Self::Clock => &[(ItemId::GoldOre, 4), (ItemId::Coal, 1)],
+            Self::Map => &[(ItemId::OakPlanks, 8), (ItemId::Compass, 1)],
+        }
+    }
+
+    /// Item made by the recipe, with its count
+    pub fn result(&self) -> (ItemId, u32) {
+        match *self {
+            Self::OakPlanks => (ItemId::OakPlanks, 4),
+            Self::Chest => (ItemId::Chest, 1),
+            Self::Glass => (ItemId::Glass, 1),
+            Self::Snowball => (ItemId::Snowball, 4),
+            Self::Arrow => (ItemId::Arrow, 4),
+            Self::Bow => (ItemId::Bow, 1),
+            Self::WoodenPickaxe => (ItemId::WoodenPickaxe, 1),
+            Self::StonePickaxe => (ItemId::StonePickaxe, 1),
+            Self::IronPickaxe => (ItemId::IronPickaxe, 1),
+            Self::GoldenPickaxe => (ItemId::GoldenPickaxe, 1),
+            Self::DiamondPickaxe => (ItemId::DiamondPickaxe, 1),
+            Self::Compass => (ItemId::Compass, 1),
+            Self::Clock => (ItemId::Clock, 1),
+            Self::Map => (ItemId::Map, 1),
+        }
+    }
+
+    /// Recipes start locked, a player unlocks them the first time they get one of these items
+    pub fn unlocked_by(&self) -> &'static [ItemId] {
+        match *self {
+            Self::OakPlanks => &[ItemId::OakLog],
+            Self::Chest => &[ItemId::OakPlanks],
+            Self::Glass => &[ItemId::Sand],
+            Self::Snowball => &[ItemId::Snow, ItemId::Snowball],
+            Self::Arrow => &[ItemId::Bone, ItemId::Arrow],
+            Self::Bow => &[ItemId::Bone, ItemId::Bow],
+            Self::WoodenPickaxe => &[ItemId::OakPlanks, ItemId::WoodenPickaxe],
+            Self::StonePickaxe => &[ItemId::Cobblestone, ItemId::StonePickaxe],
+            Self::IronPickaxe => &[ItemId::IronOre, ItemId::IronPickaxe],
+            Self::GoldenPickaxe => &[ItemId::GoldOre, ItemId::GoldenPickaxe],
+            Self::DiamondPickaxe => &[ItemId::Diamond, ItemId::DiamondPickaxe],
+            Self::Compass => &[ItemId::IronOre, ItemId::Compass],
+            Self::Clock => &[ItemId::GoldOre, ItemId::Clock],
+            Self::Map => &[ItemId::Compass, ItemId::Map],
+        }
+    }
+}
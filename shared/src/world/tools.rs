@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use super::{BlockId, ItemId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ToolKind {
+    Pickaxe,
+}
+
+/// Material of a tool, a block needing a tier can be mined with any tool of that tier or above
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ToolTier {
+    Wood,
+    Stone,
+    Iron,
+    Diamond,
+}
+
+/// Changes what a block drops when broken with the tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DropModifier {
+    /// Blocks drop themselves instead of their usual drops
+    SilkTouch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Tool {
+    pub kind: ToolKind,
+    pub tier: ToolTier,
+    pub modifier: Option<DropModifier>,
+}
+
+impl Tool {
+    const fn pickaxe(tier: ToolTier) -> Self {
+        Self {
+            kind: ToolKind::Pickaxe,
+            tier,
+            modifier: None,
+        }
+    }
+
+    /// Whether the tool is good enough to get the drops of the block
+    pub fn can_harvest(&self, block: BlockId) -> bool {
+        match block.required_tool() {
+            Some((kind, tier)) => self.kind == kind && self.tier >= tier,
+            None => true,
+        }
+    }
+}
+
+impl ItemId {
+    pub fn tool(&self) -> Option<Tool> {
+        match *self {
+            Self::WoodenPickaxe => Some(Tool::pickaxe(ToolTier::Wood)),
+            Self::StonePickaxe => Some(Tool::pickaxe(ToolTier::Stone)),
+            Self::IronPickaxe => Some(Tool::pickaxe(ToolTier::Iron)),
+            // Too soft to dig deep, but careful enough to keep the blocks whole
+            Self::GoldenPickaxe => Some(Tool {
+                modifier: Some(DropModifier::SilkTouch),
+                ..Tool::pickaxe(ToolTier::Wood)
+            }),
+            Self::DiamondPickaxe => Some(Tool::pickaxe(ToolTier::Diamond)),
+            _ => None,
+        }
+    }
+}
+
+impl BlockId {
+    /// Tool needed for the block to drop anything, it can still be broken by hand
+    pub fn required_tool(&self) -> Option<(ToolKind, ToolTier)> {
+        match *self {
+            Self::Stone | Self::Cobblestone | Self::CoalOre => {
+                Some((ToolKind::Pickaxe, ToolTier::Wood))
+            }
+            Self::IronOre => Some((ToolKind::Pickaxe, ToolTier::Stone)),
+            Self::GoldOre | Self::DiamondOre => Some((ToolKind::Pickaxe, ToolTier::Iron)),
+            _ => None,
+        }
+    }
+
+    /// Item dropped by the block when mined with silk touch, `None` if it drops as usual
+    pub fn silk_touch_drop(&self) -> Option<ItemId> {
+        match *self {
+            Self::Grass => Some(ItemId::Grass),
+            Self::Stone => Some(ItemId::Stone),
+            Self::Glass => Some(ItemId::Glass),
+            Self::Snow => Some(ItemId::Snow),
+            Self::OakLeaves => Some(ItemId::OakLeaves),
+            Self::Poppy => Some(ItemId::Poppy),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ItemId, MobKind};
+
+/// What a player gives to a trader and what they get back
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Trade {
+    pub cost: (ItemId, u32),
+    pub result: (ItemId, u32),
+    /// Times the trade can be made before the trader restocks
+    pub max_uses: u32,
+}
+
+/// A trade of a given trader, with the number of times it was made since the last restock
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TradeOffer {
+    pub trade: Trade,
+    pub uses: u32,
+}
+
+impl TradeOffer {
+    pub fn new(trade: Trade) -> Self {
+        Self { trade, uses: 0 }
+    }
+
+    pub fn is_sold_out(&self) -> bool {
+        self.uses >= self.trade.max_uses
+    }
+}
+
+const fn trade(cost: (ItemId, u32), result: (ItemId, u32), max_uses: u32) -> Trade {
+    Trade {
+        cost,
+        result,
+        max_uses,
+    }
+}
+
+const VILLAGER_TRADES: [Trade; 10] = [
+    trade((ItemId::RottenFlesh, 16), (ItemId::Coal, 4), 8),
+    trade((ItemId::OakLog, 16), (ItemId::Glass, 8), 6),
+    trade((ItemId::Bone, 8), (ItemId::Arrow, 16), 8),
+    trade((ItemId::Coal, 16), (ItemId::IronOre, 2), 4),
+    trade((ItemId::IronOre, 6), (ItemId::Bow, 1), 2),
+    trade((ItemId::GoldOre, 4), (ItemId::Diamond, 1), 2),
+    trade((ItemId::OakLog, 8), (ItemId::WoodenPickaxe, 1), 2),
+    trade((ItemId::Cobblestone, 16), (ItemId::StonePickaxe, 1), 2),
+    trade((ItemId::IronOre, 8), (ItemId::IronPickaxe, 1), 1),
+    trade((ItemId::IronOre, 2), (ItemId::FlintAndSteel, 1), 2),
+];
+
+impl MobKind {
+    /// Trades offered by the mob, empty for mobs which do not trade
+    pub fn trade_table(&self) -> &'static [Trade] {
+        match *self {
+            MobKind::Villager => &VILLAGER_TRADES,
+            _ => &[],
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use bevy::math::IVec3;
+use serde::{Deserialize, Serialize};
+
+/// Why a chunk has to be sent to the clients again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DirtyReason {
+    Blocks,
+    Light,
+    Entities,
+}
+
+/// Every reason a chunk was marked for since the last drain
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirtyReasons {
+    pub blocks: bool,
+    pub light: bool,
+    pub entities: bool,
+}
+
+impl DirtyReasons {
+    pub fn add(&mut self, reason: DirtyReason) {
+        match reason {
+            DirtyReason::Blocks => self.blocks = true,
+            DirtyReason::Light => self.light = true,
+            DirtyReason::Entities => self.entities = true,
+        }
+    }
+
+    pub fn contains(&self, reason: DirtyReason) -> bool {
+        match reason {
+            DirtyReason::Blocks => self.blocks,
+            DirtyReason::Light => self.light,
+            DirtyReason::Entities => self.entities,
+        }
+    }
+}
+
+/// Chunks waiting to be sent again, each one is listed once however many times it was marked
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ChunkUpdates {
+    chunks: HashMap<IVec3, DirtyReasons>,
+}
+
+impl ChunkUpdates {
+    pub fn mark(&mut self, chunk_pos: IVec3, reason: DirtyReason) {
+        self.chunks.entry(chunk_pos).or_default().add(reason);
+    }
+
+    pub fn reasons(&self, chunk_pos: &IVec3) -> Option<DirtyReasons> {
+        self.chunks.get(chunk_pos).copied()
+    }
+
+    pub fn contains(&self, chunk_pos: &IVec3) -> bool {
+        self.chunks.contains_key(chunk_pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Takes every marked chunk, leaving none behind
+    pub fn drain(&mut self) -> impl Iterator<Item = (IVec3, DirtyReasons)> + '_ {
+        self.chunks.drain()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_marked_many_times_is_drained_once_with_every_reason() {
+        let mut updates = ChunkUpdates::default();
+        let edited = IVec3::new(1, -2, 3);
+        let other = IVec3::new(0, 0, 0);
+        updates.mark(edited, DirtyReason::Blocks);
+        updates.mark(edited, DirtyReason::Blocks);
+        updates.mark(edited, DirtyReason::Light);
+        updates.mark(other, DirtyReason::Entities);
+        updates.mark(edited, DirtyReason::Entities);
+
+        let mut drained: Vec<_> = updates.drain().collect();
+        drained.sort_by_key(|(pos, _)| pos.to_array());
+
+        assert_eq!(
+            drained,
+            vec![
+                (
+                    other,
+                    DirtyReasons {
+                        entities: true,
+                        ..Default::default()
+                    }
+                ),
+                (
+                    edited,
+                    DirtyReasons {
+                        blocks: true,
+                        light: true,
+                        entities: true,
+                    }
+                ),
+            ]
+        );
+        assert!(updates.is_empty());
+        assert_eq!(updates.drain().count(), 0);
+    }
+}
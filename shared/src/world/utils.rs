@@ -1,59 +1,6 @@
-use bevy::math::{IVec3, Vec3};
+use bevy::math::IVec3;
 
-use crate::CHUNK_SIZE;
-
-pub fn block_to_chunk_coord(x: i32) -> i32 {
-    if x >= 0 {
-        x / CHUNK_SIZE
-    } else {
-        (x - (CHUNK_SIZE - 1)) / CHUNK_SIZE
-    }
-}
-
-pub fn block_vec3_to_chunk_v3_coord(v: Vec3) -> Vec3 {
-    Vec3::new(
-        block_to_chunk_coord(v.x as i32) as f32,
-        block_to_chunk_coord(v.y as i32) as f32,
-        block_to_chunk_coord(v.z as i32) as f32,
-    )
-}
-
-// TODO: rename chunk_pos(ition) to chunk_index
-pub fn world_position_to_chunk_position(v: Vec3) -> IVec3 {
-    IVec3::new(
-        block_to_chunk_coord(v.x as i32),
-        block_to_chunk_coord(v.y as i32),
-        block_to_chunk_coord(v.z as i32),
-    )
-}
-
-pub fn chunk_offset_to_global_pos(chunk_pos: &IVec3, local_block_offset: &IVec3) -> IVec3 {
-    *chunk_pos * CHUNK_SIZE + *local_block_offset
-}
-
-pub fn chunk_index_to_world_position(chunk_index: &IVec3) -> Vec3 {
-    Vec3 {
-        x: chunk_index.x as f32 * CHUNK_SIZE as f32,
-        y: chunk_index.y as f32 * CHUNK_SIZE as f32,
-        z: chunk_index.z as f32 * CHUNK_SIZE as f32,
-    }
-}
-
-pub fn global_block_to_local_offset(global_block_pos: &IVec3) -> IVec3 {
-    IVec3 {
-        x: ((global_block_pos.x % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE,
-        y: ((global_block_pos.y % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE,
-        z: ((global_block_pos.z % CHUNK_SIZE) + CHUNK_SIZE) % CHUNK_SIZE,
-    }
-}
-
-pub fn global_block_to_chunk_pos(global_block_pos: &IVec3) -> IVec3 {
-    IVec3::new(
-        block_to_chunk_coord(global_block_pos.x),
-        block_to_chunk_coord(global_block_pos.y),
-        block_to_chunk_coord(global_block_pos.z),
-    )
-}
+use crate::DAY_DURATION_IN_TICKS;
 
 pub const SIX_OFFSETS: [IVec3; 6] = [
     IVec3::new(1, 0, 0),
@@ -63,3 +10,8 @@ pub const SIX_OFFSETS: [IVec3; 6] = [
     IVec3::new(0, 0, 1),
     IVec3::new(0, 0, -1),
 ];
+
+/// The sun is above the horizon during the second half of each day cycle
+pub fn is_daytime(tick: u64) -> bool {
+    tick % DAY_DURATION_IN_TICKS >= DAY_DURATION_IN_TICKS / 2
+}
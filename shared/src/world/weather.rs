@@ -0,0 +1,47 @@
+use bevy_ecs::resource::Resource;
+use serde::{Deserialize, Serialize};
+
+use super::BiomeType;
+
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    Clear,
+    /// Snow in the cold biomes, rain everywhere else
+    Precipitation,
+}
+
+impl Weather {
+    pub const ALL: [Weather; 2] = [Weather::Clear, Weather::Precipitation];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|weather| weather.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Precipitation => "precipitation",
+        }
+    }
+}
+
+/// Current weather of the world and how long it lasts, saved with it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeatherState {
+    pub weather: Weather,
+    /// Ticks before the weather changes, 0 to pick a new duration
+    pub remaining_ticks: u64,
+}
+
+impl WeatherState {
+    pub fn is_snowing_in(&self, biome: BiomeType) -> bool {
+        self.weather == Weather::Precipitation && biome.is_cold()
+    }
+
+    pub fn is_raining_in(&self, biome: BiomeType) -> bool {
+        self.weather == Weather::Precipitation && !biome.is_cold()
+    }
+}